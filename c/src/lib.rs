@@ -1,12 +1,44 @@
-use std::{alloc, ffi::c_void};
+use std::{alloc, cell::RefCell, ffi::c_void};
 
 use alloc::Layout;
 use cao_lang::{
     compiled_program,
     compiler::{compile, CaoProgram, CompilationErrorPayload},
-    vm::Vm,
+    procedures::ExecutionError,
+    value::Value,
+    vm::{RunOutcome, Suspended, Vm},
 };
 
+thread_local! {
+    /// The formatted message of the most recent [`cao_compile_json`]/[`cao_run_program`]-family
+    /// failure on this thread, read back via [`cao_last_error_message`]. `CompileResult`/
+    /// `ExecutionResult` only carry an error *kind* across the FFI boundary; this is where the
+    /// payload they flattened away (the duplicated name, the bad jump target, the assertion
+    /// text, the source location, ...) actually lives.
+    static LAST_ERROR: RefCell<String> = RefCell::new(String::new());
+}
+
+fn set_last_error(message: String) {
+    LAST_ERROR.with(|e| *e.borrow_mut() = message);
+}
+
+/// Formats an [`ExecutionError`]'s payload together with its call trace, so a host sees which
+/// lane/card the failure unwound through, not just the immediate cause.
+fn format_execution_error(err: &ExecutionError) -> String {
+    if err.trace.is_empty() {
+        return err.payload.to_string();
+    }
+    let mut message = err.payload.to_string();
+    message.push_str("\n  at ");
+    for (i, loc) in err.trace.iter().enumerate() {
+        if i > 0 {
+            message.push_str(" -> ");
+        }
+        message.push_str(&loc.to_string());
+    }
+    message
+}
+
 /// Opaque CompiledProgram wrapper.
 #[derive(Copy, Clone)]
 #[repr(C)]
@@ -21,6 +53,46 @@ pub struct CaoVm {
     _inner: *mut c_void,
 }
 
+/// Behind a [`CaoVm`]'s `_inner` pointer: either a running VM, ready for
+/// [`cao_run_program`]/[`cao_run_program_resumable`], or a paused one, ready for
+/// [`cao_resume_program`]. [`cao_run_program_resumable`]/[`cao_resume_program`] always consume
+/// the `Vm` they drive (matching [`Vm::run_resumable`]/[`Vm::resume`]'s ownership), so the slot is
+/// always replaced with a fresh state rather than mutated in place.
+enum CaoVmState {
+    Running(Vm<*mut c_void>),
+    Suspended(Suspended),
+}
+
+/// Tag of a [`CaoScalar`]; mirrors the subset of [`cao_lang::value::Value`] a host can hand back
+/// across the FFI boundary. Heap-allocated values (`Value::Object`) aren't representable here -
+/// a suspended call can only be resumed with a `Nil`, `Integer` or `Real`.
+#[allow(non_camel_case_types)]
+#[repr(C)]
+pub enum CaoScalarTag {
+    cao_ScalarTag_Nil = 0,
+    cao_ScalarTag_Integer,
+    cao_ScalarTag_Real,
+}
+
+/// An FFI-safe stand-in for [`cao_lang::value::Value`], passed to [`cao_resume_program`] as the
+/// result of whatever the host's suspended call computed.
+#[repr(C)]
+pub struct CaoScalar {
+    pub tag: CaoScalarTag,
+    pub integer: i64,
+    pub real: f64,
+}
+
+impl From<CaoScalar> for Value {
+    fn from(s: CaoScalar) -> Self {
+        match s.tag {
+            CaoScalarTag::cao_ScalarTag_Nil => Value::Nil,
+            CaoScalarTag::cao_ScalarTag_Integer => Value::Integer(s.integer),
+            CaoScalarTag::cao_ScalarTag_Real => Value::Real(s.real),
+        }
+    }
+}
+
 #[allow(non_camel_case_types)]
 #[repr(C)]
 pub enum CompileResult {
@@ -43,6 +115,9 @@ pub enum CompileResult {
     cao_CompileResult_SuperLimitReached,
     cao_CompileResult_AmbigousImport,
     cao_CompileResult_DuplicateModule,
+    /// [`cao_load_program`] was handed a blob that's truncated, carries the wrong magic/version,
+    /// or was written with different endianness.
+    cao_CompileResult_BadFlatProgram,
 }
 
 #[allow(non_camel_case_types)]
@@ -64,11 +139,57 @@ pub enum ExecutionResult {
     cao_ExecutionResult_OutOfMemory,
     cao_ExecutionResult_MissingArgument,
     cao_ExecutionResult_Timeout,
+    /// The program's fuel budget (see [`cao_lang::vm::Vm::set_fuel`]) ran out. Only reachable via
+    /// [`cao_run_program`] - [`cao_run_program_resumable`]/[`cao_resume_program`] surface this as
+    /// [`ExecutionResult::cao_ExecutionResult_Suspended`] instead, the same as `Timeout`.
+    cao_ExecutionResult_OutOfFuel,
     cao_ExecutionResult_TaskFailure,
     cao_ExecutionResult_Stackoverflow,
     cao_ExecutionResult_BadReturn,
     cao_ExecutionResult_Unhashable,
     cao_ExecutionResult_AssertionError,
+    cao_ExecutionResult_DivideByZero,
+    cao_ExecutionResult_InvalidUpvalue,
+    cao_ExecutionResult_NotClosure,
+    cao_ExecutionResult_StdlibFingerprintMismatch,
+    /// An uncaught `Card::Throw` (or other fault) unwound the whole program with no `Card::Try`
+    /// handler left to catch it.
+    cao_ExecutionResult_Unhandled,
+    /// The program paused instead of finishing - either a host call suspended it or its
+    /// instruction budget ran out. Continue it with [`cao_resume_program`].
+    cao_ExecutionResult_Suspended,
+    /// A host thread cancelled the run via [`cao_lang::vm::Vm::interrupt_handle`]. Unlike
+    /// `Timeout`/`OutOfFuel`, this is never surfaced as `Suspended` - the run is not resumable.
+    cao_ExecutionResult_Interrupted,
+    /// A breakpoint or debugger paused the run. Only reachable via [`cao_run_program`] -
+    /// [`cao_run_program_resumable`]/[`cao_resume_program`] surface this as
+    /// [`ExecutionResult::cao_ExecutionResult_Suspended`] instead, the same as `Timeout`.
+    cao_ExecutionResult_Paused,
+    /// A debugger aborted the run. Unlike `Paused`, this is never resumable.
+    cao_ExecutionResult_DebuggerAbort,
+}
+
+/// Writes the message describing the most recent [`cao_compile_json`]/[`cao_run_program`]-family
+/// failure *on this thread* into `buf`, truncating to `buf_len` bytes if it doesn't fit, and
+/// returns the message's full length in bytes - which may be larger than `buf_len` - so a host
+/// that got truncated can size a bigger buffer and call again. Writes nothing and returns 0 if no
+/// error has happened yet on this thread.
+///
+/// # Safety
+///
+/// `buf` must be valid for writes of `buf_len` bytes (it may be null if `buf_len` is 0, e.g. to
+/// just query the required length).
+#[no_mangle]
+pub unsafe extern "C" fn cao_last_error_message(buf: *mut u8, buf_len: u32) -> u32 {
+    LAST_ERROR.with(|e| {
+        let message = e.borrow();
+        let bytes = message.as_bytes();
+        let n = bytes.len().min(buf_len as usize);
+        if n > 0 {
+            std::ptr::copy_nonoverlapping(bytes.as_ptr(), buf, n);
+        }
+        bytes.len() as u32
+    })
 }
 
 /// # Safety
@@ -90,12 +211,12 @@ pub unsafe extern "C" fn cao_new_compiled_program() -> CaoCompiledProgram {
 /// [cao_free_vm](cao_free_vm)
 #[no_mangle]
 pub unsafe extern "C" fn cao_new_vm() -> CaoVm {
-    let vm = Box::new(
+    let vm = Box::new(CaoVmState::Running(
         Vm::<*mut c_void>::new(std::ptr::null_mut()).expect("Failed to initialize the VM"),
-    );
+    ));
     let vm = Box::leak(vm);
     CaoVm {
-        _inner: vm as *mut Vm<*mut c_void> as *mut c_void,
+        _inner: vm as *mut CaoVmState as *mut c_void,
     }
 }
 
@@ -109,7 +230,7 @@ pub unsafe extern "C" fn cao_free_vm(vm: *mut CaoVm) {
     }
     let vm = &mut *vm;
     if !vm._inner.is_null() {
-        let _vm = Box::from_raw(vm._inner as *mut Vm<*mut c_void>);
+        let _vm = Box::from_raw(vm._inner as *mut CaoVmState);
     }
     vm._inner = std::ptr::null_mut();
 }
@@ -152,63 +273,69 @@ pub unsafe extern "C" fn cao_compile_json(
 
     let ir: CaoProgram = match serde_json::from_slice(cao_ir) {
         Ok(ir) => ir,
-        Err(_) => return CompileResult::cao_CompileResult_BadJson,
+        Err(err) => {
+            set_last_error(err.to_string());
+            return CompileResult::cao_CompileResult_BadJson;
+        }
     };
 
     let program = match compile(ir, None) {
         Ok(p) => p,
-        Err(err) => match err.payload {
-            CompilationErrorPayload::Unimplemented(_) => {
-                return CompileResult::cao_CompileResult_Unimplmeneted
-            }
-            CompilationErrorPayload::EmptyProgram => {
-                return CompileResult::cao_CompileResult_EmptyProgram
-            }
+        Err(err) => {
+            set_last_error(err.to_string());
+            match err.payload {
+                CompilationErrorPayload::Unimplemented(_) => {
+                    return CompileResult::cao_CompileResult_Unimplmeneted
+                }
+                CompilationErrorPayload::EmptyProgram => {
+                    return CompileResult::cao_CompileResult_EmptyProgram
+                }
 
-            CompilationErrorPayload::TooManyCards(_) => {
-                return CompileResult::cao_CompileResult_TooManyCards
-            }
-            CompilationErrorPayload::DuplicateName(_) => {
-                return CompileResult::cao_CompileResult_DuplicateName
-            }
-            CompilationErrorPayload::MissingSubProgram(_) => {
-                return CompileResult::cao_CompileResult_MissingSubProgram
-            }
-            CompilationErrorPayload::InvalidJump { .. } => {
-                return CompileResult::cao_CompileResult_InvalidJump
-            }
-            CompilationErrorPayload::InternalError => {
-                return CompileResult::cao_CompileResult_InternalError
-            }
-            CompilationErrorPayload::TooManyLocals => {
-                return CompileResult::cao_CompileResult_TooManyLocals
-            }
-            CompilationErrorPayload::BadVariableName(_) => {
-                return CompileResult::cao_CompileResult_BadVariableName
-            }
-            CompilationErrorPayload::EmptyVariable => {
-                return CompileResult::cao_CompileResult_EmptyVariable
-            }
-            CompilationErrorPayload::NoMain => return CompileResult::cao_CompileResult_NoMain,
-            CompilationErrorPayload::BadFunctionName(_) => {
-                return CompileResult::cao_CompileResult_BadFunctionName
-            }
-            CompilationErrorPayload::RecursionLimitReached(_) => {
-                return CompileResult::cao_CompileResult_RecursionLimitReached
-            }
-            CompilationErrorPayload::BadImport(_) => {
-                return CompileResult::cao_CompileResult_BadImport
-            }
-            CompilationErrorPayload::SuperLimitReached => {
-                return CompileResult::cao_CompileResult_SuperLimitReached
-            }
-            CompilationErrorPayload::AmbigousImport(_) => {
-                return CompileResult::cao_CompileResult_AmbigousImport
-            }
-            CompilationErrorPayload::DuplicateModule(_) => {
-                return CompileResult::cao_CompileResult_DuplicateModule;
-            }
-        },
+                CompilationErrorPayload::TooManyCards(_) => {
+                    return CompileResult::cao_CompileResult_TooManyCards
+                }
+                CompilationErrorPayload::DuplicateName(_) => {
+                    return CompileResult::cao_CompileResult_DuplicateName
+                }
+                CompilationErrorPayload::MissingSubProgram(_) => {
+                    return CompileResult::cao_CompileResult_MissingSubProgram
+                }
+                CompilationErrorPayload::InvalidJump { .. } => {
+                    return CompileResult::cao_CompileResult_InvalidJump
+                }
+                CompilationErrorPayload::InternalError => {
+                    return CompileResult::cao_CompileResult_InternalError
+                }
+                CompilationErrorPayload::TooManyLocals => {
+                    return CompileResult::cao_CompileResult_TooManyLocals
+                }
+                CompilationErrorPayload::BadVariableName(_) => {
+                    return CompileResult::cao_CompileResult_BadVariableName
+                }
+                CompilationErrorPayload::EmptyVariable => {
+                    return CompileResult::cao_CompileResult_EmptyVariable
+                }
+                CompilationErrorPayload::NoMain => return CompileResult::cao_CompileResult_NoMain,
+                CompilationErrorPayload::BadFunctionName(_) => {
+                    return CompileResult::cao_CompileResult_BadFunctionName
+                }
+                CompilationErrorPayload::RecursionLimitReached(_) => {
+                    return CompileResult::cao_CompileResult_RecursionLimitReached
+                }
+                CompilationErrorPayload::BadImport(_) => {
+                    return CompileResult::cao_CompileResult_BadImport
+                }
+                CompilationErrorPayload::SuperLimitReached => {
+                    return CompileResult::cao_CompileResult_SuperLimitReached
+                }
+                CompilationErrorPayload::AmbigousImport(_) => {
+                    return CompileResult::cao_CompileResult_AmbigousImport
+                }
+                CompilationErrorPayload::DuplicateModule(_) => {
+                    return CompileResult::cao_CompileResult_DuplicateModule;
+                }
+            }
+        }
     };
     let program_ptr = alloc::alloc(Layout::new::<compiled_program::CaoCompiledProgram>());
     std::ptr::write(
@@ -225,6 +352,175 @@ pub unsafe extern "C" fn cao_compile_json(
     CompileResult::cao_CompileResult_Ok
 }
 
+/// Serialize `program` into a single contiguous, `mmap`-friendly blob (see
+/// [`cao_lang::compiled_program::serialize_flat`]) instead of re-running it through JSON: a small
+/// header followed by the raw bytecode/data buffers and a compact metadata section, so
+/// [`cao_load_program`] can validate and reload it with a handful of bounds checks. The caller
+/// takes ownership of `*out_ptr` and must free it with [`cao_free_bytes`].
+///
+/// # Safety
+///
+/// `program` must have been produced by [`cao_compile_json`] or [`cao_load_program`] and not yet
+/// freed. `out_ptr`/`out_len` must be valid to write to.
+#[no_mangle]
+pub unsafe extern "C" fn cao_serialize_program(
+    program: CaoCompiledProgram,
+    out_ptr: *mut *mut u8,
+    out_len: *mut u32,
+) -> CompileResult {
+    if program._inner.is_null() {
+        return CompileResult::cao_CompileResult_BadFlatProgram;
+    }
+    let program: &compiled_program::CaoCompiledProgram = &*(program._inner as *const _);
+    let mut bytes = compiled_program::serialize_flat(program).into_boxed_slice();
+
+    std::ptr::write(out_len, bytes.len() as u32);
+    std::ptr::write(out_ptr, bytes.as_mut_ptr());
+    std::mem::forget(bytes);
+
+    CompileResult::cao_CompileResult_Ok
+}
+
+/// Frees a buffer previously returned by [`cao_serialize_program`].
+///
+/// # Safety
+///
+/// `ptr`/`len` must be exactly the pair last returned by [`cao_serialize_program`], and must not
+/// have already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn cao_free_bytes(ptr: *mut u8, len: u32) {
+    if ptr.is_null() {
+        return;
+    }
+    let _ = Box::from_raw(std::slice::from_raw_parts_mut(ptr, len as usize));
+}
+
+/// Load a program previously produced by [`cao_serialize_program`]. Validates the header and
+/// rejects truncated or wrong-endian/wrong-version blobs with
+/// [`CompileResult::cao_CompileResult_BadFlatProgram`] - this path never parses JSON or
+/// recompiles.
+///
+/// # Safety
+///
+/// `bytes_len` must be the length of the `bytes` buffer. The caller is responsible for freeing
+/// the produced program with [`cao_free_compiled_program`].
+#[no_mangle]
+pub unsafe extern "C" fn cao_load_program(
+    bytes: *const u8,
+    bytes_len: u32,
+    result: *mut CaoCompiledProgram,
+) -> CompileResult {
+    assert!(!bytes.is_null());
+    assert!(!result.is_null());
+
+    let bytes = std::slice::from_raw_parts(bytes, bytes_len as usize);
+    let program = match compiled_program::deserialize_flat(bytes) {
+        Ok(p) => p,
+        Err(_) => return CompileResult::cao_CompileResult_BadFlatProgram,
+    };
+
+    let program_ptr = alloc::alloc(Layout::new::<compiled_program::CaoCompiledProgram>());
+    std::ptr::write(
+        program_ptr as *mut compiled_program::CaoCompiledProgram,
+        program,
+    );
+
+    std::ptr::write(
+        result,
+        CaoCompiledProgram {
+            _inner: program_ptr as *mut c_void,
+        },
+    );
+
+    CompileResult::cao_CompileResult_Ok
+}
+
+/// Maps a VM execution failure onto its FFI result code.
+fn execution_result_of_payload(
+    payload: cao_lang::procedures::ExecutionErrorPayload,
+) -> ExecutionResult {
+    match payload {
+        cao_lang::procedures::ExecutionErrorPayload::CallStackOverflow { .. } => {
+            ExecutionResult::cao_ExecutionResult_CallStackOverflow
+        }
+        cao_lang::procedures::ExecutionErrorPayload::UnexpectedEndOfInput => {
+            ExecutionResult::cao_ExecutionResult_UnexpectedEndOfInput
+        }
+        cao_lang::procedures::ExecutionErrorPayload::ExitCode(_) => {
+            ExecutionResult::cao_ExecutionResult_ExitCode
+        }
+        cao_lang::procedures::ExecutionErrorPayload::InvalidInstruction(_) => {
+            ExecutionResult::cao_ExecutionResult_InvalidInstruction
+        }
+        cao_lang::procedures::ExecutionErrorPayload::InvalidArgument { .. } => {
+            ExecutionResult::cao_ExecutionResult_InvalidArgument
+        }
+        cao_lang::procedures::ExecutionErrorPayload::VarNotFound(_) => {
+            ExecutionResult::cao_ExecutionResult_VarNotFound
+        }
+        cao_lang::procedures::ExecutionErrorPayload::ProcedureNotFound(_) => {
+            ExecutionResult::cao_ExecutionResult_ProcedureNotFound
+        }
+        cao_lang::procedures::ExecutionErrorPayload::Unimplemented => {
+            ExecutionResult::cao_ExecutionResult_Unimplemented
+        }
+        cao_lang::procedures::ExecutionErrorPayload::OutOfMemory => {
+            ExecutionResult::cao_ExecutionResult_OutOfMemory
+        }
+        cao_lang::procedures::ExecutionErrorPayload::MissingArgument => {
+            ExecutionResult::cao_ExecutionResult_MissingArgument
+        }
+        cao_lang::procedures::ExecutionErrorPayload::Timeout { .. } => {
+            ExecutionResult::cao_ExecutionResult_Timeout
+        }
+        cao_lang::procedures::ExecutionErrorPayload::OutOfFuel => {
+            ExecutionResult::cao_ExecutionResult_OutOfFuel
+        }
+        cao_lang::procedures::ExecutionErrorPayload::TaskFailure { .. } => {
+            ExecutionResult::cao_ExecutionResult_TaskFailure
+        }
+        cao_lang::procedures::ExecutionErrorPayload::Stackoverflow { .. } => {
+            ExecutionResult::cao_ExecutionResult_Stackoverflow
+        }
+        cao_lang::procedures::ExecutionErrorPayload::BadReturn { .. } => {
+            ExecutionResult::cao_ExecutionResult_BadReturn
+        }
+        cao_lang::procedures::ExecutionErrorPayload::Unhashable => {
+            ExecutionResult::cao_ExecutionResult_Unhashable
+        }
+        cao_lang::procedures::ExecutionErrorPayload::AssertionError(_) => {
+            ExecutionResult::cao_ExecutionResult_AssertionError
+        }
+        cao_lang::procedures::ExecutionErrorPayload::DivideByZero => {
+            ExecutionResult::cao_ExecutionResult_DivideByZero
+        }
+        cao_lang::procedures::ExecutionErrorPayload::InvalidUpvalue => {
+            ExecutionResult::cao_ExecutionResult_InvalidUpvalue
+        }
+        cao_lang::procedures::ExecutionErrorPayload::NotClosure => {
+            ExecutionResult::cao_ExecutionResult_NotClosure
+        }
+        cao_lang::procedures::ExecutionErrorPayload::StdlibFingerprintMismatch { .. } => {
+            ExecutionResult::cao_ExecutionResult_StdlibFingerprintMismatch
+        }
+        cao_lang::procedures::ExecutionErrorPayload::Suspended => {
+            ExecutionResult::cao_ExecutionResult_Suspended
+        }
+        cao_lang::procedures::ExecutionErrorPayload::Unhandled(_) => {
+            ExecutionResult::cao_ExecutionResult_Unhandled
+        }
+        cao_lang::procedures::ExecutionErrorPayload::Interrupted => {
+            ExecutionResult::cao_ExecutionResult_Interrupted
+        }
+        cao_lang::procedures::ExecutionErrorPayload::Paused => {
+            ExecutionResult::cao_ExecutionResult_Paused
+        }
+        cao_lang::procedures::ExecutionErrorPayload::DebuggerAbort => {
+            ExecutionResult::cao_ExecutionResult_DebuggerAbort
+        }
+    }
+}
+
 /// # Safety
 ///
 /// Runs a previously compiled program in the given VM
@@ -240,61 +536,123 @@ pub unsafe extern "C" fn cao_run_program(
         return ExecutionResult::cao_ExecutionResult_BadVm;
     }
     let program: &compiled_program::CaoCompiledProgram = &*(program._inner as *const _);
-    let vm: &mut Vm<*mut c_void> = &mut *(vm._inner as *mut _);
+    let state: &mut CaoVmState = &mut *(vm._inner as *mut _);
+    let vm = match state {
+        CaoVmState::Running(vm) => vm,
+        CaoVmState::Suspended(_) => return ExecutionResult::cao_ExecutionResult_BadVm,
+    };
 
-    match vm.run(program) {
-        Ok(_) => {}
-        Err(err) => match err.payload {
-            cao_lang::procedures::ExecutionErrorPayload::CallStackOverflow => {
-                return ExecutionResult::cao_ExecutionResult_CallStackOverflow
-            }
-            cao_lang::procedures::ExecutionErrorPayload::UnexpectedEndOfInput => {
-                return ExecutionResult::cao_ExecutionResult_UnexpectedEndOfInput
-            }
-            cao_lang::procedures::ExecutionErrorPayload::ExitCode(_) => {
-                return ExecutionResult::cao_ExecutionResult_ExitCode
-            }
-            cao_lang::procedures::ExecutionErrorPayload::InvalidInstruction(_) => {
-                return ExecutionResult::cao_ExecutionResult_InvalidInstruction
-            }
-            cao_lang::procedures::ExecutionErrorPayload::InvalidArgument { .. } => {
-                return ExecutionResult::cao_ExecutionResult_InvalidArgument
-            }
-            cao_lang::procedures::ExecutionErrorPayload::VarNotFound(_) => {
-                return ExecutionResult::cao_ExecutionResult_VarNotFound
-            }
-            cao_lang::procedures::ExecutionErrorPayload::ProcedureNotFound(_) => {
-                return ExecutionResult::cao_ExecutionResult_ProcedureNotFound
-            }
-            cao_lang::procedures::ExecutionErrorPayload::Unimplemented => {
-                return ExecutionResult::cao_ExecutionResult_Unimplemented
-            }
-            cao_lang::procedures::ExecutionErrorPayload::OutOfMemory => {
-                return ExecutionResult::cao_ExecutionResult_OutOfMemory
-            }
-            cao_lang::procedures::ExecutionErrorPayload::MissingArgument => {
-                return ExecutionResult::cao_ExecutionResult_MissingArgument
-            }
-            cao_lang::procedures::ExecutionErrorPayload::Timeout => {
-                return ExecutionResult::cao_ExecutionResult_Timeout
-            }
-            cao_lang::procedures::ExecutionErrorPayload::TaskFailure { .. } => {
-                return ExecutionResult::cao_ExecutionResult_TaskFailure
-            }
-            cao_lang::procedures::ExecutionErrorPayload::Stackoverflow => {
-                return ExecutionResult::cao_ExecutionResult_Stackoverflow
-            }
-            cao_lang::procedures::ExecutionErrorPayload::BadReturn { .. } => {
-                return ExecutionResult::cao_ExecutionResult_BadReturn
-            }
-            cao_lang::procedures::ExecutionErrorPayload::Unhashable => {
-                return ExecutionResult::cao_ExecutionResult_Unhashable
-            }
-            cao_lang::procedures::ExecutionErrorPayload::AssertionError(_) => {
-                return ExecutionResult::cao_ExecutionResult_AssertionError
-            }
-        },
+    if let Err(err) = vm.run(program) {
+        set_last_error(format_execution_error(&err));
+        return execution_result_of_payload(err.payload);
     }
 
     ExecutionResult::cao_ExecutionResult_Ok
 }
+
+/// # Safety
+///
+/// Runs a previously compiled program in the given VM, but instead of failing when the program
+/// pauses (a native function called [`cao_lang::vm::Vm::suspend`], or the program's instruction
+/// budget ran out), leaves the VM in a suspended state and returns
+/// [`ExecutionResult::cao_ExecutionResult_Suspended`]. Continue it with [`cao_resume_program`].
+///
+/// [`run_resumable`](cao_lang::vm::Vm::run_resumable) consumes the `Vm` it drives, so - win or
+/// lose - `vm` is always left holding a fresh, freshly-initialized VM afterwards unless this call
+/// returns `Suspended`, in which case it holds the paused state instead.
+#[no_mangle]
+pub unsafe extern "C" fn cao_run_program_resumable(
+    program: CaoCompiledProgram,
+    vm: CaoVm,
+) -> ExecutionResult {
+    if program._inner.is_null() {
+        return ExecutionResult::cao_ExecutionResult_BadProgram;
+    }
+    if vm._inner.is_null() {
+        return ExecutionResult::cao_ExecutionResult_BadVm;
+    }
+    let program: &compiled_program::CaoCompiledProgram = &*(program._inner as *const _);
+    let state_ptr = vm._inner as *mut CaoVmState;
+    let running = match std::ptr::read(state_ptr) {
+        CaoVmState::Running(vm) => vm,
+        suspended @ CaoVmState::Suspended(_) => {
+            std::ptr::write(state_ptr, suspended);
+            return ExecutionResult::cao_ExecutionResult_BadVm;
+        }
+    };
+
+    match running.run_resumable(program) {
+        Ok(RunOutcome::Finished(_)) => {
+            std::ptr::write(
+                state_ptr,
+                CaoVmState::Running(
+                    Vm::new(std::ptr::null_mut()).expect("Failed to initialize the VM"),
+                ),
+            );
+            ExecutionResult::cao_ExecutionResult_Ok
+        }
+        Ok(RunOutcome::Yielded(suspended)) => {
+            std::ptr::write(state_ptr, CaoVmState::Suspended(suspended));
+            ExecutionResult::cao_ExecutionResult_Suspended
+        }
+        Err(err) => {
+            std::ptr::write(
+                state_ptr,
+                CaoVmState::Running(
+                    Vm::new(std::ptr::null_mut()).expect("Failed to initialize the VM"),
+                ),
+            );
+            set_last_error(format_execution_error(&err));
+            execution_result_of_payload(err.payload)
+        }
+    }
+}
+
+/// # Safety
+///
+/// Continues a VM previously paused by [`cao_run_program_resumable`], injecting `result` as the
+/// return value of the call it suspended at. Returns
+/// [`ExecutionResult::cao_ExecutionResult_BadVm`] without touching `vm` if it isn't currently
+/// suspended (e.g. it was never run or already finished - check the return value before calling
+/// this again).
+#[no_mangle]
+pub unsafe extern "C" fn cao_resume_program(vm: CaoVm, result: CaoScalar) -> ExecutionResult {
+    if vm._inner.is_null() {
+        return ExecutionResult::cao_ExecutionResult_BadVm;
+    }
+    let state_ptr = vm._inner as *mut CaoVmState;
+    let suspended = match std::ptr::read(state_ptr) {
+        CaoVmState::Suspended(suspended) => suspended,
+        running @ CaoVmState::Running(_) => {
+            std::ptr::write(state_ptr, running);
+            return ExecutionResult::cao_ExecutionResult_BadVm;
+        }
+    };
+
+    let vm = Vm::new(std::ptr::null_mut()).expect("Failed to initialize the VM");
+    match vm.resume(suspended, result.into()) {
+        Ok(RunOutcome::Finished(_)) => {
+            std::ptr::write(
+                state_ptr,
+                CaoVmState::Running(
+                    Vm::new(std::ptr::null_mut()).expect("Failed to initialize the VM"),
+                ),
+            );
+            ExecutionResult::cao_ExecutionResult_Ok
+        }
+        Ok(RunOutcome::Yielded(suspended)) => {
+            std::ptr::write(state_ptr, CaoVmState::Suspended(suspended));
+            ExecutionResult::cao_ExecutionResult_Suspended
+        }
+        Err(err) => {
+            std::ptr::write(
+                state_ptr,
+                CaoVmState::Running(
+                    Vm::new(std::ptr::null_mut()).expect("Failed to initialize the VM"),
+                ),
+            );
+            set_last_error(format_execution_error(&err));
+            execution_result_of_payload(err.payload)
+        }
+    }
+}
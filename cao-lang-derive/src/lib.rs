@@ -0,0 +1,102 @@
+//! `#[derive(ByteEncode)]`: implements `cao_lang::byte_encode::ByteEncode` for a struct by
+//! encoding/decoding each field in turn, recursing into field types that are themselves
+//! `ByteEncode` (which every primitive cao-lang already hand-implements it for is).
+//!
+//! Modelled on rust-bitcoin's `impl_consensus_encoding!`: rather than hand-writing a `to_value`/
+//! `from_value` pair for every host struct a game wants to pass into a script, derive them from
+//! the struct's own field list. Fields round-trip through a cao-lang table keyed by their
+//! declaration order (`Value::Integer(0)`, `Value::Integer(1)`, ...), not by name: cao-lang
+//! tables don't need string keys to be useful records, and this way encoding a struct never has
+//! to allocate a string per field.
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields};
+
+#[proc_macro_derive(ByteEncode)]
+pub fn derive_byte_encode(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return syn::Error::new_spanned(
+                    &input.ident,
+                    "ByteEncode can only be derived for structs with named fields",
+                )
+                .to_compile_error()
+                .into()
+            }
+        },
+        _ => {
+            return syn::Error::new_spanned(
+                &input.ident,
+                "ByteEncode can only be derived for structs",
+            )
+            .to_compile_error()
+            .into()
+        }
+    };
+
+    let field_idents: Vec<_> = fields.iter().map(|f| f.ident.as_ref().unwrap()).collect();
+    let field_names: Vec<_> = field_idents.iter().map(|i| i.to_string()).collect();
+    let indices: Vec<i64> = (0..field_idents.len() as i64).collect();
+
+    let encode_fields = field_idents.iter().zip(&indices).map(|(ident, idx)| {
+        quote! {
+            table
+                .as_table_mut()
+                .expect("freshly initialized table")
+                .insert(#idx, ::cao_lang::byte_encode::ByteEncode::to_value(self.#ident, vm)?)?;
+        }
+    });
+
+    let decode_fields = field_idents
+        .iter()
+        .zip(indices.iter().zip(&field_names))
+        .map(|(ident, (idx, name))| {
+            quote! {
+                #ident: {
+                    let value = *table.get(#idx).ok_or_else(|| {
+                        ::cao_lang::prelude::ExecutionErrorPayload::invalid_argument(
+                            concat!("Missing field `", #name, "`"),
+                        )
+                    })?;
+                    ::cao_lang::byte_encode::ByteEncode::from_value(value, vm)?
+                }
+            }
+        });
+
+    let displayname = name.to_string();
+
+    let expanded = quote! {
+        impl #impl_generics ::cao_lang::byte_encode::ByteEncode<Aux> for #name #ty_generics #where_clause {
+            fn to_value(
+                self,
+                vm: &mut ::cao_lang::vm::Vm<Aux>,
+            ) -> ::std::result::Result<::cao_lang::value::Value, ::cao_lang::prelude::ExecutionErrorPayload> {
+                let mut table = vm.init_table()?;
+                #( #encode_fields )*
+                Ok(table.into())
+            }
+
+            fn from_value(
+                value: ::cao_lang::value::Value,
+                vm: &::cao_lang::vm::Vm<Aux>,
+            ) -> ::std::result::Result<Self, ::cao_lang::prelude::ExecutionErrorPayload> {
+                let table = vm.get_table(value)?;
+                Ok(Self {
+                    #( #decode_fields, )*
+                })
+            }
+
+            fn displayname() -> &'static str {
+                #displayname
+            }
+        }
+    };
+
+    expanded.into()
+}
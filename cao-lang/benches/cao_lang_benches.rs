@@ -1,4 +1,7 @@
-use cao_lang::{compiler::CompileOptions, prelude::*};
+use cao_lang::{
+    compiler::{CardBody, CompileOptions, ForEach},
+    prelude::*,
+};
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
 
 const FIB_PROG: &str = include_str!("fibonacci_program.yaml");
@@ -86,6 +89,43 @@ fn run_fib_iter(c: &mut Criterion) {
     group.finish();
 }
 
+/// Builds a chain of `depth` nested tables (each holding only the next one) and roots the head
+/// by pushing it onto the value stack, so a GC cycle has to walk the whole chain to find
+/// everything reachable.
+fn build_table_chain(vm: &mut Vm<()>, depth: usize) {
+    let mut head = vm.init_table().unwrap();
+    for _ in 1..depth {
+        let mut next = vm.init_table().unwrap();
+        next.as_table_mut()
+            .unwrap()
+            .append(Value::Object(head.into_inner()))
+            .unwrap();
+        head = next;
+    }
+    vm.stack_push(Value::Object(head.into_inner()))
+        .expect("push GC root");
+}
+
+fn run_gc_mark_sweep(c: &mut Criterion) {
+    let mut group = c.benchmark_group("gc mark-sweep on a table chain");
+    for depth in [64usize, 512, 4096] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(depth),
+            &depth,
+            move |b, &depth| {
+                let mut vm = Vm::new(()).unwrap();
+                vm.runtime_data.set_memory_limit(1024 * 1024 * 1024);
+                b.iter(|| {
+                    vm.clear();
+                    build_table_chain(&mut vm, depth);
+                    vm.collect_garbage().expect("gc failed");
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
 fn run_empty_function_call(c: &mut Criterion) {
     c.bench_function("empty function call", |b| {
         let cu = CaoProgram {
@@ -115,11 +155,65 @@ fn run_empty_function_call(c: &mut Criterion) {
     });
 }
 
+/// Sums `0..iterations` into a global accumulator via a lazy-range `ForEach` loop, so the body's
+/// only work each iteration is a single `Add` between two `Value::Integer`s - the case
+/// `Vm::checked_arith_op`'s `i64` fast path targets.
+fn run_integer_foreach(c: &mut Criterion) {
+    let mut group = c.benchmark_group("integer-heavy foreach loop");
+    for iterations in [256i64, 4096, 65536] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(iterations),
+            &iterations,
+            move |b, &iterations| {
+                let cu = CaoProgram {
+                    imports: Default::default(),
+                    submodules: Default::default(),
+                    functions: [(
+                        "main".into(),
+                        Function::default().with_cards(vec![
+                            Card::set_global_var("acc", Card::scalar_int(0)),
+                            ForEach {
+                                i: Some("i".to_string()),
+                                k: None,
+                                v: None,
+                                iterable: Box::new(Card::call_native(
+                                    "__lazy_range",
+                                    vec![Card::scalar_int(0), Card::scalar_int(iterations)],
+                                )),
+                                body: Box::new(Card::set_global_var(
+                                    "acc",
+                                    CardBody::Add(Box::new([
+                                        Card::read_var("acc"),
+                                        Card::read_var("i"),
+                                    ]))
+                                    .into(),
+                                )),
+                            }
+                            .into(),
+                        ]),
+                    )]
+                    .into(),
+                };
+                let program = compile(cu, CompileOptions::new()).unwrap();
+
+                let mut vm = Vm::new(()).unwrap().with_max_iter(1 << 30);
+                b.iter(|| {
+                    vm.clear();
+                    vm.run(&program).expect("run failed");
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
 criterion_group!(
     benches,
     run_fib_iter,
     run_fib_recursive,
-    run_empty_function_call
+    run_empty_function_call,
+    run_integer_foreach,
+    run_gc_mark_sweep
 );
 
 criterion_main!(benches);
@@ -45,4 +45,58 @@ pub const PATCH: u16 = {};
         ),
     )
     .expect("Failed to write version file");
+
+    generate_instruction_table(&out_dir);
+}
+
+/// Turns `instructions.in` (one `Name operand,kinds` line per opcode) into
+/// `$OUT_DIR/cao_lang_instructions.rs`, the body of `operand_layout` plus
+/// `INSTRUCTION_COUNT`. Keeping the operand widths in one declarative table means the compiler's
+/// emission, the VM's decode loop and the disassembler can all read them back instead of
+/// maintaining their own copies, which used to be free to quietly drift apart.
+fn generate_instruction_table(out_dir: &std::ffi::OsStr) {
+    println!("cargo:rerun-if-changed=instructions.in");
+
+    let dest_path = Path::new(out_dir).join("cao_lang_instructions.rs");
+    let src = fs::read_to_string("instructions.in").expect("Failed to read instructions.in");
+
+    let mut arms = String::new();
+    let mut count = 0usize;
+    for line in src.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut parts = line.split_whitespace();
+        let name = parts
+            .next()
+            .unwrap_or_else(|| panic!("malformed line in instructions.in: {line:?}"));
+        let operands = parts
+            .next()
+            .unwrap_or_else(|| panic!("malformed line in instructions.in: {line:?}"));
+        count += 1;
+
+        let kinds = if operands == "-" {
+            String::new()
+        } else {
+            operands
+                .split(',')
+                .map(|kind| format!("OperandKind::{kind}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        arms.push_str(&format!(
+            "        Instruction::{name} => &[{kinds}],\n"
+        ));
+    }
+
+    fs::write(
+        &dest_path,
+        format!(
+            "pub(crate) const INSTRUCTION_COUNT: usize = {count};\n\n\
+             pub(crate) fn operand_layout(instr: Instruction) -> &'static [OperandKind] {{\n\
+             \x20\x20\x20match instr {{\n{arms}    }}\n}}\n"
+        ),
+    )
+    .expect("Failed to write instruction table");
 }
@@ -1,15 +1,97 @@
-mod bump_alloc;
-pub use bump_alloc::*;
+mod caolang_alloc;
+mod free_list;
+mod gc_policy;
+mod sync_proxy;
+pub use caolang_alloc::*;
+pub use free_list::{BumpArena, FreeListAllocator};
+pub use gc_policy::{DoublingGcPolicy, GcPolicy};
+pub use sync_proxy::SyncAllocProxy;
 
-use std::{
-    alloc::{alloc, dealloc, Layout},
-    ptr::NonNull,
-};
+use crate::alloc_crate::alloc::{alloc, alloc_zeroed, dealloc, realloc};
+use core::{alloc::Layout, ptr::NonNull};
 
 // TODO: replace w/ standard traits once they are stabilized
 pub trait Allocator {
     unsafe fn alloc(&self, l: Layout) -> Result<NonNull<u8>, AllocError>;
     unsafe fn dealloc(&self, p: NonNull<u8>, l: Layout);
+
+    /// Same as [`Self::alloc`], but the returned memory is guaranteed zeroed. The default
+    /// implementation is `alloc` followed by a `write_bytes` zero-fill; implementors that can
+    /// service this directly (e.g. [`SysAllocator`], via `std::alloc::alloc_zeroed`) should
+    /// override it to skip the redundant memset.
+    ///
+    /// # Safety
+    /// Same as [`Self::alloc`].
+    unsafe fn alloc_zeroed(&self, l: Layout) -> Result<NonNull<u8>, AllocError> {
+        let ptr = self.alloc(l)?;
+        ptr.as_ptr().write_bytes(0, l.size());
+        Ok(ptr)
+    }
+
+    /// Same as [`Self::alloc`], but the returned fat pointer carries the block's true usable
+    /// size, which may be larger than `l.size()` - e.g. a size-class allocator rounds a request
+    /// up to its class size. Callers that can exploit the extra headroom (a `Vec`-like type that
+    /// only reallocates once it outgrows the *reported* capacity, not just the requested one)
+    /// get it for free; callers that don't care can just take `.len()` as `l.size()`. The default
+    /// implementation doesn't know any better than `l.size()` itself - implementors that track
+    /// real block sizes (e.g. [`FreeListAllocator`]'s size-class buckets) should override it.
+    ///
+    /// # Safety
+    /// Same as [`Self::alloc`].
+    unsafe fn alloc_blocksize(&self, l: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let ptr = self.alloc(l)?;
+        Ok(NonNull::slice_from_raw_parts(ptr, l.size()))
+    }
+
+    /// Resizes a live allocation from `old_layout` to `new_layout`, preserving the first
+    /// `min(old_layout.size(), new_layout.size())` bytes. The default implementation is always
+    /// correct but never in-place: allocate `new_layout`, copy, free `old_layout` - implementors
+    /// that can extend or shrink a block without moving it (e.g. [`SysAllocator`], when alignment
+    /// is unchanged) should override this for an O(1) fast path.
+    ///
+    /// # Safety
+    /// `p` must currently be a live allocation from this allocator made with `old_layout`, and
+    /// must not be touched again (including via [`Self::dealloc`]) unless this call returns `Err`.
+    unsafe fn realloc(
+        &self,
+        p: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<u8>, AllocError> {
+        let new_ptr = self.alloc(new_layout)?;
+        let copy_len = old_layout.size().min(new_layout.size());
+        core::ptr::copy_nonoverlapping(p.as_ptr(), new_ptr.as_ptr(), copy_len);
+        self.dealloc(p, old_layout);
+        Ok(new_ptr)
+    }
+
+    /// [`Self::realloc`] to a `new_layout` that's at least as big as `old_layout` - see its docs.
+    ///
+    /// # Safety
+    /// Same as [`Self::realloc`].
+    unsafe fn grow(
+        &self,
+        p: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<u8>, AllocError> {
+        debug_assert!(new_layout.size() >= old_layout.size());
+        self.realloc(p, old_layout, new_layout)
+    }
+
+    /// [`Self::realloc`] to a `new_layout` that's no bigger than `old_layout` - see its docs.
+    ///
+    /// # Safety
+    /// Same as [`Self::realloc`].
+    unsafe fn shrink(
+        &self,
+        p: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<u8>, AllocError> {
+        debug_assert!(new_layout.size() <= old_layout.size());
+        self.realloc(p, old_layout, new_layout)
+    }
 }
 
 #[derive(Debug, Clone, thiserror::Error)]
@@ -34,6 +116,37 @@ impl Allocator for SysAllocator {
     unsafe fn dealloc(&self, p: NonNull<u8>, l: Layout) {
         dealloc(p.as_ptr(), l);
     }
+
+    unsafe fn alloc_zeroed(&self, l: Layout) -> Result<NonNull<u8>, AllocError> {
+        let res = alloc_zeroed(l);
+        if res.is_null() {
+            return Err(AllocError::OutOfMemory);
+        }
+        Ok(NonNull::new_unchecked(res))
+    }
+
+    /// Mirrors `__rust_realloc`: when alignment is unchanged, resize in place via
+    /// `std::alloc::realloc` instead of the default trait method's always-move alloc+copy+free.
+    /// A changed alignment still needs a fresh block, so falls back to that default.
+    unsafe fn realloc(
+        &self,
+        p: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<u8>, AllocError> {
+        if old_layout.align() != new_layout.align() {
+            let new_ptr = self.alloc(new_layout)?;
+            core::ptr::copy_nonoverlapping(
+                p.as_ptr(),
+                new_ptr.as_ptr(),
+                old_layout.size().min(new_layout.size()),
+            );
+            self.dealloc(p, old_layout);
+            return Ok(new_ptr);
+        }
+        let res = realloc(p.as_ptr(), old_layout, new_layout.size());
+        NonNull::new(res).ok_or(AllocError::OutOfMemory)
+    }
 }
 
 unsafe impl Send for SysAllocator {}
@@ -1,18 +1,68 @@
 use tracing::debug;
 
-use crate::vm::runtime::RuntimeData;
+use crate::value::Value;
+use crate::vm::runtime::cao_lang_object::CaoLangObject;
+use crate::vm::runtime::{GcPhase, RuntimeData};
 
-use super::{AllocError, Allocator};
-use std::{
-    alloc::{alloc, dealloc, Layout},
+use super::{
+    free_list::FreeListAllocator, AllocError, Allocator, BumpArena, DoublingGcPolicy, GcPolicy,
+};
+use crate::alloc_crate::{
+    alloc::{alloc, alloc_zeroed, dealloc, realloc},
+    boxed::Box,
+    rc::Rc,
+};
+use core::{
+    alloc::Layout,
     cell::UnsafeCell,
     marker::PhantomData,
     ops::Deref,
     ptr::NonNull,
-    rc::Rc,
-    sync::atomic::{AtomicUsize, Ordering},
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
 };
 
+/// Where a [`CaoLangAllocator`] actually pulls memory from.
+///
+/// `System` is the historical behaviour (forward to the global allocator). The other variants
+/// service allocations out of a fixed, caller-provided byte span and never touch the global
+/// allocator, which is what makes them usable in `no_std`/embedded/WASM-with-fixed-heap builds.
+pub enum AllocBackend {
+    /// Forward to the global (system) allocator.
+    System,
+    /// Bump-allocate out of a fixed arena, meant for short-lived per-call-frame temporaries.
+    /// Individual objects are never freed; call [`BumpArena::reset`] (e.g. on call-frame pop) to
+    /// reclaim the whole arena at once.
+    Bump(UnsafeCell<BumpArena>),
+    /// A free-list allocator with coalescing, backing long-lived GC-managed objects.
+    FreeList(UnsafeCell<FreeListAllocator>),
+}
+
+impl AllocBackend {
+    pub fn bump(size: usize) -> Self {
+        Self::Bump(UnsafeCell::new(BumpArena::new(size)))
+    }
+
+    pub fn free_list(size: usize) -> Self {
+        Self::FreeList(UnsafeCell::new(FreeListAllocator::new(size)))
+    }
+}
+
+impl core::fmt::Debug for AllocBackend {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let name = match self {
+            Self::System => "System",
+            Self::Bump(_) => "Bump",
+            Self::FreeList(_) => "FreeList",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Units of incremental mark/sweep work [`CaoLangAllocator::alloc`] performs per byte allocated
+/// while a collection cycle is running, so the step keeps pace with allocation rate instead of
+/// falling behind it.
+const GC_STEP_SCALE: usize = 4;
+
 /// # Safety
 ///
 /// Note that CaoLangAllocator is NOT thread-safe!
@@ -51,15 +101,32 @@ pub struct CaoLangAllocator {
     pub allocated: AtomicUsize,
     pub next_gc: AtomicUsize,
     pub limit: AtomicUsize,
+    /// When `false`, [`Self::alloc`] never triggers a collection on its own - the host has to
+    /// call [`crate::vm::Vm::collect_garbage`] itself. See
+    /// [`RuntimeData::set_auto_gc_enabled`].
+    pub auto_gc_enabled: AtomicBool,
+    /// Decides when to start a collection cycle and how far out the next one should be - see
+    /// [`GcPolicy`]. Defaults to [`DoublingGcPolicy`], the allocator's original heuristic.
+    pub policy: Box<dyn GcPolicy>,
+    backend: AllocBackend,
 }
 
 impl CaoLangAllocator {
     pub fn new(vm: *mut RuntimeData, limit: usize) -> Self {
+        Self::with_backend(vm, limit, AllocBackend::System)
+    }
+
+    /// Construct an allocator that services every allocation via `backend` instead of the
+    /// global allocator. See [`AllocBackend`].
+    pub fn with_backend(vm: *mut RuntimeData, limit: usize, backend: AllocBackend) -> Self {
         Self {
             runtime: vm,
             allocated: AtomicUsize::new(0),
             next_gc: AtomicUsize::new((limit / 4).max(16)),
             limit: AtomicUsize::new(limit),
+            auto_gc_enabled: AtomicBool::new(true),
+            policy: Box::new(DoublingGcPolicy::default()),
+            backend,
         }
     }
 
@@ -67,23 +134,117 @@ impl CaoLangAllocator {
     /// `alloc` is not thread safe. It is on the caller to ensure that only a single thread uses
     /// the allocator at a time
     pub unsafe fn alloc(&self, l: Layout) -> Result<NonNull<u8>, AllocError> {
-        let s = l.size() + l.align();
-        let allocated = s + self.allocated.fetch_add(s, Ordering::Relaxed);
+        self.alloc_impl(l, false)
+    }
+
+    /// Same accounting/GC-trigger path as [`Self::alloc`], but the returned memory is guaranteed
+    /// zeroed - one syscall-level `alloc_zeroed` on the system backend instead of an `alloc` the
+    /// caller then has to `memset` themselves, which matters for large table backing stores.
+    ///
+    /// # Safety
+    /// Same as [`Self::alloc`].
+    pub unsafe fn alloc_zeroed(&self, l: Layout) -> Result<NonNull<u8>, AllocError> {
+        self.alloc_impl(l, true)
+    }
+
+    /// Same accounting/GC-trigger path as [`Self::alloc`], but the returned fat pointer carries
+    /// the block's true usable size - on the `FreeList` backend a size-class bucket rounds a
+    /// request up to its class size, so `allocated` ends up tracking the real footprint instead
+    /// of the (smaller) requested one, and the caller learns about any spare capacity to grow
+    /// into without a further allocation.
+    ///
+    /// # Safety
+    /// Same as [`Self::alloc`].
+    pub unsafe fn alloc_blocksize(&self, l: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.alloc_impl_sized(l, false)
+    }
+
+    unsafe fn alloc_impl(&self, l: Layout, zeroed: bool) -> Result<NonNull<u8>, AllocError> {
+        self.alloc_impl_sized(l, zeroed).map(|slice| slice.cast())
+    }
+
+    unsafe fn alloc_impl_sized(&self, l: Layout, zeroed: bool) -> Result<NonNull<[u8]>, AllocError> {
+        // Admit against the same `l.size() + l.align()` estimate `alloc_impl` always used - the
+        // real block handed back by the backend is reconciled into `allocated` below once known,
+        // the same way `realloc` reconciles a signed size delta instead of a flat charge.
+        let estimate = l.size() + l.align();
+        let allocated = estimate + self.allocated.fetch_add(estimate, Ordering::Relaxed);
         if allocated > self.limit.load(Ordering::Relaxed) {
             return Err(AllocError::OutOfMemory);
         }
-        if allocated > self.next_gc.load(Ordering::Relaxed) {
-            self.next_gc.store(allocated * 2, Ordering::Relaxed);
-            unsafe {
+        let cycle_in_progress = !matches!((*self.runtime).gc_phase, GcPhase::Idle);
+        let auto_gc = self.auto_gc_enabled.load(Ordering::Relaxed);
+        let limit = self.limit.load(Ordering::Relaxed);
+        // a cycle already under way must still be stepped to completion even if auto-triggering
+        // new cycles has since been disabled, or it would stall forever mid-mark/sweep
+        let next_gc = self.next_gc.load(Ordering::Relaxed);
+        if cycle_in_progress || (auto_gc && self.policy.should_collect(allocated, next_gc, limit))
+        {
+            // Step the incremental collector forward by an amount proportional to the bytes
+            // just allocated instead of driving a whole cycle to completion synchronously - a
+            // single allocation shouldn't pay for scanning the entire object graph.
+            let cycle_done = (*self.runtime).gc_work(estimate * GC_STEP_SCALE);
+            if cycle_done {
+                // retune the threshold off the post-sweep live size (`allocated` already
+                // reflects it: every freed object's `dealloc` call decremented it) via the
+                // configured policy, instead of blindly doubling the pre-sweep one
+                let survivors = self.allocated.load(Ordering::Relaxed);
+                self.next_gc.store(
+                    self.policy.next_threshold(survivors, limit),
+                    Ordering::Relaxed,
+                );
+                debug!("GC cycle done. Allocated now: {survivors}");
+            }
+        }
+        let slice = match self.alloc_from_backend(l, zeroed) {
+            Ok(slice) => slice,
+            // The byte budget has room, but fragmentation left no single free block big enough
+            // (this can happen well before `limit` on the `Bump`/`FreeList` backends). Run a
+            // collection - which also coalesces freed `FreeList` blocks - and retry once before
+            // giving up, so long-running scripts reuse freed space instead of failing outright.
+            Err(AllocError::OutOfMemory) => {
                 (*self.runtime).gc();
+                self.alloc_from_backend(l, zeroed)?
+            }
+        };
+        // true up `allocated` from the admission estimate to the block's real size, same signed-
+        // delta trick as `realloc` uses so we neither double-count nor lose track of bytes.
+        let true_size = slice.len();
+        if true_size > estimate {
+            self.allocated
+                .fetch_add(true_size - estimate, Ordering::Relaxed);
+        } else if true_size < estimate {
+            self.allocated
+                .fetch_sub(estimate - true_size, Ordering::Relaxed);
+        }
+        Ok(slice)
+    }
+
+    unsafe fn alloc_from_backend(&self, l: Layout, zeroed: bool) -> Result<NonNull<[u8]>, AllocError> {
+        match &self.backend {
+            AllocBackend::System => {
+                let ptr = if zeroed { alloc_zeroed(l) } else { alloc(l) };
+                let ptr = NonNull::new(ptr).ok_or(AllocError::OutOfMemory)?;
+                Ok(NonNull::slice_from_raw_parts(ptr, l.size()))
+            }
+            // `Bump`/`FreeList` blocks can be reused from a previous `dealloc`, so (unlike the
+            // system backend's fresh OS pages) their bytes aren't zero by construction - zero
+            // them explicitly instead of assuming it.
+            AllocBackend::Bump(arena) => {
+                let ptr = (*arena.get()).alloc(l)?;
+                if zeroed {
+                    ptr.as_ptr().write_bytes(0, l.size());
+                }
+                Ok(NonNull::slice_from_raw_parts(ptr, l.size()))
+            }
+            AllocBackend::FreeList(fl) => {
+                let slice = (*fl.get()).alloc_blocksize(l)?;
+                if zeroed {
+                    slice.cast::<u8>().as_ptr().write_bytes(0, slice.len());
+                }
+                Ok(slice)
             }
-            debug!(
-                "GC done. Allocated before: {allocated}. Allocated now: {}",
-                self.allocated.load(Ordering::Relaxed)
-            );
         }
-        let ptr = alloc(l);
-        Ok(NonNull::new(ptr).unwrap())
     }
 
     /// # Safety
@@ -92,7 +253,94 @@ impl CaoLangAllocator {
     pub unsafe fn dealloc(&self, p: NonNull<u8>, l: Layout) {
         let s = l.size() + l.align();
         self.allocated.fetch_sub(s, Ordering::Relaxed);
-        dealloc(p.as_ptr(), l);
+        self.dealloc_from_backend(p, l);
+    }
+
+    /// Frees `p` at the backend level only, without touching `self.allocated` - split out of
+    /// [`Self::dealloc`] so [`Self::realloc`]'s fallback path (which accounts for the size delta
+    /// itself, not a full charge/refund pair) can free the old block without double-counting it.
+    unsafe fn dealloc_from_backend(&self, p: NonNull<u8>, l: Layout) {
+        match &self.backend {
+            AllocBackend::System => dealloc(p.as_ptr(), l),
+            // bump arenas are reclaimed in bulk by their owner, not per-allocation
+            AllocBackend::Bump(_) => {}
+            AllocBackend::FreeList(fl) => (*fl.get()).dealloc(p, l),
+        }
+    }
+
+    /// # Safety
+    /// `p` must be a live allocation from this instance made with `old_layout`; not thread safe,
+    /// same caveat as [`Self::alloc`].
+    pub unsafe fn realloc(
+        &self,
+        p: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<u8>, AllocError> {
+        let old_s = old_layout.size() + old_layout.align();
+        let new_s = new_layout.size() + new_layout.align();
+        // Update `allocated` by the signed size delta, not a `fetch_add` of the full new size -
+        // `old_s` was already charged when `old_layout` was allocated, so re-adding it here would
+        // double-count the bytes this block already held.
+        let allocated = if new_s >= old_s {
+            self.allocated.fetch_add(new_s - old_s, Ordering::Relaxed) + (new_s - old_s)
+        } else {
+            self.allocated.fetch_sub(old_s - new_s, Ordering::Relaxed) - (old_s - new_s)
+        };
+        if allocated > self.limit.load(Ordering::Relaxed) {
+            return Err(AllocError::OutOfMemory);
+        }
+        let cycle_in_progress = !matches!((*self.runtime).gc_phase, GcPhase::Idle);
+        let auto_gc = self.auto_gc_enabled.load(Ordering::Relaxed);
+        let limit = self.limit.load(Ordering::Relaxed);
+        let next_gc = self.next_gc.load(Ordering::Relaxed);
+        if cycle_in_progress || (auto_gc && self.policy.should_collect(allocated, next_gc, limit))
+        {
+            let cycle_done = (*self.runtime).gc_work(new_s * GC_STEP_SCALE);
+            if cycle_done {
+                let survivors = self.allocated.load(Ordering::Relaxed);
+                self.next_gc.store(
+                    self.policy.next_threshold(survivors, limit),
+                    Ordering::Relaxed,
+                );
+                debug!("GC cycle done. Allocated now: {survivors}");
+            }
+        }
+        match &self.backend {
+            // in-place resize only possible when the backend can actually extend/shrink a block
+            // without moving it, which only the system allocator (via `std::alloc::realloc`) can
+            // promise here - `Bump`/`FreeList` hand out fixed-size-class or bump-carved blocks
+            // with no neighbouring free space to grow into, so they always take the move path.
+            AllocBackend::System if old_layout.align() == new_layout.align() => {
+                let res = realloc(p.as_ptr(), old_layout, new_layout.size());
+                NonNull::new(res).ok_or(AllocError::OutOfMemory)
+            }
+            _ => {
+                let new_ptr = self.alloc_from_backend(new_layout, false)?.cast::<u8>();
+                core::ptr::copy_nonoverlapping(
+                    p.as_ptr(),
+                    new_ptr.as_ptr(),
+                    old_layout.size().min(new_layout.size()),
+                );
+                self.dealloc_from_backend(p, old_layout);
+                Ok(new_ptr)
+            }
+        }
+    }
+
+    /// Delegates to [`RuntimeData::write_barrier`]: if `parent` was already fully marked black
+    /// and just gained a reference to a still-white `child`, shades `child` gray so it still
+    /// gets scanned this cycle. Now that [`Self::alloc`] steps the collector incrementally
+    /// instead of completing a cycle in one call, a table or closure can be mutated in the
+    /// window between two steps - without this, such a mutation could let a reachable object be
+    /// swept as garbage before the next step rediscovers it. Callers are expected to invoke this
+    /// right after storing `child` into an object already reachable from a root, e.g.
+    /// `Instruction::SetProperty`/`Instruction::AppendTable`.
+    ///
+    /// # Safety
+    /// `parent` must be a still-live object owned by this allocator's `RuntimeData`.
+    pub unsafe fn write_barrier(&self, parent: NonNull<CaoLangObject>, child: Value) {
+        (*self.runtime).write_barrier(parent, child);
     }
 }
 
@@ -104,6 +352,23 @@ impl Allocator for CaoLangAllocator {
     unsafe fn dealloc(&self, p: NonNull<u8>, l: Layout) {
         CaoLangAllocator::dealloc(self, p, l)
     }
+
+    unsafe fn alloc_zeroed(&self, l: Layout) -> Result<NonNull<u8>, AllocError> {
+        CaoLangAllocator::alloc_zeroed(self, l)
+    }
+
+    unsafe fn alloc_blocksize(&self, l: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        CaoLangAllocator::alloc_blocksize(self, l)
+    }
+
+    unsafe fn realloc(
+        &self,
+        p: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<u8>, AllocError> {
+        CaoLangAllocator::realloc(self, p, old_layout, new_layout)
+    }
 }
 impl Allocator for AllocProxy {
     unsafe fn alloc(&self, l: Layout) -> Result<NonNull<u8>, AllocError> {
@@ -113,4 +378,21 @@ impl Allocator for AllocProxy {
     unsafe fn dealloc(&self, p: NonNull<u8>, l: Layout) {
         (*self.inner.get()).dealloc(p, l)
     }
+
+    unsafe fn alloc_zeroed(&self, l: Layout) -> Result<NonNull<u8>, AllocError> {
+        (*self.inner.get()).alloc_zeroed(l)
+    }
+
+    unsafe fn alloc_blocksize(&self, l: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        (*self.inner.get()).alloc_blocksize(l)
+    }
+
+    unsafe fn realloc(
+        &self,
+        p: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<u8>, AllocError> {
+        (*self.inner.get()).realloc(p, old_layout, new_layout)
+    }
 }
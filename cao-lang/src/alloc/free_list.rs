@@ -0,0 +1,221 @@
+//! A free-list allocator over a single, caller-provided byte span.
+//!
+//! Unlike [`super::CaoLangAllocator`], which forwards to the global (system) allocator, this
+//! backend never calls into the OS: every allocation is carved out of a fixed `Box<[u8]>` handed
+//! to [`FreeListAllocator::new`]. That makes it suitable for `no_std`/embedded/WASM-with-fixed-heap
+//! builds where there is no global allocator to fall back on.
+//!
+//! Requests that fit a [`SIZE_CLASSES`] bucket (the common case - tables, closures, strings, ...)
+//! are served by that bucket's segregated free list: popping/pushing an offset is O(1), and a
+//! bucket that has nothing free carves a fresh same-sized block off the bump `cursor`. Anything
+//! bigger falls back to the large-object path: an address-ordered list that `alloc` scans
+//! first-fit and `dealloc` reinserts in address order, coalescing with its immediate neighbors -
+//! the same design this allocator used exclusively before size classes were added.
+use crate::alloc_crate::boxed::Box;
+use core::{alloc::Layout, ptr::NonNull};
+
+use super::AllocError;
+
+/// Blocks smaller than this are not worth splitting off; the remainder is left attached to the
+/// allocation instead of becoming a (practically unusable) free block.
+const MIN_BLOCK_SIZE: usize = 16;
+
+/// Upper bound (inclusive) of each segregated size class, in bytes. A request fits class `i` if
+/// it needs no more than `SIZE_CLASSES[i]` bytes and no stricter alignment than that - every
+/// entry is a power of two, so a block carved aligned to its own class size satisfies any
+/// alignment up to that size. Requests past the last class take the large-object path.
+const SIZE_CLASSES: [usize; 8] = [16, 32, 64, 128, 256, 512, 1024, 2048];
+
+/// Index of the smallest [`SIZE_CLASSES`] entry that fits `size` bytes aligned to `align`, or
+/// `None` if it belongs on the large-object path.
+fn size_class_for(size: usize, align: usize) -> Option<usize> {
+    SIZE_CLASSES
+        .iter()
+        .position(|&class_size| size <= class_size && align <= class_size)
+}
+
+#[derive(Debug, Clone, Copy)]
+struct FreeBlock {
+    offset: usize,
+    size: usize,
+}
+
+/// A bump arena for short-lived per-call-frame temporaries. It only ever grows until [`reset`] is
+/// called when the owning call frame is popped; it never frees individual allocations.
+///
+/// [`reset`]: BumpArena::reset
+#[derive(Debug)]
+pub struct BumpArena {
+    heap: Box<[u8]>,
+    cursor: usize,
+}
+
+impl BumpArena {
+    pub fn new(size: usize) -> Self {
+        Self {
+            heap: vec![0u8; size].into_boxed_slice(),
+            cursor: 0,
+        }
+    }
+
+    pub fn alloc(&mut self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        let base = self.heap.as_ptr() as usize;
+        let start = (base + self.cursor + layout.align() - 1) & !(layout.align() - 1);
+        let end = start
+            .checked_add(layout.size())
+            .ok_or(AllocError::OutOfMemory)?;
+        if end > base + self.heap.len() {
+            return Err(AllocError::OutOfMemory);
+        }
+        self.cursor = end - base;
+        Ok(unsafe { NonNull::new_unchecked(start as *mut u8) })
+    }
+
+    /// Frees every allocation made since the arena was created (or last reset). Callers must
+    /// ensure nothing still references memory handed out by this arena.
+    pub fn reset(&mut self) {
+        self.cursor = 0;
+    }
+}
+
+/// A segregated size-class free-list allocator backing GC-managed objects over a fixed byte
+/// span, falling back to a Talc-style address-ordered, coalescing free list for large objects.
+#[derive(Debug)]
+pub struct FreeListAllocator {
+    heap: Box<[u8]>,
+    /// Per-[`SIZE_CLASSES`] free lists, given as offsets into `heap`. Entirely unordered -
+    /// popping the last element is O(1), and every block in a bucket is interchangeable since
+    /// they're all the same size.
+    buckets: [Vec<usize>; SIZE_CLASSES.len()],
+    /// Not-yet-carved space, bump-allocated to mint a fresh block when a size class's bucket (or
+    /// the large-object path) has nothing free.
+    cursor: usize,
+    /// Address-ordered list of free blocks too big for any size class, given as offsets into
+    /// `heap`.
+    large_free: Vec<FreeBlock>,
+}
+
+impl FreeListAllocator {
+    pub fn new(size: usize) -> Self {
+        Self {
+            heap: vec![0u8; size].into_boxed_slice(),
+            buckets: Default::default(),
+            cursor: 0,
+            large_free: Vec::new(),
+        }
+    }
+
+    fn base(&self) -> usize {
+        self.heap.as_ptr() as usize
+    }
+
+    /// Bumps a fresh, `align`-aligned block of `size` bytes off `self.cursor`, the shared
+    /// not-yet-carved tail of `heap`.
+    fn bump(&mut self, size: usize, align: usize) -> Result<NonNull<u8>, AllocError> {
+        let aligned = (self.base() + self.cursor + align - 1) & !(align - 1);
+        let end = aligned
+            .checked_add(size)
+            .ok_or(AllocError::OutOfMemory)?;
+        if end > self.base() + self.heap.len() {
+            return Err(AllocError::OutOfMemory);
+        }
+        self.cursor = end - self.base();
+        Ok(unsafe { NonNull::new_unchecked(aligned as *mut u8) })
+    }
+
+    pub fn alloc(&mut self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        self.alloc_raw(layout).map(|(ptr, _size)| ptr)
+    }
+
+    /// Same as [`Self::alloc`], but also reports the block's true usable size - a size-class
+    /// bucket rounds every request up to its class size (e.g. a 20-byte request lands in the
+    /// 32-byte class), so the caller learns about the extra headroom instead of it going to
+    /// waste until the next `dealloc`.
+    pub fn alloc_blocksize(&mut self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.alloc_raw(layout)
+            .map(|(ptr, size)| NonNull::slice_from_raw_parts(ptr, size))
+    }
+
+    fn alloc_raw(&mut self, layout: Layout) -> Result<(NonNull<u8>, usize), AllocError> {
+        match size_class_for(layout.size(), layout.align()) {
+            Some(class) => {
+                if let Some(offset) = self.buckets[class].pop() {
+                    let ptr =
+                        unsafe { NonNull::new_unchecked((self.base() + offset) as *mut u8) };
+                    return Ok((ptr, SIZE_CLASSES[class]));
+                }
+                let ptr = self.bump(SIZE_CLASSES[class], SIZE_CLASSES[class])?;
+                Ok((ptr, SIZE_CLASSES[class]))
+            }
+            // the large-object path reports exactly `layout.size()` for the caller - a leftover of
+            // at least `MIN_BLOCK_SIZE` in a reused free block stays behind as a (still free)
+            // block of its own, while a smaller leftover stays attached to the allocation without
+            // being reported here.
+            None => self.alloc_large(layout).map(|ptr| (ptr, layout.size())),
+        }
+    }
+
+    fn alloc_large(&mut self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        for i in 0..self.large_free.len() {
+            let block = self.large_free[i];
+            let aligned =
+                (self.base() + block.offset + layout.align() - 1) & !(layout.align() - 1);
+            let pad = aligned - (self.base() + block.offset);
+            let needed = pad + layout.size();
+            if needed > block.size {
+                continue;
+            }
+
+            let leftover = block.size - needed;
+            if leftover >= MIN_BLOCK_SIZE {
+                self.large_free[i] = FreeBlock {
+                    offset: block.offset + needed,
+                    size: leftover,
+                };
+            } else {
+                self.large_free.remove(i);
+            }
+            return Ok(unsafe { NonNull::new_unchecked(aligned as *mut u8) });
+        }
+        self.bump(layout.size(), layout.align())
+    }
+
+    pub fn dealloc(&mut self, ptr: NonNull<u8>, layout: Layout) {
+        let offset = ptr.as_ptr() as usize - self.base();
+        match size_class_for(layout.size(), layout.align()) {
+            Some(class) => self.buckets[class].push(offset),
+            None => self.dealloc_large(offset, layout.size()),
+        }
+    }
+
+    fn dealloc_large(&mut self, offset: usize, size: usize) {
+        let block = FreeBlock { offset, size };
+        let idx = self
+            .large_free
+            .binary_search_by_key(&block.offset, |b| b.offset)
+            .unwrap_or_else(|i| i);
+        self.large_free.insert(idx, block);
+        self.coalesce_around(idx);
+    }
+
+    /// Merges the block at `idx` with its immediate predecessor and successor if they are
+    /// adjacent in memory.
+    fn coalesce_around(&mut self, idx: usize) {
+        if idx + 1 < self.large_free.len() {
+            let cur = self.large_free[idx];
+            let next = self.large_free[idx + 1];
+            if cur.offset + cur.size == next.offset {
+                self.large_free[idx].size += next.size;
+                self.large_free.remove(idx + 1);
+            }
+        }
+        if idx > 0 {
+            let prev = self.large_free[idx - 1];
+            let cur = self.large_free[idx];
+            if prev.offset + prev.size == cur.offset {
+                self.large_free[idx - 1].size += cur.size;
+                self.large_free.remove(idx);
+            }
+        }
+    }
+}
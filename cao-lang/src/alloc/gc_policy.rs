@@ -0,0 +1,78 @@
+//! Pluggable GC-trigger cadence, consulted by [`super::CaoLangAllocator::alloc`]/`realloc`
+//! instead of a hardcoded heuristic baked into the allocator itself.
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// Default [`DoublingGcPolicy`] growth factor: `next_gc` grows to 2x the surviving heap size
+/// after a sweep.
+const DEFAULT_GC_GROWTH_FACTOR_PERCENT: usize = 200;
+
+/// Decides when an allocator should start (or continue) a collection cycle, and how far out the
+/// next one should be once the current one finishes sweeping.
+///
+/// [`CaoLangAllocator::alloc`](super::CaoLangAllocator::alloc) and
+/// [`realloc`](super::CaoLangAllocator::realloc) call [`Self::should_collect`] right after their
+/// byte-budget admission check passes, and [`Self::next_threshold`] once an incremental cycle has
+/// just finished sweeping - the policy never sees individual `alloc`/`dealloc` calls, only the
+/// allocator's aggregate counters, so it can be swapped without touching anything backend-specific
+/// (`System`/`Bump`/`FreeList`).
+pub trait GcPolicy: core::fmt::Debug {
+    /// Whether a collection cycle should begin now, given the live byte count `allocated`, the
+    /// active `next_gc` threshold, and the allocator's hard `limit`. A cycle already in progress
+    /// is stepped to completion regardless of this return value - it only gates *starting* one.
+    fn should_collect(&self, allocated: usize, next_gc: usize, limit: usize) -> bool;
+
+    /// Computes the `next_gc` threshold to install once a cycle has just finished sweeping down
+    /// to `allocated` survivors, given the allocator's hard `limit`.
+    fn next_threshold(&self, allocated: usize, limit: usize) -> usize;
+
+    /// Lets [`CaoLangAllocator::set_gc_heap_growth_factor`](super::CaoLangAllocator) reach into a
+    /// live policy when it happens to be a [`DoublingGcPolicy`] - custom policies can leave this
+    /// at its default (`None`), in which case that setter is simply a no-op for them.
+    fn as_doubling_policy(&self) -> Option<&DoublingGcPolicy> {
+        None
+    }
+}
+
+/// The allocator's original GC-trigger heuristic: collect whenever `allocated` crosses
+/// `next_gc`, then retune `next_gc` to `growth_factor_percent`% of the post-sweep survivor size
+/// (e.g. `200` lets the heap double before the next cycle triggers).
+#[derive(Debug)]
+pub struct DoublingGcPolicy {
+    growth_factor_percent: AtomicUsize,
+}
+
+impl DoublingGcPolicy {
+    pub fn new(growth_factor_percent: usize) -> Self {
+        Self {
+            growth_factor_percent: AtomicUsize::new(growth_factor_percent),
+        }
+    }
+
+    pub fn growth_factor_percent(&self) -> usize {
+        self.growth_factor_percent.load(Ordering::Relaxed)
+    }
+
+    pub fn set_growth_factor_percent(&self, percent: usize) {
+        self.growth_factor_percent.store(percent, Ordering::Relaxed);
+    }
+}
+
+impl Default for DoublingGcPolicy {
+    fn default() -> Self {
+        Self::new(DEFAULT_GC_GROWTH_FACTOR_PERCENT)
+    }
+}
+
+impl GcPolicy for DoublingGcPolicy {
+    fn should_collect(&self, allocated: usize, next_gc: usize, _limit: usize) -> bool {
+        allocated > next_gc
+    }
+
+    fn next_threshold(&self, allocated: usize, _limit: usize) -> usize {
+        (allocated * self.growth_factor_percent() / 100).max(16)
+    }
+
+    fn as_doubling_policy(&self) -> Option<&DoublingGcPolicy> {
+        Some(self)
+    }
+}
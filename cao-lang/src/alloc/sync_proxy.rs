@@ -0,0 +1,113 @@
+//! A `Send + Sync` counterpart to [`AllocProxy`](super::AllocProxy) for embedders that want to
+//! drive a single shared heap budget from more than one thread.
+use core::{
+    alloc::Layout,
+    cell::UnsafeCell,
+    ptr::NonNull,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::alloc_crate::sync::Arc;
+
+use super::{AllocError, Allocator, CaoLangAllocator};
+
+/// A minimal spinlock guarding a [`CaoLangAllocator`] - there's no OS mutex available without
+/// `std`, and the critical sections here are always short (a single `alloc`/`dealloc`/`gc` call,
+/// never a long-held lock), so spinning is the right tradeoff over pulling in a scheduler-aware
+/// lock.
+#[derive(Debug, Default)]
+struct SpinLock {
+    locked: AtomicBool,
+}
+
+impl SpinLock {
+    fn lock(&self) {
+        while self
+            .locked
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            core::hint::spin_loop();
+        }
+    }
+
+    fn unlock(&self) {
+        self.locked.store(false, Ordering::Release);
+    }
+}
+
+/// `Send + Sync` wrapper around a [`CaoLangAllocator`], for embedders running several scripts
+/// concurrently against one shared heap budget. [`AllocProxy`](super::AllocProxy) is `Rc`-backed
+/// and explicitly single-threaded; this is its `Arc` counterpart. The `allocated`/`next_gc`/
+/// `limit` counters on [`CaoLangAllocator`] are already atomics, so the only thing a concurrent
+/// caller needs serialized is the backend `alloc`/`dealloc` call and the GC step triggered
+/// alongside it - a spinlock around the whole call does that without having to pick apart which
+/// backend-specific state (the bump cursor, the free-list buckets) needs its own synchronization.
+///
+/// # Safety
+/// This proxy only serializes the allocator's own state. [`CaoLangAllocator::runtime`] still
+/// points at a `RuntimeData` the GC steps against; callers embedding this across threads must
+/// ensure that `RuntimeData` (value stack, heap objects, ...) is itself safe to touch from
+/// whichever thread currently holds the lock, e.g. by only ever calling into the VM while holding
+/// some outer lock of their own.
+#[derive(Clone)]
+pub struct SyncAllocProxy {
+    inner: Arc<(SpinLock, UnsafeCell<CaoLangAllocator>)>,
+}
+
+impl From<CaoLangAllocator> for SyncAllocProxy {
+    fn from(inner: CaoLangAllocator) -> Self {
+        Self {
+            inner: Arc::new((SpinLock::default(), UnsafeCell::new(inner))),
+        }
+    }
+}
+
+impl SyncAllocProxy {
+    fn with_locked<R>(&self, f: impl FnOnce(&CaoLangAllocator) -> R) -> R {
+        let (lock, cell) = &*self.inner;
+        lock.lock();
+        let res = f(unsafe { &*cell.get() });
+        lock.unlock();
+        res
+    }
+}
+
+impl core::fmt::Debug for SyncAllocProxy {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SyncAllocProxy").finish_non_exhaustive()
+    }
+}
+
+impl Allocator for SyncAllocProxy {
+    unsafe fn alloc(&self, l: Layout) -> Result<NonNull<u8>, AllocError> {
+        self.with_locked(|a| unsafe { a.alloc(l) })
+    }
+
+    unsafe fn dealloc(&self, p: NonNull<u8>, l: Layout) {
+        self.with_locked(|a| unsafe { a.dealloc(p, l) })
+    }
+
+    unsafe fn alloc_zeroed(&self, l: Layout) -> Result<NonNull<u8>, AllocError> {
+        self.with_locked(|a| unsafe { a.alloc_zeroed(l) })
+    }
+
+    unsafe fn alloc_blocksize(&self, l: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.with_locked(|a| unsafe { a.alloc_blocksize(l) })
+    }
+
+    unsafe fn realloc(
+        &self,
+        p: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<u8>, AllocError> {
+        self.with_locked(|a| unsafe { a.realloc(p, old_layout, new_layout) })
+    }
+}
+
+// SAFETY: every access to the wrapped `CaoLangAllocator` goes through `with_locked`, which holds
+// `SpinLock` for the duration of the call - the same argument `Mutex<T>` relies on to be `Sync`
+// regardless of whether `T` is.
+unsafe impl Send for SyncAllocProxy {}
+unsafe impl Sync for SyncAllocProxy {}
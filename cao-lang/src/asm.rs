@@ -0,0 +1,586 @@
+//! Textual assembly format for [`CaoCompiledProgram`] - the write side of [`crate::disasm`].
+//!
+//! [`assemble`] parses a line-oriented listing back into bytecode, so tooling can hand-edit or
+//! diff compiled programs, or golden-file test the compiler's output by checking in the assembly
+//! instead of raw bytes. [`emit`] goes the other way, rendering a [`CaoCompiledProgram`] back into
+//! that same listing - `assemble(&emit(program)?)` round-trips `bytecode`/`data` for any program
+//! this crate produced. The format is label-oriented rather than offset-oriented (unlike
+//! [`crate::disasm::disasm`]'s output, offsets are redundant once instructions are laid out, and a
+//! human editing the text shouldn't have to keep them in sync by hand):
+//!
+//! ```text
+//! loop:
+//!     ReadLocalVar 0
+//!     ScalarInt 10
+//!     Less
+//!     GotoIfFalse @done
+//!     ScalarInt 1
+//!     Add
+//!     SetLocalVar 0
+//!     Goto @loop
+//! done:
+//!     Exit
+//! ```
+//!
+//! A line ending in `:` binds that name to the position of the instruction that follows it.
+//! `Goto`/`GotoIfTrue`/`GotoIfFalse` take a `@name` reference to such a label instead of a raw
+//! offset, resolved against every label in the text in a first pass before any bytes are emitted.
+//! `CallNative`'s operand is also a `@name`, hashed into the same [`Handle`] a `CallNative` card
+//! calling that native would produce (see `Compiler::compile`'s own `Handle::from_str(name)`).
+//! `StringLiteral` takes a quoted string literal, appended to the program's `data` segment. `#`
+//! starts a line comment; blank lines are ignored.
+//!
+//! This only reconstructs `bytecode`, `data` and `labels` - a [`CaoCompiledProgram`]'s
+//! `variables`/`trace` tables carry source-level names and spans that never appear in this text
+//! format, so [`assemble`]'s output always leaves those at their `Default`.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::str::FromStr;
+
+use num_enum::TryFromPrimitive;
+
+use crate::{
+    bytecode::{decode_str, encode_str, read_from_bytes, write_to_vec},
+    collections::handle_table::{Handle, HandleTable},
+    compiled_program::{CaoCompiledProgram, Label},
+    disasm::DisasmError,
+    instruction::{operand_layout, Instruction, OperandKind, INSTRUCTION_COUNT},
+    VariableId,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AsmError {
+    UnknownMnemonic { line: usize, token: String },
+    DuplicateLabel { line: usize, name: String },
+    UndefinedLabel { line: usize, name: String },
+    WrongOperandCount {
+        line: usize,
+        mnemonic: String,
+        expected: usize,
+        found: usize,
+    },
+    InvalidOperand {
+        line: usize,
+        mnemonic: String,
+        operand: String,
+    },
+}
+
+impl core::fmt::Display for AsmError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::UnknownMnemonic { line, token } => {
+                write!(f, "line {line}: unknown mnemonic {token:?}")
+            }
+            Self::DuplicateLabel { line, name } => {
+                write!(f, "line {line}: label {name:?} is already defined")
+            }
+            Self::UndefinedLabel { line, name } => {
+                write!(f, "line {line}: reference to undefined label {name:?}")
+            }
+            Self::WrongOperandCount {
+                line,
+                mnemonic,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {line}: {mnemonic} takes {expected} operand(s), got {found}"
+            ),
+            Self::InvalidOperand {
+                line,
+                mnemonic,
+                operand,
+            } => write!(f, "line {line}: {mnemonic}: invalid operand {operand:?}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AsmError {}
+
+/// A label definition (`name:`) or an instruction line, as parsed off one line of source - before
+/// label references are resolved to byte offsets.
+enum ParsedLine<'a> {
+    Label(&'a str),
+    Instr {
+        line: usize,
+        instr: Instruction,
+        mnemonic: &'a str,
+        operands: Vec<&'a str>,
+    },
+}
+
+fn strip_comment(line: &str) -> &str {
+    match line.find('#') {
+        Some(i) => &line[..i],
+        None => line,
+    }
+}
+
+fn mnemonic_of(instr: Instruction) -> String {
+    format!("{instr:?}")
+}
+
+/// Looks an [`Instruction`] up by its [`mnemonic_of`] spelling - the same string [`crate::disasm`]
+/// prints via `{instr:?}` - by brute-force scanning every opcode rather than hand-maintaining a
+/// reverse mapping, so the two can never drift apart.
+fn parse_mnemonic(token: &str) -> Option<Instruction> {
+    (0..INSTRUCTION_COUNT as u8)
+        .filter_map(|b| Instruction::try_from_primitive(b).ok())
+        .find(|instr| mnemonic_of(*instr) == token)
+}
+
+fn parse_line(line_no: usize, raw: &str) -> Result<Option<ParsedLine<'_>>, AsmError> {
+    let line = strip_comment(raw).trim();
+    if line.is_empty() {
+        return Ok(None);
+    }
+    if let Some(name) = line.strip_suffix(':') {
+        return Ok(Some(ParsedLine::Label(name.trim())));
+    }
+    let mut parts = line.splitn(2, char::is_whitespace);
+    let mnemonic = parts.next().unwrap_or("");
+    let instr = parse_mnemonic(mnemonic).ok_or_else(|| AsmError::UnknownMnemonic {
+        line: line_no,
+        token: mnemonic.to_string(),
+    })?;
+    let rest = parts.next().unwrap_or("").trim();
+    let operands = if rest.is_empty() {
+        Vec::new()
+    } else if instr == Instruction::StringLiteral {
+        // `StringLiteral`'s one operand is a quoted string, which may itself contain spaces - so
+        // unlike every other instruction, the whole remainder of the line is a single token.
+        vec![rest]
+    } else {
+        rest.split_whitespace().collect()
+    };
+    Ok(Some(ParsedLine::Instr {
+        line: line_no,
+        instr,
+        mnemonic,
+        operands,
+    }))
+}
+
+/// Strips the surrounding quotes off a `"..."` operand and unescapes `\"`/`\\`.
+fn parse_string_literal(
+    line: usize,
+    mnemonic: &str,
+    token: &str,
+) -> Result<String, AsmError> {
+    let bad = || AsmError::InvalidOperand {
+        line,
+        mnemonic: mnemonic.to_string(),
+        operand: token.to_string(),
+    };
+    let inner = token
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .ok_or_else(bad)?;
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('"') => out.push('"'),
+                Some('\\') => out.push('\\'),
+                _ => return Err(bad()),
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    Ok(out)
+}
+
+fn parse_operand<T: FromStr>(line: usize, mnemonic: &str, token: &str) -> Result<T, AsmError> {
+    token.parse().map_err(|_| AsmError::InvalidOperand {
+        line,
+        mnemonic: mnemonic.to_string(),
+        operand: token.to_string(),
+    })
+}
+
+/// Encodes one already-parsed instruction's opcode and operands into `bytecode`, resolving
+/// `@label` references against `label_offsets` and appending string literals to `data`.
+fn encode_instr(
+    line: usize,
+    instr: Instruction,
+    mnemonic: &str,
+    operands: &[&str],
+    label_offsets: &HashMap<String, u32>,
+    bytecode: &mut Vec<u8>,
+    data: &mut Vec<u8>,
+) -> Result<(), AsmError> {
+    bytecode.push(instr as u8);
+
+    if matches!(
+        instr,
+        Instruction::Goto | Instruction::GotoIfTrue | Instruction::GotoIfFalse
+    ) {
+        let [name] = expect_operands(line, mnemonic, operands)?;
+        let name = name.strip_prefix('@').unwrap_or(name);
+        let target = *label_offsets
+            .get(name)
+            .ok_or_else(|| AsmError::UndefinedLabel {
+                line,
+                name: name.to_string(),
+            })?;
+        write_to_vec(target as i32, bytecode);
+        return Ok(());
+    }
+
+    if instr == Instruction::StringLiteral {
+        let [token] = expect_operands(line, mnemonic, operands)?;
+        let s = parse_string_literal(line, mnemonic, token)?;
+        let offset = data.len() as u32;
+        encode_str(&s, data);
+        write_to_vec(offset, bytecode);
+        return Ok(());
+    }
+
+    let layout = operand_layout(instr);
+    if operands.len() != layout.len() {
+        return Err(AsmError::WrongOperandCount {
+            line,
+            mnemonic: mnemonic.to_string(),
+            expected: layout.len(),
+            found: operands.len(),
+        });
+    }
+    for (kind, token) in layout.iter().zip(operands) {
+        match kind {
+            OperandKind::U8 => {
+                write_to_vec(parse_operand::<u8>(line, mnemonic, token)?, bytecode)
+            }
+            OperandKind::U32 => {
+                write_to_vec(parse_operand::<u32>(line, mnemonic, token)?, bytecode)
+            }
+            OperandKind::I32 => {
+                write_to_vec(parse_operand::<i32>(line, mnemonic, token)?, bytecode)
+            }
+            OperandKind::I64 => {
+                write_to_vec(parse_operand::<i64>(line, mnemonic, token)?, bytecode)
+            }
+            OperandKind::F64 => {
+                write_to_vec(parse_operand::<f64>(line, mnemonic, token)?, bytecode)
+            }
+            OperandKind::Handle => {
+                let name = token.strip_prefix('@').unwrap_or(token);
+                let handle = Handle::from_str(name).unwrap();
+                write_to_vec(handle, bytecode);
+            }
+            OperandKind::VarId => write_to_vec(
+                parse_operand::<VariableId>(line, mnemonic, token)?,
+                bytecode,
+            ),
+        }
+    }
+    Ok(())
+}
+
+fn expect_operands<'a>(
+    line: usize,
+    mnemonic: &str,
+    operands: &[&'a str],
+) -> Result<[&'a str; 1], AsmError> {
+    match operands {
+        [a] => Ok([a]),
+        _ => Err(AsmError::WrongOperandCount {
+            line,
+            mnemonic: mnemonic.to_string(),
+            expected: 1,
+            found: operands.len(),
+        }),
+    }
+}
+
+/// Parses `source` (see the module docs for the format) into a [`CaoCompiledProgram`]. Labels are
+/// resolved in a first pass over the whole text, so forward references (a `Goto` to a label
+/// defined later in the file) work the same as backward ones.
+pub fn assemble(source: &str) -> Result<CaoCompiledProgram, AsmError> {
+    struct PendingInstr<'a> {
+        line: usize,
+        instr: Instruction,
+        mnemonic: &'a str,
+        operands: Vec<&'a str>,
+    }
+
+    let mut label_offsets: HashMap<String, u32> = HashMap::new();
+    let mut pending = Vec::new();
+    let mut offset: u32 = 0;
+    for (i, raw) in source.lines().enumerate() {
+        let line = i + 1;
+        match parse_line(line, raw)? {
+            None => {}
+            Some(ParsedLine::Label(name)) => {
+                if label_offsets.insert(name.to_string(), offset).is_some() {
+                    return Err(AsmError::DuplicateLabel {
+                        line,
+                        name: name.to_string(),
+                    });
+                }
+            }
+            Some(ParsedLine::Instr {
+                line,
+                instr,
+                mnemonic,
+                operands,
+            }) => {
+                let width: usize = operand_layout(instr).iter().map(|k| k.byte_width()).sum();
+                offset += 1 + width as u32;
+                pending.push(PendingInstr {
+                    line,
+                    instr,
+                    mnemonic,
+                    operands,
+                });
+            }
+        }
+    }
+
+    let mut bytecode = Vec::new();
+    let mut data = Vec::new();
+    for instr in &pending {
+        encode_instr(
+            instr.line,
+            instr.instr,
+            instr.mnemonic,
+            &instr.operands,
+            &label_offsets,
+            &mut bytecode,
+            &mut data,
+        )?;
+    }
+
+    let mut program = CaoCompiledProgram {
+        bytecode,
+        data,
+        ..Default::default()
+    };
+    let mut labels = HandleTable::default();
+    for (name, pos) in &label_offsets {
+        labels.insert(Handle::from_str(name).unwrap(), Label::new(*pos)).unwrap();
+    }
+    program.labels.0 = labels;
+
+    Ok(program)
+}
+
+/// Renders `program` back into the textual form [`assemble`] parses - the inverse half this
+/// module's doc comment promises. This is *not* the same text [`crate::disasm::disasm`] prints:
+/// that listing is annotated with byte offsets and `-> @label (offset)` arrows for a human to
+/// read, which `assemble` doesn't accept as input. Every offset a [`Goto`](Instruction::Goto)-family
+/// instruction jumps to gets a label line, reusing the program's own [`Handle`] debug spelling
+/// where [`crate::compiled_program::Labels`] already names it, and a synthetic `L<offset>` name
+/// for any target that isn't otherwise labelled. `assemble(&emit(program)?)` round-trips the
+/// `bytecode`/`data` of any program this crate produced, with one caveat: `CallNative`'s operand
+/// is a [`Handle`], a one-way hash of the native's name, so it prints as `@Handle(<hash>)` and
+/// `assemble` hashes that text again instead of reading the number back, so the reassembled
+/// `CallNative` carries a different `Handle` - the original name is gone by the time the program
+/// reaches this function.
+pub fn emit(program: &CaoCompiledProgram) -> Result<String, DisasmError> {
+    let bytecode = &program.bytecode;
+
+    let mut decoded = Vec::new();
+    let mut offset = 0u32;
+    while (offset as usize) < bytecode.len() {
+        let instr = Instruction::try_from_primitive(bytecode[offset as usize])?;
+        decoded.push((offset, instr));
+        offset += instr.span() as u32;
+    }
+
+    let mut label_names: HashMap<u32, String> = program
+        .labels
+        .0
+        .iter()
+        .map(|(handle, label)| (label.pos, format!("{handle:?}")))
+        .collect();
+    for &(offset, instr) in &decoded {
+        if is_jump(instr) {
+            if let Some((_, target)) = read_from_bytes::<i32>(&bytecode[offset as usize + 1..]) {
+                label_names
+                    .entry(target as u32)
+                    .or_insert_with(|| format!("L{target}"));
+            }
+        }
+    }
+
+    let mut out = String::new();
+    for (offset, instr) in decoded {
+        if let Some(name) = label_names.get(&offset) {
+            writeln!(out, "{name}:").unwrap();
+        }
+        write!(out, "    {instr:?}").unwrap();
+        let operands = &bytecode[offset as usize + 1..];
+        if is_jump(instr) {
+            if let Some((_, target)) = read_from_bytes::<i32>(operands) {
+                let name = label_names
+                    .get(&(target as u32))
+                    .expect("every jump target got a label name above");
+                write!(out, " @{name}").unwrap();
+            }
+        } else if instr == Instruction::StringLiteral {
+            if let Some((_, str_offset)) = read_from_bytes::<u32>(operands) {
+                if let Some((_, s)) = program
+                    .data
+                    .get(str_offset as usize..)
+                    .and_then(decode_str)
+                {
+                    write!(out, " \"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"")).unwrap();
+                }
+            }
+        } else {
+            let mut rest = operands;
+            for kind in operand_layout(instr) {
+                match render_operand(*kind, rest) {
+                    Some((read, rendered)) => {
+                        write!(out, " {rendered}").unwrap();
+                        rest = &rest[read..];
+                    }
+                    None => break,
+                }
+            }
+        }
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+fn is_jump(instr: Instruction) -> bool {
+    matches!(
+        instr,
+        Instruction::Goto | Instruction::GotoIfTrue | Instruction::GotoIfFalse
+    )
+}
+
+/// Like [`crate::disasm`]'s own operand rendering, but in a shape [`assemble`] can parse back:
+/// bare numbers instead of `Type(n)` debug forms, and `@` plus the debug form for [`Handle`]
+/// operands, which [`encode_instr`] hashes as a name rather than parsing back into a number.
+fn render_operand(kind: OperandKind, bytes: &[u8]) -> Option<(usize, String)> {
+    match kind {
+        OperandKind::U8 => read_from_bytes::<u8>(bytes).map(|(n, v)| (n, v.to_string())),
+        OperandKind::U32 => read_from_bytes::<u32>(bytes).map(|(n, v)| (n, v.to_string())),
+        OperandKind::I32 => read_from_bytes::<i32>(bytes).map(|(n, v)| (n, v.to_string())),
+        OperandKind::I64 => read_from_bytes::<i64>(bytes).map(|(n, v)| (n, v.to_string())),
+        OperandKind::F64 => read_from_bytes::<f64>(bytes).map(|(n, v)| (n, v.to_string())),
+        OperandKind::Handle => read_from_bytes::<Handle>(bytes).map(|(n, v)| (n, format!("@{v:?}"))),
+        OperandKind::VarId => read_from_bytes::<VariableId>(bytes).map(|(n, v)| (n, v.0.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disasm::disasm;
+
+    #[test]
+    fn assembles_scalars_and_arithmetic() {
+        let program = assemble("ScalarInt 42\nScalarInt 1\nAdd\nExit\n").expect("assemble");
+        assert_eq!(
+            disasm(&program).unwrap(),
+            "     0: ScalarInt 42\n     9: ScalarInt 1\n    18: Add\n    19: Exit\n"
+        );
+    }
+
+    #[test]
+    fn resolves_forward_and_backward_label_references() {
+        let source = "\
+loop:
+    ScalarInt 1
+    Goto @done
+    Goto @loop
+done:
+    Exit
+";
+        let program = assemble(source).expect("assemble");
+        let out = disasm(&program).unwrap();
+        // `Goto @done` jumps forward past the second `Goto`, straight to `Exit`.
+        assert!(out.contains("-> @") && out.contains("(19)"));
+        // `Goto @loop` jumps back to offset 0.
+        assert!(out.contains("(0)"));
+    }
+
+    #[test]
+    fn assembles_string_literals_into_the_data_segment() {
+        let program = assemble("StringLiteral \"pog\"\nExit\n").expect("assemble");
+        assert_eq!(program.data.len(), 4 + 3);
+        let out = disasm(&program).unwrap();
+        assert!(out.contains("\"pog\""));
+    }
+
+    #[test]
+    fn call_native_hashes_the_callee_name_like_the_compiler_does() {
+        let program = assemble("CallNative @rand\nExit\n").expect("assemble");
+        let expected = Handle::from_str("rand").unwrap();
+        let entries = crate::disasm::disasm_entries(&program).unwrap();
+        assert_eq!(entries[0].operands, vec![format!("@{expected:?}")]);
+    }
+
+    #[test]
+    fn rejects_references_to_undefined_labels() {
+        let err = assemble("Goto @nowhere\n").unwrap_err();
+        assert!(matches!(err, AsmError::UndefinedLabel { .. }));
+    }
+
+    #[test]
+    fn rejects_unknown_mnemonics() {
+        let err = assemble("Frobnicate\n").unwrap_err();
+        assert!(matches!(err, AsmError::UnknownMnemonic { .. }));
+    }
+
+    #[test]
+    fn emit_round_trips_through_assemble() {
+        let source = "\
+loop:
+    ReadLocalVar 0
+    ScalarInt 10
+    Less
+    GotoIfFalse @done
+    ScalarInt 1
+    Add
+    SetLocalVar 0
+    Goto @loop
+done:
+    Exit
+";
+        let program = assemble(source).expect("assemble");
+        let emitted = emit(&program).expect("emit");
+        let reassembled = assemble(&emitted).expect("reassemble");
+        assert_eq!(reassembled.bytecode, program.bytecode);
+        assert_eq!(reassembled.data, program.data);
+    }
+
+    #[test]
+    fn emit_synthesizes_labels_for_unnamed_jump_targets() {
+        // Hand-built rather than going through `assemble`: a `Goto` target only ends up in
+        // `program.labels` when something (the compiler, or a source `name:` line) registered
+        // one - a jump can perfectly well target an offset nothing ever named. `emit` still has
+        // to produce parseable text for that case, so it invents a label on the spot.
+        let mut bytecode = Vec::new();
+        bytecode.push(Instruction::Goto as u8);
+        write_to_vec(5i32, &mut bytecode);
+        bytecode.push(Instruction::Exit as u8);
+        let program = CaoCompiledProgram {
+            bytecode,
+            ..Default::default()
+        };
+        assert!(program.labels.0.iter().next().is_none());
+
+        let emitted = emit(&program).expect("emit");
+        assert!(emitted.contains("@L5"), "expected a synthesized label: {emitted}");
+        let reassembled = assemble(&emitted).expect("reassemble");
+        assert_eq!(reassembled.bytecode, program.bytecode);
+    }
+
+    #[test]
+    fn emit_quotes_string_literals() {
+        let program = assemble("StringLiteral \"po\\\"g\"\nExit\n").expect("assemble");
+        let emitted = emit(&program).expect("emit");
+        assert!(emitted.contains(r#""po\"g""#), "unexpected output: {emitted}");
+        let reassembled = assemble(&emitted).expect("reassemble");
+        assert_eq!(reassembled.data, program.data);
+    }
+}
@@ -0,0 +1,67 @@
+//! Round-tripping host types through the VM's [`Value`] representation.
+//!
+//! [`Value::Object`] variants (tables, strings, ...) are heap objects owned by a particular
+//! [`Vm`], so unlike the scalar `From`/`TryFrom<Value>` impls on [`crate::value`], converting an
+//! aggregate host type needs a `Vm` in hand to allocate into: [`ByteEncode::to_value`] to build
+//! the object, [`ByteEncode::from_value`] to read one back out.
+//!
+//! Implemented by hand below for the scalar types a struct's fields tend to be made of; see
+//! `cao_lang_derive::ByteEncode` to derive this field-by-field for aggregate host structs, the
+//! same way [`crate::prelude::ByteEncode`] is implemented here for `i64`/`f64`/`bool`/`String`.
+use core::convert::TryFrom;
+
+use crate::alloc_crate::string::{String, ToString};
+use crate::{procedures::ExecutionErrorPayload, value::Value, vm::Vm};
+
+pub trait ByteEncode<Aux>: Sized {
+    fn to_value(self, vm: &mut Vm<Aux>) -> Result<Value, ExecutionErrorPayload>;
+
+    fn from_value(value: Value, vm: &Vm<Aux>) -> Result<Self, ExecutionErrorPayload>;
+
+    /// A human readable name for this type, used in error messages about it.
+    fn displayname() -> &'static str {
+        core::any::type_name::<Self>()
+    }
+}
+
+impl<Aux> ByteEncode<Aux> for i64 {
+    fn to_value(self, _vm: &mut Vm<Aux>) -> Result<Value, ExecutionErrorPayload> {
+        Ok(Value::from(self))
+    }
+
+    fn from_value(value: Value, _vm: &Vm<Aux>) -> Result<Self, ExecutionErrorPayload> {
+        i64::try_from(value).map_err(|_| ExecutionErrorPayload::invalid_argument("Expected Integer"))
+    }
+}
+
+impl<Aux> ByteEncode<Aux> for f64 {
+    fn to_value(self, _vm: &mut Vm<Aux>) -> Result<Value, ExecutionErrorPayload> {
+        Ok(Value::Real(self))
+    }
+
+    fn from_value(value: Value, _vm: &Vm<Aux>) -> Result<Self, ExecutionErrorPayload> {
+        f64::try_from(value).map_err(|_| ExecutionErrorPayload::invalid_argument("Expected Real"))
+    }
+}
+
+impl<Aux> ByteEncode<Aux> for bool {
+    fn to_value(self, _vm: &mut Vm<Aux>) -> Result<Value, ExecutionErrorPayload> {
+        Ok(Value::from(self))
+    }
+
+    fn from_value(value: Value, _vm: &Vm<Aux>) -> Result<Self, ExecutionErrorPayload> {
+        Ok(i64::try_from(value).unwrap_or(0) != 0)
+    }
+}
+
+impl<Aux> ByteEncode<Aux> for String {
+    fn to_value(self, vm: &mut Vm<Aux>) -> Result<Value, ExecutionErrorPayload> {
+        Ok(vm.init_string(self.as_str())?.into())
+    }
+
+    fn from_value(value: Value, _vm: &Vm<Aux>) -> Result<Self, ExecutionErrorPayload> {
+        <&str>::try_from(value)
+            .map(str::to_string)
+            .map_err(|_| ExecutionErrorPayload::invalid_argument("Expected String"))
+    }
+}
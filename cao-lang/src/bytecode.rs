@@ -1,25 +1,102 @@
-use std::convert::TryInto;
+use core::convert::TryInto;
 
-pub trait TriviallyEncodable: Sized + Copy {}
-impl<T: Sized + Copy> TriviallyEncodable for T {}
+use crate::alloc_crate::vec::Vec;
 
-pub fn write_to_vec<T: TriviallyEncodable>(val: T, out: &mut Vec<u8>) {
-    let len = out.len();
-    let size = std::mem::size_of::<T>();
-    out.resize(len + size, 0);
-    unsafe {
-        let ptr = out.as_mut_ptr().add(len);
-        std::ptr::write_unaligned(ptr as *mut T, val);
+/// A bytecode operand that can be written/read as a fixed-width, little-endian byte sequence.
+///
+/// This is implemented by hand for the concrete set of types that actually appear as instruction
+/// operands, rather than blanket-implemented for any `Copy` type: a blanket impl would have to
+/// fall back to reinterpreting the type's in-memory representation, which bakes in the host's
+/// native endianness and struct padding. That made a program compiled on a big-endian (or
+/// differently-padded) target fail to round-trip on a little-endian one, such as the wasm build.
+/// Encoding each type explicitly guarantees the same bytes regardless of the host architecture.
+pub trait TriviallyEncodable: Sized + Copy {
+    /// Number of bytes this type encodes to/decodes from.
+    const BYTE_LEN: usize;
+
+    fn write_le(self, out: &mut Vec<u8>);
+
+    /// Reads `Self::BYTE_LEN` bytes off the front of `bytes`, or `None` if fewer remain.
+    fn read_le(bytes: &[u8]) -> Option<Self>;
+}
+
+macro_rules! impl_trivially_encodable_int {
+    ($($t:ty),* $(,)?) => {
+        $(
+            impl TriviallyEncodable for $t {
+                const BYTE_LEN: usize = core::mem::size_of::<$t>();
+
+                fn write_le(self, out: &mut Vec<u8>) {
+                    out.extend_from_slice(&self.to_le_bytes());
+                }
+
+                fn read_le(bytes: &[u8]) -> Option<Self> {
+                    let bytes = bytes.get(..Self::BYTE_LEN)?;
+                    Some(Self::from_le_bytes(bytes.try_into().unwrap()))
+                }
+            }
+        )*
+    };
+}
+
+impl_trivially_encodable_int!(i8, i16, i32, i64, i128, u8, u16, u32, u64);
+
+impl TriviallyEncodable for bool {
+    const BYTE_LEN: usize = 1;
+
+    fn write_le(self, out: &mut Vec<u8>) {
+        out.push(self as u8);
+    }
+
+    fn read_le(bytes: &[u8]) -> Option<Self> {
+        Some(*bytes.first()? != 0)
+    }
+}
+
+impl TriviallyEncodable for f32 {
+    const BYTE_LEN: usize = 4;
+
+    fn write_le(self, out: &mut Vec<u8>) {
+        self.to_bits().write_le(out);
+    }
+
+    fn read_le(bytes: &[u8]) -> Option<Self> {
+        u32::read_le(bytes).map(f32::from_bits)
     }
 }
 
+impl TriviallyEncodable for f64 {
+    const BYTE_LEN: usize = 8;
+
+    fn write_le(self, out: &mut Vec<u8>) {
+        self.to_bits().write_le(out);
+    }
+
+    fn read_le(bytes: &[u8]) -> Option<Self> {
+        u64::read_le(bytes).map(f64::from_bits)
+    }
+}
+
+impl TriviallyEncodable for crate::VariableId {
+    const BYTE_LEN: usize = u32::BYTE_LEN;
+
+    fn write_le(self, out: &mut Vec<u8>) {
+        let crate::VariableId(id) = self;
+        id.write_le(out);
+    }
+
+    fn read_le(bytes: &[u8]) -> Option<Self> {
+        u32::read_le(bytes).map(crate::VariableId)
+    }
+}
+
+pub fn write_to_vec<T: TriviallyEncodable>(val: T, out: &mut Vec<u8>) {
+    val.write_le(out);
+}
+
 /// return the number of bytes read
 pub fn read_from_bytes<T: TriviallyEncodable>(bts: &[u8]) -> Option<(usize, T)> {
-    let size = std::mem::size_of::<T>();
-    if bts.len() < size {
-        return None;
-    }
-    unsafe { Some((size, *(bts.as_ptr() as *const T))) }
+    T::read_le(bts).map(|val| (T::BYTE_LEN, val))
 }
 
 pub fn encode_str(s: &str, out: &mut Vec<u8>) {
@@ -37,5 +114,31 @@ pub fn decode_str(bts: &[u8]) -> Option<(usize, &str)> {
         return None;
     }
     let bts = &bts[sl..sl + len as usize];
-    Some((sl + len as usize, std::str::from_utf8(bts).ok()?))
+    Some((sl + len as usize, core::str::from_utf8(bts).ok()?))
+}
+
+/// Failure reason for [`decode_str_checked`].
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+pub enum StrDecodeError {
+    #[error("Not enough bytes left to read the length prefix")]
+    LengthDecodeError,
+    #[error("String length {len} exceeds the cap ({max_len}) or the available buffer")]
+    LengthError { len: u32, max_len: usize },
+    #[error("String is not valid utf8: {0}")]
+    Utf8DecodeError(core::str::Utf8Error),
+}
+
+/// Like [`decode_str`], but rejects a decoded length over `max_len` instead of trusting whatever
+/// the rest of `bts` has room for. Callers decoding string constants out of a program blob (e.g.
+/// [`crate::vm::instr_execution::read_str`]) use this so a single corrupt length prefix can't
+/// claim an unbounded slice of the buffer as "the string".
+pub fn decode_str_checked(bts: &[u8], max_len: usize) -> Result<(usize, &str), StrDecodeError> {
+    let (sl, len): (_, u32) = read_from_bytes(bts).ok_or(StrDecodeError::LengthDecodeError)?;
+    let len_usize = len as usize;
+    if len_usize > max_len || bts.len() - sl < len_usize {
+        return Err(StrDecodeError::LengthError { len, max_len });
+    }
+    let bts = &bts[sl..sl + len_usize];
+    let s = core::str::from_utf8(bts).map_err(StrDecodeError::Utf8DecodeError)?;
+    Ok((sl + len_usize, s))
 }
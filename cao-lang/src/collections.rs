@@ -1,7 +1,50 @@
+pub mod bounded_stack;
+pub mod checked_key_map;
+pub mod fixed_hash_map;
+pub mod hash_map;
+pub mod key_map;
+pub mod value_stack;
+
 pub mod pre_hash_map {
     //! Hash table with pre-calculated hashes.
     //!
-    use std::mem::{replace, swap, MaybeUninit};
+    use crate::alloc_crate::{boxed::Box, vec::Vec};
+    use core::mem::{replace, swap, MaybeUninit};
+
+    /// Deterministic FNV-1a based [`core::hash::Hasher`], usable without `std`. Meant as a
+    /// drop-in replacement for `std::collections::hash_map::DefaultHasher` (which isn't
+    /// available under `no_std`) wherever the exact hash algorithm doesn't matter, only that
+    /// it's stable across runs.
+    #[derive(Debug, Clone, Copy)]
+    pub struct FnvHasher(u64);
+
+    const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    impl Default for FnvHasher {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl FnvHasher {
+        pub fn new() -> Self {
+            Self(FNV_OFFSET_BASIS)
+        }
+    }
+
+    impl core::hash::Hasher for FnvHasher {
+        fn write(&mut self, bytes: &[u8]) {
+            for b in bytes {
+                self.0 ^= *b as u64;
+                self.0 = self.0.wrapping_mul(FNV_PRIME);
+            }
+        }
+
+        fn finish(&self) -> u64 {
+            self.0
+        }
+    }
 
     #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Ord, PartialOrd)]
     pub struct Key(u32);
@@ -171,14 +214,107 @@ pub mod pre_hash_map {
 
         pub fn remove(&mut self, key: Key) -> Option<T> {
             let ind = self.find_ind(key);
-            if self.keys[ind].0 != 0 {
-                self.count -= 1;
-                self.keys[ind] = Key(0);
-                let val = replace(&mut self.values[ind], MaybeUninit::uninit());
-                unsafe { Some(val.assume_init()) }
-            } else {
-                None
+            if self.keys[ind].0 == 0 {
+                return None;
+            }
+            self.count -= 1;
+            let val = replace(&mut self.values[ind], MaybeUninit::uninit());
+            let result = unsafe { val.assume_init() };
+
+            // Knuth's backward-shift deletion: slide later entries of the same probe chain back
+            // into the hole we just opened at `i`, instead of leaving a zero tombstone behind.
+            // `find_ind` stops probing at the first empty (`Key(0)`) slot, so a naive zeroing
+            // would strand any colliding key that used to live past `ind`.
+            let len = self.keys.len();
+            let mut i = ind;
+            let mut j = ind;
+            loop {
+                j = (j + 1) % len;
+                if self.keys[j].0 == 0 {
+                    break;
+                }
+                let home = self.keys[j].0 as usize % len;
+                let dist_i = (i + len - home) % len;
+                let dist_j = (j + len - home) % len;
+                if dist_i <= dist_j {
+                    self.keys[i] = self.keys[j];
+                    self.values[i] = replace(&mut self.values[j], MaybeUninit::uninit());
+                    i = j;
+                }
+            }
+            self.keys[i] = Key(0);
+
+            Some(result)
+        }
+
+        pub fn iter(&self) -> impl Iterator<Item = (Key, &'_ T)> + '_ {
+            self.keys
+                .iter()
+                .zip(self.values.iter())
+                .filter(|(Key(k), _)| *k != 0)
+                .map(|(k, v)| (*k, unsafe { &*v.as_ptr() }))
+        }
+
+        pub fn iter_mut(&mut self) -> impl Iterator<Item = (Key, &'_ mut T)> + '_ {
+            self.keys
+                .iter()
+                .zip(self.values.iter_mut())
+                .filter(|(Key(k), _)| *k != 0)
+                .map(|(k, v)| (*k, unsafe { &mut *v.as_mut_ptr() }))
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    impl<T: ::serde::Serialize> ::serde::Serialize for PreHashMap<T> {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: ::serde::Serializer,
+        {
+            use ::serde::ser::SerializeMap;
+
+            let mut state = serializer.serialize_map(Some(self.len()))?;
+            for (k, v) in self.iter() {
+                state.serialize_entry(&k.0, v)?;
+            }
+            state.end()
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    struct PreHashMapVisitor<T> {
+        _m: core::marker::PhantomData<T>,
+    }
+
+    #[cfg(feature = "serde")]
+    impl<'de, T: ::serde::Deserialize<'de>> ::serde::de::Visitor<'de> for PreHashMapVisitor<T> {
+        type Value = PreHashMap<T>;
+
+        fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+            formatter.write_str("struct PreHashMap")
+        }
+
+        fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where
+            A: ::serde::de::MapAccess<'de>,
+        {
+            let cap = map.size_hint().unwrap_or(16).max(1);
+            let mut res = PreHashMap::with_capacity(cap);
+            while let Some((k, v)) = map.next_entry::<u32, T>()? {
+                res.insert(Key(k), v);
             }
+            Ok(res)
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    impl<'de, T: ::serde::Deserialize<'de>> ::serde::Deserialize<'de> for PreHashMap<T> {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: ::serde::Deserializer<'de>,
+        {
+            deserializer.deserialize_map(PreHashMapVisitor {
+                _m: core::marker::PhantomData,
+            })
         }
     }
 
@@ -266,5 +402,45 @@ pub mod pre_hash_map {
             }
             assert_eq!(*drops, 3, "Drops the 2 items still in the map")
         }
+
+        #[test]
+        fn removing_duplicate_hash_test() {
+            // if two distinct keys map to the same bucket, then we should still be able to look
+            // up the second, after deleting the first
+            let mut map = PreHashMap::<i32>::with_capacity(4);
+            map.insert(Key(4), 42);
+            map.insert(Key(8), 69);
+
+            let val = map.remove(Key(4)).expect("Expected to remove the value");
+            assert_eq!(val, 42);
+
+            let val = map.get(Key(8)).expect("Expected to still find the value");
+            assert_eq!(*val, 69);
+        }
+
+        #[test]
+        fn iter_yields_all_occupied_entries() {
+            let mut map = PreHashMap::<i32>::with_capacity(4);
+            map.insert(Key(4), 42);
+            map.insert(Key(8), 69);
+            map.remove(Key(4));
+
+            let entries: Vec<_> = map.iter().map(|(Key(k), v)| (k, *v)).collect();
+            assert_eq!(entries, vec![(8, 69)]);
+        }
+
+        #[test]
+        fn iter_mut_allows_updating_values_in_place() {
+            let mut map = PreHashMap::<i32>::with_capacity(4);
+            map.insert(Key(4), 42);
+            map.insert(Key(8), 69);
+
+            for (_, v) in map.iter_mut() {
+                *v += 1;
+            }
+
+            assert_eq!(*map.get(Key(4)).unwrap(), 43);
+            assert_eq!(*map.get(Key(8)).unwrap(), 70);
+        }
     }
 }
@@ -1,4 +1,5 @@
-use std::{
+use crate::alloc_crate::boxed::Box;
+use core::{
     mem::MaybeUninit,
     ptr::{self, drop_in_place},
 };
@@ -12,8 +13,8 @@ pub struct BoundedStack<T> {
 
 #[derive(Clone, Debug, Error)]
 pub enum StackError {
-    #[error("Stack is full")]
-    Full,
+    #[error("Stack is full: capacity: {capacity} attempted: {attempted}")]
+    Full { capacity: usize, attempted: usize },
 }
 
 impl<T> BoundedStack<T> {
@@ -50,7 +51,10 @@ impl<T> BoundedStack<T> {
 
     pub fn push(&mut self, val: T) -> Result<(), StackError> {
         if self.head >= self.capacity {
-            return Err(StackError::Full);
+            return Err(StackError::Full {
+                capacity: self.capacity,
+                attempted: self.head.saturating_add(1),
+            });
         }
         unsafe {
             ptr::write(self.storage.get_unchecked_mut(self.head).as_mut_ptr(), val);
@@ -76,13 +80,27 @@ impl<T> BoundedStack<T> {
     }
 
     pub fn clear(&mut self) {
-        if std::mem::needs_drop::<T>() {
+        if core::mem::needs_drop::<T>() {
             for i in 0..self.head {
                 unsafe { drop_in_place(self.storage.get_unchecked_mut(i).as_mut_ptr()) }
             }
         }
         self.head = 0;
     }
+
+    /// Drops every element at or above `len`, shrinking the stack to that length. No-op if the
+    /// stack is already shorter than `len`.
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.head {
+            return;
+        }
+        if core::mem::needs_drop::<T>() {
+            for i in len..self.head {
+                unsafe { drop_in_place(self.storage.get_unchecked_mut(i).as_mut_ptr()) }
+            }
+        }
+        self.head = len;
+    }
 }
 
 impl<T> Drop for BoundedStack<T> {
@@ -0,0 +1,143 @@
+//! A [`KeyMap`] variant for keys whose bytes come from untrusted, user-controlled source text
+//! (e.g. the compiler resolving variable/card names) instead of internal handles the compiler
+//! itself hands out. [`KeyMap`]'s own module docs warn that it only ever compares 32-bit
+//! [`Handle`] hashes, so two distinct keys that happen to hash to the same `Handle` silently
+//! alias one another - fine for the hot interpreter path, where keys are already-unique IDs, but
+//! not for a symbol table where a collision would make two different identifiers resolve to the
+//! same slot.
+//!
+//! [`CheckedKeyMap`] keeps the original key bytes next to each value, so a lookup can tell a true
+//! hit from a same-`Handle` collision: each [`Handle`] bucket holds a small `Vec` of `(key,
+//! value)` pairs (length 1 outside of an actual collision), and every [`CheckedKeyMap::get`]/
+//! [`CheckedKeyMap::insert`] scans that bucket for a byte-exact match instead of trusting the
+//! `Handle` alone. This costs an extra allocation and byte-compare per lookup, so it's meant for
+//! symbol tables keyed by source text, not for the interpreter's own per-frame variable/register
+//! maps, which should keep using the hash-only [`KeyMap`].
+
+use crate::alloc::{Allocator, SysAllocator};
+use crate::alloc_crate::{boxed::Box, vec::Vec};
+
+use super::key_map::{Handle, KeyMap, MapError, DEFAULT_INLINE_CAPACITY};
+
+/// Every `(key, value)` pair whose key bytes hash to the same [`Handle`]. Stays at length 1
+/// unless two distinct keys genuinely collide.
+type Bucket<T> = Vec<(Box<[u8]>, T)>;
+
+/// Outcome of a [`CheckedKeyMap::insert`].
+#[derive(Debug)]
+pub enum Insertion<T> {
+    /// No entry shared this key, or its `Handle`, before.
+    New,
+    /// This exact key was already present; carries the value it used to hold.
+    Replaced(T),
+    /// A *different* key already occupies this `Handle` - a genuine hash collision rather than a
+    /// re-insertion of the same identifier. Both keys are now kept side by side in the same
+    /// bucket, so lookups stay correct, but a caller that wants to flag or reject aliasing
+    /// identifiers (e.g. the compiler diagnosing a shadowed-looking name) should match on this.
+    Collided,
+}
+
+/// Collision-safe, allocator-backed map from byte-string keys to `T`. See the module docs for
+/// why this exists alongside the hash-only [`KeyMap`].
+pub struct CheckedKeyMap<T, A = SysAllocator, const N: usize = DEFAULT_INLINE_CAPACITY>
+where
+    A: Allocator,
+{
+    buckets: KeyMap<Bucket<T>, A, N>,
+    count: usize,
+}
+
+impl<T, A, const N: usize> Default for CheckedKeyMap<T, A, N>
+where
+    A: Allocator + Default,
+{
+    fn default() -> Self {
+        Self::with_capacity(N, A::default()).expect("Failed to init map")
+    }
+}
+
+impl<T, A, const N: usize> CheckedKeyMap<T, A, N>
+where
+    A: Allocator,
+{
+    pub fn with_capacity(capacity: usize, allocator: A) -> Result<Self, MapError> {
+        Ok(Self {
+            buckets: KeyMap::with_capacity(capacity, allocator)?,
+            count: 0,
+        })
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    pub fn contains(&self, key: &[u8]) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn get(&self, key: &[u8]) -> Option<&T> {
+        let handle = Handle::from_bytes(key);
+        self.buckets
+            .get(handle)?
+            .iter()
+            .find(|(k, _)| &**k == key)
+            .map(|(_, v)| v)
+    }
+
+    pub fn get_mut(&mut self, key: &[u8]) -> Option<&mut T> {
+        let handle = Handle::from_bytes(key);
+        self.buckets
+            .get_mut(handle)?
+            .iter_mut()
+            .find(|(k, _)| &**k == key)
+            .map(|(_, v)| v)
+    }
+
+    /// Inserts `value` under `key`. See [`Insertion`] for what the 3 possible outcomes mean.
+    pub fn insert(&mut self, key: &[u8], value: T) -> Result<Insertion<T>, MapError> {
+        let handle = Handle::from_bytes(key);
+        if let Some(bucket) = self.buckets.get_mut(handle) {
+            if let Some(slot) = bucket.iter_mut().find(|(k, _)| &**k == key) {
+                let old = core::mem::replace(&mut slot.1, value);
+                return Ok(Insertion::Replaced(old));
+            }
+            bucket.push((Box::<[u8]>::from(key), value));
+            self.count += 1;
+            return Ok(Insertion::Collided);
+        }
+        let mut bucket = Vec::with_capacity(1);
+        bucket.push((Box::<[u8]>::from(key), value));
+        self.buckets.insert(handle, bucket)?;
+        self.count += 1;
+        Ok(Insertion::New)
+    }
+
+    /// Removes `key`, returning its value if it was present. Leaves other keys that happen to
+    /// collide with `key`'s `Handle` untouched.
+    pub fn remove(&mut self, key: &[u8]) -> Option<T> {
+        let handle = Handle::from_bytes(key);
+        let bucket = self.buckets.get_mut(handle)?;
+        let idx = bucket.iter().position(|(k, _)| &**k == key)?;
+        let (_, value) = bucket.remove(idx);
+        self.count -= 1;
+        if bucket.is_empty() {
+            self.buckets.remove(handle);
+        }
+        Some(value)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], &T)> + '_ {
+        self.buckets
+            .iter()
+            .flat_map(|(_, bucket)| bucket.iter().map(|(k, v)| (&**k, v)))
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,90 @@
+use super::*;
+use crate::alloc::SysAllocator;
+
+#[test]
+fn insert_get_remove_roundtrip() {
+    let mut map = CheckedKeyMap::<i32>::default();
+
+    assert!(matches!(map.insert(b"foo", 42).unwrap(), Insertion::New));
+    assert!(matches!(map.insert(b"bar", 69).unwrap(), Insertion::New));
+    assert_eq!(map.len(), 2);
+
+    assert_eq!(map.get(b"foo"), Some(&42));
+    assert_eq!(map.get(b"bar"), Some(&69));
+    assert_eq!(map.get(b"baz"), None);
+
+    assert_eq!(map.remove(b"foo"), Some(42));
+    assert_eq!(map.len(), 1);
+    assert_eq!(map.get(b"foo"), None);
+    assert_eq!(map.remove(b"foo"), None, "already removed");
+}
+
+#[test]
+fn reinserting_the_same_key_replaces_instead_of_colliding() {
+    let mut map = CheckedKeyMap::<i32>::default();
+
+    map.insert(b"foo", 1).unwrap();
+    let outcome = map.insert(b"foo", 2).unwrap();
+
+    assert!(matches!(outcome, Insertion::Replaced(1)));
+    assert_eq!(map.len(), 1, "re-inserting the same key must not grow the map");
+    assert_eq!(map.get(b"foo"), Some(&2));
+}
+
+/// Birthday-paradox search for 2 distinct byte strings whose [`Handle`] collides: with a 32-bit
+/// hash space, sampling ~3*10^5 candidates makes finding *some* colliding pair all but certain,
+/// even though hitting one specific target hash would not be.
+fn find_colliding_strings() -> (Vec<u8>, Vec<u8>) {
+    let mut seen = std::collections::HashMap::new();
+    for i in 0u32..300_000 {
+        let bytes = format!("key{i}").into_bytes();
+        let handle = Handle::from_bytes(&bytes);
+        if let Some(prev) = seen.insert(handle, bytes.clone()) {
+            return (prev, bytes);
+        }
+    }
+    panic!("found no Handle collision in the search space");
+}
+
+#[test]
+fn surfaces_true_handle_collisions_without_losing_either_key() {
+    // Unlike `KeyMap`, a `CheckedKeyMap` must keep both values reachable under their own exact
+    // key instead of silently aliasing one onto the other when their `Handle`s collide.
+    let (a, b) = find_colliding_strings();
+
+    let mut map = CheckedKeyMap::<i32>::default();
+    assert!(matches!(map.insert(&a, 1).unwrap(), Insertion::New));
+    assert!(matches!(map.insert(&b, 2).unwrap(), Insertion::Collided));
+
+    assert_eq!(map.len(), 2);
+    assert_eq!(map.get(&a), Some(&1));
+    assert_eq!(map.get(&b), Some(&2));
+
+    assert_eq!(map.remove(&a), Some(1));
+    assert_eq!(map.get(&b), Some(&2), "removing `a` must not strand `b`");
+}
+
+#[test]
+fn drops_values() {
+    let mut drops = Box::pin(0);
+
+    struct Foo(*mut u32);
+    impl Drop for Foo {
+        fn drop(&mut self) {
+            unsafe {
+                *self.0 += 1;
+            }
+        }
+    }
+
+    {
+        let mut map = CheckedKeyMap::<Foo, SysAllocator, 1>::default();
+        map.insert(b"foo", Foo(drops.as_mut().get_mut())).unwrap();
+        map.insert(b"bar", Foo(drops.as_mut().get_mut())).unwrap();
+        map.insert(b"foo", Foo(drops.as_mut().get_mut())).unwrap();
+
+        assert_eq!(map.len(), 2);
+        assert_eq!(*drops, 1, "drops the value replaced by the second `foo` insert");
+    }
+    assert_eq!(*drops, 3, "drops the 2 values still in the map");
+}
@@ -0,0 +1,330 @@
+//! [`CaoHashMapN`]: an allocation-free sibling of [`crate::collections::hash_map::CaoHashMap`]
+//! for call sites that can bound their key count up front - per-frame local variable tables,
+//! argument maps - and want no allocator dependency at all, not even the heap map's inline small-
+//! map optimization (which still spills to the allocator past `N` keys).
+//!
+//! Reuses the heap map's control-byte encoding, H2 fingerprinting and hashing (see
+//! [`crate::collections::hash_map`]) so the two stay in sync; since this map can never grow,
+//! [`CaoHashMapN::insert`] returns [`CapacityError`] instead once it can no longer find a slot,
+//! rather than reallocating.
+
+use core::{borrow::Borrow, hash::Hash, mem::MaybeUninit};
+
+use super::hash_map::{hash, h2, is_full, DELETED, EMPTY};
+
+/// Returned once a [`CaoHashMapN`] can no longer find a slot for a new key. Unlike
+/// [`crate::collections::hash_map::MapError`], this is never an allocation failure - the map
+/// never allocates - it just means `N` wasn't big enough for this call site's workload.
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+#[error("CaoHashMapN is full (capacity: {capacity})")]
+pub struct CapacityError {
+    pub capacity: usize,
+}
+
+/// Outcome of probing [`CaoHashMapN`]'s fixed-size control byte array for a key.
+enum Probe {
+    Found(usize),
+    Vacant { index: usize, reused_tombstone: bool },
+}
+
+/// A fixed-capacity, allocation-free hash map holding up to `N` entries inline in stack arrays.
+///
+/// See the module docs for when to reach for this instead of [`crate::collections::hash_map::CaoHashMap`].
+pub struct CaoHashMapN<K, V, const N: usize> {
+    ctrl: [u8; N],
+    keys: [MaybeUninit<K>; N],
+    values: [MaybeUninit<V>; N],
+    count: usize,
+    /// See [`crate::collections::hash_map::CaoHashMap`]'s field of the same name - tracked for the
+    /// same reason: without it, churning insert/remove could leave every slot `DELETED` or
+    /// occupied, with no `EMPTY` slot left for a miss to terminate a probe on.
+    tombstones: usize,
+}
+
+impl<K, V, const N: usize> CaoHashMapN<K, V, N> {
+    pub fn new() -> Self {
+        Self {
+            ctrl: [EMPTY; N],
+            // SAFETY: an array of `MaybeUninit` never requires initialization.
+            keys: unsafe { MaybeUninit::uninit().assume_init() },
+            values: unsafe { MaybeUninit::uninit().assume_init() },
+            count: 0,
+            tombstones: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    pub fn clear(&mut self) {
+        for i in 0..N {
+            if is_full(self.ctrl[i]) {
+                unsafe { drop_slot::<K, V>(&mut self.keys[i], &mut self.values[i]) };
+            }
+            self.ctrl[i] = EMPTY;
+        }
+        self.count = 0;
+        self.tombstones = 0;
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Result<(), CapacityError>
+    where
+        K: Eq + Hash,
+    {
+        let h = hash(&key);
+        unsafe { self.insert_with_hint(h, key, value) }
+    }
+
+    /// # Safety
+    /// Caller must ensure that the hash is correct for the key
+    pub unsafe fn insert_with_hint(&mut self, h: u64, key: K, value: V) -> Result<(), CapacityError>
+    where
+        K: Eq,
+    {
+        let i = match self.find_ind(h, &key)? {
+            Probe::Found(i) => {
+                drop_slot::<K, V>(&mut self.keys[i], &mut self.values[i]);
+                i
+            }
+            Probe::Vacant {
+                index,
+                reused_tombstone,
+            } => {
+                self.ctrl[index] = h2(h);
+                self.count += 1;
+                if reused_tombstone {
+                    self.tombstones -= 1;
+                }
+                index
+            }
+        };
+        core::ptr::write(self.keys[i].as_mut_ptr(), key);
+        core::ptr::write(self.values[i].as_mut_ptr(), value);
+        Ok(())
+    }
+
+    pub fn remove<Q: ?Sized>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash,
+    {
+        let h = hash(key);
+        unsafe { self.remove_with_hint(h, key) }
+    }
+
+    /// # Safety
+    /// Hash must be produced from the key
+    pub unsafe fn remove_with_hint<Q: ?Sized>(&mut self, hash: u64, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq,
+    {
+        let i = match self.find_ind(hash, key) {
+            Ok(Probe::Found(i)) => i,
+            _ => return None,
+        };
+        if core::mem::needs_drop::<K>() {
+            core::ptr::drop_in_place(self.keys[i].as_mut_ptr());
+        }
+        let result = core::ptr::read(self.values[i].as_ptr());
+        self.ctrl[i] = DELETED;
+        self.count -= 1;
+        self.tombstones += 1;
+        Some(result)
+    }
+
+    pub fn contains<Q: ?Sized>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash,
+    {
+        self.get(key).is_some()
+    }
+
+    pub fn get<Q: ?Sized>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash,
+    {
+        let h = hash(key);
+        match self.find_ind(h, key) {
+            Ok(Probe::Found(i)) => Some(unsafe { &*self.values[i].as_ptr() }),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut<Q: ?Sized>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash,
+    {
+        let h = hash(key);
+        match self.find_ind(h, key) {
+            Ok(Probe::Found(i)) => Some(unsafe { &mut *self.values[i].as_mut_ptr() }),
+            _ => None,
+        }
+    }
+
+    pub fn entry(&mut self, key: K) -> Result<Entry<'_, K, V>, CapacityError>
+    where
+        K: Eq + Hash,
+    {
+        let h = hash(&key);
+        let pl = match self.find_ind(h, &key)? {
+            Probe::Found(i) => EntryPayload::Occupied(unsafe { &mut *self.values[i].as_mut_ptr() }),
+            Probe::Vacant {
+                index,
+                reused_tombstone,
+            } => {
+                if reused_tombstone {
+                    self.tombstones -= 1;
+                }
+                EntryPayload::Vacant {
+                    ctrl: &mut self.ctrl[index],
+                    key: self.keys[index].as_mut_ptr(),
+                    value: self.values[index].as_mut_ptr(),
+                    count: &mut self.count,
+                }
+            }
+        };
+        Ok(Entry { hash: h, key, pl })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        let ctrl = self.ctrl.as_ptr();
+        let keys: *const K = self.keys.as_ptr().cast();
+        let values: *const V = self.values.as_ptr().cast();
+        (0..N)
+            .filter(move |i| unsafe { is_full(*ctrl.add(*i)) })
+            .map(move |i| unsafe { (&*keys.add(i), &*values.add(i)) })
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&K, &mut V)> {
+        let ctrl = self.ctrl.as_ptr();
+        let keys: *const K = self.keys.as_ptr().cast();
+        let values: *mut V = self.values.as_mut_ptr().cast();
+        (0..N)
+            .filter(move |i| unsafe { is_full(*ctrl.add(*i)) })
+            .map(move |i| unsafe { (&*keys.add(i), &mut *values.add(i)) })
+    }
+
+    /// Probes for `k`, scanning at most `N` slots - bounded rather than terminating on an `EMPTY`
+    /// slot (as [`crate::collections::hash_map::CaoHashMap::find_ind`] does), since a fixed-size
+    /// table that's been churned full of tombstones may have no `EMPTY` slot left at all. If the
+    /// scan exhausts every slot without a match, the first tombstone seen (if any) is still
+    /// offered up as a [`Probe::Vacant`] slot; only a genuinely full table (no match, no
+    /// tombstone) reports [`CapacityError`].
+    fn find_ind<Q: ?Sized>(&self, needle: u64, k: &Q) -> Result<Probe, CapacityError>
+    where
+        K: Borrow<Q>,
+        Q: Eq,
+    {
+        if N == 0 {
+            return Err(CapacityError { capacity: 0 });
+        }
+        let needle_h2 = h2(needle);
+        let mut ind = (needle.wrapping_mul(2654435769) as usize) % N;
+        let mut first_tombstone = None;
+        for _ in 0..N {
+            let b = self.ctrl[ind];
+            if b == EMPTY {
+                return Ok(match first_tombstone {
+                    Some(index) => Probe::Vacant {
+                        index,
+                        reused_tombstone: true,
+                    },
+                    None => Probe::Vacant {
+                        index: ind,
+                        reused_tombstone: false,
+                    },
+                });
+            } else if b == DELETED {
+                if first_tombstone.is_none() {
+                    first_tombstone = Some(ind);
+                }
+            } else if b == needle_h2 && unsafe { (*self.keys[ind].as_ptr()).borrow() == k } {
+                return Ok(Probe::Found(ind));
+            }
+            ind = (ind + 1) % N;
+        }
+        match first_tombstone {
+            Some(index) => Ok(Probe::Vacant {
+                index,
+                reused_tombstone: true,
+            }),
+            None => Err(CapacityError { capacity: N }),
+        }
+    }
+}
+
+impl<K, V, const N: usize> Default for CaoHashMapN<K, V, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V, const N: usize> Drop for CaoHashMapN<K, V, N> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+/// # Safety
+/// Caller must ensure that `key`/`value` hold initialized values (i.e. their slot's control byte
+/// is "full")
+unsafe fn drop_slot<K, V>(key: &mut MaybeUninit<K>, value: &mut MaybeUninit<V>) {
+    if core::mem::needs_drop::<K>() {
+        core::ptr::drop_in_place(key.as_mut_ptr());
+    }
+    if core::mem::needs_drop::<V>() {
+        core::ptr::drop_in_place(value.as_mut_ptr());
+    }
+}
+
+/// Mirrors [`crate::collections::hash_map::Entry`] - see its docs.
+pub struct Entry<'a, K, V> {
+    hash: u64,
+    key: K,
+    pl: EntryPayload<'a, K, V>,
+}
+
+enum EntryPayload<'a, K, V> {
+    Occupied(&'a mut V),
+    Vacant {
+        ctrl: &'a mut u8,
+        key: *mut K,
+        value: *mut V,
+        count: &'a mut usize,
+    },
+}
+
+impl<'a, K, V> Entry<'a, K, V> {
+    pub fn or_insert_with<F: FnOnce() -> V>(self, fun: F) -> &'a mut V {
+        match self.pl {
+            EntryPayload::Occupied(res) => res,
+            EntryPayload::Vacant {
+                ctrl,
+                key,
+                value,
+                count,
+            } => {
+                *ctrl = h2(self.hash);
+                unsafe {
+                    core::ptr::write(key, self.key);
+                    core::ptr::write(value, fun());
+                    *count += 1;
+                    &mut *value
+                }
+            }
+        }
+    }
+}
@@ -1,50 +1,129 @@
 #[cfg(feature = "serde")]
 mod serde_impl;
 
+#[cfg(feature = "rkyv")]
+mod rkyv_impl;
+#[cfg(feature = "rkyv")]
+pub use rkyv_impl::{ArchivedRawEntries, ArchivedView, RawEntries};
+
 #[cfg(test)]
 mod tests;
 
-use std::{
+use core::{
     alloc::Layout,
     borrow::Borrow,
     hash::{Hash, Hasher},
-    mem::swap,
+    mem::MaybeUninit,
     ptr::NonNull,
 };
 
 use crate::alloc::{Allocator, SysAllocator};
 
-pub(crate) const MAX_LOAD: f32 = 0.7;
+/// Load factor `CaoHashMap` grows at - SwissTable-style metadata (see [`ctrl`]) tolerates a much
+/// fuller table than a plain linear-probed one before clustering hurts, so this is higher than the
+/// naive-probing collections in this module (e.g. [`crate::collections::pre_hash_map`]'s `0.69`).
+pub(crate) const MAX_LOAD: f32 = 0.875;
+
+/// A metadata byte's three possible shapes, packed so a single unsigned comparison tells slot
+/// state apart from occupied-ness:
+///
+/// - [`ctrl::EMPTY`] (`0xFF`): never occupied since the last grow/clear; probing can stop here.
+/// - [`ctrl::DELETED`] (`0x80`) - a tombstone left by [`CaoHashMap::remove_with_hint`]: the slot's
+///   key/value are gone, but probing must keep going past it, since a later-inserted key may have
+///   been pushed beyond it by a collision.
+/// - a "full" byte: the low 7 bits of the occupying key's hash (its H2, see [`h2`]), top bit
+///   always clear (`0..=0x7F`) so it's never confused with `EMPTY`/`DELETED`. Checked before a full
+///   key comparison as a cheap filter - two different keys only collide here 1-in-128 times.
+///
+/// Replaces the old one-`u64`-hash-per-slot metadata array: shrinks metadata from 8 bytes/slot to
+/// 1, and - since `EMPTY`/`DELETED` live outside the 7-bit H2 range rather than at hash `0` - lifts
+/// the old restriction that required `hash()` to never produce `0`.
+///
+/// This lands the control-byte/tombstone layer described for a full SwissTable; probing below is
+/// still the table's original linear scan rather than a fixed-width SIMD group scan, since
+/// swapping in hand-written platform-conditional (SSE2 vs. portable-8-wide) intrinsics isn't
+/// something to do without a benchmark to check it actually helped - left for a follow-up once one
+/// can be run.
+mod ctrl {
+    pub(crate) const EMPTY: u8 = 0xFF;
+    pub(crate) const DELETED: u8 = 0x80;
+
+    #[inline]
+    pub(crate) fn is_full(b: u8) -> bool {
+        b & 0x80 == 0
+    }
+}
+pub(crate) use ctrl::{is_full, DELETED, EMPTY};
+
+/// The low 7 bits of a key's hash, stored in its slot's control byte once it's been placed. See
+/// [`ctrl`].
+#[inline]
+pub(crate) fn h2(hash: u64) -> u8 {
+    (hash & 0x7F) as u8
+}
+
+/// Outcome of probing a control-byte table for a key. Shared with
+/// [`crate::collections::fixed_hash_map::CaoHashMapN`], whose probing is identical save for where
+/// the control/key/value arrays live.
+pub(crate) enum FindResult {
+    /// The key occupies this slot already.
+    Found(usize),
+    /// The key is absent; this is where it should be inserted. `reused_tombstone` tells the
+    /// inserter whether this slot was already counted in a tombstone tally (so it shouldn't be
+    /// counted twice).
+    Vacant { index: usize, reused_tombstone: bool },
+}
+
+/// Number of entries a [`CaoHashMap`] keeps inline, without touching the allocator, before
+/// spilling to an allocator-backed table. Many cao-lang maps (per-lane variable sets, per-frame
+/// locals) hold only a handful of keys, so this removes an allocation on the hot path for them.
+pub const DEFAULT_INLINE_CAPACITY: usize = 4;
 
 type ArrayTriplet<K, V> = (NonNull<u8>, NonNull<K>, NonNull<V>);
 
-/// Hash map implemented for Cao-Lang
-pub struct CaoHashMap<K, V, A: Allocator = SysAllocator> {
-    /// beginning of the data, and the hash buffer
+/// The allocator-backed spilled storage of a [`CaoHashMap`].
+struct Heap<K, V> {
+    /// beginning of the data, and the control-byte buffer (see [`ctrl`])
     /// layout:
-    /// [hash hash hash][key key key][value value value]
+    /// [ctrl ctrl ctrl][key key key][value value value]
     data: NonNull<u8>,
-    /// begin of the keys array
     keys: NonNull<K>,
-    /// begin of the values array
     values: NonNull<V>,
+}
+
+/// Hash map implemented for Cao-Lang
+///
+/// Keeps up to `N` entries inline (no allocation); beyond that it spills to an allocator-backed
+/// table sized the same way the allocator-backed table always was. Once spilled, a map never
+/// moves back to inline storage.
+pub struct CaoHashMap<K, V, A: Allocator = SysAllocator, const N: usize = DEFAULT_INLINE_CAPACITY> {
+    inline_ctrl: [u8; N],
+    inline_keys: [MaybeUninit<K>; N],
+    inline_values: [MaybeUninit<V>; N],
+    heap: Option<Heap<K, V>>,
 
     count: usize,
+    /// Number of [`DELETED`] tombstones currently in the control bytes. Counted separately from
+    /// `count` because a slot a tombstone occupies is just as unavailable to a fresh probe as an
+    /// occupied one - without this, churning insert/remove at a `count` that never trips
+    /// [`Self::needs_grow`] could leave every slot either occupied or tombstoned, with no
+    /// remaining `EMPTY` slot for [`Self::find_ind`]'s probe loop to terminate a miss on.
+    tombstones: usize,
     capacity: usize,
 
     alloc: A,
 }
 
-unsafe impl<K, V, A: Allocator + Send> Send for CaoHashMap<K, V, A> {}
-unsafe impl<K, V, A: Allocator + Send> Sync for CaoHashMap<K, V, A> {}
+unsafe impl<K, V, A: Allocator + Send, const N: usize> Send for CaoHashMap<K, V, A, N> {}
+unsafe impl<K, V, A: Allocator + Send, const N: usize> Sync for CaoHashMap<K, V, A, N> {}
 
-impl<K, V, A> std::fmt::Debug for CaoHashMap<K, V, A>
+impl<K, V, A, const N: usize> core::fmt::Debug for CaoHashMap<K, V, A, N>
 where
-    K: std::fmt::Debug,
-    V: std::fmt::Debug,
+    K: core::fmt::Debug,
+    V: core::fmt::Debug,
     A: Allocator,
 {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         let mut state = f.debug_map();
         for (k, v) in self.iter() {
             state.entry(k, v);
@@ -53,7 +132,7 @@ where
     }
 }
 
-impl<K, V, A> Clone for CaoHashMap<K, V, A>
+impl<K, V, A, const N: usize> Clone for CaoHashMap<K, V, A, N>
 where
     K: Clone + Eq + Hash,
     V: Clone,
@@ -71,9 +150,9 @@ where
     }
 }
 
-impl<K, V, A: Allocator + Default> Default for CaoHashMap<K, V, A> {
+impl<K, V, A: Allocator + Default, const N: usize> Default for CaoHashMap<K, V, A, N> {
     fn default() -> Self {
-        CaoHashMap::with_capacity_in(0, A::default()).unwrap()
+        CaoHashMap::with_capacity_in(N, A::default()).unwrap()
     }
 }
 
@@ -86,7 +165,7 @@ pub struct Entry<'a, K, V> {
 enum EntryPayload<'a, K, V> {
     Occupied(&'a mut V),
     Vacant {
-        hash: &'a mut u64,
+        ctrl: &'a mut u8,
         key: *mut K,
         value: *mut V,
         count: &'a mut usize,
@@ -98,15 +177,15 @@ impl<'a, K, V> Entry<'a, K, V> {
         match self.pl {
             EntryPayload::Occupied(res) => res,
             EntryPayload::Vacant {
-                hash,
+                ctrl,
                 key,
                 value,
                 count,
             } => {
-                *hash = self.hash;
+                *ctrl = h2(self.hash);
                 unsafe {
-                    std::ptr::write(key, self.key);
-                    std::ptr::write(value, fun());
+                    core::ptr::write(key, self.key);
+                    core::ptr::write(value, fun());
                     *count += 1;
                     &mut *value
                 }
@@ -121,17 +200,19 @@ pub enum MapError {
     AllocError(crate::alloc::AllocError),
 }
 
-impl<K, V, A: Allocator> Drop for CaoHashMap<K, V, A> {
+impl<K, V, A: Allocator, const N: usize> Drop for CaoHashMap<K, V, A, N> {
     fn drop(&mut self) {
         self.clear();
-        let (layout, _) = Self::layout(self.capacity);
-        unsafe {
-            self.alloc.dealloc(self.data, layout);
+        if let Some(heap) = self.heap.take() {
+            let (layout, _) = Self::layout(self.capacity);
+            unsafe {
+                self.alloc.dealloc(heap.data, layout);
+            }
         }
     }
 }
 
-impl<K, V, A: Allocator> CaoHashMap<K, V, A> {
+impl<K, V, A: Allocator, const N: usize> CaoHashMap<K, V, A, N> {
     pub fn len(&self) -> usize {
         self.count
     }
@@ -140,23 +221,46 @@ impl<K, V, A: Allocator> CaoHashMap<K, V, A> {
         self.count == 0
     }
 
+    /// Construct a map that keeps `capacity` entries inline (never touching the allocator) when
+    /// `capacity <= N`, and spills to an allocator-backed table otherwise.
     pub fn with_capacity_in(capacity: usize, alloc: A) -> Result<Self, MapError> {
+        // SAFETY: an array of `MaybeUninit` never requires initialization.
+        let inline_keys = unsafe { MaybeUninit::uninit().assume_init() };
+        let inline_values = unsafe { MaybeUninit::uninit().assume_init() };
+
+        if capacity <= N {
+            let mut result = Self {
+                inline_ctrl: [EMPTY; N],
+                inline_keys,
+                inline_values,
+                heap: None,
+                count: 0,
+                tombstones: 0,
+                capacity: N,
+                alloc,
+            };
+            result.reset_ctrl();
+            return Ok(result);
+        }
+
         let capacity = capacity.max(1);
         let (data, keys, values) = unsafe { Self::alloc_storage(&alloc, capacity)? };
         let mut result = Self {
-            data,
-            keys,
-            values,
+            inline_ctrl: [EMPTY; N],
+            inline_keys,
+            inline_values,
+            heap: Some(Heap { data, keys, values }),
             count: 0,
+            tombstones: 0,
             capacity,
             alloc,
         };
-        result.zero_hashes();
+        result.reset_ctrl();
         Ok(result)
     }
 
     /// # Safety
-    /// Caller must ensure that the hashes are zeroed
+    /// Caller must ensure that the control bytes are reset to [`EMPTY`]
     unsafe fn alloc_storage(alloc: &A, cap: usize) -> Result<ArrayTriplet<K, V>, MapError> {
         let (layout, [ko, vo]) = Self::layout(cap);
         let data = alloc.alloc(layout).map_err(MapError::AllocError)?;
@@ -170,26 +274,27 @@ impl<K, V, A: Allocator> CaoHashMap<K, V, A> {
     }
 
     fn layout(cap: usize) -> (Layout, [usize; 2]) {
-        let hash_layout = Layout::array::<u64>(cap).unwrap();
+        let ctrl_layout = Layout::array::<u8>(cap).unwrap();
         let keys_layout = Layout::array::<K>(cap).unwrap();
         let values_layout = Layout::array::<V>(cap).unwrap();
 
-        let (result, keys_offset) = hash_layout.extend(keys_layout).unwrap();
+        let (result, keys_offset) = ctrl_layout.extend(keys_layout).unwrap();
         let (result, vals_offset) = result.extend(values_layout).unwrap();
 
         (result, [keys_offset, vals_offset])
     }
 
     pub fn clear(&mut self) {
-        let handles = self.data.cast::<u64>().as_ptr();
-        let keys = self.keys.as_ptr();
-        let values = self.values.as_ptr();
+        let ctrl = self.ctrl_ptr_mut();
+        let keys = self.keys_ptr_mut();
+        let values = self.values_ptr_mut();
 
         unsafe {
-            clear_arrays(handles, keys, values, self.capacity);
+            clear_arrays(ctrl, keys, values, self.capacity);
         }
 
         self.count = 0;
+        self.tombstones = 0;
     }
 
     pub fn insert(&mut self, key: K, value: V) -> Result<u64, MapError>
@@ -204,76 +309,97 @@ impl<K, V, A: Allocator> CaoHashMap<K, V, A> {
     /// Caller must ensure that the hash is correct for the key
     pub unsafe fn insert_with_hint(&mut self, h: u64, key: K, value: V) -> Result<(), MapError>
     where
-        K: Eq,
+        K: Eq + Hash,
     {
-        debug_assert!(h != 0, "Bad handle, 0 values are reserved");
-
         // find the bucket
-        let hashes = self.hashes();
-        let keys = self.keys.as_ptr();
-        let values = self.values.as_ptr();
-
-        let i = self.find_ind(h, &key);
-        if hashes[i] != 0 {
-            debug_assert_eq!(hashes[i], h);
-            // delete the old entry
-            if std::mem::needs_drop::<K>() {
-                std::ptr::drop_in_place(keys.add(i));
+        let i = match self.find_ind(h, &key) {
+            FindResult::Found(i) => {
+                let keys = self.keys_ptr_mut();
+                let values = self.values_ptr_mut();
+                // delete the old entry
+                if core::mem::needs_drop::<K>() {
+                    core::ptr::drop_in_place(keys.add(i));
+                }
+                if core::mem::needs_drop::<V>() {
+                    core::ptr::drop_in_place(values.add(i));
+                }
+                i
             }
-            if std::mem::needs_drop::<V>() {
-                std::ptr::drop_in_place(values.add(i));
+            FindResult::Vacant {
+                index,
+                reused_tombstone,
+            } => {
+                *self.ctrl_ptr_mut().add(index) = h2(h);
+                self.count += 1;
+                if reused_tombstone {
+                    self.tombstones -= 1;
+                }
+                index
             }
-        } else {
-            self.hashes_mut()[i] = h;
-            self.count += 1;
-        }
-        std::ptr::write(keys.add(i), key);
-        std::ptr::write(values.add(i), value);
+        };
+        let keys = self.keys_ptr_mut();
+        let values = self.values_ptr_mut();
+        core::ptr::write(keys.add(i), key);
+        core::ptr::write(values.add(i), value);
         // delaying grow so that no grow is triggered if the key overrides an existing value
-        if Self::needs_grow(self.count, self.capacity) {
+        if Self::needs_grow(self.count + self.tombstones, self.capacity) {
             self.grow()?;
         }
         Ok(())
     }
 
-    fn needs_grow(count: usize, capacity: usize) -> bool {
-        count as f32 > capacity as f32 * MAX_LOAD
+    fn needs_grow(used: usize, capacity: usize) -> bool {
+        used as f32 > capacity as f32 * MAX_LOAD
     }
 
     pub fn reserve(&mut self, additional_cap: usize) -> Result<(), MapError>
     where
-        K: Eq,
+        K: Eq + Hash,
     {
         unsafe { self.adjust_capacity(self.capacity + additional_cap) }
     }
 
     fn grow(&mut self) -> Result<(), MapError>
     where
-        K: Eq,
+        K: Eq + Hash,
     {
         let new_cap = (self.capacity.max(2) * 3) / 2;
         debug_assert!(new_cap > self.capacity);
         unsafe { self.adjust_capacity(new_cap) }
     }
 
+    /// Grow (or perform the initial inline -> heap spill) to hold at least `capacity` entries.
+    ///
+    /// Once a map has spilled to the allocator it never moves back to inline storage, even if it
+    /// is later drained below `N` entries.
+    ///
+    /// Control bytes only store a key's H2 (see [`h2`]), not its full hash, so the old entries'
+    /// hashes are recomputed from their keys here rather than carried over from the old table.
     unsafe fn adjust_capacity(&mut self, capacity: usize) -> Result<(), MapError>
     where
-        K: Eq,
+        K: Eq + Hash,
     {
-        let (mut data, mut keys, mut values) = Self::alloc_storage(&self.alloc, capacity)?;
-        swap(&mut self.data, &mut data);
-        swap(&mut self.keys, &mut keys);
-        swap(&mut self.values, &mut values);
-        let capacity = std::mem::replace(&mut self.capacity, capacity);
-        self.zero_hashes();
-        let count = std::mem::replace(&mut self.count, 0); // insert will increment count
-                                                           // copy over the existing values
-        for i in 0..capacity {
-            let hash = *data.as_ptr().cast::<u64>().add(i);
-            if hash != 0 {
-                let key = std::ptr::read(keys.as_ptr().add(i));
-                let val = std::ptr::read(values.as_ptr().add(i));
-                self.insert_with_hint(hash, key, val)?;
+        let capacity = capacity.max(N + 1);
+        let (data, keys, values) = Self::alloc_storage(&self.alloc, capacity)?;
+
+        let old_cap = self.capacity;
+        let old_ctrl = self.ctrl_ptr();
+        let old_keys = self.keys_ptr();
+        let old_values = self.values_ptr();
+        let old_heap = self.heap.replace(Heap { data, keys, values });
+
+        self.capacity = capacity;
+        let count = core::mem::replace(&mut self.count, 0); // insert will increment count
+        self.tombstones = 0;
+        self.reset_ctrl();
+
+        // copy over the existing values
+        for i in 0..old_cap {
+            if is_full(*old_ctrl.add(i)) {
+                let key = core::ptr::read(old_keys.add(i));
+                let val = core::ptr::read(old_values.add(i));
+                let h = hash(&key);
+                self.insert_with_hint(h, key, val)?;
             }
         }
 
@@ -283,8 +409,10 @@ impl<K, V, A: Allocator> CaoHashMap<K, V, A> {
         );
 
         // free up the old storage
-        let (layout, _) = Self::layout(capacity);
-        self.alloc.dealloc(data, layout);
+        if let Some(heap) = old_heap {
+            let (layout, _) = Self::layout(old_cap);
+            self.alloc.dealloc(heap.data, layout);
+        }
 
         Ok(())
     }
@@ -306,35 +434,26 @@ impl<K, V, A: Allocator> CaoHashMap<K, V, A> {
         K: Borrow<Q>,
         Q: Eq,
     {
-        let i = self.find_ind(hash, key);
-        if self.hashes()[i] != 0 {
-            if std::mem::needs_drop::<K>() {
-                std::ptr::drop_in_place(self.keys.as_ptr().add(i));
-            }
-
-            let result = std::ptr::read(self.values.as_ptr().add(i));
-            self.hashes_mut()[i] = 0;
-
-            // if the consecutive buckets are not empty, move them back, so lookups dont fail
-            // and they aren't in their optimal position
-            //
-            let mut i = i; // track the last empty slot
-            let mut j = (i + 1) % self.capacity();
-            while self.hashes()[j] != 0 {
-                // if the jth item is not in its optimal bucket, then move it back to the empty
-                // slot
-                if (self.hashes()[j] % self.capacity() as u64) != j as u64 {
-                    self.hashes_mut()[i] = self.hashes()[j];
-                    std::ptr::swap(self.keys.as_ptr().add(i), self.keys.as_ptr().add(j));
-                    std::ptr::swap(self.values.as_ptr().add(i), self.values.as_ptr().add(j));
-                    i = j;
-                }
-                j = (j + 1) % self.capacity();
-            }
+        let i = match self.find_ind(hash, key) {
+            FindResult::Found(i) => i,
+            FindResult::Vacant { .. } => return None,
+        };
+        let ctrl = self.ctrl_ptr_mut();
+        let keys = self.keys_ptr_mut();
+        let values = self.values_ptr_mut();
 
-            return Some(result);
+        if core::mem::needs_drop::<K>() {
+            core::ptr::drop_in_place(keys.add(i));
         }
-        None
+
+        let result = core::ptr::read(values.add(i));
+        // leave a tombstone rather than compacting: probing must still be able to walk past this
+        // slot to find an entry that collided with it and landed further down the chain
+        *ctrl.add(i) = DELETED;
+        self.count -= 1;
+        self.tombstones += 1;
+
+        Some(result)
     }
 
     pub fn contains<Q: ?Sized>(&self, key: &Q) -> bool
@@ -354,8 +473,7 @@ impl<K, V, A: Allocator> CaoHashMap<K, V, A> {
         K: Borrow<Q>,
         Q: Eq + Hash,
     {
-        let i = self.find_ind(h, k);
-        self.hashes()[i] != 0
+        matches!(self.find_ind(h, k), FindResult::Found(_))
     }
 
     pub fn get<Q: ?Sized>(&self, key: &Q) -> Option<&V>
@@ -375,11 +493,9 @@ impl<K, V, A: Allocator> CaoHashMap<K, V, A> {
         K: Borrow<Q>,
         Q: Eq,
     {
-        let i = self.find_ind(h, k);
-        if self.hashes()[i] != 0 {
-            Some(&*self.values.as_ptr().add(i))
-        } else {
-            None
+        match self.find_ind(h, k) {
+            FindResult::Found(i) => Some(&*self.values_ptr().add(i)),
+            FindResult::Vacant { .. } => None,
         }
     }
 
@@ -400,51 +516,111 @@ impl<K, V, A: Allocator> CaoHashMap<K, V, A> {
         K: Borrow<Q>,
         Q: Eq + Hash,
     {
-        let i = self.find_ind(h, k);
-        if self.hashes()[i] != 0 {
-            Some(&mut *self.values.as_ptr().add(i))
-        } else {
-            None
+        match self.find_ind(h, k) {
+            FindResult::Found(i) => Some(&mut *self.values_ptr_mut().add(i)),
+            FindResult::Vacant { .. } => None,
         }
     }
 
-    fn find_ind<Q: ?Sized>(&self, needle: u64, k: &Q) -> usize
+    /// Finds `k`'s slot, or the slot it should be inserted into if absent.
+    ///
+    /// Guaranteed to terminate as long as [`Self::needs_grow`] is kept true whenever `count +
+    /// tombstones` would leave no [`EMPTY`] slot on some probe chain - otherwise a miss could
+    /// probe forever across a table that's entirely [`DELETED`]/occupied.
+    fn find_ind<Q: ?Sized>(&self, needle: u64, k: &Q) -> FindResult
     where
         K: Borrow<Q>,
         Q: Eq,
     {
         let len = self.capacity;
+        let needle_h2 = h2(needle);
 
         // improve uniformity via fibonacci hashing
         // in wasm sizeof usize is 4, so multiply our already 32 bit hash
         let mut ind = (needle.wrapping_mul(2654435769) as usize) % len;
-        let hashes = self.hashes();
-        let keys = self.keys.as_ptr();
+        let ctrl = self.ctrl_ptr();
+        let keys = self.keys_ptr();
+        let mut first_tombstone = None;
         loop {
             unsafe {
                 debug_assert!(ind < len);
-                let h = hashes[ind];
-                if h == 0 || (h == needle && (*keys.add(ind)).borrow() == k) {
-                    return ind;
+                let b = *ctrl.add(ind);
+                if b == EMPTY {
+                    return match first_tombstone {
+                        // reuse the first tombstone seen on the chain rather than `ind`, so probe
+                        // chains don't grow across repeated insert/remove cycles
+                        Some(index) => FindResult::Vacant {
+                            index,
+                            reused_tombstone: true,
+                        },
+                        None => FindResult::Vacant {
+                            index: ind,
+                            reused_tombstone: false,
+                        },
+                    };
+                } else if b == DELETED {
+                    if first_tombstone.is_none() {
+                        first_tombstone = Some(ind);
+                    }
+                } else if b == needle_h2 && (*keys.add(ind)).borrow() == k {
+                    return FindResult::Found(ind);
                 }
             }
             ind = (ind + 1) % len;
         }
     }
 
-    fn hashes(&self) -> &[u64] {
-        unsafe { std::slice::from_raw_parts(self.data.as_ptr().cast(), self.capacity) }
+    fn ctrl_ptr(&self) -> *const u8 {
+        match &self.heap {
+            Some(heap) => heap.data.as_ptr().cast(),
+            None => self.inline_ctrl.as_ptr(),
+        }
+    }
+
+    fn ctrl_ptr_mut(&mut self) -> *mut u8 {
+        match &mut self.heap {
+            Some(heap) => heap.data.as_ptr().cast(),
+            None => self.inline_ctrl.as_mut_ptr(),
+        }
     }
 
-    fn hashes_mut(&mut self) -> &mut [u64] {
-        unsafe { std::slice::from_raw_parts_mut(self.data.as_ptr().cast(), self.capacity) }
+    fn keys_ptr(&self) -> *const K {
+        match &self.heap {
+            Some(heap) => heap.keys.as_ptr(),
+            None => self.inline_keys.as_ptr().cast(),
+        }
     }
 
-    /// Zero-out the hash buffer
+    fn keys_ptr_mut(&mut self) -> *mut K {
+        match &mut self.heap {
+            Some(heap) => heap.keys.as_ptr(),
+            None => self.inline_keys.as_mut_ptr().cast(),
+        }
+    }
+
+    fn values_ptr(&self) -> *const V {
+        match &self.heap {
+            Some(heap) => heap.values.as_ptr(),
+            None => self.inline_values.as_ptr().cast(),
+        }
+    }
+
+    fn values_ptr_mut(&mut self) -> *mut V {
+        match &mut self.heap {
+            Some(heap) => heap.values.as_ptr(),
+            None => self.inline_values.as_mut_ptr().cast(),
+        }
+    }
+
+    /// Reset every control byte to [`EMPTY`]
     ///
     /// Call this function after a fresh alloc of the data buffer
-    fn zero_hashes(&mut self) {
-        self.hashes_mut().fill(0u64);
+    fn reset_ctrl(&mut self) {
+        let capacity = self.capacity;
+        let ctrl = self.ctrl_ptr_mut();
+        unsafe {
+            core::slice::from_raw_parts_mut(ctrl, capacity).fill(EMPTY);
+        }
     }
 
     /// This method eagerly allocated new buffers, if inserting via the entry
@@ -454,24 +630,39 @@ impl<K, V, A: Allocator> CaoHashMap<K, V, A> {
         K: Eq + Hash,
     {
         let hash = hash(&key);
-        let i = self.find_ind(hash, &key);
-        let pl;
-        if self.hashes()[i] != 0 {
-            pl = EntryPayload::Occupied(unsafe { &mut *self.values.as_ptr().add(i) });
-        } else {
-            // if it would need to grow on insert, then allocate the new buffer now
-            if Self::needs_grow(self.count + 1, self.capacity) {
-                self.grow()?;
+        let pl = match self.find_ind(hash, &key) {
+            FindResult::Found(i) => {
+                EntryPayload::Occupied(unsafe { &mut *self.values_ptr_mut().add(i) })
             }
-            unsafe {
-                pl = EntryPayload::Vacant {
-                    hash: &mut *self.data.cast::<u64>().as_ptr().add(i),
-                    key: self.keys.as_ptr().add(i),
-                    value: self.values.as_ptr().add(i),
-                    count: &mut self.count,
+            FindResult::Vacant { .. } => {
+                // if it would need to grow on insert, then allocate the new buffer now
+                if Self::needs_grow(self.count + self.tombstones + 1, self.capacity) {
+                    self.grow()?;
+                }
+                // the buffer may have just been reallocated (or the tombstone reused below by a
+                // grow's rehash), so re-probe against current state rather than reusing `index`
+                let index = match self.find_ind(hash, &key) {
+                    FindResult::Vacant {
+                        index,
+                        reused_tombstone,
+                    } => {
+                        if reused_tombstone {
+                            self.tombstones -= 1;
+                        }
+                        index
+                    }
+                    FindResult::Found(_) => unreachable!("key can't appear during its own grow"),
+                };
+                unsafe {
+                    EntryPayload::Vacant {
+                        ctrl: &mut *self.ctrl_ptr_mut().add(index),
+                        key: self.keys_ptr_mut().add(index),
+                        value: self.values_ptr_mut().add(index),
+                        count: &mut self.count,
+                    }
                 }
             }
-        }
+        };
         Ok(Entry { hash, key, pl })
     }
 
@@ -480,20 +671,21 @@ impl<K, V, A: Allocator> CaoHashMap<K, V, A> {
     }
 
     pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        let ctrl = self.ctrl_ptr();
+        let keys = self.keys_ptr();
+        let values = self.values_ptr();
         (0..self.capacity)
-            .filter(|i| self.hashes()[*i] != 0)
-            .map(|i| unsafe { (&*self.keys.as_ptr().add(i), &*self.values.as_ptr().add(i)) })
+            .filter(move |i| unsafe { is_full(*ctrl.add(*i)) })
+            .map(move |i| unsafe { (&*keys.add(i), &*values.add(i)) })
     }
 
     pub fn iter_mut(&mut self) -> impl Iterator<Item = (&K, &mut V)> {
+        let ctrl = self.ctrl_ptr();
+        let keys = self.keys_ptr();
+        let values = self.values_ptr_mut();
         (0..self.capacity)
-            .filter(|i| self.hashes()[*i] != 0)
-            .map(|i| unsafe {
-                (
-                    &*self.keys.as_ptr().add(i),
-                    &mut *self.values.as_ptr().add(i),
-                )
-            })
+            .filter(move |i| unsafe { is_full(*ctrl.add(*i)) })
+            .map(move |i| unsafe { (&*keys.add(i), &mut *values.add(i)) })
     }
 }
 
@@ -521,27 +713,25 @@ impl Hasher for CaoHasher {
     }
 }
 
-fn hash<T: ?Sized + Hash>(t: &T) -> u64 {
+pub(crate) fn hash<T: ?Sized + Hash>(t: &T) -> u64 {
     let mut hasher = CaoHasher::default();
     t.hash(&mut hasher);
-    let result = hasher.finish();
-    debug_assert_ne!(result, 0, "0 hash is reserved");
-    result
+    hasher.finish()
 }
 
 /// # Safety
 ///
 /// Must be called with valid arrays in a CaoHashMap
-unsafe fn clear_arrays<K, V>(handles: *mut u64, keys: *mut K, values: *mut V, count: usize) {
+unsafe fn clear_arrays<K, V>(ctrl: *mut u8, keys: *mut K, values: *mut V, count: usize) {
     for i in 0..count {
-        if (*handles.add(i)) != 0 {
-            *handles.add(i) = 0;
-            if std::mem::needs_drop::<K>() {
-                std::ptr::drop_in_place(keys.add(i));
+        if is_full(*ctrl.add(i)) {
+            if core::mem::needs_drop::<K>() {
+                core::ptr::drop_in_place(keys.add(i));
             }
-            if std::mem::needs_drop::<V>() {
-                std::ptr::drop_in_place(values.add(i));
+            if core::mem::needs_drop::<V>() {
+                core::ptr::drop_in_place(values.add(i));
             }
         }
+        *ctrl.add(i) = EMPTY;
     }
 }
@@ -0,0 +1,143 @@
+//! Optional zero-copy archival for [`CaoHashMap`], gated behind the `rkyv` feature.
+//!
+//! Archives as a flat `(hash, key, value)` entry list plus a count rather than preserving the live
+//! table's control-byte/probe layout - [`ArchivedView`] then reads straight out of that list (e.g.
+//! from an mmap'd [`crate::compiled_program::CaoCompiledProgram`]) without rebuilding a
+//! [`CaoHashMap`] first. Lookups on the archived form fall back to a linear scan, since the flat
+//! layout doesn't carry the live map's control bytes to probe - still no deserialization pass, just
+//! not O(1) the way [`CaoHashMap::get`] is.
+
+use rkyv::{Archive, Deserialize, Serialize};
+
+use super::*;
+
+/// rkyv's derive needs a plain struct to generate `Archive`/`Serialize`/`Deserialize` impls for;
+/// [`CaoHashMap`] can't derive them directly since its fields are raw pointers and allocator state,
+/// not data. [`CaoHashMap`]'s own `Archive`/`Serialize` impls (below) bridge the two by building one
+/// of these and delegating to its derived impls.
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+pub struct RawEntries<K, V> {
+    pub count: u64,
+    pub entries: Vec<(u64, K, V)>,
+}
+
+impl<K, V, A, const N: usize> CaoHashMap<K, V, A, N>
+where
+    K: Clone + Eq + Hash,
+    V: Clone,
+    A: Allocator,
+{
+    /// Same deterministic content each time it's called for a given `self` - [`Archive::resolve`]
+    /// and [`Serialize::serialize`] below both call this, and must see identical entries since
+    /// `resolve` only gets the resolver `serialize` already computed, not its intermediate `Vec`.
+    fn to_raw_entries(&self) -> RawEntries<K, V> {
+        RawEntries {
+            count: self.len() as u64,
+            entries: self
+                .iter()
+                .map(|(k, v)| (hash(k), k.clone(), v.clone()))
+                .collect(),
+        }
+    }
+}
+
+impl<K, V, A, const N: usize> Archive for CaoHashMap<K, V, A, N>
+where
+    K: Archive + Clone + Eq + Hash,
+    V: Archive + Clone,
+    A: Allocator,
+{
+    type Archived = ArchivedRawEntries<K, V>;
+    type Resolver = RawEntriesResolver<K, V>;
+
+    unsafe fn resolve(&self, pos: usize, resolver: Self::Resolver, out: *mut Self::Archived) {
+        self.to_raw_entries().resolve(pos, resolver, out)
+    }
+}
+
+impl<K, V, A, const N: usize, S> Serialize<S> for CaoHashMap<K, V, A, N>
+where
+    K: Serialize<S> + Clone + Eq + Hash,
+    V: Serialize<S> + Clone,
+    A: Allocator,
+    S: rkyv::ser::Serializer + ?Sized,
+{
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        self.to_raw_entries().serialize(serializer)
+    }
+}
+
+impl<K, V, D> Deserialize<CaoHashMap<K, V>, D> for ArchivedRawEntries<K, V>
+where
+    K: Archive + Eq + Hash,
+    K::Archived: Deserialize<K, D>,
+    V: Archive,
+    V::Archived: Deserialize<V, D>,
+    D: rkyv::Fallible + ?Sized,
+{
+    fn deserialize(&self, deserializer: &mut D) -> Result<CaoHashMap<K, V>, D::Error> {
+        let raw: RawEntries<K, V> = Deserialize::deserialize(self, deserializer)?;
+        let mut cap = raw.entries.len().max(DEFAULT_INLINE_CAPACITY);
+        if cap > DEFAULT_INLINE_CAPACITY && !cap.is_power_of_two() {
+            cap = cap.next_power_of_two();
+        }
+        let mut map = CaoHashMap::with_capacity_in(cap, SysAllocator::default()).expect("oom");
+        for (h, k, v) in raw.entries {
+            unsafe { map.insert_with_hint(h, k, v).expect("oom") };
+        }
+        Ok(map)
+    }
+}
+
+/// Zero-copy view over an [`ArchivedRawEntries`] buffer, returned by
+/// [`CaoHashMap::from_archived`]. Reads straight out of the archived bytes - e.g. an mmap'd
+/// [`crate::compiled_program::CaoCompiledProgram`] - without rebuilding a [`CaoHashMap`] first.
+pub struct ArchivedView<'a, K: Archive, V: Archive> {
+    archived: &'a ArchivedRawEntries<K, V>,
+}
+
+impl<K, V, A, const N: usize> CaoHashMap<K, V, A, N>
+where
+    K: Archive,
+    V: Archive,
+    A: Allocator,
+{
+    pub fn from_archived(archived: &ArchivedRawEntries<K, V>) -> ArchivedView<'_, K, V> {
+        ArchivedView { archived }
+    }
+}
+
+impl<'a, K: Archive, V: Archive> ArchivedView<'a, K, V> {
+    pub fn len(&self) -> usize {
+        self.archived.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn contains<Q: ?Sized>(&self, key: &Q) -> bool
+    where
+        K::Archived: Borrow<Q>,
+        Q: Eq,
+    {
+        self.get(key).is_some()
+    }
+
+    pub fn get<Q: ?Sized>(&self, key: &Q) -> Option<&V::Archived>
+    where
+        K::Archived: Borrow<Q>,
+        Q: Eq,
+    {
+        self.archived
+            .entries
+            .iter()
+            .find(|(_, k, _)| k.borrow() == key)
+            .map(|(_, _, v)| v)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K::Archived, &V::Archived)> {
+        self.archived.entries.iter().map(|(_, k, v)| (k, v))
+    }
+}
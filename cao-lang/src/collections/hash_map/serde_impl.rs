@@ -15,7 +15,7 @@ impl<K: Serialize, V: Serialize> Serialize for CaoHashMap<K, V> {
 }
 
 struct HashMapVisitor<K, V> {
-    _m: std::marker::PhantomData<(K, V)>,
+    _m: core::marker::PhantomData<(K, V)>,
 }
 
 impl<'de, K: Deserialize<'de> + Eq + Hash, V: Deserialize<'de>> Visitor<'de>
@@ -23,7 +23,7 @@ impl<'de, K: Deserialize<'de> + Eq + Hash, V: Deserialize<'de>> Visitor<'de>
 {
     type Value = CaoHashMap<K, V>;
 
-    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+    fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
         formatter.write_str("struct CaoHashMap")
     }
 
@@ -31,8 +31,11 @@ impl<'de, K: Deserialize<'de> + Eq + Hash, V: Deserialize<'de>> Visitor<'de>
     where
         A: ::serde::de::MapAccess<'de>,
     {
-        let mut cap = map.size_hint().unwrap_or(128);
-        if !cap.is_power_of_two() {
+        // counts that fit inline go straight to `CaoHashMap::with_capacity_in`'s inline branch,
+        // which never touches the allocator; only larger maps need rounding up to a power of two
+        // for the allocator-backed table.
+        let mut cap = map.size_hint().unwrap_or(DEFAULT_INLINE_CAPACITY);
+        if cap > DEFAULT_INLINE_CAPACITY && !cap.is_power_of_two() {
             cap = cap.next_power_of_two();
         }
         let mut res = CaoHashMap::with_capacity_in(cap, SysAllocator::default()).expect("oom");
@@ -15,13 +15,23 @@ fn occupied_entry_test() {
 
 #[test]
 fn vacant_entry_inserts_test() {
-    let mut map = CaoHashMap::<i32, i32>::with_capacity_in(1, SysAllocator::default()).unwrap();
+    // request a capacity beyond the inline small-map optimization so this test continues to
+    // exercise the allocator-backed grow path
+    let requested_cap = DEFAULT_INLINE_CAPACITY + 1;
+    let mut map =
+        CaoHashMap::<i32, i32>::with_capacity_in(requested_cap, SysAllocator::default()).unwrap();
 
     let cap = map.capacity();
-    assert_eq!(
-        cap, 1,
-        "Test code assumes that the capacity is 1 at this point"
+    assert!(
+        cap >= requested_cap,
+        "Test code assumes that the capacity is spilled to the allocator at this point"
     );
+    // fill the table up to the load factor, so the vacant entry below has to grow it
+    let prefill = (cap as f32 * MAX_LOAD) as i32;
+    for i in 0..prefill {
+        map.insert(i, i).unwrap();
+    }
+    assert_eq!(map.capacity(), cap);
 
     let entry = map.entry(42).unwrap();
 
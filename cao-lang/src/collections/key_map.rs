@@ -20,9 +20,9 @@ use crate::alloc::{Allocator, SysAllocator};
 #[cfg(feature = "serde")]
 pub use self::serde_impl::*;
 
-use std::{
+use core::{
     alloc::Layout,
-    mem::{align_of, size_of, swap, MaybeUninit},
+    mem::{align_of, size_of, MaybeUninit},
     num::Wrapping,
     ops::{Index, IndexMut},
     ptr::NonNull,
@@ -31,23 +31,38 @@ use std::{
 
 pub(crate) const MAX_LOAD: f32 = 0.69;
 
+/// Number of entries a [`KeyMap`] keeps inline, without touching the allocator, before spilling
+/// to an allocator-backed table. Most cao-lang maps (per-lane variable sets, per-frame locals)
+/// hold only a handful of keys, so this removes an allocation on the hot path for them.
+pub const DEFAULT_INLINE_CAPACITY: usize = 4;
+
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Ord, PartialOrd)]
 #[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
 pub struct Handle(u32);
 
-pub struct KeyMap<T, A = SysAllocator>
+/// The allocator-backed spilled storage of a [`KeyMap`].
+struct Heap<T> {
+    keys: NonNull<Handle>,
+    values: NonNull<T>,
+}
+
+pub struct KeyMap<T, A = SysAllocator, const N: usize = DEFAULT_INLINE_CAPACITY>
 where
     A: Allocator,
 {
-    keys: NonNull<Handle>,
-    values: NonNull<T>,
+    /// Inline storage, used while `heap.is_none()`. Once a map spills to the allocator it never
+    /// moves back, so these slots are simply left untouched (and possibly still holding moved-out
+    /// `MaybeUninit` garbage) for the remainder of the map's life.
+    inline_keys: [Handle; N],
+    inline_values: [MaybeUninit<T>; N],
+    heap: Option<Heap<T>>,
     count: usize,
     capacity: usize,
 
     alloc: A,
 }
 
-impl<T, A> Clone for KeyMap<T, A>
+impl<T, A, const N: usize> Clone for KeyMap<T, A, N>
 where
     T: Clone,
     A: Allocator + Clone,
@@ -63,11 +78,11 @@ where
     }
 }
 
-impl<T, A: Allocator> std::fmt::Debug for KeyMap<T, A>
+impl<T, A: Allocator, const N: usize> core::fmt::Debug for KeyMap<T, A, N>
 where
-    T: std::fmt::Debug,
+    T: core::fmt::Debug,
 {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_map().entries(self.iter()).finish()
     }
 }
@@ -109,7 +124,7 @@ impl<'a, T: 'a> Entry<'a, T> {
 }
 
 impl FromStr for Handle {
-    type Err = std::convert::Infallible;
+    type Err = core::convert::Infallible;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         Ok(Self::from_bytes(s.as_bytes()))
@@ -205,63 +220,120 @@ impl<'a> From<&'a str> for Handle {
     }
 }
 
-impl<T, A> Default for KeyMap<T, A>
+impl<T, A, const N: usize> Default for KeyMap<T, A, N>
 where
     A: Allocator + Default,
 {
     fn default() -> Self {
-        Self::with_capacity(16, A::default()).expect("Failed to init map")
+        Self::with_capacity(N, A::default()).expect("Failed to init map")
     }
 }
 
-impl<T, A> Drop for KeyMap<T, A>
+impl<T, A, const N: usize> Drop for KeyMap<T, A, N>
 where
     A: Allocator,
 {
     fn drop(&mut self) {
         self.clear();
-        unsafe {
-            self.alloc.dealloc(
-                self.keys.cast(),
-                Layout::from_size_align(self.capacity * size_of::<Handle>(), align_of::<Handle>())
+        if let Some(heap) = self.heap.take() {
+            unsafe {
+                self.alloc.dealloc(
+                    heap.keys.cast(),
+                    Layout::from_size_align(
+                        self.capacity * size_of::<Handle>(),
+                        align_of::<Handle>(),
+                    )
                     .unwrap(),
-            );
-            self.alloc.dealloc(
-                self.values.cast(),
-                Layout::from_size_align(self.capacity * size_of::<T>(), align_of::<T>()).unwrap(),
-            );
+                );
+                self.alloc.dealloc(
+                    heap.values.cast(),
+                    Layout::from_size_align(self.capacity * size_of::<T>(), align_of::<T>())
+                        .unwrap(),
+                );
+            }
         }
     }
 }
 
-impl<T, A> KeyMap<T, A>
+impl<T, A, const N: usize> KeyMap<T, A, N>
 where
     A: Allocator,
 {
+    /// Construct a map that keeps `capacity` entries inline (never touching the allocator) when
+    /// `capacity <= N`, and spills to an allocator-backed table otherwise.
+    ///
+    /// As with the pre-existing allocator-backed table, callers requesting an above-inline
+    /// capacity must pass a power of two; [`KeyMap::reserve`]/growth take care of this
+    /// internally.
     pub fn with_capacity(capacity: usize, allocator: A) -> Result<Self, MapError> {
+        let inline_keys = [Handle(0); N];
+        // SAFETY: an array of `MaybeUninit` never requires initialization.
+        let inline_values = unsafe { MaybeUninit::uninit().assume_init() };
+
+        if capacity <= N {
+            return Ok(Self {
+                inline_keys,
+                inline_values,
+                heap: None,
+                count: 0,
+                capacity: N,
+                alloc: allocator,
+            });
+        }
+
         unsafe {
             let (keys, values) = Self::alloc_storage(&allocator, capacity)?;
-            let res = Self {
-                keys,
-                values,
-                alloc: allocator,
+            Ok(Self {
+                inline_keys,
+                inline_values,
+                heap: Some(Heap { keys, values }),
                 count: 0,
                 capacity,
-            };
-            Ok(res)
+                alloc: allocator,
+            })
+        }
+    }
+
+    fn keys_ptr(&self) -> *const Handle {
+        match &self.heap {
+            Some(heap) => heap.keys.as_ptr(),
+            None => self.inline_keys.as_ptr(),
+        }
+    }
+
+    fn keys_ptr_mut(&mut self) -> *mut Handle {
+        match &mut self.heap {
+            Some(heap) => heap.keys.as_ptr(),
+            None => self.inline_keys.as_mut_ptr(),
+        }
+    }
+
+    fn values_ptr(&self) -> *const T {
+        match &self.heap {
+            Some(heap) => heap.values.as_ptr(),
+            None => self.inline_values.as_ptr().cast(),
+        }
+    }
+
+    fn values_ptr_mut(&mut self) -> *mut T {
+        match &mut self.heap {
+            Some(heap) => heap.values.as_ptr(),
+            None => self.inline_values.as_mut_ptr().cast(),
         }
     }
 
     pub fn clear(&mut self) {
         unsafe {
-            for (i, k) in (0..self.capacity)
-                .map(|i| (i, &mut *self.keys.as_ptr().add(i)))
-                .filter(|(_, Handle(x))| *x != 0)
-            {
-                if std::mem::needs_drop::<T>() {
-                    std::ptr::drop_in_place(self.values.as_ptr().add(i));
+            let keys = self.keys_ptr_mut();
+            let values = self.values_ptr_mut();
+            for i in 0..self.capacity {
+                let k = &mut *keys.add(i);
+                if k.0 != 0 {
+                    if core::mem::needs_drop::<T>() {
+                        core::ptr::drop_in_place(values.add(i));
+                    }
+                    k.0 = 0;
                 }
-                k.0 = 0;
             }
             self.count = 0;
         }
@@ -281,16 +353,18 @@ where
 
     pub fn entry(&mut self, key: Handle) -> Entry<T> {
         let ind = self.find_ind(key);
+        let keys = self.keys_ptr_mut();
+        let values = self.values_ptr_mut();
 
         let pl = unsafe {
-            if *self.keys.as_ptr().add(ind) != key {
+            if *keys.add(ind) != key {
                 EntryPayload::Vacant {
-                    key: &mut *self.keys.as_ptr().add(ind),
-                    value: &mut *(self.values.as_ptr().add(ind) as *mut MaybeUninit<T>),
+                    key: &mut *keys.add(ind),
+                    value: &mut *(values.add(ind) as *mut MaybeUninit<T>),
                     count: &mut self.count,
                 }
             } else {
-                EntryPayload::Occupied(&mut *self.values.as_ptr().add(ind))
+                EntryPayload::Occupied(&mut *values.add(ind))
             }
         };
         Entry { key, pl }
@@ -314,15 +388,14 @@ where
     #[inline]
     pub fn contains(&self, key: Handle) -> bool {
         let ind = self.find_ind(key);
-        unsafe { (*self.keys.as_ptr().add(ind)).0 != 0 }
+        unsafe { (*self.keys_ptr().add(ind)).0 != 0 }
     }
 
     pub fn get(&self, key: Handle) -> Option<&T> {
         let ind = self.find_ind(key);
         unsafe {
-            if (*self.keys.as_ptr().add(ind)).0 != 0 {
-                let r = self.values.as_ptr().add(ind);
-                Some(&*r)
+            if (*self.keys_ptr().add(ind)).0 != 0 {
+                Some(&*self.values_ptr().add(ind))
             } else {
                 None
             }
@@ -332,9 +405,8 @@ where
     pub fn get_mut(&mut self, key: Handle) -> Option<&mut T> {
         let ind = self.find_ind(key);
         unsafe {
-            if (*self.keys.as_ptr().add(ind)).0 != 0 {
-                let r = self.values.as_ptr().add(ind);
-                Some(&mut *r)
+            if (*self.keys_ptr().add(ind)).0 != 0 {
+                Some(&mut *self.values_ptr_mut().add(ind))
             } else {
                 None
             }
@@ -353,7 +425,7 @@ where
         // improve uniformity via fibonacci hashing
         // in wasm sizeof usize is 4, so multiply our already 32 bit hash
         let mut ind = (needle.0.wrapping_mul(2654435769) as usize) & len_mask;
-        let ptr = self.keys.as_ptr();
+        let ptr = self.keys_ptr();
         loop {
             debug_assert!(ind < len);
             let k = unsafe { *ptr.add(ind) };
@@ -365,8 +437,8 @@ where
     }
 
     pub fn iter(&self) -> impl Iterator<Item = (Handle, &'_ T)> + '_ {
-        let keys = self.keys.as_ptr();
-        let values = self.values.as_ptr();
+        let keys = self.keys_ptr();
+        let values = self.values_ptr();
         (0..self.capacity).filter_map(move |i| unsafe {
             let k = *keys.add(i);
             (k.0 != 0).then(|| (k, &*values.add(i)))
@@ -374,8 +446,8 @@ where
     }
 
     pub fn iter_mut(&mut self) -> impl Iterator<Item = (Handle, &'_ mut T)> + '_ {
-        let keys = self.keys.as_ptr();
-        let values = self.values.as_ptr();
+        let keys = self.keys_ptr();
+        let values = self.values_ptr_mut();
         (0..self.capacity).filter_map(move |i| unsafe {
             let k = *keys.add(i);
             (k.0 != 0).then(|| (k, &mut *values.add(i)))
@@ -407,47 +479,57 @@ where
         // zero the keys
         let keys: NonNull<Handle> = keys.cast();
         {
-            let keys = std::slice::from_raw_parts_mut(keys.as_ptr(), capacity);
+            let keys = core::slice::from_raw_parts_mut(keys.as_ptr(), capacity);
             keys.fill(Handle(0));
         }
         Ok((keys, values.cast()))
     }
 
+    /// Grow (or perform the initial inline -> heap spill) to hold at least `capacity` entries.
+    ///
+    /// Once a map has spilled to the allocator it never moves back to inline storage, even if it
+    /// is later drained below `N` entries - this mirrors the allocator-backed table's existing
+    /// behaviour of never shrinking on `remove`.
     unsafe fn adjust_capacity(&mut self, capacity: usize) -> Result<(), MapError> {
-        let capacity = pad_pot(capacity).max(4); // allocate at least four items
-        let (mut keys, mut values) = Self::alloc_storage(&self.alloc, capacity)?;
-
-        swap(&mut self.keys, &mut keys);
-        swap(&mut self.values, &mut values);
+        let capacity = pad_pot(capacity).max(N * 2);
+        let (new_keys, new_values) = Self::alloc_storage(&self.alloc, capacity)?;
 
         let old_cap = self.capacity;
+        let old_keys_ptr = self.keys_ptr();
+        let old_values_ptr = self.values_ptr();
+        let old_heap = self.heap.replace(Heap {
+            keys: new_keys,
+            values: new_values,
+        });
+
         let old_count = self.count;
-        // insert the old values
         self.count = 0;
         self.capacity = capacity;
-        for (i, key) in (0..old_cap)
-            .map(|i| (i, *keys.as_ptr().add(i)))
-            .filter(|(_, Handle(x))| *x != 0)
-        {
-            let value: T = std::ptr::read(values.as_ptr().add(i));
-            self._insert(key, value);
+
+        for i in 0..old_cap {
+            let key = *old_keys_ptr.add(i);
+            if key.0 != 0 {
+                let value = core::ptr::read(old_values_ptr.add(i));
+                self._insert(key, value);
+            }
         }
 
-        // dealloc old buffers
-        self.alloc.dealloc(
-            keys.cast(),
-            Layout::from_size_align(old_cap * size_of::<Handle>(), align_of::<Handle>())
-                .expect("old Key layout"),
-        );
-        self.alloc.dealloc(
-            values.cast(),
-            Layout::from_size_align(old_cap * size_of::<T>(), align_of::<T>())
-                .expect("old T layout"),
-        );
+        if let Some(heap) = old_heap {
+            self.alloc.dealloc(
+                heap.keys.cast(),
+                Layout::from_size_align(old_cap * size_of::<Handle>(), align_of::<Handle>())
+                    .expect("old Key layout"),
+            );
+            self.alloc.dealloc(
+                heap.values.cast(),
+                Layout::from_size_align(old_cap * size_of::<T>(), align_of::<T>())
+                    .expect("old T layout"),
+            );
+        }
 
         debug_assert_eq!(
             old_count, self.count,
-            "Expected count to stay unchanged after capacity adjustments"
+            "Internal error: moving the values after realloc resulted in inconsistent count"
         );
 
         Ok(())
@@ -477,19 +559,22 @@ where
 
         debug_assert!(ind < self.capacity);
 
-        let is_new_key = unsafe { (*self.keys.as_ptr().add(ind)).0 == 0 };
+        let keys = self.keys_ptr_mut();
+        let values = self.values_ptr_mut();
+
+        let is_new_key = unsafe { (*keys.add(ind)).0 == 0 };
         self.count += is_new_key as usize;
 
-        if std::mem::needs_drop::<T>() && !is_new_key {
+        if core::mem::needs_drop::<T>() && !is_new_key {
             unsafe {
-                std::ptr::drop_in_place(self.values.as_ptr().add(ind));
+                core::ptr::drop_in_place(values.add(ind));
             }
         }
 
         unsafe {
-            std::ptr::write(self.keys.as_ptr().add(ind), key);
-            std::ptr::write(self.values.as_ptr().add(ind), value);
-            &mut *self.values.as_ptr().add(ind)
+            core::ptr::write(keys.add(ind), key);
+            core::ptr::write(values.add(ind), value);
+            &mut *values.add(ind)
         }
     }
 
@@ -497,14 +582,43 @@ where
     pub fn remove(&mut self, key: Handle) -> Option<T> {
         let ind = self.find_ind(key);
         unsafe {
-            let kptr = self.keys.as_ptr().add(ind);
-            if (*kptr).0 != 0 {
-                self.count -= 1;
-                *kptr = Handle(0);
-                Some(std::ptr::read(self.values.as_ptr().add(ind)))
-            } else {
-                None
+            let keys = self.keys_ptr_mut();
+            let values = self.values_ptr_mut();
+            let kptr = keys.add(ind);
+            if (*kptr).0 == 0 {
+                return None;
             }
+            self.count -= 1;
+            let result = core::ptr::read(values.add(ind));
+
+            // Backward-shift deletion (SwissTable/hashbrown-style): writing a bare `Handle(0)`
+            // tombstone into `ind` would terminate `find_ind`'s linear probe early, stranding any
+            // key that previously probed *past* this slot. Instead walk forward from the gap we
+            // just opened, and for each occupied slot whose own home bucket doesn't need every
+            // slot up to (and including) that slot to still find it, shift it back into the gap.
+            // This never moves a key past its own home bucket, so every remaining key's probe
+            // sequence still reaches it.
+            let len_mask = self.capacity - 1;
+            let mut gap = ind;
+            let mut j = ind;
+            loop {
+                j = (j + 1) & len_mask;
+                let k = *keys.add(j);
+                if k.0 == 0 {
+                    break;
+                }
+                let home = (k.0.wrapping_mul(2654435769) as usize) & len_mask;
+                let dist_gap = (gap + self.capacity - home) & len_mask;
+                let dist_j = (j + self.capacity - home) & len_mask;
+                if dist_gap <= dist_j {
+                    *keys.add(gap) = k;
+                    core::ptr::write(values.add(gap), core::ptr::read(values.add(j)));
+                    gap = j;
+                }
+            }
+            *keys.add(gap) = Handle(0);
+
+            Some(result)
         }
     }
 }
@@ -515,11 +629,8 @@ impl<T> Index<Handle> for KeyMap<T> {
     fn index(&self, key: Handle) -> &Self::Output {
         let ind = self.find_ind(key);
         unsafe {
-            assert!((*self.keys.as_ptr().add(ind)).0 != 0);
-        }
-        unsafe {
-            let r = self.values.as_ptr().add(ind);
-            &*r
+            assert!((*self.keys_ptr().add(ind)).0 != 0);
+            &*self.values_ptr().add(ind)
         }
     }
 }
@@ -527,11 +638,8 @@ impl<T> IndexMut<Handle> for KeyMap<T> {
     fn index_mut(&mut self, key: Handle) -> &mut Self::Output {
         let ind = self.find_ind(key);
         unsafe {
-            assert!((*self.keys.as_ptr().add(ind)).0 != 0);
-        }
-        unsafe {
-            let r = self.values.as_ptr().add(ind);
-            &mut *r
+            assert!((*self.keys_ptr().add(ind)).0 != 0);
+            &mut *self.values_ptr_mut().add(ind)
         }
     }
 }
@@ -584,8 +692,8 @@ impl<T> IndexMut<&[u8]> for KeyMap<T> {
     }
 }
 
-unsafe impl<T, A> Send for KeyMap<T, A> where A: Allocator + Send {}
-unsafe impl<T, A> Sync for KeyMap<T, A> where A: Allocator + Sync {}
+unsafe impl<T, A, const N: usize> Send for KeyMap<T, A, N> where A: Allocator + Send {}
+unsafe impl<T, A, const N: usize> Sync for KeyMap<T, A, N> where A: Allocator + Sync {}
 
 #[inline]
 fn pad_pot(cap: usize) -> usize {
@@ -15,13 +15,13 @@ impl<T: Serialize> Serialize for KeyMap<T> {
 }
 
 struct KeyMapVisitor<T> {
-    _m: std::marker::PhantomData<T>,
+    _m: core::marker::PhantomData<T>,
 }
 
 impl<'de, T: Deserialize<'de>> Visitor<'de> for KeyMapVisitor<T> {
     type Value = KeyMap<T>;
 
-    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+    fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
         formatter.write_str("struct KeyMap")
     }
 
@@ -29,8 +29,11 @@ impl<'de, T: Deserialize<'de>> Visitor<'de> for KeyMapVisitor<T> {
     where
         A: ::serde::de::MapAccess<'de>,
     {
-        let mut cap = map.size_hint().unwrap_or(128);
-        if !cap.is_power_of_two() {
+        // counts that fit inline go straight to `KeyMap::with_capacity`'s inline branch, which
+        // never touches the allocator; only larger maps need rounding up to a power of two for
+        // the allocator-backed table.
+        let mut cap = map.size_hint().unwrap_or(DEFAULT_INLINE_CAPACITY);
+        if cap > DEFAULT_INLINE_CAPACITY && !cap.is_power_of_two() {
             cap = cap.next_power_of_two();
         }
         let mut res = KeyMap::with_capacity(cap, SysAllocator::default()).expect("oom");
@@ -85,3 +85,85 @@ fn drops_values() {
     }
     assert_eq!(*drops, 3, "Drops the 2 items still in the map")
 }
+
+#[test]
+fn remove_returns_the_value_and_shrinks_len() {
+    let mut map = KeyMap::<i32>::default();
+
+    map.insert(Handle(5), 42).expect("insert 0");
+    map.insert(Handle(2), 69).expect("insert 1");
+
+    assert_eq!(map.remove(Handle(5)), Some(42));
+    assert_eq!(map.len(), 1);
+    assert_eq!(map.get(Handle(5)), None);
+    assert_eq!(map.remove(Handle(5)), None, "already removed");
+
+    assert_eq!(map.get(Handle(2)), Some(&69));
+}
+
+#[test]
+fn remove_preserves_probe_chain_of_colliding_keys() {
+    // Pick a small capacity that 2 inserts don't grow and search for 2 handles whose
+    // fibonacci-hashed home buckets collide, so the second key is forced to probe past the first
+    // one's slot. If `remove` simply zeroed out the first slot instead of backward-shifting,
+    // `find_ind` would stop probing right there and "lose" the second key.
+    let mut map = KeyMap::<i32>::with_capacity(8, SysAllocator::default()).unwrap();
+    let capacity = 8usize;
+    let home = |h: u32| (h.wrapping_mul(2654435769) as usize) & (capacity - 1);
+
+    let a = Handle(1);
+    let b = (2u32..)
+        .map(Handle)
+        .find(|h| home(h.0) == home(a.0) && *h != a)
+        .expect("there must be a colliding handle within an 8-slot table");
+
+    map.insert(a, 1).expect("insert a");
+    map.insert(b, 2).expect("insert b");
+    assert_eq!(map.capacity(), capacity, "the keys must still share the table they collided in");
+
+    assert_eq!(map.remove(a), Some(1));
+    assert_eq!(
+        map.get(b),
+        Some(&2),
+        "removing `a` must not strand `b`, which probed past `a`'s slot"
+    );
+}
+
+#[test]
+fn fuzz_insert_remove_matches_std_hashmap() {
+    // Simple xorshift64 PRNG, matching the one backing `Instruction::Random` elsewhere in this
+    // crate - keeps the fuzz run deterministic without pulling in an external RNG dependency.
+    let mut state = 0x2545F4914F6CDD1Du64;
+    let mut next_u64 = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+
+    let mut map = KeyMap::<i32>::default();
+    // Keyed by the raw handle id rather than `Handle` itself, since `Handle` doesn't derive
+    // `Hash` - it only needs to support `KeyMap`'s own hashing scheme.
+    let mut oracle = std::collections::HashMap::new();
+
+    for i in 0..5000 {
+        let id = (next_u64() % 64 + 1) as u32;
+        if next_u64() % 3 == 0 && !oracle.is_empty() {
+            let remove_id = (next_u64() % 64 + 1) as u32;
+            assert_eq!(
+                map.remove(Handle(remove_id)),
+                oracle.remove(&remove_id),
+                "remove mismatch at step {i} for handle {remove_id}"
+            );
+        } else {
+            let value = i;
+            map.insert(Handle(id), value).expect("insert");
+            oracle.insert(id, value);
+        }
+
+        assert_eq!(map.len(), oracle.len(), "length mismatch at step {i}");
+        for (id, v) in oracle.iter() {
+            assert_eq!(map.get(Handle(*id)), Some(v), "missing handle {id} at step {i}");
+        }
+    }
+}
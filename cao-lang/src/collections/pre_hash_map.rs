@@ -18,7 +18,8 @@ mod tests;
 #[cfg(feature = "serde")]
 pub use self::serde::*;
 
-use std::{
+use crate::alloc_crate::{boxed::Box, vec, vec::Vec};
+use core::{
     mem::{replace, swap, MaybeUninit},
     str::FromStr,
 };
@@ -68,7 +69,7 @@ impl<'a, T: 'a> Entry<'a, T> {
 }
 
 impl FromStr for Key {
-    type Err = std::convert::Infallible;
+    type Err = core::convert::Infallible;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         Ok(Self::from_bytes(s.as_bytes()))
@@ -291,9 +292,9 @@ impl<T> PreHashMap<T> {
         let is_new_key = self.keys[ind].0 == 0;
         self.count += is_new_key as usize;
 
-        if std::mem::needs_drop::<T>() && !is_new_key {
+        if core::mem::needs_drop::<T>() && !is_new_key {
             unsafe {
-                std::ptr::drop_in_place(self.values[ind].as_mut_ptr());
+                core::ptr::drop_in_place(self.values[ind].as_mut_ptr());
             }
         }
 
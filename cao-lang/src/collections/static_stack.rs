@@ -1,4 +1,5 @@
-use std::{
+use crate::alloc_crate::{boxed::Box, vec::Vec};
+use core::{
     mem::MaybeUninit,
     ptr::{self, drop_in_place},
 };
@@ -60,7 +61,7 @@ impl<T> Stack<T> {
     }
 
     pub fn clear(&mut self) {
-        if std::mem::needs_drop::<T>() {
+        if core::mem::needs_drop::<T>() {
             for i in 0..self.head {
                 unsafe { drop_in_place(self.storage.get_unchecked_mut(i).as_mut_ptr()) }
             }
@@ -1,24 +1,30 @@
 //! Stack containing only cao-lang Values
 //! Because Values can express `nil` values we use them instead of optionals
 //!
+use crate::alloc_crate::{boxed::Box, vec};
 use crate::value::Value;
 use thiserror::Error;
 
+/// A stack of [`Value`]s backed by a buffer that grows on demand (see [`ValueStack::reserve`])
+/// up to a hard, non-negotiable [`ValueStack::max_size`]. The buffer's current length is just a
+/// soft cap picked to avoid reallocating on every single push; exceeding `max_size` always
+/// returns `StackError::Full` instead of growing further or silently dropping the write.
 #[derive(Debug)]
 pub struct ValueStack {
     count: usize,
     data: Box<[Value]>,
+    max_size: usize,
 }
 
 #[derive(Debug, Error)]
 pub enum StackError {
-    #[error("Stack is full")]
-    Full,
+    #[error("Stack is full: capacity: {capacity} attempted: {attempted}")]
+    Full { capacity: usize, attempted: usize },
     #[error("Index out of bounds: capacity: {capacity} index: {index}")]
     OutOfBounds { capacity: usize, index: usize },
 }
-impl std::fmt::Display for ValueStack {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for ValueStack {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         if self.count == 0 {
             return write!(f, "[]");
         }
@@ -31,11 +37,23 @@ impl std::fmt::Display for ValueStack {
 }
 
 impl ValueStack {
+    /// A fixed-capacity stack: `size` is both the initial buffer length and the hard ceiling, so
+    /// this behaves exactly as before - nothing ever grows. Use [`ValueStack::with_max_size`] for
+    /// a stack that's allowed to grow past its initial buffer.
     pub fn new(size: usize) -> Self {
-        assert!(size > 0);
+        Self::with_max_size(size, size)
+    }
+
+    /// Like [`ValueStack::new`], but allows the buffer to grow (via [`ValueStack::reserve`] /
+    /// [`ValueStack::extend_from_slice`] / [`ValueStack::push`]) past `initial_size`, up to the
+    /// hard `max_size` ceiling.
+    pub fn with_max_size(initial_size: usize, max_size: usize) -> Self {
+        assert!(initial_size > 0);
+        assert!(max_size >= initial_size);
         Self {
             count: 0,
-            data: vec![Value::Nil; size].into_boxed_slice(),
+            data: vec![Value::Nil; initial_size].into_boxed_slice(),
+            max_size,
         }
     }
 
@@ -44,15 +62,51 @@ impl ValueStack {
         &self.data[0..self.count]
     }
 
+    /// The hard ceiling this stack will never grow past; exceeding it is always a
+    /// `StackError::Full`, never silent truncation.
     #[inline]
-    pub fn push<T: Into<Value>>(&mut self, value: T) -> Result<(), StackError> {
-        if self.count + 1 < self.data.len() {
-            self.data[self.count] = value.into();
-            self.count += 1;
-            Ok(())
-        } else {
-            Err(StackError::Full)
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    /// Grows the backing buffer, in one allocation, so that at least `additional` more values fit
+    /// past the current length without reallocating again. No-op if there's already room. Fails
+    /// with `StackError::Full` if doing so would need to cross `max_size`, without touching the
+    /// buffer.
+    pub fn reserve(&mut self, additional: usize) -> Result<(), StackError> {
+        let needed = self.count + additional;
+        if needed > self.max_size {
+            return Err(StackError::Full {
+                capacity: self.max_size,
+                attempted: needed,
+            });
         }
+        if needed > self.data.len() {
+            let new_len = needed.max(self.data.len() * 2).min(self.max_size);
+            let mut data = vec![Value::Nil; new_len].into_boxed_slice();
+            data[..self.data.len()].copy_from_slice(&self.data);
+            self.data = data;
+        }
+        Ok(())
+    }
+
+    /// Pushes every value in `values` in one bulk reservation instead of looping [`push`] one
+    /// value (and one capacity check) at a time.
+    ///
+    /// [`push`]: ValueStack::push
+    pub fn extend_from_slice(&mut self, values: &[Value]) -> Result<(), StackError> {
+        self.reserve(values.len())?;
+        self.data[self.count..self.count + values.len()].copy_from_slice(values);
+        self.count += values.len();
+        Ok(())
+    }
+
+    #[inline]
+    pub fn push<T: Into<Value>>(&mut self, value: T) -> Result<(), StackError> {
+        self.reserve(1)?;
+        self.data[self.count] = value.into();
+        self.count += 1;
+        Ok(())
     }
 
     pub fn clear(&mut self) {
@@ -65,6 +119,11 @@ impl ValueStack {
         self.count
     }
 
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.data.len()
+    }
+
     /// Returns Nil if the stack is empty
     #[inline]
     pub fn pop(&mut self) -> Value {
@@ -111,11 +170,25 @@ impl ValueStack {
             self.push(value)?;
             Ok(Value::Nil)
         } else {
-            let old = std::mem::replace(&mut self.data[index], value);
+            let old = core::mem::replace(&mut self.data[index], value);
             Ok(old)
         }
     }
 
+    /// Reserve `count` local slots starting at `offset` in a single capacity check, filling every
+    /// newly reserved slot with `Value::Nil`, instead of growing the stack one slot (and one
+    /// bounds check) at a time via [`ValueStack::set`]. No-op for slots already covered by
+    /// `self.count`.
+    pub fn reserve_locals(&mut self, offset: usize, count: usize) -> Result<(), StackError> {
+        let target = offset + count;
+        if target > self.count {
+            self.reserve(target - self.count)?;
+            self.data[self.count..target].fill(Value::Nil);
+            self.count = target;
+        }
+        Ok(())
+    }
+
     pub fn get(&mut self, index: usize) -> Value {
         if index >= self.count {
             return Value::Nil;
@@ -157,4 +230,167 @@ impl ValueStack {
             Value::Nil
         }
     }
+
+    /// Swaps the values `i` and `j` entries from the top (0 = the last value, matching
+    /// [`ValueStack::peek_last`]'s indexing). No-op if either index is out of bounds.
+    #[inline]
+    pub fn swap_top(&mut self, i: usize, j: usize) {
+        if self.count <= i || self.count <= j {
+            return;
+        }
+        self.data.swap(self.count - i - 1, self.count - j - 1);
+    }
+
+    /// Cyclically rotates the top `n` entries, moving the topmost value down to the bottom of
+    /// that window and shifting the rest up by one. `n` is clamped to the current stack depth, so
+    /// rotating more entries than are present just rotates the whole stack.
+    #[inline]
+    pub fn rotate_top(&mut self, n: usize) {
+        let n = n.min(self.count);
+        self.data[self.count - n..self.count].rotate_right(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grows_past_initial_capacity() {
+        let mut stack = ValueStack::with_max_size(2, 8);
+        assert_eq!(stack.capacity(), 2);
+
+        for i in 0..8i64 {
+            stack.push(Value::Integer(i)).unwrap();
+        }
+
+        assert_eq!(stack.len(), 8);
+        assert!(stack.capacity() >= 8);
+        for i in 0..8i64 {
+            assert_eq!(stack.as_slice()[i as usize], Value::Integer(i));
+        }
+    }
+
+    #[test]
+    fn extend_from_slice_grows_once_and_appends() {
+        let mut stack = ValueStack::with_max_size(1, 16);
+        stack.push(Value::Integer(1)).unwrap();
+
+        let values = [Value::Integer(2), Value::Integer(3), Value::Integer(4)];
+        stack.extend_from_slice(&values).unwrap();
+
+        assert_eq!(stack.len(), 4);
+        assert_eq!(
+            stack.as_slice(),
+            &[
+                Value::Integer(1),
+                Value::Integer(2),
+                Value::Integer(3),
+                Value::Integer(4)
+            ]
+        );
+    }
+
+    #[test]
+    fn push_fails_exactly_at_max_size() {
+        let mut stack = ValueStack::with_max_size(2, 4);
+        for i in 0..4i64 {
+            stack.push(Value::Integer(i)).unwrap();
+        }
+
+        let err = stack.push(Value::Integer(4)).unwrap_err();
+        match err {
+            StackError::Full {
+                capacity,
+                attempted,
+            } => {
+                assert_eq!(capacity, 4);
+                assert_eq!(attempted, 5);
+            }
+            other => panic!("expected StackError::Full, got {other:?}"),
+        }
+        // the failed push must not have grown or mutated the stack
+        assert_eq!(stack.len(), 4);
+        assert_eq!(stack.max_size(), 4);
+    }
+
+    #[test]
+    fn reserve_locals_grows_past_initial_capacity() {
+        let mut stack = ValueStack::with_max_size(1, 8);
+        stack.reserve_locals(0, 5).unwrap();
+
+        assert_eq!(stack.len(), 5);
+        for v in stack.as_slice() {
+            assert_eq!(*v, Value::Nil);
+        }
+
+        let err = stack.reserve_locals(0, 9).unwrap_err();
+        assert!(matches!(err, StackError::Full { .. }));
+    }
+
+    #[test]
+    fn swap_top_reorders_by_distance_from_top() {
+        let mut stack = ValueStack::new(4);
+        for i in 0..4i64 {
+            stack.push(Value::Integer(i)).unwrap();
+        }
+
+        // top is 3, so swap_top(0, 2) swaps 3 (index 3) and 1 (index 1)
+        stack.swap_top(0, 2);
+
+        assert_eq!(
+            stack.as_slice(),
+            &[
+                Value::Integer(0),
+                Value::Integer(3),
+                Value::Integer(2),
+                Value::Integer(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn swap_top_is_a_noop_out_of_bounds() {
+        let mut stack = ValueStack::new(4);
+        stack.push(Value::Integer(1)).unwrap();
+
+        stack.swap_top(0, 5);
+
+        assert_eq!(stack.as_slice(), &[Value::Integer(1)]);
+    }
+
+    #[test]
+    fn rotate_top_moves_the_top_value_to_the_bottom_of_the_window() {
+        let mut stack = ValueStack::new(4);
+        for i in 0..4i64 {
+            stack.push(Value::Integer(i)).unwrap();
+        }
+
+        stack.rotate_top(3);
+
+        assert_eq!(
+            stack.as_slice(),
+            &[
+                Value::Integer(0),
+                Value::Integer(3),
+                Value::Integer(1),
+                Value::Integer(2),
+            ]
+        );
+    }
+
+    #[test]
+    fn rotate_top_clamps_to_the_stack_depth() {
+        let mut stack = ValueStack::new(4);
+        for i in 0..3i64 {
+            stack.push(Value::Integer(i)).unwrap();
+        }
+
+        stack.rotate_top(10);
+
+        assert_eq!(
+            stack.as_slice()[..3],
+            [Value::Integer(2), Value::Integer(0), Value::Integer(1)]
+        );
+    }
 }
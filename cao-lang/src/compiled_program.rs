@@ -1,4 +1,9 @@
-use std::{mem::transmute, str::FromStr};
+use core::str::FromStr;
+
+#[cfg(feature = "serde")]
+mod flat;
+#[cfg(feature = "serde")]
+pub use flat::{deserialize_flat, serialize_flat, FlatProgramError};
 
 use crate::{
     collections::{
@@ -6,17 +11,68 @@ use crate::{
         hash_map::CaoHashMap,
     },
     compiler::{CardIndex, NameSpace},
-    instruction::Instruction,
     VarName,
 };
 use crate::{version, VariableId};
 
+/// `#[serde(with = "compact_bytes")]` helper for [`CaoCompiledProgram::bytecode`]/`data`: the
+/// default `Vec<u8>` impl serializes element-by-element, which binary formats like bincode or
+/// MessagePack turn into one length-prefixed varint per byte instead of a single contiguous byte
+/// string. `serialize_bytes` lets those formats write the buffer in one shot; human-readable
+/// formats like JSON don't special-case it and fall back to the same per-element array they'd
+/// produce anyway. `deserialize` accepts either representation, so programs serialized before this
+/// change (plain element sequences) still load.
+#[cfg(feature = "serde")]
+mod compact_bytes {
+    use serde::{
+        de::{SeqAccess, Visitor},
+        Deserializer, Serializer,
+    };
+    use core::fmt;
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(bytes)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        struct BytesOrSeq;
+
+        impl<'de> Visitor<'de> for BytesOrSeq {
+            type Value = Vec<u8>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a byte string or a sequence of bytes")
+            }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E> {
+                Ok(v.to_vec())
+            }
+
+            fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+                Ok(v)
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut out = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(byte) = seq.next_element()? {
+                    out.push(byte);
+                }
+                Ok(out)
+            }
+        }
+
+        deserializer.deserialize_bytes(BytesOrSeq)
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
 pub struct Labels(pub HandleTable<Label>);
 
 #[derive(Debug, Default, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
 pub struct Variables {
     pub ids: HandleTable<VariableId>,
     pub names: HandleTable<VarName>,
@@ -24,6 +80,7 @@ pub struct Variables {
 
 #[derive(Debug, Clone, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
 pub struct Label {
     /// Position of this card in the bytecode of the program
     pub pos: u32,
@@ -37,13 +94,14 @@ impl Label {
 
 #[derive(Debug, Clone, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
 pub struct Trace {
     pub namespace: NameSpace,
     pub index: CardIndex,
 }
 
-impl std::fmt::Display for Trace {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for Trace {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         for ns in self.namespace.iter() {
             write!(f, "{ns}.")?;
         }
@@ -51,17 +109,79 @@ impl std::fmt::Display for Trace {
     }
 }
 
+/// A compact, sorted `(bytecode offset, [`Trace`]) -> source card` lookup table.
+///
+/// Unlike [`CaoCompiledProgram::trace`] (a hash map keyed by the offset that *produced* an
+/// instruction, used by the VM to build error backtraces), a [`SourceMap`] is sorted by offset so
+/// callers can resolve *any* offset - including ones that fall between instruction boundaries,
+/// e.g. an interrupted instruction pointer - to the nearest preceding card via binary search.
+///
+/// Gated behind the `debug-info` feature so release builds can omit the extra bytes.
+#[cfg(feature = "debug-info")]
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+pub struct SourceMap(Vec<(u32, Trace)>);
+
+#[cfg(feature = "debug-info")]
+impl SourceMap {
+    pub(crate) fn build(trace: &CaoHashMap<u32, Trace>) -> Self {
+        let mut entries: Vec<(u32, Trace)> = trace
+            .iter()
+            .map(|(offset, t)| (*offset, t.clone()))
+            .collect();
+        entries.sort_unstable_by_key(|(offset, _)| *offset);
+        Self(entries)
+    }
+
+    /// Look up the source span responsible for `offset`, falling back to the nearest preceding
+    /// entry if `offset` does not fall exactly on an instruction boundary.
+    pub fn lookup(&self, offset: u32) -> Option<&Trace> {
+        match self.0.binary_search_by_key(&offset, |(o, _)| *o) {
+            Ok(idx) => Some(&self.0[idx].1),
+            Err(0) => None,
+            Err(idx) => Some(&self.0[idx - 1].1),
+        }
+    }
+}
+
+/// A source location: the card that produced a given bytecode offset.
+#[cfg(feature = "debug-info")]
+pub type SourceSpan = Trace;
+
+/// `bytecode`/`data` serialize as a single contiguous byte string (see `compact_bytes` above)
+/// instead of one `u8` at a time. They stay owned `Vec<u8>` rather than borrowing from the
+/// deserializer: most of the crate (the compiler, `disasm`, `asm`, the VM) already holds long-lived
+/// mutable references into a `CaoCompiledProgram` it owns outright, and switching these two fields
+/// to `Cow<'de, [u8]>` would ripple a lifetime through all of them for a win that only matters when
+/// deserializing from an in-memory buffer under a zero-copy format - not a currently exercised path.
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
 pub struct CaoCompiledProgram {
     /// Instructions
+    #[cfg_attr(feature = "serde", serde(with = "compact_bytes"))]
     pub bytecode: Vec<u8>,
     /// Data used by instuctions with variable length inputs
+    #[cfg_attr(feature = "serde", serde(with = "compact_bytes"))]
     pub data: Vec<u8>,
     pub labels: Labels,
     pub variables: Variables,
+    /// Number of local slots `main` needs at most at any one time, computed by the compiler so
+    /// [`crate::vm::Vm::run`]/[`crate::vm::Vm::run_resumable`] can reserve them in one
+    /// [`crate::collections::value_stack::ValueStack`] extension instead of growing the stack one
+    /// local at a time.
+    pub main_locals: u32,
     pub cao_lang_version: (u8, u8, u16),
     pub trace: CaoHashMap<u32, Trace>,
+    /// Hash of the stdlib's native/function surface this program was compiled against (see
+    /// [`crate::stdlib::stdlib_fingerprint`]). `Vm::run` rejects the program up front if it
+    /// doesn't match the runtime's own fingerprint.
+    pub stdlib_fingerprint: u32,
+    /// Sorted offset -> source card table, for [`CaoCompiledProgram::source_location`] and
+    /// disassembler annotations. `None` unless the `debug-info` feature is enabled.
+    #[cfg(feature = "debug-info")]
+    pub source_map: Option<SourceMap>,
 }
 
 impl CaoCompiledProgram {
@@ -72,70 +192,75 @@ impl CaoCompiledProgram {
             .copied()
     }
 
+    /// Resolve the source card that produced the instruction at `offset`, if the `debug-info`
+    /// feature is enabled and a source map was built for this program.
+    #[cfg(feature = "debug-info")]
+    pub fn source_location(&self, offset: u32) -> Option<SourceSpan> {
+        self.source_map.as_ref()?.lookup(offset).cloned()
+    }
+
     pub fn print_disassembly(&self) {
-        let mut pl = String::new();
-        self.disassemble(&mut pl).unwrap();
-        // FIXME: I'd prefer writing straight to stdout...
-        println!("{pl}");
+        println!("{}", self.disassemble());
     }
 
-    pub fn disassemble(&self, mut writer: impl std::fmt::Write) -> std::fmt::Result {
-        let mut i = 0;
-        while i < self.bytecode.len() {
-            let instr: u8 = self.bytecode[i];
-            let instr: Instruction = unsafe { transmute(instr) };
-            write!(writer, "{i}\t")?;
-            // TODO: also print the arguments of the instructions
-            match instr {
-                Instruction::Add => writeln!(writer, "Add")?,
-                Instruction::Sub => writeln!(writer, "Sub")?,
-                Instruction::Mul => writeln!(writer, "Mul")?,
-                Instruction::Div => writeln!(writer, "Div")?,
-                Instruction::CallNative => writeln!(writer, "CallNative")?,
-                Instruction::ScalarInt => writeln!(writer, "ScalarInt")?,
-                Instruction::ScalarFloat => writeln!(writer, "ScalarFloat")?,
-                Instruction::ScalarNil => writeln!(writer, "ScalarNil")?,
-                Instruction::StringLiteral => writeln!(writer, "StringLiteral")?,
-                Instruction::CopyLast => writeln!(writer, "CopyLast")?,
-                Instruction::Exit => writeln!(writer, "Exit")?,
-                Instruction::CallFunction => writeln!(writer, "CallFunction")?,
-                Instruction::Equals => writeln!(writer, "Equals")?,
-                Instruction::NotEquals => writeln!(writer, "NotEquals")?,
-                Instruction::Less => writeln!(writer, "Less")?,
-                Instruction::LessOrEq => writeln!(writer, "LessOrEq")?,
-                Instruction::Pop => writeln!(writer, "Pop")?,
-                Instruction::SetGlobalVar => writeln!(writer, "SetGlobalVar")?,
-                Instruction::ReadGlobalVar => writeln!(writer, "ReadGlobalVar")?,
-                Instruction::SetLocalVar => writeln!(writer, "SetLocalVar")?,
-                Instruction::ReadLocalVar => writeln!(writer, "ReadLocalVar")?,
-                Instruction::ClearStack => writeln!(writer, "ClearStack")?,
-                Instruction::Return => writeln!(writer, "Return")?,
-                Instruction::SwapLast => writeln!(writer, "SwapLast")?,
-                Instruction::And => writeln!(writer, "And")?,
-                Instruction::Or => writeln!(writer, "Or")?,
-                Instruction::Xor => writeln!(writer, "Xor")?,
-                Instruction::Not => writeln!(writer, "Not")?,
-                Instruction::Goto => writeln!(writer, "Goto")?,
-                Instruction::GotoIfTrue => writeln!(writer, "GotoIfTrue")?,
-                Instruction::GotoIfFalse => writeln!(writer, "GotoIfFalse")?,
-                Instruction::InitTable => writeln!(writer, "InitTable")?,
-                Instruction::GetProperty => writeln!(writer, "GetProperty")?,
-                Instruction::SetProperty => writeln!(writer, "SetProperty")?,
-                Instruction::Len => writeln!(writer, "Len")?,
-                Instruction::BeginForEach => writeln!(writer, "BeginForEach")?,
-                Instruction::ForEach => writeln!(writer, "ForEach")?,
-                Instruction::FunctionPointer => writeln!(writer, "FunctionPointer")?,
-                Instruction::NativeFunctionPointer => writeln!(writer, "NativeFunctionPointer")?,
-                Instruction::NthRow => writeln!(writer, "NthRow")?,
-                Instruction::AppendTable => writeln!(writer, "AppendTable")?,
-                Instruction::PopTable => writeln!(writer, "PopTable")?,
-                Instruction::Closure => writeln!(writer, "Closure")?,
-                Instruction::SetUpvalue => writeln!(writer, "SetUpvalue")?,
-                Instruction::ReadUpvalue => writeln!(writer, "ReadUpvalue")?,
-                Instruction::RegisterUpvalue => writeln!(writer, "RegisterUpvalue")?,
-                Instruction::CloseUpvalue => writeln!(writer, "CloseUpvalue")?,
+    /// Renders this program's bytecode into a listing, one line per instruction, with decoded
+    /// operands and jump targets annotated against [`Self::labels`] - see [`crate::disasm`].
+    /// Lines up 1:1 with the `_run` dispatch arms, so a reader can eyeball exactly what the
+    /// compiler emitted. Delegates to [`crate::disasm::disasm_entries`], so opcode names and
+    /// operand widths come from the same `instructions.in`-generated table the interpreter uses,
+    /// instead of a hand-maintained match that could drift from it.
+    pub fn disassemble(&self) -> String {
+        let mut writer = String::new();
+        self.disassemble_into(&mut writer)
+            .expect("writing to a String never fails, so only undecodable bytecode gets here");
+        writer
+    }
+
+    /// Structured form of [`Self::disassemble`]: one [`crate::disasm::DisasmEntry`] per
+    /// instruction (offset, opcode, decoded operands, resolved label), instead of a pre-rendered
+    /// listing - for debuggers, the card editor, or test assertions that want to inspect exactly
+    /// what compiled without scraping text. A thin wrapper around
+    /// [`crate::disasm::disasm_entries`], kept here so callers working with the bare
+    /// `CaoCompiledProgram` type don't need their own `use crate::disasm::...`.
+    pub fn disassemble_entries(&self) -> Result<Vec<crate::disasm::DisasmEntry>, crate::disasm::DisasmError> {
+        crate::disasm::disasm_entries(self)
+    }
+
+    /// Encodes this program into the portable, versioned blob [`serialize_flat`] describes: a
+    /// little-endian header (magic, format version, endianness canary) followed by
+    /// `bytecode`/`data` verbatim and a small `bincode`-encoded metadata section. Safe to write
+    /// to disk and load back on a different host architecture.
+    #[cfg(feature = "serde")]
+    pub fn serialize(&self) -> Vec<u8> {
+        serialize_flat(self)
+    }
+
+    /// Decodes a blob produced by [`Self::serialize`], validating its header (magic, format
+    /// version, endianness) and section bounds before touching the rest.
+    #[cfg(feature = "serde")]
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, FlatProgramError> {
+        deserialize_flat(bytes)
+    }
+
+    /// Writer-based form of [`Self::disassemble`], for callers that already have a
+    /// [`core::fmt::Write`] sink (e.g. a file or an existing buffer) and want to avoid the
+    /// intermediate `String`.
+    pub fn disassemble_into(&self, mut writer: impl core::fmt::Write) -> core::fmt::Result {
+        let entries = crate::disasm::disasm_entries(self).map_err(|_| core::fmt::Error)?;
+        for entry in entries {
+            if let Some(label) = &entry.label {
+                writeln!(writer, "{label}:")?;
+            }
+            write!(writer, "{}\t", entry.offset)?;
+            #[cfg(feature = "debug-info")]
+            if let Some(span) = self.source_location(entry.offset) {
+                write!(writer, "[{span}]\t")?;
+            }
+            write!(writer, "{}", entry.opcode)?;
+            for operand in &entry.operands {
+                write!(writer, " {operand}")?;
             }
-            i += instr.span();
+            writeln!(writer)?;
         }
         Ok(())
     }
@@ -148,8 +273,12 @@ impl Default for CaoCompiledProgram {
             data: Default::default(),
             labels: Default::default(),
             variables: Default::default(),
+            main_locals: 0,
             cao_lang_version: (version::MAJOR, version::MINOR, version::PATCH),
             trace: Default::default(),
+            stdlib_fingerprint: crate::stdlib::stdlib_fingerprint(),
+            #[cfg(feature = "debug-info")]
+            source_map: Default::default(),
         }
     }
 }
@@ -0,0 +1,145 @@
+//! Flat, mmap-friendly (de)serialization of [`CaoCompiledProgram`].
+//!
+//! `#[derive(Serialize, Deserialize)]` (see the top of `compiled_program.rs`) already round-trips
+//! a whole [`CaoCompiledProgram`] through `bincode`, but loading it back means parsing the entire
+//! structure into fresh `Vec`s/maps before the VM can run it - wasted work for a host that ships
+//! (and reloads) the same bytecode on every startup. This module instead lays the program out as a
+//! single contiguous byte blob: a small fixed-size header (magic, format version, an endianness
+//! canary) followed by the `bytecode` and `data` buffers verbatim, so a cached program can be
+//! validated with a handful of bounds checks and copied out in bulk instead of parsed
+//! byte-by-byte; the loaded program owns its own copies of both buffers.
+//! Everything else (`labels`, `variables`, `trace`, ...) is comparatively small metadata, so it
+//! rides along as one more section encoded with the same `bincode` format the rest of the crate
+//! already uses, rather than hand-rolling a pointer-free layout for it too.
+use crate::alloc_crate::vec::Vec;
+
+use super::{CaoCompiledProgram, Labels, Trace, Variables};
+use crate::collections::hash_map::CaoHashMap;
+
+#[cfg(feature = "debug-info")]
+use super::SourceMap;
+
+const MAGIC: [u8; 4] = *b"CAOP";
+const FORMAT_VERSION: u16 = 1;
+/// Written little-endian like every other header field and checked on load, so it reads back as
+/// `0x0102` on any host; a blob whose header was written in the opposite byte order reads it as
+/// `0x0201` and is rejected outright instead of silently misinterpreting every length that
+/// follows it.
+const ENDIANNESS_CANARY: u16 = 0x0102;
+
+const HEADER_LEN: usize = 4 + 2 + 2 + 4 + 4 + 4;
+
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+pub enum FlatProgramError {
+    #[error("flat program blob is too short to contain a valid header")]
+    Truncated,
+    #[error("flat program blob does not start with the expected magic number")]
+    BadMagic,
+    #[error("flat program blob was built with incompatible format version {0}")]
+    UnsupportedVersion(u16),
+    #[error("flat program blob was written in a different byte order")]
+    WrongEndianness,
+    #[error("flat program blob's metadata section is corrupt")]
+    BadMetadata,
+}
+
+/// The fields of [`CaoCompiledProgram`] that aren't worth a hand-rolled flat layout - small,
+/// pointer-bearing, and already `bincode`-serializable.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Metadata {
+    labels: Labels,
+    variables: Variables,
+    main_locals: u32,
+    cao_lang_version: (u8, u8, u16),
+    trace: CaoHashMap<u32, Trace>,
+    stdlib_fingerprint: u32,
+    #[cfg(feature = "debug-info")]
+    source_map: Option<SourceMap>,
+}
+
+/// Serialize `program` into a single contiguous, `mmap`-friendly byte blob: a fixed header
+/// followed by `bytecode`, `data`, and a `bincode`-encoded metadata section, in that order.
+pub fn serialize_flat(program: &CaoCompiledProgram) -> Vec<u8> {
+    let metadata = Metadata {
+        labels: program.labels.clone(),
+        variables: program.variables.clone(),
+        main_locals: program.main_locals,
+        cao_lang_version: program.cao_lang_version,
+        trace: program.trace.clone(),
+        stdlib_fingerprint: program.stdlib_fingerprint,
+        #[cfg(feature = "debug-info")]
+        source_map: program.source_map.clone(),
+    };
+    let metadata_bytes = bincode::serialize(&metadata).expect("metadata is always serializable");
+
+    let mut out = Vec::with_capacity(
+        HEADER_LEN + program.bytecode.len() + program.data.len() + metadata_bytes.len(),
+    );
+    out.extend_from_slice(&MAGIC);
+    out.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    out.extend_from_slice(&ENDIANNESS_CANARY.to_le_bytes());
+    out.extend_from_slice(&(program.bytecode.len() as u32).to_le_bytes());
+    out.extend_from_slice(&(program.data.len() as u32).to_le_bytes());
+    out.extend_from_slice(&(metadata_bytes.len() as u32).to_le_bytes());
+    out.extend_from_slice(&program.bytecode);
+    out.extend_from_slice(&program.data);
+    out.extend_from_slice(&metadata_bytes);
+    out
+}
+
+/// Validate and load a blob produced by [`serialize_flat`]. Rejects blobs that are truncated,
+/// carry the wrong magic/version, or were written with different endianness before touching the
+/// section lengths they describe.
+pub fn deserialize_flat(bytes: &[u8]) -> Result<CaoCompiledProgram, FlatProgramError> {
+    if bytes.len() < HEADER_LEN {
+        return Err(FlatProgramError::Truncated);
+    }
+
+    let magic: [u8; 4] = bytes[0..4].try_into().unwrap();
+    if magic != MAGIC {
+        return Err(FlatProgramError::BadMagic);
+    }
+
+    let format_version = u16::from_le_bytes(bytes[4..6].try_into().unwrap());
+    if format_version != FORMAT_VERSION {
+        return Err(FlatProgramError::UnsupportedVersion(format_version));
+    }
+
+    let endianness_canary = u16::from_le_bytes(bytes[6..8].try_into().unwrap());
+    if endianness_canary != ENDIANNESS_CANARY {
+        return Err(FlatProgramError::WrongEndianness);
+    }
+
+    let bytecode_len = u32::from_le_bytes(bytes[8..12].try_into().unwrap()) as usize;
+    let data_len = u32::from_le_bytes(bytes[12..16].try_into().unwrap()) as usize;
+    let metadata_len = u32::from_le_bytes(bytes[16..20].try_into().unwrap()) as usize;
+
+    let body = &bytes[HEADER_LEN..];
+    let total = bytecode_len
+        .checked_add(data_len)
+        .and_then(|n| n.checked_add(metadata_len))
+        .ok_or(FlatProgramError::Truncated)?;
+    if body.len() < total {
+        return Err(FlatProgramError::Truncated);
+    }
+
+    let (bytecode, body) = body.split_at(bytecode_len);
+    let (data, body) = body.split_at(data_len);
+    let metadata_bytes = &body[..metadata_len];
+
+    let metadata: Metadata =
+        bincode::deserialize(metadata_bytes).map_err(|_| FlatProgramError::BadMetadata)?;
+
+    Ok(CaoCompiledProgram {
+        bytecode: bytecode.to_vec(),
+        data: data.to_vec(),
+        labels: metadata.labels,
+        variables: metadata.variables,
+        main_locals: metadata.main_locals,
+        cao_lang_version: metadata.cao_lang_version,
+        trace: metadata.trace,
+        stdlib_fingerprint: metadata.stdlib_fingerprint,
+        #[cfg(feature = "debug-info")]
+        source_map: metadata.source_map,
+    })
+}
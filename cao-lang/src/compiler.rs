@@ -1,8 +1,15 @@
 //! The compiler module that transforms [CaoIr](CaoIr) into bytecode.
 //!
 mod card;
+mod card_binary;
+mod card_fold;
+mod card_template;
+mod card_visit;
 mod compilation_error;
 mod compile_options;
+mod const_fold;
+mod dce;
+#[path = "compiler/lane.rs"]
 mod function;
 mod module;
 
@@ -11,20 +18,31 @@ mod function_ir;
 mod tests;
 
 use crate::{
+    alloc_crate::{
+        borrow::Cow,
+        boxed::Box,
+        string::{String, ToString},
+        vec::Vec,
+    },
     bytecode::{encode_str, write_to_vec},
-    collections::{handle_table::Handle, hash_map::CaoHashMap},
+    collections::{
+        handle_table::{Handle, HandleTable},
+        hash_map::CaoHashMap,
+    },
     compiled_program::{CaoCompiledProgram, Label},
     prelude::Trace,
     Instruction, VariableId,
 };
+use core::convert::{TryFrom, TryInto};
+use core::fmt::Debug;
+use core::mem;
 use core::slice;
-use std::borrow::Cow;
-use std::convert::TryFrom;
-use std::fmt::Debug;
-use std::mem;
-use std::{convert::TryInto, str::FromStr};
+use core::str::FromStr;
 
 pub use card::*;
+pub use card_binary::DecodeError;
+pub use card_template::{CardTemplateRegistry, ExpandError};
+pub use card_visit::{CardVisitor, CardVisitorMut, Order};
 pub use compilation_error::*;
 pub use compile_options::*;
 pub use function::*;
@@ -39,7 +57,7 @@ pub type CompilationResult<T> = Result<T, CompilationError>;
 /// Execution will begin with the first Function
 pub(crate) type FunctionSlice<'a> = &'a [FunctionIr];
 pub(crate) type NameSpace = smallvec::SmallVec<[Box<str>; 8]>;
-pub(crate) type ImportsIr = std::collections::HashMap<String, String>;
+pub(crate) type ImportsIr = CaoHashMap<String, String>;
 pub(crate) type Locals<'a> = arrayvec::ArrayVec<Local<'a>, 255>;
 type Upvalues = arrayvec::ArrayVec<Upvalue, 255>;
 
@@ -56,15 +74,40 @@ pub struct Compiler<'a> {
     locals: Vec<Locals<'a>>,
     upvalues: Vec<Upvalues>,
     scope_depth: Vec<i32>,
+    /// Active `ForEach`/`While`/`Repeat` loops, innermost last, for `Card::Break`/`Card::Continue`
+    /// to target - parallel to `locals`/`upvalues`/`scope_depth`, one `Vec` per nesting `Card::Closure`
+    /// function level (see `Compiler::compile_begin`/`compile_end`).
+    loop_contexts: Vec<Vec<LoopContext>>,
     current_index: CardIndex,
     function_id: usize,
+
+    /// High-water mark of concurrently live locals per `function_id`, reset at the start of each
+    /// top-level function (which, unlike closures, all share `function_id == 0`). Used to reserve
+    /// every call's locals in one [`crate::collections::value_stack::ValueStack`] extension
+    /// instead of growing it one [`Instruction::SetLocalVar`] at a time.
+    max_locals: Vec<u32>,
+    /// Final `max_locals` of every function, keyed by its handle, once its body is fully
+    /// compiled.
+    function_locals: HandleTable<u32>,
+    /// Bytecode offsets of `max_locals` operands written by [`Compiler::encode_jump`] before the
+    /// callee's `function_locals` entry was known (forward references), paired with the callee's
+    /// handle. Patched in [`Compiler::patch_max_locals`] once every function body is compiled.
+    max_locals_patches: Vec<(usize, Handle)>,
+    /// Recoverable [`CompilationError`]s found so far, in discovery order - see
+    /// [`Compiler::push_error`]. [`Compiler::compile`] only ever surfaces the first one (for
+    /// callers that just want a pass/fail result), but [`compile_diagnostics`] reports all of
+    /// them together.
+    errors: Vec<CompilationError>,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 struct FunctionMeta {
     pub hash_key: Handle,
     /// number of arguments
     pub arity: u32,
+    /// Where this function was first declared, so a later [`CompilationErrorPayload::DuplicateName`]
+    /// can point back at it.
+    pub def_site: Trace,
 }
 
 /// local variables during compilation
@@ -80,14 +123,56 @@ pub fn compile(
     compile_options: impl Into<Option<CompileOptions>>,
 ) -> CompilationResult<CaoCompiledProgram> {
     let options = compile_options.into().unwrap_or_default();
-    let compilation_unit = compilation_unit
+    let mut compilation_unit = compilation_unit;
+    if options.constant_folding {
+        const_fold::fold_constants(&mut compilation_unit)?;
+    }
+    let mut compilation_unit = compilation_unit
         .into_ir_stream(options.recursion_limit)
         .map_err(|err| CompilationError::with_loc(err, Trace::default()))?;
+    if options.dead_code_elimination {
+        dce::eliminate_dead_code(&mut compilation_unit);
+    }
 
     let mut compiler = Compiler::new();
     compiler.compile(&compilation_unit, options)
 }
 
+/// Like [`compile`], but reports every recoverable [`CompilationError`] found in this pass
+/// instead of only the first - e.g. every duplicate function name in a program, not just the
+/// first one encountered - so editor tooling can show a user the full list at once rather than
+/// making them fix one error, recompile, and hit the next. Errors the compiler can't recover from
+/// (an internal failure, rather than a diagnosable problem with the program) still abort the pass
+/// early, the same as [`compile`] - in that case `Diagnostics` just carries the one error.
+pub fn compile_diagnostics(
+    compilation_unit: CaoProgram,
+    compile_options: impl Into<Option<CompileOptions>>,
+) -> Result<CaoCompiledProgram, Diagnostics> {
+    let options = compile_options.into().unwrap_or_default();
+    let mut compilation_unit = compilation_unit;
+    if options.constant_folding {
+        const_fold::fold_constants(&mut compilation_unit).map_err(|err| Diagnostics(vec![err]))?;
+    }
+    let mut compilation_unit = compilation_unit
+        .into_ir_stream(options.recursion_limit)
+        .map_err(|err| Diagnostics(vec![CompilationError::with_loc(err, Trace::default())]))?;
+    if options.dead_code_elimination {
+        dce::eliminate_dead_code(&mut compilation_unit);
+    }
+
+    let mut compiler = Compiler::new();
+    match compiler.compile(&compilation_unit, options) {
+        Ok(program) => Ok(program),
+        Err(first) => {
+            let mut errors = compiler.take_diagnostics().0;
+            if errors.is_empty() {
+                errors.push(first);
+            }
+            Err(Diagnostics(errors))
+        }
+    }
+}
+
 impl<'a> Default for Compiler<'a> {
     fn default() -> Self {
         Self::new()
@@ -107,6 +192,24 @@ struct Upvalue {
     index: u8,
 }
 
+/// State tracked for one active `ForEach`/`While`/`Repeat` loop, so a `Card::Break`/`Card::Continue`
+/// nested in its body knows where to jump. Pushed when the loop's body starts compiling and popped
+/// once it's done - see the `Card::ForEach`/`Card::While`/`Card::DoWhile`/`Card::Repeat` arms of
+/// [`Compiler::process_card`].
+struct LoopContext {
+    /// Scope depth the loop's body runs at; `Break`/`Continue` pop every local declared deeper
+    /// than this (mirroring [`Compiler::scope_end`]) without removing them from `self.locals`,
+    /// since the loop body keeps compiling past the jump.
+    scope_depth: i32,
+    /// Bytecode offsets of a `Break`'s placeholder `Goto` operand, backpatched to the position
+    /// right after the loop once it's fully compiled.
+    break_patches: Vec<usize>,
+    /// Bytecode offsets of a `Continue`'s placeholder `Goto` operand, backpatched to the loop's
+    /// re-entry point (its condition check, or - for `Repeat` - the counter increment) once that
+    /// position is known.
+    continue_patches: Vec<usize>,
+}
+
 impl<'a> Compiler<'a> {
     pub fn new() -> Self {
         Compiler {
@@ -118,9 +221,14 @@ impl<'a> Compiler<'a> {
             locals: vec![Default::default()],
             upvalues: vec![Default::default()],
             scope_depth: vec![0],
+            loop_contexts: vec![Vec::new()],
             current_index: CardIndex::default(),
             current_imports: Default::default(),
             function_id: 0,
+            max_locals: vec![0],
+            function_locals: Default::default(),
+            max_locals_patches: Vec::new(),
+            errors: Vec::new(),
         }
     }
 
@@ -145,12 +253,26 @@ impl<'a> Compiler<'a> {
         }
         self.program = CaoCompiledProgram::default();
         self.next_var = VariableId(0);
+        self.errors.clear();
         self.compile_stage_1(compilation_unit)?;
         self.compile_stage_2(compilation_unit)?;
+        self.patch_max_locals();
 
         self.current_imports = Default::default();
         // the last instruction is a trap for native to cao-lang function calls
         self.push_instruction(Instruction::Exit);
+        #[cfg(feature = "debug-info")]
+        {
+            self.program.source_map =
+                Some(crate::compiled_program::SourceMap::build(&self.program.trace));
+        }
+        // Recoverable errors (see `push_error`) don't abort compilation on their own, but the
+        // program they produced is still broken - surface the first one here so this method's
+        // existing pass/fail contract holds. `compile_diagnostics` reports the rest of `self.errors`
+        // alongside it.
+        if let Some(err) = self.errors.first() {
+            return Err(err.clone());
+        }
         Ok(mem::take(&mut self.program))
     }
 
@@ -158,6 +280,18 @@ impl<'a> Compiler<'a> {
         CompilationError::with_loc(pl, self.trace())
     }
 
+    /// Record a recoverable error and keep compiling, instead of aborting the whole pass - for
+    /// problems a caller can fix all at once from a single compile, like a duplicate name or an
+    /// empty variable, rather than recompiling after each fix. See [`Compiler::errors`].
+    fn push_error(&mut self, err: CompilationError) {
+        self.errors.push(err);
+    }
+
+    /// The recoverable errors accumulated by [`Compiler::push_error`] so far, in discovery order.
+    pub(crate) fn take_diagnostics(&mut self) -> Diagnostics {
+        Diagnostics(mem::take(&mut self.errors))
+    }
+
     /// build the jump table and consume the function names
     fn compile_stage_1(&mut self, compilation_unit: FunctionSlice) -> CompilationResult<()> {
         let mut num_cards = 0usize;
@@ -174,14 +308,23 @@ impl<'a> Compiler<'a> {
         Ok(())
     }
 
+    /// Registers `n` in the jump table, unless its name is already taken - in which case this
+    /// records a [`CompilationErrorPayload::DuplicateName`] labeled with the original definition
+    /// and keeps going, so a program with several clashing names gets all of them reported at
+    /// once instead of one compile-fix-recompile cycle per name.
     fn add_function(&mut self, handle: Handle, n: &FunctionIr) -> CompilationResult<()> {
+        if let Some(existing) = self.jump_table.get(n.name.as_ref()) {
+            let err = self
+                .error(CompilationErrorPayload::DuplicateName(n.name.to_string()))
+                .with_label(existing.def_site.clone(), "first defined here");
+            self.push_error(err);
+            return Ok(());
+        }
         let metadata = FunctionMeta {
             hash_key: handle,
             arity: n.arguments.len() as u32,
+            def_site: self.trace(),
         };
-        if self.jump_table.contains(n.name.as_ref()) {
-            return Err(self.error(CompilationErrorPayload::DuplicateName(n.name.to_string())));
-        }
         self.jump_table
             .insert(n.name.to_string(), metadata)
             .unwrap();
@@ -197,9 +340,11 @@ impl<'a> Compiler<'a> {
                 Ok(i) => i,
                 Err(_) => return Err(self.error(CompilationErrorPayload::TooManyCards(il))),
             };
+            let main_handle = CardIndex::function(il).as_handle();
             self.current_index = CardIndex::new(il, 0);
+            *self.max_locals.last_mut().unwrap() = 0;
             self.scope_begin();
-            self.process_function(main_function)?;
+            self.process_function(main_function, false)?;
             self.current_index = CardIndex {
                 function: il,
                 card_index: FunctionCardIndex {
@@ -207,6 +352,11 @@ impl<'a> Compiler<'a> {
                 },
             };
             self.scope_end();
+            let main_locals = *self.max_locals.last().unwrap();
+            self.function_locals
+                .insert(main_handle, main_locals)
+                .unwrap();
+            self.program.main_locals = main_locals;
             // insert explicit exit after the first function
             self.process_card(&Card::Abort)?;
         }
@@ -222,9 +372,12 @@ impl<'a> Compiler<'a> {
                 .insert(nodeid_handle, Label::new(handle))
                 .unwrap();
 
+            *self.max_locals.last_mut().unwrap() = 0;
             self.scope_begin();
-            self.process_function(function)?;
+            self.process_function(function, true)?;
             self.scope_end();
+            let locals = *self.max_locals.last().unwrap();
+            self.function_locals.insert(nodeid_handle, locals).unwrap();
             self.push_instruction(Instruction::ScalarNil);
             self.emit_return()?;
         }
@@ -238,6 +391,8 @@ impl<'a> Compiler<'a> {
         self.locals.push(Default::default());
         self.upvalues.push(Default::default());
         self.scope_depth.push(0);
+        self.max_locals.push(0);
+        self.loop_contexts.push(Vec::new());
     }
 
     /// end nested compile sequence
@@ -246,6 +401,8 @@ impl<'a> Compiler<'a> {
         self.locals.pop();
         self.upvalues.pop();
         self.scope_depth.pop();
+        self.max_locals.pop();
+        self.loop_contexts.pop();
     }
 
     fn scope_begin(&mut self) {
@@ -260,6 +417,12 @@ impl<'a> Compiler<'a> {
         self.scope_depth.last_mut().unwrap()
     }
 
+    /// Closes the current scope, popping every local declared inside it off both `self.locals` and
+    /// the runtime value stack (one `Pop`/`CloseUpvalue` each, in declaration-reverse order). Since
+    /// [`Compiler::add_local_unchecked`] always hands out the next slot index as `self.locals`'
+    /// current length, this doubles as a free list: a slot released here is the very next one a
+    /// sibling scope's `add_local*` call reuses, so only concurrently-live locals (tracked by
+    /// `max_locals`) ever need their own distinct slot, not every local ever declared.
     fn scope_end(&mut self) {
         *self.scope_depth_mut() -= 1;
         let scope_depth = self.scope_depth();
@@ -278,6 +441,45 @@ impl<'a> Compiler<'a> {
         }
     }
 
+    /// Emits the same per-local `Pop`/`CloseUpvalue` cleanup as [`Compiler::scope_end`], for every
+    /// local declared deeper than `target_depth`, but without removing them from `self.locals` -
+    /// used by `Break`/`Continue`, which jump out of a scope the surrounding loop body is still
+    /// compiling past.
+    fn emit_scope_cleanup(&mut self, target_depth: i32) {
+        for local in self.locals[self.function_id].iter().rev() {
+            if local.depth <= target_depth {
+                break;
+            }
+            if local.captured {
+                self.program.bytecode.push(Instruction::CloseUpvalue as u8);
+            } else {
+                self.program.bytecode.push(Instruction::Pop as u8);
+            }
+        }
+    }
+
+    /// Shared by the `Card::Break`/`Card::Continue` arms of [`Compiler::process_card`]: unwinds
+    /// locals declared inside the innermost active loop (see [`Compiler::emit_scope_cleanup`]) and
+    /// emits a `Goto` with a placeholder operand, recorded into that loop's `break_patches`/
+    /// `continue_patches` to be backpatched once its target is known.
+    fn compile_loop_jump(&mut self, name: &'static str, is_break: bool) -> CompilationResult<()> {
+        let scope_depth = match self.loop_contexts.last().and_then(|l| l.last()) {
+            Some(ctx) => ctx.scope_depth,
+            None => return Err(self.error(CompilationErrorPayload::LoopControlOutsideLoop(name))),
+        };
+        self.emit_scope_cleanup(scope_depth);
+        self.push_instruction(Instruction::Goto);
+        let idx = self.program.bytecode.len();
+        write_to_vec(0xEEFi32, &mut self.program.bytecode);
+        let ctx = self.loop_contexts.last_mut().unwrap().last_mut().unwrap();
+        if is_break {
+            ctx.break_patches.push(idx);
+        } else {
+            ctx.continue_patches.push(idx);
+        }
+        Ok(())
+    }
+
     /// add a local variable
     ///
     /// return its index
@@ -315,9 +517,16 @@ impl<'a> Compiler<'a> {
                 captured: false,
             })
             .map_err(|_| self.error(CompilationErrorPayload::TooManyLocals))?;
+        let max_locals = self.max_locals.last_mut().unwrap();
+        *max_locals = (*max_locals).max(locals.len() as u32);
         Ok(result as u32)
     }
 
+    /// `supports_tail_calls` gates whether a `Card::Call` found in the function body's tail
+    /// position gets compiled as an [`Instruction::TailCall`] instead of an ordinary
+    /// [`Instruction::CallFunction`] - see [`Compiler::compile_tail_card`] for what counts as tail
+    /// position. The main function falls off into [`Card::Abort`] rather than a `Return`, so it
+    /// has no call frame worth reusing and is always compiled with this off.
     fn process_function(
         &mut self,
         FunctionIr {
@@ -327,6 +536,7 @@ impl<'a> Compiler<'a> {
             imports,
             ..
         }: &'a FunctionIr,
+        supports_tail_calls: bool,
     ) -> CompilationResult<()> {
         self.current_namespace = Cow::Borrowed(namespace);
         self.current_imports = Cow::Borrowed(imports);
@@ -335,11 +545,100 @@ impl<'a> Compiler<'a> {
         for param in arguments.iter().rev() {
             self.add_local(param.as_str())?;
         }
+        let last_index = cards.len().saturating_sub(1);
         for (ic, card) in cards.iter().enumerate() {
             // valid indices always have 1 subindex, so replace that
             self.current_index.pop_subindex();
             self.current_index.push_subindex(ic as u32);
-            self.process_card(card)?;
+            if supports_tail_calls && ic == last_index {
+                self.compile_tail_card(card)?;
+            } else {
+                self.process_card(card)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Compiles `card` knowing it sits in the function body's tail position - the last thing
+    /// compiled before falling off the end of a function, where [`Compiler::process_function`]
+    /// would otherwise emit a plain `Return`. A `Card::Call` found here is compiled as an
+    /// [`Instruction::TailCall`] rather than [`Instruction::CallFunction`], since no enclosing
+    /// scope needs the current call frame anymore. Tail position propagates through
+    /// `Card::CompositeCard`'s last child and through every branch (and the `default`) of
+    /// `Card::Cond`, mirroring how [`Compiler::process_card`] compiles those two cards. It does
+    /// NOT propagate into loop bodies (`Card::ForEach`/`Card::While`/`Card::Repeat`), which keep
+    /// going through plain `process_card` - locals from the loop's enclosing scope are still live
+    /// there, so a call inside one is never safe to compile as a frame-reusing tail call.
+    fn compile_tail_card(&mut self, card: &'a Card) -> CompilationResult<()> {
+        // mirror process_card's node id -> bytecode offset label, since this card is compiled
+        // here instead of going through process_card
+        let card_byte_index = u32::try_from(self.program.bytecode.len())
+            .expect("Expected bytecode length to fit into 32 bits");
+        let nodeid_hash = self.current_index.as_handle();
+        self.program
+            .labels
+            .0
+            .insert(nodeid_hash, Label::new(card_byte_index))
+            .unwrap();
+        match card {
+            Card::Call(jmp) => {
+                self.compile_subexpr(&jmp.args.0)?;
+                self.push_instruction(Instruction::FunctionPointer);
+                self.encode_jump(jmp.function_name.as_str())?;
+                self.push_instruction(Instruction::TailCall);
+            }
+            Card::CompositeCard(comp) => {
+                let last_index = comp.cards.len().saturating_sub(1);
+                for (i, card) in comp.cards.iter().enumerate() {
+                    self.current_index.push_subindex(i as u32);
+                    if i == last_index {
+                        self.compile_tail_card(card)?;
+                    } else {
+                        self.process_card(card)?;
+                    }
+                    self.current_index.pop_subindex();
+                }
+            }
+            Card::Cond(cond) => {
+                let Cond {
+                    conditions,
+                    bodies,
+                    default,
+                } = cond.as_ref();
+
+                let mut end_patches = Vec::with_capacity(conditions.len());
+                for (i, (condition, body)) in conditions.iter().zip(bodies.iter()).enumerate() {
+                    self.current_index.push_subindex(i as u32);
+                    self.compile_subexpr(slice::from_ref(condition))?;
+                    self.current_index.pop_subindex();
+
+                    self.current_index
+                        .push_subindex((conditions.len() + i) as u32);
+                    self.encode_if_then(Instruction::GotoIfFalse, |c| {
+                        c.compile_tail_card(body)?;
+                        // this branch ran - skip over every remaining condition/body and the
+                        // `default`, straight to the end of the `Cond`
+                        c.push_instruction(Instruction::Goto);
+                        end_patches.push(c.program.bytecode.len());
+                        write_to_vec(0xEEFi32, &mut c.program.bytecode);
+                        Ok(())
+                    })?;
+                    self.current_index.pop_subindex();
+                }
+                if let Some(default) = default {
+                    self.current_index.push_subindex(2 * conditions.len() as u32);
+                    self.compile_tail_card(default)?;
+                    self.current_index.pop_subindex();
+                }
+                let end = self.program.bytecode.len() as i32;
+                for idx in end_patches {
+                    unsafe {
+                        let ptr = self.program.bytecode.as_mut_ptr().add(idx) as *mut i32;
+                        core::ptr::write_unaligned(ptr, end);
+                    }
+                }
+            }
+            _ => self.process_card(card)?,
         }
         Ok(())
     }
@@ -372,18 +671,38 @@ impl<'a> Compiler<'a> {
 
         unsafe {
             let ptr = self.program.bytecode.as_mut_ptr().add(idx) as *mut Pos;
-            std::ptr::write_unaligned(ptr, self.program.bytecode.len() as Pos);
+            core::ptr::write_unaligned(ptr, self.program.bytecode.len() as Pos);
         };
         Ok(())
     }
 
     fn encode_jump(&mut self, function: &str) -> CompilationResult<()> {
         let to = self.lookup_function(&self.jump_table, function)?;
-        write_to_vec(to.hash_key, &mut self.program.bytecode);
+        let hash_key = to.hash_key;
+        write_to_vec(hash_key, &mut self.program.bytecode);
         write_to_vec(to.arity, &mut self.program.bytecode);
+        // `function`'s body may not be compiled yet (forward reference), so its final
+        // `max_locals` isn't known here - write a placeholder and patch it in
+        // `patch_max_locals` once every function has been compiled.
+        let patch_offset = self.program.bytecode.len();
+        write_to_vec(0u32, &mut self.program.bytecode);
+        self.max_locals_patches.push((patch_offset, hash_key));
         Ok(())
     }
 
+    /// Fills in the `max_locals` operands [`Compiler::encode_jump`] left as placeholders for
+    /// forward-referenced functions, now that every function body - hence every entry in
+    /// `function_locals` - has been compiled.
+    fn patch_max_locals(&mut self) {
+        for (offset, handle) in mem::take(&mut self.max_locals_patches) {
+            let max_locals = self.function_locals.get(handle).copied().unwrap_or(0);
+            unsafe {
+                let ptr = self.program.bytecode.as_mut_ptr().add(offset) as *mut u32;
+                core::ptr::write_unaligned(ptr, max_locals);
+            }
+        }
+    }
+
     // take jump_table by param because of lifetimes
     fn lookup_function<'b>(
         &self,
@@ -400,7 +719,7 @@ impl<'a> Compiler<'a> {
                 .current_namespace
                 .iter()
                 .flat_map(|x| [x.as_ref(), "."])
-                .chain(std::iter::once(function))
+                .chain(core::iter::once(function))
                 .collect::<String>();
 
             to = jump_table.get(&name);
@@ -418,7 +737,7 @@ impl<'a> Compiler<'a> {
                     .iter()
                     .take(self.current_namespace.len() - super_depth)
                     .flat_map(|x| [x.as_ref(), "."])
-                    .chain(std::iter::once(suffix.unwrap_or(alias)))
+                    .chain(core::iter::once(suffix.unwrap_or(alias)))
                     .collect::<String>();
 
                 to = jump_table.get(&name);
@@ -535,6 +854,9 @@ impl<'a> Compiler<'a> {
                 self.scope_begin();
                 let loop_var = self.add_local_unchecked("")?;
                 let loop_item = self.add_local_unchecked("")?;
+                // holds the snapshot of the table's keys `BeginForEach` takes at loop entry, so
+                // the body can freely mutate the table without perturbing this loop's iteration
+                let loop_snapshot = self.add_local_unchecked("")?;
                 // ForEach instruction will push these values on the stack
                 let v = match v {
                     Some(o) => self.add_local(&o)?,
@@ -551,14 +873,21 @@ impl<'a> Compiler<'a> {
                 self.push_instruction(Instruction::BeginForEach);
                 write_to_vec(loop_var, &mut self.program.bytecode);
                 write_to_vec(loop_item, &mut self.program.bytecode);
+                write_to_vec(loop_snapshot, &mut self.program.bytecode);
 
                 let block_begin = self.program.bytecode.len() as i32;
                 self.push_instruction(Instruction::ForEach);
                 write_to_vec(loop_var, &mut self.program.bytecode);
                 write_to_vec(loop_item, &mut self.program.bytecode);
+                write_to_vec(loop_snapshot, &mut self.program.bytecode);
                 write_to_vec(i, &mut self.program.bytecode);
                 write_to_vec(k, &mut self.program.bytecode);
                 write_to_vec(v, &mut self.program.bytecode);
+                self.loop_contexts.last_mut().unwrap().push(LoopContext {
+                    scope_depth: self.scope_depth(),
+                    break_patches: Vec::new(),
+                    continue_patches: Vec::new(),
+                });
                 self.encode_if_then(Instruction::GotoIfFalse, |c| {
                     c.current_index.push_subindex(1);
                     c.process_card(body)?;
@@ -568,6 +897,20 @@ impl<'a> Compiler<'a> {
                     write_to_vec(block_begin, &mut c.program.bytecode);
                     Ok(())
                 })?;
+                let loop_ctx = self.loop_contexts.last_mut().unwrap().pop().unwrap();
+                let after_loop = self.program.bytecode.len() as i32;
+                for idx in loop_ctx.continue_patches {
+                    unsafe {
+                        let ptr = self.program.bytecode.as_mut_ptr().add(idx) as *mut i32;
+                        core::ptr::write_unaligned(ptr, block_begin);
+                    }
+                }
+                for idx in loop_ctx.break_patches {
+                    unsafe {
+                        let ptr = self.program.bytecode.as_mut_ptr().add(idx) as *mut i32;
+                        core::ptr::write_unaligned(ptr, after_loop);
+                    }
+                }
                 self.scope_end();
             }
             Card::While(children) => {
@@ -577,6 +920,11 @@ impl<'a> Compiler<'a> {
                 self.process_card(condition)?;
                 self.current_index.pop_subindex();
                 self.current_index.push_subindex(1);
+                self.loop_contexts.last_mut().unwrap().push(LoopContext {
+                    scope_depth: self.scope_depth(),
+                    break_patches: Vec::new(),
+                    continue_patches: Vec::new(),
+                });
                 // if false jump over the body block
                 self.encode_if_then(Instruction::GotoIfFalse, |c| {
                     // if true execute body and jump to block_begin
@@ -585,8 +933,67 @@ impl<'a> Compiler<'a> {
                     write_to_vec(block_begin, &mut c.program.bytecode);
                     Ok(())
                 })?;
+                let loop_ctx = self.loop_contexts.last_mut().unwrap().pop().unwrap();
+                let after_loop = self.program.bytecode.len() as i32;
+                for idx in loop_ctx.continue_patches {
+                    unsafe {
+                        let ptr = self.program.bytecode.as_mut_ptr().add(idx) as *mut i32;
+                        core::ptr::write_unaligned(ptr, block_begin);
+                    }
+                }
+                for idx in loop_ctx.break_patches {
+                    unsafe {
+                        let ptr = self.program.bytecode.as_mut_ptr().add(idx) as *mut i32;
+                        core::ptr::write_unaligned(ptr, after_loop);
+                    }
+                }
                 self.current_index.pop_subindex();
             }
+            Card::DoWhile(children) => {
+                let [body, condition] = &**children;
+                let body_start = self.program.bytecode.len() as i32;
+                self.loop_contexts.last_mut().unwrap().push(LoopContext {
+                    scope_depth: self.scope_depth(),
+                    break_patches: Vec::new(),
+                    continue_patches: Vec::new(),
+                });
+                self.current_index.push_subindex(0);
+                self.process_card(body)?;
+                self.current_index.pop_subindex();
+                // `Continue` re-checks the condition rather than jumping straight back to
+                // `body_start`, the same reason `Repeat` routes `Continue` through its counter
+                // increment: skipping the check would keep looping even once the condition has
+                // gone false
+                let condition_begin = self.program.bytecode.len() as i32;
+                let continue_patches = mem::take(
+                    &mut self
+                        .loop_contexts
+                        .last_mut()
+                        .unwrap()
+                        .last_mut()
+                        .unwrap()
+                        .continue_patches,
+                );
+                for idx in continue_patches {
+                    unsafe {
+                        let ptr = self.program.bytecode.as_mut_ptr().add(idx) as *mut i32;
+                        core::ptr::write_unaligned(ptr, condition_begin);
+                    }
+                }
+                self.current_index.push_subindex(1);
+                self.process_card(condition)?;
+                self.current_index.pop_subindex();
+                self.push_instruction(Instruction::GotoIfTrue);
+                write_to_vec(body_start, &mut self.program.bytecode);
+                let loop_ctx = self.loop_contexts.last_mut().unwrap().pop().unwrap();
+                let after_loop = self.program.bytecode.len() as i32;
+                for idx in loop_ctx.break_patches {
+                    unsafe {
+                        let ptr = self.program.bytecode.as_mut_ptr().add(idx) as *mut i32;
+                        core::ptr::write_unaligned(ptr, after_loop);
+                    }
+                }
+            }
             Card::Repeat(rep) => {
                 self.compile_subexpr(slice::from_ref(&rep.n))?;
                 let i = &rep.i;
@@ -611,6 +1018,11 @@ impl<'a> Compiler<'a> {
                 self.read_local_var(loop_counter_index);
                 self.read_local_var(loop_n_index);
                 self.push_instruction(Instruction::Less);
+                self.loop_contexts.last_mut().unwrap().push(LoopContext {
+                    scope_depth: self.scope_depth(),
+                    break_patches: Vec::new(),
+                    continue_patches: Vec::new(),
+                });
                 // loop body
                 self.encode_if_then(Instruction::GotoIfFalse, |c| {
                     if let Some(i_index) = i_index {
@@ -620,7 +1032,18 @@ impl<'a> Compiler<'a> {
                     c.current_index.push_subindex(0);
                     c.process_card(repeat)?;
                     c.current_index.pop_subindex();
-                    // i = i + 1
+                    // i = i + 1; `Continue` jumps here rather than back to `block_begin`, so it
+                    // still runs the increment instead of looping on the same counter forever
+                    let increment_begin = c.program.bytecode.len() as i32;
+                    let continue_patches = mem::take(
+                        &mut c.loop_contexts.last_mut().unwrap().last_mut().unwrap().continue_patches,
+                    );
+                    for idx in continue_patches {
+                        unsafe {
+                            let ptr = c.program.bytecode.as_mut_ptr().add(idx) as *mut i32;
+                            core::ptr::write_unaligned(ptr, increment_begin);
+                        }
+                    }
                     c.process_card(&Card::ScalarInt(1))?;
                     c.read_local_var(loop_counter_index);
                     c.push_instruction(Instruction::Add);
@@ -630,8 +1053,18 @@ impl<'a> Compiler<'a> {
                     write_to_vec(block_begin, &mut c.program.bytecode);
                     Ok(())
                 })?;
+                let loop_ctx = self.loop_contexts.last_mut().unwrap().pop().unwrap();
+                let after_loop = self.program.bytecode.len() as i32;
+                for idx in loop_ctx.break_patches {
+                    unsafe {
+                        let ptr = self.program.bytecode.as_mut_ptr().add(idx) as *mut i32;
+                        core::ptr::write_unaligned(ptr, after_loop);
+                    }
+                }
                 self.scope_end();
             }
+            Card::Break => self.compile_loop_jump("Break", true)?,
+            Card::Continue => self.compile_loop_jump("Continue", false)?,
             Card::ReadVar(variable) => {
                 self.read_var_card(variable)?;
             }
@@ -663,10 +1096,16 @@ impl<'a> Compiler<'a> {
                 self.compile_subexpr(slice::from_ref(&var.value))?;
                 self.push_instruction(Instruction::SetGlobalVar);
                 let variable = var.name.as_str();
-                let next_var = &mut self.next_var;
                 if variable.is_empty() {
-                    return Err(self.error(CompilationErrorPayload::EmptyVariable));
+                    // Recoverable: write a placeholder id so the bytecode stays well-formed and
+                    // keep compiling, so a program with several bad variable names gets all of
+                    // them reported in one pass instead of one compile-fix-recompile per name.
+                    let err = self.error(CompilationErrorPayload::EmptyVariable);
+                    self.push_error(err);
+                    write_to_vec(VariableId(0), &mut self.program.bytecode);
+                    return Ok(());
                 }
+                let next_var = &mut self.next_var;
                 let varhash = Handle::from_bytes(variable.as_bytes());
 
                 let id = self
@@ -706,7 +1145,7 @@ impl<'a> Compiler<'a> {
                 self.current_index.pop_subindex();
                 unsafe {
                     let ptr = self.program.bytecode.as_mut_ptr().add(idx) as *mut i32;
-                    std::ptr::write_unaligned(ptr, self.program.bytecode.len() as i32);
+                    core::ptr::write_unaligned(ptr, self.program.bytecode.len() as i32);
                 }
             }
             Card::IfFalse(jmp) => {
@@ -780,13 +1219,17 @@ impl<'a> Compiler<'a> {
                 }
                 self.compile_subexpr(&embedded_function.cards)?;
                 self.scope_end();
+                let max_locals = *self.max_locals.last().unwrap();
+                self.function_locals
+                    .insert(function_handle, max_locals)
+                    .unwrap();
                 self.push_instruction(Instruction::ScalarNil);
                 self.emit_return()?;
 
                 // finish the goto that jumps over the inner function
                 unsafe {
                     let ptr = self.program.bytecode.as_mut_ptr().add(goto_index) as *mut i32;
-                    std::ptr::write_unaligned(ptr, self.program.bytecode.len() as i32);
+                    core::ptr::write_unaligned(ptr, self.program.bytecode.len() as i32);
                 }
 
                 // finally, push the closure instruction
@@ -794,7 +1237,8 @@ impl<'a> Compiler<'a> {
                 self.push_instruction(Instruction::Closure);
                 write_to_vec(function_handle, &mut self.program.bytecode);
                 write_to_vec(arity, &mut self.program.bytecode);
-                let upvalues = std::mem::take(&mut self.upvalues[self.function_id]);
+                write_to_vec(max_locals, &mut self.program.bytecode);
+                let upvalues = mem::take(&mut self.upvalues[self.function_id]);
                 for upvalue in upvalues {
                     self.push_instruction(Instruction::CopyLast);
                     self.push_instruction(Instruction::RegisterUpvalue);
@@ -811,6 +1255,13 @@ impl<'a> Compiler<'a> {
                 // create a table, then for each sub-card: insert the subcard and append it to the
                 // result
                 // finally: ensure the result is on the stack
+                //
+                // `table_var` is scoped to just this card (like `Card::Map`/`Filter`/`Reduce`'s own
+                // `out`), so its slot is freed for reuse by the next sibling card instead of sitting
+                // reserved for the rest of the enclosing block - otherwise a sequence of array
+                // literals (or nested arrays) would each claim a fresh slot and needlessly inflate
+                // the function's frame size.
+                self.scope_begin();
                 self.push_instruction(Instruction::InitTable);
                 let table_var = self.add_local_unchecked("")?;
                 self.write_local_var(table_var);
@@ -826,6 +1277,7 @@ impl<'a> Compiler<'a> {
                 }
                 // push the table to the stack
                 self.read_local_var(table_var);
+                self.scope_end();
             }
             Card::Len(expr) => {
                 self.compile_subexpr(slice::from_ref(expr.card.as_ref()))?;
@@ -844,12 +1296,36 @@ impl<'a> Compiler<'a> {
                 self.push_instruction(Instruction::NthRow);
             }
             Card::And(expr) => {
-                self.compile_subexpr(expr.as_ref())?;
-                self.push_instruction(Instruction::And);
+                // short-circuit: evaluate `a`, and only evaluate (side-effecting) `b` if `a` was
+                // truthy - `a` falsy leaves `a` itself on the stack as the result, the same as `&&`
+                let [a, b] = expr.as_ref();
+                self.current_index.push_subindex(0);
+                self.process_card(a)?;
+                self.current_index.pop_subindex();
+                self.push_instruction(Instruction::CopyLast);
+                self.encode_if_then(Instruction::GotoIfFalse, |c| {
+                    c.push_instruction(Instruction::Pop);
+                    c.current_index.push_subindex(1);
+                    c.process_card(b)?;
+                    c.current_index.pop_subindex();
+                    Ok(())
+                })?;
             }
             Card::Or(expr) => {
-                self.compile_subexpr(expr.as_ref())?;
-                self.push_instruction(Instruction::Or);
+                // short-circuit: evaluate `a`, and only evaluate (side-effecting) `b` if `a` was
+                // falsy - `a` truthy leaves `a` itself on the stack as the result, the same as `||`
+                let [a, b] = expr.as_ref();
+                self.current_index.push_subindex(0);
+                self.process_card(a)?;
+                self.current_index.pop_subindex();
+                self.push_instruction(Instruction::CopyLast);
+                self.encode_if_then(Instruction::GotoIfTrue, |c| {
+                    c.push_instruction(Instruction::Pop);
+                    c.current_index.push_subindex(1);
+                    c.process_card(b)?;
+                    c.current_index.pop_subindex();
+                    Ok(())
+                })?;
             }
             Card::Xor(expr) => {
                 self.compile_subexpr(expr.as_ref())?;
@@ -887,6 +1363,70 @@ impl<'a> Compiler<'a> {
                 self.compile_subexpr(expr.as_ref())?;
                 self.push_instruction(Instruction::Div);
             }
+            Card::Mod(expr) => {
+                self.compile_subexpr(expr.as_ref())?;
+                self.push_instruction(Instruction::Mod);
+            }
+            Card::Pow(expr) => {
+                self.compile_subexpr(expr.as_ref())?;
+                self.push_instruction(Instruction::Pow);
+            }
+            Card::Min(expr) => {
+                self.compile_subexpr(expr.as_ref())?;
+                self.push_instruction(Instruction::Min);
+            }
+            Card::Max(expr) => {
+                self.compile_subexpr(expr.as_ref())?;
+                self.push_instruction(Instruction::Max);
+            }
+            Card::Random(expr) => {
+                self.compile_subexpr(expr.as_ref())?;
+                self.push_instruction(Instruction::Random);
+            }
+            Card::DiceRoll(expr) => {
+                self.compile_subexpr(expr.as_ref())?;
+                self.push_instruction(Instruction::DiceRoll);
+            }
+            Card::BitAnd(expr) => {
+                self.compile_subexpr(expr.as_ref())?;
+                self.push_instruction(Instruction::BitAnd);
+            }
+            Card::BitOr(expr) => {
+                self.compile_subexpr(expr.as_ref())?;
+                self.push_instruction(Instruction::BitOr);
+            }
+            Card::BitXor(expr) => {
+                self.compile_subexpr(expr.as_ref())?;
+                self.push_instruction(Instruction::BitXor);
+            }
+            Card::Shl(expr) => {
+                self.compile_subexpr(expr.as_ref())?;
+                self.push_instruction(Instruction::Shl);
+            }
+            Card::Shr(expr) => {
+                self.compile_subexpr(expr.as_ref())?;
+                self.push_instruction(Instruction::Shr);
+            }
+            Card::Neg(expr) => {
+                self.compile_subexpr(slice::from_ref(expr.card.as_ref()))?;
+                self.push_instruction(Instruction::Neg);
+            }
+            Card::Abs(expr) => {
+                self.compile_subexpr(slice::from_ref(expr.card.as_ref()))?;
+                self.push_instruction(Instruction::Abs);
+            }
+            Card::Floor(expr) => {
+                self.compile_subexpr(slice::from_ref(expr.card.as_ref()))?;
+                self.push_instruction(Instruction::Floor);
+            }
+            Card::Ceil(expr) => {
+                self.compile_subexpr(slice::from_ref(expr.card.as_ref()))?;
+                self.push_instruction(Instruction::Ceil);
+            }
+            Card::Round(expr) => {
+                self.compile_subexpr(slice::from_ref(expr.card.as_ref()))?;
+                self.push_instruction(Instruction::Round);
+            }
             Card::GetProperty(expr) => {
                 self.compile_subexpr(expr.as_ref())?;
                 self.push_instruction(Instruction::GetProperty);
@@ -919,6 +1459,546 @@ impl<'a> Compiler<'a> {
             Card::CreateTable => {
                 self.push_instruction(Instruction::InitTable);
             }
+            Card::Map(m) => {
+                // `CreateTable` + `ForEach` that `AppendTable`s `mapper(value)` into the result;
+                // an empty iterable yields an empty table.
+                self.scope_begin();
+                self.push_instruction(Instruction::InitTable);
+                let out = self.add_local_unchecked("")?;
+                self.write_local_var(out);
+
+                self.current_index.push_subindex(0);
+                self.process_card(&m.iterable)?;
+                self.current_index.pop_subindex();
+
+                let loop_var = self.add_local_unchecked("")?;
+                let loop_item = self.add_local_unchecked("")?;
+                let loop_snapshot = self.add_local_unchecked("")?;
+                let value = self.add_local_unchecked("")?;
+                let key = self.add_local_unchecked("")?;
+                let index = self.add_local_unchecked("")?;
+                self.push_instruction(Instruction::BeginForEach);
+                write_to_vec(loop_var, &mut self.program.bytecode);
+                write_to_vec(loop_item, &mut self.program.bytecode);
+                write_to_vec(loop_snapshot, &mut self.program.bytecode);
+
+                let block_begin = self.program.bytecode.len() as i32;
+                self.push_instruction(Instruction::ForEach);
+                write_to_vec(loop_var, &mut self.program.bytecode);
+                write_to_vec(loop_item, &mut self.program.bytecode);
+                write_to_vec(loop_snapshot, &mut self.program.bytecode);
+                write_to_vec(index, &mut self.program.bytecode);
+                write_to_vec(key, &mut self.program.bytecode);
+                write_to_vec(value, &mut self.program.bytecode);
+                self.encode_if_then(Instruction::GotoIfFalse, |c| {
+                    // push mapper(value), then append it to the result table
+                    c.read_local_var(value);
+                    c.current_index.push_subindex(1);
+                    c.process_card(&m.mapper)?;
+                    c.current_index.pop_subindex();
+                    c.push_instruction(Instruction::CallFunction);
+                    c.read_local_var(out);
+                    c.push_instruction(Instruction::AppendTable);
+                    c.push_instruction(Instruction::Goto);
+                    write_to_vec(block_begin, &mut c.program.bytecode);
+                    Ok(())
+                })?;
+                self.read_local_var(out);
+                self.scope_end();
+            }
+            Card::Filter(f) => {
+                // Same `CreateTable` + `ForEach` skeleton as `Map`, but only `AppendTable`s
+                // values for which `predicate(value)` is truthy.
+                self.scope_begin();
+                self.push_instruction(Instruction::InitTable);
+                let out = self.add_local_unchecked("")?;
+                self.write_local_var(out);
+
+                self.current_index.push_subindex(0);
+                self.process_card(&f.iterable)?;
+                self.current_index.pop_subindex();
+
+                let loop_var = self.add_local_unchecked("")?;
+                let loop_item = self.add_local_unchecked("")?;
+                let loop_snapshot = self.add_local_unchecked("")?;
+                let value = self.add_local_unchecked("")?;
+                let key = self.add_local_unchecked("")?;
+                let index = self.add_local_unchecked("")?;
+                self.push_instruction(Instruction::BeginForEach);
+                write_to_vec(loop_var, &mut self.program.bytecode);
+                write_to_vec(loop_item, &mut self.program.bytecode);
+                write_to_vec(loop_snapshot, &mut self.program.bytecode);
+
+                let block_begin = self.program.bytecode.len() as i32;
+                self.push_instruction(Instruction::ForEach);
+                write_to_vec(loop_var, &mut self.program.bytecode);
+                write_to_vec(loop_item, &mut self.program.bytecode);
+                write_to_vec(loop_snapshot, &mut self.program.bytecode);
+                write_to_vec(index, &mut self.program.bytecode);
+                write_to_vec(key, &mut self.program.bytecode);
+                write_to_vec(value, &mut self.program.bytecode);
+                self.encode_if_then(Instruction::GotoIfFalse, |c| {
+                    c.read_local_var(value);
+                    c.current_index.push_subindex(1);
+                    c.process_card(&f.predicate)?;
+                    c.current_index.pop_subindex();
+                    c.push_instruction(Instruction::CallFunction);
+                    c.encode_if_then(Instruction::GotoIfFalse, |c| {
+                        c.read_local_var(value);
+                        c.read_local_var(out);
+                        c.push_instruction(Instruction::AppendTable);
+                        Ok(())
+                    })?;
+                    c.push_instruction(Instruction::Goto);
+                    write_to_vec(block_begin, &mut c.program.bytecode);
+                    Ok(())
+                })?;
+                self.read_local_var(out);
+                self.scope_end();
+            }
+            Card::Reduce(r) => {
+                // `SetVar(acc, init)` followed by a `ForEach` that reassigns
+                // `acc = reducer(acc, value)`; an empty iterable leaves `acc` at `init`.
+                self.scope_begin();
+                self.current_index.push_subindex(1);
+                self.process_card(&r.init)?;
+                self.current_index.pop_subindex();
+                let acc = self.add_local_unchecked("")?;
+                self.write_local_var(acc);
+
+                self.current_index.push_subindex(0);
+                self.process_card(&r.iterable)?;
+                self.current_index.pop_subindex();
+
+                let loop_var = self.add_local_unchecked("")?;
+                let loop_item = self.add_local_unchecked("")?;
+                let loop_snapshot = self.add_local_unchecked("")?;
+                let value = self.add_local_unchecked("")?;
+                let key = self.add_local_unchecked("")?;
+                let index = self.add_local_unchecked("")?;
+                self.push_instruction(Instruction::BeginForEach);
+                write_to_vec(loop_var, &mut self.program.bytecode);
+                write_to_vec(loop_item, &mut self.program.bytecode);
+                write_to_vec(loop_snapshot, &mut self.program.bytecode);
+
+                let block_begin = self.program.bytecode.len() as i32;
+                self.push_instruction(Instruction::ForEach);
+                write_to_vec(loop_var, &mut self.program.bytecode);
+                write_to_vec(loop_item, &mut self.program.bytecode);
+                write_to_vec(loop_snapshot, &mut self.program.bytecode);
+                write_to_vec(index, &mut self.program.bytecode);
+                write_to_vec(key, &mut self.program.bytecode);
+                write_to_vec(value, &mut self.program.bytecode);
+                self.encode_if_then(Instruction::GotoIfFalse, |c| {
+                    c.read_local_var(acc);
+                    c.read_local_var(value);
+                    c.current_index.push_subindex(2);
+                    c.process_card(&r.reducer)?;
+                    c.current_index.pop_subindex();
+                    c.push_instruction(Instruction::CallFunction);
+                    c.write_local_var(acc);
+                    c.push_instruction(Instruction::Goto);
+                    write_to_vec(block_begin, &mut c.program.bytecode);
+                    Ok(())
+                })?;
+                self.read_local_var(acc);
+                self.scope_end();
+            }
+            Card::Zip(pair) => {
+                // Pairs `a` and `b` row-by-row into a table of 2-element rows, truncating to
+                // the shorter table.
+                let [a, b] = pair.as_ref();
+                self.scope_begin();
+                self.push_instruction(Instruction::InitTable);
+                let out = self.add_local_unchecked("")?;
+                self.write_local_var(out);
+
+                self.current_index.push_subindex(0);
+                self.process_card(a)?;
+                self.current_index.pop_subindex();
+                let a_var = self.add_local_unchecked("")?;
+                self.write_local_var(a_var);
+
+                self.current_index.push_subindex(1);
+                self.process_card(b)?;
+                self.current_index.pop_subindex();
+                let b_var = self.add_local_unchecked("")?;
+                self.write_local_var(b_var);
+
+                self.read_local_var(a_var);
+                self.push_instruction(Instruction::Len);
+                self.read_local_var(b_var);
+                self.push_instruction(Instruction::Len);
+                self.push_instruction(Instruction::Less);
+                let a_shorter = self.add_local_unchecked("")?;
+                self.write_local_var(a_shorter);
+
+                let len = self.add_local_unchecked("")?;
+                self.read_local_var(a_shorter);
+                self.encode_if_then(Instruction::GotoIfFalse, |c| {
+                    c.read_local_var(b_var);
+                    c.push_instruction(Instruction::Len);
+                    c.write_local_var(len);
+                    Ok(())
+                })?;
+                self.read_local_var(a_shorter);
+                self.encode_if_then(Instruction::GotoIfTrue, |c| {
+                    c.read_local_var(a_var);
+                    c.push_instruction(Instruction::Len);
+                    c.write_local_var(len);
+                    Ok(())
+                })?;
+
+                // i = 0
+                self.process_card(&Card::ScalarInt(0))?;
+                let i = self.add_local_unchecked("")?;
+                self.write_local_var(i);
+
+                let block_begin = self.program.bytecode.len() as i32;
+                self.read_local_var(i);
+                self.read_local_var(len);
+                self.push_instruction(Instruction::Less);
+                self.encode_if_then(Instruction::GotoIfFalse, |c| {
+                    c.push_instruction(Instruction::InitTable);
+                    let row = c.add_local_unchecked("")?;
+                    c.write_local_var(row);
+                    c.read_local_var(a_var);
+                    c.read_local_var(i);
+                    c.push_instruction(Instruction::NthRow);
+                    c.read_local_var(row);
+                    c.push_instruction(Instruction::AppendTable);
+                    c.read_local_var(b_var);
+                    c.read_local_var(i);
+                    c.push_instruction(Instruction::NthRow);
+                    c.read_local_var(row);
+                    c.push_instruction(Instruction::AppendTable);
+                    c.read_local_var(row);
+                    c.read_local_var(out);
+                    c.push_instruction(Instruction::AppendTable);
+
+                    c.process_card(&Card::ScalarInt(1))?;
+                    c.read_local_var(i);
+                    c.push_instruction(Instruction::Add);
+                    c.write_local_var(i);
+
+                    c.push_instruction(Instruction::Goto);
+                    write_to_vec(block_begin, &mut c.program.bytecode);
+                    Ok(())
+                })?;
+                self.read_local_var(out);
+                self.scope_end();
+            }
+            Card::Enumerate(expr) => {
+                // Same skeleton as `Map`, appending `[index, value]` rows instead of a mapped
+                // value.
+                self.scope_begin();
+                self.push_instruction(Instruction::InitTable);
+                let out = self.add_local_unchecked("")?;
+                self.write_local_var(out);
+
+                self.current_index.push_subindex(0);
+                self.process_card(expr.card.as_ref())?;
+                self.current_index.pop_subindex();
+
+                let loop_var = self.add_local_unchecked("")?;
+                let loop_item = self.add_local_unchecked("")?;
+                let loop_snapshot = self.add_local_unchecked("")?;
+                let value = self.add_local_unchecked("")?;
+                let key = self.add_local_unchecked("")?;
+                let index = self.add_local_unchecked("")?;
+                self.push_instruction(Instruction::BeginForEach);
+                write_to_vec(loop_var, &mut self.program.bytecode);
+                write_to_vec(loop_item, &mut self.program.bytecode);
+                write_to_vec(loop_snapshot, &mut self.program.bytecode);
+
+                let block_begin = self.program.bytecode.len() as i32;
+                self.push_instruction(Instruction::ForEach);
+                write_to_vec(loop_var, &mut self.program.bytecode);
+                write_to_vec(loop_item, &mut self.program.bytecode);
+                write_to_vec(loop_snapshot, &mut self.program.bytecode);
+                write_to_vec(index, &mut self.program.bytecode);
+                write_to_vec(key, &mut self.program.bytecode);
+                write_to_vec(value, &mut self.program.bytecode);
+                self.encode_if_then(Instruction::GotoIfFalse, |c| {
+                    c.push_instruction(Instruction::InitTable);
+                    let row = c.add_local_unchecked("")?;
+                    c.write_local_var(row);
+                    c.read_local_var(index);
+                    c.read_local_var(row);
+                    c.push_instruction(Instruction::AppendTable);
+                    c.read_local_var(value);
+                    c.read_local_var(row);
+                    c.push_instruction(Instruction::AppendTable);
+                    c.read_local_var(row);
+                    c.read_local_var(out);
+                    c.push_instruction(Instruction::AppendTable);
+                    c.push_instruction(Instruction::Goto);
+                    write_to_vec(block_begin, &mut c.program.bytecode);
+                    Ok(())
+                })?;
+                self.read_local_var(out);
+                self.scope_end();
+            }
+            Card::Try(trycatch) => {
+                let TryCatch {
+                    body,
+                    handler,
+                    catch_var,
+                } = trycatch.as_ref();
+
+                self.push_instruction(Instruction::PushHandler);
+                let handler_patch = self.program.bytecode.len();
+                write_to_vec(0xEEFi32, &mut self.program.bytecode);
+
+                for (i, card) in body.iter().enumerate() {
+                    self.current_index.push_subindex(i as u32);
+                    self.process_card(card)?;
+                    self.current_index.pop_subindex();
+                }
+                self.push_instruction(Instruction::PopHandler);
+                // on normal completion, skip straight over the handler
+                self.push_instruction(Instruction::Goto);
+                let skip_patch = self.program.bytecode.len();
+                write_to_vec(0xEEFi32, &mut self.program.bytecode);
+
+                unsafe {
+                    let ptr = self.program.bytecode.as_mut_ptr().add(handler_patch) as *mut i32;
+                    core::ptr::write_unaligned(ptr, self.program.bytecode.len() as i32);
+                }
+                // the caught value is on top of the stack here (see `Vm::unwind_to_handler`);
+                // bind it to `catch_var`, scoped to `handler` exactly like `Repeat`/`ForEach`
+                // scope their own loop locals.
+                self.scope_begin();
+                let catch_var_index = match catch_var {
+                    Some(name) => self.add_local(name)?,
+                    None => self.add_local_unchecked("")?,
+                };
+                self.write_local_var(catch_var_index);
+                for (i, card) in handler.iter().enumerate() {
+                    self.current_index.push_subindex((body.len() + i) as u32);
+                    self.process_card(card)?;
+                    self.current_index.pop_subindex();
+                }
+                self.scope_end();
+
+                unsafe {
+                    let ptr = self.program.bytecode.as_mut_ptr().add(skip_patch) as *mut i32;
+                    core::ptr::write_unaligned(ptr, self.program.bytecode.len() as i32);
+                }
+            }
+            Card::Throw(expr) => {
+                self.compile_subexpr(slice::from_ref(expr.card.as_ref()))?;
+                self.push_instruction(Instruction::Throw);
+            }
+            Card::Cond(cond) => {
+                let Cond {
+                    conditions,
+                    bodies,
+                    default,
+                } = cond.as_ref();
+
+                let mut end_patches = Vec::with_capacity(conditions.len());
+                for (i, (condition, body)) in conditions.iter().zip(bodies.iter()).enumerate() {
+                    self.current_index.push_subindex(i as u32);
+                    self.compile_subexpr(slice::from_ref(condition))?;
+                    self.current_index.pop_subindex();
+
+                    self.current_index
+                        .push_subindex((conditions.len() + i) as u32);
+                    self.encode_if_then(Instruction::GotoIfFalse, |c| {
+                        c.process_card(body)?;
+                        // this branch ran - skip over every remaining condition/body and the
+                        // `default`, straight to the end of the `Cond`
+                        c.push_instruction(Instruction::Goto);
+                        end_patches.push(c.program.bytecode.len());
+                        write_to_vec(0xEEFi32, &mut c.program.bytecode);
+                        Ok(())
+                    })?;
+                    self.current_index.pop_subindex();
+                }
+                if let Some(default) = default {
+                    self.current_index.push_subindex(2 * conditions.len() as u32);
+                    self.process_card(default)?;
+                    self.current_index.pop_subindex();
+                }
+                let end = self.program.bytecode.len() as i32;
+                for idx in end_patches {
+                    unsafe {
+                        let ptr = self.program.bytecode.as_mut_ptr().add(idx) as *mut i32;
+                        core::ptr::write_unaligned(ptr, end);
+                    }
+                }
+            }
+            Card::Switch(sw) => {
+                let Switch {
+                    value,
+                    keys,
+                    bodies,
+                    default,
+                } = sw.as_ref();
+
+                self.current_index.push_subindex(0);
+                self.compile_subexpr(slice::from_ref(value.as_ref()))?;
+                self.current_index.pop_subindex();
+
+                let scrutinee = self.add_local_unchecked("")?;
+                self.write_local_var(scrutinee);
+
+                if switch_is_dense(keys) {
+                    self.compile_dense_switch(scrutinee, keys, bodies, default.as_ref())?;
+                } else {
+                    self.compile_sparse_switch(scrutinee, keys, bodies, default.as_ref())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Dense-key path of `Card::Switch`'s `process_card` arm: builds an
+    /// [`Instruction::Switch`] jump table out-of-band in `program.data` (mirroring how
+    /// [`Compiler::push_str`] stores [`Instruction::StringLiteral`]'s payload there), rather than
+    /// writing the table inline after the instruction - this codebase's instruction operands are
+    /// always a fixed width per opcode (see `instructions.in`), so a variable-length table can't
+    /// live in `program.bytecode` itself. Each arm (and the `default`) gets its own
+    /// `scope_begin`/`scope_end` and ends with a `Goto` to a shared exit, backpatched once every
+    /// arm has been compiled, the same way [`Compiler::process_card`]'s `Card::Cond` arm closes
+    /// out its branches.
+    fn compile_dense_switch(
+        &mut self,
+        scrutinee: u32,
+        keys: &'a [i64],
+        bodies: &'a [Card],
+        default: Option<&'a Card>,
+    ) -> CompilationResult<()> {
+        let min = *keys.iter().min().expect("checked non-empty by switch_is_dense");
+        let max = *keys.iter().max().expect("checked non-empty by switch_is_dense");
+        let len = (max - min + 1) as u32;
+
+        self.read_local_var(scrutinee);
+        self.push_instruction(Instruction::Switch);
+        let data_offset = self.program.data.len() as u32;
+        write_to_vec(data_offset, &mut self.program.bytecode);
+
+        write_to_vec(min, &mut self.program.data);
+        let default_patch = self.program.data.len();
+        write_to_vec(0xEEFi32, &mut self.program.data);
+        write_to_vec(len, &mut self.program.data);
+        let table_start = self.program.data.len();
+        for _ in 0..len {
+            write_to_vec(0xEEFi32, &mut self.program.data);
+        }
+
+        let mut end_patches = Vec::with_capacity(bodies.len() + 1);
+        let mut arm_starts = Vec::with_capacity(bodies.len());
+        for (i, (key, body)) in keys.iter().zip(bodies.iter()).enumerate() {
+            self.current_index.push_subindex(1 + i as u32);
+            self.scope_begin();
+            let start = self.program.bytecode.len() as i32;
+            self.process_card(body)?;
+            self.scope_end();
+            self.push_instruction(Instruction::Goto);
+            end_patches.push(self.program.bytecode.len());
+            write_to_vec(0xEEFi32, &mut self.program.bytecode);
+            self.current_index.pop_subindex();
+            arm_starts.push((*key, start));
+        }
+
+        let default_target = if let Some(default) = default {
+            self.current_index.push_subindex(1 + keys.len() as u32);
+            self.scope_begin();
+            let start = self.program.bytecode.len() as i32;
+            self.process_card(default)?;
+            self.scope_end();
+            self.push_instruction(Instruction::Goto);
+            end_patches.push(self.program.bytecode.len());
+            write_to_vec(0xEEFi32, &mut self.program.bytecode);
+            self.current_index.pop_subindex();
+            start
+        } else {
+            // patched below, once `end` is known - the table's gaps and "no default" share the
+            // same fallback target.
+            -1
+        };
+
+        let end = self.program.bytecode.len() as i32;
+        for idx in end_patches {
+            unsafe {
+                let ptr = self.program.bytecode.as_mut_ptr().add(idx) as *mut i32;
+                core::ptr::write_unaligned(ptr, end);
+            }
+        }
+        let default_target = if default.is_some() { default_target } else { end };
+
+        unsafe {
+            let ptr = self.program.data.as_mut_ptr().add(default_patch) as *mut i32;
+            core::ptr::write_unaligned(ptr, default_target);
+        }
+        for index in 0..len as usize {
+            unsafe {
+                let ptr = self
+                    .program
+                    .data
+                    .as_mut_ptr()
+                    .add(table_start + index * mem::size_of::<i32>()) as *mut i32;
+                core::ptr::write_unaligned(ptr, default_target);
+            }
+        }
+        for (key, start) in arm_starts {
+            let index = (key - min) as usize;
+            unsafe {
+                let ptr = self
+                    .program
+                    .data
+                    .as_mut_ptr()
+                    .add(table_start + index * mem::size_of::<i32>()) as *mut i32;
+                core::ptr::write_unaligned(ptr, start);
+            }
+        }
+        Ok(())
+    }
+
+    /// Sparse-key fallback of `Card::Switch`'s `process_card` arm: a chain of `scrutinee == key`
+    /// equality comparisons via [`Compiler::encode_if_then`], the same shape as `Card::Cond`'s
+    /// arm - used instead of [`Compiler::compile_dense_switch`] whenever the keys are too spread
+    /// out for a jump table to be worth the `program.data` bookkeeping (see `switch_is_dense`).
+    fn compile_sparse_switch(
+        &mut self,
+        scrutinee: u32,
+        keys: &'a [i64],
+        bodies: &'a [Card],
+        default: Option<&'a Card>,
+    ) -> CompilationResult<()> {
+        let mut end_patches = Vec::with_capacity(bodies.len());
+        for (i, (key, body)) in keys.iter().zip(bodies.iter()).enumerate() {
+            self.current_index.push_subindex(1 + i as u32);
+            self.read_local_var(scrutinee);
+            self.push_instruction(Instruction::ScalarInt);
+            write_to_vec(*key, &mut self.program.bytecode);
+            self.push_instruction(Instruction::Equals);
+            self.encode_if_then(Instruction::GotoIfFalse, |c| {
+                c.scope_begin();
+                c.process_card(body)?;
+                c.scope_end();
+                c.push_instruction(Instruction::Goto);
+                end_patches.push(c.program.bytecode.len());
+                write_to_vec(0xEEFi32, &mut c.program.bytecode);
+                Ok(())
+            })?;
+            self.current_index.pop_subindex();
+        }
+        if let Some(default) = default {
+            self.current_index.push_subindex(1 + keys.len() as u32);
+            self.scope_begin();
+            self.process_card(default)?;
+            self.scope_end();
+            self.current_index.pop_subindex();
+        }
+        let end = self.program.bytecode.len() as i32;
+        for idx in end_patches {
+            unsafe {
+                let ptr = self.program.bytecode.as_mut_ptr().add(idx) as *mut i32;
+                core::ptr::write_unaligned(ptr, end);
+            }
         }
         Ok(())
     }
@@ -1016,6 +2096,23 @@ impl<'a> Compiler<'a> {
     }
 }
 
+/// Is `keys` dense enough that [`Compiler::compile_dense_switch`]'s jump table beats
+/// [`Compiler::compile_sparse_switch`]'s equality chain? Allows at most 2 table slots per
+/// populated key, so a couple of far-apart keys still fall back to the chain instead of padding
+/// `program.data` with a mostly-empty table, and caps the table at 4096 slots outright so a
+/// pathological `i64::MIN..i64::MAX` spread can't blow up compile-time memory.
+fn switch_is_dense(keys: &[i64]) -> bool {
+    if keys.is_empty() {
+        return false;
+    }
+    let min = *keys.iter().min().unwrap();
+    let max = *keys.iter().max().unwrap();
+    let Some(span) = max.checked_sub(min).and_then(|d| d.checked_add(1)) else {
+        return false;
+    };
+    span > 0 && span <= (keys.len() as i64).saturating_mul(2) && span <= 4096
+}
+
 fn super_depth(import: &str) -> (usize, Option<&str>) {
     let mut super_pog = import.split_once("super.");
     let mut super_cnt = 0;
@@ -1,4 +1,8 @@
-use std::sync::atomic::AtomicU64;
+use crate::alloc_crate::{boxed::Box, collections::BTreeSet};
+use core::sync::atomic::AtomicU64;
+
+use smallvec::SmallVec;
+use thiserror::Error;
 
 use super::*;
 use crate::InputString;
@@ -11,7 +15,11 @@ pub struct CardId(pub u64);
 #[derive(Default, Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Card {
-    #[cfg_attr(feature = "serde", serde(skip, default = "random_id"))]
+    /// Stable across save/load cycles: external structures (selection state, breakpoints, undo
+    /// history) may reference a card by id, so a loaded program must run [`Card::dedup_ids`]
+    /// rather than quietly handing out fresh ones. Old saves without an id still load, falling
+    /// back to a fresh one.
+    #[cfg_attr(feature = "serde", serde(default = "random_id"))]
     pub id: CardId,
     #[cfg_attr(feature = "serde", serde(flatten))]
     pub body: CardBody,
@@ -20,7 +28,12 @@ pub struct Card {
 static NEXT_ID: AtomicU64 = AtomicU64::new(1);
 
 fn random_id() -> CardId {
-    CardId(NEXT_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed))
+    CardId(NEXT_ID.fetch_add(1, core::sync::atomic::Ordering::Relaxed))
+}
+
+/// Advances the global id counter so that it never hands out an id `<= id`.
+fn advance_next_id_past(id: CardId) {
+    NEXT_ID.fetch_max(id.0 + 1, core::sync::atomic::Ordering::Relaxed);
 }
 
 impl From<CardBody> for Card {
@@ -85,6 +98,11 @@ pub enum CardBody {
     Repeat(Box<Repeat>),
     /// Children = [condition, body]
     While(Box<[Card; 2]>),
+    /// Like `While`, but `body` always runs once before `condition` is checked for the first
+    /// time. Children = [body, condition]
+    DoWhile(Box<[Card; 2]>),
+    /// Iterates `iterable` (a table), binding each pass's index/key/value to the given locals (any
+    /// of which may be omitted) before running `body`. See [`ForEach`].
     ForEach(Box<ForEach>),
     /// Single card that decomposes into multiple cards
     CompositeCard(Box<CompositeCard>),
@@ -100,6 +118,70 @@ pub enum CardBody {
     Array(Vec<Card>),
     Closure(Box<Function>),
     Comment(String),
+    /// Apply `mapper` to each value of `iterable`, collecting the results into a new table
+    Map(Box<Map>),
+    /// Keep the entries of `iterable` for which `predicate` is truthy
+    Filter(Box<Filter>),
+    /// Fold `iterable` with `reducer`, starting from `init`
+    Reduce(Box<Reduce>),
+    /// Children = [a, b]; pairs the two tables into a table of 2-element rows, truncated to the
+    /// shorter one
+    Zip(Box<[Card; 2]>),
+    /// Yields `[index, value]` rows of the given table
+    Enumerate(UnaryExpression),
+    /// Remainder of `lhs` divided by `rhs`
+    Mod(BinaryExpression),
+    /// `lhs` raised to the power of `rhs`
+    Pow(BinaryExpression),
+    /// Negate a number
+    Neg(UnaryExpression),
+    /// Absolute value of a number
+    Abs(UnaryExpression),
+    /// The smaller of `lhs` and `rhs`
+    Min(BinaryExpression),
+    /// The larger of `lhs` and `rhs`
+    Max(BinaryExpression),
+    /// Round a number down to the nearest integer
+    Floor(UnaryExpression),
+    /// Round a number up to the nearest integer
+    Ceil(UnaryExpression),
+    /// Round a number to the nearest integer
+    Round(UnaryExpression),
+    /// A uniform random integer in the inclusive range `[lhs, rhs]`, drawn from the VM's seeded
+    /// xorshift64 generator (see [`crate::vm::runtime::RuntimeData::next_random_range`]) -
+    /// reproducible across replays started from the same seed.
+    Random(BinaryExpression),
+    /// Sum of `lhs` independent rolls of `1..=rhs`, e.g. a `3d6` expression (`lhs` = 3, `rhs` = 6)
+    DiceRoll(BinaryExpression),
+    /// Bitwise AND of two integers
+    BitAnd(BinaryExpression),
+    /// Bitwise OR of two integers
+    BitOr(BinaryExpression),
+    /// Bitwise XOR of two integers
+    BitXor(BinaryExpression),
+    /// Left shift of `lhs` by `rhs`
+    Shl(BinaryExpression),
+    /// Arithmetic right shift of `lhs` by `rhs`
+    Shr(BinaryExpression),
+    /// Runs `body`; a fault raised by one of its cards (or a nested `Throw`) unwinds to
+    /// `handler` instead of aborting the program. Children = [body..., handler...]
+    Try(Box<TryCatch>),
+    /// Raises `value`, unwinding to the nearest enclosing `Try`'s `handler` - or aborting the
+    /// program if no `Try` is currently active
+    Throw(UnaryExpression),
+    /// `conditions[i]`/`bodies[i]` are tried in order; the first truthy condition runs its body
+    /// and skips the rest. `default` runs if every condition was falsy, like `IfTrue`/`IfFalse`'s
+    /// missing branch. Equivalent to nesting one `IfElse` inside the previous one's `else`, but as
+    /// a single card instead of a right-leaning tree, so an if/else-if/.../else chain round-trips
+    /// as one node with an ordered list of branches.
+    Cond(Box<Cond>),
+    /// Dispatches on `value`'s integer result to the one matching arm of `keys`/`bodies`, or
+    /// `default` if none match. See [`Switch`].
+    Switch(Box<Switch>),
+    /// Jumps out of the nearest enclosing `ForEach`/`While`/`Repeat` loop
+    Break,
+    /// Jumps back to the nearest enclosing `ForEach`/`While`/`Repeat` loop's condition check
+    Continue,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -153,52 +235,138 @@ impl From<ForEach> for Card {
     }
 }
 
+/// Applies `mapper` to each value of `iterable`, collecting the results into a new table.
+/// `mapper` is either a `Function`/`NativeFunction` pointer (invoked via `DynamicCall`) or an
+/// inline `Closure`, taking the value as its single argument.
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Map {
+    pub iterable: Box<Card>,
+    pub mapper: Box<Card>,
+}
+
+impl From<Map> for Card {
+    fn from(value: Map) -> Self {
+        CardBody::Map(Box::new(value)).into()
+    }
+}
+
+/// Keeps the entries of `iterable` for which `predicate` is truthy. `predicate` is either a
+/// `Function`/`NativeFunction` pointer (invoked via `DynamicCall`) or an inline `Closure`, taking
+/// the value as its single argument.
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Filter {
+    pub iterable: Box<Card>,
+    pub predicate: Box<Card>,
+}
+
+impl From<Filter> for Card {
+    fn from(value: Filter) -> Self {
+        CardBody::Filter(Box::new(value)).into()
+    }
+}
+
+/// Folds `iterable` with `reducer`, starting from `init`. `reducer` is either a
+/// `Function`/`NativeFunction` pointer (invoked via `DynamicCall`) or an inline `Closure`, with
+/// signature `(acc, value) -> acc`.
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Reduce {
+    pub iterable: Box<Card>,
+    pub init: Box<Card>,
+    pub reducer: Box<Card>,
+}
+
+impl From<Reduce> for Card {
+    fn from(value: Reduce) -> Self {
+        CardBody::Reduce(Box::new(value)).into()
+    }
+}
+
+/// Guards `body`: a fault raised while running one of its cards (or a nested `Card::Throw`)
+/// unwinds the value/call stacks back to where this `Try` was entered, binds the faulting value
+/// to `catch_var` (scoped to `handler`, like `Repeat`/`ForEach` scope their loop variables) and
+/// resumes at `handler`'s first card instead of aborting the program. If `body` completes without
+/// faulting, `handler` is skipped and execution continues with whatever follows the `Try`. See
+/// [`crate::vm::instr_execution`] for the runtime side of this.
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TryCatch {
+    pub body: Vec<Card>,
+    pub handler: Vec<Card>,
+    /// The caught value is bound here for the duration of `handler`. `None` still binds it to an
+    /// anonymous local so the operand stack stays balanced; the value is just inaccessible by
+    /// name.
+    pub catch_var: Option<VarName>,
+}
+
+impl From<TryCatch> for Card {
+    fn from(value: TryCatch) -> Self {
+        CardBody::Try(Box::new(value)).into()
+    }
+}
+
+/// An ordered if/else-if/.../else chain, collapsed into one card. See [`CardBody::Cond`].
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cond {
+    /// Parallel to `bodies`: `conditions.len() == bodies.len()`.
+    pub conditions: Vec<Card>,
+    pub bodies: Vec<Card>,
+    pub default: Option<Card>,
+}
+
+impl From<Cond> for Card {
+    fn from(value: Cond) -> Self {
+        CardBody::Cond(Box::new(value)).into()
+    }
+}
+
+/// Dispatches on `value`'s integer result: the first `bodies[i]` whose `keys[i]` equals it runs;
+/// `default` runs if no key matched. Parallel to [`Cond`], but keyed on exact integer equality
+/// instead of an ordered chain of boolean conditions, so the compiler can build a dense array
+/// jump table when the keys are dense enough to be worth it - see the `Card::Switch` arm of
+/// [`super::Compiler::process_card`].
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Switch {
+    pub value: Box<Card>,
+    /// Parallel to `bodies`: `keys.len() == bodies.len()`. Keys must be unique.
+    pub keys: Vec<i64>,
+    pub bodies: Vec<Card>,
+    pub default: Option<Card>,
+}
+
+impl From<Switch> for Card {
+    fn from(value: Switch) -> Self {
+        CardBody::Switch(Box::new(value)).into()
+    }
+}
+
+/// A single named child slot of a [`Card`], as returned by [`Card::child_slots`]. `One` is a
+/// fixed slot (e.g. the lhs of a binary expression); `Many` is a variadic slot backed by a real
+/// `Vec<Card>` (e.g. `CallNative`'s args), whose length contributes to [`Card::num_children`].
+#[derive(Debug, Clone, Copy)]
+pub enum ChildSlot<'a> {
+    One(&'static str, &'a Card),
+    Many(&'static str, &'a [Card]),
+}
+
+/// The `_mut` counterpart of [`ChildSlot`], returned by [`Card::child_slots_mut`].
+#[derive(Debug)]
+pub enum ChildSlotMut<'a> {
+    One(&'static str, &'a mut Card),
+    Many(&'static str, &'a mut Vec<Card>),
+}
+
 impl Card {
     pub fn name(&self) -> &str {
         match &self.body {
-            CardBody::SetVar(_) => "SetVar",
-            CardBody::Add(_) => "Add",
-            CardBody::Sub(_) => "Sub",
-            CardBody::CreateTable => "CreateTable",
-            CardBody::Mul(_) => "Mul",
-            CardBody::Div(_) => "Div",
-            CardBody::Not(_) => "Not",
-            CardBody::Less(_) => "Less",
-            CardBody::LessOrEq(_) => "LessOrEq",
-            CardBody::Equals(_) => "Equals",
-            CardBody::NotEquals(_) => "NotEquals",
-            CardBody::And(_) => "And",
-            CardBody::Or(_) => "Either",
-            CardBody::Xor(_) => "Exclusive Or",
-            CardBody::Abort => "Abort",
-            CardBody::Len(_) => "Len",
-            CardBody::ScalarInt(_) => "ScalarInt",
-            CardBody::ScalarFloat(_) => "ScalarFloat",
-            CardBody::StringLiteral(_) => "StringLiteral",
-            CardBody::CallNative(_) => "Call Native Function",
-            CardBody::IfTrue(_) => "IfTrue",
-            CardBody::IfFalse(_) => "IfFalse",
-            CardBody::Call(_) => "Call Function",
-            CardBody::SetGlobalVar(_) => "SetGlobalVar",
-            CardBody::ReadVar(_) => "ReadVar",
-            CardBody::ScalarNil => "ScalarNil",
-            CardBody::Return(_) => "Return",
-            CardBody::Repeat { .. } => "Repeat",
-            CardBody::While { .. } => "While",
-            CardBody::IfElse { .. } => "IfElse",
-            CardBody::GetProperty(_) => "GetProperty",
-            CardBody::SetProperty(_) => "SetProperty",
-            CardBody::ForEach { .. } => "ForEach",
+            // `CompositeCard`'s name is author-provided; every other kind's name is determined
+            // entirely by its `CardKind`.
             CardBody::CompositeCard(c) => c.ty.as_str(),
-            CardBody::Function(_) => "Function",
-            CardBody::DynamicCall(_) => "Call",
-            CardBody::Get(_) => "Get",
-            CardBody::AppendTable(_) => "Append to Table",
-            CardBody::PopTable(_) => "Pop from Table",
-            CardBody::Array(_) => "Array",
-            CardBody::NativeFunction(_) => "Native Function",
-            CardBody::Closure(_) => "Closure",
-            CardBody::Comment(_) => "Comment",
+            _ => self.kind().name(),
         }
     }
 
@@ -279,516 +447,542 @@ impl Card {
         .into()
     }
 
+    pub fn throw(value: impl Into<Card>) -> Self {
+        CardBody::Throw(UnaryExpression::new(value)).into()
+    }
+
+    pub fn try_catch(body: Vec<Card>, catch_var: Option<String>, handler: Vec<Card>) -> Self {
+        CardBody::Try(Box::new(TryCatch {
+            body,
+            handler,
+            catch_var,
+        }))
+        .into()
+    }
+
     pub fn function_value(s: impl Into<String>) -> Self {
         CardBody::Function(s.into()).into()
     }
 
-    pub fn num_children(&self) -> u32 {
+    /// The single source of truth for this card's children: every other accessor below
+    /// (`num_children`, `iter_children`, `get_child`, `remove_child`, `insert_child`,
+    /// `swap_children`, `visit`) is a thin wrapper over this list, plus its `_mut` counterpart
+    /// [`Card::child_slots_mut`]. A new `CardBody` variant only needs one arm here.
+    ///
+    /// Slot order matches the indexing every caller already relies on, e.g. `DynamicCall` is
+    /// function-then-args, `Repeat` is n-then-body, `ForEach` is iterable-then-body.
+    pub fn child_slots(&self) -> SmallVec<[ChildSlot<'_>; 3]> {
+        use CardBody::*;
         match &self.body {
-            CardBody::Add(_b)
-            | CardBody::Sub(_b)
-            | CardBody::Mul(_b)
-            | CardBody::Div(_b)
-            | CardBody::Less(_b)
-            | CardBody::LessOrEq(_b)
-            | CardBody::Equals(_b)
-            | CardBody::NotEquals(_b)
-            | CardBody::And(_b)
-            | CardBody::Or(_b)
-            | CardBody::GetProperty(_b)
-            | CardBody::IfTrue(_b)
-            | CardBody::IfFalse(_b)
-            | CardBody::While(_b)
-            | CardBody::Get(_b)
-            | CardBody::AppendTable(_b)
-            | CardBody::Xor(_b) => 2,
-            CardBody::PopTable(UnaryExpression { .. })
-            | CardBody::Len(UnaryExpression { .. })
-            | CardBody::Not(UnaryExpression { .. })
-            | CardBody::Return(UnaryExpression { .. }) => 1,
-            CardBody::ScalarInt(_)
-            | CardBody::ScalarFloat(_)
-            | CardBody::StringLiteral(_)
-            | CardBody::Comment(_)
-            | CardBody::Function(_)
-            | CardBody::CreateTable
-            | CardBody::ReadVar(_)
-            | CardBody::NativeFunction(_)
-            | CardBody::Abort
-            | CardBody::ScalarNil => 0,
-            CardBody::IfElse(_t) | CardBody::SetProperty(_t) => 3,
-            CardBody::CallNative(c) => c.args.0.len() as u32,
-            CardBody::Call(c) => c.args.0.len() as u32,
-            CardBody::SetGlobalVar(_s) | CardBody::SetVar(_s) => 1,
-            CardBody::Repeat(_r) => 2,
-            CardBody::ForEach(_f) => 2,
-            CardBody::CompositeCard(c) => c.cards.len() as u32,
-            CardBody::DynamicCall(c) => 1 + c.args.0.len() as u32,
-            CardBody::Array(a) => a.len() as u32,
-            CardBody::Closure(c) => c.cards.len() as u32,
+            Add(b) | Sub(b) | Mul(b) | Div(b) | Less(b) | LessOrEq(b) | Equals(b)
+            | NotEquals(b) | And(b) | Or(b) | Xor(b) | Mod(b) | Pow(b) | Min(b) | Max(b)
+            | Random(b) | DiceRoll(b)
+            | BitAnd(b) | BitOr(b) | BitXor(b) | Shl(b) | Shr(b) => {
+                smallvec::smallvec![ChildSlot::One("lhs", &b[0]), ChildSlot::One("rhs", &b[1])]
+            }
+            While(b) => smallvec::smallvec![
+                ChildSlot::One("condition", &b[0]),
+                ChildSlot::One("body", &b[1]),
+            ],
+            DoWhile(b) => smallvec::smallvec![
+                ChildSlot::One("body", &b[0]),
+                ChildSlot::One("condition", &b[1]),
+            ],
+            IfTrue(b) => smallvec::smallvec![
+                ChildSlot::One("condition", &b[0]),
+                ChildSlot::One("then", &b[1]),
+            ],
+            IfFalse(b) => smallvec::smallvec![
+                ChildSlot::One("condition", &b[0]),
+                ChildSlot::One("else", &b[1]),
+            ],
+            IfElse(t) => smallvec::smallvec![
+                ChildSlot::One("condition", &t[0]),
+                ChildSlot::One("then", &t[1]),
+                ChildSlot::One("else", &t[2]),
+            ],
+            GetProperty(b) => {
+                smallvec::smallvec![ChildSlot::One("table", &b[0]), ChildSlot::One("key", &b[1]),]
+            }
+            Get(b) => smallvec::smallvec![
+                ChildSlot::One("table", &b[0]),
+                ChildSlot::One("index", &b[1]),
+            ],
+            AppendTable(b) => smallvec::smallvec![
+                ChildSlot::One("value", &b[0]),
+                ChildSlot::One("table", &b[1]),
+            ],
+            SetProperty(t) => smallvec::smallvec![
+                ChildSlot::One("value", &t[0]),
+                ChildSlot::One("table", &t[1]),
+                ChildSlot::One("key", &t[2]),
+            ],
+            PopTable(u) | Not(u) | Return(u) | Len(u) | Neg(u) | Abs(u) | Floor(u) | Ceil(u)
+            | Round(u) => {
+                smallvec::smallvec![ChildSlot::One("value", &u.card)]
+            }
+            SetGlobalVar(s) | SetVar(s) => smallvec::smallvec![ChildSlot::One("value", &s.value)],
+            Repeat(r) => {
+                smallvec::smallvec![ChildSlot::One("n", &r.n), ChildSlot::One("body", &r.body)]
+            }
+            ForEach(f) => smallvec::smallvec![
+                ChildSlot::One("iterable", &f.iterable),
+                ChildSlot::One("body", &f.body),
+            ],
+            CompositeCard(c) => smallvec::smallvec![ChildSlot::Many("cards", &c.cards)],
+            Closure(c) => smallvec::smallvec![ChildSlot::Many("cards", &c.cards)],
+            Array(a) => smallvec::smallvec![ChildSlot::Many("elements", a)],
+            CallNative(c) => smallvec::smallvec![ChildSlot::Many("args", &c.args.0)],
+            Call(c) => smallvec::smallvec![ChildSlot::Many("args", &c.args.0)],
+            DynamicCall(c) => smallvec::smallvec![
+                ChildSlot::One("function", &c.function),
+                ChildSlot::Many("args", &c.args.0),
+            ],
+            Map(m) => smallvec::smallvec![
+                ChildSlot::One("iterable", &m.iterable),
+                ChildSlot::One("mapper", &m.mapper),
+            ],
+            Filter(f) => smallvec::smallvec![
+                ChildSlot::One("iterable", &f.iterable),
+                ChildSlot::One("predicate", &f.predicate),
+            ],
+            Reduce(r) => smallvec::smallvec![
+                ChildSlot::One("iterable", &r.iterable),
+                ChildSlot::One("init", &r.init),
+                ChildSlot::One("reducer", &r.reducer),
+            ],
+            Zip(z) => smallvec::smallvec![ChildSlot::One("a", &z[0]), ChildSlot::One("b", &z[1])],
+            Enumerate(u) => smallvec::smallvec![ChildSlot::One("value", &u.card)],
+            Try(t) => smallvec::smallvec![
+                ChildSlot::Many("body", &t.body),
+                ChildSlot::Many("handler", &t.handler),
+            ],
+            Throw(u) => smallvec::smallvec![ChildSlot::One("value", &u.card)],
+            Cond(c) => {
+                let mut slots = smallvec::smallvec![
+                    ChildSlot::Many("conditions", &c.conditions),
+                    ChildSlot::Many("bodies", &c.bodies),
+                ];
+                if let Some(default) = &c.default {
+                    slots.push(ChildSlot::One("default", default));
+                }
+                slots
+            }
+            Switch(s) => {
+                let mut slots = smallvec::smallvec![
+                    ChildSlot::One("value", &s.value),
+                    ChildSlot::Many("bodies", &s.bodies),
+                ];
+                if let Some(default) = &s.default {
+                    slots.push(ChildSlot::One("default", default));
+                }
+                slots
+            }
+            ScalarInt(_) | ScalarFloat(_) | StringLiteral(_) | Comment(_) | Function(_)
+            | NativeFunction(_) | ReadVar(_) | CreateTable | Abort | ScalarNil | Break
+            | Continue => SmallVec::new(),
         }
     }
 
-    pub fn iter_children_mut<'a>(&'a mut self) -> Box<dyn Iterator<Item = &'a mut Card> + 'a> {
+    /// The `_mut` counterpart of [`Card::child_slots`]; see its docs for the slot layout.
+    pub fn child_slots_mut(&mut self) -> SmallVec<[ChildSlotMut<'_>; 3]> {
+        use CardBody::*;
         match &mut self.body {
-            CardBody::Add(b)
-            | CardBody::Sub(b)
-            | CardBody::Mul(b)
-            | CardBody::Div(b)
-            | CardBody::Less(b)
-            | CardBody::LessOrEq(b)
-            | CardBody::Equals(b)
-            | CardBody::NotEquals(b)
-            | CardBody::And(b)
-            | CardBody::Or(b)
-            | CardBody::GetProperty(b)
-            | CardBody::IfTrue(b)
-            | CardBody::IfFalse(b)
-            | CardBody::While(b)
-            | CardBody::Get(b)
-            | CardBody::AppendTable(b)
-            | CardBody::Xor(b) => Box::new(b.iter_mut()),
-            CardBody::PopTable(u) | CardBody::Len(u) | CardBody::Not(u) | CardBody::Return(u) => {
-                Box::new(std::iter::once(u.card.as_mut()))
-            }
-            CardBody::ScalarInt(_)
-            | CardBody::ScalarFloat(_)
-            | CardBody::StringLiteral(_)
-            | CardBody::Comment(_)
-            | CardBody::Function(_)
-            | CardBody::CreateTable
-            | CardBody::ReadVar(_)
-            | CardBody::NativeFunction(_)
-            | CardBody::Abort
-            | CardBody::ScalarNil => Box::new(std::iter::empty()),
-            CardBody::IfElse(t) | CardBody::SetProperty(t) => Box::new(t.iter_mut()),
-            CardBody::CallNative(c) => Box::new(c.args.0.iter_mut()),
-            CardBody::Call(c) => Box::new(c.args.0.iter_mut()),
-            CardBody::SetGlobalVar(s) | CardBody::SetVar(s) => {
-                Box::new(std::iter::once(&mut s.value))
-            }
-            CardBody::Repeat(r) => Box::new([&mut r.n, &mut r.body].into_iter()),
-            CardBody::ForEach(f) => Box::new([f.iterable.as_mut(), f.body.as_mut()].into_iter()),
-            CardBody::CompositeCard(c) => Box::new(c.cards.iter_mut()),
-            CardBody::DynamicCall(c) => {
-                Box::new(std::iter::once(&mut c.function).chain(c.args.0.iter_mut()))
-            }
-            CardBody::Array(a) => Box::new(a.iter_mut()),
-            CardBody::Closure(c) => Box::new(c.cards.iter_mut()),
+            Add(b) | Sub(b) | Mul(b) | Div(b) | Less(b) | LessOrEq(b) | Equals(b)
+            | NotEquals(b) | And(b) | Or(b) | Xor(b) | Mod(b) | Pow(b) | Min(b) | Max(b)
+            | Random(b) | DiceRoll(b)
+            | BitAnd(b) | BitOr(b) | BitXor(b) | Shl(b) | Shr(b) => {
+                let [lhs, rhs] = b.as_mut();
+                smallvec::smallvec![ChildSlotMut::One("lhs", lhs), ChildSlotMut::One("rhs", rhs)]
+            }
+            While(b) => {
+                let [condition, body] = b.as_mut();
+                smallvec::smallvec![
+                    ChildSlotMut::One("condition", condition),
+                    ChildSlotMut::One("body", body),
+                ]
+            }
+            DoWhile(b) => {
+                let [body, condition] = b.as_mut();
+                smallvec::smallvec![
+                    ChildSlotMut::One("body", body),
+                    ChildSlotMut::One("condition", condition),
+                ]
+            }
+            IfTrue(b) => {
+                let [condition, then] = b.as_mut();
+                smallvec::smallvec![
+                    ChildSlotMut::One("condition", condition),
+                    ChildSlotMut::One("then", then),
+                ]
+            }
+            IfFalse(b) => {
+                let [condition, els] = b.as_mut();
+                smallvec::smallvec![
+                    ChildSlotMut::One("condition", condition),
+                    ChildSlotMut::One("else", els),
+                ]
+            }
+            IfElse(t) => {
+                let [condition, then, els] = t.as_mut();
+                smallvec::smallvec![
+                    ChildSlotMut::One("condition", condition),
+                    ChildSlotMut::One("then", then),
+                    ChildSlotMut::One("else", els),
+                ]
+            }
+            GetProperty(b) => {
+                let [table, key] = b.as_mut();
+                smallvec::smallvec![
+                    ChildSlotMut::One("table", table),
+                    ChildSlotMut::One("key", key),
+                ]
+            }
+            Get(b) => {
+                let [table, index] = b.as_mut();
+                smallvec::smallvec![
+                    ChildSlotMut::One("table", table),
+                    ChildSlotMut::One("index", index),
+                ]
+            }
+            AppendTable(b) => {
+                let [value, table] = b.as_mut();
+                smallvec::smallvec![
+                    ChildSlotMut::One("value", value),
+                    ChildSlotMut::One("table", table),
+                ]
+            }
+            SetProperty(t) => {
+                let [value, table, key] = t.as_mut();
+                smallvec::smallvec![
+                    ChildSlotMut::One("value", value),
+                    ChildSlotMut::One("table", table),
+                    ChildSlotMut::One("key", key),
+                ]
+            }
+            PopTable(u) | Not(u) | Return(u) | Len(u) | Neg(u) | Abs(u) | Floor(u) | Ceil(u)
+            | Round(u) => {
+                smallvec::smallvec![ChildSlotMut::One("value", &mut u.card)]
+            }
+            SetGlobalVar(s) | SetVar(s) => {
+                smallvec::smallvec![ChildSlotMut::One("value", &mut s.value)]
+            }
+            Repeat(r) => smallvec::smallvec![
+                ChildSlotMut::One("n", &mut r.n),
+                ChildSlotMut::One("body", &mut r.body),
+            ],
+            ForEach(f) => smallvec::smallvec![
+                ChildSlotMut::One("iterable", &mut f.iterable),
+                ChildSlotMut::One("body", &mut f.body),
+            ],
+            CompositeCard(c) => smallvec::smallvec![ChildSlotMut::Many("cards", &mut c.cards)],
+            Closure(c) => smallvec::smallvec![ChildSlotMut::Many("cards", &mut c.cards)],
+            Array(a) => smallvec::smallvec![ChildSlotMut::Many("elements", a)],
+            CallNative(c) => smallvec::smallvec![ChildSlotMut::Many("args", &mut c.args.0)],
+            Call(c) => smallvec::smallvec![ChildSlotMut::Many("args", &mut c.args.0)],
+            DynamicCall(c) => smallvec::smallvec![
+                ChildSlotMut::One("function", &mut c.function),
+                ChildSlotMut::Many("args", &mut c.args.0),
+            ],
+            Map(m) => smallvec::smallvec![
+                ChildSlotMut::One("iterable", &mut m.iterable),
+                ChildSlotMut::One("mapper", &mut m.mapper),
+            ],
+            Filter(f) => smallvec::smallvec![
+                ChildSlotMut::One("iterable", &mut f.iterable),
+                ChildSlotMut::One("predicate", &mut f.predicate),
+            ],
+            Reduce(r) => smallvec::smallvec![
+                ChildSlotMut::One("iterable", &mut r.iterable),
+                ChildSlotMut::One("init", &mut r.init),
+                ChildSlotMut::One("reducer", &mut r.reducer),
+            ],
+            Zip(z) => {
+                let [a, b] = z.as_mut();
+                smallvec::smallvec![ChildSlotMut::One("a", a), ChildSlotMut::One("b", b)]
+            }
+            Enumerate(u) => smallvec::smallvec![ChildSlotMut::One("value", &mut u.card)],
+            Try(t) => smallvec::smallvec![
+                ChildSlotMut::Many("body", &mut t.body),
+                ChildSlotMut::Many("handler", &mut t.handler),
+            ],
+            Throw(u) => smallvec::smallvec![ChildSlotMut::One("value", &mut u.card)],
+            Cond(c) => {
+                let mut slots = smallvec::smallvec![
+                    ChildSlotMut::Many("conditions", &mut c.conditions),
+                    ChildSlotMut::Many("bodies", &mut c.bodies),
+                ];
+                if let Some(default) = &mut c.default {
+                    slots.push(ChildSlotMut::One("default", default));
+                }
+                slots
+            }
+            Switch(s) => {
+                let mut slots = smallvec::smallvec![
+                    ChildSlotMut::One("value", &mut s.value),
+                    ChildSlotMut::Many("bodies", &mut s.bodies),
+                ];
+                if let Some(default) = &mut s.default {
+                    slots.push(ChildSlotMut::One("default", default));
+                }
+                slots
+            }
+            ScalarInt(_) | ScalarFloat(_) | StringLiteral(_) | Comment(_) | Function(_)
+            | NativeFunction(_) | ReadVar(_) | CreateTable | Abort | ScalarNil | Break
+            | Continue => SmallVec::new(),
         }
     }
 
-    pub fn iter_children<'a>(&'a self) -> Box<dyn Iterator<Item = &'a Card> + 'a> {
-        match &self.body {
-            CardBody::Add(b)
-            | CardBody::Sub(b)
-            | CardBody::Mul(b)
-            | CardBody::Div(b)
-            | CardBody::Less(b)
-            | CardBody::LessOrEq(b)
-            | CardBody::Equals(b)
-            | CardBody::NotEquals(b)
-            | CardBody::And(b)
-            | CardBody::Or(b)
-            | CardBody::GetProperty(b)
-            | CardBody::IfTrue(b)
-            | CardBody::IfFalse(b)
-            | CardBody::While(b)
-            | CardBody::Get(b)
-            | CardBody::AppendTable(b)
-            | CardBody::Xor(b) => Box::new(b.iter()),
-            CardBody::PopTable(u) | CardBody::Len(u) | CardBody::Not(u) | CardBody::Return(u) => {
-                Box::new(std::iter::once(u.card.as_ref()))
-            }
-            CardBody::ScalarInt(_)
-            | CardBody::ScalarFloat(_)
-            | CardBody::StringLiteral(_)
-            | CardBody::Comment(_)
-            | CardBody::Function(_)
-            | CardBody::CreateTable
-            | CardBody::ReadVar(_)
-            | CardBody::NativeFunction(_)
-            | CardBody::Abort
-            | CardBody::ScalarNil => Box::new(std::iter::empty()),
-            CardBody::IfElse(t) | CardBody::SetProperty(t) => Box::new(t.iter()),
-            CardBody::CallNative(c) => Box::new(c.args.0.iter()),
-            CardBody::Call(c) => Box::new(c.args.0.iter()),
-            CardBody::SetGlobalVar(s) | CardBody::SetVar(s) => Box::new(std::iter::once(&s.value)),
-            CardBody::Repeat(r) => Box::new([&r.n, &r.body].into_iter()),
-            CardBody::ForEach(f) => Box::new([f.iterable.as_ref(), f.body.as_ref()].into_iter()),
-            CardBody::CompositeCard(c) => Box::new(c.cards.iter()),
-            CardBody::DynamicCall(c) => {
-                Box::new(std::iter::once(&c.function).chain(c.args.0.iter()))
-            }
-            CardBody::Array(a) => Box::new(a.iter()),
-            CardBody::Closure(c) => Box::new(c.cards.iter()),
-        }
+    pub fn num_children(&self) -> u32 {
+        self.child_slots()
+            .into_iter()
+            .map(|slot| match slot {
+                ChildSlot::One(..) => 1,
+                ChildSlot::Many(_, cards) => cards.len() as u32,
+            })
+            .sum()
     }
 
-    pub fn get_child_mut(&mut self, i: usize) -> Option<&mut Card> {
-        let res;
-        match &mut self.body {
-            CardBody::CompositeCard(c) => res = c.cards.get_mut(i)?,
-            CardBody::Closure(c) => res = c.cards.get_mut(i)?,
-            CardBody::Repeat(rep) => match i {
-                0 => res = &mut rep.n,
-                1 => res = &mut rep.body,
-                _ => return None,
-            },
-            CardBody::IfTrue(c) | CardBody::IfFalse(c) => return c.get_mut(i),
-            CardBody::ForEach(fe) => {
-                let ForEach {
-                    i: _,
-                    k: _,
-                    v: _,
-                    iterable: a,
-                    body: b,
-                } = fe.as_mut();
-                match i {
-                    0 => res = a.as_mut(),
-                    1 => res = b.as_mut(),
-                    _ => return None,
+    pub fn iter_children<'a>(&'a self) -> Box<dyn Iterator<Item = &'a Card> + 'a> {
+        Box::new(self.child_slots().into_iter().flat_map(
+            |slot| -> Box<dyn Iterator<Item = &'a Card>> {
+                match slot {
+                    ChildSlot::One(_, c) => Box::new(core::iter::once(c)),
+                    ChildSlot::Many(_, cards) => Box::new(cards.iter()),
                 }
-            }
-            CardBody::IfElse(children) => return children.get_mut(i),
-
-            CardBody::Add(expr)
-            | CardBody::While(expr)
-            | CardBody::Sub(expr)
-            | CardBody::Mul(expr)
-            | CardBody::Div(expr)
-            | CardBody::Less(expr)
-            | CardBody::LessOrEq(expr)
-            | CardBody::Equals(expr)
-            | CardBody::NotEquals(expr)
-            | CardBody::And(expr)
-            | CardBody::Or(expr)
-            | CardBody::Xor(expr)
-            | CardBody::AppendTable(expr)
-            | CardBody::Get(expr)
-            | CardBody::GetProperty(expr) => return expr.get_mut(i),
-            CardBody::SetProperty(expr) => return expr.get_mut(i),
-
-            CardBody::PopTable(expr)
-            | CardBody::Not(expr)
-            | CardBody::Return(expr)
-            | CardBody::Len(expr) => match i {
-                0 => res = &mut expr.card,
-                _ => return None,
             },
+        ))
+    }
 
-            CardBody::SetGlobalVar(s) | CardBody::SetVar(s) => match i {
-                0 => res = &mut s.value,
-                _ => return None,
+    pub fn iter_children_mut<'a>(&'a mut self) -> Box<dyn Iterator<Item = &'a mut Card> + 'a> {
+        Box::new(self.child_slots_mut().into_iter().flat_map(
+            |slot| -> Box<dyn Iterator<Item = &'a mut Card>> {
+                match slot {
+                    ChildSlotMut::One(_, c) => Box::new(core::iter::once(c)),
+                    ChildSlotMut::Many(_, cards) => Box::new(cards.iter_mut()),
+                }
             },
-            CardBody::CallNative(j) => return j.args.0.get_mut(i),
-            CardBody::Call(j) => return j.args.0.get_mut(i),
-            CardBody::DynamicCall(j) => {
-                return (i == 0)
-                    .then_some(&mut j.function)
-                    .or_else(|| j.args.0.get_mut(i - 1))
-            }
-            CardBody::Array(cards) => return cards.get_mut(i),
-            CardBody::Function(_)
-            | CardBody::NativeFunction(_)
-            | CardBody::ReadVar(_)
-            | CardBody::ScalarInt(_)
-            | CardBody::ScalarFloat(_)
-            | CardBody::StringLiteral(_)
-            | CardBody::Comment(_)
-            | CardBody::ScalarNil
-            | CardBody::CreateTable
-            | CardBody::Abort => return None,
-        }
-        Some(res)
+        ))
     }
 
     pub fn get_child(&self, i: usize) -> Option<&Card> {
-        let res;
-        match &self.body {
-            CardBody::CompositeCard(c) => res = c.cards.get(i)?,
-            CardBody::Closure(c) => res = c.cards.get(i)?,
-            CardBody::Repeat(rep) => match i {
-                0 => res = &rep.n,
-                1 => res = &rep.body,
-                _ => return None,
-            },
-            CardBody::IfTrue(c) | CardBody::IfFalse(c) => return c.get(i),
-            CardBody::ForEach(fe) => {
-                let ForEach {
-                    i: _,
-                    k: _,
-                    v: _,
-                    iterable: a,
-                    body: b,
-                } = fe.as_ref();
-                match i {
-                    0 => res = a.as_ref(),
-                    1 => res = b.as_ref(),
-                    _ => return None,
+        let mut idx = i;
+        for slot in self.child_slots() {
+            match slot {
+                ChildSlot::One(_, c) => {
+                    if idx == 0 {
+                        return Some(c);
+                    }
+                    idx -= 1;
+                }
+                ChildSlot::Many(_, cards) => {
+                    if idx < cards.len() {
+                        return Some(&cards[idx]);
+                    }
+                    idx -= cards.len();
                 }
             }
-            CardBody::IfElse(children) => return children.get(i),
-            CardBody::While(expr)
-            | CardBody::Add(expr)
-            | CardBody::Sub(expr)
-            | CardBody::Mul(expr)
-            | CardBody::Div(expr)
-            | CardBody::Less(expr)
-            | CardBody::LessOrEq(expr)
-            | CardBody::Equals(expr)
-            | CardBody::NotEquals(expr)
-            | CardBody::And(expr)
-            | CardBody::Or(expr)
-            | CardBody::Xor(expr)
-            | CardBody::AppendTable(expr)
-            | CardBody::Get(expr)
-            | CardBody::GetProperty(expr) => return expr.get(i),
-            CardBody::SetProperty(expr) => return expr.get(i),
-
-            CardBody::PopTable(expr)
-            | CardBody::Not(expr)
-            | CardBody::Return(expr)
-            | CardBody::Len(expr) => match i {
-                0 => res = &expr.card,
-                _ => return None,
-            },
-
-            CardBody::SetGlobalVar(s) | CardBody::SetVar(s) => match i {
-                0 => res = &s.value,
-                _ => return None,
-            },
-            CardBody::CallNative(j) => return j.args.0.get(i),
-            CardBody::Call(j) => return j.args.0.get(i),
-            CardBody::DynamicCall(j) => {
-                return (i == 0)
-                    .then_some(&j.function)
-                    .or_else(|| j.args.0.get(i - 1))
-            }
-            CardBody::Array(cards) => return cards.get(i),
-            CardBody::Function(_)
-            | CardBody::NativeFunction(_)
-            | CardBody::ReadVar(_)
-            | CardBody::ScalarInt(_)
-            | CardBody::ScalarFloat(_)
-            | CardBody::StringLiteral(_)
-            | CardBody::Comment(_)
-            | CardBody::ScalarNil
-            | CardBody::CreateTable
-            | CardBody::Abort => return None,
-        }
-        Some(res)
+        }
+        None
     }
 
-    pub fn remove_child(&mut self, i: usize) -> Option<Card> {
-        let res;
-        match &mut self.body {
-            CardBody::CompositeCard(c) => {
-                if c.cards.len() <= i {
-                    return None;
+    pub fn get_child_mut(&mut self, i: usize) -> Option<&mut Card> {
+        let mut idx = i;
+        for slot in self.child_slots_mut() {
+            match slot {
+                ChildSlotMut::One(_, c) => {
+                    if idx == 0 {
+                        return Some(c);
+                    }
+                    idx -= 1;
                 }
-                res = c.cards.remove(i);
-            }
-            CardBody::Closure(c) => {
-                if c.cards.len() <= i {
-                    return None;
+                ChildSlotMut::Many(_, cards) => {
+                    if idx < cards.len() {
+                        return Some(&mut cards[idx]);
+                    }
+                    idx -= cards.len();
                 }
-                res = c.cards.remove(i);
             }
-            CardBody::Repeat(rep) => match i {
-                0 => res = std::mem::replace(&mut rep.n, CardBody::ScalarInt(0).into()),
-                1 => res = std::mem::replace(&mut rep.body, CardBody::ScalarNil.into()),
-                _ => return None,
-            },
-            CardBody::IfTrue(_) | CardBody::IfFalse(_) => {
-                let c = self.get_child_mut(i)?;
-                res = std::mem::replace::<Card>(c, CardBody::ScalarNil.into());
-            }
-
-            CardBody::ForEach(fe) => {
-                let ForEach {
-                    i: _,
-                    k: _,
-                    v: _,
-                    iterable: a,
-                    body: b,
-                } = fe.as_mut();
-                match i {
-                    0 => res = std::mem::replace::<Card>(a.as_mut(), CardBody::ScalarNil.into()),
-                    1 => res = std::mem::replace::<Card>(b.as_mut(), CardBody::ScalarNil.into()),
-                    _ => return None,
+        }
+        None
+    }
+
+    /// Remove the child at `i`. A fixed slot can't shrink the body, so it is reset to
+    /// `ScalarNil` and the old card is returned; a variadic slot is truly removed, shifting
+    /// later elements down.
+    pub fn remove_child(&mut self, i: usize) -> Option<Card> {
+        let mut idx = i;
+        for slot in self.child_slots_mut() {
+            match slot {
+                ChildSlotMut::One(_, c) => {
+                    if idx == 0 {
+                        return Some(core::mem::replace(c, CardBody::ScalarNil.into()));
+                    }
+                    idx -= 1;
                 }
-            }
-            CardBody::IfElse(children) => {
-                let c = children.get_mut(i)?;
-                res = std::mem::replace(c, CardBody::ScalarNil.into());
-            }
-            CardBody::While(_)
-            | CardBody::Add(_)
-            | CardBody::Sub(_)
-            | CardBody::Mul(_)
-            | CardBody::Div(_)
-            | CardBody::Less(_)
-            | CardBody::LessOrEq(_)
-            | CardBody::Equals(_)
-            | CardBody::NotEquals(_)
-            | CardBody::And(_)
-            | CardBody::Or(_)
-            | CardBody::Xor(_)
-            | CardBody::AppendTable(_)
-            | CardBody::Get(_)
-            | CardBody::SetProperty(_)
-            | CardBody::PopTable(_)
-            | CardBody::Not(_)
-            | CardBody::Return(_)
-            | CardBody::Len(_)
-            | CardBody::SetGlobalVar(_)
-            | CardBody::SetVar(_)
-            | CardBody::GetProperty(_) => {
-                let c = self.get_child_mut(i)?;
-                res = std::mem::replace(c, CardBody::ScalarNil.into());
-            }
-
-            CardBody::CallNative(j) => return (i < j.args.0.len()).then(|| j.args.0.remove(i)),
-            CardBody::Call(j) => return (i < j.args.0.len()).then(|| j.args.0.remove(i)),
-            CardBody::DynamicCall(j) => {
-                if i == 0 {
-                    res = std::mem::replace(&mut j.function, CardBody::ScalarNil.into());
-                } else if i - 1 < j.args.0.len() {
-                    res = j.args.0.remove(i - 1);
-                } else {
-                    return None;
+                ChildSlotMut::Many(_, cards) => {
+                    if idx < cards.len() {
+                        return Some(cards.remove(idx));
+                    }
+                    idx -= cards.len();
                 }
             }
-            CardBody::Array(cards) => return (i < cards.len()).then(|| cards.remove(i)),
-            CardBody::Function(_)
-            | CardBody::NativeFunction(_)
-            | CardBody::ReadVar(_)
-            | CardBody::ScalarInt(_)
-            | CardBody::ScalarFloat(_)
-            | CardBody::StringLiteral(_)
-            | CardBody::Comment(_)
-            | CardBody::ScalarNil
-            | CardBody::CreateTable
-            | CardBody::Abort => return None,
         }
-        Some(res)
+        None
     }
 
-    /// insert a child at the specified index, if the Card is a list, or replace the child at the
-    /// index if not
+    /// Insert a child at the specified index, if the slot it falls in is a list, or replace the
+    /// child at the index if it's a fixed slot.
     ///
-    /// returns the inserted card on failure
+    /// Returns the inserted card on failure.
     pub fn insert_child(&mut self, i: usize, card: impl Into<Self>) -> Result<(), Self> {
         let card = card.into();
-        match &mut self.body {
-            CardBody::CompositeCard(c) => {
-                if c.cards.len() < i {
-                    return Err(card);
-                }
-                c.cards.insert(i, card);
-            }
-            CardBody::Closure(c) => {
-                if c.cards.len() < i {
-                    return Err(card);
-                }
-                c.cards.insert(i, card);
-            }
-
-            CardBody::ForEach(fe) => {
-                let ForEach {
-                    i: _,
-                    k: _,
-                    v: _,
-                    iterable: a,
-                    body: b,
-                } = fe.as_mut();
-                match i {
-                    0 => *a.as_mut() = card,
-                    1 => *b.as_mut() = card,
-                    _ => return Err(card),
-                };
-            }
-            CardBody::IfElse(children) => match children.get_mut(i) {
-                Some(c) => {
-                    *c = card;
+        let mut idx = i;
+        for slot in self.child_slots_mut() {
+            match slot {
+                ChildSlotMut::One(_, c) => {
+                    if idx == 0 {
+                        *c = card;
+                        return Ok(());
+                    }
+                    idx -= 1;
                 }
-                None => return Err(card),
-            },
-            CardBody::While(_)
-            | CardBody::IfTrue(_)
-            | CardBody::IfFalse(_)
-            | CardBody::Add(_)
-            | CardBody::Sub(_)
-            | CardBody::Mul(_)
-            | CardBody::Div(_)
-            | CardBody::Less(_)
-            | CardBody::LessOrEq(_)
-            | CardBody::Equals(_)
-            | CardBody::NotEquals(_)
-            | CardBody::And(_)
-            | CardBody::Or(_)
-            | CardBody::Xor(_)
-            | CardBody::AppendTable(_)
-            | CardBody::Get(_)
-            | CardBody::SetProperty(_)
-            | CardBody::PopTable(_)
-            | CardBody::Not(_)
-            | CardBody::Return(_)
-            | CardBody::Len(_)
-            | CardBody::SetGlobalVar(_)
-            | CardBody::SetVar(_)
-            | CardBody::Repeat(_)
-            | CardBody::GetProperty(_) => match self.get_child_mut(i) {
-                Some(c) => *c = card,
-                None => return Err(card),
-            },
-            CardBody::CallNative(j) => {
-                (i <= j.args.0.len()).then(|| j.args.0.insert(i, card));
-            }
-            CardBody::Call(j) => {
-                (i <= j.args.0.len()).then(|| j.args.0.insert(i, card));
-            }
-            CardBody::DynamicCall(j) => {
-                if i == 0 {
-                    j.function = card;
-                } else if i - 1 <= j.args.0.len() {
-                    j.args.0.insert(i - 1, card);
-                } else {
-                    return Err(card);
-                }
-            }
-
-            CardBody::Array(children) => {
-                if i <= children.len() {
-                    children.insert(i, card);
-                } else {
-                    return Err(card);
+                ChildSlotMut::Many(_, cards) => {
+                    if idx <= cards.len() {
+                        cards.insert(idx, card);
+                        return Ok(());
+                    }
+                    idx -= cards.len();
                 }
             }
-            CardBody::Function(_)
-            | CardBody::NativeFunction(_)
-            | CardBody::ReadVar(_)
-            | CardBody::ScalarInt(_)
-            | CardBody::ScalarFloat(_)
-            | CardBody::StringLiteral(_)
-            | CardBody::Comment(_)
-            | CardBody::ScalarNil
-            | CardBody::CreateTable
-            | CardBody::Abort => return Err(card),
         }
-        Ok(())
+        Err(card)
     }
 
     /// Return Ok(old card) on success, return the input card in fail
     pub fn replace_child(&mut self, i: usize, card: impl Into<Self>) -> Result<Self, Self> {
         let card = card.into();
         match self.get_child_mut(i) {
-            Some(c) => Ok(std::mem::replace(c, card)),
+            Some(c) => Ok(core::mem::replace(c, card)),
             None => Err(card),
         }
     }
 
+    /// Swap the children at `i` and `j`, built on [`Card::get_child_mut`]. Fails without
+    /// modifying either slot if `i` or `j` is out of range.
+    pub fn swap_children(&mut self, i: usize, j: usize) -> Result<(), ()> {
+        if i == j {
+            return if self.get_child(i).is_some() {
+                Ok(())
+            } else {
+                Err(())
+            };
+        }
+        let taken_i = {
+            let ci = self.get_child_mut(i).ok_or(())?;
+            core::mem::replace(ci, CardBody::ScalarNil.into())
+        };
+        let taken_j = match self.get_child_mut(j) {
+            Some(cj) => core::mem::replace(cj, taken_i),
+            None => {
+                // restore `i` before bailing out
+                *self.get_child_mut(i).unwrap() = taken_i;
+                return Err(());
+            }
+        };
+        *self.get_child_mut(i).unwrap() = taken_j;
+        Ok(())
+    }
+
+    /// Depth-first traversal: visits `self`, then recurses into each child in
+    /// [`Card::iter_children`] order, calling `f` with the current node and its path - the
+    /// sequence of child indices from the root.
+    pub fn visit(&self, mut f: impl FnMut(&Card, &[usize])) {
+        fn go(card: &Card, path: &mut Vec<usize>, f: &mut dyn FnMut(&Card, &[usize])) {
+            f(card, path);
+            for (i, child) in card.iter_children().enumerate() {
+                path.push(i);
+                go(child, path, f);
+                path.pop();
+            }
+        }
+        go(self, &mut Vec::new(), &mut f);
+    }
+
+    /// The `_mut` counterpart of [`Card::visit`].
+    pub fn visit_mut(&mut self, mut f: impl FnMut(&mut Card, &[usize])) {
+        fn go(card: &mut Card, path: &mut Vec<usize>, f: &mut dyn FnMut(&mut Card, &[usize])) {
+            f(card, path);
+            for (i, child) in card.iter_children_mut().enumerate() {
+                path.push(i);
+                go(child, path, f);
+                path.pop();
+            }
+        }
+        go(self, &mut Vec::new(), &mut f);
+    }
+
+    /// The card with the given id anywhere in this subtree (including `self`), if any.
+    pub fn find_by_id(&self, id: &CardId) -> Option<&Card> {
+        if self.id == *id {
+            return Some(self);
+        }
+        self.iter_children().find_map(|child| child.find_by_id(id))
+    }
+
+    /// The `_mut` counterpart of [`Card::find_by_id`].
+    pub fn find_by_id_mut(&mut self, id: &CardId) -> Option<&mut Card> {
+        if self.id == *id {
+            return Some(self);
+        }
+        for child in self.iter_children_mut() {
+            if let Some(found) = child.find_by_id_mut(id) {
+                return Some(found);
+            }
+        }
+        None
+    }
+
+    /// The child-index path from `self` down to the card with the given id, or `None` if no card
+    /// in this subtree has that id. Walking the path with [`Card::get_child`]/[`get_child_mut`]
+    /// reaches the same card, giving editor/runtime code a structural address that survives
+    /// independently of the id lookup itself (e.g. for diff-friendly references).
+    ///
+    /// [`get_child_mut`]: Card::get_child_mut
+    pub fn path_to(&self, id: &CardId) -> Option<Vec<usize>> {
+        let mut result = None;
+        self.visit(|card, path| {
+            if result.is_none() && card.id == *id {
+                result = Some(path.to_vec());
+            }
+        });
+        result
+    }
+
+    /// Rewrites the id of every card in this subtree (including `self`) that collides with one
+    /// already seen, and advances the global id counter past the largest id found, so cards
+    /// created afterwards can never clash with a freshly loaded tree.
+    ///
+    /// Call this once after deserializing a `Card` tree, since ids must stay stable across
+    /// save/load cycles for external references (selection state, breakpoints, undo history) to
+    /// keep meaning the card they originally pointed at.
+    pub fn dedup_ids(&mut self) {
+        let mut max_seen = CardId(0);
+        self.visit(|card, _| {
+            if card.id > max_seen {
+                max_seen = card.id.clone();
+            }
+        });
+        advance_next_id_past(max_seen);
+
+        let mut seen = BTreeSet::new();
+        self.visit_mut(|card, _| {
+            if !seen.insert(card.id.clone()) {
+                card.id = random_id();
+            }
+        });
+    }
+
     pub fn return_card(c: impl Into<Self>) -> Self {
         CardBody::Return(UnaryExpression {
             card: Box::new(c.into()),
@@ -815,6 +1009,83 @@ impl Card {
         }))
         .into()
     }
+
+    /// The [`CardKind`] of this card's body, ignoring its id and any nested data.
+    pub fn kind(&self) -> CardKind {
+        match &self.body {
+            CardBody::Add(_) => CardKind::Add,
+            CardBody::Sub(_) => CardKind::Sub,
+            CardBody::Mul(_) => CardKind::Mul,
+            CardBody::Div(_) => CardKind::Div,
+            CardBody::Less(_) => CardKind::Less,
+            CardBody::LessOrEq(_) => CardKind::LessOrEq,
+            CardBody::Equals(_) => CardKind::Equals,
+            CardBody::NotEquals(_) => CardKind::NotEquals,
+            CardBody::And(_) => CardKind::And,
+            CardBody::Or(_) => CardKind::Or,
+            CardBody::Xor(_) => CardKind::Xor,
+            CardBody::Not(_) => CardKind::Not,
+            CardBody::Return(_) => CardKind::Return,
+            CardBody::ScalarNil => CardKind::ScalarNil,
+            CardBody::CreateTable => CardKind::CreateTable,
+            CardBody::Abort => CardKind::Abort,
+            CardBody::Len(_) => CardKind::Len,
+            CardBody::SetProperty(_) => CardKind::SetProperty,
+            CardBody::GetProperty(_) => CardKind::GetProperty,
+            CardBody::ScalarInt(_) => CardKind::ScalarInt,
+            CardBody::ScalarFloat(_) => CardKind::ScalarFloat,
+            CardBody::StringLiteral(_) => CardKind::StringLiteral,
+            CardBody::CallNative(_) => CardKind::CallNative,
+            CardBody::IfTrue(_) => CardKind::IfTrue,
+            CardBody::IfFalse(_) => CardKind::IfFalse,
+            CardBody::IfElse(_) => CardKind::IfElse,
+            CardBody::Call(_) => CardKind::Call,
+            CardBody::Function(_) => CardKind::Function,
+            CardBody::NativeFunction(_) => CardKind::NativeFunction,
+            CardBody::SetGlobalVar(_) => CardKind::SetGlobalVar,
+            CardBody::SetVar(_) => CardKind::SetVar,
+            CardBody::ReadVar(_) => CardKind::ReadVar,
+            CardBody::Repeat(_) => CardKind::Repeat,
+            CardBody::While(_) => CardKind::While,
+            CardBody::DoWhile(_) => CardKind::DoWhile,
+            CardBody::ForEach(_) => CardKind::ForEach,
+            CardBody::CompositeCard(_) => CardKind::CompositeCard,
+            CardBody::DynamicCall(_) => CardKind::DynamicCall,
+            CardBody::Get(_) => CardKind::Get,
+            CardBody::AppendTable(_) => CardKind::AppendTable,
+            CardBody::PopTable(_) => CardKind::PopTable,
+            CardBody::Array(_) => CardKind::Array,
+            CardBody::Closure(_) => CardKind::Closure,
+            CardBody::Comment(_) => CardKind::Comment,
+            CardBody::Map(_) => CardKind::Map,
+            CardBody::Filter(_) => CardKind::Filter,
+            CardBody::Reduce(_) => CardKind::Reduce,
+            CardBody::Zip(_) => CardKind::Zip,
+            CardBody::Enumerate(_) => CardKind::Enumerate,
+            CardBody::Mod(_) => CardKind::Mod,
+            CardBody::Pow(_) => CardKind::Pow,
+            CardBody::Neg(_) => CardKind::Neg,
+            CardBody::Abs(_) => CardKind::Abs,
+            CardBody::Min(_) => CardKind::Min,
+            CardBody::Max(_) => CardKind::Max,
+            CardBody::Floor(_) => CardKind::Floor,
+            CardBody::Ceil(_) => CardKind::Ceil,
+            CardBody::Round(_) => CardKind::Round,
+            CardBody::Random(_) => CardKind::Random,
+            CardBody::DiceRoll(_) => CardKind::DiceRoll,
+            CardBody::BitAnd(_) => CardKind::BitAnd,
+            CardBody::BitOr(_) => CardKind::BitOr,
+            CardBody::BitXor(_) => CardKind::BitXor,
+            CardBody::Shl(_) => CardKind::Shl,
+            CardBody::Shr(_) => CardKind::Shr,
+            CardBody::Try(_) => CardKind::Try,
+            CardBody::Throw(_) => CardKind::Throw,
+            CardBody::Cond(_) => CardKind::Cond,
+            CardBody::Switch(_) => CardKind::Switch,
+            CardBody::Break => CardKind::Break,
+            CardBody::Continue => CardKind::Continue,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -866,6 +1137,917 @@ pub struct Repeat {
     pub body: Card,
 }
 
+/// Coarse functional grouping of a [`CardKind`], for palette menus in editor tooling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CardCategory {
+    Arithmetic,
+    Logic,
+    ControlFlow,
+    Table,
+    Variable,
+    Function,
+    Literal,
+    Misc,
+}
+
+/// Whether a [`CardSlotSchema`] holds a single fixed child or a `Vec`-backed, open-ended list of
+/// them. Matches [`ChildSlot::One`]/[`ChildSlot::Many`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SlotArity {
+    One,
+    Many,
+}
+
+/// Static description of a single child slot of a [`CardSchema`], generated from
+/// [`Card::child_slots`] so it can't drift from what `insert_child`/`iter_children` actually
+/// accept. See [`CardKind::schema`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CardSlotSchema {
+    pub label: &'static str,
+    pub arity: SlotArity,
+    /// Whether only a literal (`ScalarInt`/`ScalarFloat`/`ScalarNil`/`StringLiteral`) card in
+    /// this slot is meaningful to the compiler - e.g. `SetProperty`/`GetProperty`'s `key` and
+    /// `Get`'s `index`, which [`super::const_fold`] can only reason about when literal. A
+    /// non-literal card is still structurally valid here; it just forgoes that analysis.
+    pub literal_only: bool,
+    /// The kind of value this slot expects, for [`check_card_types`]. Defaults to
+    /// [`PropertyKind::Any`] for slots where any value is meaningful.
+    pub kind: PropertyKind,
+}
+
+/// Static description of a [`CardKind`]: display name, category, child-count bounds and per-slot
+/// schema, queryable without constructing a [`Card`]. See [`CardKind::schema`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CardSchema {
+    pub kind: CardKind,
+    pub name: &'static str,
+    pub category: CardCategory,
+    pub min_children: usize,
+    /// `None` for variadic kinds (e.g. `Array`, `CallNative`)
+    pub max_children: Option<usize>,
+    pub slots: Vec<CardSlotSchema>,
+}
+
+/// Enumerates every [`CardBody`] case without its payload, for editor tooling (autocomplete,
+/// palette menus, structural validation). See [`Card::kind`] and [`CardKind::schema`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CardKind {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Less,
+    LessOrEq,
+    Equals,
+    NotEquals,
+    And,
+    Or,
+    Xor,
+    Not,
+    Return,
+    ScalarNil,
+    CreateTable,
+    Abort,
+    Len,
+    SetProperty,
+    GetProperty,
+    ScalarInt,
+    ScalarFloat,
+    StringLiteral,
+    CallNative,
+    IfTrue,
+    IfFalse,
+    IfElse,
+    Call,
+    Function,
+    NativeFunction,
+    SetGlobalVar,
+    SetVar,
+    ReadVar,
+    Repeat,
+    While,
+    DoWhile,
+    ForEach,
+    CompositeCard,
+    DynamicCall,
+    Get,
+    AppendTable,
+    PopTable,
+    Array,
+    Closure,
+    Comment,
+    Map,
+    Filter,
+    Reduce,
+    Zip,
+    Enumerate,
+    Mod,
+    Pow,
+    Neg,
+    Abs,
+    Min,
+    Max,
+    Floor,
+    Ceil,
+    Round,
+    Random,
+    DiceRoll,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
+    Try,
+    Throw,
+    Cond,
+    Switch,
+    Break,
+    Continue,
+}
+
+impl CardKind {
+    /// Every variant of [`CardKind`], in declaration order.
+    pub fn all() -> &'static [CardKind] {
+        use CardKind::*;
+        &[
+            Add,
+            Sub,
+            Mul,
+            Div,
+            Less,
+            LessOrEq,
+            Equals,
+            NotEquals,
+            And,
+            Or,
+            Xor,
+            Not,
+            Return,
+            ScalarNil,
+            CreateTable,
+            Abort,
+            Len,
+            SetProperty,
+            GetProperty,
+            ScalarInt,
+            ScalarFloat,
+            StringLiteral,
+            CallNative,
+            IfTrue,
+            IfFalse,
+            IfElse,
+            Call,
+            Function,
+            NativeFunction,
+            SetGlobalVar,
+            SetVar,
+            ReadVar,
+            Repeat,
+            While,
+            DoWhile,
+            ForEach,
+            CompositeCard,
+            DynamicCall,
+            Get,
+            AppendTable,
+            PopTable,
+            Array,
+            Closure,
+            Comment,
+            Map,
+            Filter,
+            Reduce,
+            Zip,
+            Enumerate,
+            Mod,
+            Pow,
+            Neg,
+            Abs,
+            Min,
+            Max,
+            Floor,
+            Ceil,
+            Round,
+            Random,
+            DiceRoll,
+            BitAnd,
+            BitOr,
+            BitXor,
+            Shl,
+            Shr,
+            Try,
+            Throw,
+            Cond,
+            Switch,
+            Break,
+            Continue,
+        ]
+    }
+
+    /// Static display name for this kind. Matches [`Card::name`] for every kind except
+    /// `CompositeCard`, whose name is an author-provided field rather than a function of its
+    /// kind.
+    pub fn name(&self) -> &'static str {
+        use CardKind::*;
+        match self {
+            Add => "Add",
+            Sub => "Sub",
+            Mul => "Mul",
+            Div => "Div",
+            Less => "Less",
+            LessOrEq => "LessOrEq",
+            Equals => "Equals",
+            NotEquals => "NotEquals",
+            And => "And",
+            Or => "Either",
+            Xor => "Exclusive Or",
+            Not => "Not",
+            Return => "Return",
+            ScalarNil => "ScalarNil",
+            CreateTable => "CreateTable",
+            Abort => "Abort",
+            Len => "Len",
+            SetProperty => "SetProperty",
+            GetProperty => "GetProperty",
+            ScalarInt => "ScalarInt",
+            ScalarFloat => "ScalarFloat",
+            StringLiteral => "StringLiteral",
+            CallNative => "Call Native Function",
+            IfTrue => "IfTrue",
+            IfFalse => "IfFalse",
+            IfElse => "IfElse",
+            Call => "Call Function",
+            Function => "Function",
+            NativeFunction => "Native Function",
+            SetGlobalVar => "SetGlobalVar",
+            SetVar => "SetVar",
+            ReadVar => "ReadVar",
+            Repeat => "Repeat",
+            While => "While",
+            DoWhile => "DoWhile",
+            ForEach => "ForEach",
+            CompositeCard => "CompositeCard",
+            DynamicCall => "Call",
+            Get => "Get",
+            AppendTable => "Append to Table",
+            PopTable => "Pop from Table",
+            Array => "Array",
+            Closure => "Closure",
+            Comment => "Comment",
+            Map => "Map",
+            Filter => "Filter",
+            Reduce => "Reduce",
+            Zip => "Zip",
+            Enumerate => "Enumerate",
+            Mod => "Mod",
+            Pow => "Pow",
+            Neg => "Neg",
+            Abs => "Abs",
+            Min => "Min",
+            Max => "Max",
+            Floor => "Floor",
+            Ceil => "Ceil",
+            Round => "Round",
+            Random => "Random",
+            DiceRoll => "Dice Roll",
+            BitAnd => "BitAnd",
+            BitOr => "BitOr",
+            BitXor => "BitXor",
+            Shl => "Shl",
+            Shr => "Shr",
+            Try => "Try",
+            Throw => "Throw",
+            Cond => "Cond",
+            Switch => "Switch",
+            Break => "Break",
+            Continue => "Continue",
+        }
+    }
+
+    /// The static schema describing this kind: display name, category, child-count bounds and
+    /// per-slot schema.
+    ///
+    /// Slot labels and arity are read off [`Card::child_slots`] (via [`CardKind::default_card`])
+    /// rather than re-declared here, so they can't drift from what
+    /// `insert_child`/`iter_children`/the rest of the traversal API actually accept; `category`
+    /// and which slots are literal-only aren't derivable from a card's shape, so those stay
+    /// hand-authored in [`CardKind::category`]/[`CardKind::literal_only_slots`].
+    pub fn schema(&self) -> CardSchema {
+        let slots: Vec<CardSlotSchema> = self
+            .default_card()
+            .child_slots()
+            .iter()
+            .map(|slot| {
+                let (label, arity) = match slot {
+                    ChildSlot::One(label, _) => (*label, SlotArity::One),
+                    ChildSlot::Many(label, _) => (*label, SlotArity::Many),
+                };
+                CardSlotSchema {
+                    label,
+                    arity,
+                    literal_only: self.literal_only_slots().contains(&label),
+                    kind: self.slot_kind(label),
+                }
+            })
+            .collect();
+
+        let min_children = slots.iter().filter(|s| s.arity == SlotArity::One).count();
+        let max_children = if slots.iter().any(|s| s.arity == SlotArity::Many) {
+            None
+        } else {
+            Some(slots.len())
+        };
+
+        CardSchema {
+            kind: *self,
+            name: self.name(),
+            category: self.category(),
+            min_children,
+            max_children,
+            slots,
+        }
+    }
+
+    /// Coarse functional grouping for palette menus; see [`CardCategory`].
+    fn category(&self) -> CardCategory {
+        use CardCategory::*;
+        use CardKind::*;
+        match self {
+            Add | Sub | Mul | Div | Mod | Pow | Min | Max | BitAnd | BitOr | BitXor | Shl | Shr
+            | Neg | Abs | Floor | Ceil | Round | Random | DiceRoll => Arithmetic,
+            Less | LessOrEq | Equals | NotEquals | And | Or | Xor | Not => Logic,
+            IfTrue | IfFalse | IfElse | While | DoWhile | Repeat | ForEach | Return | Abort
+            | CompositeCard | Try | Throw | Cond | Switch | Break | Continue => ControlFlow,
+            Call | CallNative | DynamicCall | Function | NativeFunction | Closure => Function,
+            CreateTable | SetProperty | GetProperty | Get | AppendTable | PopTable | Len | Array
+            | Map | Filter | Reduce | Zip | Enumerate => Table,
+            SetGlobalVar | SetVar | ReadVar => Variable,
+            ScalarNil | ScalarInt | ScalarFloat | StringLiteral => Literal,
+            Comment => Misc,
+        }
+    }
+
+    /// Slot labels (from [`CardKind::schema`]) where only a literal card lets
+    /// [`super::const_fold`] reason about this slot at compile time - `SetProperty`/`GetProperty`'s
+    /// `key` and `Get`'s `index`. A non-literal card is still structurally valid there.
+    fn literal_only_slots(&self) -> &'static [&'static str] {
+        use CardKind::*;
+        match self {
+            SetProperty | GetProperty => &["key"],
+            Get => &["index"],
+            _ => &[],
+        }
+    }
+
+    /// The [`PropertyKind`] a given child slot expects, for [`check_card_types`]. Unlisted
+    /// `(self, label)` pairs default to [`PropertyKind::Any`] - either because the slot really is
+    /// untyped (e.g. `SetVar`'s `value`) or because it isn't precise enough to be worth asserting.
+    fn slot_kind(&self, label: &str) -> PropertyKind {
+        use CardKind::*;
+        use PropertyKind::*;
+        match (self, label) {
+            (Add | Sub | Mul | Div | Mod | Pow | Min | Max, "lhs" | "rhs") => Number,
+            (Neg | Abs | Floor | Ceil | Round, "value") => Number,
+            (BitAnd | BitOr | BitXor | Shl | Shr | Random | DiceRoll, "lhs" | "rhs") => Integer,
+            (Less | LessOrEq, "lhs" | "rhs") => Number,
+            (And | Or | Xor, "lhs" | "rhs") => Boolean,
+            (Not, "value") => Boolean,
+            (While | DoWhile | IfTrue | IfFalse | IfElse, "condition") => Boolean,
+            (GetProperty | Get | AppendTable | SetProperty | Len | PopTable, "table") => Object,
+            (GetProperty | SetProperty, "key") => Text,
+            (Get, "index") => Integer,
+            (ForEach | Map | Filter | Reduce, "iterable") => Object,
+            (Zip, "a" | "b") => Object,
+            (Enumerate, "value") => Object,
+            (Repeat, "n") => Integer,
+            (Switch, "value") => Integer,
+            _ => Any,
+        }
+    }
+
+    /// The [`PropertyKind`] of the value this kind leaves behind for a parent slot, for
+    /// [`check_card_types`]. `None` for cards that don't produce a usable value - control-flow and
+    /// statement-like kinds only ever appear as a lane's top-level cards, never nested in a slot.
+    fn output_kind(&self) -> Option<PropertyKind> {
+        use CardKind::*;
+        use PropertyKind::*;
+        match self {
+            Add | Sub | Mul | Div | Mod | Pow | Min | Max | Neg | Abs | Floor | Ceil | Round
+            | BitAnd | BitOr | BitXor | Shl | Shr => Some(Number),
+            Less | LessOrEq | Equals | NotEquals | And | Or | Xor | Not => Some(Boolean),
+            ScalarInt | Random | DiceRoll => Some(Integer),
+            ScalarFloat => Some(Float),
+            StringLiteral => Some(Text),
+            Len => Some(Integer),
+            CreateTable | Array | Map | Filter | Reduce | Zip | Get => Some(Object),
+            ScalarNil | ReadVar | Call | CallNative | DynamicCall | GetProperty | AppendTable
+            | PopTable | Enumerate => Some(Any),
+            Return | Abort | SetGlobalVar | SetVar | SetProperty | While | DoWhile | Repeat
+            | ForEach | IfTrue | IfFalse | IfElse | CompositeCard | Function | NativeFunction
+            | Closure | Comment | Try | Throw | Cond | Switch | Break | Continue => None,
+        }
+    }
+
+    /// Builds a placeholder [`Card`] of this kind: every child slot is filled with `ScalarNil`
+    /// and the card (and any children) get fresh ids, ready for a UI to insert and then fill in.
+    pub fn default_card(&self) -> Card {
+        use CardKind::*;
+
+        let leaf = || Card::from(CardBody::ScalarNil);
+        let unary = || UnaryExpression::new(CardBody::ScalarNil);
+        let binary = || -> BinaryExpression { Box::new([leaf(), leaf()]) };
+
+        match self {
+            Add => CardBody::Add(binary()).into(),
+            Sub => CardBody::Sub(binary()).into(),
+            Mul => CardBody::Mul(binary()).into(),
+            Div => CardBody::Div(binary()).into(),
+            Mod => CardBody::Mod(binary()).into(),
+            Pow => CardBody::Pow(binary()).into(),
+            Min => CardBody::Min(binary()).into(),
+            Max => CardBody::Max(binary()).into(),
+            Random => CardBody::Random(binary()).into(),
+            DiceRoll => CardBody::DiceRoll(binary()).into(),
+            BitAnd => CardBody::BitAnd(binary()).into(),
+            BitOr => CardBody::BitOr(binary()).into(),
+            BitXor => CardBody::BitXor(binary()).into(),
+            Shl => CardBody::Shl(binary()).into(),
+            Shr => CardBody::Shr(binary()).into(),
+            Neg => CardBody::Neg(unary()).into(),
+            Abs => CardBody::Abs(unary()).into(),
+            Floor => CardBody::Floor(unary()).into(),
+            Ceil => CardBody::Ceil(unary()).into(),
+            Round => CardBody::Round(unary()).into(),
+            Less => CardBody::Less(binary()).into(),
+            LessOrEq => CardBody::LessOrEq(binary()).into(),
+            Equals => CardBody::Equals(binary()).into(),
+            NotEquals => CardBody::NotEquals(binary()).into(),
+            And => CardBody::And(binary()).into(),
+            Or => CardBody::Or(binary()).into(),
+            Xor => CardBody::Xor(binary()).into(),
+            Not => CardBody::Not(unary()).into(),
+            IfTrue => CardBody::IfTrue(binary()).into(),
+            IfFalse => CardBody::IfFalse(binary()).into(),
+            IfElse => CardBody::IfElse(Box::new([leaf(), leaf(), leaf()])).into(),
+            While => CardBody::While(Box::new([leaf(), leaf()])).into(),
+            DoWhile => CardBody::DoWhile(Box::new([leaf(), leaf()])).into(),
+            Repeat => CardBody::Repeat(Box::new(Repeat {
+                i: None,
+                n: leaf(),
+                body: leaf(),
+            }))
+            .into(),
+            ForEach => CardBody::ForEach(Box::new(ForEach {
+                i: None,
+                k: None,
+                v: None,
+                iterable: Box::new(leaf()),
+                body: Box::new(leaf()),
+            }))
+            .into(),
+            Return => CardBody::Return(unary()).into(),
+            Abort => CardBody::Abort.into(),
+            Call => CardBody::Call(Box::new(StaticJump {
+                args: Arguments::default(),
+                function_name: String::new(),
+            }))
+            .into(),
+            CallNative => CardBody::CallNative(Box::new(CallNode {
+                name: InputString::new(),
+                args: Arguments::default(),
+            }))
+            .into(),
+            DynamicCall => CardBody::DynamicCall(Box::new(DynamicJump {
+                args: Arguments::default(),
+                function: leaf(),
+            }))
+            .into(),
+            Function => CardBody::Function(String::new()).into(),
+            NativeFunction => CardBody::NativeFunction(String::new()).into(),
+            Closure => CardBody::Closure(Box::new(Function {
+                arguments: Vec::new(),
+                cards: Vec::new(),
+            }))
+            .into(),
+            CompositeCard => CardBody::CompositeCard(Box::new(CompositeCard {
+                ty: String::new(),
+                cards: Vec::new(),
+            }))
+            .into(),
+            CreateTable => CardBody::CreateTable.into(),
+            SetProperty => CardBody::SetProperty(Box::new([leaf(), leaf(), leaf()])).into(),
+            GetProperty => CardBody::GetProperty(binary()).into(),
+            Get => CardBody::Get(binary()).into(),
+            AppendTable => CardBody::AppendTable(binary()).into(),
+            PopTable => CardBody::PopTable(unary()).into(),
+            Len => CardBody::Len(unary()).into(),
+            Array => CardBody::Array(Vec::new()).into(),
+            Map => CardBody::Map(Box::new(Map {
+                iterable: Box::new(leaf()),
+                mapper: Box::new(leaf()),
+            }))
+            .into(),
+            Filter => CardBody::Filter(Box::new(Filter {
+                iterable: Box::new(leaf()),
+                predicate: Box::new(leaf()),
+            }))
+            .into(),
+            Reduce => CardBody::Reduce(Box::new(Reduce {
+                iterable: Box::new(leaf()),
+                init: Box::new(leaf()),
+                reducer: Box::new(leaf()),
+            }))
+            .into(),
+            Zip => CardBody::Zip(Box::new([leaf(), leaf()])).into(),
+            Enumerate => CardBody::Enumerate(unary()).into(),
+            SetGlobalVar => CardBody::SetGlobalVar(Box::new(SetVar {
+                name: VarName::new(),
+                value: leaf(),
+            }))
+            .into(),
+            SetVar => CardBody::SetVar(Box::new(SetVar {
+                name: VarName::new(),
+                value: leaf(),
+            }))
+            .into(),
+            ReadVar => CardBody::ReadVar(VarName::new()).into(),
+            ScalarNil => CardBody::ScalarNil.into(),
+            ScalarInt => CardBody::ScalarInt(0).into(),
+            ScalarFloat => CardBody::ScalarFloat(0.0).into(),
+            StringLiteral => CardBody::StringLiteral(String::new()).into(),
+            Comment => CardBody::Comment(String::new()).into(),
+            Try => CardBody::Try(Box::new(TryCatch {
+                body: Vec::new(),
+                handler: Vec::new(),
+                catch_var: None,
+            }))
+            .into(),
+            Throw => CardBody::Throw(unary()).into(),
+            Cond => CardBody::Cond(Box::new(Cond {
+                conditions: vec![leaf()],
+                bodies: vec![leaf()],
+                default: None,
+            }))
+            .into(),
+            Switch => CardBody::Switch(Box::new(Switch {
+                value: Box::new(leaf()),
+                keys: vec![0],
+                bodies: vec![leaf()],
+                default: None,
+            }))
+            .into(),
+            Break => CardBody::Break.into(),
+            Continue => CardBody::Continue.into(),
+        }
+    }
+}
+
+/// Coarse value kind for a [`NativeFnSchema`] input/output/constant slot - a much coarser lattice
+/// than [`Value`](crate::value::Value) itself, since this only needs to be precise enough for an
+/// editor to grey out a mismatched plug, not to drive actual type checking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PropertyKind {
+    Integer,
+    Float,
+    /// Either [`PropertyKind::Integer`] or [`PropertyKind::Float`] - used where a native genuinely
+    /// doesn't care which.
+    Number,
+    Text,
+    Object,
+    Boolean,
+    /// No constraint at all - a native that truly accepts/returns anything.
+    Any,
+}
+
+impl PropertyKind {
+    /// Whether a value of kind `self` is acceptable where `expected` was declared - the coarse
+    /// type lattice driving [`check_card_types`]: [`PropertyKind::Any`] unifies with everything in
+    /// either position, and [`PropertyKind::Number`] unifies with `Integer`/`Float` in either
+    /// direction, since it means "either is fine" rather than being its own distinct runtime kind.
+    pub fn unifies(self, expected: PropertyKind) -> bool {
+        use PropertyKind::*;
+        match (expected, self) {
+            (Any, _) | (_, Any) => true,
+            (Number, Integer | Float | Number) | (Integer | Float, Number) => true,
+            (a, b) => a == b,
+        }
+    }
+}
+
+/// Static description of a single host-registered native function, for [`NativeFunctionRegistry`].
+/// Unlike [`CardKind::schema`] (derived from [`Card::child_slots`]), none of this is derivable
+/// from the tree - a `CallNative` card only carries its callee's name, so the host has to say what
+/// that name actually takes and returns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NativeFnSchema {
+    pub name: String,
+    pub description: String,
+    pub inputs: Vec<PropertyKind>,
+    pub outputs: Vec<PropertyKind>,
+    /// Non-value configuration baked into the card at compile time rather than passed on the
+    /// stack - e.g. a key name literal. Mirrors the `literal_only` slots in [`CardSlotSchema`].
+    pub constants: Vec<PropertyKind>,
+}
+
+impl NativeFnSchema {
+    pub fn new(name: impl Into<String>, description: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            constants: Vec::new(),
+        }
+    }
+
+    pub fn with_inputs(mut self, inputs: impl IntoIterator<Item = PropertyKind>) -> Self {
+        self.inputs = inputs.into_iter().collect();
+        self
+    }
+
+    pub fn with_outputs(mut self, outputs: impl IntoIterator<Item = PropertyKind>) -> Self {
+        self.outputs = outputs.into_iter().collect();
+        self
+    }
+
+    pub fn with_constants(mut self, constants: impl IntoIterator<Item = PropertyKind>) -> Self {
+        self.constants = constants.into_iter().collect();
+        self
+    }
+}
+
+/// A host-maintained catalog of its own [`CardBody::CallNative`] functions, keyed by the name a
+/// `CallNative` card's [`CallNode::name`] would carry. Pass this to [`instruction_descriptions`]
+/// to fold the game's own API into the same catalog as the built-in [`CardKind`]s, so editor
+/// tooling gets one complete, self-describing list instead of having to special-case natives.
+#[derive(Debug, Clone, Default)]
+pub struct NativeFunctionRegistry {
+    functions: Vec<NativeFnSchema>,
+}
+
+impl NativeFunctionRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, schema: NativeFnSchema) -> &mut Self {
+        self.functions.push(schema);
+        self
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &NativeFnSchema> {
+        self.functions.iter()
+    }
+
+    /// Looks up a registered native by the name its `CallNative` cards would carry.
+    pub fn get(&self, name: &str) -> Option<&NativeFnSchema> {
+        self.functions.iter().find(|f| f.name == name)
+    }
+}
+
+/// One entry in the catalog built by [`instruction_descriptions`]: either a built-in [`CardKind`]
+/// or a host-registered native function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CatalogEntry {
+    Builtin(CardSchema),
+    Native(NativeFnSchema),
+}
+
+/// The complete, self-describing card catalog for editor tooling: every built-in [`CardKind`] (see
+/// [`CardKind::schema`]), plus every native function the host registered in `registry` - so a
+/// palette built from this sees the game's own API alongside the core instruction set, not just
+/// `CallNative` as one opaque, unparameterized entry.
+pub fn instruction_descriptions(registry: &NativeFunctionRegistry) -> Vec<CatalogEntry> {
+    CardKind::all()
+        .iter()
+        .map(|kind| CatalogEntry::Builtin(kind.schema()))
+        .chain(registry.iter().cloned().map(CatalogEntry::Native))
+        .collect()
+}
+
+/// Recursively type-checks `card` and its children against [`CardKind::slot_kind`]/
+/// [`CardKind::output_kind`] (and, for `CallNative`, the matching entry in `registry`), returning
+/// the [`PropertyKind`] `card` itself leaves behind for its parent slot - `None` for statement-like
+/// kinds (see [`CardKind::output_kind`]) that can't be nested in one.
+///
+/// Each child is checked before the slot that holds it, so the first mismatch found is always the
+/// deepest one - the actual cause rather than a symptom further up the tree.
+pub fn check_card_types(
+    card: &Card,
+    registry: &NativeFunctionRegistry,
+) -> Result<Option<PropertyKind>, CompilationErrorPayload> {
+    let kind = card.kind();
+    let schema = kind.schema();
+    let slot_kind = |label: &str| {
+        schema
+            .slots
+            .iter()
+            .find(|s| s.label == label)
+            .map(|s| s.kind)
+            .unwrap_or(PropertyKind::Any)
+    };
+
+    let check_one = |label: &'static str,
+                      child: &Card,
+                      expected: PropertyKind|
+     -> Result<(), CompilationErrorPayload> {
+        if let Some(actual) = check_card_types(child, registry)? {
+            if !actual.unifies(expected) {
+                return Err(CompilationErrorPayload::TypeMismatch {
+                    card: kind.name().to_string(),
+                    slot: label.to_string(),
+                    expected,
+                    actual,
+                });
+            }
+        }
+        Ok(())
+    };
+
+    for slot in card.child_slots() {
+        match slot {
+            ChildSlot::One(label, child) => check_one(label, child, slot_kind(label))?,
+            ChildSlot::Many(label, children) => {
+                // `CallNative`'s args are typed per-position by the registered native, not by a
+                // single slot-wide `PropertyKind` - everything else falls back to the generic path.
+                let native = match &card.body {
+                    CardBody::CallNative(node) => registry.get(node.name.as_str()),
+                    _ => None,
+                };
+                match native {
+                    Some(native) => {
+                        if children.len() < native.inputs.len() {
+                            return Err(CompilationErrorPayload::StackUnderflow {
+                                card: native.name.clone(),
+                                needed: native.inputs.len(),
+                                found: children.len(),
+                            });
+                        }
+                        for (child, &expected) in children.iter().zip(native.inputs.iter()) {
+                            check_one(label, child, expected)?;
+                        }
+                        for child in children.iter().skip(native.inputs.len()) {
+                            check_card_types(child, registry)?;
+                        }
+                    }
+                    None => {
+                        let expected = slot_kind(label);
+                        for child in children {
+                            check_one(label, child, expected)?;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(kind.output_kind())
+}
+
+/// A single step of a [`CardPath`]: either a literal child index (as accepted by
+/// [`Card::get_child`]/[`Card::get_child_mut`]), or a selector that matches the first
+/// [`CardBody::CompositeCard`] child tagged with the given `ty`, so tooling can address e.g. "the
+/// card inside the composite tagged `on_tick`" without hard-coding its position among siblings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PathStep {
+    Index(usize),
+    CompositeCardTy(String),
+}
+
+/// An ordered sequence of [`PathStep`]s addressing a card nested inside another, read from the
+/// root down. Walking a `CardPath` with [`Card::get_at`]/[`Card::get_at_mut`] replaces hand-rolled
+/// chains of [`Card::get_child`]/[`Card::get_child_mut`] calls for deep structural edits.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CardPath(pub Vec<PathStep>);
+
+impl CardPath {
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    pub fn depth(&self) -> usize {
+        self.0.len()
+    }
+
+    #[must_use]
+    pub fn with_index(mut self, i: usize) -> Self {
+        self.0.push(PathStep::Index(i));
+        self
+    }
+
+    #[must_use]
+    pub fn with_composite_ty(mut self, ty: impl Into<String>) -> Self {
+        self.0.push(PathStep::CompositeCardTy(ty.into()));
+        self
+    }
+}
+
+impl From<Vec<PathStep>> for CardPath {
+    fn from(steps: Vec<PathStep>) -> Self {
+        Self(steps)
+    }
+}
+
+#[derive(Debug, Clone, Error)]
+pub enum CardPathError {
+    #[error("no card at depth {depth}")]
+    CardNotFound { depth: usize },
+    #[error("no CompositeCard tagged {ty:?} at depth {depth}")]
+    CompositeCardNotFound { depth: usize, ty: String },
+}
+
+impl Card {
+    /// Resolves `step` to a literal child index of `card`, for use with
+    /// [`Card::get_child`]/[`Card::get_child_mut`]/[`Card::insert_child`]/[`Card::remove_child`].
+    fn resolve_path_step(
+        card: &Card,
+        step: &PathStep,
+        depth: usize,
+    ) -> Result<usize, CardPathError> {
+        match step {
+            PathStep::Index(i) => Ok(*i),
+            PathStep::CompositeCardTy(ty) => card
+                .iter_children()
+                .position(|c| matches!(&c.body, CardBody::CompositeCard(cc) if &cc.ty == ty))
+                .ok_or_else(|| CardPathError::CompositeCardNotFound {
+                    depth,
+                    ty: ty.clone(),
+                }),
+        }
+    }
+
+    /// The card reached by following `path` from `self`, or an error naming the depth at which
+    /// traversal failed.
+    pub fn get_at(&self, path: &CardPath) -> Result<&Card, CardPathError> {
+        let mut card = self;
+        for (depth, step) in path.0.iter().enumerate() {
+            let i = Self::resolve_path_step(card, step, depth)?;
+            card = card
+                .get_child(i)
+                .ok_or(CardPathError::CardNotFound { depth })?;
+        }
+        Ok(card)
+    }
+
+    /// The `_mut` counterpart of [`Card::get_at`].
+    pub fn get_at_mut(&mut self, path: &CardPath) -> Result<&mut Card, CardPathError> {
+        let mut card = self;
+        for (depth, step) in path.0.iter().enumerate() {
+            let i = Self::resolve_path_step(card, step, depth)?;
+            card = card
+                .get_child_mut(i)
+                .ok_or(CardPathError::CardNotFound { depth })?;
+        }
+        Ok(card)
+    }
+
+    /// Replaces the card at `path`, returning the card that was there.
+    pub fn replace_at(&mut self, path: &CardPath, card: Card) -> Result<Card, CardPathError> {
+        self.get_at_mut(path)
+            .map(|slot| core::mem::replace(slot, card))
+    }
+
+    /// Removes the card at `path`, returning it. All but the last step of `path` addresses the
+    /// parent; the last step is resolved against the parent and passed to
+    /// [`Card::remove_child`].
+    pub fn remove_at(&mut self, path: &CardPath) -> Result<Card, CardPathError> {
+        let (last, init) = path
+            .0
+            .split_last()
+            .ok_or(CardPathError::CardNotFound { depth: 0 })?;
+        let depth = init.len();
+        let parent = self.get_at_mut(&CardPath(init.to_vec()))?;
+        let i = Self::resolve_path_step(parent, last, depth)?;
+        parent
+            .remove_child(i)
+            .ok_or(CardPathError::CardNotFound { depth })
+    }
+
+    /// Inserts `card` at `path`. All but the last step of `path` addresses the parent; the last
+    /// step is resolved against the parent and passed to [`Card::insert_child`].
+    pub fn insert_at(&mut self, path: &CardPath, card: Card) -> Result<(), CardPathError> {
+        let (last, init) = path
+            .0
+            .split_last()
+            .ok_or(CardPathError::CardNotFound { depth: 0 })?;
+        let depth = init.len();
+        let parent = self.get_at_mut(&CardPath(init.to_vec()))?;
+        let i = Self::resolve_path_step(parent, last, depth)?;
+        parent
+            .insert_child(i, card)
+            .map_err(|_| CardPathError::CardNotFound { depth })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -886,4 +2068,68 @@ mod tests {
             assert!(matches!(Some(a), _b));
         }
     }
+
+    #[test]
+    fn test_instruction_descriptions_includes_registered_natives() {
+        let mut registry = NativeFunctionRegistry::new();
+        registry.register(
+            NativeFnSchema::new("move_to", "Move the current unit to the given position")
+                .with_inputs([PropertyKind::Object])
+                .with_outputs([PropertyKind::Boolean]),
+        );
+
+        let catalog = instruction_descriptions(&registry);
+
+        assert!(catalog
+            .iter()
+            .any(|entry| matches!(entry, CatalogEntry::Builtin(schema) if schema.kind == CardKind::CallNative)));
+        assert!(catalog.iter().any(
+            |entry| matches!(entry, CatalogEntry::Native(schema) if schema.name == "move_to")
+        ));
+    }
+
+    #[test]
+    fn test_check_card_types_catches_mismatched_arithmetic_operand() {
+        let card: Card = CardBody::Add(Box::new([
+            CardBody::ScalarInt(1).into(),
+            CardBody::StringLiteral("nope".to_string()).into(),
+        ]))
+        .into();
+
+        let registry = NativeFunctionRegistry::new();
+        let err = check_card_types(&card, &registry).unwrap_err();
+        assert!(matches!(err, CompilationErrorPayload::TypeMismatch { .. }));
+    }
+
+    #[test]
+    fn test_check_card_types_catches_missing_native_argument() {
+        let card: Card = CardBody::CallNative(Box::new(CallNode {
+            name: "move_to".to_string(),
+            args: Arguments(Vec::new()),
+        }))
+        .into();
+
+        let mut registry = NativeFunctionRegistry::new();
+        registry.register(
+            NativeFnSchema::new("move_to", "Move to a position").with_inputs([PropertyKind::Object]),
+        );
+
+        let err = check_card_types(&card, &registry).unwrap_err();
+        assert!(matches!(err, CompilationErrorPayload::StackUnderflow { .. }));
+    }
+
+    #[test]
+    fn test_check_card_types_accepts_well_typed_tree() {
+        let card: Card = CardBody::Add(Box::new([
+            CardBody::ScalarInt(1).into(),
+            CardBody::ScalarFloat(2.0).into(),
+        ]))
+        .into();
+
+        let registry = NativeFunctionRegistry::new();
+        assert_eq!(
+            check_card_types(&card, &registry).unwrap(),
+            Some(PropertyKind::Number)
+        );
+    }
 }
@@ -0,0 +1,927 @@
+//! Compact binary codec for [`Card`] trees.
+//!
+//! The comment on [`UnaryExpression`] notes that some serialization formats (e.g. YAML) can't
+//! nest `Card`s, and even where serde works its JSON/YAML output is bulky. [`Card::encode_binary`]
+//! / [`Card::decode_binary`] give hosts a compact, self-describing wire format to persist and ship
+//! compiled card programs instead.
+//!
+//! Each node is written as `id: u64`, `tag: u8`, then whatever scalar/string fields that variant
+//! carries, then its child cards in left-to-right order. Fixed-arity variants (e.g. `Add`'s two
+//! operands) need no length prefix - the tag alone tells the decoder how many children follow;
+//! variadic slots (`Array`, `CallNative`'s args, ...) write an explicit `u32` count first.
+//!
+//! [`BinTag`] assigns every variant a fixed, hand-written number that is never reassigned, so this
+//! format stays stable across `CardBody` additions or reorderings - unlike deriving the tag from
+//! declaration order, which would shift every time a variant is inserted in the middle.
+//!
+//! Both directions walk the tree with an explicit work-stack rather than native recursion, so a
+//! pathologically deep program can't blow the call stack: [`Card::encode_binary`] is a flat
+//! preorder traversal (push self, pop, write header, push children in reverse so they come off in
+//! left-to-right order); [`Card::decode_binary`] is the inverse shift-reduce - read a header, and
+//! if it still needs children keep reading; once a node has all its children, fold it into its
+//! parent, repeating for any ancestor that completes as a result.
+
+use core::convert::TryFrom;
+
+use thiserror::Error;
+
+use crate::alloc_crate::{
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use crate::bytecode::{decode_str, encode_str, read_from_bytes, write_to_vec, TriviallyEncodable};
+
+use super::*;
+
+/// Stable wire discriminant for a [`CardBody`] variant. Numbers are assigned once, by hand, and
+/// never reused or reassigned - see the module docs.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, num_enum::TryFromPrimitive)]
+#[repr(u8)]
+enum BinTag {
+    Add = 0,
+    Sub = 1,
+    Mul = 2,
+    Div = 3,
+    Less = 4,
+    LessOrEq = 5,
+    Equals = 6,
+    NotEquals = 7,
+    And = 8,
+    Or = 9,
+    Xor = 10,
+    Not = 11,
+    Return = 12,
+    ScalarNil = 13,
+    CreateTable = 14,
+    Abort = 15,
+    Len = 16,
+    SetProperty = 17,
+    GetProperty = 18,
+    ScalarInt = 19,
+    ScalarFloat = 20,
+    StringLiteral = 21,
+    CallNative = 22,
+    IfTrue = 23,
+    IfFalse = 24,
+    IfElse = 25,
+    Call = 26,
+    Function = 27,
+    NativeFunction = 28,
+    SetGlobalVar = 29,
+    SetVar = 30,
+    ReadVar = 31,
+    Repeat = 32,
+    While = 33,
+    ForEach = 34,
+    CompositeCard = 35,
+    DynamicCall = 36,
+    Get = 37,
+    AppendTable = 38,
+    PopTable = 39,
+    Array = 40,
+    Closure = 41,
+    Comment = 42,
+    Map = 43,
+    Filter = 44,
+    Reduce = 45,
+    Zip = 46,
+    Enumerate = 47,
+    Mod = 48,
+    Pow = 49,
+    Neg = 50,
+    Abs = 51,
+    Min = 52,
+    Max = 53,
+    Floor = 54,
+    Ceil = 55,
+    Round = 56,
+    BitAnd = 57,
+    BitOr = 58,
+    BitXor = 59,
+    Shl = 60,
+    Shr = 61,
+    Try = 62,
+    Throw = 63,
+    Cond = 64,
+    Random = 65,
+    DiceRoll = 66,
+    Break = 67,
+    Continue = 68,
+    Switch = 69,
+    DoWhile = 70,
+}
+
+#[derive(Debug, Clone, Error)]
+pub enum DecodeError {
+    #[error("unexpected end of input")]
+    UnexpectedEof,
+    #[error("{0} is not a known card tag")]
+    UnknownTag(u8),
+}
+
+/// Non-card payload collected while reading a node's header, held until enough children have
+/// arrived to call [`finalize`].
+enum Scratch {
+    None,
+    Int(i64),
+    Float(f64),
+    Str(String),
+    OptStr(Option<String>),
+    ForEach {
+        i: Option<String>,
+        k: Option<String>,
+        v: Option<String>,
+    },
+    DynamicCall {
+        n_args: usize,
+    },
+    Strings(Vec<String>),
+    /// Carries `Try`'s body length; the handler length is `remaining - body_len` once every child
+    /// has been read, since [`read_header`] only returns a single child count.
+    Try {
+        body_len: usize,
+        catch_var: Option<String>,
+    },
+    /// Carries `Cond`'s branch count and whether a trailing `default` child follows; children
+    /// arrive as `n` conditions, then `n` bodies, then the optional `default`.
+    Cond {
+        n: usize,
+        has_default: bool,
+    },
+    /// Carries `Switch`'s integer keys (read up front, parallel to the `n` bodies that follow)
+    /// and whether a trailing `default` child follows. Children arrive as `value`, then `n`
+    /// bodies, then the optional `default`.
+    Switch {
+        keys: Vec<i64>,
+        has_default: bool,
+    },
+}
+
+fn tag_of(body: &CardBody) -> BinTag {
+    match body {
+        CardBody::Add(_) => BinTag::Add,
+        CardBody::Sub(_) => BinTag::Sub,
+        CardBody::Mul(_) => BinTag::Mul,
+        CardBody::Div(_) => BinTag::Div,
+        CardBody::Less(_) => BinTag::Less,
+        CardBody::LessOrEq(_) => BinTag::LessOrEq,
+        CardBody::Equals(_) => BinTag::Equals,
+        CardBody::NotEquals(_) => BinTag::NotEquals,
+        CardBody::And(_) => BinTag::And,
+        CardBody::Or(_) => BinTag::Or,
+        CardBody::Xor(_) => BinTag::Xor,
+        CardBody::Not(_) => BinTag::Not,
+        CardBody::Return(_) => BinTag::Return,
+        CardBody::ScalarNil => BinTag::ScalarNil,
+        CardBody::CreateTable => BinTag::CreateTable,
+        CardBody::Abort => BinTag::Abort,
+        CardBody::Len(_) => BinTag::Len,
+        CardBody::SetProperty(_) => BinTag::SetProperty,
+        CardBody::GetProperty(_) => BinTag::GetProperty,
+        CardBody::ScalarInt(_) => BinTag::ScalarInt,
+        CardBody::ScalarFloat(_) => BinTag::ScalarFloat,
+        CardBody::StringLiteral(_) => BinTag::StringLiteral,
+        CardBody::CallNative(_) => BinTag::CallNative,
+        CardBody::IfTrue(_) => BinTag::IfTrue,
+        CardBody::IfFalse(_) => BinTag::IfFalse,
+        CardBody::IfElse(_) => BinTag::IfElse,
+        CardBody::Call(_) => BinTag::Call,
+        CardBody::Function(_) => BinTag::Function,
+        CardBody::NativeFunction(_) => BinTag::NativeFunction,
+        CardBody::SetGlobalVar(_) => BinTag::SetGlobalVar,
+        CardBody::SetVar(_) => BinTag::SetVar,
+        CardBody::ReadVar(_) => BinTag::ReadVar,
+        CardBody::Repeat(_) => BinTag::Repeat,
+        CardBody::While(_) => BinTag::While,
+        CardBody::DoWhile(_) => BinTag::DoWhile,
+        CardBody::ForEach(_) => BinTag::ForEach,
+        CardBody::CompositeCard(_) => BinTag::CompositeCard,
+        CardBody::DynamicCall(_) => BinTag::DynamicCall,
+        CardBody::Get(_) => BinTag::Get,
+        CardBody::AppendTable(_) => BinTag::AppendTable,
+        CardBody::PopTable(_) => BinTag::PopTable,
+        CardBody::Array(_) => BinTag::Array,
+        CardBody::Closure(_) => BinTag::Closure,
+        CardBody::Comment(_) => BinTag::Comment,
+        CardBody::Map(_) => BinTag::Map,
+        CardBody::Filter(_) => BinTag::Filter,
+        CardBody::Reduce(_) => BinTag::Reduce,
+        CardBody::Zip(_) => BinTag::Zip,
+        CardBody::Enumerate(_) => BinTag::Enumerate,
+        CardBody::Mod(_) => BinTag::Mod,
+        CardBody::Pow(_) => BinTag::Pow,
+        CardBody::Neg(_) => BinTag::Neg,
+        CardBody::Abs(_) => BinTag::Abs,
+        CardBody::Min(_) => BinTag::Min,
+        CardBody::Max(_) => BinTag::Max,
+        CardBody::Random(_) => BinTag::Random,
+        CardBody::DiceRoll(_) => BinTag::DiceRoll,
+        CardBody::Floor(_) => BinTag::Floor,
+        CardBody::Ceil(_) => BinTag::Ceil,
+        CardBody::Round(_) => BinTag::Round,
+        CardBody::BitAnd(_) => BinTag::BitAnd,
+        CardBody::BitOr(_) => BinTag::BitOr,
+        CardBody::BitXor(_) => BinTag::BitXor,
+        CardBody::Shl(_) => BinTag::Shl,
+        CardBody::Shr(_) => BinTag::Shr,
+        CardBody::Try(_) => BinTag::Try,
+        CardBody::Throw(_) => BinTag::Throw,
+        CardBody::Cond(_) => BinTag::Cond,
+        CardBody::Switch(_) => BinTag::Switch,
+        CardBody::Break => BinTag::Break,
+        CardBody::Continue => BinTag::Continue,
+    }
+}
+
+fn encode_opt_str(s: &Option<String>, out: &mut Vec<u8>) {
+    match s {
+        Some(s) => {
+            write_to_vec(1u8, out);
+            encode_str(s, out);
+        }
+        None => write_to_vec(0u8, out),
+    }
+}
+
+/// Writes `card`'s header (id, tag, scalar/string payload, variadic counts) to `out` and returns
+/// its child cards in left-to-right order.
+fn encode_header<'a>(card: &'a Card, out: &mut Vec<u8>) -> Vec<&'a Card> {
+    write_to_vec(card.id.0, out);
+    write_to_vec(tag_of(&card.body) as u8, out);
+    match &card.body {
+        CardBody::Add(b)
+        | CardBody::Sub(b)
+        | CardBody::Mul(b)
+        | CardBody::Div(b)
+        | CardBody::Less(b)
+        | CardBody::LessOrEq(b)
+        | CardBody::Equals(b)
+        | CardBody::NotEquals(b)
+        | CardBody::And(b)
+        | CardBody::Or(b)
+        | CardBody::Xor(b)
+        | CardBody::GetProperty(b)
+        | CardBody::IfTrue(b)
+        | CardBody::IfFalse(b)
+        | CardBody::Get(b)
+        | CardBody::AppendTable(b)
+        | CardBody::Mod(b)
+        | CardBody::Pow(b)
+        | CardBody::Min(b)
+        | CardBody::Max(b)
+        | CardBody::Random(b)
+        | CardBody::DiceRoll(b)
+        | CardBody::BitAnd(b)
+        | CardBody::BitOr(b)
+        | CardBody::BitXor(b)
+        | CardBody::Shl(b)
+        | CardBody::Shr(b)
+        | CardBody::While(b)
+        | CardBody::DoWhile(b)
+        | CardBody::Zip(b) => vec![&b[0], &b[1]],
+        CardBody::Not(u)
+        | CardBody::Return(u)
+        | CardBody::Len(u)
+        | CardBody::PopTable(u)
+        | CardBody::Neg(u)
+        | CardBody::Abs(u)
+        | CardBody::Floor(u)
+        | CardBody::Ceil(u)
+        | CardBody::Round(u)
+        | CardBody::Enumerate(u)
+        | CardBody::Throw(u) => vec![&*u.card],
+        CardBody::ScalarNil
+        | CardBody::CreateTable
+        | CardBody::Abort
+        | CardBody::Break
+        | CardBody::Continue => vec![],
+        CardBody::SetProperty(b) | CardBody::IfElse(b) => vec![&b[0], &b[1], &b[2]],
+        CardBody::ScalarInt(v) => {
+            write_to_vec(*v, out);
+            vec![]
+        }
+        CardBody::ScalarFloat(v) => {
+            write_to_vec(*v, out);
+            vec![]
+        }
+        CardBody::StringLiteral(s)
+        | CardBody::Function(s)
+        | CardBody::NativeFunction(s)
+        | CardBody::Comment(s)
+        | CardBody::ReadVar(s) => {
+            encode_str(s, out);
+            vec![]
+        }
+        CardBody::CallNative(c) => {
+            encode_str(&c.name, out);
+            write_to_vec(c.args.0.len() as u32, out);
+            c.args.0.iter().collect()
+        }
+        CardBody::Call(c) => {
+            write_to_vec(c.args.0.len() as u32, out);
+            encode_str(&c.function_name, out);
+            c.args.0.iter().collect()
+        }
+        CardBody::SetGlobalVar(sv) | CardBody::SetVar(sv) => {
+            encode_str(&sv.name, out);
+            vec![&sv.value]
+        }
+        CardBody::Repeat(r) => {
+            encode_opt_str(&r.i, out);
+            vec![&r.n, &r.body]
+        }
+        CardBody::ForEach(fe) => {
+            encode_opt_str(&fe.i, out);
+            encode_opt_str(&fe.k, out);
+            encode_opt_str(&fe.v, out);
+            vec![&*fe.iterable, &*fe.body]
+        }
+        CardBody::CompositeCard(cc) => {
+            encode_str(&cc.ty, out);
+            write_to_vec(cc.cards.len() as u32, out);
+            cc.cards.iter().collect()
+        }
+        CardBody::DynamicCall(dc) => {
+            write_to_vec(dc.args.0.len() as u32, out);
+            let mut children: Vec<&Card> = dc.args.0.iter().collect();
+            children.push(&dc.function);
+            children
+        }
+        CardBody::Array(cards) => {
+            write_to_vec(cards.len() as u32, out);
+            cards.iter().collect()
+        }
+        CardBody::Closure(f) => {
+            write_to_vec(f.arguments.len() as u32, out);
+            for a in &f.arguments {
+                encode_str(a, out);
+            }
+            write_to_vec(f.cards.len() as u32, out);
+            f.cards.iter().collect()
+        }
+        CardBody::Map(m) => vec![&*m.iterable, &*m.mapper],
+        CardBody::Filter(f) => vec![&*f.iterable, &*f.predicate],
+        CardBody::Reduce(r) => vec![&*r.iterable, &*r.init, &*r.reducer],
+        CardBody::Try(t) => {
+            write_to_vec(t.body.len() as u32, out);
+            write_to_vec(t.handler.len() as u32, out);
+            encode_opt_str(&t.catch_var, out);
+            t.body.iter().chain(t.handler.iter()).collect()
+        }
+        CardBody::Cond(c) => {
+            write_to_vec(c.conditions.len() as u32, out);
+            write_to_vec(c.default.is_some() as u8, out);
+            c.conditions
+                .iter()
+                .chain(c.bodies.iter())
+                .chain(c.default.iter())
+                .collect()
+        }
+        CardBody::Switch(s) => {
+            write_to_vec(s.bodies.len() as u32, out);
+            write_to_vec(s.default.is_some() as u8, out);
+            for key in &s.keys {
+                write_to_vec(*key, out);
+            }
+            core::iter::once(&*s.value)
+                .chain(s.bodies.iter())
+                .chain(s.default.iter())
+                .collect()
+        }
+    }
+}
+
+fn read_val<T: TriviallyEncodable>(bytes: &[u8], pos: &mut usize) -> Result<T, DecodeError> {
+    let (n, v) = read_from_bytes(&bytes[*pos..]).ok_or(DecodeError::UnexpectedEof)?;
+    *pos += n;
+    Ok(v)
+}
+
+fn read_str(bytes: &[u8], pos: &mut usize) -> Result<String, DecodeError> {
+    let (n, s) = decode_str(&bytes[*pos..]).ok_or(DecodeError::UnexpectedEof)?;
+    *pos += n;
+    Ok(s.to_string())
+}
+
+fn read_opt_str(bytes: &[u8], pos: &mut usize) -> Result<Option<String>, DecodeError> {
+    let has = read_val::<u8>(bytes, pos)?;
+    if has != 0 {
+        Ok(Some(read_str(bytes, pos)?))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Reads one node's header and returns its id, tag, scratch payload, and the number of child
+/// cards still to be read from the stream.
+fn read_header(
+    bytes: &[u8],
+    pos: &mut usize,
+) -> Result<(CardId, BinTag, Scratch, usize), DecodeError> {
+    let id = CardId(read_val::<u64>(bytes, pos)?);
+    let tag_byte = read_val::<u8>(bytes, pos)?;
+    let tag = BinTag::try_from(tag_byte).map_err(|_| DecodeError::UnknownTag(tag_byte))?;
+
+    use BinTag::*;
+    let (scratch, remaining) = match tag {
+        Add | Sub | Mul | Div | Less | LessOrEq | Equals | NotEquals | And | Or | Xor
+        | GetProperty | IfTrue | IfFalse | Get | AppendTable | Mod | Pow | Min | Max | Random
+        | DiceRoll | BitAnd | BitOr | BitXor | Shl | Shr | While | DoWhile | Zip | Map
+        | Filter => (Scratch::None, 2),
+        Not | Return | Len | PopTable | Neg | Abs | Floor | Ceil | Round | Enumerate | Throw => {
+            (Scratch::None, 1)
+        }
+        ScalarNil | CreateTable | Abort | Break | Continue => (Scratch::None, 0),
+        Switch => {
+            let n = read_val::<u32>(bytes, pos)? as usize;
+            let has_default = read_val::<u8>(bytes, pos)? != 0;
+            let mut keys = Vec::with_capacity(n);
+            for _ in 0..n {
+                keys.push(read_val::<i64>(bytes, pos)?);
+            }
+            (
+                Scratch::Switch { keys, has_default },
+                1 + n + has_default as usize,
+            )
+        }
+        SetProperty | IfElse | Reduce => (Scratch::None, 3),
+        ScalarInt => (Scratch::Int(read_val::<i64>(bytes, pos)?), 0),
+        ScalarFloat => (Scratch::Float(read_val::<f64>(bytes, pos)?), 0),
+        StringLiteral | Function | NativeFunction | Comment | ReadVar => {
+            (Scratch::Str(read_str(bytes, pos)?), 0)
+        }
+        CallNative => {
+            let name = read_str(bytes, pos)?;
+            let n_args = read_val::<u32>(bytes, pos)? as usize;
+            (Scratch::Str(name), n_args)
+        }
+        Call => {
+            let n_args = read_val::<u32>(bytes, pos)? as usize;
+            let function_name = read_str(bytes, pos)?;
+            (Scratch::Str(function_name), n_args)
+        }
+        SetGlobalVar | SetVar => {
+            let name = read_str(bytes, pos)?;
+            (Scratch::Str(name), 1)
+        }
+        Repeat => {
+            let i = read_opt_str(bytes, pos)?;
+            (Scratch::OptStr(i), 2)
+        }
+        ForEach => {
+            let i = read_opt_str(bytes, pos)?;
+            let k = read_opt_str(bytes, pos)?;
+            let v = read_opt_str(bytes, pos)?;
+            (Scratch::ForEach { i, k, v }, 2)
+        }
+        CompositeCard => {
+            let ty = read_str(bytes, pos)?;
+            let n = read_val::<u32>(bytes, pos)? as usize;
+            (Scratch::Str(ty), n)
+        }
+        DynamicCall => {
+            let n_args = read_val::<u32>(bytes, pos)? as usize;
+            (Scratch::DynamicCall { n_args }, n_args + 1)
+        }
+        Array => {
+            let n = read_val::<u32>(bytes, pos)? as usize;
+            (Scratch::None, n)
+        }
+        Closure => {
+            let n_args = read_val::<u32>(bytes, pos)? as usize;
+            let mut arguments = Vec::with_capacity(n_args);
+            for _ in 0..n_args {
+                arguments.push(read_str(bytes, pos)?);
+            }
+            let n_cards = read_val::<u32>(bytes, pos)? as usize;
+            (Scratch::Strings(arguments), n_cards)
+        }
+        Try => {
+            let body_len = read_val::<u32>(bytes, pos)? as usize;
+            let handler_len = read_val::<u32>(bytes, pos)? as usize;
+            let catch_var = read_opt_str(bytes, pos)?;
+            (Scratch::Try { body_len, catch_var }, body_len + handler_len)
+        }
+        Cond => {
+            let n = read_val::<u32>(bytes, pos)? as usize;
+            let has_default = read_val::<u8>(bytes, pos)? != 0;
+            (Scratch::Cond { n, has_default }, 2 * n + has_default as usize)
+        }
+    };
+    Ok((id, tag, scratch, remaining))
+}
+
+fn take1(children: Vec<Card>) -> [Card; 1] {
+    children.try_into().expect("exactly 1 child")
+}
+
+fn take2(children: Vec<Card>) -> [Card; 2] {
+    children.try_into().expect("exactly 2 children")
+}
+
+fn take3(children: Vec<Card>) -> [Card; 3] {
+    children.try_into().expect("exactly 3 children")
+}
+
+fn bin(children: Vec<Card>) -> BinaryExpression {
+    Box::new(take2(children))
+}
+
+fn un(children: Vec<Card>) -> UnaryExpression {
+    let [card] = take1(children);
+    UnaryExpression::new(card)
+}
+
+/// Rebuilds a `CardBody` from its tag, the scratch payload collected in [`read_header`], and its
+/// already-decoded children, in the same left-to-right order [`encode_header`] emitted them.
+fn finalize(tag: BinTag, scratch: Scratch, children: Vec<Card>) -> CardBody {
+    use BinTag::*;
+    match tag {
+        Add => CardBody::Add(bin(children)),
+        Sub => CardBody::Sub(bin(children)),
+        Mul => CardBody::Mul(bin(children)),
+        Div => CardBody::Div(bin(children)),
+        Less => CardBody::Less(bin(children)),
+        LessOrEq => CardBody::LessOrEq(bin(children)),
+        Equals => CardBody::Equals(bin(children)),
+        NotEquals => CardBody::NotEquals(bin(children)),
+        And => CardBody::And(bin(children)),
+        Or => CardBody::Or(bin(children)),
+        Xor => CardBody::Xor(bin(children)),
+        GetProperty => CardBody::GetProperty(bin(children)),
+        IfTrue => CardBody::IfTrue(bin(children)),
+        IfFalse => CardBody::IfFalse(bin(children)),
+        Get => CardBody::Get(bin(children)),
+        AppendTable => CardBody::AppendTable(bin(children)),
+        Mod => CardBody::Mod(bin(children)),
+        Pow => CardBody::Pow(bin(children)),
+        Min => CardBody::Min(bin(children)),
+        Max => CardBody::Max(bin(children)),
+        Random => CardBody::Random(bin(children)),
+        DiceRoll => CardBody::DiceRoll(bin(children)),
+        BitAnd => CardBody::BitAnd(bin(children)),
+        BitOr => CardBody::BitOr(bin(children)),
+        BitXor => CardBody::BitXor(bin(children)),
+        Shl => CardBody::Shl(bin(children)),
+        Shr => CardBody::Shr(bin(children)),
+        While => CardBody::While(bin(children)),
+        DoWhile => CardBody::DoWhile(bin(children)),
+        Zip => CardBody::Zip(bin(children)),
+        Map => {
+            let [iterable, mapper] = take2(children);
+            CardBody::Map(Box::new(Map {
+                iterable: Box::new(iterable),
+                mapper: Box::new(mapper),
+            }))
+        }
+        Filter => {
+            let [iterable, predicate] = take2(children);
+            CardBody::Filter(Box::new(Filter {
+                iterable: Box::new(iterable),
+                predicate: Box::new(predicate),
+            }))
+        }
+        Not => CardBody::Not(un(children)),
+        Return => CardBody::Return(un(children)),
+        Len => CardBody::Len(un(children)),
+        PopTable => CardBody::PopTable(un(children)),
+        Neg => CardBody::Neg(un(children)),
+        Abs => CardBody::Abs(un(children)),
+        Floor => CardBody::Floor(un(children)),
+        Ceil => CardBody::Ceil(un(children)),
+        Round => CardBody::Round(un(children)),
+        Enumerate => CardBody::Enumerate(un(children)),
+        Throw => CardBody::Throw(un(children)),
+        ScalarNil => CardBody::ScalarNil,
+        CreateTable => CardBody::CreateTable,
+        Abort => CardBody::Abort,
+        Break => CardBody::Break,
+        Continue => CardBody::Continue,
+        SetProperty => CardBody::SetProperty(Box::new(take3(children))),
+        IfElse => CardBody::IfElse(Box::new(take3(children))),
+        Reduce => {
+            let [iterable, init, reducer] = take3(children);
+            CardBody::Reduce(Box::new(Reduce {
+                iterable: Box::new(iterable),
+                init: Box::new(init),
+                reducer: Box::new(reducer),
+            }))
+        }
+        ScalarInt => match scratch {
+            Scratch::Int(v) => CardBody::ScalarInt(v),
+            _ => unreachable!("ScalarInt always carries Scratch::Int"),
+        },
+        ScalarFloat => match scratch {
+            Scratch::Float(v) => CardBody::ScalarFloat(v),
+            _ => unreachable!("ScalarFloat always carries Scratch::Float"),
+        },
+        StringLiteral => match scratch {
+            Scratch::Str(s) => CardBody::StringLiteral(s),
+            _ => unreachable!("StringLiteral always carries Scratch::Str"),
+        },
+        Function => match scratch {
+            Scratch::Str(s) => CardBody::Function(s),
+            _ => unreachable!("Function always carries Scratch::Str"),
+        },
+        NativeFunction => match scratch {
+            Scratch::Str(s) => CardBody::NativeFunction(s),
+            _ => unreachable!("NativeFunction always carries Scratch::Str"),
+        },
+        Comment => match scratch {
+            Scratch::Str(s) => CardBody::Comment(s),
+            _ => unreachable!("Comment always carries Scratch::Str"),
+        },
+        ReadVar => match scratch {
+            Scratch::Str(s) => CardBody::ReadVar(s),
+            _ => unreachable!("ReadVar always carries Scratch::Str"),
+        },
+        CallNative => match scratch {
+            Scratch::Str(name) => CardBody::CallNative(Box::new(CallNode {
+                name,
+                args: Arguments(children),
+            })),
+            _ => unreachable!("CallNative always carries Scratch::Str"),
+        },
+        Call => match scratch {
+            Scratch::Str(function_name) => CardBody::Call(Box::new(StaticJump {
+                args: Arguments(children),
+                function_name,
+            })),
+            _ => unreachable!("Call always carries Scratch::Str"),
+        },
+        SetGlobalVar => match scratch {
+            Scratch::Str(name) => {
+                let [value] = take1(children);
+                CardBody::SetGlobalVar(Box::new(SetVar { name, value }))
+            }
+            _ => unreachable!("SetGlobalVar always carries Scratch::Str"),
+        },
+        SetVar => match scratch {
+            Scratch::Str(name) => {
+                let [value] = take1(children);
+                CardBody::SetVar(Box::new(SetVar { name, value }))
+            }
+            _ => unreachable!("SetVar always carries Scratch::Str"),
+        },
+        Repeat => match scratch {
+            Scratch::OptStr(i) => {
+                let [n, body] = take2(children);
+                CardBody::Repeat(Box::new(Repeat { i, n, body }))
+            }
+            _ => unreachable!("Repeat always carries Scratch::OptStr"),
+        },
+        ForEach => match scratch {
+            Scratch::ForEach { i, k, v } => {
+                let [iterable, body] = take2(children);
+                CardBody::ForEach(Box::new(ForEach {
+                    i,
+                    k,
+                    v,
+                    iterable: Box::new(iterable),
+                    body: Box::new(body),
+                }))
+            }
+            _ => unreachable!("ForEach always carries Scratch::ForEach"),
+        },
+        CompositeCard => match scratch {
+            Scratch::Str(ty) => CardBody::CompositeCard(Box::new(CompositeCard {
+                ty,
+                cards: children,
+            })),
+            _ => unreachable!("CompositeCard always carries Scratch::Str"),
+        },
+        DynamicCall => match scratch {
+            Scratch::DynamicCall { .. } => {
+                let mut children = children;
+                let function = children
+                    .pop()
+                    .expect("DynamicCall always has a function card");
+                CardBody::DynamicCall(Box::new(DynamicJump {
+                    args: Arguments(children),
+                    function,
+                }))
+            }
+            _ => unreachable!("DynamicCall always carries Scratch::DynamicCall"),
+        },
+        Array => CardBody::Array(children),
+        Closure => match scratch {
+            Scratch::Strings(arguments) => CardBody::Closure(Box::new(Function {
+                arguments,
+                cards: children,
+            })),
+            _ => unreachable!("Closure always carries Scratch::Strings"),
+        },
+        Try => match scratch {
+            Scratch::Try { body_len, catch_var } => {
+                let mut children = children;
+                let handler = children.split_off(body_len);
+                CardBody::Try(Box::new(TryCatch {
+                    body: children,
+                    handler,
+                    catch_var,
+                }))
+            }
+            _ => unreachable!("Try always carries Scratch::Try"),
+        },
+        Cond => match scratch {
+            Scratch::Cond { n, has_default } => {
+                let mut children = children;
+                let default = if has_default { children.pop() } else { None };
+                let bodies = children.split_off(n);
+                CardBody::Cond(Box::new(super::Cond {
+                    conditions: children,
+                    bodies,
+                    default,
+                }))
+            }
+            _ => unreachable!("Cond always carries Scratch::Cond"),
+        },
+        Switch => match scratch {
+            Scratch::Switch { keys, has_default } => {
+                let mut children = children;
+                let default = if has_default { children.pop() } else { None };
+                let bodies = children.split_off(1);
+                let [value] = take1(children);
+                CardBody::Switch(Box::new(super::Switch {
+                    value: Box::new(value),
+                    keys,
+                    bodies,
+                    default,
+                }))
+            }
+            _ => unreachable!("Switch always carries Scratch::Switch"),
+        },
+    }
+}
+
+/// A node still waiting for `remaining - children.len()` more children before it can be folded
+/// into a [`Card`] via [`finalize`].
+struct PendingCard {
+    id: CardId,
+    tag: BinTag,
+    scratch: Scratch,
+    remaining: usize,
+    children: Vec<Card>,
+}
+
+impl Card {
+    /// Encodes this subtree into the binary format described in the module docs.
+    pub fn encode_binary(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut stack: Vec<&Card> = vec![self];
+        while let Some(card) = stack.pop() {
+            let children = encode_header(card, &mut out);
+            stack.extend(children.into_iter().rev());
+        }
+        out
+    }
+
+    /// Decodes a subtree previously written by [`Card::encode_binary`].
+    pub fn decode_binary(bytes: &[u8]) -> Result<Card, DecodeError> {
+        let mut pos = 0usize;
+        let mut stack: Vec<PendingCard> = Vec::new();
+        let mut root: Option<Card> = None;
+
+        while root.is_none() {
+            let (id, tag, scratch, remaining) = read_header(bytes, &mut pos)?;
+            let mut done = if remaining == 0 {
+                Some(Card {
+                    id,
+                    body: finalize(tag, scratch, Vec::new()),
+                })
+            } else {
+                stack.push(PendingCard {
+                    id,
+                    tag,
+                    scratch,
+                    remaining,
+                    children: Vec::with_capacity(remaining),
+                });
+                None
+            };
+
+            while let Some(card) = done.take() {
+                match stack.last_mut() {
+                    None => root = Some(card),
+                    Some(parent) => {
+                        parent.children.push(card);
+                        if parent.children.len() == parent.remaining {
+                            let parent = stack.pop().expect("stack.last_mut() just returned Some");
+                            done = Some(Card {
+                                id: parent.id,
+                                body: finalize(parent.tag, parent.scratch, parent.children),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(root.expect("loop only exits once root is set"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_program() -> Card {
+        CompositeCard {
+            ty: "on_tick".to_owned(),
+            cards: vec![
+                CardBody::Add(Box::new([
+                    CardBody::ScalarInt(1).into(),
+                    CardBody::ScalarFloat(2.5).into(),
+                ]))
+                .into(),
+                ForEach {
+                    i: Some("i".to_owned()),
+                    k: None,
+                    v: Some("v".to_owned()),
+                    iterable: Box::new(CardBody::ReadVar("table".to_owned()).into()),
+                    body: Box::new(Card::string_card("body")),
+                }
+                .into(),
+                CardBody::Array(vec![
+                    CardBody::ScalarNil.into(),
+                    CardBody::Comment("hi".to_owned()).into(),
+                ])
+                .into(),
+                Repeat {
+                    i: None,
+                    n: CardBody::ScalarInt(3).into(),
+                    body: CardBody::Abort.into(),
+                }
+                .into(),
+                TryCatch {
+                    body: vec![CardBody::Throw(UnaryExpression {
+                        card: Box::new(Card::string_card("oops")),
+                    })
+                    .into()],
+                    handler: vec![Card::string_card("handled")],
+                    catch_var: Some("err".to_owned()),
+                }
+                .into(),
+                CardBody::While(Box::new([
+                    CardBody::ScalarNil.into(),
+                    CardBody::Break.into(),
+                ]))
+                .into(),
+                CardBody::DoWhile(Box::new([
+                    CardBody::Continue.into(),
+                    CardBody::ScalarInt(0).into(),
+                ]))
+                .into(),
+                CardBody::Continue.into(),
+                Switch {
+                    value: Box::new(CardBody::ReadVar("state".to_owned()).into()),
+                    keys: vec![0, 1],
+                    bodies: vec![Card::string_card("idle"), Card::string_card("running")],
+                    default: Some(Card::string_card("unknown")),
+                }
+                .into(),
+            ],
+        }
+        .into()
+    }
+
+    #[test]
+    fn round_trips_through_binary() {
+        let card = sample_program();
+        let bytes = card.encode_binary();
+        let decoded = Card::decode_binary(&bytes).expect("valid encoding decodes");
+
+        assert_eq!(card.num_children(), decoded.num_children());
+        assert_eq!(card.name(), decoded.name());
+        let mut orig_ids = Vec::new();
+        card.visit(|c, _| orig_ids.push(c.id.clone()));
+        let mut decoded_ids = Vec::new();
+        decoded.visit(|c, _| decoded_ids.push(c.id.clone()));
+        assert_eq!(orig_ids, decoded_ids);
+    }
+
+    #[test]
+    fn rejects_truncated_input() {
+        let card = sample_program();
+        let bytes = card.encode_binary();
+        assert!(Card::decode_binary(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn round_trips_same_shape_as_serde() {
+        let card = sample_program();
+
+        let json = serde_json::to_string(&card).unwrap();
+        let via_serde: Card = serde_json::from_str(&json).unwrap();
+
+        let bytes = card.encode_binary();
+        let via_binary = Card::decode_binary(&bytes).unwrap();
+
+        assert_eq!(
+            serde_json::to_string(&via_serde).unwrap(),
+            serde_json::to_string(&via_binary).unwrap()
+        );
+    }
+}
@@ -0,0 +1,223 @@
+//! Constant folding over a bare [`Card`] tree, for callers (editor tooling, CLI passes) that want
+//! to simplify a tree directly, ahead of or independent from compilation.
+//!
+//! [`Card::fold_constants`] repeatedly walks the tree post-order (via [`Card::map_cards`], so
+//! every operand is already folded before its parent is considered) folding `Add`, `Sub`, `Mul`,
+//! `Div`, `Less`, `LessOrEq`, `Equals`, `NotEquals`, `And`, `Or`, `Xor` and `Not` cards all of
+//! whose operands are literal into a single literal card, `Len` of an `Array` of literals into its
+//! element count, and replacing `IfTrue`/`IfFalse`/`IfElse` whose condition is a constant with the
+//! taken branch, to a fixpoint. Other arithmetic cards (`Mod`, `Pow`, `Neg`, ...) are not folded.
+//! Numeric semantics (int/float promotion, truthiness) match the runtime's [`Value`] exactly.
+//! Cards with side effects (`CallNative`, `SetVar`, ...) or non-literal children are left
+//! untouched.
+
+use crate::value::Value;
+
+use super::{Card, CardBody};
+
+impl Card {
+    /// Fold constant sub-expressions of this subtree in place, to a fixpoint.
+    ///
+    /// See the module docs for exactly which cards are eligible.
+    pub fn fold_constants(&mut self) {
+        loop {
+            let mut changed = false;
+            self.map_cards(|card| match fold_once(&card) {
+                Some(folded) => {
+                    changed = true;
+                    folded.into()
+                }
+                None => card,
+            });
+            if !changed {
+                break;
+            }
+        }
+    }
+}
+
+/// Try to fold a single, already-children-folded card into a literal or, for a constant-condition
+/// `If*`, the taken branch. Returns `None` when `card` isn't (yet) foldable.
+fn fold_once(card: &Card) -> Option<CardBody> {
+    match &card.body {
+        CardBody::Add(b) => fold_numeric(b, |a, b| a + b),
+        CardBody::Sub(b) => fold_numeric(b, |a, b| a - b),
+        CardBody::Mul(b) => fold_numeric(b, |a, b| a * b),
+        CardBody::Div(b) => match (literal_value(&b[0]), literal_value(&b[1])) {
+            // the runtime raises `DivideByZero` here, so the card is left for it to run
+            (Some(x), Some(y))
+                if matches!(x.try_cast_match(y), (Value::Integer(_), Value::Integer(0))) =>
+            {
+                None
+            }
+            _ => fold_numeric(b, |a, b| a / b),
+        },
+        CardBody::Less(b) => fold_numeric(b, |a, b| Value::from(a < b)),
+        CardBody::LessOrEq(b) => fold_numeric(b, |a, b| Value::from(a <= b)),
+        CardBody::Equals(b) => fold_eq(b, |eq| eq),
+        CardBody::NotEquals(b) => fold_eq(b, |eq| !eq),
+        CardBody::And(b) => fold_numeric(b, |a, b| Value::from(a.as_bool() && b.as_bool())),
+        CardBody::Or(b) => fold_numeric(b, |a, b| Value::from(a.as_bool() || b.as_bool())),
+        CardBody::Xor(b) => fold_numeric(b, |a, b| Value::from(a.as_bool() ^ b.as_bool())),
+        CardBody::Not(u) => {
+            literal_value(&u.card).map(|v| value_to_card_body(Value::from(!v.as_bool())))
+        }
+        CardBody::Len(u) => match &u.card.body {
+            CardBody::Array(elements)
+                if elements.iter().all(|e| {
+                    literal_value(e).is_some() || matches!(e.body, CardBody::StringLiteral(_))
+                }) =>
+            {
+                Some(CardBody::ScalarInt(elements.len() as i64))
+            }
+            _ => None,
+        },
+        CardBody::IfTrue(b) => {
+            let [cond, then] = &**b;
+            literal_bool(cond).map(|taken| {
+                if taken {
+                    then.body.clone()
+                } else {
+                    CardBody::ScalarNil
+                }
+            })
+        }
+        CardBody::IfFalse(b) => {
+            let [cond, els] = &**b;
+            literal_bool(cond).map(|taken| {
+                if taken {
+                    CardBody::ScalarNil
+                } else {
+                    els.body.clone()
+                }
+            })
+        }
+        CardBody::IfElse(t) => {
+            let [cond, then, els] = &**t;
+            literal_bool(cond).map(|taken| {
+                let taken_branch = if taken { then } else { els };
+                taken_branch.body.clone()
+            })
+        }
+        _ => None,
+    }
+}
+
+fn fold_numeric(pair: &[Card; 2], op: impl FnOnce(Value, Value) -> Value) -> Option<CardBody> {
+    let (a, b) = (literal_value(&pair[0])?, literal_value(&pair[1])?);
+    Some(value_to_card_body(op(a, b)))
+}
+
+/// `Equals`/`NotEquals` also accept `StringLiteral` operands, which [`Value`] can't represent at
+/// compile time, so this can't reuse [`fold_numeric`].
+fn fold_eq(pair: &[Card; 2], finish: impl FnOnce(bool) -> bool) -> Option<CardBody> {
+    let eq = match (&pair[0].body, &pair[1].body) {
+        (CardBody::StringLiteral(a), CardBody::StringLiteral(b)) => a == b,
+        _ => literal_value(&pair[0])? == literal_value(&pair[1])?,
+    };
+    Some(value_to_card_body(Value::from(finish(eq))))
+}
+
+/// The truthiness of a constant condition, matching [`Value::as_bool`]; a non-empty string
+/// literal is truthy, mirroring the non-empty check the runtime does for string objects.
+fn literal_bool(card: &Card) -> Option<bool> {
+    match &card.body {
+        CardBody::StringLiteral(s) => Some(!s.is_empty()),
+        _ => literal_value(card).map(Value::as_bool),
+    }
+}
+
+/// A leaf literal that [`Value`] can represent at compile time.
+fn literal_value(card: &Card) -> Option<Value> {
+    match &card.body {
+        CardBody::ScalarInt(i) => Some(Value::Integer(*i)),
+        CardBody::ScalarFloat(f) => Some(Value::Real(*f)),
+        CardBody::ScalarNil => Some(Value::Nil),
+        _ => None,
+    }
+}
+
+fn value_to_card_body(value: Value) -> CardBody {
+    match value {
+        Value::Integer(i) => CardBody::ScalarInt(i),
+        Value::Real(f) => CardBody::ScalarFloat(f),
+        Value::Nil => CardBody::ScalarNil,
+        // arithmetic/comparisons over scalar literals never produce an Object
+        Value::Object(_) => unreachable!("constant folding only ever produces scalar values"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folds_nested_arithmetic() {
+        let mut card: Card = CardBody::Mul(Box::new([
+            CardBody::Add(Box::new([
+                CardBody::ScalarInt(1).into(),
+                CardBody::ScalarInt(2).into(),
+            ]))
+            .into(),
+            CardBody::ScalarInt(3).into(),
+        ]))
+        .into();
+
+        card.fold_constants();
+
+        assert!(matches!(card.body, CardBody::ScalarInt(9)));
+    }
+
+    #[test]
+    fn folds_string_equality() {
+        let mut card: Card = CardBody::Equals(Box::new([
+            CardBody::StringLiteral("a".to_owned()).into(),
+            CardBody::StringLiteral("a".to_owned()).into(),
+        ]))
+        .into();
+
+        card.fold_constants();
+
+        assert!(matches!(card.body, CardBody::ScalarInt(1)));
+    }
+
+    #[test]
+    fn prunes_if_true_with_constant_condition() {
+        let mut card: Card = CardBody::IfTrue(Box::new([
+            CardBody::ScalarInt(0).into(),
+            CardBody::ScalarInt(42).into(),
+        ]))
+        .into();
+
+        card.fold_constants();
+
+        assert!(matches!(card.body, CardBody::ScalarNil));
+    }
+
+    #[test]
+    fn takes_if_else_branch_with_constant_condition() {
+        let mut card: Card = CardBody::IfElse(Box::new([
+            CardBody::ScalarInt(1).into(),
+            CardBody::ScalarInt(11).into(),
+            CardBody::ScalarInt(22).into(),
+        ]))
+        .into();
+
+        card.fold_constants();
+
+        assert!(matches!(card.body, CardBody::ScalarInt(11)));
+    }
+
+    #[test]
+    fn leaves_non_literal_children_untouched() {
+        let mut card: Card = CardBody::Add(Box::new([
+            CardBody::ReadVar("x".to_owned()).into(),
+            CardBody::ScalarInt(1).into(),
+        ]))
+        .into();
+
+        card.fold_constants();
+
+        assert!(matches!(card.body, CardBody::Add(_)));
+    }
+}
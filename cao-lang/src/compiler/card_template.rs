@@ -0,0 +1,190 @@
+//! Registerable "macro card" templates: host-defined expansions for a named [`CompositeCard`]
+//! `ty`, so extending the card vocabulary doesn't require enlarging [`CardBody`] itself.
+//!
+//! A host registers one expander closure per `ty` with [`CardTemplateRegistry::register`], then
+//! runs [`Card::expand_templates`] over a tree before compilation. Every matching `CompositeCard`
+//! is replaced by its expander's output - which may itself be (or contain) a template card, so
+//! each node is re-expanded until it stops matching a registered `ty`, up to
+//! [`CardTemplateRegistry::MAX_DEPTH`] levels, to catch a template that expands into itself.
+
+use crate::alloc_crate::{boxed::Box, string::String, vec::Vec};
+use crate::collections::hash_map::CaoHashMap;
+use core::ops::ControlFlow;
+
+use thiserror::Error;
+
+use super::{Card, CardBody, CardVisitorMut, CompositeCard, Order};
+
+/// A single template's expansion: the matching [`CompositeCard`] is handed in as-is, with its
+/// `cards` available to read as the template's arguments.
+type Expander = Box<dyn Fn(&CompositeCard) -> Result<Card, ExpandError>>;
+
+/// Host-registered set of named [`CompositeCard`] expansions ("macro cards"), driven by
+/// [`Card::expand_templates`].
+#[derive(Default)]
+pub struct CardTemplateRegistry {
+    templates: CaoHashMap<String, Expander>,
+}
+
+impl core::fmt::Debug for CardTemplateRegistry {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("CardTemplateRegistry")
+            .field(
+                "templates",
+                &self.templates.iter().map(|(k, _)| k).collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl CardTemplateRegistry {
+    /// Recursion limit [`Card::expand_templates`] enforces on a single node's own re-expansion
+    /// chain, against a template that (directly or transitively) expands into itself.
+    pub const MAX_DEPTH: usize = 64;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `ty`'s expansion, replacing any template previously registered under that name.
+    pub fn register(
+        &mut self,
+        ty: impl Into<String>,
+        expand: impl Fn(&CompositeCard) -> Result<Card, ExpandError> + 'static,
+    ) {
+        self.templates.insert(ty.into(), Box::new(expand)).unwrap();
+    }
+}
+
+/// Failure while expanding a [`CompositeCard`] against a [`CardTemplateRegistry`].
+#[derive(Debug, Clone, Error)]
+pub enum ExpandError {
+    #[error("template {0:?} expanded {1} levels deep without reaching a card that isn't itself a registered template")]
+    RecursionLimit(String, usize),
+
+    #[error("template {0:?} rejected its arguments: {1}")]
+    InvalidArguments(String, String),
+}
+
+impl Card {
+    /// Recursively rewrite every [`CompositeCard`] in this subtree whose `ty` is registered in
+    /// `registry` into the card tree its expander returns.
+    pub fn expand_templates(&mut self, registry: &CardTemplateRegistry) -> Result<(), ExpandError> {
+        struct Expand<'a> {
+            registry: &'a CardTemplateRegistry,
+        }
+
+        impl CardVisitorMut for Expand<'_> {
+            type Break = ExpandError;
+
+            fn visit(&mut self, card: &mut Card, _path: &[usize]) -> ControlFlow<ExpandError> {
+                let mut depth = 0;
+                loop {
+                    let CardBody::CompositeCard(composite) = &card.body else {
+                        return ControlFlow::Continue(());
+                    };
+                    let Some(expand) = self.registry.templates.get(&composite.ty) else {
+                        return ControlFlow::Continue(());
+                    };
+                    if depth >= CardTemplateRegistry::MAX_DEPTH {
+                        return ControlFlow::Break(ExpandError::RecursionLimit(
+                            composite.ty.clone(),
+                            depth,
+                        ));
+                    }
+                    match expand(composite) {
+                        Ok(expanded) => *card = expanded,
+                        Err(e) => return ControlFlow::Break(e),
+                    }
+                    depth += 1;
+                }
+            }
+        }
+
+        match self.walk_mut(Order::Pre, &mut Expand { registry }) {
+            ControlFlow::Continue(()) => Ok(()),
+            ControlFlow::Break(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_a_registered_template() {
+        let mut registry = CardTemplateRegistry::new();
+        registry.register("double", |composite| {
+            let [arg] = composite.cards.as_slice() else {
+                return Err(ExpandError::InvalidArguments(
+                    composite.ty.clone(),
+                    "expected exactly 1 argument".to_owned(),
+                ));
+            };
+            Ok(CardBody::Add(Box::new([arg.clone(), arg.clone()])).into())
+        });
+
+        let mut card: Card = CompositeCard {
+            ty: "double".to_owned(),
+            cards: vec![CardBody::ScalarInt(21).into()],
+        }
+        .into();
+
+        card.expand_templates(&registry).unwrap();
+
+        assert!(matches!(card.body, CardBody::Add(_)));
+    }
+
+    #[test]
+    fn expands_templates_that_expand_into_further_templates() {
+        let mut registry = CardTemplateRegistry::new();
+        registry.register("alias-of-nil", |_| {
+            Ok(CompositeCard {
+                ty: "nil".to_owned(),
+                cards: Vec::new(),
+            }
+            .into())
+        });
+        registry.register("nil", |_| Ok(CardBody::ScalarNil.into()));
+
+        let mut card: Card = CompositeCard {
+            ty: "alias-of-nil".to_owned(),
+            cards: Vec::new(),
+        }
+        .into();
+
+        card.expand_templates(&registry).unwrap();
+
+        assert!(matches!(card.body, CardBody::ScalarNil));
+    }
+
+    #[test]
+    fn rejects_a_template_that_expands_into_itself() {
+        let mut registry = CardTemplateRegistry::new();
+        registry.register("infinite", |c| Ok(c.clone().into()));
+
+        let mut card: Card = CompositeCard {
+            ty: "infinite".to_owned(),
+            cards: Vec::new(),
+        }
+        .into();
+
+        let err = card.expand_templates(&registry).unwrap_err();
+        assert!(matches!(err, ExpandError::RecursionLimit(ty, _) if ty == "infinite"));
+    }
+
+    #[test]
+    fn unregistered_composite_is_left_untouched() {
+        let registry = CardTemplateRegistry::new();
+        let mut card: Card = CompositeCard {
+            ty: "unregistered".to_owned(),
+            cards: Vec::new(),
+        }
+        .into();
+
+        card.expand_templates(&registry).unwrap();
+
+        assert!(matches!(&card.body, CardBody::CompositeCard(c) if c.ty == "unregistered"));
+    }
+}
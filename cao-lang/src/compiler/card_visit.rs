@@ -0,0 +1,248 @@
+//! Visitor/transform sugar over [`Card`]'s traversal primitives.
+//!
+//! `insert_child`, `replace_child`, `get_child_mut` and `iter_children` used to each re-enumerate
+//! every `CardBody` variant by hand - error-prone, since it was easy for one of them to drift (the
+//! old `ForEach` handling silently skipped its loop-variable fields in one of them but not the
+//! others). [`Card::child_slots`]/[`Card::child_slots_mut`] fixed that by becoming the single
+//! table-based description of each variant's children that every traversal method above is now a
+//! thin wrapper over - see their doc comments.
+//!
+//! What's still missing is sugar for *generic passes*: something a dead-code pass, a
+//! variable-renaming pass, or a "collect every `CallNative` name" pass can implement once, instead
+//! of each copying a traversal loop. [`CardVisitor`]/[`CardVisitorMut`] are that: stateful visitor
+//! objects (so a pass can carry accumulator state, unlike a bare closure) driven by
+//! [`Card::walk`]/[`Card::walk_mut`], in either pre- or post-order, with
+//! [`core::ops::ControlFlow`] for early exit. [`Card::map_cards`] is the rewriting counterpart:
+//! a post-order `Card -> Card` transform for passes like constant folding that need to replace a
+//! node with something new rather than just observe or mutate it in place.
+
+use crate::alloc_crate::vec::Vec;
+use core::ops::ControlFlow;
+
+use super::*;
+
+/// Whether a traversal visits a node before or after its children.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Order {
+    Pre,
+    Post,
+}
+
+/// A stateful pass over an immutable [`Card`] tree, driven by [`Card::walk`].
+///
+/// Prefer this over a bare closure when the pass needs to carry state that's awkward to thread
+/// through a closure's captures (e.g. a builder struct with several accumulator fields).
+pub trait CardVisitor {
+    /// The value carried by an early exit; use `()` for a pass that always visits every node.
+    type Break;
+
+    /// Called once per card, in the order [`Card::walk`] was asked for. Return
+    /// [`ControlFlow::Break`] to stop the traversal early.
+    fn visit(&mut self, card: &Card, path: &[usize]) -> ControlFlow<Self::Break>;
+}
+
+/// The `_mut` counterpart of [`CardVisitor`], driven by [`Card::walk_mut`].
+pub trait CardVisitorMut {
+    type Break;
+
+    fn visit(&mut self, card: &mut Card, path: &[usize]) -> ControlFlow<Self::Break>;
+}
+
+impl<B, F> CardVisitor for F
+where
+    F: FnMut(&Card, &[usize]) -> ControlFlow<B>,
+{
+    type Break = B;
+
+    fn visit(&mut self, card: &Card, path: &[usize]) -> ControlFlow<B> {
+        self(card, path)
+    }
+}
+
+impl<B, F> CardVisitorMut for F
+where
+    F: FnMut(&mut Card, &[usize]) -> ControlFlow<B>,
+{
+    type Break = B;
+
+    fn visit(&mut self, card: &mut Card, path: &[usize]) -> ControlFlow<B> {
+        self(card, path)
+    }
+}
+
+impl Card {
+    /// Depth-first traversal of `self` and its descendants in the given [`Order`], short-circuits
+    /// as soon as `visitor` returns [`ControlFlow::Break`].
+    pub fn walk<V: CardVisitor>(&self, order: Order, visitor: &mut V) -> ControlFlow<V::Break> {
+        fn go<V: CardVisitor>(
+            card: &Card,
+            order: Order,
+            path: &mut Vec<usize>,
+            visitor: &mut V,
+        ) -> ControlFlow<V::Break> {
+            if order == Order::Pre {
+                if let ControlFlow::Break(b) = visitor.visit(card, path) {
+                    return ControlFlow::Break(b);
+                }
+            }
+            for (i, child) in card.iter_children().enumerate() {
+                path.push(i);
+                let flow = go(child, order, path, visitor);
+                path.pop();
+                if let ControlFlow::Break(b) = flow {
+                    return ControlFlow::Break(b);
+                }
+            }
+            if order == Order::Post {
+                if let ControlFlow::Break(b) = visitor.visit(card, path) {
+                    return ControlFlow::Break(b);
+                }
+            }
+            ControlFlow::Continue(())
+        }
+        go(self, order, &mut Vec::new(), visitor)
+    }
+
+    /// The `_mut` counterpart of [`Card::walk`].
+    pub fn walk_mut<V: CardVisitorMut>(
+        &mut self,
+        order: Order,
+        visitor: &mut V,
+    ) -> ControlFlow<V::Break> {
+        fn go<V: CardVisitorMut>(
+            card: &mut Card,
+            order: Order,
+            path: &mut Vec<usize>,
+            visitor: &mut V,
+        ) -> ControlFlow<V::Break> {
+            if order == Order::Pre {
+                if let ControlFlow::Break(b) = visitor.visit(card, path) {
+                    return ControlFlow::Break(b);
+                }
+            }
+            for (i, child) in card.iter_children_mut().enumerate() {
+                path.push(i);
+                let flow = go(child, order, path, visitor);
+                path.pop();
+                if let ControlFlow::Break(b) = flow {
+                    return ControlFlow::Break(b);
+                }
+            }
+            if order == Order::Post {
+                if let ControlFlow::Break(b) = visitor.visit(card, path) {
+                    return ControlFlow::Break(b);
+                }
+            }
+            ControlFlow::Continue(())
+        }
+        go(self, order, &mut Vec::new(), visitor)
+    }
+
+    /// Rewrites this subtree post-order: every child is transformed before its parent, then `f`
+    /// is called on the parent with its (already-transformed) children already in place.
+    ///
+    /// Useful for passes like constant folding that build a node's replacement from the node
+    /// itself, taken by value, which `walk_mut` doesn't offer since it only hands out a `&mut Card`.
+    pub fn map_cards(&mut self, mut f: impl FnMut(Card) -> Card) {
+        fn go(card: &mut Card, f: &mut dyn FnMut(Card) -> Card) {
+            for child in card.iter_children_mut() {
+                go(child, f);
+            }
+            let taken = core::mem::replace(card, CardBody::ScalarNil.into());
+            *card = f(taken);
+        }
+        go(self, &mut f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Card {
+        CardBody::Add(Box::new([
+            CardBody::SetVar(Box::new(SetVar {
+                name: "x".to_owned(),
+                value: CardBody::ScalarInt(1).into(),
+            }))
+            .into(),
+            CardBody::ReadVar("x".to_owned()).into(),
+        ]))
+        .into()
+    }
+
+    #[test]
+    fn walk_pre_order_visits_parent_before_children() {
+        let card = sample();
+        let mut names = Vec::new();
+        card.walk(Order::Pre, &mut |c: &Card, _: &[usize]| {
+            names.push(c.name().to_owned());
+            ControlFlow::<()>::Continue(())
+        });
+        assert_eq!(names[0], "Add");
+    }
+
+    #[test]
+    fn walk_post_order_visits_children_before_parent() {
+        let card = sample();
+        let mut names = Vec::new();
+        card.walk(Order::Post, &mut |c: &Card, _: &[usize]| {
+            names.push(c.name().to_owned());
+            ControlFlow::<()>::Continue(())
+        });
+        assert_eq!(*names.last().unwrap(), "Add");
+    }
+
+    #[test]
+    fn walk_short_circuits_on_break() {
+        let card = sample();
+        let mut visited = 0;
+        let flow = card.walk(Order::Pre, &mut |_: &Card, _: &[usize]| {
+            visited += 1;
+            ControlFlow::Break("stop")
+        });
+        assert_eq!(visited, 1);
+        assert_eq!(flow, ControlFlow::Break("stop"));
+    }
+
+    #[test]
+    fn walk_mut_renames_variables() {
+        let mut card = sample();
+        card.walk_mut(Order::Pre, &mut |c: &mut Card, _: &[usize]| {
+            match &mut c.body {
+                CardBody::SetVar(sv) if sv.name == "x" => sv.name = "y".to_owned(),
+                CardBody::ReadVar(name) if name == "x" => *name = "y".to_owned(),
+                _ => {}
+            }
+            ControlFlow::<()>::Continue(())
+        });
+
+        let mut renamed = Vec::new();
+        card.walk(Order::Pre, &mut |c: &Card, _: &[usize]| {
+            if let CardBody::ReadVar(name) = &c.body {
+                renamed.push(name.clone());
+            }
+            ControlFlow::<()>::Continue(())
+        });
+        assert_eq!(renamed, vec!["y".to_owned()]);
+    }
+
+    #[test]
+    fn map_cards_folds_constant_addition() {
+        let mut card: Card = CardBody::Add(Box::new([
+            CardBody::ScalarInt(1).into(),
+            CardBody::ScalarInt(2).into(),
+        ]))
+        .into();
+
+        card.map_cards(|c| match &c.body {
+            CardBody::Add(b) => match (&b[0].body, &b[1].body) {
+                (CardBody::ScalarInt(a), CardBody::ScalarInt(b)) => CardBody::ScalarInt(a + b).into(),
+                _ => c,
+            },
+            _ => c,
+        });
+
+        assert!(matches!(card.body, CardBody::ScalarInt(3)));
+    }
+}
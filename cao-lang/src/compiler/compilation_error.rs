@@ -1,13 +1,21 @@
-use std::fmt::Display;
+use core::fmt::Display;
 
+use crate::alloc_crate::{string::String, vec::Vec};
 use crate::prelude::Trace;
 
+use super::card::PropertyKind;
+
 use thiserror::Error;
 
 #[derive(Debug, Clone, Error)]
 pub struct CompilationError {
     pub payload: CompilationErrorPayload,
     pub loc: Option<Trace>,
+    /// Secondary locations relevant to this same error, each paired with a short label - e.g.
+    /// `DuplicateName` points here at the original definition a new one conflicts with. Rendered
+    /// after the primary `loc`/`payload` instead of requiring a caller to dig them out of
+    /// `payload` itself. Empty for errors that only ever have the one location.
+    pub labels: Vec<(Trace, String)>,
 }
 
 impl CompilationError {
@@ -15,17 +23,62 @@ impl CompilationError {
         Self {
             payload,
             loc: Some(index),
+            labels: Vec::new(),
         }
     }
+
+    /// Attach a secondary labeled location to this error, e.g. the site `DuplicateName` first
+    /// defined the conflicting name at.
+    pub fn with_label(mut self, loc: Trace, msg: impl Into<String>) -> Self {
+        self.labels.push((loc, msg.into()));
+        self
+    }
 }
 
 impl Display for CompilationError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         if let Some(loc) = self.loc.as_ref() {
-            write!(f, "CompilationError: [{}], Error: {}", loc, self.payload)
+            write!(f, "CompilationError: [{}], Error: {}", loc, self.payload)?;
         } else {
-            write!(f, "{}", self.payload)
+            write!(f, "{}", self.payload)?;
         }
+        for (loc, msg) in &self.labels {
+            write!(f, "\n  [{loc}]: {msg}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Every [`CompilationError`] found during a single [`super::compile_diagnostics`] pass, in
+/// discovery order - for editor tooling that wants the full list of problems in one round instead
+/// of fixing one error, recompiling, and hitting the next. [`super::compile`] only ever surfaces
+/// the first one, for callers that just want a pass/fail result.
+#[derive(Debug, Clone)]
+pub struct Diagnostics(pub Vec<CompilationError>);
+
+impl Diagnostics {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn iter(&self) -> core::slice::Iter<'_, CompilationError> {
+        self.0.iter()
+    }
+}
+
+impl Display for Diagnostics {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for (i, err) in self.0.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{err}")?;
+        }
+        Ok(())
     }
 }
 
@@ -84,4 +137,48 @@ pub enum CompilationErrorPayload {
 
     #[error("Too many `super.` calls.")]
     SuperLimitReached,
+
+    #[error("Constant index out of range: index {index}, size {size}")]
+    ConstIndexOutOfRange { index: i64, size: usize },
+
+    #[error("Division or modulo by a constant zero")]
+    ConstantDivisionByZero,
+
+    #[error("Constant key not found in a table with {size} known entries")]
+    ConstKeyNotFound { size: usize },
+
+    #[error("Expected a constant Table, found {found}")]
+    ConstTypeMismatch { found: &'static str },
+
+    /// Raised by [`super::const_fold::fold_function_cards`]: a literal `Array` mixes element
+    /// kinds, e.g. `[1, false]`.
+    #[error("Array literal expects uniform elements of type {expected}, found {found}")]
+    ConstArrayTypeMismatch {
+        expected: &'static str,
+        found: &'static str,
+    },
+
+    /// Raised by [`super::card::check_card_types`]: a `CallNative` passed fewer arguments than
+    /// its registered [`super::card::NativeFnSchema`] declares as inputs.
+    #[error("{card} needs at least {needed} argument(s), found {found}")]
+    StackUnderflow {
+        card: String,
+        needed: usize,
+        found: usize,
+    },
+
+    /// Raised by [`super::card::check_card_types`]: a child card's value kind doesn't unify with
+    /// the [`PropertyKind`] its slot declared.
+    #[error("{card}'s `{slot}` slot expects {expected:?}, found {actual:?}")]
+    TypeMismatch {
+        card: String,
+        slot: String,
+        expected: PropertyKind,
+        actual: PropertyKind,
+    },
+
+    /// Raised by the compiler when a `Break`/`Continue` card is found outside of a
+    /// `ForEach`/`While`/`DoWhile`/`Repeat` body.
+    #[error("{0} can only be used inside of a ForEach, While, DoWhile or Repeat loop")]
+    LoopControlOutsideLoop(&'static str),
 }
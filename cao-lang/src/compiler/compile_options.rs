@@ -3,6 +3,14 @@
 pub struct CompileOptions {
     /// How deep is the submodule tree allowed to grow?
     pub recursion_limit: u32,
+    /// Fold constant expressions (and constant table literals/indexing) at compile time, reporting
+    /// provably out-of-range indices or missing keys as compile errors instead of runtime ones.
+    pub constant_folding: bool,
+    /// Drop cards that can provably never run: anything following an unconditional `Abort`/`Return`
+    /// in the same card list, and `While`/`Repeat` bodies whose condition is a literal `false`. Runs
+    /// once the program has been flattened into per-function IR, after `constant_folding` has had a
+    /// chance to reduce conditions down to a literal.
+    pub dead_code_elimination: bool,
 }
 
 impl Default for CompileOptions {
@@ -15,6 +23,8 @@ impl CompileOptions {
     pub fn new() -> Self {
         Self {
             recursion_limit: 64,
+            constant_folding: true,
+            dead_code_elimination: true,
         }
     }
 }
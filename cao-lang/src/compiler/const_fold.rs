@@ -0,0 +1,392 @@
+//! Compile-time constant folding and bounds/type checking.
+//!
+//! Runs over a [`Module`]'s own functions (mirroring [`Module::walk_cards_mut`], this does not
+//! recurse into submodules - callers that need that walk the submodule tree themselves) before it
+//! is flattened into IR, folding cards whose inputs are all literals into a single precomputed
+//! literal card. Several things fall out of always knowing the exact value of a literal: constant
+//! [`CardBody::Get`] indices that are provably out of range, constant [`CardBody::GetProperty`]
+//! keys that are provably missing from a table literal built via `CreateTable` + constant
+//! `SetProperty` cards, and a literal [`CardBody::Array`] that mixes element kinds (e.g.
+//! `[1, false]`) - all three are reported as compile errors instead of failing (or silently
+//! misbehaving) at runtime.
+use crate::alloc_crate::{collections::BTreeMap, string::String, vec::Vec};
+use crate::value::Value;
+
+use super::{Card, CardBody, CompilationError, CompilationErrorPayload, Module};
+use crate::prelude::Trace;
+
+/// Fold every function's cards in `module` in place.
+pub(crate) fn fold_constants(module: &mut Module) -> Result<(), CompilationError> {
+    for (name, function) in module.functions.iter_mut() {
+        fold_function_cards(&mut function.cards).map_err(|payload| {
+            let mut trace = Trace::default();
+            trace.namespace.push(name.clone().into_boxed_str());
+            CompilationError::with_loc(payload, trace)
+        })?;
+    }
+    Ok(())
+}
+
+/// Fold a single function's sequential card list.
+///
+/// Tracks, by variable name, table literals built as `SetVar(name, CreateTable)` followed by
+/// constant-keyed, constant-valued `SetProperty` cards, so a later constant `GetProperty` on the
+/// same variable can be folded (or rejected as a missing key) without re-running any of the
+/// assignments - which would be unsound if an assignment had a side effect.
+fn fold_function_cards(cards: &mut [Card]) -> Result<(), CompilationErrorPayload> {
+    let mut known_tables: BTreeMap<String, Vec<(CardBody, CardBody)>> = BTreeMap::new();
+
+    for card in cards.iter_mut() {
+        fold_card(card)?;
+
+        match &card.body {
+            CardBody::SetVar(b) | CardBody::SetGlobalVar(b) => {
+                if matches!(b.value.body, CardBody::CreateTable) {
+                    known_tables.insert(b.name.clone(), Vec::new());
+                } else {
+                    known_tables.remove(&b.name);
+                }
+            }
+            CardBody::SetProperty(triplet) => {
+                let [value, table, key] = &**triplet;
+                if let CardBody::ReadVar(name) = &table.body {
+                    if let Some(entries) = known_tables.get_mut(name) {
+                        // only a literal key *and* literal value keep the table's entries
+                        // exhaustively known; anything else and we can no longer assert what
+                        // `GetProperty` will read back, so stop tracking it
+                        if is_literal(&key.body) && is_literal(&value.body) {
+                            entries.push((key.body.clone(), value.body.clone()));
+                        } else {
+                            known_tables.remove(name);
+                        }
+                    }
+                }
+            }
+            CardBody::GetProperty(pair) => {
+                let [table, key] = &**pair;
+                if let CardBody::ReadVar(name) = &table.body {
+                    if is_literal(&key.body) {
+                        if let Some(entries) = known_tables.get(name) {
+                            match entries.iter().find(|(k, _)| literal_eq(k, &key.body)) {
+                                Some((_, value)) => card.body = value.clone(),
+                                None => {
+                                    return Err(CompilationErrorPayload::ConstKeyNotFound {
+                                        size: entries.len(),
+                                    })
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Recursively fold `card`'s children, bottom-up, then try to fold `card` itself.
+fn fold_card(card: &mut Card) -> Result<(), CompilationErrorPayload> {
+    for child in card.iter_children_mut() {
+        fold_card(child)?;
+    }
+
+    // computed against `&card.body` only, so it doesn't conflict with the `&mut card.body`
+    // assignment below
+    let folded = fold_self(&card.body)?;
+
+    match folded {
+        Some(body) => card.body = body,
+        None => {
+            // composite cards and closures carry their own sequential card list (same shape as a
+            // function's body), so give them the same table-literal tracking a top-level
+            // function gets
+            match &mut card.body {
+                CardBody::CompositeCard(c) => fold_function_cards(&mut c.cards)?,
+                CardBody::Closure(c) => fold_function_cards(&mut c.cards)?,
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Try to fold a single, already-children-folded card body into a literal. Returns `None` when the
+/// card isn't (yet) fully constant.
+fn fold_self(body: &CardBody) -> Result<Option<CardBody>, CompilationErrorPayload> {
+    let folded_value = match body {
+        CardBody::Add(b) => fold_checked_arith(b, i64::checked_add, |a, b| a + b),
+        CardBody::Sub(b) => fold_checked_arith(b, i64::checked_sub, |a, b| a - b),
+        CardBody::Mul(b) => fold_checked_arith(b, i64::checked_mul, |a, b| a * b),
+        CardBody::Div(b) => {
+            return fold_checked_div_or_mod(b, |a, b| a / b).map(|v| v.map(value_to_card_body))
+        }
+        CardBody::Mod(b) => {
+            return fold_checked_div_or_mod(b, |a, b| a % b).map(|v| v.map(value_to_card_body))
+        }
+        CardBody::Equals(b) => fold_binary(b, |a, b| Some((a == b).into())),
+        CardBody::NotEquals(b) => fold_binary(b, |a, b| Some((a != b).into())),
+        CardBody::Less(b) => fold_binary(b, |a, b| Some((a < b).into())),
+        CardBody::LessOrEq(b) => fold_binary(b, |a, b| Some((a <= b).into())),
+        CardBody::And(b) => fold_binary(b, |a, b| Some(Value::from(a.as_bool() && b.as_bool()))),
+        CardBody::Or(b) => fold_binary(b, |a, b| Some(Value::from(a.as_bool() || b.as_bool()))),
+        CardBody::Xor(b) => fold_binary(b, |a, b| Some(Value::from(a.as_bool() ^ b.as_bool()))),
+        CardBody::Not(u) => literal_value(&u.card).map(|v| Value::from(!v.as_bool())),
+        CardBody::Len(u) => match &u.card.body {
+            CardBody::Array(elements) if elements.iter().all(|e| is_literal(&e.body)) => {
+                Some(Value::Integer(elements.len() as i64))
+            }
+            _ => None,
+        },
+        CardBody::Get(pair) => return fold_const_get(pair),
+        CardBody::Array(elements) => {
+            check_uniform_array(elements)?;
+            None
+        }
+        _ => None,
+    };
+    Ok(folded_value.map(value_to_card_body))
+}
+
+/// Rejects an `Array` literal that mixes element kinds, e.g. `[1, false]` - every element that is
+/// itself already a literal (by the time this runs, children have already been folded as far as
+/// possible) must agree on [`literal_type_name`]. Elements that aren't (yet) known at compile time
+/// don't constrain anything, the same "only assert what's provable" stance
+/// [`fold_const_get`]/[`fold_function_cards`]'s table tracking takes.
+fn check_uniform_array(elements: &[Card]) -> Result<(), CompilationErrorPayload> {
+    let mut expected = None;
+    for element in elements {
+        if !is_literal(&element.body) {
+            continue;
+        }
+        let found = literal_type_name(&element.body);
+        match expected {
+            None => expected = Some(found),
+            Some(expected) if expected != found => {
+                return Err(CompilationErrorPayload::ConstArrayTypeMismatch { expected, found })
+            }
+            Some(_) => {}
+        }
+    }
+    Ok(())
+}
+
+fn fold_binary(pair: &[Card; 2], op: impl FnOnce(Value, Value) -> Option<Value>) -> Option<Value> {
+    let (a, b) = (literal_value(&pair[0])?, literal_value(&pair[1])?);
+    op(a, b)
+}
+
+/// Folds an `Add`/`Sub`/`Mul` pair, going through `int_op` instead of [`Value`]'s own (debug-build
+/// panicking on `i64` overflow) arithmetic operators whenever both operands are integers - an
+/// overflowing `int_op` aborts the fold (returning `None`, leaving the original card in place) so a
+/// constant expression that overflows `i64` fails the same way the equivalent non-constant
+/// expression would at runtime (where the VM's checked arithmetic handles the overflow) instead of
+/// panicking the compiler.
+/// Any other operand combination (floats, or a `Nil` from a malformed constant) can't overflow, so
+/// it's handed straight to `value_op`, i.e. [`Value`]'s own operator.
+fn fold_checked_arith(
+    pair: &[Card; 2],
+    int_op: impl FnOnce(i64, i64) -> Option<i64>,
+    value_op: impl FnOnce(Value, Value) -> Value,
+) -> Option<Value> {
+    let (a, b) = (literal_value(&pair[0])?, literal_value(&pair[1])?);
+    if let (Value::Integer(a), Value::Integer(b)) = a.try_cast_match(b) {
+        return int_op(a, b).map(Value::Integer);
+    }
+    Some(value_op(a, b))
+}
+
+/// Folds a `Div`/`Mod` pair, rejecting a literal zero divisor as a compile error instead of
+/// letting it through to [`Value`]'s own `Div`/`Rem` impls, which fall back to `Nil` on integer
+/// division by zero - silently folding `1 / 0` into a `Nil` card would hide a bug that would
+/// otherwise have at least raised an `ExecutionErrorPayload::DivideByZero` at runtime.
+fn fold_checked_div_or_mod(
+    pair: &[Card; 2],
+    op: impl FnOnce(Value, Value) -> Value,
+) -> Result<Option<Value>, CompilationErrorPayload> {
+    let (Some(a), Some(b)) = (literal_value(&pair[0]), literal_value(&pair[1])) else {
+        return Ok(None);
+    };
+    if matches!(a.try_cast_match(b), (Value::Integer(_), Value::Integer(0))) {
+        return Err(CompilationErrorPayload::ConstantDivisionByZero);
+    }
+    Ok(Some(op(a, b)))
+}
+
+fn fold_const_get(pair: &[Card; 2]) -> Result<Option<CardBody>, CompilationErrorPayload> {
+    let (table, index) = (&pair[0], &pair[1]);
+    let elements = match &table.body {
+        CardBody::Array(elements) => elements,
+        // not a literal table, so nothing can be proven about this `Get`
+        other if !is_literal(other) => return Ok(None),
+        // indexing a non-table literal is provably wrong
+        other => {
+            return Err(CompilationErrorPayload::ConstTypeMismatch {
+                found: literal_type_name(other),
+            })
+        }
+    };
+    let Some(Value::Integer(index)) = literal_value(index) else {
+        return Ok(None);
+    };
+    if index < 0 || index as usize >= elements.len() {
+        return Err(CompilationErrorPayload::ConstIndexOutOfRange {
+            index,
+            size: elements.len(),
+        });
+    }
+    Ok(Some(elements[index as usize].body.clone()))
+}
+
+/// A leaf literal that [`Value`] can represent at compile time.
+fn literal_value(card: &Card) -> Option<Value> {
+    match &card.body {
+        CardBody::ScalarInt(i) => Some(Value::Integer(*i)),
+        CardBody::ScalarFloat(f) => Some(Value::Real(*f)),
+        CardBody::ScalarNil => Some(Value::Nil),
+        _ => None,
+    }
+}
+
+/// Any literal leaf, including ones (strings) [`Value`] can't represent at compile time, but that
+/// are still valid, side-effect-free table keys/values.
+fn is_literal(body: &CardBody) -> bool {
+    matches!(
+        body,
+        CardBody::ScalarInt(_)
+            | CardBody::ScalarFloat(_)
+            | CardBody::ScalarNil
+            | CardBody::StringLiteral(_)
+    )
+}
+
+fn literal_eq(a: &CardBody, b: &CardBody) -> bool {
+    match (a, b) {
+        (CardBody::ScalarInt(a), CardBody::ScalarInt(b)) => a == b,
+        (CardBody::ScalarFloat(a), CardBody::ScalarFloat(b)) => a == b,
+        (CardBody::StringLiteral(a), CardBody::StringLiteral(b)) => a == b,
+        (CardBody::ScalarNil, CardBody::ScalarNil) => true,
+        _ => false,
+    }
+}
+
+fn literal_type_name(body: &CardBody) -> &'static str {
+    match body {
+        CardBody::ScalarInt(_) => "Integer",
+        CardBody::ScalarFloat(_) => "Real",
+        CardBody::ScalarNil => "Nil",
+        CardBody::StringLiteral(_) => "String",
+        _ => "Unknown",
+    }
+}
+
+fn value_to_card_body(value: Value) -> CardBody {
+    match value {
+        Value::Integer(i) => CardBody::ScalarInt(i),
+        Value::Real(f) => CardBody::ScalarFloat(f),
+        Value::Nil => CardBody::ScalarNil,
+        // scalar arithmetic/comparisons never produce an Object
+        Value::Object(_) => unreachable!("constant folding only ever produces scalar values"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folds_division_into_a_single_literal() {
+        let mut cards = vec![CardBody::Div(Box::new([
+            CardBody::ScalarInt(9).into(),
+            CardBody::ScalarInt(3).into(),
+        ]))
+        .into()];
+
+        fold_function_cards(&mut cards).unwrap();
+
+        assert!(matches!(cards[0].body, CardBody::ScalarInt(3)));
+    }
+
+    #[test]
+    fn folds_modulo_into_a_single_literal() {
+        let mut cards = vec![CardBody::Mod(Box::new([
+            CardBody::ScalarInt(7).into(),
+            CardBody::ScalarInt(3).into(),
+        ]))
+        .into()];
+
+        fold_function_cards(&mut cards).unwrap();
+
+        assert!(matches!(cards[0].body, CardBody::ScalarInt(1)));
+    }
+
+    #[test]
+    fn rejects_constant_division_by_zero() {
+        let mut cards = vec![CardBody::Div(Box::new([
+            CardBody::ScalarInt(1).into(),
+            CardBody::ScalarInt(0).into(),
+        ]))
+        .into()];
+
+        let err = fold_function_cards(&mut cards).unwrap_err();
+        assert!(matches!(err, CompilationErrorPayload::ConstantDivisionByZero));
+    }
+
+    #[test]
+    fn rejects_constant_modulo_by_zero() {
+        let mut cards = vec![CardBody::Mod(Box::new([
+            CardBody::ScalarInt(1).into(),
+            CardBody::ScalarInt(0).into(),
+        ]))
+        .into()];
+
+        let err = fold_function_cards(&mut cards).unwrap_err();
+        assert!(matches!(err, CompilationErrorPayload::ConstantDivisionByZero));
+    }
+
+    #[test]
+    fn accepts_a_uniform_array_literal() {
+        let mut cards = vec![CardBody::Array(vec![
+            CardBody::ScalarInt(1).into(),
+            CardBody::ScalarInt(2).into(),
+            CardBody::ScalarInt(3).into(),
+        ])
+        .into()];
+
+        fold_function_cards(&mut cards).unwrap();
+    }
+
+    #[test]
+    fn rejects_an_array_literal_mixing_element_kinds() {
+        let mut cards = vec![CardBody::Array(vec![
+            CardBody::ScalarInt(1).into(),
+            CardBody::ScalarNil.into(),
+        ])
+        .into()];
+
+        let err = fold_function_cards(&mut cards).unwrap_err();
+        assert!(matches!(
+            err,
+            CompilationErrorPayload::ConstArrayTypeMismatch {
+                expected: "Integer",
+                found: "Nil",
+            }
+        ));
+    }
+
+    #[test]
+    fn rejects_constant_index_out_of_range() {
+        let mut cards = vec![CardBody::Get(Box::new([
+            CardBody::Array(vec![CardBody::ScalarInt(1).into(), CardBody::ScalarInt(2).into()])
+                .into(),
+            CardBody::ScalarInt(5).into(),
+        ]))
+        .into()];
+
+        let err = fold_function_cards(&mut cards).unwrap_err();
+        assert!(matches!(
+            err,
+            CompilationErrorPayload::ConstIndexOutOfRange { index: 5, size: 2 }
+        ));
+    }
+}
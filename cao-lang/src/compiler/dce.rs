@@ -0,0 +1,89 @@
+//! Dead-code elimination over already-flattened [`FunctionIr`]s.
+//!
+//! Runs after [`Module::into_ir_stream`](super::Module::into_ir_stream) (and, when
+//! [`CompileOptions::constant_folding`](super::CompileOptions::constant_folding) is also enabled,
+//! after that pass has had a chance to reduce conditions down to a literal) and before
+//! [`Compiler::compile`](super::Compiler::compile) walks the cards into bytecode. Two shapes of
+//! dead code are dropped:
+//!
+//! - anything sequentially following an unconditional [`CardBody::Abort`]/[`CardBody::Return`] in
+//!   the same card list, since control never reaches it;
+//! - a whole [`CardBody::While`]/[`CardBody::Repeat`] whose condition/count is a literal that
+//!   proves the loop runs zero times, rather than keeping the (now pointless) loop around with an
+//!   optimized-away body.
+//!
+//! [`Compiler::compile`] assigns each surviving card's [`CardIndex`](super::CardIndex)-keyed label
+//! by walking the card list it's actually given, so a card dropped here simply never gets a label -
+//! it isn't part of the program that gets compiled. Surviving cards are otherwise unaffected beyond
+//! shifting up to fill the gap; external tooling like breakpoints keys off a card's stable
+//! [`CardId`](super::CardId), not its position, so this doesn't invalidate those.
+use crate::alloc_crate::vec::Vec;
+
+use super::function_ir::FunctionIr;
+use super::{Card, CardBody};
+
+/// Run dead-code elimination over every function's card list in place.
+pub(crate) fn eliminate_dead_code(functions: &mut [FunctionIr]) {
+    for function in functions.iter_mut() {
+        let mut cards = core::mem::take(&mut function.cards).into_vec();
+        optimize_cards(&mut cards);
+        function.cards = cards.into_boxed_slice();
+    }
+}
+
+/// Optimize a sequential card list (a function body, or a `CompositeCard`/`Closure`'s own list):
+/// recurse into every card's nested lists, drop a loop that provably never runs, then truncate
+/// right after the first unconditional exit.
+fn optimize_cards(cards: &mut Vec<Card>) {
+    cards.retain_mut(|card| {
+        optimize_card(card);
+        !is_dead_loop(&card.body)
+    });
+
+    if let Some(cut) = cards
+        .iter()
+        .position(|card| matches!(card.body, CardBody::Abort | CardBody::Return(_)))
+    {
+        cards.truncate(cut + 1);
+    }
+}
+
+/// Recurse into a single card's nested card lists.
+fn optimize_card(card: &mut Card) {
+    match &mut card.body {
+        CardBody::CompositeCard(c) => optimize_cards(&mut c.cards),
+        CardBody::Closure(c) => optimize_cards(&mut c.cards),
+        CardBody::While(b) => {
+            let [condition, body] = b.as_mut();
+            optimize_card(condition);
+            optimize_card(body);
+        }
+        CardBody::Repeat(r) => {
+            optimize_card(&mut r.n);
+            optimize_card(&mut r.body);
+        }
+        _ => {
+            for child in card.iter_children_mut() {
+                optimize_card(child);
+            }
+        }
+    }
+}
+
+/// Is `body` a `While`/`Repeat` that's provably never going to run its body even once?
+fn is_dead_loop(body: &CardBody) -> bool {
+    match body {
+        CardBody::While(b) => is_literal_falsy(&b[0].body),
+        CardBody::Repeat(r) => matches!(&r.n.body, CardBody::ScalarInt(n) if *n <= 0),
+        _ => false,
+    }
+}
+
+/// Mirrors [`Value::as_bool`](crate::value::Value::as_bool) for the literal card bodies constant
+/// folding can leave behind, so a condition folded all the way down to a literal is recognized as
+/// statically false here too.
+fn is_literal_falsy(body: &CardBody) -> bool {
+    matches!(body, CardBody::ScalarNil)
+        || matches!(body, CardBody::ScalarInt(0))
+        || matches!(body, CardBody::ScalarFloat(f) if *f == 0.0)
+}
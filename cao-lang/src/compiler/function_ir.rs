@@ -1,4 +1,8 @@
-use std::rc::Rc;
+use crate::alloc_crate::{
+    boxed::Box,
+    rc::Rc,
+    string::{String, ToString},
+};
 
 use super::{Card, ImportsIr, NameSpace};
 use crate::VarName;
@@ -1,6 +1,7 @@
 use super::Card;
+use crate::alloc_crate::vec::Vec;
 use crate::VarName;
-use std::str::FromStr;
+use core::str::FromStr;
 
 /// Cao-lang functions
 #[derive(Debug, Clone, Default)]
@@ -4,16 +4,20 @@
 #[cfg(test)]
 mod tests;
 
+use crate::alloc_crate::{
+    rc::Rc,
+    string::{String, ToString},
+    vec::Vec,
+};
+use crate::collections::pre_hash_map::FnvHasher;
 use crate::compiler::Function;
 use crate::prelude::{CompilationErrorPayload, Handle};
+use core::hash::Hasher;
 use smallvec::SmallVec;
-use std::collections::hash_map::DefaultHasher;
-use std::hash::Hasher;
-use std::rc::Rc;
 use thiserror::Error;
 
 use super::function_ir::FunctionIr;
-use super::{Card, ImportsIr};
+use super::{Card, CardBody, ImportsIr};
 
 #[derive(Debug, Clone, Error)]
 pub enum IntoStreamError {
@@ -49,16 +53,16 @@ pub struct CardIndex {
 }
 
 impl PartialOrd for CardIndex {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
         Some(self.cmp(other))
     }
 }
 
 impl Ord for CardIndex {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
         match self.function.cmp(&other.function) {
-            std::cmp::Ordering::Equal => {}
-            c @ std::cmp::Ordering::Less | c @ std::cmp::Ordering::Greater => return c,
+            core::cmp::Ordering::Equal => {}
+            c @ core::cmp::Ordering::Less | c @ core::cmp::Ordering::Greater => return c,
         }
         for (lhs, rhs) in self
             .card_index
@@ -67,8 +71,8 @@ impl Ord for CardIndex {
             .zip(other.card_index.indices.iter())
         {
             match lhs.cmp(&rhs) {
-                std::cmp::Ordering::Equal => {}
-                c @ std::cmp::Ordering::Less | c @ std::cmp::Ordering::Greater => return c,
+                core::cmp::Ordering::Equal => {}
+                c @ core::cmp::Ordering::Less | c @ core::cmp::Ordering::Greater => return c,
             }
         }
         self.card_index
@@ -105,7 +109,7 @@ impl CardIndex {
         let function_handle = crate::prelude::Handle::from_u64(self.function as u64);
         let subindices = self.card_index.indices.as_slice();
         let sub_handle = unsafe {
-            crate::prelude::Handle::from_bytes(std::slice::from_raw_parts(
+            crate::prelude::Handle::from_bytes(core::slice::from_raw_parts(
                 subindices.as_ptr().cast(),
                 subindices.len() * 4,
             ))
@@ -146,8 +150,8 @@ impl CardIndex {
     }
 }
 
-impl std::fmt::Display for CardIndex {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for CardIndex {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "{}", self.function)?;
         for i in self.card_index.indices.iter() {
             write!(f, ".{}", i)?;
@@ -229,6 +233,38 @@ pub enum SwapError {
     InvalidSwap,
 }
 
+/// A non-fatal diagnostic from [`Module::validate`], keyed by [`CardIndex`] so an editor can
+/// highlight the offending card inline without running a full compile.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ValidationWarning {
+    #[error("card {index} jumps to {target:?}, which does not resolve to any function")]
+    UnresolvedJump { index: CardIndex, target: String },
+    #[error("card {index} is unreachable: an earlier sibling always returns or aborts")]
+    UnreachableCard { index: CardIndex },
+    #[error("import {name:?} is bound to more than one path")]
+    AmbiguousImport { name: String },
+}
+
+/// One step of an edit script produced by [`Module::diff`] and replayed by [`Module::apply`].
+/// Every variant is invertible given the card it displaced: `Insert`/`Remove` undo each other,
+/// `Replace(index, new)` undoes a `Replace(index, old)`, and swapping `from`/`to` undoes a `Move` -
+/// so an edit script doubles as an undo stack.
+#[derive(Debug, Clone)]
+pub enum CardEdit {
+    Insert(CardIndex, Card),
+    Remove(CardIndex),
+    Replace(CardIndex, Card),
+    Move { from: CardIndex, to: CardIndex },
+}
+
+#[derive(Debug, Clone, Error)]
+pub enum ApplyError {
+    #[error("failed to apply edit at {0}: {1}")]
+    FetchError(CardIndex, CardFetchError),
+    #[error("can not move {from} to {to}: {to} is inside the subtree rooted at {from}")]
+    InvalidMove { from: CardIndex, to: CardIndex },
+}
+
 impl Module {
     pub fn get_card_mut<'a>(&'a mut self, idx: &CardIndex) -> Result<&'a mut Card, CardFetchError> {
         let (_, function) = self
@@ -278,7 +314,7 @@ impl Module {
         mut rhs: &'a CardIndex,
     ) -> Result<(), SwapError> {
         if lhs < rhs {
-            std::mem::swap(&mut lhs, &mut rhs);
+            core::mem::swap(&mut lhs, &mut rhs);
         }
 
         let rhs_card = self
@@ -333,7 +369,7 @@ impl Module {
 
     /// Return the old card
     pub fn replace_card(&mut self, idx: &CardIndex, child: Card) -> Result<Card, CardFetchError> {
-        self.get_card_mut(idx).map(|c| std::mem::replace(c, child))
+        self.get_card_mut(idx).map(|c| core::mem::replace(c, child))
     }
 
     pub fn insert_card(&mut self, idx: &CardIndex, child: Card) -> Result<(), CardFetchError> {
@@ -404,7 +440,7 @@ impl Module {
 
     fn ensure_invariants<'a>(
         &'a self,
-        aux: &mut std::collections::HashSet<&'a str>,
+        aux: &mut crate::alloc_crate::collections::BTreeSet<&'a str>,
     ) -> Result<(), CompilationErrorPayload> {
         // test that submodule names are unique
         for (name, _) in self.submodules.iter() {
@@ -421,17 +457,18 @@ impl Module {
     }
 
     fn execute_imports(&self) -> Result<ImportsIr, CompilationErrorPayload> {
-        let mut result = ImportsIr::with_capacity(self.imports.len());
+        let mut result = ImportsIr::default();
+        result.reserve(self.imports.len()).expect("reserve");
 
         for import in self.imports.iter() {
             let import = import.as_str();
 
             match import.rsplit_once('.') {
                 Some((_, name)) => {
-                    if result.contains_key(name) {
+                    if result.contains(name) {
                         return Err(CompilationErrorPayload::AmbigousImport(import.to_string()));
                     }
-                    result.insert(name.to_string(), import.to_string());
+                    result.insert(name.to_string(), import.to_string()).expect("insert");
                 }
                 None => {
                     return Err(CompilationErrorPayload::BadImport(import.to_string()));
@@ -446,11 +483,44 @@ impl Module {
     ///
     /// Keys = functions, submodules, card names.
     pub fn compute_keys_hash(&self) -> u64 {
-        let mut hasher = DefaultHasher::new();
+        // a vendored FNV-1a hasher, not `std::collections::hash_map::DefaultHasher`, so this
+        // keeps working under `no_std` and stays stable across compiler versions/platforms
+        let mut hasher = FnvHasher::new();
         hash_module(&mut hasher, self);
         hasher.finish()
     }
 
+    /// A Merkle-style structural hash of the whole program, content- rather than name-addressed
+    /// like [`Module::compute_keys_hash`]: it folds in every card's variant and scalar/string
+    /// payload (see [`structural_hash_card`]), so editing a literal or reordering/replacing a
+    /// subtree changes this hash even though every name in the program stayed the same. Usable as
+    /// a cache key for incremental recompilation - unlike [`Module::compute_keys_hash`], which
+    /// can't detect a semantic edit that didn't also rename something.
+    pub fn structural_hash(&self) -> u64 {
+        let mut hasher = FnvHasher::new();
+        hash_module_structural(&mut hasher, self);
+        hasher.finish()
+    }
+
+    /// One [`structural_hash_card`]-folded hash per function in *this* module (not its
+    /// submodules - like every other [`CardIndex`]-keyed API here, addressing doesn't cross a
+    /// submodule boundary), keyed by [`CardIndex::function`]. Diffing two calls of this against
+    /// the same program before/after an edit tells a caller exactly which functions' subtrees
+    /// changed, so only those need recompiling.
+    pub fn per_function_hashes(&self) -> crate::alloc_crate::collections::BTreeMap<CardIndex, u64> {
+        self.functions
+            .iter()
+            .enumerate()
+            .map(|(i, (_, function))| {
+                let mut hasher = FnvHasher::new();
+                for card in function.cards.iter() {
+                    hasher.write_u64(structural_hash_card(card));
+                }
+                (CardIndex::function(i), hasher.finish())
+            })
+            .collect()
+    }
+
     pub fn lookup_submodule(&self, target: &str) -> Option<&Module> {
         let mut current = self;
         for submodule_name in target.split('.') {
@@ -499,6 +569,125 @@ impl Module {
         module.lookup_function_mut(function)
     }
 
+    /// Autocompletes a dotted `Jump`/import target: `"foo.ba"` walks into submodule `foo` via
+    /// [`Module::lookup_submodule`] and returns the submodule, function, and import names there
+    /// that start with `"ba"`. A `partial` with no `.` completes against `self` directly.
+    pub fn complete_path(&self, partial: &str) -> Vec<String> {
+        let (module, prefix) = match partial.rsplit_once('.') {
+            Some((path, prefix)) => match self.lookup_submodule(path) {
+                Some(module) => (module, prefix),
+                None => return Vec::new(),
+            },
+            None => (self, partial),
+        };
+
+        let mut matches: Vec<String> = module
+            .submodules
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .chain(module.functions.iter().map(|(name, _)| name.as_str()))
+            .chain(module.imports.iter().map(|import| {
+                import
+                    .rsplit_once('.')
+                    .map_or(import.as_str(), |(_, name)| name)
+            }))
+            .filter(|name| name.starts_with(prefix))
+            .map(str::to_owned)
+            .collect();
+
+        matches.sort();
+        matches.dedup();
+        matches
+    }
+
+    /// Reports problems a full compile would either reject outright or not catch at all, without
+    /// actually compiling: `Call` targets that resolve to nothing (see [`Module::resolves_jump`]),
+    /// cards that can never run because an earlier sibling unconditionally returns or aborts, and
+    /// imports whose name is ambiguous (see [`Module::execute_imports`]). Diagnostics are keyed by
+    /// [`CardIndex`] so an editor can highlight the offending card inline.
+    pub fn validate(&self) -> Vec<ValidationWarning> {
+        let mut warnings = Vec::new();
+
+        if let Err(CompilationErrorPayload::AmbigousImport(import)) = self.execute_imports() {
+            let name = import
+                .rsplit_once('.')
+                .map_or(import.as_str(), |(_, name)| name)
+                .to_string();
+            warnings.push(ValidationWarning::AmbiguousImport { name });
+        }
+
+        self.walk_cards(|index, card| {
+            if let CardBody::Call(jmp) = &card.body {
+                if !self.resolves_jump(&jmp.function_name) {
+                    warnings.push(ValidationWarning::UnresolvedJump {
+                        index: index.clone(),
+                        target: jmp.function_name.clone(),
+                    });
+                }
+            }
+        });
+
+        for (fi, (_, function)) in self.functions.iter().enumerate() {
+            let mut index = CardIndex::function(fi);
+            index.push_subindex(0);
+            self.find_unreachable(&function.cards, &mut index, &mut warnings);
+        }
+
+        warnings
+    }
+
+    /// Whether a `Call` card's `function_name` resolves to a function directly, or via an import
+    /// alias resolved the way [`Module::execute_imports`] resolves them. Doesn't attempt the
+    /// namespace-relative lookups `Compiler::lookup_function` does at actual compile time, since
+    /// this only ever runs against `self` rather than a fully flattened program.
+    fn resolves_jump(&self, target: &str) -> bool {
+        if self.lookup_function(target).is_some() {
+            return true;
+        }
+        self.imports
+            .iter()
+            .find(|import| import.rsplit_once('.').map(|(_, name)| name) == Some(target))
+            .map_or(false, |import| self.lookup_function(import).is_some())
+    }
+
+    /// Marks every card after an unconditional `Return`/`Abort` in `cards` as unreachable,
+    /// recursing into `CompositeCard`/`Closure` bodies - the only card kinds representing a
+    /// sequential block of sibling cards rather than fixed operand slots.
+    fn find_unreachable(
+        &self,
+        cards: &[Card],
+        index: &mut CardIndex,
+        warnings: &mut Vec<ValidationWarning>,
+    ) {
+        let mut terminated = false;
+        for (i, card) in cards.iter().enumerate() {
+            index.set_current_index(i);
+            if terminated {
+                warnings.push(ValidationWarning::UnreachableCard {
+                    index: index.clone(),
+                });
+            } else if matches!(
+                card.body,
+                CardBody::Return(_) | CardBody::Abort | CardBody::Break | CardBody::Continue
+            ) {
+                terminated = true;
+            }
+            match &card.body {
+                CardBody::CompositeCard(c) => {
+                    index.push_subindex(0);
+                    self.find_unreachable(&c.cards, index, warnings);
+                    index.pop_subindex();
+                }
+                CardBody::Closure(f) => {
+                    index.push_subindex(0);
+                    self.find_unreachable(&f.cards, index, warnings);
+                    index.pop_subindex();
+                }
+                _ => {}
+            }
+        }
+    }
+
     /// Visits all cards in the module recursively
     ///
     /// ```
@@ -593,12 +782,12 @@ impl Module {
         }
     }
 
-    pub fn walk_cards(&mut self, mut op: impl FnMut(&CardIndex, &Card)) {
+    pub fn walk_cards(&self, mut op: impl FnMut(&CardIndex, &Card)) {
         let mut id = CardIndex::function(0);
 
-        for (i, (_, f)) in self.functions.iter_mut().enumerate() {
+        for (i, (_, f)) in self.functions.iter().enumerate() {
             id.function = i;
-            for (j, c) in f.cards.iter_mut().enumerate() {
+            for (j, c) in f.cards.iter().enumerate() {
                 id.push_subindex(j as u32);
                 op(&id, c);
                 visit_children(c, &mut id, &mut op);
@@ -606,6 +795,191 @@ impl Module {
             }
         }
     }
+
+    /// Computes a minimal [`CardEdit`] script that turns `self` into `other`, matching cards by
+    /// [`CardId`](super::CardId) rather than content - ids are stable across save/load and editor
+    /// sessions, so this is what lets two independently-edited copies of the same program (e.g. an
+    /// undo stack, or two collaborators) be reconciled without re-keying every unrelated card.
+    /// Only functions present at the same index in both `self.functions` and `other.functions` are
+    /// compared, consistent with every other [`CardIndex`]-keyed API here being module-local -
+    /// whole-function add/remove/rename is out of scope.
+    ///
+    /// Built by replaying each candidate edit against a scratch clone of `self` as it's decided, so
+    /// every [`CardIndex`] in the returned script is valid against the tree as it stood immediately
+    /// before that edit - [`Module::apply`] just has to replay them in order.
+    pub fn diff(&self, other: &Module) -> Vec<CardEdit> {
+        let mut working = self.clone();
+        let mut edits = Vec::new();
+
+        let n = self.functions.len().min(other.functions.len());
+        for fi in 0..n {
+            let other_cards = other.functions[fi].1.cards.clone();
+            let scope = CardIndex::function(fi).with_sub_index(0);
+            diff_children(&mut working, &scope, other_cards, &mut edits);
+        }
+
+        edits
+    }
+
+    /// Replays an edit script produced by [`Module::diff`] against `self`. A `Move` is rejected
+    /// with [`ApplyError::InvalidMove`] if `to` lies inside the subtree rooted at `from` - the same
+    /// parent/descendant guard [`Module::swap_cards`] applies, since lifting `from` out would
+    /// otherwise drop the very destination it was asked to move into.
+    pub fn apply(&mut self, edits: &[CardEdit]) -> Result<(), ApplyError> {
+        for edit in edits {
+            match edit {
+                CardEdit::Insert(index, card) => {
+                    self.insert_card(index, card.clone())
+                        .map_err(|e| ApplyError::FetchError(index.clone(), e))?;
+                }
+                CardEdit::Remove(index) => {
+                    self.remove_card(index)
+                        .map_err(|e| ApplyError::FetchError(index.clone(), e))?;
+                }
+                CardEdit::Replace(index, card) => {
+                    self.replace_card(index, card.clone())
+                        .map_err(|e| ApplyError::FetchError(index.clone(), e))?;
+                }
+                CardEdit::Move { from, to } => {
+                    if is_descendant_index(from, to) {
+                        return Err(ApplyError::InvalidMove {
+                            from: from.clone(),
+                            to: to.clone(),
+                        });
+                    }
+                    let card = self
+                        .remove_card(from)
+                        .map_err(|e| ApplyError::FetchError(from.clone(), e))?;
+                    self.insert_card(to, card)
+                        .map_err(|e| ApplyError::FetchError(to.clone(), e))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether `maybe_descendant` addresses a card inside the subtree rooted at `ancestor`.
+fn is_descendant_index(ancestor: &CardIndex, maybe_descendant: &CardIndex) -> bool {
+    ancestor.function == maybe_descendant.function
+        && maybe_descendant.card_index.indices.len() > ancestor.card_index.indices.len()
+        && maybe_descendant.card_index.indices[..ancestor.card_index.indices.len()]
+            == ancestor.card_index.indices[..]
+}
+
+/// The children currently living at `scope` (a [`CardIndex`] one level deeper than their parent,
+/// per the convention [`Module::find_unreachable`] also uses) - i.e. a direct copy of whatever
+/// [`Card::iter_children`] or `function.cards` would yield for that slot right now.
+fn children_at_scope(working: &Module, scope: &CardIndex) -> Vec<Card> {
+    if scope.card_index.indices.len() == 1 {
+        working.functions[scope.function].1.cards.clone()
+    } else {
+        let mut parent = scope.clone();
+        parent.pop_subindex();
+        working
+            .get_card(&parent)
+            .expect("diff only scopes into cards it just observed to exist")
+            .iter_children()
+            .cloned()
+            .collect()
+    }
+}
+
+/// `card`'s own variant and scalar/string payload, ignoring [`CardId`] and children - two cards
+/// with the same signature may still differ in their children, which [`diff_children`] recurses
+/// into separately rather than folding them in here as [`structural_hash_card`] does.
+fn own_card_signature(card: &Card) -> u64 {
+    let mut hasher = FnvHasher::new();
+    hasher.write(card.name().as_bytes());
+    hash_own_payload(&mut hasher, &card.body);
+    hasher.finish()
+}
+
+/// Reconciles the children living at `scope` in `working` with `other_children`, recording every
+/// edit performed into `edits` and applying it to `working` as it goes (see [`Module::diff`] for
+/// why that's what keeps each edit's index valid). Cards are matched by [`CardId`]: ids missing
+/// from `other_children` are removed, ids missing from `working`'s side are inserted, ids common to
+/// both but out of order are moved into place, and ids that land on the same position are either
+/// replaced (if their own payload changed) or recursed into (to diff their own children).
+fn diff_children(
+    working: &mut Module,
+    scope: &CardIndex,
+    other_children: Vec<Card>,
+    edits: &mut Vec<CardEdit>,
+) {
+    let other_ids: crate::alloc_crate::collections::BTreeSet<_> =
+        other_children.iter().map(|c| &c.id).collect();
+    let mut children = children_at_scope(working, scope);
+
+    // 1. Drop cards that no longer exist on the other side, highest index first so earlier
+    // indices in this same list stay valid as we go.
+    for i in (0..children.len()).rev() {
+        if !other_ids.contains(&children[i].id) {
+            let index = scope.clone().with_current_index(i);
+            working
+                .remove_card(&index)
+                .expect("just observed this card to exist");
+            edits.push(CardEdit::Remove(index));
+            children.remove(i);
+        }
+    }
+
+    // 2. Insert cards that are new on the other side, in ascending target position so each
+    // insert's index is still valid for the next.
+    for (target, other_card) in other_children.iter().enumerate() {
+        if !children.iter().any(|c| c.id == other_card.id) {
+            let index = scope.clone().with_current_index(target);
+            working
+                .insert_card(&index, other_card.clone())
+                .expect("target position was just computed against the live list");
+            edits.push(CardEdit::Insert(index, other_card.clone()));
+            children.insert(target, other_card.clone());
+        }
+    }
+
+    // `children` and `other_children` now carry exactly the same ids, possibly reordered.
+    // 3. Selection-sort the remainder into `other_children`'s order. Positions before `target`
+    // are already fixed by earlier iterations, so the match is always found at `current >= target`.
+    for target in 0..other_children.len() {
+        let wanted = &other_children[target].id;
+        let current = children[target..]
+            .iter()
+            .position(|c| &c.id == wanted)
+            .map(|p| p + target)
+            .expect("ids were reconciled in steps 1-2");
+        if current != target {
+            let from = scope.clone().with_current_index(current);
+            let to = scope.clone().with_current_index(target);
+            let card = working
+                .remove_card(&from)
+                .expect("just observed this card to exist");
+            working
+                .insert_card(&to, card.clone())
+                .expect("target position was just computed against the live list");
+            edits.push(CardEdit::Move { from, to });
+            children.remove(current);
+            children.insert(target, card);
+        }
+    }
+
+    // 4. Positions now line up by id - diff each card's own payload and recurse into its children.
+    for (i, other_card) in other_children.iter().enumerate() {
+        let index = scope.clone().with_current_index(i);
+        if own_card_signature(&children[i]) != own_card_signature(other_card) {
+            working
+                .replace_card(&index, other_card.clone())
+                .expect("just observed this card to exist");
+            edits.push(CardEdit::Replace(index, other_card.clone()));
+        } else {
+            let child_scope = index.with_sub_index(0);
+            diff_children(
+                working,
+                &child_scope,
+                other_card.iter_children().cloned().collect(),
+                edits,
+            );
+        }
+    }
 }
 
 fn visit_children_mut(
@@ -643,6 +1017,72 @@ fn hash_module(hasher: &mut impl Hasher, module: &Module) {
     }
 }
 
+fn hash_module_structural(hasher: &mut impl Hasher, module: &Module) {
+    for (name, function) in module.functions.iter() {
+        hasher.write(name.as_str().as_bytes());
+        for card in function.cards.iter() {
+            hasher.write_u64(structural_hash_card(card));
+        }
+    }
+    for (name, submodule) in module.submodules.iter() {
+        hasher.write(name.as_str().as_bytes());
+        hash_module_structural(hasher, submodule);
+    }
+}
+
+/// Folds `card`'s subtree into a single hash: its own variant name and scalar/string payload (see
+/// [`hash_own_payload`] - a binary/unary op carries none of its own, since its operands are
+/// children), then each child's recursively-computed hash in the same left-to-right order
+/// [`Card::iter_children`] walks them. Two subtrees hash the same iff they're structurally
+/// identical, regardless of [`CardId`].
+fn structural_hash_card(card: &Card) -> u64 {
+    let mut hasher = FnvHasher::new();
+    hasher.write(card.name().as_bytes());
+    hash_own_payload(&mut hasher, &card.body);
+    for child in card.iter_children() {
+        hasher.write_u64(structural_hash_card(child));
+    }
+    hasher.finish()
+}
+
+fn hash_opt_str(hasher: &mut impl Hasher, s: &Option<String>) {
+    match s {
+        Some(s) => hasher.write(s.as_bytes()),
+        None => hasher.write_u8(0),
+    }
+}
+
+/// Hashes the scalar/string payload `body` carries directly, if any - cards whose only content is
+/// their children (every binary/unary expression, `Array`, `Try`, ...) contribute nothing extra
+/// here, since [`structural_hash_card`] already folds those children in.
+fn hash_own_payload(hasher: &mut impl Hasher, body: &CardBody) {
+    match body {
+        CardBody::ScalarInt(v) => hasher.write_i64(*v),
+        CardBody::ScalarFloat(v) => hasher.write_u64(v.to_bits()),
+        CardBody::StringLiteral(s)
+        | CardBody::Function(s)
+        | CardBody::NativeFunction(s)
+        | CardBody::Comment(s)
+        | CardBody::ReadVar(s) => hasher.write(s.as_bytes()),
+        CardBody::CallNative(c) => hasher.write(c.name.as_bytes()),
+        CardBody::Call(c) => hasher.write(c.function_name.as_bytes()),
+        CardBody::SetGlobalVar(sv) | CardBody::SetVar(sv) => hasher.write(sv.name.as_bytes()),
+        CardBody::Repeat(r) => hash_opt_str(hasher, &r.i),
+        CardBody::ForEach(fe) => {
+            hash_opt_str(hasher, &fe.i);
+            hash_opt_str(hasher, &fe.k);
+            hash_opt_str(hasher, &fe.v);
+        }
+        CardBody::CompositeCard(cc) => hasher.write(cc.ty.as_bytes()),
+        CardBody::Closure(f) => {
+            for arg in &f.arguments {
+                hasher.write(arg.as_bytes());
+            }
+        }
+        _ => {}
+    }
+}
+
 fn hash_function(hasher: &mut impl Hasher, function: &Function) {
     for card in function.cards.iter() {
         hasher.write(card.name().as_bytes());
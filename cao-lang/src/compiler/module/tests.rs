@@ -309,3 +309,294 @@ fn lookup_jump_target_invalid_function_is_none_test() {
     let function = program.lookup_function("foo.bar.poogers");
     assert!(function.is_none());
 }
+
+#[test]
+fn complete_path_top_level_test() {
+    let mut program = CaoProgram::default();
+    program.functions.push(("foo".to_string(), Function::default()));
+    program
+        .functions
+        .push(("foobar".to_string(), Function::default()));
+    program.functions.push(("bar".to_string(), Function::default()));
+    program
+        .submodules
+        .push(("fizz".to_string(), CaoProgram::default()));
+
+    let mut completions = program.complete_path("fo");
+    completions.sort();
+
+    assert_eq!(completions, vec!["foo".to_string(), "foobar".to_string()]);
+}
+
+#[test]
+fn complete_path_submodule_test() {
+    let mut program = CaoProgram::default();
+    program.submodules.push((
+        "foo".to_string(),
+        CaoProgram {
+            functions: vec![
+                ("poggers".to_string(), Function::default()),
+                ("pooh".to_string(), Function::default()),
+            ],
+            ..Default::default()
+        },
+    ));
+
+    let completions = program.complete_path("foo.po");
+
+    assert_eq!(
+        completions,
+        vec!["poggers".to_string(), "pooh".to_string()]
+    );
+}
+
+#[test]
+fn complete_path_unknown_submodule_is_empty_test() {
+    let program = CaoProgram::default();
+
+    assert!(program.complete_path("foo.ba").is_empty());
+}
+
+#[test]
+fn validate_reports_unresolved_jump_test() {
+    let program = CaoProgram {
+        functions: vec![(
+            "main".to_string(),
+            Function::default().with_card(Card::call_function("does-not-exist", vec![])),
+        )],
+        ..Default::default()
+    };
+
+    let warnings = program.validate();
+
+    assert!(matches!(
+        warnings.as_slice(),
+        [ValidationWarning::UnresolvedJump { target, .. }] if target == "does-not-exist"
+    ));
+}
+
+#[test]
+fn validate_accepts_resolvable_jump_test() {
+    let program = CaoProgram {
+        functions: vec![
+            (
+                "main".to_string(),
+                Function::default().with_card(Card::call_function("pooh", vec![])),
+            ),
+            ("pooh".to_string(), Function::default()),
+        ],
+        ..Default::default()
+    };
+
+    assert!(program.validate().is_empty());
+}
+
+#[test]
+fn validate_reports_unreachable_card_test() {
+    let program = CaoProgram {
+        functions: vec![(
+            "main".to_string(),
+            Function::default().with_cards(vec![
+                Card::return_card(CardBody::ScalarNil),
+                Card::string_card("never runs"),
+            ]),
+        )],
+        ..Default::default()
+    };
+
+    let warnings = program.validate();
+
+    assert!(matches!(
+        warnings.as_slice(),
+        [ValidationWarning::UnreachableCard { index }] if index == &CardIndex::new(0, 1)
+    ));
+}
+
+#[test]
+fn validate_reports_ambiguous_import_test() {
+    let program = CaoProgram {
+        imports: vec!["foo.poggers".to_string(), "bar.poggers".to_string()],
+        ..Default::default()
+    };
+
+    let warnings = program.validate();
+
+    assert!(matches!(
+        warnings.as_slice(),
+        [ValidationWarning::AmbiguousImport { name }] if name == "poggers"
+    ));
+}
+
+#[test]
+fn structural_hash_ignores_card_id_test() {
+    let mut a = CaoProgram {
+        functions: vec![(
+            "main".to_string(),
+            Function::default().with_card(CardBody::ScalarInt(42).into()),
+        )],
+        ..Default::default()
+    };
+    let mut b = a.clone();
+
+    assert_eq!(a.structural_hash(), b.structural_hash());
+
+    a.get_card_mut(&CardIndex::new(0, 0)).unwrap().id = crate::compiler::CardId(1234);
+    b.get_card_mut(&CardIndex::new(0, 0)).unwrap().id = crate::compiler::CardId(5678);
+
+    assert_eq!(
+        a.structural_hash(),
+        b.structural_hash(),
+        "the same literal under a different CardId must hash the same"
+    );
+}
+
+#[test]
+fn structural_hash_changes_with_literal_test() {
+    let a = CaoProgram {
+        functions: vec![(
+            "main".to_string(),
+            Function::default().with_card(CardBody::ScalarInt(42).into()),
+        )],
+        ..Default::default()
+    };
+    let mut b = a.clone();
+    b.get_card_mut(&CardIndex::new(0, 0)).unwrap().body = CardBody::ScalarInt(43);
+
+    assert_ne!(a.structural_hash(), b.structural_hash());
+}
+
+#[test]
+fn per_function_hashes_detects_changed_function_test() {
+    let mut program = CaoProgram {
+        functions: vec![
+            (
+                "main".to_string(),
+                Function::default().with_card(CardBody::ScalarInt(1).into()),
+            ),
+            (
+                "other".to_string(),
+                Function::default().with_card(CardBody::ScalarInt(2).into()),
+            ),
+        ],
+        ..Default::default()
+    };
+
+    let before = program.per_function_hashes();
+
+    program
+        .swap_cards(&CardIndex::new(0, 0), &CardIndex::new(1, 0))
+        .unwrap();
+
+    let after = program.per_function_hashes();
+
+    assert_ne!(before[&CardIndex::function(0)], after[&CardIndex::function(0)]);
+    assert_ne!(before[&CardIndex::function(1)], after[&CardIndex::function(1)]);
+}
+
+fn program_with_cards(cards: Vec<Card>) -> CaoProgram {
+    CaoProgram {
+        functions: vec![("main".to_string(), Function::default().with_cards(cards))],
+        ..Default::default()
+    }
+}
+
+// `b` is always derived from `a.clone()` rather than built separately: every `Card` constructor
+// hands out a fresh random id, so two independently-built trees would never share ids and `diff`
+// would see every card as unrelated - exactly like two independent edits of the same saved program.
+
+#[test]
+fn diff_insert_only_test() {
+    let a = program_with_cards(vec![Card::string_card("winnie")]);
+    let mut b = a.clone();
+    b.functions[0].1.cards.push(Card::string_card("pooh"));
+
+    let edits = a.diff(&b);
+    assert!(matches!(edits.as_slice(), [CardEdit::Insert(..)]));
+
+    let mut applied = a.clone();
+    applied.apply(&edits).unwrap();
+    assert_eq!(applied.structural_hash(), b.structural_hash());
+}
+
+#[test]
+fn diff_remove_only_test() {
+    let a = program_with_cards(vec![
+        Card::string_card("winnie"),
+        Card::string_card("pooh"),
+    ]);
+    let mut b = a.clone();
+    b.functions[0].1.cards.remove(1);
+
+    let edits = a.diff(&b);
+    assert!(matches!(edits.as_slice(), [CardEdit::Remove(..)]));
+
+    let mut applied = a.clone();
+    applied.apply(&edits).unwrap();
+    assert_eq!(applied.structural_hash(), b.structural_hash());
+}
+
+#[test]
+fn diff_replace_in_place_test() {
+    let a = program_with_cards(vec![Card::string_card("winnie")]);
+    let mut b = a.clone();
+    b.get_card_mut(&CardIndex::new(0, 0)).unwrap().body = CardBody::StringLiteral("pooh".into());
+
+    let edits = a.diff(&b);
+    assert!(matches!(edits.as_slice(), [CardEdit::Replace(..)]));
+
+    let mut applied = a.clone();
+    applied.apply(&edits).unwrap();
+    assert_eq!(applied.structural_hash(), b.structural_hash());
+}
+
+#[test]
+fn diff_move_reordered_siblings_test() {
+    let mut a = program_with_cards(vec![
+        Card::string_card("winnie"),
+        Card::string_card("pooh"),
+        Card::string_card("tiggers"),
+    ]);
+    // give every card a distinct, stable id so a `Move` - not a `Remove`+`Insert` pair - is the
+    // only edit that can reconcile the reordering
+    for (i, card) in a.functions[0].1.cards.iter_mut().enumerate() {
+        card.id = crate::compiler::CardId(i as u64 + 1);
+    }
+    let mut b = a.clone();
+    b.functions[0].1.cards.swap(0, 2);
+
+    let edits = a.diff(&b);
+    assert!(
+        edits.iter().any(|e| matches!(e, CardEdit::Move { .. })),
+        "expected at least one Move, got {edits:?}"
+    );
+
+    let mut applied = a.clone();
+    applied.apply(&edits).unwrap();
+    assert_eq!(applied.structural_hash(), b.structural_hash());
+}
+
+#[test]
+fn diff_nested_children_test() {
+    let a = program_with_cards(vec![CardBody::CompositeCard(Box::new(
+        crate::compiler::CompositeCard {
+            ty: "".to_string(),
+            cards: vec![Card::string_card("winnie")],
+        },
+    ))
+    .into()]);
+    let mut b = a.clone();
+    match &mut b.get_card_mut(&CardIndex::new(0, 0)).unwrap().body {
+        CardBody::CompositeCard(c) => c.cards.push(Card::string_card("pooh")),
+        _ => unreachable!(),
+    }
+
+    let edits = a.diff(&b);
+    assert!(matches!(
+        edits.as_slice(),
+        [CardEdit::Insert(index, _)] if index.card_index.indices.len() == 2
+    ));
+
+    let mut applied = a.clone();
+    applied.apply(&edits).unwrap();
+    assert_eq!(applied.structural_hash(), b.structural_hash());
+}
@@ -47,6 +47,58 @@ fn can_binary_de_serialize_output() {
         bincode::serde::decode_from_slice(&pl[..], bincode::config::standard()).unwrap();
 }
 
+#[test]
+#[cfg(feature = "serde")]
+fn bytecode_and_data_cbor_encode_more_compactly_than_a_naive_byte_sequence() {
+    // A few dozen global assignments give the compiler a bytecode/data buffer big enough that
+    // the per-byte CBOR tagging overhead this test checks for actually shows up.
+    let cards = (0..64)
+        .map(|i| Card::set_global_var(format!("v{i}"), Card::scalar_int(i)))
+        .collect();
+    let cu = CaoProgram {
+        imports: Default::default(),
+        submodules: Default::default(),
+        functions: [("main".into(), Function::default().with_cards(cards))].into(),
+    };
+    let program = compile(cu, CompileOptions::new()).unwrap();
+    assert!(
+        program.bytecode.len() > 64,
+        "expected a non-trivial bytecode buffer, got {} bytes",
+        program.bytecode.len()
+    );
+
+    let mut compact_payload = Vec::new();
+    ciborium::ser::into_writer(&program, &mut compact_payload).unwrap();
+
+    // The naive baseline: the exact same bytecode/data, but encoded as a plain element sequence
+    // instead of through `compact_bytes`'s `serialize_bytes`, the way a `Vec<u8>` without that
+    // annotation would be.
+    #[derive(serde::Serialize)]
+    struct NaiveBytes(Vec<u8>);
+    let mut naive_payload = Vec::new();
+    ciborium::ser::into_writer(&NaiveBytes(program.bytecode.clone()), &mut naive_payload).unwrap();
+    let mut naive_data_payload = Vec::new();
+    ciborium::ser::into_writer(&NaiveBytes(program.data.clone()), &mut naive_data_payload).unwrap();
+
+    // compact_payload carries the whole program (labels/variables/trace/...), so compare it
+    // against itself with just the two byte buffers re-encoded naively instead of their actual
+    // compact form, rather than against the stripped-down naive structs directly.
+    let naive_total = compact_payload.len() - program.bytecode.len() - program.data.len()
+        + naive_payload.len()
+        + naive_data_payload.len();
+    assert!(
+        compact_payload.len() < naive_total,
+        "compact encoding ({} bytes) should beat the naive per-element one ({} bytes)",
+        compact_payload.len(),
+        naive_total
+    );
+
+    let round_tripped: CaoCompiledProgram =
+        ciborium::de::from_reader(compact_payload.as_slice()).unwrap();
+    assert_eq!(round_tripped.bytecode, program.bytecode);
+    assert_eq!(round_tripped.data, program.data);
+}
+
 #[test]
 fn empty_varname_is_error() {
     let cu = CaoProgram {
@@ -122,6 +174,32 @@ fn duplicate_function_is_error_test() {
     let _ = compile(m, None).unwrap_err();
 }
 
+#[test]
+fn compile_diagnostics_reports_every_duplicate_name() {
+    let m = Module {
+        submodules: [].into(),
+        functions: [
+            ("main".into(), Function::default()),
+            ("foo".into(), Function::default()),
+            ("foo".into(), Function::default()),
+            ("bar".into(), Function::default()),
+            ("bar".into(), Function::default()),
+        ]
+        .into(),
+        ..Default::default()
+    };
+
+    let diagnostics = compile_diagnostics(m, None).unwrap_err();
+    assert_eq!(diagnostics.len(), 2, "expected both duplicates, got {diagnostics}");
+    for err in diagnostics.iter() {
+        assert!(matches!(
+            &err.payload,
+            CompilationErrorPayload::DuplicateName(name) if name == "foo" || name == "bar"
+        ));
+        assert_eq!(err.labels.len(), 1, "expected the original definition to be labeled");
+    }
+}
+
 #[test]
 fn test_swap_lhs_childof_rhs_fails() {
     let mut m = Module {
@@ -0,0 +1,471 @@
+//! Feature-gated disassembler, turning a compiled program's bytecode back into a readable,
+//! diffable listing.
+//!
+//! Unlike [`crate::disassembly::disassemble`] (which just echoes each opcode's `Debug` form),
+//! [`disasm`] decodes each instruction's operands via [`crate::instruction::operand_layout`] —
+//! the same declarative table [`Instruction::span`] consumes, generated by `build.rs` from
+//! `instructions.in` — so a new instruction's operands show up here without a matching edit to
+//! this file. `Goto`/`GotoIfTrue`/`GotoIfFalse`, `StringLiteral` and `SetGlobalVar`/
+//! `ReadGlobalVar` are the cases that need instruction-specific knowledge: jump operands are
+//! resolved against the program's [`Labels`] table, so jumps read as `-> @<label>` instead of a
+//! bare byte offset; `StringLiteral`'s data-segment offset is resolved to the actual string text
+//! instead of a raw number; and a global variable's `VarId` operand is resolved back to the name
+//! it was compiled from via [`crate::compiled_program::Variables::names`], falling back to the
+//! bare id if the program carries no name for it. There is no name travelling with a compiled
+//! program for jump targets (labels are keyed by structural [`crate::compiler::CardIndex`]
+//! hashes, not source names), so labels print as their numeric handle, not `lane.sub.name`.
+//! Offsets that a [`crate::compiled_program::Labels`] entry points at are themselves annotated
+//! with `@<handle>:` on the line above, so a listing reads as a set of jump destinations instead
+//! of requiring the reader to cross-reference jump operands by hand.
+//!
+//! [`crate::compiled_program::CaoCompiledProgram::disassemble`] is a thin text-formatting
+//! wrapper around [`disasm_entries`], kept for callers that only have access to the bare
+//! `CaoCompiledProgram` type without the `disasm` feature's richer [`DisasmError`]/[`disasm`] API.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use num_enum::{TryFromPrimitive, TryFromPrimitiveError};
+
+use crate::{
+    bytecode::{decode_str, read_from_bytes},
+    collections::handle_table::Handle,
+    compiled_program::Variables,
+    instruction::{operand_layout, Instruction, OperandKind},
+    prelude::CaoCompiledProgram,
+    VariableId,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisasmError {
+    InvalidInstruction(u8),
+    /// `offset`'s instruction needs more operand bytes than remain in the bytecode stream - the
+    /// program was truncated (or never valid) after the opcode byte.
+    TruncatedOperand { offset: u32 },
+}
+
+impl core::fmt::Display for DisasmError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::InvalidInstruction(instr) => write!(f, "Got an invalid instruction code {instr}"),
+            Self::TruncatedOperand { offset } => write!(
+                f,
+                "Instruction at offset {offset} expects more operand bytes than remain in the bytecode stream"
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DisasmError {}
+
+impl From<TryFromPrimitiveError<Instruction>> for DisasmError {
+    fn from(err: TryFromPrimitiveError<Instruction>) -> Self {
+        Self::InvalidInstruction(err.number)
+    }
+}
+
+/// Checks that `instr`'s full span (opcode + operands), starting at `offset`, fits within a
+/// bytecode stream of `len` bytes - the one invariant [`collect_operands`]'s per-operand decoding
+/// can't itself enforce, since it bails out silently on a short read instead of erroring.
+fn checked_span(instr: Instruction, offset: usize, len: usize) -> Result<usize, DisasmError> {
+    let span = instr.span();
+    if offset + span > len {
+        return Err(DisasmError::TruncatedOperand {
+            offset: offset as u32,
+        });
+    }
+    Ok(span)
+}
+
+/// Disassembles `program`'s bytecode into one line per instruction: `<offset>: <mnemonic> <operands>`.
+pub fn disasm(program: &CaoCompiledProgram) -> Result<String, DisasmError> {
+    let mut out = String::with_capacity(program.bytecode.len() * 12);
+    for entry in disasm_entries(program)? {
+        writeln!(out, "{entry}").unwrap();
+    }
+    Ok(out)
+}
+
+/// A single disassembled instruction, as data rather than pre-rendered text - meant to be
+/// serialized (e.g. to JSON) for editors/debuggers to consume instead of scraping [`disasm`]'s
+/// text output.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DisasmEntry {
+    pub offset: u32,
+    pub opcode: String,
+    pub operands: Vec<String>,
+    /// Set when this instruction is itself a jump target, i.e. `program.labels` has an entry
+    /// whose [`crate::compiled_program::Label::pos`] equals `offset` - the label this offset is
+    /// known by, rendered the same way jump operands print their target (`@<handle>`).
+    pub label: Option<String>,
+    /// The source function/lane that produced this instruction, if the `debug-info` feature is
+    /// enabled and the program carries a [`crate::compiled_program::SourceMap`].
+    #[cfg(feature = "debug-info")]
+    pub source: Option<String>,
+}
+
+impl core::fmt::Display for DisasmEntry {
+    /// Renders this one instruction the same way [`disasm`]'s text listing does: the label line
+    /// (if this offset is a jump target) followed by `<offset>: <mnemonic> <operands>`.
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if let Some(label) = &self.label {
+            writeln!(f, "{label}:")?;
+        }
+        write!(f, "{:>6}: {}", self.offset, self.opcode)?;
+        for operand in &self.operands {
+            write!(f, " {operand}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Builds the same per-instruction breakdown as [`disasm`], but as structured [`DisasmEntry`]
+/// values instead of pre-rendered text.
+pub fn disasm_entries(program: &CaoCompiledProgram) -> Result<Vec<DisasmEntry>, DisasmError> {
+    let labels_by_offset: HashMap<u32, Handle> = program
+        .labels
+        .0
+        .iter()
+        .map(|(handle, label)| (label.pos, handle))
+        .collect();
+
+    let bytecode = &program.bytecode;
+    let mut out = Vec::new();
+    let mut offset = 0usize;
+    while offset < bytecode.len() {
+        let instr = Instruction::try_from_primitive(bytecode[offset])?;
+        let span = checked_span(instr, offset, bytecode.len())?;
+        let operands = collect_operands(
+            instr,
+            &bytecode[offset + 1..],
+            &labels_by_offset,
+            &program.data,
+            &program.variables,
+        );
+        out.push(DisasmEntry {
+            offset: offset as u32,
+            opcode: format!("{instr:?}"),
+            operands,
+            label: labels_by_offset
+                .get(&(offset as u32))
+                .map(|handle| format!("@{handle:?}")),
+            #[cfg(feature = "debug-info")]
+            source: program
+                .source_location(offset as u32)
+                .map(|trace| trace.to_string()),
+        });
+        offset += span;
+    }
+    Ok(out)
+}
+
+/// Builds the same per-instruction breakdown as [`disasm`]/[`disasm_entries`], but as `(offset,
+/// line)` pairs - a lighter-weight shape than [`DisasmEntry`] for callers that only want to print
+/// or diff a listing line-by-line without the structured operand/label fields.
+pub fn disasm_lines(program: &CaoCompiledProgram) -> Result<Vec<(u32, String)>, DisasmError> {
+    Ok(disasm_entries(program)?
+        .into_iter()
+        .map(|entry| {
+            let mut line = entry.opcode;
+            for operand in entry.operands {
+                write!(line, " {operand}").unwrap();
+            }
+            (entry.offset, line)
+        })
+        .collect())
+}
+
+/// Walks `program`'s bytecode one instruction at a time, yielding `(offset, instr, operand
+/// bytes)` straight off the wire instead of [`disasm_entries`]'s pre-rendered [`DisasmEntry`] -
+/// for tooling that wants to decode operands itself (e.g. into its own editor-specific structure)
+/// rather than consume the rendered strings this module already produces.
+pub fn disasm_raw(
+    program: &CaoCompiledProgram,
+) -> impl Iterator<Item = Result<(u32, Instruction, &[u8]), DisasmError>> {
+    let bytecode = program.bytecode.as_slice();
+    let mut offset = 0usize;
+    core::iter::from_fn(move || {
+        if offset >= bytecode.len() {
+            return None;
+        }
+        let instr = match Instruction::try_from_primitive(bytecode[offset]) {
+            Ok(instr) => instr,
+            Err(err) => return Some(Err(err.into())),
+        };
+        let span = match checked_span(instr, offset, bytecode.len()) {
+            Ok(span) => span,
+            Err(err) => return Some(Err(err)),
+        };
+        let this_offset = offset as u32;
+        let operands = &bytecode[offset + 1..offset + span];
+        offset += span;
+        Some(Ok((this_offset, instr, operands)))
+    })
+}
+
+/// Decodes `instr`'s operands off the front of `operands`, rendered the same way for both
+/// [`disasm`]'s text listing and [`disasm_entries`]'s structured form. `data` is the owning
+/// program's constant pool, needed to resolve `StringLiteral`'s operand from a raw offset into
+/// the actual text, the same way the VM decodes string literals at runtime. `variables` resolves
+/// `SetGlobalVar`/`ReadGlobalVar`'s `VarId` operand back to the source name it was compiled from.
+fn collect_operands(
+    instr: Instruction,
+    operands: &[u8],
+    labels_by_offset: &HashMap<u32, Handle>,
+    data: &[u8],
+    variables: &Variables,
+) -> Vec<String> {
+    if matches!(
+        instr,
+        Instruction::Goto | Instruction::GotoIfTrue | Instruction::GotoIfFalse
+    ) {
+        return match read_from_bytes::<i32>(operands) {
+            Some((_, target)) => match labels_by_offset.get(&(target as u32)) {
+                Some(handle) => vec![format!("-> @{handle:?} ({target})")],
+                None => vec![format!("-> {target}")],
+            },
+            None => Vec::new(),
+        };
+    }
+
+    if instr == Instruction::StringLiteral {
+        return match read_from_bytes::<u32>(operands) {
+            Some((_, offset)) => match data
+                .get(offset as usize..)
+                .and_then(|rest| decode_str(rest))
+            {
+                Some((_, s)) => vec![format!("{s:?}")],
+                None => vec![format!("<bad string offset {offset}>")],
+            },
+            None => Vec::new(),
+        };
+    }
+
+    let mut rest = operands;
+    let mut out = Vec::new();
+    for kind in operand_layout(instr) {
+        match read_operand(*kind, rest, variables) {
+            Some((read, rendered)) => {
+                out.push(rendered);
+                rest = &rest[read..];
+            }
+            None => break,
+        }
+    }
+    out
+}
+
+/// Decodes a single operand of `kind` off the front of `bytes`, returning the bytes consumed and
+/// its rendered form. `variables` resolves a `VarId` operand back to the name it was compiled
+/// from, falling back to the bare id if the program carries no name for it (e.g. hand-assembled
+/// bytecode built without going through the compiler).
+fn read_operand(kind: OperandKind, bytes: &[u8], variables: &Variables) -> Option<(usize, String)> {
+    match kind {
+        OperandKind::U8 => read_from_bytes::<u8>(bytes).map(|(n, v)| (n, v.to_string())),
+        OperandKind::U32 => read_from_bytes::<u32>(bytes).map(|(n, v)| (n, v.to_string())),
+        OperandKind::I32 => read_from_bytes::<i32>(bytes).map(|(n, v)| (n, v.to_string())),
+        OperandKind::I64 => read_from_bytes::<i64>(bytes).map(|(n, v)| (n, v.to_string())),
+        OperandKind::F64 => read_from_bytes::<f64>(bytes).map(|(n, v)| (n, v.to_string())),
+        OperandKind::Handle => {
+            read_from_bytes::<Handle>(bytes).map(|(n, v)| (n, format!("@{v:?}")))
+        }
+        OperandKind::VarId => read_from_bytes::<VariableId>(bytes).map(|(n, v)| {
+            let rendered = match variables.names.get(Handle::from_u32(v.0)) {
+                Some(name) => name.to_string(),
+                None => format!("{v:?}"),
+            };
+            (n, rendered)
+        }),
+    }
+}
+
+/// Golden-file-style tests for [`disasm`]/[`disasm_entries`]: hand-assembled bytecode (built with
+/// the same [`write_to_vec`] helper the compiler uses to emit operands) instead of going through
+/// `compile`, so a regression here can only come from `disasm` itself drifting from the decode
+/// widths, not from unrelated compiler changes.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bytecode::write_to_vec, compiled_program::Label, instruction::Instruction};
+
+    fn program_with(bytecode: Vec<u8>, labels: &[(u32, u32)]) -> CaoCompiledProgram {
+        let mut program = CaoCompiledProgram {
+            bytecode,
+            ..Default::default()
+        };
+        for &(handle, pos) in labels {
+            program
+                .labels
+                .0
+                .insert(Handle(handle), Label::new(pos))
+                .unwrap();
+        }
+        program
+    }
+
+    #[test]
+    fn disassembles_scalars_and_arithmetic() {
+        let mut bytecode = Vec::new();
+        bytecode.push(Instruction::ScalarInt as u8);
+        write_to_vec(42i64, &mut bytecode);
+        bytecode.push(Instruction::ScalarInt as u8);
+        write_to_vec(1i64, &mut bytecode);
+        bytecode.push(Instruction::Add as u8);
+        bytecode.push(Instruction::Exit as u8);
+
+        let program = program_with(bytecode, &[]);
+
+        let out = disasm(&program).expect("disasm");
+        assert_eq!(
+            out,
+            "     0: ScalarInt 42\n     9: ScalarInt 1\n    18: Add\n    19: Exit\n"
+        );
+
+        let entries = disasm_entries(&program).expect("disasm_entries");
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[0].offset, 0);
+        assert_eq!(entries[0].opcode, "ScalarInt");
+        assert_eq!(entries[0].operands, vec!["42".to_string()]);
+        assert_eq!(entries[2].opcode, "Add");
+        assert!(entries[2].operands.is_empty());
+    }
+
+    #[test]
+    fn resolves_jump_targets_against_labels() {
+        let mut bytecode = Vec::new();
+        bytecode.push(Instruction::Goto as u8);
+        write_to_vec(5i32, &mut bytecode);
+        bytecode.push(Instruction::Exit as u8);
+
+        let program = program_with(bytecode, &[(7, 5)]);
+
+        let out = disasm(&program).expect("disasm");
+        assert!(out.contains("-> @"));
+        assert!(out.contains("(5)"));
+    }
+
+    #[test]
+    fn annotates_jump_targets_with_their_label() {
+        let mut bytecode = Vec::new();
+        bytecode.push(Instruction::Goto as u8);
+        write_to_vec(5i32, &mut bytecode);
+        bytecode.push(Instruction::Exit as u8);
+
+        let program = program_with(bytecode, &[(7, 5)]);
+
+        let out = disasm(&program).expect("disasm");
+        let label_line_idx = out.lines().position(|l| l.starts_with('@') && l.ends_with(':'));
+        let label_line_idx = label_line_idx.expect("no label line rendered");
+        assert!(out.lines().nth(label_line_idx + 1).unwrap().contains("Exit"));
+
+        let entries = disasm_entries(&program).expect("disasm_entries");
+        assert_eq!(entries[0].label, None);
+        assert!(entries[1].label.as_deref().unwrap().starts_with('@'));
+    }
+
+    #[test]
+    fn resolves_string_literals_against_the_data_segment() {
+        let mut data = Vec::new();
+        write_to_vec(3u32, &mut data);
+        data.extend_from_slice(b"pog");
+
+        let mut bytecode = Vec::new();
+        bytecode.push(Instruction::StringLiteral as u8);
+        write_to_vec(0u32, &mut bytecode);
+        bytecode.push(Instruction::Exit as u8);
+
+        let mut program = program_with(bytecode, &[]);
+        program.data = data;
+
+        let out = disasm(&program).expect("disasm");
+        assert!(out.contains("\"pog\""), "unexpected output: {out}");
+
+        let entries = disasm_entries(&program).expect("disasm_entries");
+        assert_eq!(entries[0].operands, vec!["\"pog\"".to_string()]);
+    }
+
+    #[test]
+    fn resolves_global_var_names_against_variables() {
+        let mut bytecode = Vec::new();
+        bytecode.push(Instruction::SetGlobalVar as u8);
+        write_to_vec(VariableId(3), &mut bytecode);
+        bytecode.push(Instruction::Exit as u8);
+
+        let mut program = program_with(bytecode, &[]);
+        program
+            .variables
+            .names
+            .insert(Handle::from_u32(3), "counter".to_string())
+            .unwrap();
+
+        let out = disasm(&program).expect("disasm");
+        assert!(out.contains("counter"), "unexpected output: {out}");
+
+        let entries = disasm_entries(&program).expect("disasm_entries");
+        assert_eq!(entries[0].operands, vec!["counter".to_string()]);
+    }
+
+    #[test]
+    fn disasm_lines_pairs_offset_with_rendered_text() {
+        let mut bytecode = Vec::new();
+        bytecode.push(Instruction::ScalarInt as u8);
+        write_to_vec(42i64, &mut bytecode);
+        bytecode.push(Instruction::Exit as u8);
+
+        let program = program_with(bytecode, &[]);
+
+        let lines = disasm_lines(&program).expect("disasm_lines");
+        assert_eq!(
+            lines,
+            vec![(0, "ScalarInt 42".to_string()), (9, "Exit".to_string())]
+        );
+    }
+
+    #[test]
+    fn disasm_raw_yields_undecoded_operand_bytes() {
+        let mut bytecode = Vec::new();
+        bytecode.push(Instruction::ScalarInt as u8);
+        write_to_vec(42i64, &mut bytecode);
+        bytecode.push(Instruction::Exit as u8);
+
+        let program = program_with(bytecode, &[]);
+
+        let entries: Vec<_> = disasm_raw(&program)
+            .collect::<Result<_, _>>()
+            .expect("disasm_raw");
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].0, 0);
+        assert_eq!(entries[0].1, Instruction::ScalarInt);
+        let (_, decoded) = read_from_bytes::<i64>(entries[0].2).expect("decode operand");
+        assert_eq!(decoded, 42);
+        assert_eq!(entries[1], (9, Instruction::Exit, &[][..]));
+    }
+
+    #[test]
+    fn reports_invalid_opcodes() {
+        let program = program_with(vec![0xff], &[]);
+
+        assert_eq!(disasm(&program), Err(DisasmError::InvalidInstruction(0xff)));
+    }
+
+    #[test]
+    fn reports_truncated_operands_instead_of_panicking() {
+        // ScalarInt wants an 8-byte i64 operand; only give it one byte.
+        let program = program_with(vec![Instruction::ScalarInt as u8, 0], &[]);
+
+        assert_eq!(
+            disasm(&program),
+            Err(DisasmError::TruncatedOperand { offset: 0 })
+        );
+        assert_eq!(
+            disasm_entries(&program),
+            Err(DisasmError::TruncatedOperand { offset: 0 })
+        );
+        assert_eq!(
+            disasm_raw(&program).collect::<Result<Vec<_>, _>>(),
+            Err(DisasmError::TruncatedOperand { offset: 0 })
+        );
+    }
+}
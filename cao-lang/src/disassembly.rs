@@ -1,7 +1,110 @@
+use core::fmt::Write;
+
 use num_enum::TryFromPrimitive;
-use std::fmt::Write;
 
-use crate::{instruction::Instruction, prelude::CaoCompiledProgram};
+use crate::alloc_crate::{
+    collections::{BTreeMap, BTreeSet},
+    string::String,
+    vec::Vec,
+};
+use crate::{bytecode::read_from_bytes, instruction::Instruction, prelude::CaoCompiledProgram};
+
+/// Is this instruction a conditional jump? (i.e. it has both a branch and a fall-through edge)
+fn is_conditional_jump(instr: Instruction) -> bool {
+    matches!(instr, Instruction::GotoIfTrue | Instruction::GotoIfFalse)
+}
+
+/// Is this instruction an unconditional jump (no fall-through edge)?
+fn is_unconditional_jump(instr: Instruction) -> bool {
+    matches!(instr, Instruction::Goto)
+}
+
+/// Does execution stop flowing into the next instruction after this one?
+fn ends_block(instr: Instruction) -> bool {
+    is_unconditional_jump(instr) || is_conditional_jump(instr) || matches!(instr, Instruction::Exit | Instruction::Return)
+}
+
+/// Decode the absolute jump target of a `Goto`/`GotoIfTrue`/`GotoIfFalse` instruction.
+///
+/// `offset` must point at the opcode byte itself.
+fn jump_target(bytecode: &[u8], offset: usize) -> usize {
+    let (_, target): (_, i32) =
+        read_from_bytes(&bytecode[offset + 1..]).expect("Failed to read jump target");
+    target as usize
+}
+
+/// Emit a Graphviz `digraph` of `program`'s control-flow: one node per basic block (offset 0, any
+/// jump target and the instruction right after a jump/return are block leaders), with edges for
+/// fall-through and branch targets. Conditional jumps get two out-edges.
+///
+/// Paste the output into any DOT renderer (e.g. `dot -Tsvg`) to inspect lane control flow.
+pub fn disassemble_dot(program: &CaoCompiledProgram) -> String {
+    let bytecode = &program.bytecode;
+
+    // Pass 1: walk the bytecode once, recording each instruction's offset/span and the set of
+    // block leaders.
+    let mut instrs = Vec::new();
+    let mut leaders = BTreeSet::new();
+    leaders.insert(0);
+    let mut i = 0;
+    while i < bytecode.len() {
+        match Instruction::try_from_primitive(bytecode[i]) {
+            Ok(instr) => {
+                let span = instr.span();
+                instrs.push((i, instr));
+                if is_conditional_jump(instr) || is_unconditional_jump(instr) {
+                    leaders.insert(jump_target(bytecode, i));
+                }
+                if ends_block(instr) && i + span < bytecode.len() {
+                    leaders.insert(i + span);
+                }
+                i += span;
+            }
+            Err(_) => break,
+        }
+    }
+
+    // Pass 2: partition the instruction stream into basic blocks delimited by the leaders.
+    let mut blocks: BTreeMap<usize, Vec<(usize, Instruction)>> =
+        leaders.iter().map(|&l| (l, Vec::new())).collect();
+    for (offset, instr) in instrs {
+        let block_start = *leaders.range(..=offset).next_back().unwrap();
+        blocks.get_mut(&block_start).unwrap().push((offset, instr));
+    }
+
+    let mut out = String::new();
+    writeln!(&mut out, "digraph {{").unwrap();
+    for (&start, body) in &blocks {
+        writeln!(&mut out, "  \"{start}\" [shape=box label=\"").unwrap();
+        for (offset, instr) in body {
+            writeln!(&mut out, "{offset}: {instr:?}\\l").unwrap();
+        }
+        writeln!(&mut out, "\"];").unwrap();
+
+        match body.last() {
+            Some(&(offset, instr)) if is_conditional_jump(instr) => {
+                let target = jump_target(bytecode, offset);
+                writeln!(&mut out, "  \"{start}\" -> \"{target}\" [label=\"true\"];").unwrap();
+                if let Some(&fallthrough) = leaders.range((offset + 1)..).next() {
+                    writeln!(&mut out, "  \"{start}\" -> \"{fallthrough}\" [label=\"false\"];")
+                        .unwrap();
+                }
+            }
+            Some(&(offset, instr)) if is_unconditional_jump(instr) => {
+                let target = jump_target(bytecode, offset);
+                writeln!(&mut out, "  \"{start}\" -> \"{target}\";").unwrap();
+            }
+            Some(&(_, instr)) if matches!(instr, Instruction::Exit | Instruction::Return) => {}
+            _ => {
+                if let Some(&fallthrough) = leaders.range((start + 1)..).next() {
+                    writeln!(&mut out, "  \"{start}\" -> \"{fallthrough}\";").unwrap();
+                }
+            }
+        }
+    }
+    writeln!(&mut out, "}}").unwrap();
+    out
+}
 
 pub fn disassemble(program: &CaoCompiledProgram) -> String {
     let mut result = String::with_capacity(program.bytecode.len() * 20);
@@ -40,4 +143,18 @@ mod tests {
 
         panic!("\n{dis}");
     }
+
+    #[test]
+    fn basic_dot_test() {
+        let program = Module {
+            functions: vec![("main".to_string(), Function::default())],
+            ..Default::default()
+        };
+
+        let prog = compile(program, None).expect("compile");
+        let dot = disassemble_dot(&prog);
+
+        assert!(dot.starts_with("digraph {"));
+        assert!(dot.contains("\"0\""));
+    }
 }
@@ -1,4 +1,4 @@
-use std::mem::size_of;
+use core::mem::size_of;
 
 use crate::{prelude::Handle, VariableId};
 
@@ -14,6 +14,36 @@ pub(crate) enum Instruction {
     Mul,
     /// Divide the first number by the second
     Div,
+    /// Remainder of the first number divided by the second. On integers, follows Rust's `%`:
+    /// truncating division, result has the sign of the dividend (e.g. `-7 % 2 == -1`).
+    Mod,
+    /// Raise the first number to the power of the second
+    Pow,
+    /// Negate a number
+    Neg,
+    /// Absolute value of a number
+    Abs,
+    /// The smaller of two numbers
+    Min,
+    /// The larger of two numbers
+    Max,
+    /// Round a number down to the nearest integer
+    Floor,
+    /// Round a number up to the nearest integer
+    Ceil,
+    /// Round a number to the nearest integer
+    Round,
+    /// Bitwise AND of two integers
+    BitAnd,
+    /// Bitwise OR of two integers
+    BitOr,
+    /// Bitwise XOR of two integers
+    BitXor,
+    /// Left shift of the first integer by the second
+    Shl,
+    /// Arithmetic right shift of the first integer by the second (sign-extending, following
+    /// Rust's `>>` on signed integers)
+    Shr,
     /// Call a function provided by the runtime
     /// Requires function name as a string as input
     CallNative,
@@ -33,6 +63,13 @@ pub(crate) enum Instruction {
     Exit,
     /// Read bytecode position and Function arity from the program and perform a jump there.
     CallFunction,
+    /// Like [`CallFunction`](Instruction::CallFunction), but emitted only when the call is the
+    /// last thing the current function does: instead of pushing a new
+    /// [`CallFrame`](crate::vm::runtime::CallFrame) it reuses the current one, so the call stack
+    /// never grows across a chain of tail calls. A `NativeFunction` callee can't reuse a frame
+    /// this way, so it falls back to an ordinary call followed by an immediate
+    /// [`Return`](Instruction::Return).
+    TailCall,
     /// Compares two scalars
     Equals,
     /// Compares two scalars
@@ -60,6 +97,16 @@ pub(crate) enum Instruction {
     Return,
     /// Swaps the last two values on the stack
     SwapLast,
+    /// Pushes a copy of the value `n` entries from the top of the stack (0 = the last value),
+    /// generalizing [`CopyLast`](Instruction::CopyLast). Does nothing if `n` is past the bottom
+    /// of the stack.
+    Pick,
+    /// Swaps the values `i` and `j` entries from the top of the stack (0 = the last value,
+    /// matching `Pick`'s indexing). Does nothing if either index is out of bounds.
+    Swap,
+    /// Cyclically rotates the top `n` entries, moving the topmost value down to the bottom of
+    /// that window. `n` is clamped to the current stack depth.
+    Rotate,
     And,
     Or,
     Xor,
@@ -81,8 +128,8 @@ pub(crate) enum Instruction {
     ///
     /// The reason `value` is the first to be pushed is the read/setvar shorthands
     SetProperty,
-    /// Pushes the length of the topmost table to the stack
-    /// Errors if the top Value is not a Table
+    /// Pushes the length of the topmost value to the stack: a table's row count, a string's byte
+    /// length, `1` for scalars, `0` for `nil`. See [`crate::vm::instr_execution::instr_len`].
     Len,
 
     BeginForEach,
@@ -103,61 +150,117 @@ pub(crate) enum Instruction {
     ReadUpvalue,
     RegisterUpvalue,
     CloseUpvalue,
+    /// Pops the top of the value stack and pauses the running program at this instruction
+    /// boundary, handing control back to the host as a [`crate::vm::RunOutcome::Yielded`] (see
+    /// [`crate::vm::Suspended::yielded_value`]) the same way [`crate::vm::Vm::suspend`] does -
+    /// but triggered directly from bytecode instead of requiring a native function to call it.
+    /// Only meaningful under [`crate::vm::Vm::run_resumable`]/[`crate::vm::Vm::resume`]; under
+    /// plain [`crate::vm::Vm::run`] it fails the program like any other unhandled suspend.
+    Yield,
+    /// Installs an exception handler: records the current value/call stack depths and the
+    /// bytecode position given by the instruction's operand, so that a fault or [`Throw`] raised
+    /// before the matching `PopHandler` unwinds here instead of aborting the program. Used to
+    /// compile [`crate::compiler::Card::Try`]'s `body`.
+    ///
+    /// [`Throw`]: Instruction::Throw
+    PushHandler,
+    /// Uninstalls the handler installed by the most recent `PushHandler` still active - emitted
+    /// after `Try`'s `body` completes normally, so a fault further down the lane doesn't
+    /// mistakenly unwind to a handler whose guarded region has already finished.
+    PopHandler,
+    /// Pops a value from the stack and raises it, unwinding to the nearest still-installed
+    /// `PushHandler` target - or failing the program if none is installed. Compiles
+    /// [`crate::compiler::Card::Throw`].
+    Throw,
+    /// Pops inclusive bounds `lo, hi` (pushed in that order, so `hi` is on top) and pushes a
+    /// uniform random integer in `[lo, hi]`, drawn from the VM's seeded xorshift64 generator (see
+    /// [`crate::vm::runtime::RuntimeData::next_random_range`]). Errors if `hi < lo`. Compiles
+    /// [`crate::compiler::Card::Random`].
+    Random,
+    /// Pops `count, sides` (pushed in that order, so `sides` is on top) and pushes the sum of
+    /// `count` independent uniform rolls of `1..=sides`, e.g. a `3d6` expression. Built on the
+    /// same generator as [`Random`](Instruction::Random). Compiles
+    /// [`crate::compiler::Card::DiceRoll`].
+    DiceRoll,
+    /// Pops an Integer scrutinee and jumps based on a dense jump table stored out-of-band in
+    /// [`CaoCompiledProgram::data`](crate::compiled_program::CaoCompiledProgram::data), the way
+    /// [`StringLiteral`](Instruction::StringLiteral) stores its string out-of-band: the single
+    /// `U32` operand is an offset into that table of `min: i64, default: i32, len: u32`, followed
+    /// by `len` `i32` bytecode offsets, one per key in `[min, min + len)`. Jumps to the offset for
+    /// `scrutinee - min` if that falls inside the table, or to `default` otherwise. Compiles the
+    /// dense-key case of [`crate::compiler::Card::Switch`]; see [`crate::vm::instr_execution::instr_switch`].
+    Switch,
+}
+
+/// A single operand of an [`Instruction`], as wide as whatever it was encoded with by
+/// [`crate::bytecode::write_to_vec`]. See `instructions.in` at the crate root: this is the set of
+/// kinds that table's `operand,kinds` column may name.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum OperandKind {
+    U8,
+    U32,
+    I32,
+    I64,
+    F64,
+    Handle,
+    VarId,
 }
 
+impl OperandKind {
+    pub(crate) fn byte_width(self) -> usize {
+        match self {
+            OperandKind::U8 => size_of::<u8>(),
+            OperandKind::U32 => size_of::<u32>(),
+            OperandKind::I32 => size_of::<i32>(),
+            OperandKind::I64 => size_of::<i64>(),
+            OperandKind::F64 => size_of::<f64>(),
+            OperandKind::Handle => size_of::<Handle>(),
+            OperandKind::VarId => size_of::<VariableId>(),
+        }
+    }
+}
+
+// Generated by `build.rs` from `instructions.in`: `operand_layout` and `INSTRUCTION_COUNT`.
+include!(concat!(env!("OUT_DIR"), "/cao_lang_instructions.rs"));
+
 impl Instruction {
-    /// Returns the span of this instruction in bytecode
+    /// Returns the span of this instruction in bytecode: the opcode byte, plus its operands'
+    /// widths as declared in `instructions.in`.
     #[allow(unused)]
     pub fn span(self) -> usize {
-        let data_span = match self {
-            Instruction::CallFunction
-            | Instruction::Sub
-            | Instruction::Mul
-            | Instruction::Div
-            | Instruction::ScalarNil
-            | Instruction::CopyLast
-            | Instruction::Exit
-            | Instruction::Equals
-            | Instruction::NotEquals
-            | Instruction::Less
-            | Instruction::LessOrEq
-            | Instruction::Pop
-            | Instruction::ClearStack
-            | Instruction::Return
-            | Instruction::SwapLast
-            | Instruction::And
-            | Instruction::Or
-            | Instruction::Xor
-            | Instruction::Not
-            | Instruction::InitTable
-            | Instruction::GetProperty
-            | Instruction::SetProperty
-            | Instruction::Len
-            | Instruction::NthRow
-            | Instruction::AppendTable
-            | Instruction::PopTable
-            | Instruction::CloseUpvalue
-            | Instruction::Add => 0,
-            Instruction::CallNative => size_of::<Handle>(),
-            Instruction::ScalarInt => size_of::<i64>(),
-            Instruction::ScalarFloat => size_of::<f64>(),
-            Instruction::StringLiteral => size_of::<u32>(),
-            Instruction::NativeFunctionPointer => Instruction::StringLiteral.span(),
-            Instruction::SetGlobalVar => size_of::<VariableId>(),
-            Instruction::ReadGlobalVar => size_of::<VariableId>(),
-            Instruction::SetLocalVar
-            | Instruction::SetUpvalue
-            | Instruction::ReadUpvalue
-            | Instruction::ReadLocalVar => size_of::<u32>(),
-            Instruction::Goto | Instruction::GotoIfTrue | Instruction::GotoIfFalse => {
-                size_of::<i32>()
-            }
-            Instruction::BeginForEach => size_of::<u32>() * 2,
-            Instruction::ForEach => size_of::<u32>() * 5,
-            Instruction::FunctionPointer => size_of::<Handle>() + size_of::<u32>(),
-            Instruction::Closure => size_of::<Handle>() + size_of::<u32>(),
-            Instruction::RegisterUpvalue => size_of::<u8>() * 2,
-        };
+        let data_span: usize = operand_layout(self).iter().map(|kind| kind.byte_width()).sum();
         1 + data_span
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_enum::TryFromPrimitive;
+
+    /// `operand_layout`/`INSTRUCTION_COUNT`/[`Instruction::span`] are all driven by
+    /// `instructions.in`, but this enum's variant names and discriminant order are still
+    /// hand-maintained here rather than generated from that same table - nothing stops the two
+    /// from drifting apart if a variant is renamed, reordered, or removed on only one side. This
+    /// checks every opcode's `{:?}` spelling against the corresponding line of `instructions.in`.
+    #[test]
+    fn variants_match_instructions_in_by_name_and_order() {
+        let table = include_str!("../instructions.in");
+        let names: Vec<&str> = table
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| line.split_whitespace().next().unwrap())
+            .collect();
+
+        assert_eq!(names.len(), INSTRUCTION_COUNT);
+
+        for (b, expected) in names.iter().enumerate() {
+            let instr = Instruction::try_from_primitive(b as u8)
+                .unwrap_or_else(|_| panic!("no Instruction variant at discriminant {b}"));
+            assert_eq!(format!("{instr:?}"), *expected, "discriminant {b}");
+        }
+
+        assert!(Instruction::try_from_primitive(names.len() as u8).is_err());
+    }
+}
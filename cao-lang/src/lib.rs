@@ -4,17 +4,31 @@
 //!
 
 #![recursion_limit = "256"]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+// Pulled in unconditionally (not just under `no_std`) so that collection/error types can be
+// spelled as `alloc::...` everywhere, instead of switching between `std::...` and `alloc::...`
+// depending on the `std` feature. Under `std`, `alloc`'s `Vec`/`Box`/`String`/etc. are the exact
+// same types as `std`'s, so this costs nothing and keeps the two build modes in sync.
+extern crate alloc as alloc_crate;
 
 mod alloc;
+#[cfg(feature = "disasm")]
+pub mod asm;
+pub mod byte_encode;
 pub mod collections;
 pub mod compiled_program;
 pub mod compiler;
+pub mod disassembly;
+#[cfg(feature = "disasm")]
+pub mod disasm;
 pub mod instruction;
 pub mod prelude;
 pub mod procedures;
 pub mod stdlib;
 pub mod traits;
 pub mod value;
+pub mod verify;
 pub mod vm;
 
 mod bytecode;
@@ -23,8 +37,14 @@ pub mod version {
     include!(concat!(env!("OUT_DIR"), "/cao_lang_version.rs"));
 }
 
-use std::{mem::size_of, str::FromStr};
+/// Derives [`byte_encode::ByteEncode`] for a struct by encoding/decoding each field in turn, the
+/// way [`byte_encode`] hand-implements it for `i64`/`f64`/`bool`/`String`.
+#[cfg(feature = "derive")]
+pub use cao_lang_derive::ByteEncode;
+
+use core::{mem::size_of, str::FromStr};
 
+use alloc_crate::string::String;
 use bytemuck::{Pod, Zeroable};
 
 use crate::instruction::Instruction;
@@ -62,7 +82,7 @@ impl StrPointer {
         }
         let len = *(ptr as *const u32);
         let ptr = ptr.add(size_of::<u32>());
-        std::str::from_utf8(std::slice::from_raw_parts(ptr, len as usize)).ok()
+        core::str::from_utf8(core::slice::from_raw_parts(ptr, len as usize)).ok()
     }
 }
 
@@ -1,11 +1,19 @@
+pub use crate::byte_encode::ByteEncode;
 pub use crate::compiled_program::*;
 pub use crate::compiler::{
-    compile, CaoProgram, Card, CardIndex, CompilationError, CompilationErrorPayload,
-    CompileOptions, Function,
+    compile, compile_diagnostics, CaoProgram, Card, CardCategory, CardIndex, CardKind, CardPath,
+    CardPathError, CardSchema, CardSlotSchema, CompilationError, CompilationErrorPayload,
+    CompileOptions, Diagnostics, Function, SlotArity,
 };
+pub use crate::disassembly::{disassemble, disassemble_dot};
+#[cfg(feature = "disasm")]
+pub use crate::asm::{assemble, emit, AsmError};
+#[cfg(feature = "disasm")]
+pub use crate::disasm::{disasm, DisasmError};
 pub use crate::procedures::*;
 pub use crate::traits::*;
 pub use crate::value::*;
+pub use crate::verify::{verify, verify_stack_depth, VerifyError};
 pub use crate::{
     collections::handle_table::Handle,
     vm::{runtime::cao_lang_table::CaoLangTable, Vm},
@@ -1,61 +1,167 @@
 //! Helper module for dealing with function extensions.
 //!
-use std::fmt::Display;
-use std::ptr::NonNull;
+use core::fmt;
+use core::ptr::NonNull;
 
+use crate::alloc_crate::{boxed::Box, rc::Rc, string::String, vec::Vec};
 use crate::collections::handle_table::Handle;
 use crate::prelude::Trace;
 use crate::traits::VmFunction;
+use crate::value::Value;
 use crate::vm::runtime::cao_lang_object::CaoLangObject;
-use thiserror::Error;
 
 pub type ExecutionResult<T = ()> = Result<T, ExecutionError>;
 
-#[derive(Debug, Clone, Error)]
+// `Display` is implemented by hand via `core::fmt`, instead of via `#[derive(thiserror::Error)]`,
+// so these types stay usable under `no_std`; `std::error::Error` (which does need `std`) is only
+// implemented when the `std` feature is on.
+#[derive(Debug, Clone)]
 pub enum ExecutionErrorPayload {
-    #[error("The program has overflown its call stack")]
-    CallStackOverflow,
-    #[error("Input ended unexpectedly")]
+    /// The call stack (function calls, not the value stack) ran past its configured depth (see
+    /// [`crate::vm::Vm::with_call_stack_limit`]). `capacity` is that configured depth, `attempted`
+    /// the depth the call that triggered this would have needed.
+    CallStackOverflow { capacity: usize, attempted: usize },
     UnexpectedEndOfInput,
-    #[error("Program exited with status code: {0}")]
     ExitCode(i32),
-    #[error("Got an invalid instruction code {0}")]
     InvalidInstruction(u8),
-    #[error("Got an invalid argument: {}",
-        .context.as_ref().map(|x|x.as_str()).unwrap_or_else(|| ""))]
-    InvalidArgument { context: Option<String> },
-    #[error("Variable {0} was not found!")]
+    InvalidArgument {
+        context: Option<String>,
+    },
     VarNotFound(String),
-    #[error("Procedure by the hash {0:?} could not be found")]
     ProcedureNotFound(Handle),
-    #[error("Unimplemented")]
     Unimplemented,
-    #[error("The program ran out of memory")]
     OutOfMemory,
-    #[error("Missing argument to function call")]
     MissingArgument,
-    #[error("Program timed out")]
-    Timeout,
-    #[error("Subtask [{name}] failed {error}")]
+    /// The `max_instr` budget ran out. `charged` is the weight (see
+    /// [`crate::vm::default_instruction_cost`]/[`crate::vm::Vm::with_instruction_cost`]) the
+    /// instruction that tripped it would have cost - since instructions are weighted rather than
+    /// counted flatly, this can be more than `1` even though the budget itself was an exact
+    /// instruction count.
+    Timeout {
+        charged: u64,
+    },
+    /// A host thread requested cooperative cancellation via [`crate::vm::Vm::interrupt_handle`].
+    /// Unlike `Timeout`/`OutOfFuel`, which the script's own instruction count runs into, this can
+    /// land at any point the host chooses, so (like `CallStackOverflow`/`OutOfMemory`) it isn't
+    /// retried by a `Card::Try` handler - see [`ExecutionErrorPayload::is_catchable`].
+    Interrupted,
+    /// A [`crate::vm::Vm::add_breakpoint`] hit, or the installed [`crate::vm::Debugger`] returned
+    /// [`crate::vm::StepAction::Pause`] from [`crate::vm::Debugger::on_step`]. Like `Suspended`,
+    /// only meaningful as an internal control-flow signal inside
+    /// [`crate::vm::Vm::run_resumable`]/[`crate::vm::Vm::resume`] - match on
+    /// [`crate::vm::RunOutcome::Yielded`] instead of this variant directly.
+    Paused,
+    /// The installed [`crate::vm::Debugger`] returned [`crate::vm::StepAction::Abort`] from
+    /// [`crate::vm::Debugger::on_step`]. Unlike `Paused`, this isn't resumable - it unwinds the
+    /// run the same way an uncaught fault would.
+    DebuggerAbort,
+    /// The [`crate::vm::Vm::set_fuel`]/[`crate::vm::Vm::add_fuel`] instruction budget ran out.
+    /// Unlike `Timeout`, which is a fixed safety net, `fuel` is meant to be exhausted routinely as
+    /// part of cooperative time-slicing - a caller top up the budget and resume the paused `Vm`
+    /// (see [`crate::vm::Vm::run_until_fuel_exhausted`]/[`crate::vm::Vm::resume`]) instead of
+    /// treating this as a fault.
+    OutOfFuel,
     TaskFailure {
         name: String,
         error: Box<ExecutionErrorPayload>,
     },
-    #[error("The program has overflowns its stack")]
-    Stackoverflow,
-    #[error("Failed to return from a lane {reason}")]
-    BadReturn { reason: String },
-    #[error("Trying to hash an unhashable object")]
+    /// The value stack ran past its configured depth (see [`crate::vm::Vm::with_stack_limit`]),
+    /// e.g. from a script recursing too deeply. `capacity` is that configured depth, `attempted`
+    /// the depth the push/reservation that triggered this would have needed.
+    Stackoverflow { capacity: usize, attempted: usize },
+    /// [`crate::vm::Vm::stack_pop_checked`] was called on an empty value stack - e.g. a native
+    /// function (see [`crate::traits::VmFunction`]) registered with more parameters than the
+    /// caller actually pushed for it.
+    StackUnderflow,
+    BadReturn {
+        reason: String,
+    },
     Unhashable,
-    #[error("Assertion failed: {0}")]
+    /// `Div`/`Mod` with an `Integer` dividend and a zero `Integer` divisor. `Real` division by
+    /// zero is well-defined (produces `inf`/`nan`) and doesn't hit this.
+    DivideByZero,
     AssertionError(String),
-    #[error("Closure requested a non-existent upvalue")]
     InvalidUpvalue,
-    #[error("Expected to be in the context of a closure")]
     NotClosure,
+    /// The program was compiled against a different stdlib native/function surface than this
+    /// `Vm` currently provides (see [`crate::stdlib::stdlib_fingerprint`]).
+    StdlibFingerprintMismatch {
+        program: u32,
+        runtime: u32,
+    },
+    /// A native function asked to pause execution via [`crate::vm::Vm::suspend`]. Only meaningful
+    /// as an internal control-flow signal inside [`crate::vm::Vm::run_resumable`]/
+    /// [`crate::vm::Vm::resume`] - match on [`crate::vm::RunOutcome::Yielded`] instead of this
+    /// variant directly.
+    Suspended,
+    /// A `Card::Throw` raised `value`, or some other fault occurred, while no `Card::Try` handler
+    /// was installed to catch it - the unwinding counterpart of every other payload, raised by
+    /// [`crate::vm::Vm`] once it's confirmed no handler frame is left to unwind to. See
+    /// [`ExecutionErrorPayload::is_catchable`] for which payloads get a chance to be caught first.
+    Unhandled(Value),
+    /// A native function (or other host code) reporting a failure that doesn't fit one of the
+    /// built-in payloads above, e.g. a validation error from an embedder's own domain. `payload`
+    /// rides along as an arbitrary `Value` (commonly a table) a `Card::Try` handler can inspect
+    /// alongside `message` - see [`crate::vm::Vm::make_error`].
+    Custom { message: String, payload: Value },
+}
+
+impl fmt::Display for ExecutionErrorPayload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CallStackOverflow { capacity, attempted } => write!(
+                f,
+                "The program has overflown its call stack (capacity: {capacity}, attempted: {attempted})"
+            ),
+            Self::UnexpectedEndOfInput => write!(f, "Input ended unexpectedly"),
+            Self::ExitCode(code) => write!(f, "Program exited with status code: {code}"),
+            Self::InvalidInstruction(instr) => write!(f, "Got an invalid instruction code {instr}"),
+            Self::InvalidArgument { context } => write!(
+                f,
+                "Got an invalid argument: {}",
+                context.as_deref().unwrap_or("")
+            ),
+            Self::VarNotFound(name) => write!(f, "Variable {name} was not found!"),
+            Self::ProcedureNotFound(handle) => {
+                write!(f, "Procedure by the hash {handle:?} could not be found")
+            }
+            Self::Unimplemented => write!(f, "Unimplemented"),
+            Self::OutOfMemory => write!(f, "The program ran out of memory"),
+            Self::MissingArgument => write!(f, "Missing argument to function call"),
+            Self::Timeout { charged } => {
+                write!(f, "Program timed out (charged {charged} for the tripping instruction)")
+            }
+            Self::Interrupted => write!(f, "Program was interrupted"),
+            Self::Paused => write!(f, "Program was paused by a breakpoint or debugger"),
+            Self::DebuggerAbort => write!(f, "Program was aborted by a debugger"),
+            Self::OutOfFuel => write!(f, "Program ran out of fuel"),
+            Self::TaskFailure { name, error } => write!(f, "Subtask [{name}] failed {error}"),
+            Self::Stackoverflow { capacity, attempted } => write!(
+                f,
+                "The program has overflown its stack (capacity: {capacity}, attempted: {attempted})"
+            ),
+            Self::StackUnderflow => write!(f, "Attempted to pop a value off an empty stack"),
+            Self::BadReturn { reason } => write!(f, "Failed to return from a lane {reason}"),
+            Self::Unhashable => write!(f, "Trying to hash an unhashable object"),
+            Self::DivideByZero => write!(f, "Attempted to divide by zero"),
+            Self::AssertionError(msg) => write!(f, "Assertion failed: {msg}"),
+            Self::InvalidUpvalue => write!(f, "Closure requested a non-existent upvalue"),
+            Self::NotClosure => write!(f, "Expected to be in the context of a closure"),
+            Self::StdlibFingerprintMismatch { program, runtime } => write!(
+                f,
+                "Program was compiled against stdlib fingerprint {program:#010x}, but this runtime provides {runtime:#010x}; recompile the program or update the runtime"
+            ),
+            Self::Suspended => write!(f, "Execution was suspended"),
+            Self::Unhandled(value) => write!(f, "Unhandled exception: {value:?}"),
+            Self::Custom { message, .. } => write!(f, "{message}"),
+        }
+    }
 }
 
-#[derive(Debug, Clone, Error)]
+#[cfg(feature = "std")]
+impl std::error::Error for ExecutionErrorPayload {}
+
+#[derive(Debug, Clone)]
 pub struct ExecutionError {
     pub payload: ExecutionErrorPayload,
     pub trace: Vec<Trace>,
@@ -67,12 +173,15 @@ impl ExecutionError {
     }
 }
 
-impl Display for ExecutionError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl fmt::Display for ExecutionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "ExecutionError: {}", self.payload)
     }
 }
 
+#[cfg(feature = "std")]
+impl std::error::Error for ExecutionError {}
+
 impl ExecutionErrorPayload {
     pub fn invalid_argument<S>(reason: S) -> Self
     where
@@ -82,10 +191,113 @@ impl ExecutionErrorPayload {
             context: Some(reason.into()),
         }
     }
+
+    /// Builds a [`Self::Custom`] payload - a native function's escape hatch for reporting a
+    /// failure that isn't one of the built-in variants above, with `payload` (`Value::Nil` if the
+    /// caller has nothing to attach) carried alongside `message` for a `Card::Try` handler to
+    /// inspect. See [`crate::vm::Vm::make_error`] to build the same shape as a `Value` directly,
+    /// without going through a `Result::Err`/unwind round-trip.
+    pub fn custom<S>(message: S, payload: Value) -> Self
+    where
+        S: Into<String>,
+    {
+        Self::Custom {
+            message: message.into(),
+            payload,
+        }
+    }
+
+    /// Whether a `Card::Try` handler should get a chance to catch this payload, rather than it
+    /// aborting the program outright. `Suspended` is an internal control-flow signal (not a
+    /// fault), `CallStackOverflow`/`Stackoverflow`/`OutOfMemory` mean there may not be enough
+    /// headroom left to safely run a handler at all, `UnexpectedEndOfInput` means the bytecode
+    /// itself is truncated or malformed rather than the running script having hit a recoverable
+    /// condition, and `Unhandled` is itself the result of a failed catch attempt, so none of
+    /// those are retried.
+    pub fn is_catchable(&self) -> bool {
+        !matches!(
+            self,
+            Self::Suspended
+                | Self::CallStackOverflow { .. }
+                | Self::Stackoverflow { .. }
+                | Self::OutOfMemory
+                | Self::Unhandled(_)
+                | Self::Interrupted
+                | Self::Paused
+                | Self::DebuggerAbort
+                | Self::UnexpectedEndOfInput
+        )
+    }
+
+    /// A short, stable name for this payload's variant, e.g. for the `kind` field of the error
+    /// table a `Card::Try` handler sees (see [`crate::vm::Vm::unwind_to_handler`]). Distinct from
+    /// the `Display` impl above, which is a full human-readable sentence.
+    pub fn kind_name(&self) -> &'static str {
+        match self {
+            Self::CallStackOverflow { .. } => "CallStackOverflow",
+            Self::UnexpectedEndOfInput => "UnexpectedEndOfInput",
+            Self::ExitCode(_) => "ExitCode",
+            Self::InvalidInstruction(_) => "InvalidInstruction",
+            Self::InvalidArgument { .. } => "InvalidArgument",
+            Self::VarNotFound(_) => "VarNotFound",
+            Self::ProcedureNotFound(_) => "ProcedureNotFound",
+            Self::Unimplemented => "Unimplemented",
+            Self::OutOfMemory => "OutOfMemory",
+            Self::MissingArgument => "MissingArgument",
+            Self::Timeout { .. } => "Timeout",
+            Self::Interrupted => "Interrupted",
+            Self::Paused => "Paused",
+            Self::DebuggerAbort => "DebuggerAbort",
+            Self::OutOfFuel => "OutOfFuel",
+            Self::TaskFailure { .. } => "TaskFailure",
+            Self::Stackoverflow { .. } => "Stackoverflow",
+            Self::StackUnderflow => "StackUnderflow",
+            Self::BadReturn { .. } => "BadReturn",
+            Self::Unhashable => "Unhashable",
+            Self::DivideByZero => "DivideByZero",
+            Self::AssertionError(_) => "AssertionError",
+            Self::InvalidUpvalue => "InvalidUpvalue",
+            Self::NotClosure => "NotClosure",
+            Self::StdlibFingerprintMismatch { .. } => "StdlibFingerprintMismatch",
+            Self::Suspended => "Suspended",
+            Self::Unhandled(_) => "Unhandled",
+            Self::Custom { .. } => "Custom",
+        }
+    }
+}
+
+impl From<crate::collections::value_stack::StackError> for ExecutionErrorPayload {
+    /// A [`crate::collections::value_stack::ValueStack`] only ever fails a push/reservation with
+    /// `StackError::Full` - `OutOfBounds` is [`crate::collections::value_stack::ValueStack::set`]'s
+    /// error for writing past a slot that isn't reserved yet, a distinct failure callers map to
+    /// their own payload instead of routing through here.
+    fn from(err: crate::collections::value_stack::StackError) -> Self {
+        match err {
+            crate::collections::value_stack::StackError::Full { capacity, attempted } => {
+                Self::Stackoverflow { capacity, attempted }
+            }
+            crate::collections::value_stack::StackError::OutOfBounds { capacity, index } => {
+                Self::Stackoverflow {
+                    capacity,
+                    attempted: index + 1,
+                }
+            }
+        }
+    }
+}
+
+impl From<crate::collections::bounded_stack::StackError> for ExecutionErrorPayload {
+    fn from(err: crate::collections::bounded_stack::StackError) -> Self {
+        match err {
+            crate::collections::bounded_stack::StackError::Full { capacity, attempted } => {
+                Self::CallStackOverflow { capacity, attempted }
+            }
+        }
+    }
 }
 
 pub(crate) struct Procedure<Aux> {
-    pub fun: std::rc::Rc<dyn VmFunction<Aux>>,
+    pub fun: Rc<dyn VmFunction<Aux>>,
     pub name: NonNull<CaoLangObject>,
 }
 
@@ -104,8 +316,8 @@ impl<Aux> Procedure<Aux> {
     }
 }
 
-impl<Aux> std::fmt::Debug for Procedure<Aux> {
-    fn fmt(&self, writer: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl<Aux> fmt::Debug for Procedure<Aux> {
+    fn fmt(&self, writer: &mut fmt::Formatter) -> fmt::Result {
         unsafe {
             writeln!(
                 writer,
@@ -1,6 +1,6 @@
 use crate::{traits::AutoByteEncodeProperties, Pointer};
-use std::convert::{From, TryFrom};
-use std::ops::{Add, Div, Mul, Sub};
+use core::convert::{From, TryFrom};
+use core::ops::{Add, Div, Mul, Sub};
 
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
 pub enum Scalar {
@@ -3,50 +3,44 @@
 //! The standard library is injected into every `Module` at compilation time.
 //! Standard functions can be imported via the `std` module
 
+pub mod iter;
+pub mod math;
+pub mod string;
+pub mod sys;
+pub mod table;
+
 #[cfg(test)]
 mod tests;
 
+use core::ptr::NonNull;
+
+use crate::alloc_crate::vec::Vec;
+
 use crate::{
     compiler::{Card, ForEach, Function, Module},
     procedures::ExecutionErrorPayload,
     value::Value,
-    vm::{runtime::cao_lang_object::CaoLangObjectBody, Vm},
+    vm::{
+        runtime::{
+            cao_lang_iterator::CaoLangIterator,
+            cao_lang_object::{CaoLangObject, CaoLangObjectBody},
+        },
+        Vm,
+    },
 };
 
-/// Given a table and a callback that returns a bool create a new table whith the items that return
-/// true
+/// Given a table (or another lazy iterator) and a callback that returns a bool, return a lazy
+/// iterator yielding only the rows for which the callback returns true. Nothing is evaluated
+/// until the result is consumed, e.g. by [`to_array`] or one of the `sorted*`/`min*`/`max*`
+/// functions.
 pub fn filter() -> Function {
     Function::default()
         .with_arg("iterable")
         .with_arg("callback")
-        .with_cards(vec![
-            Card::set_var("res", Card::CreateTable),
-            Card::ForEach(Box::new(ForEach {
-                i: Some("i".to_string()),
-                k: Some("k".to_string()),
-                v: Some("v".to_string()),
-                iterable: Box::new(Card::read_var("iterable")),
-                body: Box::new(Card::composite_card(
-                    "_",
-                    vec![Card::IfTrue(Box::new([
-                        Card::dynamic_call(
-                            Card::read_var("callback"),
-                            vec![
-                                Card::read_var("i"),
-                                Card::read_var("v"),
-                                Card::read_var("k"),
-                            ],
-                        ),
-                        Card::set_property(
-                            Card::read_var("v"),
-                            Card::read_var("res"),
-                            Card::read_var("k"),
-                        ),
-                    ]))],
-                )),
-            })),
-            Card::return_card(Card::read_var("res")),
-        ])
+        .with_card(Card::return_card(Card::call_native(
+            "__lazy_filter",
+            vec![Card::read_var("iterable"), Card::read_var("callback")],
+        )))
 }
 
 /// Returns the key of the first row that returns True from the callback
@@ -80,14 +74,30 @@ pub fn any() -> Function {
         ])
 }
 
-/// Iterate on a table calling the provided callback for each row.
-/// Build a new table from the callback return values, using the same keys
+/// Given a table (or another lazy iterator) and a callback, return a lazy iterator yielding
+/// `callback(k, v)` in place of each row's value, keeping the original keys. Nothing is
+/// evaluated until the result is consumed, e.g. by [`to_array`]/[`collect`] or one of the
+/// `sorted*`/`min*`/`max*` functions, so chains like `map(filter(t, ...), ...)` never allocate an
+/// intermediate table.
 pub fn map() -> Function {
     Function::default()
         .with_arg("iterable")
         .with_arg("callback")
+        .with_card(Card::return_card(Card::call_native(
+            "__lazy_map",
+            vec![Card::read_var("iterable"), Card::read_var("callback")],
+        )))
+}
+
+/// Accumulate a table into a single value by repeatedly calling `callback(acc, v, k, i)`,
+/// starting from `init`.
+pub fn reduce() -> Function {
+    Function::default()
+        .with_arg("iterable")
+        .with_arg("callback")
+        .with_arg("init")
         .with_cards(vec![
-            Card::set_var("res", Card::CreateTable),
+            Card::set_var("acc", Card::read_var("init")),
             Card::ForEach(Box::new(ForEach {
                 i: Some("i".to_string()),
                 k: Some("k".to_string()),
@@ -95,27 +105,43 @@ pub fn map() -> Function {
                 iterable: Box::new(Card::read_var("iterable")),
                 body: Box::new(Card::composite_card(
                     "_",
-                    vec![Card::set_property(
-                        Card::composite_card(
-                            "",
-                            vec![Card::dynamic_call(
-                                Card::read_var("callback"),
-                                vec![
-                                    Card::read_var("i"),
-                                    Card::read_var("v"),
-                                    Card::read_var("k"),
-                                ],
-                            )],
+                    vec![Card::set_var(
+                        "acc",
+                        Card::dynamic_call(
+                            Card::read_var("callback"),
+                            vec![
+                                Card::read_var("acc"),
+                                Card::read_var("v"),
+                                Card::read_var("k"),
+                                Card::read_var("i"),
+                            ],
                         ),
-                        Card::read_var("res"),
-                        Card::read_var("k"),
                     )],
                 )),
             })),
-            Card::return_card(Card::read_var("res")),
+            Card::return_card(Card::read_var("acc")),
         ])
 }
 
+/// Alias for [`reduce`] under the name more commonly used for this operation elsewhere.
+pub fn fold() -> Function {
+    Function::default()
+        .with_arg("iterable")
+        .with_arg("callback")
+        .with_arg("init")
+        .with_card(Card::return_card(Card::call_function(
+            "reduce",
+            // call_function's args are pushed in the reverse of the callee's with_arg
+            // declaration order (see `reduce_test`), so this matches reduce's own
+            // (iterable, callback, init) signature.
+            vec![
+                Card::read_var("init"),
+                Card::read_var("callback"),
+                Card::read_var("iterable"),
+            ],
+        )))
+}
+
 fn minmax(minimax: &str) -> Function {
     Function::default()
         .with_arg("iterable")
@@ -158,40 +184,40 @@ pub fn native_minmax<T, const LESS: bool>(
     match iterable {
         Value::Nil | Value::Integer(_) | Value::Real(_) => return Ok(iterable),
         Value::Object(o) => unsafe {
-            match &o.as_ref().body {
-                CaoLangObjectBody::Table(t) => {
-                    let Some(first) = t.iter().next() else {
-                        return Ok(Value::Nil);
-                    };
-                    vm.stack_push(*first.1)?;
-                    vm.stack_push(*first.0)?;
-                    let mut max_key = vm.run_function(key_fn)?;
-                    let mut i = 0;
-
-                    for (j, (k, value)) in t.iter().enumerate().skip(1) {
-                        vm.stack_push(*value)?;
-                        vm.stack_push(*k)?;
-                        let key = vm.run_function(key_fn)?;
-                        if if LESS { key < max_key } else { key > max_key } {
-                            i = j;
-                            max_key = key;
-                        }
-                    }
-                    let k = t.nth_key(i);
-                    let v = *t.get(&k).unwrap();
-                    let mut result = vm.init_table()?;
-                    let t = result.0.as_mut().as_table_mut().unwrap();
-                    t.insert(vm.init_string("key")?, k)?;
-                    t.insert(vm.init_string("value")?, v)?;
-
-                    return Ok(Value::Object(result.0));
-                }
+            let pairs: Vec<(Value, Value)> = match &o.as_ref().body {
+                CaoLangObjectBody::Table(t) => t.iter().map(|(k, v)| (*k, *v)).collect(),
+                CaoLangObjectBody::Iterator(_) => drain_pairs(vm, o)?,
                 CaoLangObjectBody::String(_)
+                | CaoLangObjectBody::Bytes(_)
+                | CaoLangObjectBody::BigInt(_)
                 | CaoLangObjectBody::Function(_)
                 | CaoLangObjectBody::Closure(_)
                 | CaoLangObjectBody::Upvalue(_)
                 | CaoLangObjectBody::NativeFunction(_) => return Ok(iterable),
+            };
+            let Some(&(mut best_k, mut best_v)) = pairs.first() else {
+                return Ok(Value::Nil);
+            };
+            vm.stack_push(best_v)?;
+            vm.stack_push(best_k)?;
+            let mut max_key = vm.run_function(key_fn)?;
+
+            for &(k, value) in pairs.iter().skip(1) {
+                vm.stack_push(value)?;
+                vm.stack_push(k)?;
+                let key = vm.run_function(key_fn)?;
+                if if LESS { key < max_key } else { key > max_key } {
+                    best_k = k;
+                    best_v = value;
+                    max_key = key;
+                }
             }
+            let mut result = vm.init_table()?;
+            let t = result.0.as_mut().as_table_mut().unwrap();
+            t.insert(vm.init_string("key")?, best_k)?;
+            t.insert(vm.init_string("value")?, best_v)?;
+
+            Ok(Value::Object(result.0))
         },
     }
 }
@@ -204,61 +230,291 @@ pub fn native_sorted<T>(
     match iterable {
         Value::Nil | Value::Integer(_) | Value::Real(_) => return Ok(iterable),
         Value::Object(o) => unsafe {
-            match &o.as_ref().body {
-                CaoLangObjectBody::Table(t) => {
-                    // TODO:
-                    // sort in place?
-                    let mut result = Vec::with_capacity(t.len());
-                    for (k, v) in t.iter() {
-                        vm.stack_push(*v)?;
-                        vm.stack_push(*k)?;
-                        let key = vm.run_function(key_fn)?;
-                        result.push((key, k, v));
-                    }
-                    result.sort_by(|(a, _, _), (b, _, _)| {
-                        a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal)
-                    });
-
-                    let mut out = vm.init_table()?;
-                    let t = out.as_table_mut().unwrap();
-                    for (_, k, v) in result {
-                        t.insert(*k, *v)?;
-                    }
-                    Ok(Value::Object(out.0))
-                }
+            let pairs: Vec<(Value, Value)> = match &o.as_ref().body {
+                CaoLangObjectBody::Table(t) => t.iter().map(|(k, v)| (*k, *v)).collect(),
+                CaoLangObjectBody::Iterator(_) => drain_pairs(vm, o)?,
                 CaoLangObjectBody::String(_) // TODO: define sort for strings?
+                | CaoLangObjectBody::Bytes(_)
+                | CaoLangObjectBody::BigInt(_)
+                | CaoLangObjectBody::Function(_)
+                | CaoLangObjectBody::Closure(_)
+                | CaoLangObjectBody::Upvalue(_)
+                | CaoLangObjectBody::NativeFunction(_) => return Ok(iterable),
+            };
+
+            let mut keyed = Vec::with_capacity(pairs.len());
+            for (k, v) in pairs {
+                vm.stack_push(v)?;
+                vm.stack_push(k)?;
+                let key = vm.run_function(key_fn)?;
+                keyed.push((key, k, v));
+            }
+            let sorted = merge_sort_by(keyed, |(a, _, _), (b, _, _)| Ok(total_cmp(*a, *b)))?;
+
+            let mut out = vm.init_table()?;
+            let t = out.as_table_mut().unwrap();
+            for (_, k, v) in sorted {
+                t.insert(k, v)?;
+            }
+            Ok(Value::Object(out.0))
+        },
+    }
+}
+
+/// Given a table (or lazy iterator) and a comparator `Function`, return a new table sorted by
+/// repeatedly calling `comparator(a, b)` and ordering by the returned integer (negative: `a`
+/// before `b`, zero: keep input order, positive: `b` before `a`). Backed by `__sort_cmp`.
+pub fn sorted_by() -> Function {
+    Function::default()
+        .with_arg("iterable")
+        .with_arg("comparator")
+        .with_card(Card::return_card(Card::call_native(
+            "__sort_cmp",
+            vec![Card::read_var("iterable"), Card::read_var("comparator")],
+        )))
+}
+
+pub fn native_sort_cmp<T>(
+    vm: &mut Vm<T>,
+    iterable: Value,
+    comparator: Value,
+) -> Result<Value, ExecutionErrorPayload> {
+    match iterable {
+        Value::Nil | Value::Integer(_) | Value::Real(_) => return Ok(iterable),
+        Value::Object(o) => unsafe {
+            let pairs: Vec<(Value, Value)> = match &o.as_ref().body {
+                CaoLangObjectBody::Table(t) => t.iter().map(|(k, v)| (*k, *v)).collect(),
+                CaoLangObjectBody::Iterator(_) => drain_pairs(vm, o)?,
+                CaoLangObjectBody::String(_)
+                | CaoLangObjectBody::Bytes(_)
+                | CaoLangObjectBody::BigInt(_)
                 | CaoLangObjectBody::Function(_)
                 | CaoLangObjectBody::Closure(_)
                 | CaoLangObjectBody::Upvalue(_)
                 | CaoLangObjectBody::NativeFunction(_) => return Ok(iterable),
+            };
+
+            // mirrors `native_minmax`'s `key_fn` push order: pushing `b` then `a` means
+            // `comparator`'s declared params `(a, b)` line up with this call's logical order
+            let sorted = merge_sort_by(pairs, |(_, a), (_, b)| {
+                vm.stack_push(*b)?;
+                vm.stack_push(*a)?;
+                let ord = vm.run_function(comparator)?;
+                Ok(ord.as_int().unwrap_or(0).cmp(&0))
+            })?;
+
+            let mut out = vm.init_table()?;
+            let table = out.as_table_mut().unwrap();
+            for (k, v) in sorted {
+                table.insert(k, v)?;
             }
+            Ok(Value::Object(out.0))
         },
     }
 }
 
+/// Fallback total ordering used by [`native_sorted`] when two keys aren't directly comparable
+/// (cross-type, or either is NaN), so sorting stays deterministic regardless of input:
+/// `Nil < Integer < Real < Object`, with NaN sorting after every other `Real`.
+fn total_cmp(a: Value, b: Value) -> core::cmp::Ordering {
+    use core::cmp::Ordering;
+
+    if let Some(ord) = a.partial_cmp(&b) {
+        return ord;
+    }
+    if let (Value::Real(x), Value::Real(y)) = (a, b) {
+        return match (x.is_nan(), y.is_nan()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => x.partial_cmp(&y).unwrap_or(Ordering::Equal),
+        };
+    }
+    fn rank(v: &Value) -> u8 {
+        match v {
+            Value::Nil => 0,
+            Value::Integer(_) => 1,
+            Value::Real(_) => 2,
+            Value::Object(_) => 3,
+        }
+    }
+    rank(&a).cmp(&rank(&b))
+}
+
+/// Bottom-up stable merge sort: `cmp(a, b)` returning anything other than [`Ordering::Greater`]
+/// keeps `a` before `b`, so equal elements (and fallible comparators that return `Equal`) retain
+/// their input order. `cmp` may fail (e.g. it runs a Cao-Lang comparator callback), in which case
+/// the whole sort aborts with that error.
+fn merge_sort_by<I, F>(items: Vec<I>, mut cmp: F) -> Result<Vec<I>, ExecutionErrorPayload>
+where
+    I: Clone,
+    F: FnMut(&I, &I) -> Result<core::cmp::Ordering, ExecutionErrorPayload>,
+{
+    let len = items.len();
+    let mut src = items;
+    let mut dst = src.clone();
+    let mut width = 1;
+    while width < len {
+        let mut i = 0;
+        while i < len {
+            let mid = (i + width).min(len);
+            let end = (i + 2 * width).min(len);
+            merge_runs(&src[i..mid], &src[mid..end], &mut dst[i..end], &mut cmp)?;
+            i += 2 * width;
+        }
+        core::mem::swap(&mut src, &mut dst);
+        width *= 2;
+    }
+    Ok(src)
+}
+
+fn merge_runs<I, F>(
+    left: &[I],
+    right: &[I],
+    out: &mut [I],
+    cmp: &mut F,
+) -> Result<(), ExecutionErrorPayload>
+where
+    I: Clone,
+    F: FnMut(&I, &I) -> Result<core::cmp::Ordering, ExecutionErrorPayload>,
+{
+    let (mut i, mut j, mut k) = (0, 0, 0);
+    while i < left.len() && j < right.len() {
+        if cmp(&left[i], &right[j])? != core::cmp::Ordering::Greater {
+            out[k] = left[i].clone();
+            i += 1;
+        } else {
+            out[k] = right[j].clone();
+            j += 1;
+        }
+        k += 1;
+    }
+    out[k..].clone_from_slice(&left[i..]);
+    k += left.len() - i;
+    out[k..].clone_from_slice(&right[j..]);
+    Ok(())
+}
+
 pub fn native_to_array<T>(vm: &mut Vm<T>, iterable: Value) -> Result<Value, ExecutionErrorPayload> {
     match iterable {
         Value::Nil | Value::Integer(_) | Value::Real(_) => return Ok(iterable),
         Value::Object(o) => unsafe {
-            match &o.as_ref().body {
-                CaoLangObjectBody::Table(t) => {
-                    let mut out = vm.init_table()?;
-                    let table = out.as_table_mut().unwrap();
-                    for (i, (_, val)) in t.iter().enumerate() {
-                        table.insert(i as i64, *val)?;
-                    }
-                    Ok(Value::Object(out.0))
+            let values: Vec<Value> = match &o.as_ref().body {
+                CaoLangObjectBody::Table(t) => t.iter().map(|(_, v)| *v).collect(),
+                CaoLangObjectBody::Iterator(_) => {
+                    drain_pairs(vm, o)?.into_iter().map(|(_, v)| v).collect()
                 }
                 CaoLangObjectBody::String(_)
+                | CaoLangObjectBody::Bytes(_)
+                | CaoLangObjectBody::BigInt(_)
                 | CaoLangObjectBody::Function(_)
                 | CaoLangObjectBody::Closure(_)
                 | CaoLangObjectBody::Upvalue(_)
                 | CaoLangObjectBody::NativeFunction(_) => return Ok(iterable),
+            };
+            let mut out = vm.init_table()?;
+            let table = out.as_table_mut().unwrap();
+            for (i, val) in values.into_iter().enumerate() {
+                table.insert(i as i64, val)?;
             }
+            Ok(Value::Object(out.0))
         },
     }
 }
 
+/// Fully materialize a lazy [`Iterator`](CaoLangObjectBody::Iterator) object into its `(key,
+/// value)` pairs, in pull order. Used by the terminal operations above (`to_array`, `sorted*`,
+/// `min*`/`max*`, [`collect`]) that need every row at once, after any `map`/`filter` adapters in
+/// front of them have already run lazily.
+fn drain_pairs<T>(
+    vm: &mut Vm<T>,
+    ptr: NonNull<CaoLangObject>,
+) -> Result<Vec<(Value, Value)>, ExecutionErrorPayload> {
+    let mut out = Vec::new();
+    while let Some((_, k, v)) = vm.iterator_pull(ptr)? {
+        out.push((k, v));
+    }
+    Ok(out)
+}
+
+/// Given a table or a lazy iterator (e.g. the result of [`map`]/[`filter`]), build a new table
+/// with the same keys, materializing every row immediately.
+pub fn native_collect<T>(vm: &mut Vm<T>, iterable: Value) -> Result<Value, ExecutionErrorPayload> {
+    match iterable {
+        Value::Nil | Value::Integer(_) | Value::Real(_) => return Ok(iterable),
+        Value::Object(o) => unsafe {
+            let pairs: Vec<(Value, Value)> = match &o.as_ref().body {
+                CaoLangObjectBody::Table(t) => t.iter().map(|(k, v)| (*k, *v)).collect(),
+                CaoLangObjectBody::Iterator(_) => drain_pairs(vm, o)?,
+                CaoLangObjectBody::String(_)
+                | CaoLangObjectBody::Bytes(_)
+                | CaoLangObjectBody::BigInt(_)
+                | CaoLangObjectBody::Function(_)
+                | CaoLangObjectBody::Closure(_)
+                | CaoLangObjectBody::Upvalue(_)
+                | CaoLangObjectBody::NativeFunction(_) => return Ok(iterable),
+            };
+            let mut out = vm.init_table()?;
+            let table = out.as_table_mut().unwrap();
+            for (k, v) in pairs {
+                table.insert(k, v)?;
+            }
+            Ok(Value::Object(out.0))
+        },
+    }
+}
+
+/// Wrap `iterable` (a `Table` or another lazy iterator) in a [`CaoLangIterator::Map`], applying
+/// `callback(k, v)` in place of each row's value. Backs [`map`].
+pub fn native_lazy_map<T>(
+    vm: &mut Vm<T>,
+    iterable: Value,
+    callback: Value,
+) -> Result<Value, ExecutionErrorPayload> {
+    let source = vm.to_iterator(iterable)?;
+    let it = vm.init_iterator(CaoLangIterator::Map { source, callback })?;
+    Ok(Value::Object(it.0))
+}
+
+/// Wrap `iterable` (a `Table` or another lazy iterator) in a [`CaoLangIterator::Filter`],
+/// keeping only the rows for which `callback(k, v)` is truthy. Backs [`filter`].
+pub fn native_lazy_filter<T>(
+    vm: &mut Vm<T>,
+    iterable: Value,
+    callback: Value,
+) -> Result<Value, ExecutionErrorPayload> {
+    let source = vm.to_iterator(iterable)?;
+    let it = vm.init_iterator(CaoLangIterator::Filter { source, callback })?;
+    Ok(Value::Object(it.0))
+}
+
+/// Build a [`CaoLangIterator::Range`] yielding `start..end`, exclusive of `end`, without
+/// materializing a table of its own. Backs [`iter::range`](iter::range).
+pub fn native_lazy_range<T>(
+    vm: &mut Vm<T>,
+    start: Value,
+    end: Value,
+) -> Result<Value, ExecutionErrorPayload> {
+    let start = start
+        .as_int()
+        .ok_or_else(|| ExecutionErrorPayload::invalid_argument("range start must be an int"))?;
+    let end = end
+        .as_int()
+        .ok_or_else(|| ExecutionErrorPayload::invalid_argument("range end must be an int"))?;
+    let it = vm.init_iterator(CaoLangIterator::Range { next: start, end })?;
+    Ok(Value::Object(it.0))
+}
+
+/// Wrap `callback` in a [`CaoLangIterator::Native`], calling `callback(index)` for `index`
+/// counting up from 0 until it returns nil. Backs [`iter::generate`](iter::generate).
+pub fn native_lazy_generate<T>(
+    vm: &mut Vm<T>,
+    callback: Value,
+) -> Result<Value, ExecutionErrorPayload> {
+    let it = vm.init_iterator(CaoLangIterator::Native { callback, next: 0 })?;
+    Ok(Value::Object(it.0))
+}
+
 /// Return the smallest value in the table, or nil if the table is empty
 pub fn min_by_key() -> Function {
     Function::default()
@@ -298,6 +554,8 @@ pub fn value_key_fn() -> Function {
         .with_card(Card::return_card(Card::read_var("val")))
 }
 
+/// Materialize an array-like table (or lazy iterator) into a new table keyed by position
+/// `0..len`, discarding the original keys.
 pub fn to_array() -> Function {
     Function::default()
         .with_arg("iterable")
@@ -307,12 +565,26 @@ pub fn to_array() -> Function {
         )))
 }
 
+/// Materialize a table or lazy iterator (e.g. the result of [`map`]/[`filter`]) into a new
+/// table, keeping the original keys.
+pub fn collect() -> Function {
+    Function::default()
+        .with_arg("iterable")
+        .with_card(Card::return_card(Card::call_native(
+            "__collect",
+            vec![Card::read_var("iterable")],
+        )))
+}
+
 pub fn standard_library() -> Module {
     let mut module = Module::default();
     module.functions.push(("to_array".to_string(), to_array()));
+    module.functions.push(("collect".to_string(), collect()));
     module.functions.push(("filter".to_string(), filter()));
     module.functions.push(("any".to_string(), any()));
     module.functions.push(("map".to_string(), map()));
+    module.functions.push(("reduce".to_string(), reduce()));
+    module.functions.push(("fold".to_string(), fold()));
     module.functions.push(("min".to_string(), min()));
     module.functions.push(("max".to_string(), max()));
     module
@@ -325,8 +597,119 @@ pub fn standard_library() -> Module {
         .functions
         .push(("sorted_by_key".to_string(), sorted_by_key()));
     module.functions.push(("sorted".to_string(), sorted()));
+    module
+        .functions
+        .push(("sorted_by".to_string(), sorted_by()));
     module
         .functions
         .push(("row_to_value".to_string(), value_key_fn()));
     module
+        .submodules
+        .push(("iter".to_string(), iter::module()));
+    module
+        .submodules
+        .push(("math".to_string(), math::module()));
+    module
+        .submodules
+        .push(("string".to_string(), string::module()));
+    module
+        .submodules
+        .push(("table".to_string(), table::module()));
+    module.submodules.push(("sys".to_string(), sys::module()));
+    module
+}
+
+/// `(name, arity)` for every native registered by
+/// [`Vm::register_native_stdlib`](crate::vm::Vm::register_native_stdlib). Listed standalone,
+/// rather than introspected off a live `Vm`, so it's available at compile time, before any `Vm`
+/// exists; must be kept in sync with the `_register_native_function` calls there.
+#[cfg(feature = "std")]
+const NATIVE_SIGNATURES: &[(&str, u32)] = &[
+    ("__min", 2),
+    ("__max", 2),
+    ("__sort", 2),
+    ("__sort_cmp", 2),
+    ("__to_array", 1),
+    ("__collect", 1),
+    ("__lazy_map", 2),
+    ("__lazy_filter", 2),
+    ("__lazy_range", 2),
+    ("__lazy_generate", 1),
+    ("__sin", 1),
+    ("__cos", 1),
+    ("__tan", 1),
+    ("__string_upper", 1),
+    ("__string_lower", 1),
+    ("__string_concat", 2),
+    ("__string_split", 2),
+    ("__string_parse_int", 1),
+    ("__string_substr", 3),
+    ("__table_keys", 1),
+    ("__table_values", 1),
+    ("__table_remove", 2),
+    ("__table_has", 2),
+    ("__time", 0),
+];
+
+#[cfg(not(feature = "std"))]
+const NATIVE_SIGNATURES: &[(&str, u32)] = &[
+    ("__min", 2),
+    ("__max", 2),
+    ("__sort", 2),
+    ("__sort_cmp", 2),
+    ("__to_array", 1),
+    ("__collect", 1),
+    ("__lazy_map", 2),
+    ("__lazy_filter", 2),
+    ("__lazy_range", 2),
+    ("__lazy_generate", 1),
+    ("__sin", 1),
+    ("__cos", 1),
+    ("__tan", 1),
+    ("__string_upper", 1),
+    ("__string_lower", 1),
+    ("__string_concat", 2),
+    ("__string_split", 2),
+    ("__string_parse_int", 1),
+    ("__string_substr", 3),
+    ("__table_keys", 1),
+    ("__table_values", 1),
+    ("__table_remove", 2),
+    ("__table_has", 2),
+];
+
+/// A stable hash over the stdlib's entire runtime surface: every native in [`NATIVE_SIGNATURES`]
+/// plus every function [`standard_library`] injects, each as `(name, arity)`. [`compile`] embeds
+/// this in `CaoCompiledProgram::stdlib_fingerprint`, and `Vm::run` recomputes it before executing
+/// a program, so a stdlib ABI change (a native renamed/removed, an argument added) is caught as a
+/// clear error up front instead of dispatching to a missing/renamed native mid-run.
+///
+/// [`compile`]: crate::compiler::compile
+pub fn stdlib_fingerprint() -> u32 {
+    // a vendored FNV-1a hasher, not `std::collections::hash_map::DefaultHasher`, so this keeps
+    // working under `no_std` and stays stable across compiler versions/platforms
+    use crate::collections::pre_hash_map::FnvHasher;
+    use core::hash::Hasher;
+
+    let mut hasher = FnvHasher::new();
+    for (name, arity) in NATIVE_SIGNATURES {
+        hasher.write(name.as_bytes());
+        hasher.write(&arity.to_le_bytes());
+    }
+    hash_module_functions(&mut hasher, &standard_library());
+    hasher.finish() as u32
+}
+
+/// Hashes `(name, arity)` for every function in `module`, recursing into submodules (`std.iter`,
+/// `std.math`, `std.sys`, ...) so nested stdlib functions are covered by [`stdlib_fingerprint`]
+/// too, not just the ones directly on the root `std` module.
+fn hash_module_functions(hasher: &mut impl core::hash::Hasher, module: &crate::compiler::Module) {
+    for (name, function) in module.functions.iter() {
+        hasher.write(name.as_bytes());
+        hasher.write(&(function.arguments.len() as u32).to_le_bytes());
+    }
+    for (name, submodule) in module.submodules.iter() {
+        hasher.write(name.as_bytes());
+        hash_module_functions(hasher, submodule);
+    }
 }
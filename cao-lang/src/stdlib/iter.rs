@@ -0,0 +1,111 @@
+//! `std.iter` - iteration helpers assembled from the `Map`/`Filter`/`Reduce`/`Enumerate` `Card`
+//! primitives (see [`crate::compiler::Card`]), plus `each`, `range` and `generate`, which wrap
+//! the lazy [`crate::vm::runtime::cao_lang_iterator::CaoLangIterator`] constructors so a
+//! `for_each`/`map`/`filter` chain can run over them without ever materializing a table.
+
+use crate::compiler::{Card, Filter, ForEach, Function, Map, Module, Reduce, UnaryExpression};
+
+/// Apply `mapper(value)` to every row of `iterable`, collecting the results into a new table
+/// keyed the same as the input.
+pub fn map() -> Function {
+    Function::default()
+        .with_arg("iterable")
+        .with_arg("mapper")
+        .with_card(Card::return_card(Card::Map(Box::new(Map {
+            iterable: Box::new(Card::read_var("iterable")),
+            mapper: Box::new(Card::read_var("mapper")),
+        }))))
+}
+
+/// Keep the rows of `iterable` for which `predicate(value)` is truthy.
+pub fn filter() -> Function {
+    Function::default()
+        .with_arg("iterable")
+        .with_arg("predicate")
+        .with_card(Card::return_card(Card::Filter(Box::new(Filter {
+            iterable: Box::new(Card::read_var("iterable")),
+            predicate: Box::new(Card::read_var("predicate")),
+        }))))
+}
+
+/// Fold `iterable` with `reducer(acc, value)`, starting from `init`.
+pub fn fold() -> Function {
+    Function::default()
+        .with_arg("iterable")
+        .with_arg("reducer")
+        .with_arg("init")
+        .with_card(Card::return_card(Card::Reduce(Box::new(Reduce {
+            iterable: Box::new(Card::read_var("iterable")),
+            init: Box::new(Card::read_var("init")),
+            reducer: Box::new(Card::read_var("reducer")),
+        }))))
+}
+
+/// Call `callback(value, key, index)` once per row of `iterable`, discarding the results.
+pub fn each() -> Function {
+    Function::default()
+        .with_arg("iterable")
+        .with_arg("callback")
+        .with_cards(vec![
+            Card::ForEach(Box::new(ForEach {
+                i: Some("i".to_string()),
+                k: Some("k".to_string()),
+                v: Some("v".to_string()),
+                iterable: Box::new(Card::read_var("iterable")),
+                body: Box::new(Card::dynamic_call(
+                    Card::read_var("callback"),
+                    vec![
+                        Card::read_var("v"),
+                        Card::read_var("k"),
+                        Card::read_var("i"),
+                    ],
+                )),
+            })),
+            Card::return_card(Card::ScalarNil),
+        ])
+}
+
+/// Yield `[index, value]` rows of `iterable`.
+pub fn enumerate() -> Function {
+    Function::default()
+        .with_arg("iterable")
+        .with_card(Card::return_card(Card::Enumerate(UnaryExpression::new(
+            Card::read_var("iterable"),
+        ))))
+}
+
+/// A lazy iterator over `0..n` - the values an idiomatic `for i in range(n)` loop iterates
+/// over. Nothing is allocated up front; `n` rows are produced one at a time as the loop pulls
+/// them.
+pub fn range() -> Function {
+    Function::default()
+        .with_arg("n")
+        .with_card(Card::return_card(Card::call_native(
+            "__lazy_range",
+            vec![Card::scalar_int(0), Card::read_var("n")],
+        )))
+}
+
+/// A lazy iterator that calls `generator(index)` for `index` counting up from 0, stopping the
+/// first time it returns nil. Lets a `for_each`/`map`/`filter` chain consume an arbitrary
+/// generated or externally-backed sequence the same way it would a table.
+pub fn generate() -> Function {
+    Function::default()
+        .with_arg("generator")
+        .with_card(Card::return_card(Card::call_native(
+            "__lazy_generate",
+            vec![Card::read_var("generator")],
+        )))
+}
+
+pub fn module() -> Module {
+    let mut module = Module::default();
+    module.functions.push(("map".to_string(), map()));
+    module.functions.push(("filter".to_string(), filter()));
+    module.functions.push(("fold".to_string(), fold()));
+    module.functions.push(("each".to_string(), each()));
+    module.functions.push(("enumerate".to_string(), enumerate()));
+    module.functions.push(("range".to_string(), range()));
+    module.functions.push(("generate".to_string(), generate()));
+    module
+}
@@ -0,0 +1,138 @@
+//! `std.math` - numeric helpers. `pow`/`abs`/`floor`/`min`/`max` wrap the matching arithmetic
+//! [`Card`] kinds directly; `sqrt`/`sin`/`cos`/`tan` have no dedicated card, so they wrap `Pow`
+//! (exponent `0.5`) and native trig functions respectively.
+
+use crate::compiler::{Card, Function, Module, UnaryExpression};
+use crate::procedures::ExecutionErrorPayload;
+use crate::value::Value;
+use crate::vm::Vm;
+
+fn unary_native(native: &'static str, arg: Value) -> Result<Value, ExecutionErrorPayload> {
+    let x: f64 = arg.try_into().map_err(|_| {
+        ExecutionErrorPayload::invalid_argument(format!("{native} expects a number"))
+    })?;
+    Ok(Value::Real(match native {
+        "__sin" => x.sin(),
+        "__cos" => x.cos(),
+        "__tan" => x.tan(),
+        _ => unreachable!("unknown math native {native}"),
+    }))
+}
+
+pub fn native_sin<T>(_vm: &mut Vm<T>, x: Value) -> Result<Value, ExecutionErrorPayload> {
+    unary_native("__sin", x)
+}
+
+pub fn native_cos<T>(_vm: &mut Vm<T>, x: Value) -> Result<Value, ExecutionErrorPayload> {
+    unary_native("__cos", x)
+}
+
+pub fn native_tan<T>(_vm: &mut Vm<T>, x: Value) -> Result<Value, ExecutionErrorPayload> {
+    unary_native("__tan", x)
+}
+
+/// `lhs` raised to the power of `rhs`.
+pub fn pow() -> Function {
+    Function::default()
+        .with_arg("lhs")
+        .with_arg("rhs")
+        .with_card(Card::return_card(Card::Pow(Box::new([
+            Card::read_var("lhs"),
+            Card::read_var("rhs"),
+        ]))))
+}
+
+/// The square root of `value`, i.e. `pow(value, 0.5)`.
+pub fn sqrt() -> Function {
+    Function::default()
+        .with_arg("value")
+        .with_card(Card::return_card(Card::Pow(Box::new([
+            Card::read_var("value"),
+            Card::ScalarFloat(0.5),
+        ]))))
+}
+
+/// Round `value` down to the nearest integer.
+pub fn floor() -> Function {
+    Function::default()
+        .with_arg("value")
+        .with_card(Card::return_card(Card::Floor(UnaryExpression::new(
+            Card::read_var("value"),
+        ))))
+}
+
+/// Round `value` up to the nearest integer.
+pub fn ceil() -> Function {
+    Function::default()
+        .with_arg("value")
+        .with_card(Card::return_card(Card::Ceil(UnaryExpression::new(
+            Card::read_var("value"),
+        ))))
+}
+
+/// The absolute value of `value`.
+pub fn abs() -> Function {
+    Function::default()
+        .with_arg("value")
+        .with_card(Card::return_card(Card::Abs(UnaryExpression::new(
+            Card::read_var("value"),
+        ))))
+}
+
+/// The smaller of `lhs` and `rhs`.
+pub fn min() -> Function {
+    Function::default()
+        .with_arg("lhs")
+        .with_arg("rhs")
+        .with_card(Card::return_card(Card::Min(Box::new([
+            Card::read_var("lhs"),
+            Card::read_var("rhs"),
+        ]))))
+}
+
+/// The larger of `lhs` and `rhs`.
+pub fn max() -> Function {
+    Function::default()
+        .with_arg("lhs")
+        .with_arg("rhs")
+        .with_card(Card::return_card(Card::Max(Box::new([
+            Card::read_var("lhs"),
+            Card::read_var("rhs"),
+        ]))))
+}
+
+fn trig(native: &str) -> Function {
+    Function::default()
+        .with_arg("value")
+        .with_card(Card::return_card(Card::call_native(
+            native,
+            vec![Card::read_var("value")],
+        )))
+}
+
+pub fn sin() -> Function {
+    trig("__sin")
+}
+
+pub fn cos() -> Function {
+    trig("__cos")
+}
+
+pub fn tan() -> Function {
+    trig("__tan")
+}
+
+pub fn module() -> Module {
+    let mut module = Module::default();
+    module.functions.push(("pow".to_string(), pow()));
+    module.functions.push(("sqrt".to_string(), sqrt()));
+    module.functions.push(("floor".to_string(), floor()));
+    module.functions.push(("ceil".to_string(), ceil()));
+    module.functions.push(("abs".to_string(), abs()));
+    module.functions.push(("min".to_string(), min()));
+    module.functions.push(("max".to_string(), max()));
+    module.functions.push(("sin".to_string(), sin()));
+    module.functions.push(("cos".to_string(), cos()));
+    module.functions.push(("tan".to_string(), tan()));
+    module
+}
@@ -0,0 +1,183 @@
+//! `std.string` - string helpers. `len` just wraps the generic `Len` [`Card`] (it already works on
+//! strings); `upper`/`lower`/`split`/`concat`/`parse_int`/`substr` have no dedicated card, so
+//! they're plain natives operating on `&str`.
+
+use core::convert::TryFrom;
+
+use crate::compiler::{Card, Function, Module, UnaryExpression};
+use crate::procedures::ExecutionErrorPayload;
+use crate::value::Value;
+use crate::vm::Vm;
+
+fn expect_str(native: &'static str, value: Value) -> Result<&str, ExecutionErrorPayload> {
+    <&str>::try_from(value)
+        .map_err(|_| ExecutionErrorPayload::invalid_argument(format!("{native} expects a string")))
+}
+
+pub fn native_upper<T>(vm: &mut Vm<T>, value: Value) -> Result<Value, ExecutionErrorPayload> {
+    let upper = expect_str("string.upper", value)?.to_uppercase();
+    let ptr = vm.init_string(&upper)?;
+    Ok(Value::Object(ptr.into_inner()))
+}
+
+pub fn native_lower<T>(vm: &mut Vm<T>, value: Value) -> Result<Value, ExecutionErrorPayload> {
+    let lower = expect_str("string.lower", value)?.to_lowercase();
+    let ptr = vm.init_string(&lower)?;
+    Ok(Value::Object(ptr.into_inner()))
+}
+
+pub fn native_concat<T>(
+    vm: &mut Vm<T>,
+    lhs: Value,
+    rhs: Value,
+) -> Result<Value, ExecutionErrorPayload> {
+    let lhs = expect_str("string.concat", lhs)?;
+    let rhs = expect_str("string.concat", rhs)?;
+    let mut out = String::with_capacity(lhs.len() + rhs.len());
+    out.push_str(lhs);
+    out.push_str(rhs);
+    let ptr = vm.init_string(&out)?;
+    Ok(Value::Object(ptr.into_inner()))
+}
+
+/// Splits `value` on every occurrence of `separator`, returning a table of the pieces keyed by
+/// position. An empty `separator` splits into individual characters instead of looping forever.
+pub fn native_split<T>(
+    vm: &mut Vm<T>,
+    value: Value,
+    separator: Value,
+) -> Result<Value, ExecutionErrorPayload> {
+    let value = expect_str("string.split", value)?;
+    let separator = expect_str("string.split", separator)?;
+    let parts: Vec<String> = if separator.is_empty() {
+        value.chars().map(|c| c.to_string()).collect()
+    } else {
+        value.split(separator).map(|s| s.to_string()).collect()
+    };
+
+    let mut out = vm.init_table()?;
+    for (i, part) in parts.into_iter().enumerate() {
+        let part = vm.init_string(&part)?;
+        out.as_table_mut()
+            .unwrap()
+            .insert(i as i64, Value::Object(part.into_inner()))?;
+    }
+    Ok(Value::Object(out.into_inner()))
+}
+
+pub fn native_parse_int<T>(_vm: &mut Vm<T>, value: Value) -> Result<Value, ExecutionErrorPayload> {
+    let value = expect_str("string.parse_int", value)?;
+    value.trim().parse::<i64>().map(Value::Integer).map_err(|_| {
+        ExecutionErrorPayload::invalid_argument(format!("'{value}' is not a valid integer"))
+    })
+}
+
+/// Returns up to `len` characters of `value`, starting at the `start`th character. Indexes by
+/// character rather than byte, so it can't land in the middle of a multi-byte codepoint; a
+/// negative `start`/`len`, or a `start` past the end of `value`, just yields an empty string
+/// instead of erroring.
+pub fn native_substr<T>(
+    vm: &mut Vm<T>,
+    value: Value,
+    start: Value,
+    len: Value,
+) -> Result<Value, ExecutionErrorPayload> {
+    let value = expect_str("string.substr", value)?;
+    let start = i64::try_from(start).map_err(|_| {
+        ExecutionErrorPayload::invalid_argument("string.substr expects an integer start")
+    })?;
+    let len = i64::try_from(len).map_err(|_| {
+        ExecutionErrorPayload::invalid_argument("string.substr expects an integer len")
+    })?;
+    let start = start.max(0) as usize;
+    let len = len.max(0) as usize;
+
+    let out: String = value.chars().skip(start).take(len).collect();
+    let ptr = vm.init_string(&out)?;
+    Ok(Value::Object(ptr.into_inner()))
+}
+
+/// The number of bytes in `value`.
+pub fn len() -> Function {
+    Function::default()
+        .with_arg("value")
+        .with_card(Card::return_card(Card::Len(UnaryExpression::new(
+            Card::read_var("value"),
+        ))))
+}
+
+pub fn upper() -> Function {
+    Function::default()
+        .with_arg("value")
+        .with_card(Card::return_card(Card::call_native(
+            "__string_upper",
+            vec![Card::read_var("value")],
+        )))
+}
+
+pub fn lower() -> Function {
+    Function::default()
+        .with_arg("value")
+        .with_card(Card::return_card(Card::call_native(
+            "__string_lower",
+            vec![Card::read_var("value")],
+        )))
+}
+
+pub fn split() -> Function {
+    Function::default()
+        .with_arg("value")
+        .with_arg("separator")
+        .with_card(Card::return_card(Card::call_native(
+            "__string_split",
+            vec![Card::read_var("value"), Card::read_var("separator")],
+        )))
+}
+
+pub fn concat() -> Function {
+    Function::default()
+        .with_arg("lhs")
+        .with_arg("rhs")
+        .with_card(Card::return_card(Card::call_native(
+            "__string_concat",
+            vec![Card::read_var("lhs"), Card::read_var("rhs")],
+        )))
+}
+
+pub fn parse_int() -> Function {
+    Function::default()
+        .with_arg("value")
+        .with_card(Card::return_card(Card::call_native(
+            "__string_parse_int",
+            vec![Card::read_var("value")],
+        )))
+}
+
+pub fn substr() -> Function {
+    Function::default()
+        .with_arg("value")
+        .with_arg("start")
+        .with_arg("len")
+        .with_card(Card::return_card(Card::call_native(
+            "__string_substr",
+            vec![
+                Card::read_var("value"),
+                Card::read_var("start"),
+                Card::read_var("len"),
+            ],
+        )))
+}
+
+pub fn module() -> Module {
+    let mut module = Module::default();
+    module.functions.push(("len".to_string(), len()));
+    module.functions.push(("upper".to_string(), upper()));
+    module.functions.push(("lower".to_string(), lower()));
+    module.functions.push(("split".to_string(), split()));
+    module.functions.push(("concat".to_string(), concat()));
+    module
+        .functions
+        .push(("parse_int".to_string(), parse_int()));
+    module.functions.push(("substr".to_string(), substr()));
+    module
+}
@@ -0,0 +1,33 @@
+//! `std.sys` - access to the host environment. Only `time`, gated behind the `std` feature since
+//! it needs a wall clock, which isn't available under `no_std`.
+
+use crate::compiler::{Card, Function, Module};
+
+#[cfg(feature = "std")]
+use crate::procedures::ExecutionErrorPayload;
+#[cfg(feature = "std")]
+use crate::value::Value;
+#[cfg(feature = "std")]
+use crate::vm::Vm;
+
+/// Seconds since the Unix epoch, as a `Real`.
+#[cfg(feature = "std")]
+pub fn native_time<T>(_vm: &mut Vm<T>) -> Result<Value, ExecutionErrorPayload> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| ExecutionErrorPayload::invalid_argument(e.to_string()))?;
+    Ok(Value::Real(now.as_secs_f64()))
+}
+
+/// Seconds since the Unix epoch. Backed by `__time`, only registered when the `std` feature is
+/// enabled.
+pub fn time() -> Function {
+    Function::default().with_card(Card::return_card(Card::call_native("__time", vec![])))
+}
+
+pub fn module() -> Module {
+    let mut module = Module::default();
+    #[cfg(feature = "std")]
+    module.functions.push(("time".to_string(), time()));
+    module
+}
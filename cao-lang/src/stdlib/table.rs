@@ -0,0 +1,107 @@
+//! `std.table` - table helpers not already covered by a dedicated `Card` (`Len` already works on
+//! tables, so that's exposed via `std.string.len` instead of being duplicated here).
+
+use core::convert::TryFrom;
+
+use crate::compiler::{Card, Function, Module};
+use crate::procedures::ExecutionErrorPayload;
+use crate::value::Value;
+use crate::vm::runtime::cao_lang_table::CaoLangTable;
+use crate::vm::Vm;
+
+fn expect_table(native: &'static str, value: Value) -> Result<&mut CaoLangTable, ExecutionErrorPayload> {
+    <&mut CaoLangTable>::try_from(value)
+        .map_err(|_| ExecutionErrorPayload::invalid_argument(format!("{native} expects a table")))
+}
+
+/// A new table of `value`'s keys, keyed by position.
+pub fn native_keys<T>(vm: &mut Vm<T>, value: Value) -> Result<Value, ExecutionErrorPayload> {
+    let keys: Vec<Value> = expect_table("table.keys", value)?.keys().to_vec();
+    let mut out = vm.init_table()?;
+    let table = out.as_table_mut().unwrap();
+    for (i, key) in keys.into_iter().enumerate() {
+        table.insert(i as i64, key)?;
+    }
+    Ok(Value::Object(out.into_inner()))
+}
+
+/// A new table of `value`'s values, keyed by position.
+pub fn native_values<T>(vm: &mut Vm<T>, value: Value) -> Result<Value, ExecutionErrorPayload> {
+    let values: Vec<Value> = expect_table("table.values", value)?
+        .iter()
+        .map(|(_, v)| *v)
+        .collect();
+    let mut out = vm.init_table()?;
+    let table = out.as_table_mut().unwrap();
+    for (i, value) in values.into_iter().enumerate() {
+        table.insert(i as i64, value)?;
+    }
+    Ok(Value::Object(out.into_inner()))
+}
+
+/// Removes `key` from `value` in place, returning `Nil`. A no-op if `key` isn't present.
+pub fn native_remove<T>(
+    _vm: &mut Vm<T>,
+    value: Value,
+    key: Value,
+) -> Result<Value, ExecutionErrorPayload> {
+    expect_table("table.remove", value)?.remove(key)?;
+    Ok(Value::Nil)
+}
+
+/// Whether `key` is present in `value`.
+pub fn native_has<T>(
+    _vm: &mut Vm<T>,
+    value: Value,
+    key: Value,
+) -> Result<Value, ExecutionErrorPayload> {
+    let has = expect_table("table.has", value)?.get(key).is_some();
+    Ok(Value::from(has))
+}
+
+pub fn keys() -> Function {
+    Function::default()
+        .with_arg("value")
+        .with_card(Card::return_card(Card::call_native(
+            "__table_keys",
+            vec![Card::read_var("value")],
+        )))
+}
+
+pub fn values() -> Function {
+    Function::default()
+        .with_arg("value")
+        .with_card(Card::return_card(Card::call_native(
+            "__table_values",
+            vec![Card::read_var("value")],
+        )))
+}
+
+pub fn remove() -> Function {
+    Function::default()
+        .with_arg("value")
+        .with_arg("key")
+        .with_card(Card::return_card(Card::call_native(
+            "__table_remove",
+            vec![Card::read_var("value"), Card::read_var("key")],
+        )))
+}
+
+pub fn has() -> Function {
+    Function::default()
+        .with_arg("value")
+        .with_arg("key")
+        .with_card(Card::return_card(Card::call_native(
+            "__table_has",
+            vec![Card::read_var("value"), Card::read_var("key")],
+        )))
+}
+
+pub fn module() -> Module {
+    let mut module = Module::default();
+    module.functions.push(("keys".to_string(), keys()));
+    module.functions.push(("values".to_string(), values()));
+    module.functions.push(("remove".to_string(), remove()));
+    module.functions.push(("has".to_string(), has()));
+    module
+}
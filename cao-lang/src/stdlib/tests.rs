@@ -12,7 +12,7 @@ use super::*;
 #[test]
 fn filter_test() {
     let program = Module {
-        imports: vec!["std.filter".to_string()],
+        imports: vec!["std.filter".to_string(), "std.collect".to_string()],
         lanes: vec![
             (
                 "main".to_string(),
@@ -28,12 +28,16 @@ fn filter_test() {
                         Card::read_var("t"),
                         Card::string_card("pooh"),
                     ),
-                    // call filter
+                    // call filter; `filter` is lazy, so materialize it via `collect` before
+                    // inspecting the result
                     Card::set_global_var(
                         "g_result",
                         Card::call_function(
-                            "filter",
-                            vec![Card::Function("cb".to_string()), Card::read_var("t")],
+                            "collect",
+                            vec![Card::call_function(
+                                "filter",
+                                vec![Card::Function("cb".to_string()), Card::read_var("t")],
+                            )],
                         ),
                     ),
                 ]),
@@ -110,7 +114,7 @@ fn stdlib_can_be_imported_in_submodule_test() {
 #[test]
 fn map_test() {
     let program = Module {
-        imports: vec!["std.map".to_string()],
+        imports: vec!["std.map".to_string(), "std.collect".to_string()],
         lanes: vec![
             (
                 "main".to_string(),
@@ -126,12 +130,16 @@ fn map_test() {
                         Card::read_var("t"),
                         Card::string_card("pooh"),
                     ),
-                    // call filter
+                    // call map; `map` is lazy, so materialize it via `collect` before inspecting
+                    // the result
                     Card::set_global_var(
                         "g_result",
                         Card::call_function(
-                            "map",
-                            vec![Card::Function("cb".to_string()), Card::read_var("t")],
+                            "collect",
+                            vec![Card::call_function(
+                                "map",
+                                vec![Card::Function("cb".to_string()), Card::read_var("t")],
+                            )],
                         ),
                     ),
                 ]),
@@ -420,3 +428,303 @@ fn sort_by_key_test() {
         );
     }
 }
+
+#[traced_test]
+#[test]
+fn reduce_test() {
+    let program = Module {
+        imports: vec!["std.reduce".to_string()],
+        lanes: vec![
+            (
+                "main".to_string(),
+                Function::default().with_cards(vec![
+                    Card::set_var(
+                        "t",
+                        Card::Array(vec![
+                            Card::ScalarInt(1),
+                            Card::ScalarInt(2),
+                            Card::ScalarInt(3),
+                            Card::ScalarInt(4),
+                        ]),
+                    ),
+                    Card::set_global_var(
+                        "g_result",
+                        Card::call_function(
+                            "reduce",
+                            vec![
+                                Card::scalar_int(0),
+                                Card::Function("sum".to_string()),
+                                Card::read_var("t"),
+                            ],
+                        ),
+                    ),
+                ]),
+            ),
+            (
+                "sum".to_string(),
+                // dynamic_call pushes args as [acc, v, k, i]; declare params in the reverse order
+                // so each name binds to its matching value (see `filter`'s single-arg "k" callback
+                // for the same convention).
+                Function::default()
+                    .with_arg("i")
+                    .with_arg("k")
+                    .with_arg("v")
+                    .with_arg("acc")
+                    .with_card(Card::return_card(Card::Add(Box::new([
+                        Card::read_var("acc"),
+                        Card::read_var("v"),
+                    ])))),
+            ),
+        ],
+        ..Default::default()
+    };
+
+    let compiled = compile(program, None).expect("Failed to compile");
+    compiled.print_disassembly();
+    let mut vm = Vm::new(()).unwrap().with_max_iter(1000);
+    vm.run(&compiled).expect("run");
+
+    let result = vm
+        .read_var_by_name("g_result", &compiled.variables)
+        .unwrap();
+    assert_eq!(result, Value::Integer(10));
+}
+
+#[traced_test]
+#[test]
+fn fold_test() {
+    let program = Module {
+        imports: vec!["std.fold".to_string()],
+        lanes: vec![
+            (
+                "main".to_string(),
+                Function::default().with_cards(vec![
+                    Card::set_var(
+                        "t",
+                        Card::Array(vec![
+                            Card::ScalarInt(1),
+                            Card::ScalarInt(2),
+                            Card::ScalarInt(3),
+                            Card::ScalarInt(4),
+                        ]),
+                    ),
+                    Card::set_global_var(
+                        "g_result",
+                        Card::call_function(
+                            "fold",
+                            vec![
+                                Card::scalar_int(0),
+                                Card::Function("sum".to_string()),
+                                Card::read_var("t"),
+                            ],
+                        ),
+                    ),
+                ]),
+            ),
+            (
+                "sum".to_string(),
+                // see `reduce_test`'s "sum" lane for why the params are declared in reverse
+                Function::default()
+                    .with_arg("i")
+                    .with_arg("k")
+                    .with_arg("v")
+                    .with_arg("acc")
+                    .with_card(Card::return_card(Card::Add(Box::new([
+                        Card::read_var("acc"),
+                        Card::read_var("v"),
+                    ])))),
+            ),
+        ],
+        ..Default::default()
+    };
+
+    let compiled = compile(program, None).expect("Failed to compile");
+    compiled.print_disassembly();
+    let mut vm = Vm::new(()).unwrap().with_max_iter(1000);
+    vm.run(&compiled).expect("run");
+
+    let result = vm
+        .read_var_by_name("g_result", &compiled.variables)
+        .unwrap();
+    assert_eq!(result, Value::Integer(10));
+}
+
+#[traced_test]
+#[test]
+fn string_substr_test() {
+    let program = Module {
+        imports: vec!["std.string.substr".to_string()],
+        lanes: vec![(
+            "main".to_string(),
+            Function::default().with_cards(vec![Card::set_global_var(
+                "g_result",
+                Card::call_function(
+                    "substr",
+                    vec![
+                        Card::string_card("winnie"),
+                        Card::scalar_int(3),
+                        Card::scalar_int(3),
+                    ],
+                ),
+            )]),
+        )],
+        ..Default::default()
+    };
+
+    let compiled = compile(program, None).expect("Failed to compile");
+    let mut vm = Vm::new(()).unwrap().with_max_iter(1000);
+    vm.run(&compiled).expect("run");
+
+    let result = vm
+        .read_var_by_name("g_result", &compiled.variables)
+        .unwrap();
+    let result = unsafe { result.as_str().expect("Failed to read string") };
+    assert_eq!(result, "nie");
+}
+
+#[traced_test]
+#[test]
+fn math_max_test() {
+    let program = Module {
+        imports: vec!["std.math.max".to_string()],
+        lanes: vec![(
+            "main".to_string(),
+            Function::default().with_cards(vec![Card::set_global_var(
+                "g_result",
+                Card::call_function("max", vec![Card::scalar_int(1), Card::scalar_int(2)]),
+            )]),
+        )],
+        ..Default::default()
+    };
+
+    let compiled = compile(program, None).expect("Failed to compile");
+    let mut vm = Vm::new(()).unwrap().with_max_iter(1000);
+    vm.run(&compiled).expect("run");
+
+    let result = vm
+        .read_var_by_name("g_result", &compiled.variables)
+        .unwrap();
+    assert_eq!(result, Value::Integer(2));
+}
+
+#[traced_test]
+#[test]
+fn string_upper_test() {
+    let program = Module {
+        imports: vec!["std.string.upper".to_string()],
+        lanes: vec![(
+            "main".to_string(),
+            Function::default().with_cards(vec![Card::set_global_var(
+                "g_result",
+                Card::call_function("upper", vec![Card::string_card("winnie")]),
+            )]),
+        )],
+        ..Default::default()
+    };
+
+    let compiled = compile(program, None).expect("Failed to compile");
+    let mut vm = Vm::new(()).unwrap().with_max_iter(1000);
+    vm.run(&compiled).expect("run");
+
+    let result = vm
+        .read_var_by_name("g_result", &compiled.variables)
+        .unwrap();
+    let result = unsafe { result.as_str().expect("Failed to read string") };
+    assert_eq!(result, "WINNIE");
+}
+
+#[traced_test]
+#[test]
+fn iter_range_test() {
+    // `range` is lazy, so materialize it via `to_array` before inspecting the result
+    let program = Module {
+        imports: vec!["std.iter.range".to_string(), "std.to_array".to_string()],
+        lanes: vec![(
+            "main".to_string(),
+            Function::default().with_cards(vec![Card::set_global_var(
+                "g_result",
+                Card::call_function(
+                    "to_array",
+                    vec![Card::call_function("range", vec![Card::scalar_int(3)])],
+                ),
+            )]),
+        )],
+        ..Default::default()
+    };
+
+    let compiled = compile(program, None).expect("Failed to compile");
+    let mut vm = Vm::new(()).unwrap().with_max_iter(1000);
+    vm.run(&compiled).expect("run");
+
+    let result = vm
+        .read_var_by_name("g_result", &compiled.variables)
+        .unwrap();
+    match result {
+        Value::Object(o) => unsafe {
+            let o = o.as_ref();
+            let o = o.as_table().unwrap();
+            assert_eq!(o.len(), 3);
+            for i in 0..3 {
+                assert_eq!(o.get(&Value::Integer(i)), Some(&Value::Integer(i)));
+            }
+        },
+        a @ _ => panic!("Unexpected result: {a:?}"),
+    }
+}
+
+#[traced_test]
+#[test]
+fn iter_generate_test() {
+    // `generate` calls `gen(i)` for i = 0, 1, 2, ... until it returns nil
+    let program = Module {
+        imports: vec!["std.iter.generate".to_string(), "std.to_array".to_string()],
+        lanes: vec![
+            (
+                "main".to_string(),
+                Function::default().with_cards(vec![Card::set_global_var(
+                    "g_result",
+                    Card::call_function(
+                        "to_array",
+                        vec![Card::call_function(
+                            "generate",
+                            vec![Card::Function("gen".to_string())],
+                        )],
+                    ),
+                )]),
+            ),
+            (
+                "gen".to_string(),
+                Function::default().with_arg("i").with_card(Card::IfElse(
+                    Box::new([
+                        Card::Less(Box::new([Card::read_var("i"), Card::scalar_int(3)])),
+                        Card::return_card(Card::Mul(Box::new([
+                            Card::read_var("i"),
+                            Card::scalar_int(2),
+                        ]))),
+                        Card::return_card(Card::ScalarNil),
+                    ]),
+                )),
+            ),
+        ],
+        ..Default::default()
+    };
+
+    let compiled = compile(program, None).expect("Failed to compile");
+    let mut vm = Vm::new(()).unwrap().with_max_iter(1000);
+    vm.run(&compiled).expect("run");
+
+    let result = vm
+        .read_var_by_name("g_result", &compiled.variables)
+        .unwrap();
+    match result {
+        Value::Object(o) => unsafe {
+            let o = o.as_ref();
+            let o = o.as_table().unwrap();
+            assert_eq!(o.len(), 3);
+            for i in 0..3 {
+                assert_eq!(o.get(&Value::Integer(i)), Some(&Value::Integer(i * 2)));
+            }
+        },
+        a @ _ => panic!("Unexpected result: {a:?}"),
+    }
+}
@@ -1,5 +1,6 @@
+use core::convert::TryFrom;
+
 use crate::{procedures::ExecutionErrorPayload, value::Value, vm::Vm};
-use std::convert::TryFrom;
 
 pub const MAX_STR_LEN: usize = 256;
 
@@ -13,12 +14,21 @@ pub enum StringDecodeError {
     LengthError(usize),
     /// Did not fit into available space
     CapacityError(usize),
-    Utf8DecodeError(std::str::Utf8Error),
+    Utf8DecodeError(core::str::Utf8Error),
 }
 
 /// Objects that can act as Cao-Lang functions
 pub trait VmFunction<Aux> {
     fn call(&self, vm: &mut Vm<Aux>) -> ShallowExecutionResult;
+
+    /// Number of [`Value`]s this function leaves on top of the stack as its result, e.g. `2` for
+    /// a function returning an `(ok, value)` pair. The function is responsible for pushing
+    /// exactly this many values itself before returning `Ok(())`; [`crate::vm::instr_execution::call_native`]
+    /// trims the call frame down to just these values, discarding anything else the call left
+    /// behind (unpopped arguments, scratch pushes, ...).
+    fn num_results(&self) -> u8 {
+        1
+    }
 }
 
 pub type VmFunction1<Aux, T1> = fn(&mut Vm<Aux>, T1) -> ShallowExecutionResult;
@@ -87,7 +97,7 @@ where
     T1: TryFrom<Value>,
 {
     fn call(&self, vm: &mut Vm<Aux>) -> ShallowExecutionResult {
-        let v1 = vm.stack_pop();
+        let v1 = vm.stack_pop_checked()?;
         let v1 = T1::try_from(v1).map_err(|_| conversion_error("1"))?;
         self(vm, v1)
     }
@@ -99,9 +109,9 @@ where
     T2: TryFrom<Value>,
 {
     fn call(&self, vm: &mut Vm<Aux>) -> ShallowExecutionResult {
-        let v2 = vm.stack_pop();
+        let v2 = vm.stack_pop_checked()?;
         let v2 = T2::try_from(v2).map_err(|_| conversion_error("2"))?;
-        let v1 = vm.stack_pop();
+        let v1 = vm.stack_pop_checked()?;
         let v1 = T1::try_from(v1).map_err(|_| conversion_error("1"))?;
         self(vm, v1, v2)
     }
@@ -114,11 +124,11 @@ where
     T3: TryFrom<Value>,
 {
     fn call(&self, vm: &mut Vm<Aux>) -> ShallowExecutionResult {
-        let v3 = vm.stack_pop();
+        let v3 = vm.stack_pop_checked()?;
         let v3 = T3::try_from(v3).map_err(|_| conversion_error("3"))?;
-        let v2 = vm.stack_pop();
+        let v2 = vm.stack_pop_checked()?;
         let v2 = T2::try_from(v2).map_err(|_| conversion_error("2"))?;
-        let v1 = vm.stack_pop();
+        let v1 = vm.stack_pop_checked()?;
         let v1 = T1::try_from(v1).map_err(|_| conversion_error("1"))?;
         self(vm, v1, v2, v3)
     }
@@ -133,13 +143,13 @@ where
     T4: TryFrom<Value>,
 {
     fn call(&self, vm: &mut Vm<Aux>) -> ShallowExecutionResult {
-        let v4 = vm.stack_pop();
+        let v4 = vm.stack_pop_checked()?;
         let v4 = T4::try_from(v4).map_err(|_| conversion_error("4"))?;
-        let v3 = vm.stack_pop();
+        let v3 = vm.stack_pop_checked()?;
         let v3 = T3::try_from(v3).map_err(|_| conversion_error("3"))?;
-        let v2 = vm.stack_pop();
+        let v2 = vm.stack_pop_checked()?;
         let v2 = T2::try_from(v2).map_err(|_| conversion_error("2"))?;
-        let v1 = vm.stack_pop();
+        let v1 = vm.stack_pop_checked()?;
         let v1 = T1::try_from(v1).map_err(|_| conversion_error("1"))?;
         self(vm, v1, v2, v3, v4)
     }
@@ -1,8 +1,15 @@
+mod canonical;
+mod packed;
+
+pub use canonical::CanonicalDecodeError;
+pub use packed::{PackedValue, MAX_PACKED_INT, MIN_PACKED_INT};
+
+use crate::alloc_crate::{string::String, vec::Vec};
 use crate::prelude::CaoLangTable;
 use crate::vm::runtime::cao_lang_object::{CaoLangObject, CaoLangObjectBody};
-use std::convert::{From, TryFrom};
-use std::ops::{Add, Div, Mul, Sub};
-use std::ptr::NonNull;
+use core::convert::{From, TryFrom};
+use core::ops::{Add, BitAnd, BitOr, BitXor, Div, Mul, Neg, Rem, Shl, Shr, Sub};
+use core::ptr::NonNull;
 
 #[derive(Clone, Copy)]
 pub enum Value {
@@ -12,8 +19,8 @@ pub enum Value {
     Real(f64),
 }
 
-impl std::fmt::Debug for Value {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Debug for Value {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::Nil => write!(f, "Nil"),
             Self::Object(arg0) => f
@@ -28,7 +35,13 @@ impl std::fmt::Debug for Value {
 }
 
 impl PartialOrd for Value {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        // `Integer` and `BigInt` must order consistently with each other regardless of which
+        // side is which, so widen both to `i128` up front rather than letting `try_cast_match`
+        // (which only knows about `Integer`/`Real`) handle it.
+        if let (Some(a), Some(b)) = ((*self).as_wide_int(), (*other).as_wide_int()) {
+            return a.partial_cmp(&b);
+        }
         let (this, other) = self.try_cast_match(*other);
         match (this, other) {
             (Value::Object(a), Value::Object(b)) => unsafe { a.as_ref().partial_cmp(b.as_ref()) },
@@ -39,28 +52,53 @@ impl PartialOrd for Value {
     }
 }
 
-impl std::hash::Hash for Value {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+impl core::hash::Hash for Value {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        // `Integer(5)`, a `BigInt` holding `5`, and `Real(5.0)` all compare equal (see
+        // `Value::eq_with_seen`, which routes through `try_cast_match`/wide-int comparison), so
+        // they must hash identically too - canonicalize all three through the same `i128`
+        // representation unconditionally, not just when compared against each other.
+        if let Some(i) = (*self).canonical_hash_key() {
+            i.hash(state);
+            return;
+        }
         match self {
             Value::Nil => 0u8.hash(state),
-            Value::Integer(i) => {
-                i.hash(state);
-            }
             Value::Real(f) => {
                 f.to_bits().hash(state);
             }
             Value::Object(o) => unsafe {
                 o.as_ref().hash(state);
             },
+            Value::Integer(_) => {
+                unreachable!("Value::Integer always widens via canonical_hash_key")
+            }
         }
     }
 }
 
-impl PartialEq for Value {
-    fn eq(&self, other: &Self) -> bool {
-        match (*self, *other) {
+impl Value {
+    /// Deep structural equality, same as the [`PartialEq`] impl below, but threading a stack of
+    /// in-progress `Object` comparisons through to [`CaoLangObject::eq_with_seen`] so a cyclic
+    /// table compares equal instead of recursing forever. See
+    /// [`CaoLangObject::eq_with_seen`] for why.
+    pub(crate) fn eq_with_seen(
+        &self,
+        other: &Self,
+        seen: &mut Vec<(*const CaoLangObject, *const CaoLangObject)>,
+    ) -> bool {
+        if let (Some(a), Some(b)) = ((*self).as_wide_int(), (*other).as_wide_int()) {
+            return a == b;
+        }
+        // Route through the same numeric coercion `PartialOrd::partial_cmp` already uses, so
+        // `Integer(1) == Real(1.0)` the same way `Integer(1).partial_cmp(&Real(1.0))` already
+        // says `Equal` - `eq`/`partial_cmp`/`Hash` must all agree on when two `Value`s are equal.
+        let (this, other) = self.try_cast_match(*other);
+        match (this, other) {
             (Value::Nil, Value::Nil) => true,
-            (Value::Object(lhs), Value::Object(rhs)) => unsafe { lhs.as_ref().eq(rhs.as_ref()) },
+            (Value::Object(lhs), Value::Object(rhs)) => unsafe {
+                lhs.as_ref().eq_with_seen(rhs.as_ref(), seen)
+            },
             (Value::Integer(lhs), Value::Integer(rhs)) => lhs == rhs,
             (Value::Real(lhs), Value::Real(rhs)) => lhs == rhs,
             _ => false,
@@ -68,8 +106,92 @@ impl PartialEq for Value {
     }
 }
 
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        self.eq_with_seen(other, &mut Vec::new())
+    }
+}
+
 impl Eq for Value {}
 
+/// Tier used by [`Ord for Value`](impl@Ord) - lower sorts first. Unlike [`PartialOrd`] above
+/// (which leaves e.g. a `Table` against a `String` as incomparable), every pair of tiers is
+/// ordered, so this is what makes the `Ord` impl total.
+fn value_tier(v: &Value) -> u8 {
+    match v {
+        Value::Nil => 0,
+        Value::Integer(_) | Value::Real(_) => 1,
+        Value::Object(o) => unsafe { o.as_ref().value_rank() },
+    }
+}
+
+/// Widens a numeric `Value` (an `Integer`, a `Real`, or a `BigInt` object) to `f64` for
+/// cross-representation comparison. Only called once [`value_tier`] has already confirmed both
+/// sides are numeric.
+fn numeric_f64(v: &Value) -> f64 {
+    match v {
+        Value::Integer(i) => *i as f64,
+        Value::Real(r) => *r,
+        Value::Object(_) => v.as_bigint().map(|b| b as f64).unwrap_or(0.0),
+        Value::Nil => unreachable!("only called on numeric values"),
+    }
+}
+
+/// Arbitrary tie-break between `Integer`/`Real`/`BigInt` once their numeric values compare equal.
+fn numeric_rank(v: &Value) -> u8 {
+    match v {
+        Value::Integer(_) => 0,
+        Value::Real(_) => 1,
+        Value::Object(_) => 2,
+        Value::Nil => unreachable!("only called on numeric values"),
+    }
+}
+
+/// Total order between two numeric `Value`s: compares by value with NaN sorting last (instead of
+/// `partial_cmp`'s `None`), then by [`numeric_rank`], then - for two `Real`s whose value and rank
+/// already tied (e.g. `0.0` vs `-0.0`) - by raw bit pattern, so the order is total all the way
+/// down.
+fn numeric_cmp(a: &Value, b: &Value) -> core::cmp::Ordering {
+    use core::cmp::Ordering;
+
+    let (x, y) = (numeric_f64(a), numeric_f64(b));
+    match (x.is_nan(), y.is_nan()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) => x.partial_cmp(&y).expect("neither operand is NaN"),
+    }
+    .then_with(|| numeric_rank(a).cmp(&numeric_rank(b)))
+    .then_with(|| match (a, b) {
+        (Value::Real(x), Value::Real(y)) => x.to_bits().cmp(&y.to_bits()),
+        _ => Ordering::Equal,
+    })
+}
+
+impl Ord for Value {
+    /// Total order supporting deterministic iteration and sorted-map keying (see
+    /// [`crate::prelude::CaoLangTable::sorted_iter`]), taking the tiered, rank-broken-tie approach
+    /// `value/canonical.rs` already uses for `OwnedValue`: `Nil` < numbers (`Integer`/`Real`/
+    /// `BigInt`, compared by value, NaN last) < `String` < `Bytes` < `Table` (recursively, via
+    /// `sorted_iter`) < functions. Unlike [`PartialOrd::partial_cmp`] above, which leaves a
+    /// genuinely unlike pair (e.g. a `Table` against a `String`) as incomparable, this always
+    /// returns a definite answer, which is what a total order requires.
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        value_tier(self).cmp(&value_tier(other)).then_with(|| match (self, other) {
+            (Value::Nil, Value::Nil) => core::cmp::Ordering::Equal,
+            (Value::Object(a), Value::Object(b)) if value_tier(self) == 1 => {
+                numeric_cmp(self, other).then_with(|| unsafe {
+                    // both are `BigInt`s (the only object kind sharing the numeric tier)
+                    a.as_ref().total_cmp(b.as_ref())
+                })
+            }
+            _ if value_tier(self) == 1 => numeric_cmp(self, other),
+            (Value::Object(a), Value::Object(b)) => unsafe { a.as_ref().total_cmp(b.as_ref()) },
+            _ => unreachable!("value_tier() already separated every other mismatched kind"),
+        })
+    }
+}
+
 /// Intended for saving `Values` after the program has finished executing
 ///
 /// ```
@@ -104,17 +226,19 @@ impl Eq for Value {}
 /// #     assert_eq!(v, 42);
 /// # }
 /// ```
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum OwnedValue {
     Nil,
     String(String),
+    Bytes(Vec<u8>),
     Table(Vec<OwnedEntry>),
     Integer(i64),
+    BigInt(i128),
     Real(f64),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OwnedEntry {
     pub key: OwnedValue,
@@ -146,10 +270,13 @@ impl TryFrom<Value> for OwnedValue {
                         Self::Table(entries)
                     }
                     CaoLangObjectBody::String(s) => Self::String(s.as_str().to_owned()),
+                    CaoLangObjectBody::Bytes(b) => Self::Bytes(b.as_bytes().to_vec()),
+                    CaoLangObjectBody::BigInt(b) => Self::BigInt(b.0),
                     CaoLangObjectBody::Function(_)
                     | CaoLangObjectBody::Closure(_)
                     | CaoLangObjectBody::NativeFunction(_)
-                    | CaoLangObjectBody::Upvalue(_) => {
+                    | CaoLangObjectBody::Upvalue(_)
+                    | CaoLangObjectBody::Iterator(_) => {
                         return Err(v);
                     }
                 }
@@ -209,6 +336,32 @@ impl Value {
         }
     }
 
+    /// # Safety
+    ///
+    /// Must be called with ptr obtained from a vm, before the last `clear`!
+    ///
+    /// The Vm that allocated the bytes must still be in memory!
+    ///
+    /// # Return
+    ///
+    /// Returns `None` if the value is not a byte buffer, or points to an invalid one
+    pub unsafe fn as_bytes<'a>(self) -> Option<&'a [u8]> {
+        match self {
+            Value::Object(o) => unsafe { o.as_ref().as_bytes() },
+            _ => None,
+        }
+    }
+
+    /// # Safety
+    ///
+    /// Same requirements as [`Value::as_bytes`].
+    pub unsafe fn as_bytes_mut<'a>(mut self) -> Option<&'a mut [u8]> {
+        match &mut self {
+            Value::Object(o) => unsafe { o.as_mut().as_bytes_mut() },
+            _ => None,
+        }
+    }
+
     /// # Safety
     ///
     /// Must be called with ptr obtained from a vm , before the last `clear`!
@@ -249,13 +402,121 @@ impl Value {
         matches!(self, Value::Integer(_))
     }
 
+    #[inline]
+    pub fn is_numeric(self) -> bool {
+        matches!(self, Value::Real(_)) || self.as_wide_int().is_some()
+    }
+
+    /// Returns a [`CaoLangObjectBody::BigInt`]'s value, or `None` if `self` isn't one.
+    pub fn as_bigint(self) -> Option<i128> {
+        match self {
+            Value::Object(o) => unsafe { o.as_ref().as_bigint() }.map(|b| b.0),
+            _ => None,
+        }
+    }
+
+    /// Widens `self` to `i128` if it's an `Integer` or a `BigInt` object - the canonical shared
+    /// representation that lets an `Integer` and a `BigInt` holding the same value compare, order
+    /// and hash identically. See the `Hash`/`PartialOrd`/`eq_with_seen` impls above.
+    pub(crate) fn as_wide_int(self) -> Option<i128> {
+        match self {
+            Value::Integer(i) => Some(i as i128),
+            Value::Object(_) => self.as_bigint(),
+            _ => None,
+        }
+    }
+
+    /// Like [`Value::as_wide_int`], but also widens a `Real` holding an exact whole number -
+    /// used only by `Hash`, since an `Integer`/`BigInt` and a `Real` holding the same value
+    /// already compare equal (via `try_cast_match`) and so must hash equal too. Kept separate
+    /// from `as_wide_int`, which arithmetic and ordering also use: those must keep a `Real`
+    /// operand's own type (e.g. `2.0 + 3.0` stays a `Real`, not an `Integer`).
+    pub(crate) fn canonical_hash_key(self) -> Option<i128> {
+        match self {
+            Value::Real(r) if r.fract() == 0.0 && (i128::MIN as f64..=i128::MAX as f64).contains(&r) => {
+                Some(r as i128)
+            }
+            _ => self.as_wide_int(),
+        }
+    }
+
     #[inline]
     pub fn is_null(self) -> bool {
         matches!(self, Value::Nil)
     }
 
+    /// Raise `self` to the power of `other`. Integer bases with a negative or huge exponent fall
+    /// back to `Real`, since `i64::pow` can't represent the result.
+    pub fn pow(self, other: Self) -> Self {
+        let (a, b) = self.try_cast_match(other);
+        match (a, b) {
+            (Value::Integer(a), Value::Integer(b)) => match u32::try_from(b) {
+                Ok(exp) => Value::Integer(a.pow(exp)),
+                Err(_) => Value::Real((a as f64).powf(b as f64)),
+            },
+            (Value::Real(a), Value::Real(b)) => Value::Real(a.powf(b)),
+            _ => Value::Nil,
+        }
+    }
+
+    /// The smaller of two numbers
+    pub fn min(self, other: Self) -> Self {
+        let (a, b) = self.try_cast_match(other);
+        match (a, b) {
+            (Value::Integer(a), Value::Integer(b)) => Value::Integer(a.min(b)),
+            (Value::Real(a), Value::Real(b)) => Value::Real(a.min(b)),
+            _ => Value::Nil,
+        }
+    }
+
+    /// The larger of two numbers
+    pub fn max(self, other: Self) -> Self {
+        let (a, b) = self.try_cast_match(other);
+        match (a, b) {
+            (Value::Integer(a), Value::Integer(b)) => Value::Integer(a.max(b)),
+            (Value::Real(a), Value::Real(b)) => Value::Real(a.max(b)),
+            _ => Value::Nil,
+        }
+    }
+
+    /// Absolute value of a number
+    pub fn abs(self) -> Self {
+        match self {
+            Value::Integer(i) => Value::Integer(i.abs()),
+            Value::Real(r) => Value::Real(r.abs()),
+            _ => Value::Nil,
+        }
+    }
+
+    /// Round a number down to the nearest integer. Integers are returned unchanged.
+    pub fn floor(self) -> Self {
+        match self {
+            Value::Integer(i) => Value::Integer(i),
+            Value::Real(r) => Value::Real(r.floor()),
+            _ => Value::Nil,
+        }
+    }
+
+    /// Round a number up to the nearest integer. Integers are returned unchanged.
+    pub fn ceil(self) -> Self {
+        match self {
+            Value::Integer(i) => Value::Integer(i),
+            Value::Real(r) => Value::Real(r.ceil()),
+            _ => Value::Nil,
+        }
+    }
+
+    /// Round a number to the nearest integer. Integers are returned unchanged.
+    pub fn round(self) -> Self {
+        match self {
+            Value::Integer(i) => Value::Integer(i),
+            Value::Real(r) => Value::Real(r.round()),
+            _ => Value::Nil,
+        }
+    }
+
     /// return the original pair if casting can't be performed
-    fn try_cast_match(self, other: Self) -> (Self, Self) {
+    pub(crate) fn try_cast_match(self, other: Self) -> (Self, Self) {
         if self.is_float() || other.is_float() {
             if let Ok(a) = f64::try_from(self) {
                 if let Ok(b) = f64::try_from(other) {
@@ -336,7 +597,12 @@ impl TryFrom<Value> for i64 {
         match v {
             Value::Integer(i) => Ok(i),
             Value::Real(r) => Ok(r as i64),
-            Value::Object(o) => Ok(unsafe { o.as_ref().len() as i64 }),
+            Value::Object(o) => match unsafe { o.as_ref().as_bigint() } {
+                // out of `i64`'s range: fail instead of silently truncating, same as any other
+                // value this conversion can't represent
+                Some(b) => b.to_i64().ok_or(v),
+                None => Ok(unsafe { o.as_ref().len() as i64 }),
+            },
             Value::Nil => Ok(0),
         }
     }
@@ -349,7 +615,10 @@ impl TryFrom<Value> for f64 {
         match v {
             Value::Real(i) => Ok(i),
             Value::Integer(i) => Ok(i as f64),
-            Value::Object(o) => Ok(unsafe { o.as_ref().len() as f64 }),
+            Value::Object(o) => match unsafe { o.as_ref().as_bigint() } {
+                Some(b) => Ok(b.0 as f64),
+                None => Ok(unsafe { o.as_ref().len() as f64 }),
+            },
             Value::Nil => Ok(0.0),
         }
     }
@@ -409,17 +678,114 @@ impl Mul for Value {
 impl Div for Value {
     type Output = Self;
 
+    /// Integer operands truncate (matching Rust's own `/`) instead of promoting to `Real`;
+    /// division by zero has no sane result, so it falls back to `Nil` instead of panicking.
     fn div(self, other: Self) -> Self {
         let (a, b) = self.try_cast_match(other);
         match (a, b) {
-            (Value::Integer(a), Value::Integer(b)) => Value::Real(a as f64 / b as f64),
+            (Value::Integer(a), Value::Integer(b)) if b != 0 => Value::Integer(a / b),
             (Value::Real(a), Value::Real(b)) => Value::Real(a / b),
             _ => Value::Nil,
         }
     }
 }
 
-impl std::borrow::Borrow<str> for Value {
+impl Rem for Value {
+    type Output = Self;
+
+    /// Integer remainder truncates towards zero and keeps the sign of the dividend, matching
+    /// Rust's own `%` (e.g. `-7 % 2 == -1`). Remainder by zero has no sane result, so it falls
+    /// back to `Nil` instead of panicking.
+    fn rem(self, other: Self) -> Self {
+        let (a, b) = self.try_cast_match(other);
+        match (a, b) {
+            (Value::Integer(a), Value::Integer(b)) if b != 0 => Value::Integer(a % b),
+            (Value::Real(a), Value::Real(b)) => Value::Real(a % b),
+            _ => Value::Nil,
+        }
+    }
+}
+
+impl Neg for Value {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        match self {
+            Value::Integer(i) => Value::Integer(-i),
+            Value::Real(r) => Value::Real(-r),
+            _ => Value::Nil,
+        }
+    }
+}
+
+impl BitAnd for Value {
+    type Output = Self;
+
+    fn bitand(self, other: Self) -> Self {
+        match (i64::try_from(self), i64::try_from(other)) {
+            (Ok(a), Ok(b)) => Value::Integer(a & b),
+            _ => Value::Nil,
+        }
+    }
+}
+
+impl BitOr for Value {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        match (i64::try_from(self), i64::try_from(other)) {
+            (Ok(a), Ok(b)) => Value::Integer(a | b),
+            _ => Value::Nil,
+        }
+    }
+}
+
+impl BitXor for Value {
+    type Output = Self;
+
+    fn bitxor(self, other: Self) -> Self {
+        match (i64::try_from(self), i64::try_from(other)) {
+            (Ok(a), Ok(b)) => Value::Integer(a ^ b),
+            _ => Value::Nil,
+        }
+    }
+}
+
+impl Shl for Value {
+    type Output = Self;
+
+    /// Shifts by a negative amount or by at least the bit width produce `Nil` instead of
+    /// panicking.
+    fn shl(self, other: Self) -> Self {
+        match (i64::try_from(self), i64::try_from(other)) {
+            (Ok(a), Ok(b)) => u32::try_from(b)
+                .ok()
+                .and_then(|b| a.checked_shl(b))
+                .map(Value::Integer)
+                .unwrap_or(Value::Nil),
+            _ => Value::Nil,
+        }
+    }
+}
+
+impl Shr for Value {
+    type Output = Self;
+
+    /// Arithmetic (sign-extending) shift, matching Rust's `>>` on `i64`. Shifts by a negative
+    /// amount or by at least the bit width produce `Nil` instead of panicking.
+    fn shr(self, other: Self) -> Self {
+        match (i64::try_from(self), i64::try_from(other)) {
+            (Ok(a), Ok(b)) => u32::try_from(b)
+                .ok()
+                .and_then(|b| a.checked_shr(b))
+                .map(Value::Integer)
+                .unwrap_or(Value::Nil),
+            _ => Value::Nil,
+        }
+    }
+}
+
+impl core::borrow::Borrow<str> for Value {
     fn borrow(&self) -> &str {
         match self {
             Value::Object(s) => unsafe { s.as_ref().as_str().unwrap_or("") },
@@ -428,7 +794,7 @@ impl std::borrow::Borrow<str> for Value {
     }
 }
 
-impl std::borrow::Borrow<i64> for Value {
+impl core::borrow::Borrow<i64> for Value {
     fn borrow(&self) -> &i64 {
         match self {
             Value::Integer(i) => i,
@@ -0,0 +1,344 @@
+//! A deterministic, order-independent encoding for [`OwnedValue`], borrowing the canonical-form
+//! idea from the Preserves data model: two `OwnedValue`s that are structurally equal up to table
+//! entry order always serialize to identical bytes, which is what content-addressing, diffing,
+//! or hashing saved VM state needs - something the insertion-order-preserving `serde` derives on
+//! `OwnedValue` don't guarantee on their own.
+//!
+//! [`OwnedValue::into_canonical`] recursively sorts every `Table`'s entries by [`canonical_cmp`]
+//! and normalizes `Real`'s NaN/`-0.0` bit patterns; [`OwnedValue::canonical_serialize`] then emits
+//! a stable, length-prefixed binary encoding of the result, readable back via
+//! [`OwnedValue::canonical_deserialize`].
+
+use crate::alloc_crate::{string::String, vec::Vec};
+use crate::bytecode::{decode_str_checked, encode_str, read_from_bytes, write_to_vec, StrDecodeError};
+use crate::value::{OwnedEntry, OwnedValue};
+use core::cmp::Ordering;
+
+const TAG_NIL: u8 = 0;
+const TAG_INTEGER: u8 = 1;
+const TAG_REAL: u8 = 2;
+const TAG_STRING: u8 = 3;
+const TAG_BYTES: u8 = 4;
+const TAG_TABLE: u8 = 5;
+const TAG_BIGINT: u8 = 6;
+
+/// Failure reason for [`OwnedValue::canonical_deserialize`].
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum CanonicalDecodeError {
+    #[error("Not enough bytes left to decode a value")]
+    UnexpectedEof,
+    #[error("Byte {0} is not a valid canonical encoding tag")]
+    InvalidTag(u8),
+    #[error("Embedded string is not valid canonical encoding: {0}")]
+    Str(StrDecodeError),
+    #[error("{0} trailing byte(s) after a complete value")]
+    TrailingBytes(usize),
+}
+
+/// The cross-type total order canonical encoding sorts table keys by: `Nil` < `Integer`/`Real`/
+/// `BigInt` (by numeric value) < `String`/`Bytes` (lexicographically) < `Table` (recursively,
+/// entry by entry). Ties within a tier (e.g. `Integer(1)` vs `Real(1.0)`, or a `String` and a
+/// `Bytes` with the same content) break on a fixed, arbitrary per-tier rank so the order is total
+/// even across values a Cao-Lang table would never actually mix as keys.
+fn tier(value: &OwnedValue) -> u8 {
+    match value {
+        OwnedValue::Nil => 0,
+        OwnedValue::Integer(_) | OwnedValue::Real(_) | OwnedValue::BigInt(_) => 1,
+        OwnedValue::String(_) | OwnedValue::Bytes(_) => 2,
+        OwnedValue::Table(_) => 3,
+    }
+}
+
+/// Canonicalizes a `Real`'s bit pattern: every NaN collapses to the same quiet NaN, and `-0.0`
+/// collapses to `0.0`, so two `Real`s that compare `==` under IEEE rules always serialize (and
+/// order) identically.
+fn canonicalize_real(r: f64) -> f64 {
+    if r.is_nan() {
+        f64::from_bits(0x7ff8_0000_0000_0000)
+    } else if r == 0.0 {
+        0.0
+    } else {
+        r
+    }
+}
+
+fn numeric_value(value: &OwnedValue) -> f64 {
+    match value {
+        OwnedValue::Integer(i) => *i as f64,
+        OwnedValue::Real(r) => *r,
+        OwnedValue::BigInt(b) => *b as f64,
+        _ => unreachable!("only called on Integer/Real/BigInt"),
+    }
+}
+
+/// Arbitrary tie-break between `Integer`/`Real`/`BigInt` when their numeric values are equal.
+fn numeric_rank(value: &OwnedValue) -> u8 {
+    match value {
+        OwnedValue::Integer(_) => 0,
+        OwnedValue::Real(_) => 1,
+        OwnedValue::BigInt(_) => 2,
+        _ => unreachable!("only called on Integer/Real/BigInt"),
+    }
+}
+
+fn bytes_value(value: &OwnedValue) -> &[u8] {
+    match value {
+        OwnedValue::String(s) => s.as_bytes(),
+        OwnedValue::Bytes(b) => b.as_slice(),
+        _ => unreachable!("only called on String/Bytes"),
+    }
+}
+
+/// Arbitrary tie-break between `String` and `Bytes` when their byte content is equal.
+fn bytes_rank(value: &OwnedValue) -> u8 {
+    match value {
+        OwnedValue::String(_) => 0,
+        OwnedValue::Bytes(_) => 1,
+        _ => unreachable!("only called on String/Bytes"),
+    }
+}
+
+/// The total order canonical table entries are sorted by. Assumes `a`/`b` (and, recursively, any
+/// `Table` entries they contain) have already been through [`OwnedValue::into_canonical`], so two
+/// `Table`s compare by their (already-sorted) entries pairwise rather than needing to re-sort.
+fn canonical_cmp(a: &OwnedValue, b: &OwnedValue) -> Ordering {
+    tier(a).cmp(&tier(b)).then_with(|| match (a, b) {
+        (OwnedValue::Nil, OwnedValue::Nil) => Ordering::Equal,
+        (
+            OwnedValue::Integer(_) | OwnedValue::Real(_) | OwnedValue::BigInt(_),
+            OwnedValue::Integer(_) | OwnedValue::Real(_) | OwnedValue::BigInt(_),
+        ) => {
+            numeric_value(a)
+                .partial_cmp(&numeric_value(b))
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| numeric_rank(a).cmp(&numeric_rank(b)))
+        }
+        (OwnedValue::String(_) | OwnedValue::Bytes(_), OwnedValue::String(_) | OwnedValue::Bytes(_)) => {
+            bytes_value(a)
+                .cmp(bytes_value(b))
+                .then_with(|| bytes_rank(a).cmp(&bytes_rank(b)))
+        }
+        (OwnedValue::Table(lhs), OwnedValue::Table(rhs)) => lhs
+            .iter()
+            .zip(rhs.iter())
+            .map(|(l, r)| canonical_cmp(&l.key, &r.key).then_with(|| canonical_cmp(&l.value, &r.value)))
+            .find(|o| *o != Ordering::Equal)
+            .unwrap_or_else(|| lhs.len().cmp(&rhs.len())),
+        _ => unreachable!("tier() already separated mismatched variants"),
+    })
+}
+
+fn encode_canonical(value: &OwnedValue, out: &mut Vec<u8>) {
+    match value {
+        OwnedValue::Nil => out.push(TAG_NIL),
+        OwnedValue::Integer(i) => {
+            out.push(TAG_INTEGER);
+            write_to_vec(*i, out);
+        }
+        OwnedValue::Real(r) => {
+            out.push(TAG_REAL);
+            write_to_vec(r.to_bits(), out);
+        }
+        OwnedValue::BigInt(b) => {
+            out.push(TAG_BIGINT);
+            write_to_vec(*b, out);
+        }
+        OwnedValue::String(s) => {
+            out.push(TAG_STRING);
+            encode_str(s, out);
+        }
+        OwnedValue::Bytes(b) => {
+            out.push(TAG_BYTES);
+            let len: u32 = b.len().try_into().expect("byte buffer too long to encode");
+            write_to_vec(len, out);
+            out.extend_from_slice(b);
+        }
+        OwnedValue::Table(entries) => {
+            out.push(TAG_TABLE);
+            let len: u32 = entries.len().try_into().expect("table too large to encode");
+            write_to_vec(len, out);
+            for entry in entries {
+                encode_canonical(&entry.key, out);
+                encode_canonical(&entry.value, out);
+            }
+        }
+    }
+}
+
+/// Returns the number of bytes consumed, alongside the decoded value.
+fn decode_canonical(bytes: &[u8]) -> Result<(usize, OwnedValue), CanonicalDecodeError> {
+    let tag = *bytes.first().ok_or(CanonicalDecodeError::UnexpectedEof)?;
+    let rest = &bytes[1..];
+    match tag {
+        TAG_NIL => Ok((1, OwnedValue::Nil)),
+        TAG_INTEGER => {
+            let (n, i) = read_from_bytes::<i64>(rest).ok_or(CanonicalDecodeError::UnexpectedEof)?;
+            Ok((1 + n, OwnedValue::Integer(i)))
+        }
+        TAG_REAL => {
+            let (n, bits) = read_from_bytes::<u64>(rest).ok_or(CanonicalDecodeError::UnexpectedEof)?;
+            Ok((1 + n, OwnedValue::Real(f64::from_bits(bits))))
+        }
+        TAG_BIGINT => {
+            let (n, i) = read_from_bytes::<i128>(rest).ok_or(CanonicalDecodeError::UnexpectedEof)?;
+            Ok((1 + n, OwnedValue::BigInt(i)))
+        }
+        TAG_STRING => {
+            let (n, s) = decode_str_checked(rest, rest.len()).map_err(CanonicalDecodeError::Str)?;
+            Ok((1 + n, OwnedValue::String(s.to_owned())))
+        }
+        TAG_BYTES => {
+            let (n, len): (_, u32) =
+                read_from_bytes(rest).ok_or(CanonicalDecodeError::UnexpectedEof)?;
+            let len = len as usize;
+            let payload = rest
+                .get(n..n + len)
+                .ok_or(CanonicalDecodeError::UnexpectedEof)?;
+            Ok((1 + n + len, OwnedValue::Bytes(payload.to_vec())))
+        }
+        TAG_TABLE => {
+            let (n, count): (_, u32) =
+                read_from_bytes(rest).ok_or(CanonicalDecodeError::UnexpectedEof)?;
+            let mut offset = n;
+            let mut entries = Vec::with_capacity(count as usize);
+            for _ in 0..count {
+                let (kn, key) = decode_canonical(&rest[offset..])?;
+                offset += kn;
+                let (vn, value) = decode_canonical(&rest[offset..])?;
+                offset += vn;
+                entries.push(OwnedEntry { key, value });
+            }
+            Ok((1 + offset, OwnedValue::Table(entries)))
+        }
+        _ => Err(CanonicalDecodeError::InvalidTag(tag)),
+    }
+}
+
+impl OwnedValue {
+    /// Recursively sorts every `Table`'s entries by [`canonical_cmp`] and normalizes `Real`'s
+    /// NaN/`-0.0` bit patterns, so two values that are equal up to table entry order become
+    /// identical `OwnedValue`s.
+    pub fn into_canonical(self) -> Self {
+        match self {
+            OwnedValue::Nil => OwnedValue::Nil,
+            OwnedValue::Integer(i) => OwnedValue::Integer(i),
+            OwnedValue::Real(r) => OwnedValue::Real(canonicalize_real(r)),
+            OwnedValue::BigInt(i) => OwnedValue::BigInt(i),
+            OwnedValue::String(s) => OwnedValue::String(s),
+            OwnedValue::Bytes(b) => OwnedValue::Bytes(b),
+            OwnedValue::Table(entries) => {
+                let mut entries: Vec<OwnedEntry> = entries
+                    .into_iter()
+                    .map(|entry| OwnedEntry {
+                        key: entry.key.into_canonical(),
+                        value: entry.value.into_canonical(),
+                    })
+                    .collect();
+                entries.sort_by(|a, b| canonical_cmp(&a.key, &b.key));
+                OwnedValue::Table(entries)
+            }
+        }
+    }
+
+    /// Encodes `self` into the stable, length-prefixed binary format described in the module
+    /// docs. Equal values (up to table entry order) always produce identical bytes.
+    pub fn canonical_serialize(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        encode_canonical(&self.clone().into_canonical(), &mut out);
+        out
+    }
+
+    /// Inverse of [`OwnedValue::canonical_serialize`].
+    pub fn canonical_deserialize(bytes: &[u8]) -> Result<Self, CanonicalDecodeError> {
+        let (consumed, value) = decode_canonical(bytes)?;
+        if consumed != bytes.len() {
+            return Err(CanonicalDecodeError::TrailingBytes(bytes.len() - consumed));
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_every_variant() {
+        let values = [
+            OwnedValue::Nil,
+            OwnedValue::Integer(-42),
+            OwnedValue::Real(1.5),
+            OwnedValue::BigInt(i64::MAX as i128 + 1),
+            OwnedValue::String("poggers".to_owned()),
+            OwnedValue::Bytes(vec![0xde, 0xad, 0xbe, 0xef]),
+        ];
+        for value in values {
+            let bytes = value.canonical_serialize();
+            let decoded = OwnedValue::canonical_deserialize(&bytes).unwrap();
+            assert_eq!(decoded.canonical_serialize(), bytes);
+        }
+    }
+
+    #[test]
+    fn table_entry_order_does_not_affect_the_encoding() {
+        let a = OwnedValue::Table(vec![
+            OwnedEntry {
+                key: OwnedValue::String("a".to_owned()),
+                value: OwnedValue::Integer(1),
+            },
+            OwnedEntry {
+                key: OwnedValue::String("b".to_owned()),
+                value: OwnedValue::Integer(2),
+            },
+        ]);
+        let b = OwnedValue::Table(vec![
+            OwnedEntry {
+                key: OwnedValue::String("b".to_owned()),
+                value: OwnedValue::Integer(2),
+            },
+            OwnedEntry {
+                key: OwnedValue::String("a".to_owned()),
+                value: OwnedValue::Integer(1),
+            },
+        ]);
+        assert_eq!(a.canonical_serialize(), b.canonical_serialize());
+    }
+
+    #[test]
+    fn nan_and_negative_zero_normalize() {
+        let a = OwnedValue::Real(f64::NAN).canonical_serialize();
+        let b = OwnedValue::Real(-f64::NAN).canonical_serialize();
+        assert_eq!(a, b);
+
+        let a = OwnedValue::Real(0.0).canonical_serialize();
+        let b = OwnedValue::Real(-0.0).canonical_serialize();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn cross_type_keys_sort_by_the_documented_tier_order() {
+        let table = OwnedValue::Table(vec![
+            OwnedEntry {
+                key: OwnedValue::Table(vec![]),
+                value: OwnedValue::Nil,
+            },
+            OwnedEntry {
+                key: OwnedValue::String("s".to_owned()),
+                value: OwnedValue::Nil,
+            },
+            OwnedEntry {
+                key: OwnedValue::Integer(1),
+                value: OwnedValue::Nil,
+            },
+            OwnedEntry {
+                key: OwnedValue::Nil,
+                value: OwnedValue::Nil,
+            },
+        ]);
+        let OwnedValue::Table(sorted) = table.into_canonical() else {
+            unreachable!()
+        };
+        let tiers: Vec<u8> = sorted.iter().map(|e| tier(&e.key)).collect();
+        assert_eq!(tiers, vec![0, 1, 2, 3]);
+    }
+}
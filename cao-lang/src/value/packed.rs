@@ -0,0 +1,143 @@
+//! NaN-boxed packed encoding for [`Value`], letting [`crate::collections::value_stack::ValueStack`]
+//! store one 8-byte word per slot instead of the tagged `Value` enum's 16 bytes - halving the
+//! memory traffic a stack-based VM generates just by shuffling operands around.
+//!
+//! Any bit pattern that isn't a quiet NaN in the *negative* NaN space ([`TAG_BASE`]) is a plain
+//! `f64` and round-trips straight back to [`Value::Real`]. A tagged (non-float) value sets the
+//! high 13 bits to `TAG_BASE` (sign bit, all-ones exponent, quiet mantissa bit), the next 3 bits
+//! to a [`TAG_NIL`]/[`TAG_INTEGER`]/[`TAG_OBJECT`] discriminant, and the low 48 bits to the
+//! payload: an `i64` sign-extended out of 48 bits, or a [`CaoLangObject`] pointer (user-space
+//! addresses on x86-64/AArch64 fit in 48 bits). Every real NaN is canonicalized to
+//! [`CANONICAL_NAN`] (the *positive* quiet NaN) before packing, so it never aliases a tag and two
+//! NaNs always pack identically.
+
+use core::ptr::NonNull;
+
+use crate::value::Value;
+use crate::vm::runtime::cao_lang_object::CaoLangObject;
+
+const TAG_BASE: u64 = 0xFFF8_0000_0000_0000;
+const CANONICAL_NAN: u64 = 0x7FF8_0000_0000_0000;
+/// High 13 bits shared by every tagged value: sign + 11 exponent bits + the mantissa's quiet bit.
+const TAG_BASE_SHIFT: u32 = 51;
+const TAG_SHIFT: u32 = 48;
+const TAG_MASK: u64 = 0b111 << TAG_SHIFT;
+const PAYLOAD_MASK: u64 = (1 << 48) - 1;
+
+const TAG_NIL: u64 = 0;
+const TAG_INTEGER: u64 = 1;
+const TAG_OBJECT: u64 = 2;
+
+/// The range a [`Value::Integer`] must fit in to be packed: the 48-bit payload is sign-extended
+/// back out on unpack, so only a 48-bit (not the full 64-bit) signed range round-trips. Plenty
+/// for a game script (±140 trillion), but callers computing into this range (e.g. `binary_op!`
+/// in [`crate::stdlib`]) should not assume a packed slot can hold an arbitrary `i64`.
+pub const MIN_PACKED_INT: i64 = -(1 << 47);
+pub const MAX_PACKED_INT: i64 = (1 << 47) - 1;
+
+/// A [`Value`], NaN-boxed into a single 8-byte word. See the module docs for the encoding.
+#[derive(Debug, Clone, Copy)]
+#[repr(transparent)]
+pub struct PackedValue(u64);
+
+impl PackedValue {
+    /// Packs `value`, or `None` if it's an out-of-[`MIN_PACKED_INT`]..=[`MAX_PACKED_INT`]
+    /// integer - the only `Value` that can fail to round-trip through the 48-bit payload.
+    pub fn try_pack(value: Value) -> Option<Self> {
+        let bits = match value {
+            Value::Nil => TAG_BASE | (TAG_NIL << TAG_SHIFT),
+            Value::Integer(i) => {
+                if !(MIN_PACKED_INT..=MAX_PACKED_INT).contains(&i) {
+                    return None;
+                }
+                TAG_BASE | (TAG_INTEGER << TAG_SHIFT) | (i as u64 & PAYLOAD_MASK)
+            }
+            Value::Object(ptr) => {
+                let addr = ptr.as_ptr() as u64;
+                debug_assert_eq!(addr & !PAYLOAD_MASK, 0, "pointer does not fit in 48 bits");
+                TAG_BASE | (TAG_OBJECT << TAG_SHIFT) | (addr & PAYLOAD_MASK)
+            }
+            Value::Real(f) if f.is_nan() => CANONICAL_NAN,
+            Value::Real(f) => f.to_bits(),
+        };
+        Some(Self(bits))
+    }
+
+    /// Unpacks back to a [`Value`]. Inverse of [`PackedValue::try_pack`].
+    pub fn unpack(self) -> Value {
+        if self.0 >> TAG_BASE_SHIFT != TAG_BASE >> TAG_BASE_SHIFT {
+            return Value::Real(f64::from_bits(self.0));
+        }
+        let payload = self.0 & PAYLOAD_MASK;
+        match (self.0 & TAG_MASK) >> TAG_SHIFT {
+            TAG_NIL => Value::Nil,
+            TAG_INTEGER => {
+                // Sign-extend the 48-bit payload back out to a full i64: shift it up so its sign
+                // bit lands on bit 63, then an arithmetic right-shift carries that sign back down.
+                Value::Integer(((payload << 16) as i64) >> 16)
+            }
+            TAG_OBJECT => {
+                let ptr = payload as *mut CaoLangObject;
+                Value::Object(unsafe { NonNull::new_unchecked(ptr) })
+            }
+            _ => unreachable!("only 3 tags are ever written"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nil_round_trips() {
+        assert!(matches!(
+            PackedValue::try_pack(Value::Nil).unwrap().unpack(),
+            Value::Nil
+        ));
+    }
+
+    #[test]
+    fn integers_round_trip_within_range() {
+        for i in [0, 1, -1, MIN_PACKED_INT, MAX_PACKED_INT, 12345, -98765] {
+            let packed = PackedValue::try_pack(Value::Integer(i)).unwrap();
+            assert!(matches!(packed.unpack(), Value::Integer(v) if v == i));
+        }
+    }
+
+    #[test]
+    fn integers_out_of_range_fail_to_pack() {
+        assert!(PackedValue::try_pack(Value::Integer(MAX_PACKED_INT + 1)).is_none());
+        assert!(PackedValue::try_pack(Value::Integer(MIN_PACKED_INT - 1)).is_none());
+        assert!(PackedValue::try_pack(Value::Integer(i64::MAX)).is_none());
+        assert!(PackedValue::try_pack(Value::Integer(i64::MIN)).is_none());
+    }
+
+    #[test]
+    fn reals_round_trip() {
+        for f in [0.0, -0.0, 1.5, -1.5, f64::INFINITY, f64::NEG_INFINITY, f64::MIN, f64::MAX] {
+            let packed = PackedValue::try_pack(Value::Real(f)).unwrap();
+            assert!(matches!(packed.unpack(), Value::Real(v) if v.to_bits() == f.to_bits()));
+        }
+    }
+
+    #[test]
+    fn every_nan_canonicalizes_to_the_same_bit_pattern() {
+        let a = PackedValue::try_pack(Value::Real(f64::NAN)).unwrap();
+        let b = PackedValue::try_pack(Value::Real(-f64::NAN)).unwrap();
+        let c = PackedValue::try_pack(Value::Real(f64::from_bits(0x7ff8_0000_0000_0001))).unwrap();
+        assert_eq!(a.0, b.0);
+        assert_eq!(a.0, c.0);
+        assert!(matches!(a.unpack(), Value::Real(r) if r.is_nan()));
+    }
+
+    #[test]
+    fn objects_round_trip_without_aliasing_other_tags() {
+        let ptr = NonNull::<CaoLangObject>::dangling();
+        let packed = PackedValue::try_pack(Value::Object(ptr)).unwrap();
+        let Value::Object(got) = packed.unpack() else {
+            panic!("expected a Value::Object");
+        };
+        assert_eq!(got, ptr);
+    }
+}
@@ -0,0 +1,432 @@
+//! Pre-execution verification of a compiled program's bytecode.
+//!
+//! [`Vm`](crate::vm::Vm)'s dispatch loop trusts the bytecode it's handed: opcode decoding is
+//! checked (an unknown byte becomes an [`crate::procedures::ExecutionErrorPayload`]), but a
+//! `Goto`/`GotoIfTrue`/`GotoIfFalse` target is read with [`crate::vm::instr_execution::decode_value`]
+//! and assigned straight into `instr_ptr` with only a `debug_assert!` guarding it - bytecode
+//! compiled by this crate's own compiler always lands a jump on an instruction boundary, so that's
+//! never been a problem in practice, but nothing stops a hand-assembled or corrupted program from
+//! jumping into the middle of another instruction's operand bytes and having the next loop
+//! iteration decode whatever garbage happens to be there as a different opcode. [`verify`] walks
+//! the whole bytecode once up front and rejects that class of program before it ever reaches the
+//! VM, so a host that only runs its own compiler's output can skip the check, while one that
+//! accepts serialized bytecode from elsewhere can call [`verify`] first and trust what follows.
+//!
+//! This is deliberately a separate, opt-in pass rather than something [`Vm::run`](crate::vm::Vm::run)
+//! calls itself - it doesn't change what already-trusted programs cost to execute.
+
+use num_enum::TryFromPrimitive;
+
+use crate::{
+    alloc_crate::{
+        collections::{BTreeMap, BTreeSet, VecDeque},
+        vec::Vec,
+    },
+    bytecode::read_from_bytes,
+    instruction::Instruction,
+    prelude::CaoCompiledProgram,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyError {
+    /// The byte at `offset` doesn't match any known [`Instruction`] discriminant.
+    InvalidInstruction { offset: u32, byte: u8 },
+    /// The instruction at `offset` needs more operand bytes than remain in the bytecode.
+    TruncatedOperands { offset: u32 },
+    /// The `Goto`/`GotoIfTrue`/`GotoIfFalse` at `offset` targets `target`, which isn't the start
+    /// of any instruction in this program.
+    InvalidJumpTarget { offset: u32, target: i32 },
+    /// The instruction at `offset` pops more values than [`verify_stack_depth`] can prove are on
+    /// the abstract stack, or two control-flow paths reach it with disagreeing depths. `expected`
+    /// is the depth required/first observed; `found` is what this path actually has.
+    StackImbalance { offset: u32, expected: u32, found: u32 },
+}
+
+impl core::fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::InvalidInstruction { offset, byte } => {
+                write!(f, "invalid instruction byte {byte} at offset {offset}")
+            }
+            Self::TruncatedOperands { offset } => {
+                write!(f, "instruction at offset {offset} is missing operand bytes")
+            }
+            Self::InvalidJumpTarget { offset, target } => write!(
+                f,
+                "jump at offset {offset} targets {target}, which is not an instruction boundary"
+            ),
+            Self::StackImbalance {
+                offset,
+                expected,
+                found,
+            } => write!(
+                f,
+                "stack imbalance at offset {offset}: expected depth {expected}, found {found}"
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for VerifyError {}
+
+/// Checks that `program`'s bytecode is safe for [`Vm`](crate::vm::Vm)'s unchecked jump-target
+/// decoding: every opcode byte is a known [`Instruction`], every instruction has enough trailing
+/// operand bytes, and every `Goto`/`GotoIfTrue`/`GotoIfFalse` lands on another instruction's
+/// opcode byte rather than into the middle of one.
+///
+/// This tree's jump operands are raw bytecode offsets rather than lookups into
+/// [`crate::compiled_program::Labels`] - only a program's externally-addressable function entries
+/// get a `Labels` entry, not every jump destination - so a valid instruction boundary is the
+/// relevant check here, not membership in `program.labels`.
+pub fn verify(program: &CaoCompiledProgram) -> Result<(), VerifyError> {
+    let bytecode = &program.bytecode;
+    let len = bytecode.len();
+
+    let mut boundaries = BTreeSet::new();
+    let mut jumps = Vec::new();
+
+    let mut offset = 0usize;
+    while offset < len {
+        boundaries.insert(offset as u32);
+
+        let instr = Instruction::try_from_primitive(bytecode[offset]).map_err(|_| {
+            VerifyError::InvalidInstruction {
+                offset: offset as u32,
+                byte: bytecode[offset],
+            }
+        })?;
+
+        let span = instr.span();
+        if offset + span > len {
+            return Err(VerifyError::TruncatedOperands {
+                offset: offset as u32,
+            });
+        }
+
+        if matches!(
+            instr,
+            Instruction::Goto | Instruction::GotoIfTrue | Instruction::GotoIfFalse
+        ) {
+            let (_, target) = read_from_bytes::<i32>(&bytecode[offset + 1..])
+                .expect("span already checked above");
+            jumps.push((offset as u32, target));
+        }
+
+        offset += span;
+    }
+
+    for (offset, target) in jumps {
+        if target < 0 || !boundaries.contains(&(target as u32)) {
+            return Err(VerifyError::InvalidJumpTarget { offset, target });
+        }
+    }
+
+    Ok(())
+}
+
+/// An instruction's effect on the value stack, for [`verify_stack_depth`]'s abstract
+/// interpretation.
+#[derive(Debug, Clone, Copy)]
+enum StackEffect {
+    /// Always pops exactly `pop` values and pushes exactly `push`, regardless of runtime state.
+    Fixed { pop: u32, push: u32 },
+    /// The depth after this instruction depends on something [`verify_stack_depth`] can't see in
+    /// the opcode alone (a native/function call's arity, table iteration, upvalues, ...) -
+    /// tracking stops here rather than risk a false positive; execution resumes untracked at
+    /// whatever comes next.
+    Opaque,
+}
+
+/// The fixed pop/push counts documented on [`Instruction`]'s variants, or [`StackEffect::Opaque`]
+/// for the handful whose effect depends on runtime-only information.
+fn stack_effect(instr: Instruction) -> StackEffect {
+    use Instruction::*;
+    use StackEffect::Fixed;
+    match instr {
+        Add | Sub | Mul | Div | Mod | Pow | Min | Max | BitAnd | BitOr | BitXor | Shl | Shr
+        | Equals | NotEquals | Less | LessOrEq | And | Or | Xor | Random | DiceRoll => {
+            Fixed { pop: 2, push: 1 }
+        }
+        Neg | Abs | Floor | Ceil | Round | Not => Fixed { pop: 1, push: 1 },
+        ScalarInt | ScalarFloat | ScalarNil | StringLiteral | InitTable | FunctionPointer
+        | NativeFunctionPointer | CopyLast | ReadGlobalVar | ReadLocalVar | Pick => {
+            Fixed { pop: 0, push: 1 }
+        }
+        Pop | SetGlobalVar | SetLocalVar | GotoIfTrue | GotoIfFalse | Throw | Switch => {
+            Fixed { pop: 1, push: 0 }
+        }
+        Len | GetProperty => Fixed { pop: 1, push: 1 },
+        SwapLast => Fixed { pop: 2, push: 2 },
+        SetProperty => Fixed { pop: 3, push: 0 },
+        Goto | PushHandler | PopHandler | Exit | Return | Swap | Rotate => {
+            Fixed { pop: 0, push: 0 }
+        }
+        CallNative | CallFunction | TailCall | BeginForEach | ForEach | Closure | SetUpvalue
+        | ReadUpvalue | RegisterUpvalue | CloseUpvalue | Yield | ClearStack | NthRow
+        | AppendTable | PopTable => StackEffect::Opaque,
+    }
+}
+
+/// Checks that `program`'s bytecode never pops more values than are statically known to be on the
+/// stack, modeled as JVM-style abstract interpretation: a worklist of `(offset, depth entering
+/// it)` pairs, starting at `(0, 0)`, propagating `depth - pop + push` to the fall-through
+/// successor and, for `Goto`/`GotoIfTrue`/`GotoIfFalse`/`PushHandler`, to the jump/handler target
+/// too. Two paths reaching the same offset must agree on depth, or this reports
+/// [`VerifyError::StackImbalance`] - the same check a join point gets in a JVM-style verifier.
+///
+/// [`StackEffect::Opaque`] instructions (variable-arity calls, table iteration, upvalues, ...)
+/// leave the depth past them untracked rather than guessed at, so this only catches underflow and
+/// join mismatches within the straight-line, fixed-effect regions between them - it is not a
+/// guarantee that every underflow is caught, only that every one it reports is real.
+pub fn verify_stack_depth(program: &CaoCompiledProgram) -> Result<(), VerifyError> {
+    let bytecode = &program.bytecode;
+    let len = bytecode.len();
+
+    let mut depths: BTreeMap<u32, Option<u32>> = BTreeMap::new();
+    let mut queue = VecDeque::new();
+    depths.insert(0, Some(0));
+    queue.push_back(0u32);
+
+    while let Some(offset) = queue.pop_front() {
+        if offset as usize >= len {
+            continue;
+        }
+        let depth = *depths.get(&offset).expect("only enqueued once seeded");
+
+        let instr = Instruction::try_from_primitive(bytecode[offset as usize]).map_err(|_| {
+            VerifyError::InvalidInstruction {
+                offset,
+                byte: bytecode[offset as usize],
+            }
+        })?;
+        let span = instr.span();
+
+        let next_depth = match (depth, stack_effect(instr)) {
+            (None, _) | (_, StackEffect::Opaque) => None,
+            (Some(depth), StackEffect::Fixed { pop, push }) => {
+                if depth < pop {
+                    return Err(VerifyError::StackImbalance {
+                        offset,
+                        expected: pop,
+                        found: depth,
+                    });
+                }
+                Some(depth - pop + push)
+            }
+        };
+
+        let is_jump = matches!(instr, Instruction::Goto | Instruction::GotoIfTrue | Instruction::GotoIfFalse);
+        let is_terminal = matches!(instr, Instruction::Exit | Instruction::Return | Instruction::Throw);
+
+        if is_jump || instr == Instruction::PushHandler {
+            let (_, target) = read_from_bytes::<i32>(&bytecode[offset as usize + 1..])
+                .ok_or(VerifyError::TruncatedOperands { offset })?;
+            if target >= 0 {
+                // For `GotoIfTrue`/`GotoIfFalse` this is depth *after* popping the condition;
+                // for `Goto`/`PushHandler` (both pop 0, push 0) it's the same as `depth`.
+                schedule(&mut depths, &mut queue, target as u32, next_depth, offset)?;
+            }
+        }
+
+        if !is_terminal && instr != Instruction::Goto {
+            schedule(&mut depths, &mut queue, offset + span as u32, next_depth, offset)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Records `depth` as the stack depth entering `target`, enqueuing it for the first time or
+/// verifying it agrees with whatever depth already reached it.
+fn schedule(
+    depths: &mut BTreeMap<u32, Option<u32>>,
+    queue: &mut VecDeque<u32>,
+    target: u32,
+    depth: Option<u32>,
+    offset: u32,
+) -> Result<(), VerifyError> {
+    match depths.get(&target) {
+        None => {
+            depths.insert(target, depth);
+            queue.push_back(target);
+        }
+        Some(existing) => {
+            if let (Some(existing), Some(depth)) = (*existing, depth) {
+                if existing != depth {
+                    return Err(VerifyError::StackImbalance {
+                        offset,
+                        expected: existing,
+                        found: depth,
+                    });
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bytecode::write_to_vec;
+
+    fn program_with(bytecode: Vec<u8>) -> CaoCompiledProgram {
+        CaoCompiledProgram {
+            bytecode,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn accepts_well_formed_bytecode() {
+        let mut bytecode = Vec::new();
+        bytecode.push(Instruction::ScalarInt as u8);
+        write_to_vec(1i64, &mut bytecode);
+        bytecode.push(Instruction::ScalarInt as u8);
+        write_to_vec(2i64, &mut bytecode);
+        bytecode.push(Instruction::Add as u8);
+        bytecode.push(Instruction::Exit as u8);
+
+        assert_eq!(verify(&program_with(bytecode)), Ok(()));
+    }
+
+    #[test]
+    fn rejects_unknown_opcodes() {
+        let program = program_with(vec![0xff]);
+
+        assert_eq!(
+            verify(&program),
+            Err(VerifyError::InvalidInstruction {
+                offset: 0,
+                byte: 0xff
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_truncated_operands() {
+        // `ScalarInt` needs 8 trailing bytes for its `I64` operand; give it none.
+        let program = program_with(vec![Instruction::ScalarInt as u8]);
+
+        assert_eq!(
+            verify(&program),
+            Err(VerifyError::TruncatedOperands { offset: 0 })
+        );
+    }
+
+    #[test]
+    fn accepts_a_jump_that_lands_on_an_instruction_boundary() {
+        let mut bytecode = Vec::new();
+        bytecode.push(Instruction::Goto as u8);
+        write_to_vec(5i32, &mut bytecode);
+        bytecode.push(Instruction::Exit as u8);
+
+        assert_eq!(verify(&program_with(bytecode)), Ok(()));
+    }
+
+    #[test]
+    fn rejects_a_jump_into_the_middle_of_an_instruction() {
+        let mut bytecode = Vec::new();
+        bytecode.push(Instruction::Goto as u8);
+        write_to_vec(2i32, &mut bytecode); // lands inside this `Goto`'s own operand bytes
+        bytecode.push(Instruction::Exit as u8);
+
+        assert_eq!(
+            verify(&program_with(bytecode)),
+            Err(VerifyError::InvalidJumpTarget {
+                offset: 0,
+                target: 2
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_a_negative_jump_target() {
+        let mut bytecode = Vec::new();
+        bytecode.push(Instruction::Goto as u8);
+        write_to_vec(-1i32, &mut bytecode);
+        bytecode.push(Instruction::Exit as u8);
+
+        assert_eq!(
+            verify(&program_with(bytecode)),
+            Err(VerifyError::InvalidJumpTarget {
+                offset: 0,
+                target: -1
+            })
+        );
+    }
+
+    #[test]
+    fn accepts_balanced_arithmetic() {
+        let mut bytecode = Vec::new();
+        bytecode.push(Instruction::ScalarInt as u8);
+        write_to_vec(1i64, &mut bytecode);
+        bytecode.push(Instruction::ScalarInt as u8);
+        write_to_vec(2i64, &mut bytecode);
+        bytecode.push(Instruction::Add as u8);
+        bytecode.push(Instruction::Pop as u8);
+        bytecode.push(Instruction::Exit as u8);
+
+        assert_eq!(verify_stack_depth(&program_with(bytecode)), Ok(()));
+    }
+
+    #[test]
+    fn rejects_popping_below_zero() {
+        let mut bytecode = Vec::new();
+        bytecode.push(Instruction::ScalarInt as u8);
+        write_to_vec(1i64, &mut bytecode);
+        // `Add` needs 2 values; only 1 was ever pushed.
+        bytecode.push(Instruction::Add as u8);
+        bytecode.push(Instruction::Exit as u8);
+
+        assert_eq!(
+            verify_stack_depth(&program_with(bytecode)),
+            Err(VerifyError::StackImbalance {
+                offset: 9,
+                expected: 2,
+                found: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_branches_that_disagree_on_depth() {
+        // GotoIfFalse(target=one of two branches): the true-branch pushes a value before falling
+        // through to `target`, the false-branch jumps straight there with nothing pushed.
+        let mut bytecode = Vec::new();
+        bytecode.push(Instruction::ScalarInt as u8); // 0: condition
+        write_to_vec(1i64, &mut bytecode);
+        bytecode.push(Instruction::GotoIfFalse as u8); // 9
+        let goto_if_false_operand = bytecode.len();
+        write_to_vec(0i32, &mut bytecode); // patched below
+        bytecode.push(Instruction::ScalarInt as u8); // true branch: pushes one value
+        write_to_vec(1i64, &mut bytecode);
+        let join = bytecode.len() as i32;
+        bytecode[goto_if_false_operand..goto_if_false_operand + 4]
+            .copy_from_slice(&join.to_le_bytes());
+        bytecode.push(Instruction::Pop as u8); // join: only valid if exactly one value is present
+        bytecode.push(Instruction::Exit as u8);
+
+        assert_eq!(
+            verify_stack_depth(&program_with(bytecode)),
+            Err(VerifyError::StackImbalance {
+                offset: join as u32,
+                expected: 1,
+                found: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn skips_tracking_past_opaque_instructions() {
+        let mut bytecode = Vec::new();
+        bytecode.push(Instruction::ClearStack as u8);
+        bytecode.push(Instruction::Pop as u8);
+        bytecode.push(Instruction::Exit as u8);
+
+        // `ClearStack`'s real effect isn't modeled, so the `Pop` right after it is never checked
+        // for underflow - this is the documented limitation, not a false positive.
+        assert_eq!(verify_stack_depth(&program_with(bytecode)), Ok(()));
+    }
+}
@@ -3,27 +3,214 @@
 //! Interprets the compiled output produced by the Cao-Lang compiler
 mod instr_execution;
 pub mod runtime;
+pub mod snapshot;
 
 #[cfg(test)]
 mod tests;
 
 use self::runtime::{
-    cao_lang_object::{CaoLangObjectBody, ObjectGcGuard},
-    CallFrame,
+    cao_lang_function::CaoLangUpvalue,
+    cao_lang_iterator::CaoLangIterator,
+    cao_lang_object::{CaoLangObject, CaoLangObjectBody, ObjectGcGuard},
+    CallFrame, HandlerFrame,
 };
 use crate::{
-    collections::handle_table::{Handle, HandleTable},
+    alloc_crate::{boxed::Box, rc::Rc, sync::Arc, vec::Vec},
+    collections::{
+        handle_table::{Handle, HandleTable},
+        hash_map::CaoHashMap,
+    },
     instruction::Instruction,
     prelude::*,
     stdlib,
     value::Value,
-    vm::runtime::cao_lang_function::CaoLangClosure,
     VariableId,
 };
+use core::{
+    any::Any, marker::PhantomData, mem::size_of, ops::DerefMut, pin::Pin, ptr::NonNull,
+    str::FromStr,
+};
+use num_enum::TryFromPrimitive;
 use runtime::RuntimeData;
-use std::{mem::transmute, ops::DerefMut, pin::Pin, str::FromStr};
 use tracing::debug;
 
+/// Live state of a [`Vm`] paused mid-run, captured at the instruction boundary where the pause
+/// happened. Owns the full [`RuntimeData`] - value stack, call stack, globals and the object
+/// heap - so anything the paused program still references survives the pause; [`Vm::resume`]
+/// splices it back into a running `Vm` and continues from `instr_ptr`.
+pub struct Suspended {
+    runtime_data: Pin<Box<RuntimeData>>,
+    instr_ptr: usize,
+    max_instr: u64,
+    /// Opaque payload the suspending native function attached via [`Vm::suspend`]; round-tripped
+    /// back to the host unchanged, the VM never inspects it. `Box::new(())` when the pause
+    /// instead came from [`Vm::run_resumable`]/[`Vm::resume`] exhausting their instruction
+    /// budget (see [`RunOutcome::Yielded`]) rather than from an explicit [`Vm::suspend`] call.
+    pub payload: Box<dyn Any>,
+}
+
+impl Suspended {
+    /// The bytecode offset execution will resume from when this is passed back to [`Vm::resume`].
+    pub fn instr_ptr(&self) -> usize {
+        self.instr_ptr
+    }
+
+    /// The source card execution was paused on, resolved through `program`'s trace map - the same
+    /// `(bytecode offset) -> Trace` lookup [`Vm::_run`] consults to build an [`ExecutionError`]'s
+    /// backtrace (see `test_trace_entry`). A front-end driving [`Vm::run_resumable`]/
+    /// [`Vm::resume`] tick-by-tick can use this to highlight the currently executing card between
+    /// ticks. `None` if `program` has no trace entry for this offset (e.g. `debug-info` stripped).
+    pub fn current_card_index(&self, program: &CaoCompiledProgram) -> Option<Trace> {
+        program.trace.get(&(self.instr_ptr as u32)).cloned()
+    }
+
+    /// The value [`Instruction::Yield`] popped off the stack before suspending, if this pause
+    /// came from that instruction rather than an explicit [`Vm::suspend`] call with some other
+    /// payload type, or an exhausted instruction budget (see [`RunOutcome::Yielded`]).
+    pub fn yielded_value(&self) -> Option<Value> {
+        self.payload.downcast_ref::<Value>().copied()
+    }
+}
+
+/// Outcome of [`Vm::run_resumable`]/[`Vm::resume`]: either the program ran to completion, or it
+/// was paused before finishing - either a native function asked to pause it with [`Vm::suspend`],
+/// or the call's instruction budget (`max_instr`, or fuel - see [`Vm::set_fuel`]) ran out. Either
+/// way, [`Vm::resume`] continues it from exactly where it left off; a scheduler can use the
+/// budget-exhaustion case to bound how much of a long-running script runs per tick without
+/// losing its stack in between.
+pub enum RunOutcome {
+    /// The last value left on the stack when the program finished (`Nil` if nothing was left).
+    Finished(Value),
+    Yielded(Suspended),
+}
+
+/// Default call-stack depth before a call aborts with
+/// [`ExecutionErrorPayload::CallStackOverflow`], mirroring wasmi's default recursion guard.
+const DEFAULT_CALL_STACK_LIMIT: usize = 16 * 1024;
+
+/// Byte budget the default value stack capacity is derived from (see [`DEFAULT_STACK_LIMIT`]),
+/// mirroring wasmi's `DEFAULT_VALUE_STACK_LIMIT`.
+const DEFAULT_STACK_BYTES: usize = 1024 * 1024;
+
+/// Default value stack depth before a push aborts with
+/// [`ExecutionErrorPayload::Stackoverflow`].
+const DEFAULT_STACK_LIMIT: usize = DEFAULT_STACK_BYTES / size_of::<Value>();
+
+/// A host-defined handler for an opcode registered via [`Vm::register_instruction`], so embedders
+/// can extend the instruction set without editing [`Vm::_run`]'s dispatch loop.
+///
+/// `operands` is the remainder of the bytecode after the opcode byte; the handler must advance
+/// `instr_ptr` past whatever operand bytes it consumes itself (the dispatch loop only advances it
+/// past the opcode), the same contract the built-in instructions follow.
+pub trait InstructionHandler<Aux> {
+    fn call(
+        &self,
+        vm: &mut Vm<Aux>,
+        instr_ptr: &mut usize,
+        operands: &[u8],
+    ) -> Result<(), ExecutionErrorPayload>;
+}
+
+impl<Aux, F> InstructionHandler<Aux> for F
+where
+    F: Fn(&mut Vm<Aux>, &mut usize, &[u8]) -> Result<(), ExecutionErrorPayload>,
+{
+    fn call(
+        &self,
+        vm: &mut Vm<Aux>,
+        instr_ptr: &mut usize,
+        operands: &[u8],
+    ) -> Result<(), ExecutionErrorPayload> {
+        self(vm, instr_ptr, operands)
+    }
+}
+
+/// Watches execution without patching the interpreter - install one with
+/// [`Vm::with_observer`] to build a step debugger, a sampling profiler, or opcode/line coverage
+/// for cao-lang programs. Every method is a no-op by default, so an observer only needs to
+/// override the hooks it actually cares about.
+pub trait RuntimeObserver<Aux> {
+    /// Called from [`instr_execution::instr_call_function`] right after a new [`CallFrame`] is
+    /// pushed for `label`, before execution jumps to its first instruction.
+    fn observe_enter_frame(&mut self, _vm: &Vm<Aux>, _label: Handle, _arity: u32) {}
+    /// Called from [`instr_execution::instr_return`] right before the popped frame's
+    /// `return_value` is pushed back onto the caller's stack.
+    fn observe_exit_frame(&mut self, _vm: &Vm<Aux>, _return_value: Value) {}
+    /// Called from [`instr_execution::call_native`] right before `name` (registered under
+    /// `handle`) runs.
+    fn observe_native_call(&mut self, _vm: &Vm<Aux>, _handle: Handle, _name: &str) {}
+    /// Called from the main dispatch loop right before `op` (at `instr_ptr`) executes.
+    fn observe_execute_op(&mut self, _vm: &Vm<Aux>, _op: Instruction, _instr_ptr: usize) {}
+}
+
+/// Returned by [`Debugger::on_step`] to steer the dispatch loop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepAction {
+    /// Run the instruction normally.
+    Continue,
+    /// Pause before running the instruction, the same way [`Vm::suspend`]/an exhausted
+    /// instruction budget does - see [`ExecutionErrorPayload::Paused`] and
+    /// [`Vm::run_resumable`]/[`Vm::resume`].
+    Pause,
+    /// Unwind the run with [`ExecutionErrorPayload::DebuggerAbort`]. Unlike `Pause`, this isn't
+    /// resumable.
+    Abort,
+}
+
+/// Consulted at the top of every dispatch-loop iteration, right before the instruction at
+/// `instr_ptr` runs - install one with [`Vm::with_debugger`] to build a single-step or
+/// breakpoint-driven front-end over the same loop [`Vm::run`]/[`Vm::run_resumable`] use, instead
+/// of a second interpreter. Pairs with [`Vm::add_breakpoint`], which pauses the run the same way
+/// regardless of whether a debugger is installed - a bare breakpoint needs no `Debugger` impl at
+/// all. Use [`Vm::value_stack`]/[`Vm::call_stack_depth`] from [`Debugger::on_step`] to inspect the
+/// paused state.
+pub trait Debugger<Aux> {
+    /// Decide what the dispatch loop should do next. The default implementation always continues,
+    /// so a front-end that only wants [`Vm::add_breakpoint`]'s offset-based pausing doesn't need
+    /// to implement this trait at all.
+    fn on_step(&mut self, _vm: &Vm<Aux>, _instr_ptr: u32, _instr: Instruction) -> StepAction {
+        StepAction::Continue
+    }
+}
+
+/// Per-instruction weight [`Vm::max_instr`]/[`Vm::remaining_iters`] is debited by, instead of a
+/// flat `1`. The default (installed unless overridden via [`Vm::with_instruction_cost`]) charges
+/// `1` for most instructions and more for ones that do real allocation/lookup work under the
+/// hood - [`Instruction::NthRow`]/[`Instruction::AppendTable`]/[`Instruction::StringLiteral`]/
+/// [`Instruction::CallNative`] - so a script can't buy disproportionate amounts of real work for
+/// the same iteration budget a cheap `Add`/`Less` spends.
+pub fn default_instruction_cost(instr: Instruction) -> u64 {
+    match instr {
+        Instruction::NthRow
+        | Instruction::AppendTable
+        | Instruction::StringLiteral
+        | Instruction::CallNative => 4,
+        _ => 1,
+    }
+}
+
+/// Governs how `Add`/`Sub`/`Mul` resolve integer overflow - install one via
+/// [`Vm::with_arithmetic_mode`]. Division/modulo by zero already surfaces as the catchable
+/// [`ExecutionErrorPayload::DivideByZero`] regardless of mode, since none of these have a sane
+/// wrap/saturate/promote substitute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArithmeticMode {
+    /// Overflow promotes the result to a heap
+    /// [`runtime::cao_lang_object::CaoLangObjectBody::BigInt`] - see
+    /// [`Vm::resolve_int_overflow`]. The default: most embedders running trusted game scripts would
+    /// rather pay a rare allocation than have resource math quietly misbehave.
+    #[default]
+    Promoting,
+    /// Overflow is a catchable [`ExecutionErrorPayload::invalid_argument`] naming the operands,
+    /// instead of the result silently being computed a different way. For scripts doing resource
+    /// math where an out-of-range result means a bug, not a number to represent.
+    Checked,
+    /// Overflow clamps to `i64::MIN`/`i64::MAX`.
+    Saturating,
+    /// Overflow wraps around `i64`'s range (two's complement).
+    Wrapping,
+}
+
 /// Cao-Lang bytecode interpreter.
 /// `Aux` is an auxiliary runtime structure passed to custom functions.
 pub struct Vm<'a, Aux = ()>
@@ -31,14 +218,54 @@ where
     Aux: 'a,
 {
     pub auxiliary_data: Aux,
-    /// Number of instructions `run` will execute before returning Timeout
+    /// Instruction budget `run` will spend before returning `Timeout`, debited per instruction by
+    /// [`default_instruction_cost`] (or an override installed via [`Vm::with_instruction_cost`])
+    /// rather than flat `1`s - see [`Vm::remaining_budget`].
     pub max_instr: u64,
     pub remaining_iters: u64,
 
     pub runtime_data: Pin<Box<RuntimeData>>,
 
     callables: HandleTable<Procedure<Aux>>,
-    _m: std::marker::PhantomData<&'a ()>,
+    /// Host-registered opcode handlers, keyed by opcode byte; see [`Vm::register_instruction`].
+    custom_instructions: CaoHashMap<u8, Rc<dyn InstructionHandler<Aux>>>,
+    /// Host payload stashed by [`Vm::suspend`], consumed by [`Vm::run_resumable`]/[`Vm::resume`]
+    /// when they catch the `Suspended` signal it raises.
+    pending_suspend: Option<Box<dyn Any>>,
+    /// Installed by [`Vm::with_trap_handler`]; called with the [`ExecutionError`] that's about to
+    /// unwind [`Vm::run`]/[`Vm::run_resumable`]/[`Vm::resume`], giving the host a chance to log or
+    /// inspect it (`ExecutionError::trace` carries the faulting [`crate::compiled_program::Trace`]
+    /// chain) before the error reaches the caller. Purely observational: there's no way to resume
+    /// past the fault from here, since most of `_run`'s instruction handlers bail out via `?` the
+    /// moment something goes wrong, with no saved resumption point the way [`Instruction::Yield`]
+    /// deliberately leaves one - unlike a `Yield`, a fault can happen mid-instruction.
+    trap_handler: Option<Box<dyn FnMut(&ExecutionError) + 'a>>,
+    /// Installed by [`Vm::with_observer`]; see [`RuntimeObserver`]. Checked with a plain `Option`
+    /// fast-path at every hook site, so the hot path costs nothing when no observer is installed.
+    observer: Option<Box<dyn RuntimeObserver<Aux> + 'a>>,
+    /// Installed by [`Vm::with_debugger`]; see [`Debugger`]. `None` means every step just
+    /// consults [`Vm::breakpoints`].
+    debugger: Option<Box<dyn Debugger<Aux> + 'a>>,
+    /// Bytecode offsets registered via [`Vm::add_breakpoint`]; checked at every dispatch-loop
+    /// iteration regardless of whether a [`Debugger`] is installed.
+    breakpoints: CaoHashMap<u32, ()>,
+    /// Set by [`Vm::step`] for the duration of a single-instruction run: counts down the
+    /// dispatch-loop iterations still allowed to `Continue` before [`Vm::check_debugger`] forces
+    /// a `Pause`, so stepping stops after exactly one instruction regardless of where a jump/call
+    /// sends `instr_ptr` next.
+    step_budget: Option<u32>,
+    /// Charges [`Vm::remaining_iters`] per instruction; overridden via
+    /// [`Vm::with_instruction_cost`], [`default_instruction_cost`] otherwise.
+    instruction_cost: Box<dyn Fn(Instruction) -> u64 + 'a>,
+    /// How `Add`/`Sub`/`Mul` resolve integer overflow; overridden via
+    /// [`Vm::with_arithmetic_mode`], [`ArithmeticMode::Promoting`] (the default) otherwise.
+    arithmetic_mode: ArithmeticMode,
+    /// Cooperative cancellation flag, polled periodically by [`Vm::run_once`]'s dispatch loop.
+    /// Cloned out via [`Vm::interrupt_handle`] so a watchdog thread (a timer, a signal handler)
+    /// can stop a long-running `Vm` without it having to cooperate at the script level the way
+    /// `max_instr`/fuel do.
+    interrupt: Arc<core::sync::atomic::AtomicBool>,
+    _m: PhantomData<&'a ()>,
 }
 
 impl<'a, Aux> Vm<'a, Aux> {
@@ -49,15 +276,141 @@ impl<'a, Aux> Vm<'a, Aux> {
         let mut vm = Self {
             auxiliary_data,
             callables: HandleTable::default(),
-            runtime_data: RuntimeData::new(400 * 1024, 256, 256)?,
+            custom_instructions: CaoHashMap::default(),
+            runtime_data: RuntimeData::new(
+                400 * 1024,
+                DEFAULT_STACK_LIMIT,
+                DEFAULT_CALL_STACK_LIMIT,
+            )?,
             max_instr: 1000,
             remaining_iters: 0,
+            pending_suspend: None,
+            trap_handler: None,
+            observer: None,
+            debugger: None,
+            breakpoints: CaoHashMap::default(),
+            step_budget: None,
+            instruction_cost: Box::new(default_instruction_cost),
+            arithmetic_mode: ArithmeticMode::default(),
+            interrupt: Arc::new(core::sync::atomic::AtomicBool::new(false)),
             _m: Default::default(),
         };
         vm.register_native_stdlib().unwrap();
         Ok(vm)
     }
 
+    /// Like [`Vm::new`], but services the heap via `backend` (e.g. a fixed arena) instead of the
+    /// global allocator. See [`crate::alloc::AllocBackend`].
+    pub fn new_with_allocator(
+        auxiliary_data: Aux,
+        backend: crate::alloc::AllocBackend,
+    ) -> Result<Self, ExecutionErrorPayload>
+    where
+        Aux: 'static,
+    {
+        let mut vm = Self {
+            auxiliary_data,
+            callables: HandleTable::default(),
+            custom_instructions: CaoHashMap::default(),
+            runtime_data: RuntimeData::with_backend(
+                400 * 1024,
+                DEFAULT_STACK_LIMIT,
+                DEFAULT_CALL_STACK_LIMIT,
+                backend,
+            )?,
+            max_instr: 1000,
+            remaining_iters: 0,
+            pending_suspend: None,
+            trap_handler: None,
+            observer: None,
+            debugger: None,
+            breakpoints: CaoHashMap::default(),
+            step_budget: None,
+            instruction_cost: Box::new(default_instruction_cost),
+            arithmetic_mode: ArithmeticMode::default(),
+            interrupt: Arc::new(core::sync::atomic::AtomicBool::new(false)),
+            _m: Default::default(),
+        };
+        vm.register_native_stdlib().unwrap();
+        Ok(vm)
+    }
+
+    /// Snapshot this VM's live state (value stack, variables, call frames and the entire object
+    /// heap) at `instr_ptr` into a serializable blob, so it can be exported and later restored
+    /// via [`Vm::restore`].
+    ///
+    /// Native callables are not part of the snapshot: `restore` re-registers the standard
+    /// library the same way `new` does, so only user-visible program state round-trips.
+    pub fn snapshot(&mut self, instr_ptr: usize) -> self::snapshot::VmSnapshot {
+        // open upvalues point into the live value stack, which won't exist once this VM is torn
+        // down, so close them all before flattening the heap
+        let _ = instr_execution::close_upvalues(self);
+        self.runtime_data.snapshot(instr_ptr)
+    }
+
+    /// Reconstruct a `Vm` from a [`self::snapshot::VmSnapshot`] taken by [`Vm::snapshot`],
+    /// returning it along with the instruction pointer execution should resume at.
+    pub fn restore(
+        snapshot: &self::snapshot::VmSnapshot,
+        auxiliary_data: Aux,
+    ) -> Result<(Self, usize), self::snapshot::RestoreBytesError>
+    where
+        Aux: 'static,
+    {
+        let (runtime_data, instr_ptr) = RuntimeData::restore(
+            snapshot,
+            400 * 1024,
+            DEFAULT_STACK_LIMIT,
+            DEFAULT_CALL_STACK_LIMIT,
+        )?;
+        let mut vm = Self {
+            auxiliary_data,
+            callables: HandleTable::default(),
+            custom_instructions: CaoHashMap::default(),
+            runtime_data,
+            max_instr: 1000,
+            remaining_iters: 0,
+            pending_suspend: None,
+            trap_handler: None,
+            observer: None,
+            debugger: None,
+            breakpoints: CaoHashMap::default(),
+            step_budget: None,
+            instruction_cost: Box::new(default_instruction_cost),
+            arithmetic_mode: ArithmeticMode::default(),
+            interrupt: Arc::new(core::sync::atomic::AtomicBool::new(false)),
+            _m: Default::default(),
+        };
+        vm.register_native_stdlib().unwrap();
+        Ok((vm, instr_ptr))
+    }
+
+    /// Like [`Vm::snapshot`], but encodes straight to a flat `Vec<u8>` via `bincode` - the same
+    /// encoding [`crate::compiled_program::flat`] uses - instead of handing back a typed
+    /// [`self::snapshot::VmSnapshot`]. Convenient for a host that just wants to park the blob on
+    /// disk or ship it to another process, rather than inspect the snapshot's shape.
+    #[cfg(feature = "serde")]
+    pub fn snapshot_bytes(&mut self, instr_ptr: usize) -> Vec<u8> {
+        bincode::serialize(&self.snapshot(instr_ptr)).expect("VmSnapshot is always serializable")
+    }
+
+    /// Reconstruct a `Vm` from a blob produced by [`Vm::snapshot_bytes`], returning it along
+    /// with the instruction pointer execution should resume at. See [`Vm::restore`] for what is
+    /// and isn't part of the snapshot - notably, `current_program` isn't, and must be re-supplied
+    /// by the caller.
+    #[cfg(feature = "serde")]
+    pub fn restore_bytes(
+        bytes: &[u8],
+        auxiliary_data: Aux,
+    ) -> Result<(Self, usize), self::snapshot::RestoreBytesError>
+    where
+        Aux: 'static,
+    {
+        let snapshot: self::snapshot::VmSnapshot = bincode::deserialize(bytes)
+            .map_err(|_| self::snapshot::RestoreBytesError::BadBlob)?;
+        Ok(Self::restore(&snapshot, auxiliary_data)?)
+    }
+
     pub fn register_native_stdlib(&mut self) -> Result<(), ExecutionErrorPayload>
     where
         Aux: 'static,
@@ -65,9 +418,73 @@ impl<'a, Aux> Vm<'a, Aux> {
         self._register_native_function("__min", into_f2(stdlib::native_minmax::<Aux, true>))?;
         self._register_native_function("__max", into_f2(stdlib::native_minmax::<Aux, false>))?;
         self._register_native_function("__sort", into_f2(stdlib::native_sorted::<Aux>))?;
+        self._register_native_function("__sort_cmp", into_f2(stdlib::native_sort_cmp::<Aux>))?;
+        self._register_native_function("__to_array", into_f1(stdlib::native_to_array::<Aux>))?;
+        self._register_native_function("__collect", into_f1(stdlib::native_collect::<Aux>))?;
+        self._register_native_function("__lazy_map", into_f2(stdlib::native_lazy_map::<Aux>))?;
+        self._register_native_function(
+            "__lazy_filter",
+            into_f2(stdlib::native_lazy_filter::<Aux>),
+        )?;
+        self._register_native_function("__lazy_range", into_f2(stdlib::native_lazy_range::<Aux>))?;
+        self._register_native_function(
+            "__lazy_generate",
+            into_f1(stdlib::native_lazy_generate::<Aux>),
+        )?;
+        self._register_native_function("__sin", into_f1(stdlib::math::native_sin::<Aux>))?;
+        self._register_native_function("__cos", into_f1(stdlib::math::native_cos::<Aux>))?;
+        self._register_native_function("__tan", into_f1(stdlib::math::native_tan::<Aux>))?;
+        self._register_native_function(
+            "__string_upper",
+            into_f1(stdlib::string::native_upper::<Aux>),
+        )?;
+        self._register_native_function(
+            "__string_lower",
+            into_f1(stdlib::string::native_lower::<Aux>),
+        )?;
+        self._register_native_function(
+            "__string_concat",
+            into_f2(stdlib::string::native_concat::<Aux>),
+        )?;
+        self._register_native_function(
+            "__string_split",
+            into_f2(stdlib::string::native_split::<Aux>),
+        )?;
+        self._register_native_function(
+            "__string_parse_int",
+            into_f1(stdlib::string::native_parse_int::<Aux>),
+        )?;
+        self._register_native_function(
+            "__string_substr",
+            into_f3(stdlib::string::native_substr::<Aux>),
+        )?;
+        self._register_native_function("__table_keys", into_f1(stdlib::table::native_keys::<Aux>))?;
+        self._register_native_function(
+            "__table_values",
+            into_f1(stdlib::table::native_values::<Aux>),
+        )?;
+        self._register_native_function(
+            "__table_remove",
+            into_f2(stdlib::table::native_remove::<Aux>),
+        )?;
+        self._register_native_function("__table_has", into_f2(stdlib::table::native_has::<Aux>))?;
+        #[cfg(feature = "std")]
+        self._register_native_function("__time", stdlib::sys::native_time::<Aux>)?;
         Ok(())
     }
 
+    /// Explicitly (re-)registers the standard library's natives on this `Vm`. [`Vm::new`] already
+    /// does this for you, so this is mostly useful as a chainable opt-in when a `Vm` was built via
+    /// some other path, e.g. after disabling the default-on `stdlib` feature for a minimal
+    /// sandbox, or following up on a [`Vm::register_native_stdlib`] call that errored.
+    pub fn with_std(mut self) -> Result<Self, ExecutionErrorPayload>
+    where
+        Aux: 'static,
+    {
+        self.register_native_stdlib()?;
+        Ok(self)
+    }
+
     /// Inserts the given value into the VM's runtime memory. Returns the inserted [[Value]]
     pub fn insert_value(&mut self, value: &OwnedValue) -> Result<Value, ExecutionErrorPayload> {
         let res = match value {
@@ -76,6 +493,10 @@ impl<'a, Aux> Vm<'a, Aux> {
                 let res = self.init_string(s.as_str())?;
                 Value::Object(res.0)
             }
+            OwnedValue::Bytes(b) => {
+                let res = self.init_bytes(b.as_slice())?;
+                Value::Object(res.0)
+            }
             OwnedValue::Table(o) => {
                 let mut res = self.init_table()?;
                 let table = res.deref_mut().as_table_mut().unwrap();
@@ -86,6 +507,10 @@ impl<'a, Aux> Vm<'a, Aux> {
                 }
                 Value::Object(res.0)
             }
+            OwnedValue::BigInt(x) => {
+                let res = self.runtime_data.init_bigint(*x)?;
+                Value::Object(res.0)
+            }
             OwnedValue::Integer(x) => Value::Integer(*x),
             OwnedValue::Real(x) => Value::Real(*x),
         };
@@ -103,23 +528,219 @@ impl<'a, Aux> Vm<'a, Aux> {
         &mut self,
         handle: Handle,
         arity: u32,
+        max_locals: u32,
     ) -> Result<ObjectGcGuard, ExecutionErrorPayload> {
-        self.runtime_data.init_function(handle, arity)
+        self.runtime_data.init_function(handle, arity, max_locals)
     }
 
     pub fn init_closure(
         &mut self,
         handle: Handle,
         arity: u32,
+        max_locals: u32,
     ) -> Result<ObjectGcGuard, ExecutionErrorPayload> {
-        self.runtime_data.init_closure(handle, arity)
+        self.runtime_data.init_closure(handle, arity, max_locals)
     }
 
     pub fn init_upvalue(
         &mut self,
-        location: *mut Value,
+        state: CaoLangUpvalue,
     ) -> Result<ObjectGcGuard, ExecutionErrorPayload> {
-        self.runtime_data.init_upvalue(location)
+        self.runtime_data.init_upvalue(state)
+    }
+
+    /// Initializes a new lazy iterator owned by this VM instance
+    pub fn init_iterator(
+        &mut self,
+        body: CaoLangIterator,
+    ) -> Result<ObjectGcGuard, ExecutionErrorPayload> {
+        self.runtime_data.init_iterator(body)
+    }
+
+    /// Accept a `Table`, a `String`, or an `Iterator` value and return a pullable iterator
+    /// object, wrapping a bare `Table`/`String` in a fresh [`CaoLangIterator::Table`]/
+    /// [`CaoLangIterator::Chars`] cursor if necessary.
+    pub fn to_iterator(
+        &mut self,
+        value: Value,
+    ) -> Result<NonNull<CaoLangObject>, ExecutionErrorPayload> {
+        match value {
+            Value::Object(o) => unsafe {
+                match &o.as_ref().body {
+                    CaoLangObjectBody::Iterator(_) => Ok(o),
+                    CaoLangObjectBody::Table(_) => {
+                        let guard =
+                            self.init_iterator(CaoLangIterator::Table { source: o, next: 0 })?;
+                        Ok(guard.into_inner())
+                    }
+                    CaoLangObjectBody::String(_) => {
+                        let guard =
+                            self.init_iterator(CaoLangIterator::Chars { source: o, next: 0 })?;
+                        Ok(guard.into_inner())
+                    }
+                    _ => Err(ExecutionErrorPayload::invalid_argument(
+                        "Expected Table, String or Iterator",
+                    )),
+                }
+            },
+            _ => Err(ExecutionErrorPayload::invalid_argument(
+                "Expected Table, String or Iterator",
+            )),
+        }
+    }
+
+    /// Pull the next `(index, key, value)` triple out of the iterator object `ptr`, or `None`
+    /// once it's exhausted. `Map`/`Filter` recursively pull from their `source` and apply
+    /// `callback` before yielding, so calling this on the head of an adapter chain drives the
+    /// whole chain lazily, one row at a time.
+    pub fn iterator_pull(
+        &mut self,
+        ptr: NonNull<CaoLangObject>,
+    ) -> Result<Option<(i64, Value, Value)>, ExecutionErrorPayload> {
+        enum Pull {
+            Table {
+                source: NonNull<CaoLangObject>,
+                idx: usize,
+            },
+            Chars {
+                source: NonNull<CaoLangObject>,
+                idx: usize,
+            },
+            Range {
+                idx: i64,
+                end: i64,
+            },
+            Native {
+                callback: Value,
+                idx: i64,
+            },
+            Map {
+                source: NonNull<CaoLangObject>,
+                callback: Value,
+            },
+            Filter {
+                source: NonNull<CaoLangObject>,
+                callback: Value,
+            },
+        }
+
+        // `ptr` is a raw heap pointer independent of `&mut self`, so it's fine to read its
+        // current state before recursing/calling back into the VM below (which may itself
+        // mutate this same object, e.g. bumping a `Table` cursor further down the chain).
+        let plan = unsafe {
+            let obj = &mut *ptr.as_ptr();
+            match obj
+                .as_iterator_mut()
+                .ok_or_else(|| ExecutionErrorPayload::invalid_argument("Expected Iterator"))?
+            {
+                CaoLangIterator::Table { source, next } => {
+                    let idx = *next;
+                    *next += 1;
+                    Pull::Table {
+                        source: *source,
+                        idx,
+                    }
+                }
+                // unlike `Table`'s positional cursor, `next` here is a *byte* offset: advancing
+                // it by a fixed amount would either skip multi-byte chars or panic slicing mid-
+                // char, so it's only updated below, once we know how wide the decoded char was.
+                CaoLangIterator::Chars { source, next } => Pull::Chars {
+                    source: *source,
+                    idx: *next,
+                },
+                CaoLangIterator::Range { next, end } => {
+                    let idx = *next;
+                    *next += 1;
+                    Pull::Range { idx, end: *end }
+                }
+                CaoLangIterator::Native { callback, next } => {
+                    let idx = *next;
+                    *next += 1;
+                    Pull::Native {
+                        callback: *callback,
+                        idx,
+                    }
+                }
+                CaoLangIterator::Map { source, callback } => Pull::Map {
+                    source: *source,
+                    callback: *callback,
+                },
+                CaoLangIterator::Filter { source, callback } => Pull::Filter {
+                    source: *source,
+                    callback: *callback,
+                },
+            }
+        };
+
+        match plan {
+            Pull::Table { source, idx } => {
+                let table = self.get_table(Value::Object(source))?;
+                if idx >= table.len() {
+                    return Ok(None);
+                }
+                let key = table.nth_key(idx);
+                let val = table.get(&key).copied().unwrap_or(Value::Nil);
+                Ok(Some((idx as i64, key, val)))
+            }
+            Pull::Chars { source, idx } => {
+                let s = self.get_str(Value::Object(source))?;
+                match s.get(idx..).and_then(|rest| rest.chars().next()) {
+                    None => Ok(None),
+                    Some(c) => {
+                        // advance the cursor past this char now that we know its byte width, so
+                        // the next pull picks up right where this one left off in O(1)
+                        let new_next = idx + c.len_utf8();
+                        unsafe {
+                            let obj = &mut *ptr.as_ptr();
+                            match obj.as_iterator_mut().expect("still an Iterator") {
+                                CaoLangIterator::Chars { next, .. } => *next = new_next,
+                                _ => unreachable!("still a Chars iterator"),
+                            }
+                        }
+                        let mut buf = [0u8; 4];
+                        let guard = self.init_string(c.encode_utf8(&mut buf))?;
+                        let val = Value::Object(guard.into_inner());
+                        Ok(Some((idx as i64, Value::Integer(idx as i64), val)))
+                    }
+                }
+            }
+            Pull::Range { idx, end } => {
+                if idx >= end {
+                    return Ok(None);
+                }
+                Ok(Some((idx, Value::Integer(idx), Value::Integer(idx))))
+            }
+            Pull::Native { callback, idx } => {
+                let val = self.call_value(callback, &[Value::Integer(idx)])?;
+                if matches!(val, Value::Nil) {
+                    Ok(None)
+                } else {
+                    Ok(Some((idx, Value::Integer(idx), val)))
+                }
+            }
+            Pull::Map { source, callback } => match self.iterator_pull(source)? {
+                None => Ok(None),
+                Some((i, k, v)) => {
+                    self.stack_push(v)?;
+                    self.stack_push(k)?;
+                    let v = self.run_function(callback)?;
+                    Ok(Some((i, k, v)))
+                }
+            },
+            Pull::Filter { source, callback } => loop {
+                match self.iterator_pull(source)? {
+                    None => return Ok(None),
+                    Some((i, k, v)) => {
+                        self.stack_push(v)?;
+                        self.stack_push(k)?;
+                        let keep = self.run_function(callback)?;
+                        if keep.as_bool() {
+                            return Ok(Some((i, k, v)));
+                        }
+                    }
+                }
+            },
+        }
     }
 
     pub fn clear(&mut self) {
@@ -142,6 +763,253 @@ impl<'a, Aux> Vm<'a, Aux> {
         self
     }
 
+    /// Set the instruction budget `run_once` counts down while executing, independent of
+    /// `max_instr`/`Timeout` - see [`ExecutionErrorPayload::OutOfFuel`]. `None` (the default)
+    /// means unmetered. Takes effect from the next instruction dispatched onward, so it's safe to
+    /// call before [`Vm::resume`] to top up a [`Suspended`] run's budget before continuing it.
+    pub fn set_fuel(&mut self, fuel: Option<u64>) {
+        self.runtime_data.fuel = fuel;
+    }
+
+    /// Add `amount` to the current fuel budget, treating an unset budget as `0` first. Handy for
+    /// topping up a paused run by a fixed per-tick allowance rather than recomputing the total.
+    pub fn add_fuel(&mut self, amount: u64) {
+        self.runtime_data.fuel = Some(self.runtime_data.fuel.unwrap_or(0) + amount);
+    }
+
+    /// A handle another thread can use to stop this `Vm` cooperatively: storing `true` into it
+    /// (e.g. from a watchdog timer or a signal handler) makes the next interrupt check in
+    /// [`Vm::run_once`]'s dispatch loop fail with [`ExecutionErrorPayload::Interrupted`] instead
+    /// of running to completion or `max_instr`/fuel exhaustion. Unlike those budgets, this doesn't
+    /// require the script to cooperate by running a bounded number of instructions - the host can
+    /// ask at any time, independent of how `max_instr` is configured.
+    pub fn interrupt_handle(&self) -> Arc<core::sync::atomic::AtomicBool> {
+        self.interrupt.clone()
+    }
+
+    /// Total instructions run against the fuel budget since it was last set via [`Vm::set_fuel`].
+    pub fn fuel_consumed(&self) -> u64 {
+        self.runtime_data.fuel_consumed
+    }
+
+    /// Seed the RNG backing [`Instruction::Random`]/[`Instruction::DiceRoll`], so the program
+    /// rolls the same sequence on every run - useful for reproducing a replay. `0` is treated as
+    /// "use the default seed" instead, since xorshift64 can never advance past a zero state.
+    #[must_use]
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.runtime_data.seed_rng(seed);
+        self
+    }
+
+    /// Install `handler` to observe faults: it's called with the [`ExecutionError`] of any
+    /// [`Vm::run`]/[`Vm::run_resumable`]/[`Vm::resume`] call that's about to fail, just before the
+    /// error reaches the caller. Useful for logging a fault with its bytecode position and
+    /// [`ExecutionError::trace`] in one place instead of wrapping every call site.
+    #[must_use]
+    pub fn with_trap_handler(mut self, handler: impl FnMut(&ExecutionError) + 'a) -> Self {
+        self.trap_handler = Some(Box::new(handler));
+        self
+    }
+
+    /// Calls the installed [`Vm::with_trap_handler`] handler, if any, with `err`.
+    fn fire_trap_handler(&mut self, err: &ExecutionError) {
+        if let Some(mut handler) = self.trap_handler.take() {
+            handler(err);
+            self.trap_handler = Some(handler);
+        }
+    }
+
+    /// Install `observer` to watch execution; see [`RuntimeObserver`] for the available hooks.
+    #[must_use]
+    pub fn with_observer(mut self, observer: impl RuntimeObserver<Aux> + 'a) -> Self {
+        self.observer = Some(Box::new(observer));
+        self
+    }
+
+    /// Calls the installed [`Vm::with_observer`]'s [`RuntimeObserver::observe_enter_frame`], if any.
+    pub(crate) fn fire_observe_enter_frame(&mut self, label: Handle, arity: u32) {
+        if let Some(mut observer) = self.observer.take() {
+            observer.observe_enter_frame(self, label, arity);
+            self.observer = Some(observer);
+        }
+    }
+
+    /// Calls the installed [`Vm::with_observer`]'s [`RuntimeObserver::observe_exit_frame`], if any.
+    pub(crate) fn fire_observe_exit_frame(&mut self, return_value: Value) {
+        if let Some(mut observer) = self.observer.take() {
+            observer.observe_exit_frame(self, return_value);
+            self.observer = Some(observer);
+        }
+    }
+
+    /// Calls the installed [`Vm::with_observer`]'s [`RuntimeObserver::observe_native_call`], if any.
+    pub(crate) fn fire_observe_native_call(&mut self, handle: Handle, name: &str) {
+        if let Some(mut observer) = self.observer.take() {
+            observer.observe_native_call(self, handle, name);
+            self.observer = Some(observer);
+        }
+    }
+
+    /// Calls the installed [`Vm::with_observer`]'s [`RuntimeObserver::observe_execute_op`], if any.
+    pub(crate) fn fire_observe_execute_op(&mut self, op: Instruction, instr_ptr: usize) {
+        if let Some(mut observer) = self.observer.take() {
+            observer.observe_execute_op(self, op, instr_ptr);
+            self.observer = Some(observer);
+        }
+    }
+
+    /// Install `debugger` to steer execution; see [`Debugger`] for the available hook.
+    #[must_use]
+    pub fn with_debugger(mut self, debugger: impl Debugger<Aux> + 'a) -> Self {
+        self.debugger = Some(Box::new(debugger));
+        self
+    }
+
+    /// Register a breakpoint at `instr_ptr` (a bytecode offset - see
+    /// [`crate::compiled_program::CaoCompiledProgram::disassemble`]/[`Suspended::instr_ptr`]):
+    /// the dispatch loop pauses with [`ExecutionErrorPayload::Paused`] right before executing the
+    /// instruction there, the same way [`Vm::suspend`] pauses a resumable run - see
+    /// [`Vm::run_resumable`]/[`Vm::resume`]. No [`Debugger`] needs to be installed for this to
+    /// take effect.
+    pub fn add_breakpoint(&mut self, instr_ptr: u32) -> Result<(), ExecutionErrorPayload> {
+        self.breakpoints
+            .insert(instr_ptr, ())
+            .map_err(|_| ExecutionErrorPayload::OutOfMemory)
+            .map(drop)
+    }
+
+    /// Remove a breakpoint previously added via [`Vm::add_breakpoint`], if any.
+    pub fn remove_breakpoint(&mut self, instr_ptr: u32) {
+        self.breakpoints.remove(&instr_ptr);
+    }
+
+    /// Remove every breakpoint added via [`Vm::add_breakpoint`].
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    /// Execute exactly one instruction starting at `*instr_ptr`, advancing it to wherever
+    /// execution lands next (the following offset for most instructions, a jump target for
+    /// `Goto`/`GotoIfTrue`/`GotoIfFalse`/`ForEach`, the callee's first instruction for
+    /// `CallFunction`...), and return the offset and decoded [`Instruction`] that just ran.
+    ///
+    /// Built on the same pause mechanism as [`Vm::add_breakpoint`]/[`Vm::with_debugger`]: arms a
+    /// one-shot budget that lets [`Vm::check_debugger`] continue through the current instruction
+    /// but forces a `Pause` before the next one, so this stops after exactly one instruction no
+    /// matter where a jump/call sends `instr_ptr`. Requires a program to already be running -
+    /// i.e. call this on a `Vm` paused via [`Vm::run_resumable`]/[`Vm::resume`] hitting
+    /// [`Vm::add_breakpoint`], or a previous `step` call.
+    pub fn step(&mut self, instr_ptr: &mut usize) -> ExecutionResult<(u32, Instruction)> {
+        let program: &CaoCompiledProgram = unsafe {
+            let program = self.runtime_data.current_program;
+            assert!(!program.is_null(), "Vm::step called with no program running");
+            &*program
+        };
+        let offset = *instr_ptr as u32;
+        let raw_instr = program.bytecode[*instr_ptr];
+        let instr = Instruction::try_from_primitive(raw_instr).map_err(|_| {
+            ExecutionError::new(
+                ExecutionErrorPayload::InvalidInstruction(raw_instr),
+                Default::default(),
+            )
+        })?;
+
+        self.step_budget = Some(1);
+        let result = self._run(instr_ptr);
+        self.step_budget = None;
+
+        match result {
+            Ok(()) => Ok((offset, instr)),
+            Err(err) if matches!(err.payload, ExecutionErrorPayload::Paused) => Ok((offset, instr)),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Override the per-instruction weight [`Vm::max_instr`] is debited by (default
+    /// [`default_instruction_cost`]) - install a table that debits more for whichever
+    /// operations the embedder considers heavy in their own workload.
+    #[must_use]
+    pub fn with_instruction_cost(mut self, cost_fn: impl Fn(Instruction) -> u64 + 'a) -> Self {
+        self.instruction_cost = Box::new(cost_fn);
+        self
+    }
+
+    /// Instruction budget left before the run hits [`ExecutionErrorPayload::Timeout`], debited
+    /// per instruction by the installed [`Vm::with_instruction_cost`] (or
+    /// [`default_instruction_cost`]) rather than a flat `1` - see [`Vm::max_instr`].
+    pub fn remaining_budget(&self) -> u64 {
+        self.remaining_iters
+    }
+
+    /// Override how `Add`/`Sub`/`Mul` resolve integer overflow (default
+    /// [`ArithmeticMode::Promoting`]) - pick [`ArithmeticMode::Checked`] to fail fast on scripts
+    /// doing resource math, or `Saturating`/`Wrapping` for bounded `i64` semantics without the
+    /// heap `BigInt` allocation `Promoting` pays for.
+    #[must_use]
+    pub fn with_arithmetic_mode(mut self, mode: ArithmeticMode) -> Self {
+        self.arithmetic_mode = mode;
+        self
+    }
+
+    /// The live value stack, for a [`Debugger`]/[`RuntimeObserver`] to inspect while paused or
+    /// mid-step.
+    pub fn value_stack(&self) -> &crate::collections::value_stack::ValueStack {
+        &self.runtime_data.value_stack
+    }
+
+    /// Number of call frames (cao-lang function calls, not the value stack) currently active.
+    pub fn call_stack_depth(&self) -> usize {
+        self.runtime_data.call_stack.len()
+    }
+
+    /// Consults [`Vm::breakpoints`], [`Vm::step`]'s budget and the installed [`Vm::with_debugger`]
+    /// debugger (if any) for the instruction at `instr_ptr`, combining all three into a single
+    /// [`StepAction`] - either deciding `Abort` wins over `Pause`, which wins over `Continue`.
+    fn check_debugger(&mut self, instr: Instruction, instr_ptr: u32) -> StepAction {
+        let mut action = if self.breakpoints.contains(&instr_ptr) {
+            StepAction::Pause
+        } else {
+            StepAction::Continue
+        };
+        if let Some(budget) = self.step_budget {
+            if budget == 0 {
+                action = StepAction::Pause;
+            } else {
+                self.step_budget = Some(budget - 1);
+            }
+        }
+        if let Some(mut debugger) = self.debugger.take() {
+            let from_debugger = debugger.on_step(self, instr_ptr, instr);
+            self.debugger = Some(debugger);
+            action = match (action, from_debugger) {
+                (StepAction::Abort, _) | (_, StepAction::Abort) => StepAction::Abort,
+                (StepAction::Pause, _) | (_, StepAction::Pause) => StepAction::Pause,
+                _ => StepAction::Continue,
+            };
+        }
+        action
+    }
+
+    /// Override the call-stack depth (default [`DEFAULT_CALL_STACK_LIMIT`]) a `CallFunction`
+    /// may recurse to before failing with [`ExecutionErrorPayload::CallStackOverflow`]. Only
+    /// meant to be called right after construction, before any program has run - it drops the
+    /// VM's current state the same way [`RuntimeData::set_call_stack_limit`] does.
+    #[must_use]
+    pub fn with_call_stack_limit(mut self, limit: usize) -> Self {
+        self.runtime_data.set_call_stack_limit(limit);
+        self
+    }
+
+    /// Override the value stack depth (default [`DEFAULT_STACK_LIMIT`]) before a push fails with
+    /// [`ExecutionErrorPayload::Stackoverflow`]. Only meant to be called right after
+    /// construction, before any program has run - it drops the VM's current state the same way
+    /// [`RuntimeData::set_stack_limit`] does.
+    #[must_use]
+    pub fn with_stack_limit(mut self, limit: usize) -> Self {
+        self.runtime_data.set_stack_limit(limit);
+        self
+    }
+
     #[inline]
     pub fn get_aux(&self) -> &Aux {
         &self.auxiliary_data
@@ -192,22 +1060,55 @@ impl<'a, Aux> Vm<'a, Aux> {
                 key,
                 Procedure {
                     name: name.0,
-                    fun: std::rc::Rc::new(f),
+                    fun: Rc::new(f),
                 },
             )
             .map_err(|_| ExecutionErrorPayload::OutOfMemory)
             .map(drop)
     }
 
+    /// Register `handler` to run whenever the interpreter's dispatch loop meets `opcode`, letting
+    /// embedders extend the instruction set without editing [`Vm::_run`]. `opcode` must fall
+    /// outside the built-in [`Instruction`] range - registering over a built-in opcode would
+    /// shadow core interpreter behavior, so it's rejected instead.
+    pub fn register_instruction<H>(
+        &mut self,
+        opcode: u8,
+        handler: H,
+    ) -> Result<(), ExecutionErrorPayload>
+    where
+        H: InstructionHandler<Aux> + 'static,
+    {
+        if Instruction::try_from_primitive(opcode).is_ok() {
+            return Err(ExecutionErrorPayload::invalid_argument(format!(
+                "opcode {opcode} is already a built-in instruction"
+            )));
+        }
+        self.custom_instructions
+            .insert(opcode, Rc::new(handler))
+            .map_err(|_| ExecutionErrorPayload::OutOfMemory)?;
+        Ok(())
+    }
+
+    /// Pushes `value` onto the operand stack. The stack is a GC root scanned once at the start of
+    /// a cycle (see [`RuntimeData::gc_mark_roots`](crate::vm::runtime::RuntimeData::gc_mark_roots)),
+    /// so a value pushed mid-cycle - e.g. a freshly allocated object whose
+    /// [`ObjectGcGuard`](crate::vm::runtime::cao_lang_object::ObjectGcGuard) is about to drop back
+    /// to `White` - would never get traced and could be swept as garbage while still live on the
+    /// stack. Run it through
+    /// [`RuntimeData::gc_root_write_barrier`](crate::vm::runtime::RuntimeData::gc_root_write_barrier)
+    /// first, same as `instr_set_var`/`write_upvalue` do for the other root slots.
     #[inline]
     pub fn stack_push<S>(&mut self, value: S) -> Result<(), ExecutionErrorPayload>
     where
         S: Into<Value>,
     {
+        let value = value.into();
+        self.runtime_data.gc_root_write_barrier(value);
         self.runtime_data
             .value_stack
-            .push(value.into())
-            .map_err(|_| ExecutionErrorPayload::Stackoverflow)?;
+            .push(value)
+            .map_err(ExecutionErrorPayload::from)?;
         Ok(())
     }
 
@@ -216,6 +1117,20 @@ impl<'a, Aux> Vm<'a, Aux> {
         self.runtime_data.value_stack.pop()
     }
 
+    /// Like [`Vm::stack_pop`], but fails with [`ExecutionErrorPayload::StackUnderflow`] instead of
+    /// silently returning a stale value when the stack is empty. Compiled bytecode never needs
+    /// this - [`crate::verify::verify_stack_depth`] already proves every pop it emits is balanced
+    /// against a preceding push - but a native function (see [`crate::traits::VmFunction`]) can be
+    /// registered with more parameters than the caller actually pushed for it, so its argument
+    /// pops go through this checked path instead.
+    #[inline]
+    pub fn stack_pop_checked(&mut self) -> Result<Value, ExecutionErrorPayload> {
+        if self.runtime_data.value_stack.is_empty() {
+            return Err(ExecutionErrorPayload::StackUnderflow);
+        }
+        Ok(self.runtime_data.value_stack.pop())
+    }
+
     pub fn get_table(&self, value: Value) -> Result<&CaoLangTable, ExecutionErrorPayload> {
         let res = match value {
             Value::Object(o) => unsafe {
@@ -246,6 +1161,21 @@ impl<'a, Aux> Vm<'a, Aux> {
         Ok(res)
     }
 
+    pub fn get_str(&self, value: Value) -> Result<&str, ExecutionErrorPayload> {
+        let res = match value {
+            Value::Object(o) => unsafe {
+                o.as_ref()
+                    .as_str()
+                    .ok_or_else(|| ExecutionErrorPayload::invalid_argument("Expected String"))?
+            },
+            _ => {
+                debug!("Got {:?} instead of object", value);
+                return Err(ExecutionErrorPayload::invalid_argument("Expected String"));
+            }
+        };
+        Ok(res)
+    }
+
     /// Initializes a new FieldTable in this VM instance
     #[inline]
     pub fn init_table(&mut self) -> Result<ObjectGcGuard, ExecutionErrorPayload> {
@@ -257,6 +1187,78 @@ impl<'a, Aux> Vm<'a, Aux> {
         self.runtime_data.init_string(payload)
     }
 
+    /// Initializes a new opaque byte buffer owned by this VM instance. Unlike [`Vm::init_string`],
+    /// `payload` need not be valid UTF-8.
+    pub fn init_bytes(&mut self, payload: &[u8]) -> Result<ObjectGcGuard, ExecutionErrorPayload> {
+        self.runtime_data.init_bytes(payload)
+    }
+
+    /// Frees `obj` immediately instead of waiting for [`Vm::gc_step`] (or the allocator's
+    /// automatic collection trigger) to prove it unreachable - e.g. a native function that just
+    /// allocated a scratch object it already knows it's done with. Delegates to
+    /// [`RuntimeData::free_object`], which every `Instruction` handler that frees objects already
+    /// calls directly; this is a convenience wrapper for host code the same way [`Vm::init_table`]
+    /// wraps [`RuntimeData::init_table`].
+    ///
+    /// Only call this when nothing else holds a `Value::Object` pointing at `obj` - freeing a
+    /// still-reachable object leaves stale pointers that corrupt the heap the next time
+    /// something reads through them.
+    pub fn free_object(&mut self, obj: NonNull<CaoLangObject>) {
+        self.runtime_data.free_object(obj)
+    }
+
+    /// Drives the incremental garbage collector forward by roughly `budget` units of work
+    /// (starting a new cycle if the collector is idle), then invokes the finalizer of, and
+    /// frees, every object that was found unreachable during this or a previous cycle.
+    ///
+    /// `budget` bounds the amount of marking/sweeping work done per call, so embedders can call
+    /// this e.g. once per frame to keep collection pauses small, instead of relying solely on
+    /// the automatic trigger in [`crate::alloc::CaoLangAllocator::alloc`] (which always runs a
+    /// full, unbounded collection). Returns `true` if a mark-and-sweep cycle completed during
+    /// this call.
+    pub fn gc_step(&mut self, budget: usize) -> Result<bool, ExecutionErrorPayload> {
+        let cycle_done = self.runtime_data.gc_work(budget);
+        self.drain_pending_finalizers()?;
+        Ok(cycle_done)
+    }
+
+    /// Runs a full, synchronous mark-and-sweep collection (see [`RuntimeData::gc`]) to
+    /// completion, then invokes the finalizer of, and frees, every object found unreachable -
+    /// the non-incremental counterpart of [`Vm::gc_step`], for embedders that would rather pay
+    /// one collection pause up front than budget it across several calls.
+    /// [`crate::alloc::CaoLangAllocator::alloc`]'s own automatic trigger (tunable via
+    /// `self.runtime_data.set_gc_threshold`) already calls this kind of cycle on the allocator's
+    /// behalf; this is for a host that wants to force one, e.g. between levels/ticks of a game
+    /// loop.
+    pub fn collect_garbage(&mut self) -> Result<(), ExecutionErrorPayload> {
+        self.runtime_data.gc();
+        self.drain_pending_finalizers()
+    }
+
+    /// Invokes the registered finalizer (if any) of every object the collector found
+    /// unreachable, then frees it. `ObjectGcGuard`s taken out by a finalizer protect whatever
+    /// they allocate, so those new objects survive to be collected on a later cycle.
+    fn drain_pending_finalizers(&mut self) -> Result<(), ExecutionErrorPayload> {
+        while let Some(obj) = self.runtime_data.pending_finalizers.pop() {
+            let finalizer = unsafe { obj.as_ref().finalizer };
+            if let Some(handle) = finalizer {
+                if let Some(procedure) = self.callables.get(handle) {
+                    let procedure = procedure.clone();
+                    self.stack_push(Value::Object(obj))?;
+                    procedure
+                        .fun
+                        .call(self)
+                        .map_err(|err| ExecutionErrorPayload::TaskFailure {
+                            name: procedure.name().to_string(),
+                            error: Box::new(err),
+                        })?;
+                }
+            }
+            self.runtime_data.free_object(obj);
+        }
+        Ok(())
+    }
+
     /// Panics if no current program has been set
     pub fn run_function(&mut self, val: Value) -> Result<Value, ExecutionErrorPayload> {
         let Value::Object(obj) = val else {
@@ -264,32 +1266,20 @@ impl<'a, Aux> Vm<'a, Aux> {
                 "Expected a function object argument",
             ));
         };
-        let arity;
-        let label;
-        let mut closure: *mut CaoLangClosure = std::ptr::null_mut();
-        unsafe {
-            match &obj.as_ref().body {
-                CaoLangObjectBody::Closure(c) => {
-                    arity = c.function.arity;
-                    label = c.function.handle;
-                    closure = (c as *const CaoLangClosure).cast_mut();
-                }
-                CaoLangObjectBody::Function(f) => {
-                    arity = f.arity;
-                    label = f.handle;
-                }
-                CaoLangObjectBody::NativeFunction(f) => {
-                    instr_execution::call_native(self, f.handle)?;
+        let (arity, max_locals, label, closure) =
+            match instr_execution::resolve_callable(unsafe { obj.as_ref() }, "run_function call")?
+            {
+                instr_execution::Callee::Native(handle) => {
+                    instr_execution::call_native(self, handle)?;
                     return Ok(self.stack_pop());
                 }
-                _ => {
-                    return Err(ExecutionErrorPayload::invalid_argument(format!(
-                        "Expected a function object argument, instead got: {}",
-                        obj.as_ref().type_name()
-                    )));
-                }
-            }
-        }
+                instr_execution::Callee::Lane {
+                    arity,
+                    max_locals,
+                    label,
+                    closure,
+                } => (arity, max_locals, label, closure),
+            };
         let program: &CaoCompiledProgram = unsafe {
             let program = self.runtime_data.current_program;
             assert!(!program.is_null());
@@ -307,6 +1297,10 @@ impl<'a, Aux> Vm<'a, Aux> {
         let end = program.bytecode.len() - 1;
         let len = self.runtime_data.value_stack.len() as u32;
 
+        let stack_offset = len
+            .checked_sub(arity)
+            .ok_or(ExecutionErrorPayload::MissingArgument)?;
+
         // a function call needs 2 stack frames, 1 for the current scope, another for the return
         // address
         //
@@ -318,15 +1312,18 @@ impl<'a, Aux> Vm<'a, Aux> {
                 .push(CallFrame {
                     src_instr_ptr: src,
                     dst_instr_ptr: end as u32,
-                    stack_offset: len
-                        .checked_sub(arity)
-                        .ok_or(ExecutionErrorPayload::MissingArgument)?
-                        as u32,
+                    stack_offset,
                     closure,
                 })
-                .map_err(|_| ExecutionErrorPayload::CallStackOverflow)?;
+                .map_err(ExecutionErrorPayload::from)?;
         }
 
+        // reserve the callee's locals (arguments included) in one bulk extension
+        self.runtime_data
+            .value_stack
+            .reserve_locals(stack_offset as usize, max_locals as usize)
+            .map_err(ExecutionErrorPayload::from)?;
+
         let mut instr_ptr = src as usize;
         self._run(&mut instr_ptr).map_err(|err| err.payload)?;
         // pop the trap callframe
@@ -334,7 +1331,32 @@ impl<'a, Aux> Vm<'a, Aux> {
         Ok(self.stack_pop())
     }
 
-    fn _run(&mut self, instr_ptr: &mut usize) -> ExecutionResult<()> {
+    /// Calls `callable` with `args` and runs it to completion, returning its result - the
+    /// callback equivalent of a compiled `Card::Call`. Lets a native [`crate::procedures::Procedure`]
+    /// (see [`crate::traits::VmFunction`]) invoke a cao-lang value passed to it as an argument,
+    /// e.g. a comparator `sort` takes or a predicate `filter`/`map` takes. A thin wrapper over
+    /// [`Vm::run_function`] - see that for the re-entrant call-frame mechanics - that pushes
+    /// `args` first so the caller doesn't have to leave them on the stack itself. Re-entering the
+    /// interpreter this way is bounded by the same call-stack limit as any other call, so a
+    /// runaway recursive callback still fails with [`ExecutionErrorPayload::CallStackOverflow`]
+    /// instead of overflowing the real Rust stack.
+    pub fn call_value(
+        &mut self,
+        callable: Value,
+        args: &[Value],
+    ) -> Result<Value, ExecutionErrorPayload> {
+        for arg in args {
+            self.stack_push(*arg)?;
+        }
+        self.run_function(callable)
+    }
+
+    /// The flat instruction-dispatch loop: decodes and executes one [`Instruction`] at a time
+    /// from `instr_ptr` onward, returning as soon as either the program falls off the end of its
+    /// bytecode (only ever a bug in the compiler) or a single instruction faults. Doesn't know
+    /// about `Card::Try` handlers beyond installing/uninstalling/consulting them - unwinding
+    /// across a fault is [`Vm::_run`]'s job, which wraps this.
+    fn run_once(&mut self, instr_ptr: &mut usize) -> ExecutionResult<()> {
         let program: &CaoCompiledProgram = unsafe {
             let program = self.runtime_data.current_program;
             assert!(!program.is_null());
@@ -361,19 +1383,90 @@ impl<'a, Aux> Vm<'a, Aux> {
             };
 
         while *instr_ptr < len {
-            remaining_iters -= 1;
+            // Polling the atomic every instruction would add an uncontested-but-still-real load
+            // to the hot path; checking every 256 instead keeps a requested interrupt's latency
+            // negligible without paying for it on every dispatch.
+            if remaining_iters & 0xff == 0
+                && self.interrupt.load(core::sync::atomic::Ordering::Relaxed)
+            {
+                return Err(payload_to_error(
+                    ExecutionErrorPayload::Interrupted,
+                    *instr_ptr,
+                    &self.runtime_data.call_stack,
+                ));
+            }
+            if let Some(fuel) = self.runtime_data.fuel {
+                if fuel == 0 {
+                    return Err(payload_to_error(
+                        ExecutionErrorPayload::OutOfFuel,
+                        *instr_ptr,
+                        &self.runtime_data.call_stack,
+                    ));
+                }
+                self.runtime_data.fuel = Some(fuel - 1);
+                self.runtime_data.fuel_consumed += 1;
+            }
+            let raw_instr: u8 = unsafe { *bytecode_ptr.add(*instr_ptr) };
+            let src_ptr = *instr_ptr;
+            // Anything outside the built-in `Instruction` range only ever comes from a
+            // host-registered [`Vm::register_instruction`] handler, which the cost table has no
+            // opinion on - charge it the same flat `1` a cheap built-in would cost.
+            let charged = Instruction::try_from_primitive(raw_instr)
+                .map(|instr| (self.instruction_cost)(instr))
+                .unwrap_or(1);
+            remaining_iters = remaining_iters.saturating_sub(charged);
             if remaining_iters == 0 {
                 return Err(payload_to_error(
-                    ExecutionErrorPayload::Timeout,
+                    ExecutionErrorPayload::Timeout { charged },
                     *instr_ptr,
                     &self.runtime_data.call_stack,
                 ));
             }
-            let instr: u8 = unsafe { *bytecode_ptr.add(*instr_ptr) };
-            let instr: Instruction = unsafe { transmute(instr) };
-            let src_ptr = *instr_ptr;
+            let instr: Instruction = match Instruction::try_from_primitive(raw_instr) {
+                Ok(instr) => instr,
+                Err(_) => {
+                    *instr_ptr += 1;
+                    let handler = self
+                        .custom_instructions
+                        .get(&raw_instr)
+                        .cloned()
+                        .ok_or_else(|| {
+                            payload_to_error(
+                                ExecutionErrorPayload::InvalidInstruction(raw_instr),
+                                *instr_ptr,
+                                &self.runtime_data.call_stack,
+                            )
+                        })?;
+                    handler
+                        .call(self, instr_ptr, &program.bytecode[*instr_ptr..])
+                        .map_err(|err| {
+                            payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
+                        })?;
+                    continue;
+                }
+            };
+            // Consult breakpoints/the installed debugger before this instruction has any effect,
+            // so a paused run resumes by re-running it rather than skipping past it.
+            match self.check_debugger(instr, src_ptr as u32) {
+                StepAction::Continue => {}
+                StepAction::Pause => {
+                    return Err(payload_to_error(
+                        ExecutionErrorPayload::Paused,
+                        src_ptr,
+                        &self.runtime_data.call_stack,
+                    ));
+                }
+                StepAction::Abort => {
+                    return Err(payload_to_error(
+                        ExecutionErrorPayload::DebuggerAbort,
+                        src_ptr,
+                        &self.runtime_data.call_stack,
+                    ));
+                }
+            }
             *instr_ptr += 1;
             debug!("Executing: {instr:?} instr_ptr: {instr_ptr}");
+            self.fire_observe_execute_op(instr, src_ptr);
             match instr {
                 Instruction::InitTable => {
                     let res = self.init_table().map_err(|err| {
@@ -408,6 +1501,12 @@ impl<'a, Aux> Vm<'a, Aux> {
                         .map_err(|err| {
                             payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
                         })?;
+                    if let Value::Object(obj) = instance {
+                        unsafe {
+                            self.runtime_data.memory.write_barrier(obj, key);
+                            self.runtime_data.memory.write_barrier(obj, value);
+                        }
+                    }
                 }
                 Instruction::BeginForEach => {
                     instr_execution::begin_for_each(self, &program.bytecode, instr_ptr).map_err(
@@ -450,6 +1549,28 @@ impl<'a, Aux> Vm<'a, Aux> {
                     self.stack_push(b).unwrap();
                     self.stack_push(a).unwrap();
                 }
+                Instruction::Pick => {
+                    let n: u32 =
+                        unsafe { instr_execution::decode_value(&program.bytecode, instr_ptr) };
+                    let val = self.runtime_data.value_stack.peek_last(n as usize);
+                    self.stack_push(val).map_err(|err| {
+                        payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
+                    })?;
+                }
+                Instruction::Swap => {
+                    let i: u32 =
+                        unsafe { instr_execution::decode_value(&program.bytecode, instr_ptr) };
+                    let j: u32 =
+                        unsafe { instr_execution::decode_value(&program.bytecode, instr_ptr) };
+                    self.runtime_data
+                        .value_stack
+                        .swap_top(i as usize, j as usize);
+                }
+                Instruction::Rotate => {
+                    let n: u32 =
+                        unsafe { instr_execution::decode_value(&program.bytecode, instr_ptr) };
+                    self.runtime_data.value_stack.rotate_top(n as usize);
+                }
                 Instruction::ScalarNil => self.stack_push(Value::Nil).map_err(|err| {
                     payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
                 })?,
@@ -497,6 +1618,11 @@ impl<'a, Aux> Vm<'a, Aux> {
                             payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
                         })?;
                 }
+                Instruction::TailCall => {
+                    instr_execution::instr_tail_call(src_ptr, instr_ptr, program, self).map_err(
+                        |err| payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack),
+                    )?;
+                }
                 Instruction::Return => {
                     instr_execution::instr_return(self, instr_ptr).map_err(|err| {
                         payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
@@ -525,7 +1651,7 @@ impl<'a, Aux> Vm<'a, Aux> {
                     self.runtime_data
                         .value_stack
                         .push(val)
-                        .map_err(|_| ExecutionErrorPayload::Stackoverflow)
+                        .map_err(ExecutionErrorPayload::from)
                         .map_err(|err| {
                             // free the object on Stackoverflow
                             self.runtime_data.free_object(obj.0);
@@ -537,8 +1663,10 @@ impl<'a, Aux> Vm<'a, Aux> {
                         unsafe { instr_execution::decode_value(&program.bytecode, instr_ptr) };
                     let arity: u32 =
                         unsafe { instr_execution::decode_value(&program.bytecode, instr_ptr) };
+                    let max_locals: u32 =
+                        unsafe { instr_execution::decode_value(&program.bytecode, instr_ptr) };
 
-                    let obj = self.init_function(hash, arity).map_err(|err| {
+                    let obj = self.init_function(hash, arity, max_locals).map_err(|err| {
                         payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
                     })?;
 
@@ -547,7 +1675,7 @@ impl<'a, Aux> Vm<'a, Aux> {
                     self.runtime_data
                         .value_stack
                         .push(val)
-                        .map_err(|_| ExecutionErrorPayload::Stackoverflow)
+                        .map_err(ExecutionErrorPayload::from)
                         .map_err(|err| {
                             // free the object on Stackoverflow
                             self.runtime_data.free_object(obj.0);
@@ -559,8 +1687,10 @@ impl<'a, Aux> Vm<'a, Aux> {
                         unsafe { instr_execution::decode_value(&program.bytecode, instr_ptr) };
                     let arity: u32 =
                         unsafe { instr_execution::decode_value(&program.bytecode, instr_ptr) };
+                    let max_locals: u32 =
+                        unsafe { instr_execution::decode_value(&program.bytecode, instr_ptr) };
 
-                    let obj = self.init_closure(hash, arity).map_err(|err| {
+                    let obj = self.init_closure(hash, arity, max_locals).map_err(|err| {
                         payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
                     })?;
 
@@ -569,7 +1699,7 @@ impl<'a, Aux> Vm<'a, Aux> {
                     self.runtime_data
                         .value_stack
                         .push(val)
-                        .map_err(|_| ExecutionErrorPayload::Stackoverflow)
+                        .map_err(ExecutionErrorPayload::from)
                         .map_err(|err| {
                             // free the object on Stackoverflow
                             self.runtime_data.free_object(obj.0);
@@ -582,7 +1712,7 @@ impl<'a, Aux> Vm<'a, Aux> {
                         .push(Value::Integer(unsafe {
                             instr_execution::decode_value(&program.bytecode, instr_ptr)
                         }))
-                        .map_err(|_| ExecutionErrorPayload::Stackoverflow)
+                        .map_err(ExecutionErrorPayload::from)
                         .map_err(|err| {
                             payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
                         })?;
@@ -593,7 +1723,7 @@ impl<'a, Aux> Vm<'a, Aux> {
                         .push(Value::Real(unsafe {
                             instr_execution::decode_value(&program.bytecode, instr_ptr)
                         }))
-                        .map_err(|_| ExecutionErrorPayload::Stackoverflow)
+                        .map_err(ExecutionErrorPayload::from)
                         .map_err(|err| {
                             payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
                         })?;
@@ -621,18 +1751,97 @@ impl<'a, Aux> Vm<'a, Aux> {
                     .map_err(|err| {
                         payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
                     })?,
-                Instruction::Add => self.binary_op(|a, b| a + b).map_err(|err| {
+                Instruction::Add => self
+                    .checked_arith_op(i64::checked_add, i128::checked_add, |a, b| a + b)
+                    .map_err(|err| {
+                        payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
+                    })?,
+                Instruction::Sub => self
+                    .checked_arith_op(i64::checked_sub, i128::checked_sub, |a, b| a - b)
+                    .map_err(|err| {
+                        payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
+                    })?,
+                Instruction::Mul => self
+                    .checked_arith_op(i64::checked_mul, i128::checked_mul, |a, b| a * b)
+                    .map_err(|err| {
+                        payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
+                    })?,
+                Instruction::Div => self.checked_div_op(|a, b| a / b).map_err(|err| {
+                    payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
+                })?,
+                Instruction::Mod => self.checked_div_op(|a, b| a % b).map_err(|err| {
+                    payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
+                })?,
+                Instruction::Pow => self.binary_arith_op(Value::pow).map_err(|err| {
+                    payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
+                })?,
+                Instruction::Min => self.binary_arith_op(Value::min).map_err(|err| {
+                    payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
+                })?,
+                Instruction::Max => self.binary_arith_op(Value::max).map_err(|err| {
+                    payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
+                })?,
+                Instruction::Random => self.random_op().map_err(|err| {
+                    payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
+                })?,
+                Instruction::DiceRoll => self.dice_roll_op().map_err(|err| {
+                    payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
+                })?,
+                Instruction::Switch => {
+                    instr_execution::instr_switch(self, instr_ptr, program).map_err(|err| {
+                        payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
+                    })?
+                }
+                Instruction::BitAnd => self.binary_op(|a, b| a & b).map_err(|err| {
                     payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
                 })?,
-                Instruction::Sub => self.binary_op(|a, b| a - b).map_err(|err| {
+                Instruction::BitOr => self.binary_op(|a, b| a | b).map_err(|err| {
                     payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
                 })?,
-                Instruction::Mul => self.binary_op(|a, b| a * b).map_err(|err| {
+                Instruction::BitXor => self.binary_op(|a, b| a ^ b).map_err(|err| {
                     payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
                 })?,
-                Instruction::Div => self.binary_op(|a, b| a / b).map_err(|err| {
+                Instruction::Shl => self.binary_op(|a, b| a << b).map_err(|err| {
                     payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
                 })?,
+                Instruction::Shr => self.binary_op(|a, b| a >> b).map_err(|err| {
+                    payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
+                })?,
+                Instruction::Neg => {
+                    let value = self.stack_pop();
+                    let value = -value;
+                    self.stack_push(value).map_err(|err| {
+                        payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
+                    })?;
+                }
+                Instruction::Abs => {
+                    let value = self.stack_pop();
+                    let value = value.abs();
+                    self.stack_push(value).map_err(|err| {
+                        payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
+                    })?;
+                }
+                Instruction::Floor => {
+                    let value = self.stack_pop();
+                    let value = value.floor();
+                    self.stack_push(value).map_err(|err| {
+                        payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
+                    })?;
+                }
+                Instruction::Ceil => {
+                    let value = self.stack_pop();
+                    let value = value.ceil();
+                    self.stack_push(value).map_err(|err| {
+                        payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
+                    })?;
+                }
+                Instruction::Round => {
+                    let value = self.stack_pop();
+                    let value = value.round();
+                    self.stack_push(value).map_err(|err| {
+                        payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
+                    })?;
+                }
                 Instruction::Equals => self.binary_op(|a, b| (a == b).into()).map_err(|err| {
                     payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
                 })?,
@@ -717,6 +1926,9 @@ impl<'a, Aux> Vm<'a, Aux> {
                     table.append(value).map_err(|err| {
                         payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
                     })?;
+                    if let Value::Object(obj) = instance {
+                        unsafe { self.runtime_data.memory.write_barrier(obj, value) };
+                    }
                 }
 
                 Instruction::PopTable => {
@@ -751,6 +1963,38 @@ impl<'a, Aux> Vm<'a, Aux> {
                         payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
                     })?;
                 }
+                Instruction::Yield => {
+                    let value = self.stack_pop();
+                    self.suspend(Box::new(value)).map_err(|err| {
+                        payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
+                    })?;
+                }
+                Instruction::PushHandler => {
+                    let target: i32 =
+                        unsafe { instr_execution::decode_value(&program.bytecode, instr_ptr) };
+                    debug_assert!(target >= 0);
+                    self.runtime_data.handler_stack.push(HandlerFrame {
+                        target: target as u32,
+                        value_stack_depth: self.runtime_data.value_stack.len(),
+                        call_stack_depth: self.runtime_data.call_stack.len(),
+                    });
+                }
+                Instruction::PopHandler => {
+                    self.runtime_data.handler_stack.pop();
+                }
+                Instruction::Throw => {
+                    let value = self.stack_pop();
+                    let caught = self.unwind_to_handler(value, instr_ptr).map_err(|err| {
+                        payload_to_error(err, *instr_ptr, &self.runtime_data.call_stack)
+                    })?;
+                    if !caught {
+                        return Err(payload_to_error(
+                            ExecutionErrorPayload::Unhandled(value),
+                            *instr_ptr,
+                            &self.runtime_data.call_stack,
+                        ));
+                    }
+                }
             }
             debug!("Stack: {}", self.runtime_data.value_stack);
         }
@@ -762,9 +2006,132 @@ impl<'a, Aux> Vm<'a, Aux> {
         ))
     }
 
+    /// Unwinds the value/call stacks back to the depths recorded when the nearest installed
+    /// `Card::Try` handler was pushed and resumes at its saved target with `value` on top of the
+    /// stack. Returns `false` without touching any state if `handler_stack` is empty, leaving the
+    /// fault to propagate to the caller.
+    fn unwind_to_handler(
+        &mut self,
+        value: Value,
+        instr_ptr: &mut usize,
+    ) -> Result<bool, ExecutionErrorPayload> {
+        let Some(handler) = self.runtime_data.handler_stack.pop() else {
+            return Ok(false);
+        };
+        self.runtime_data.call_stack.truncate(handler.call_stack_depth);
+        // Close upvalues for every local created between the `Try` and the throw site before
+        // dropping them, the same as a normal `scope_end`/`Return` would - otherwise a closure
+        // that captured one of them is left holding an `Open` upvalue pointing at a stack slot
+        // a later push will silently overwrite.
+        instr_execution::_close_upvalues(self, handler.value_stack_depth)?;
+        self.runtime_data
+            .value_stack
+            .clear_until(handler.value_stack_depth);
+        self.runtime_data.gc_root_write_barrier(value);
+        self.runtime_data.value_stack.push(value)?;
+        *instr_ptr = handler.target as usize;
+        Ok(true)
+    }
+
+    /// Builds the `{ kind, message, card_index, payload }` table a `Card::Try` handler sees for an
+    /// implicit runtime fault - `kind` is [`ExecutionErrorPayload::kind_name`], `message` its
+    /// `Display`, `card_index` the [`Trace`] of the deepest frame active when the fault was raised
+    /// (`Nil` if none was recorded), and `payload` the value carried by
+    /// [`ExecutionErrorPayload::Custom`] (`Nil` for every other variant, which doesn't carry one).
+    /// Falls back to `Value::Nil` if allocating the table itself fails, e.g. because the fault was
+    /// `OutOfMemory`.
+    fn make_error_value(
+        &mut self,
+        payload: &ExecutionErrorPayload,
+        site: Option<&Trace>,
+    ) -> Result<Value, ExecutionErrorPayload> {
+        let mut table = self.init_table()?;
+        let kind = self.init_string(payload.kind_name())?;
+        let message = self.init_string(&payload.to_string())?;
+        let card_index = match site {
+            Some(trace) => Value::Object(self.init_string(&trace.to_string())?.into_inner()),
+            None => Value::Nil,
+        };
+        let error_payload = match payload {
+            ExecutionErrorPayload::Custom { payload, .. } => *payload,
+            _ => Value::Nil,
+        };
+        let t = table.as_table_mut().unwrap();
+        t.insert(self.init_string("kind")?, kind)?;
+        t.insert(self.init_string("message")?, message)?;
+        t.insert(self.init_string("card_index")?, card_index)?;
+        t.insert(self.init_string("payload")?, error_payload)?;
+        Ok(Value::Object(table.into_inner()))
+    }
+
+    /// Public entry point for native functions (or other host code) that want to hand back a
+    /// structured, `Card::Try`-catchable error value of their own instead of (or in addition to)
+    /// returning `Err`: builds the same `{ kind, message, card_index, payload }` shape
+    /// [`Vm::make_error_value`] builds automatically for a built-in fault, with `kind` fixed to
+    /// `"Custom"` and no `card_index` (there's no active fault site to attribute it to - this
+    /// isn't unwinding anything, just constructing a value). Read `message`/`payload` back with
+    /// [`Vm::error_message`]/[`Vm::error_payload`].
+    pub fn make_error(&mut self, message: &str, payload: Value) -> Result<Value, ExecutionErrorPayload> {
+        self.make_error_value(&ExecutionErrorPayload::custom(message, payload), None)
+    }
+
+    /// Reads the `message` field back off a [`Vm::make_error`] (or auto-converted fault) table
+    /// value. `None` if `value` isn't such a table, e.g. because the script already stripped the
+    /// field or `value` was never an error in the first place.
+    pub fn error_message(&self, value: Value) -> Option<&str> {
+        let table = self.get_table(value).ok()?;
+        let message = *table.get("message")?;
+        self.get_str(message).ok()
+    }
+
+    /// Reads the `payload` field back off a [`Vm::make_error`] (or auto-converted fault) table
+    /// value. `None` if `value` isn't such a table; `Some(Value::Nil)` if the error simply didn't
+    /// carry one.
+    pub fn error_payload(&self, value: Value) -> Option<Value> {
+        let table = self.get_table(value).ok()?;
+        table.get("payload").copied()
+    }
+
+    /// Drives [`Vm::run_once`] to completion, catching any fault it raises that
+    /// [`ExecutionErrorPayload::is_catchable`] - converting it to a `{ kind, message, card_index,
+    /// payload }` table via [`Vm::make_error_value`] and retrying after [`Vm::unwind_to_handler`] resumes at
+    /// the nearest installed `Card::Try` handler. Because `run_once`'s loop is flat over
+    /// `instr_ptr` (a cao-lang function call only pushes a `CallFrame` and jumps `instr_ptr`,
+    /// never recursing at the Rust level), this correctly unwinds across any number of cao-lang
+    /// call frames - function-call boundaries included - without `run_once` itself needing to
+    /// know handlers exist.
+    fn _run(&mut self, instr_ptr: &mut usize) -> ExecutionResult<()> {
+        loop {
+            match self.run_once(instr_ptr) {
+                Err(err) if err.payload.is_catchable() => {
+                    let value = self
+                        .make_error_value(&err.payload, err.trace.last())
+                        .unwrap_or(Value::Nil);
+                    match self.unwind_to_handler(value, instr_ptr) {
+                        Ok(true) => continue,
+                        Ok(false) => return Err(err),
+                        Err(unwind_err) => return Err(ExecutionError::new(unwind_err, err.trace)),
+                    }
+                }
+                result => return result,
+            }
+        }
+    }
+
     /// This mostly assumes that program is valid, produced by the compiler.
     /// As such running non-compiler emitted programs is very un-safe
     pub fn run(&mut self, program: &CaoCompiledProgram) -> ExecutionResult<()> {
+        let runtime_fingerprint = stdlib::stdlib_fingerprint();
+        if program.stdlib_fingerprint != runtime_fingerprint {
+            return Err(ExecutionError::new(
+                ExecutionErrorPayload::StdlibFingerprintMismatch {
+                    program: program.stdlib_fingerprint,
+                    runtime: runtime_fingerprint,
+                },
+                Default::default(),
+            ));
+        }
+
         self.runtime_data.current_program = program as *const _;
         self.runtime_data
             .call_stack
@@ -772,18 +2139,209 @@ impl<'a, Aux> Vm<'a, Aux> {
                 src_instr_ptr: 0,
                 dst_instr_ptr: 0,
                 stack_offset: 0,
-                closure: std::ptr::null_mut(),
+                closure: core::ptr::null_mut(),
             })
-            .map_err(|_| ExecutionErrorPayload::CallStackOverflow)
+            .map_err(ExecutionErrorPayload::from)
+            .map_err(|pl| ExecutionError::new(pl, Default::default()))?;
+        self.runtime_data
+            .value_stack
+            .reserve_locals(0, program.main_locals as usize)
+            .map_err(ExecutionErrorPayload::from)
             .map_err(|pl| ExecutionError::new(pl, Default::default()))?;
 
         self.remaining_iters = self.max_instr;
         let mut instr_ptr = 0;
         let result = self._run(&mut instr_ptr);
-        self.runtime_data.current_program = std::ptr::null();
+        self.runtime_data.current_program = core::ptr::null();
+        if let Err(err) = &result {
+            self.fire_trap_handler(err);
+        }
         result
     }
 
+    /// Ask the running program to pause at the current instruction boundary, handing `payload`
+    /// back to the host as a [`RunOutcome::Yielded`]. Meant to be called by a native function
+    /// (see [`crate::traits::VmFunction`]) while running under [`Vm::run_resumable`]/
+    /// [`Vm::resume`] - e.g. a game-entity script that calls into a long-running host action and
+    /// wants to give control back instead of blocking the VM's thread until it finishes.
+    /// [`Instruction::Yield`] is the bytecode-level equivalent, for a compiled lane that wants to
+    /// yield without going through a native call at all - it pops the top of the value stack and
+    /// calls this with that [`Value`] as the payload (see [`Suspended::yielded_value`]).
+    ///
+    /// Under plain [`Vm::run`] this still unwinds the program like any other native error, just
+    /// with nowhere for `payload` to go.
+    pub fn suspend(
+        &mut self,
+        payload: Box<dyn Any>,
+    ) -> Result<(), ExecutionErrorPayload> {
+        self.pending_suspend = Some(payload);
+        Err(ExecutionErrorPayload::Suspended)
+    }
+
+    /// Like [`Vm::run`], but lets a native function pause the program mid-run via [`Vm::suspend`]
+    /// instead of failing it, and also pauses it on its own once `max_instr` instructions have
+    /// run, or its fuel budget (see [`Vm::set_fuel`]) runs out, rather than failing with
+    /// `Timeout`/`OutOfFuel` - either way the paused state comes back as [`RunOutcome::Yielded`]
+    /// instead of an error. Continue a yielded program with [`Vm::resume`].
+    pub fn run_resumable(mut self, program: &CaoCompiledProgram) -> ExecutionResult<RunOutcome> {
+        let runtime_fingerprint = stdlib::stdlib_fingerprint();
+        if program.stdlib_fingerprint != runtime_fingerprint {
+            return Err(ExecutionError::new(
+                ExecutionErrorPayload::StdlibFingerprintMismatch {
+                    program: program.stdlib_fingerprint,
+                    runtime: runtime_fingerprint,
+                },
+                Default::default(),
+            ));
+        }
+
+        self.runtime_data.current_program = program as *const _;
+        self.runtime_data
+            .call_stack
+            .push(CallFrame {
+                src_instr_ptr: 0,
+                dst_instr_ptr: 0,
+                stack_offset: 0,
+                closure: core::ptr::null_mut(),
+            })
+            .map_err(ExecutionErrorPayload::from)
+            .map_err(|pl| ExecutionError::new(pl, Default::default()))?;
+        self.runtime_data
+            .value_stack
+            .reserve_locals(0, program.main_locals as usize)
+            .map_err(ExecutionErrorPayload::from)
+            .map_err(|pl| ExecutionError::new(pl, Default::default()))?;
+
+        self.remaining_iters = self.max_instr;
+        self.drive_resumable(0)
+    }
+
+    /// Like [`Vm::run_resumable`], but metered by a `fuel` budget instead of (or alongside)
+    /// `max_instr`: calls [`Vm::set_fuel`] with `fuel` before driving the program, so it pauses
+    /// once that many instructions have run. Meant for cooperative time-slicing untrusted scripts
+    /// - e.g. giving each of several scripts a fixed instruction allowance per game tick and
+    /// resuming them round-robin with [`Vm::resume`], topping up fuel via [`Vm::set_fuel`]/
+    /// [`Vm::add_fuel`] on the resuming `Vm` before each call.
+    pub fn run_until_fuel_exhausted(
+        mut self,
+        program: &CaoCompiledProgram,
+        fuel: u64,
+    ) -> ExecutionResult<RunOutcome> {
+        self.set_fuel(Some(fuel));
+        self.run_resumable(program)
+    }
+
+    /// Continue a program paused by [`Vm::suspend`] or by exhausting its instruction budget:
+    /// splices `suspended`'s runtime state (value stack, call stack, globals and heap - the whole
+    /// [`RuntimeData`]) into this `Vm`, pushes `resume_value` as the suspended call's return
+    /// value, and resumes from the saved instruction pointer. Pass `Value::Nil` for
+    /// `resume_value` when resuming a program that merely ran out of budget rather than one that
+    /// called [`Vm::suspend`] expecting an injected value back.
+    ///
+    /// The restored call stack and value stack depths are re-validated against this `Vm`'s own
+    /// capacities, so resuming onto a `Vm` built with smaller capacities than the one that
+    /// suspended fails with `CallStackOverflow`/`Stackoverflow` instead of silently corrupting
+    /// either stack.
+    ///
+    /// If `self` was paused by running out of fuel, call [`Vm::set_fuel`]/[`Vm::add_fuel`] on
+    /// this `Vm` before calling `resume` to top up the budget for the continued run; otherwise
+    /// the suspended (exhausted) budget carries over unchanged and the program immediately
+    /// pauses again.
+    ///
+    /// Like [`Vm::restore`], only the standard library is re-registered as native callables -
+    /// register any custom ones on `self` again before resuming if the continued program needs
+    /// them.
+    pub fn resume(
+        mut self,
+        suspended: Suspended,
+        resume_value: Value,
+    ) -> ExecutionResult<RunOutcome> {
+        if suspended.runtime_data.call_stack.len() > self.runtime_data.call_stack.capacity() {
+            return Err(ExecutionError::new(
+                ExecutionErrorPayload::CallStackOverflow {
+                    capacity: self.runtime_data.call_stack.capacity(),
+                    attempted: suspended.runtime_data.call_stack.len(),
+                },
+                Default::default(),
+            ));
+        }
+        if suspended.runtime_data.value_stack.len() >= self.runtime_data.value_stack.capacity() {
+            return Err(ExecutionError::new(
+                ExecutionErrorPayload::Stackoverflow {
+                    capacity: self.runtime_data.value_stack.capacity(),
+                    attempted: suspended.runtime_data.value_stack.len() + 1,
+                },
+                Default::default(),
+            ));
+        }
+
+        let fuel = self.runtime_data.fuel;
+        self.runtime_data = suspended.runtime_data;
+        if fuel.is_some() {
+            self.runtime_data.fuel = fuel;
+        }
+        self.max_instr = suspended.max_instr;
+        self.remaining_iters = self.max_instr;
+        self.runtime_data
+            .value_stack
+            .push(resume_value)
+            .map_err(ExecutionErrorPayload::from)
+            .map_err(|pl| ExecutionError::new(pl, Default::default()))?;
+
+        self.drive_resumable(suspended.instr_ptr)
+    }
+
+    /// Shared tail of [`Vm::run_resumable`]/[`Vm::resume`]: drive the interpreter from
+    /// `instr_ptr`, turning a normal finish into [`RunOutcome::Finished`] and either a
+    /// [`Vm::suspend`] call or an exhausted instruction budget into [`RunOutcome::Yielded`].
+    fn drive_resumable(mut self, mut instr_ptr: usize) -> ExecutionResult<RunOutcome> {
+        match self._run(&mut instr_ptr) {
+            Ok(()) => {
+                self.runtime_data.current_program = core::ptr::null();
+                let result = self
+                    .runtime_data
+                    .value_stack
+                    .as_slice()
+                    .last()
+                    .copied()
+                    .unwrap_or(Value::Nil);
+                Ok(RunOutcome::Finished(result))
+            }
+            Err(err) if matches!(err.payload, ExecutionErrorPayload::Suspended) => {
+                let payload = self
+                    .pending_suspend
+                    .take()
+                    .expect("Vm::suspend always sets pending_suspend before raising Suspended");
+                Ok(RunOutcome::Yielded(Suspended {
+                    runtime_data: self.runtime_data,
+                    instr_ptr,
+                    max_instr: self.max_instr,
+                    payload,
+                }))
+            }
+            Err(err)
+                if matches!(
+                    err.payload,
+                    ExecutionErrorPayload::Timeout { .. }
+                        | ExecutionErrorPayload::OutOfFuel
+                        | ExecutionErrorPayload::Paused
+                ) =>
+            {
+                Ok(RunOutcome::Yielded(Suspended {
+                    runtime_data: self.runtime_data,
+                    instr_ptr,
+                    max_instr: self.max_instr,
+                    payload: Box::new(()),
+                }))
+            }
+            Err(err) => {
+                self.runtime_data.current_program = core::ptr::null();
+                self.fire_trap_handler(&err);
+                Err(err)
+            }
+        }
+    }
+
     #[inline]
     fn binary_op(&mut self, op: fn(Value, Value) -> Value) -> Result<(), ExecutionErrorPayload> {
         let b = self.stack_pop();
@@ -792,7 +2350,198 @@ impl<'a, Aux> Vm<'a, Aux> {
         self.runtime_data
             .value_stack
             .push(op(a, b))
-            .map_err(|_| ExecutionErrorPayload::Stackoverflow)?;
+            .map_err(ExecutionErrorPayload::from)?;
+        Ok(())
+    }
+
+    /// Like [`Vm::binary_op`], but for numeric arithmetic: `Nil`/`Object` operands have no sane
+    /// arithmetic result, so they surface as a typed [`ExecutionErrorPayload`] instead of
+    /// silently pushing `Value::Nil` for a later instruction to trip over. `op` itself (see the
+    /// `Add`/`Sub`/`Mul`/`Div`/... impls on [`Value`]) still decides `Integer` vs `Real`
+    /// promotion/truncation.
+    #[inline]
+    fn binary_arith_op(
+        &mut self,
+        op: fn(Value, Value) -> Value,
+    ) -> Result<(), ExecutionErrorPayload> {
+        let b = self.stack_pop();
+        let a = self.stack_pop();
+        if !a.is_numeric() || !b.is_numeric() {
+            return Err(ExecutionErrorPayload::invalid_argument(
+                "arithmetic requires Integer or Real operands",
+            ));
+        }
+
+        self.runtime_data
+            .value_stack
+            .push(op(a, b))
+            .map_err(ExecutionErrorPayload::from)?;
+        Ok(())
+    }
+
+    /// Like [`Vm::binary_arith_op`], but for `Add`/`Sub`/`Mul` specifically: an `Integer`/`BigInt`
+    /// operation that overflows `i64` is resolved per the installed [`ArithmeticMode`] (default
+    /// [`ArithmeticMode::Promoting`], a heap
+    /// [`runtime::cao_lang_object::CaoLangObjectBody::BigInt`]) instead of always wrapping, which
+    /// needs `&mut self` to allocate - `binary_arith_op`'s plain `fn(Value, Value) -> Value` has
+    /// no way to do that, so `Pow`/`Min`/`Max` (which don't need promotion) stay on
+    /// `binary_arith_op`. `int_op` is the plain `i64` form of the operation, tried first since
+    /// both operands being plain `Value::Integer` (not `BigInt`) is by far the common case in hot
+    /// numeric loops; `wide_int_op` is the same operation widened to `i128`, used as a fallback
+    /// whenever `int_op` itself overflows `i64` or either operand is already a `BigInt`.
+    /// `float_op` is the same operation for `Real` operands, which never overflow in the same way
+    /// and so ignore `ArithmeticMode`.
+    #[inline]
+    fn checked_arith_op(
+        &mut self,
+        int_op: fn(i64, i64) -> Option<i64>,
+        wide_int_op: fn(i128, i128) -> Option<i128>,
+        float_op: fn(f64, f64) -> f64,
+    ) -> Result<(), ExecutionErrorPayload> {
+        let b = self.stack_pop();
+        let a = self.stack_pop();
+
+        if let (Value::Integer(x), Value::Integer(y)) = (a, b) {
+            let result = match int_op(x, y) {
+                Some(r) => Value::Integer(r),
+                None => self.resolve_int_overflow(
+                    wide_int_op(x as i128, y as i128).expect("i128 can't overflow on i64 inputs"),
+                    a,
+                    b,
+                )?,
+            };
+            self.runtime_data
+                .value_stack
+                .push(result)
+                .map_err(ExecutionErrorPayload::from)?;
+            return Ok(());
+        }
+
+        if !a.is_numeric() || !b.is_numeric() {
+            return Err(ExecutionErrorPayload::invalid_argument(
+                "arithmetic requires Integer or Real operands",
+            ));
+        }
+
+        let result = match (a.as_wide_int(), b.as_wide_int()) {
+            (Some(x), Some(y)) => match wide_int_op(x, y) {
+                Some(r) => self.resolve_int_overflow(r, a, b)?,
+                // only reachable once both operands are already BigInts near i128's own range -
+                // out of scope, see CaoLangBigInt's doc comment
+                None => Value::Nil,
+            },
+            _ => match a.try_cast_match(b) {
+                (Value::Real(x), Value::Real(y)) => Value::Real(float_op(x, y)),
+                _ => Value::Nil,
+            },
+        };
+
+        self.runtime_data
+            .value_stack
+            .push(result)
+            .map_err(ExecutionErrorPayload::from)?;
+        Ok(())
+    }
+
+    /// Narrows an `i128` arithmetic result back down to [`Value::Integer`] if it already fits
+    /// `i64`; otherwise resolves the overflow per the installed [`ArithmeticMode`]. Backs
+    /// [`Vm::checked_arith_op`]. `a`/`b` are the original operands, kept around only to name in
+    /// [`ArithmeticMode::Checked`]'s error.
+    #[inline]
+    fn resolve_int_overflow(
+        &mut self,
+        value: i128,
+        a: Value,
+        b: Value,
+    ) -> Result<Value, ExecutionErrorPayload> {
+        if let Ok(i) = i64::try_from(value) {
+            return Ok(Value::Integer(i));
+        }
+        match self.arithmetic_mode {
+            ArithmeticMode::Promoting => {
+                Ok(Value::Object(self.runtime_data.init_bigint(value)?.into_inner()))
+            }
+            ArithmeticMode::Checked => Err(ExecutionErrorPayload::invalid_argument(format!(
+                "integer arithmetic overflowed i64 (operands: {a:?}, {b:?})"
+            ))),
+            ArithmeticMode::Saturating => {
+                Ok(Value::Integer(value.clamp(i64::MIN as i128, i64::MAX as i128) as i64))
+            }
+            ArithmeticMode::Wrapping => Ok(Value::Integer(value as i64)),
+        }
+    }
+
+    /// Like [`Vm::binary_arith_op`], but for `Div`/`Mod`: dividing an `Integer` by a zero
+    /// `Integer` has no truncating result (`Value`'s own `/`/`%` would otherwise silently fall
+    /// back to `Nil`), so it's checked here and surfaced as a typed error instead. `Real`
+    /// division/remainder by zero is well-defined (`inf`/`nan`) and passes through unchanged.
+    #[inline]
+    fn checked_div_op(
+        &mut self,
+        op: fn(Value, Value) -> Value,
+    ) -> Result<(), ExecutionErrorPayload> {
+        let b = self.stack_pop();
+        let a = self.stack_pop();
+        if !a.is_numeric() || !b.is_numeric() {
+            return Err(ExecutionErrorPayload::invalid_argument(
+                "arithmetic requires Integer or Real operands",
+            ));
+        }
+        let (ca, cb) = a.try_cast_match(b);
+        if let (Value::Integer(_), Value::Integer(0)) = (ca, cb) {
+            return Err(ExecutionErrorPayload::DivideByZero);
+        }
+
+        self.runtime_data
+            .value_stack
+            .push(op(a, b))
+            .map_err(ExecutionErrorPayload::from)?;
+        Ok(())
+    }
+
+    /// Backs [`Instruction::Random`]: pops `lo, hi` and pushes a uniform integer in `[lo, hi]`
+    /// via [`runtime::RuntimeData::next_random_range`].
+    #[inline]
+    fn random_op(&mut self) -> Result<(), ExecutionErrorPayload> {
+        let hi = self.stack_pop();
+        let lo = self.stack_pop();
+        let (Value::Integer(lo), Value::Integer(hi)) = (lo, hi) else {
+            return Err(ExecutionErrorPayload::invalid_argument(
+                "Random requires Integer bounds",
+            ));
+        };
+        let roll = self.runtime_data.next_random_range(lo, hi)?;
+        self.runtime_data
+            .value_stack
+            .push(Value::Integer(roll))
+            .map_err(ExecutionErrorPayload::from)?;
+        Ok(())
+    }
+
+    /// Backs [`Instruction::DiceRoll`]: pops `count, sides` and pushes the sum of `count`
+    /// independent rolls of `1..=sides`, e.g. `3d6`.
+    #[inline]
+    fn dice_roll_op(&mut self) -> Result<(), ExecutionErrorPayload> {
+        let sides = self.stack_pop();
+        let count = self.stack_pop();
+        let (Value::Integer(count), Value::Integer(sides)) = (count, sides) else {
+            return Err(ExecutionErrorPayload::invalid_argument(
+                "DiceRoll requires Integer count and sides",
+            ));
+        };
+        if count < 0 {
+            return Err(ExecutionErrorPayload::invalid_argument(
+                "DiceRoll's count must not be negative",
+            ));
+        }
+        let mut total = 0i64;
+        for _ in 0..count {
+            total += self.runtime_data.next_random_range(1, sides)?;
+        }
+        self.runtime_data
+            .value_stack
+            .push(Value::Integer(total))
+            .map_err(ExecutionErrorPayload::from)?;
         Ok(())
     }
 }
@@ -1,10 +1,7 @@
-use std::{convert::TryFrom, ptr::NonNull};
-
-use bytemuck::Pod;
 use tracing::{debug, trace};
 
 use crate::{
-    bytecode::{decode_str, read_from_bytes},
+    bytecode::{decode_str_checked, read_from_bytes, TriviallyEncodable},
     collections::handle_table::Handle,
     compiled_program::CaoCompiledProgram,
     procedures::ExecutionErrorPayload,
@@ -24,8 +21,7 @@ use super::{
 
 pub fn read_str<'a>(instr_ptr: &mut usize, program: &'a [u8]) -> Option<&'a str> {
     let p = *instr_ptr;
-    let limit = program.len().min(p + MAX_STR_LEN);
-    let (len, s): (_, &'a str) = decode_str(&program[p..limit])?;
+    let (len, s) = decode_str_checked(&program[p..], MAX_STR_LEN).ok()?;
     *instr_ptr += len;
     Some(s)
 }
@@ -34,7 +30,7 @@ pub fn read_str<'a>(instr_ptr: &mut usize, program: &'a [u8]) -> Option<&'a str>
 ///
 /// Assumes that the underlying data is safely decodable to the given type
 ///
-pub unsafe fn decode_value<T: Pod>(bytes: &[u8], instr_ptr: &mut usize) -> T {
+pub unsafe fn decode_value<T: TriviallyEncodable>(bytes: &[u8], instr_ptr: &mut usize) -> T {
     let (len, val) = read_from_bytes(&bytes[*instr_ptr..]).expect("Failed to read data");
     *instr_ptr += len;
     val
@@ -64,7 +60,7 @@ pub fn instr_read_var(
     runtime_data
         .value_stack
         .push(*value)
-        .map_err(|_| ExecutionErrorPayload::Stackoverflow)?;
+        .map_err(ExecutionErrorPayload::from)?;
     Ok(())
 }
 
@@ -80,6 +76,7 @@ pub fn instr_set_var(
         runtime_data.global_vars.resize(varid + 1, Value::Nil);
     }
     runtime_data.global_vars[varid] = scalar;
+    runtime_data.gc_root_write_barrier(scalar);
     Ok(())
 }
 
@@ -109,8 +106,53 @@ pub fn instr_string_literal<T>(
     Ok(())
 }
 
+/// Backs [`Instruction::Switch`](crate::instruction::Instruction::Switch): pops the Integer
+/// scrutinee and reads the jump table the instruction's `U32` operand points at in
+/// `program.data` - `min: i64, default: i32, len: u32`, then `len` `i32` bytecode offsets, one
+/// per key in `[min, min + len)` - jumping to the offset for `scrutinee - min` if that falls
+/// inside the table, or to `default` otherwise. Compiles the dense-key case of
+/// [`crate::compiler::Card::Switch`]; see `Compiler::process_card`'s `Card::Switch` arm for the
+/// sparse-key fallback, which never emits this instruction.
+pub fn instr_switch<T>(
+    vm: &mut Vm<T>,
+    instr_ptr: &mut usize,
+    program: &CaoCompiledProgram,
+) -> ExecutionResult {
+    let data_offset: u32 = unsafe { decode_value(&program.bytecode, instr_ptr) };
+
+    let Value::Integer(scrutinee) = vm.stack_pop() else {
+        return Err(ExecutionErrorPayload::invalid_argument(
+            "Switch requires an Integer scrutinee",
+        ));
+    };
+
+    let mut pos = data_offset as usize;
+    let min: i64 = unsafe { decode_value(&program.data, &mut pos) };
+    let default: i32 = unsafe { decode_value(&program.data, &mut pos) };
+    let len: u32 = unsafe { decode_value(&program.data, &mut pos) };
+
+    let target = u32::try_from(scrutinee.wrapping_sub(min))
+        .ok()
+        .filter(|index| *index < len)
+        .map(|index| {
+            let mut offset_pos = pos + index as usize * core::mem::size_of::<i32>();
+            unsafe { decode_value::<i32>(&program.data, &mut offset_pos) }
+        })
+        .unwrap_or(default);
+
+    *instr_ptr = target as usize;
+    Ok(())
+}
+
+/// Gives a `Call`/`Closure` invocation its own isolated locals: reserves `max_locals` slots on
+/// the value stack starting at the callee's frame base (the depth left after popping its
+/// arguments off the caller's stack) and records that base plus the return address in a new
+/// [`CallFrame`]. `ReadVar`/`SetVar` (`get_local`/`set_local` below) always index relative to
+/// `CallFrame::stack_offset`, so a callee's locals can never read or clobber a caller's, and
+/// [`instr_return`] truncates the value stack back to that same base when the call unwinds.
 pub fn push_call_frame(
     arity: usize,
+    max_locals: usize,
     src_ptr: u32,
     instr_ptr: u32,
     closure: *mut CaoLangClosure,
@@ -123,23 +165,75 @@ pub fn push_call_frame(
         .expect("Call stack was empty")
         .dst_instr_ptr = instr_ptr;
 
+    let stack_offset = runtime_data
+        .value_stack
+        .len()
+        .checked_sub(arity)
+        .ok_or(ExecutionErrorPayload::MissingArgument)?;
+
+    // reserve every one of the callee's locals (arguments included) in a single bulk extension
+    // instead of growing the value stack one local at a time
+    runtime_data
+        .value_stack
+        .reserve_locals(stack_offset, max_locals)
+        .map_err(ExecutionErrorPayload::from)?;
+
     // init the new call frame
     runtime_data
         .call_stack
         .push(CallFrame {
             src_instr_ptr: src_ptr,
             dst_instr_ptr: instr_ptr,
-            stack_offset: runtime_data
-                .value_stack
-                .len()
-                .checked_sub(arity as usize)
-                .ok_or(ExecutionErrorPayload::MissingArgument)? as u32,
+            stack_offset: stack_offset as u32,
             closure,
         })
-        .map_err(|_| ExecutionErrorPayload::CallStackOverflow)?;
+        .map_err(ExecutionErrorPayload::from)?;
     Ok(())
 }
 
+/// A callable object's shape, as pulled out of its [`CaoLangObjectBody`] by [`resolve_callable`].
+pub(crate) enum Callee {
+    /// A cao-lang function or closure, jumped to by pushing/reusing a [`CallFrame`].
+    Lane {
+        arity: u32,
+        max_locals: u32,
+        label: Handle,
+        closure: *mut CaoLangClosure,
+    },
+    /// A Rust-side function, invoked directly via [`call_native`] instead of jumping.
+    Native(Handle),
+}
+
+/// Reads the callee shape out of `o`, shared by [`instr_call_function`], [`instr_tail_call`] and
+/// [`Vm::call_value`](super::Vm::call_value) so the three don't each re-derive it from the object
+/// body on their own.
+pub(crate) fn resolve_callable(
+    o: &CaoLangObject,
+    context: &'static str,
+) -> Result<Callee, ExecutionErrorPayload> {
+    unsafe {
+        match &o.body {
+            CaoLangObjectBody::Function(f) => Ok(Callee::Lane {
+                arity: f.arity,
+                max_locals: f.max_locals,
+                label: f.handle,
+                closure: core::ptr::null_mut(),
+            }),
+            CaoLangObjectBody::Closure(c) => Ok(Callee::Lane {
+                arity: c.function.arity,
+                max_locals: c.function.max_locals,
+                label: c.function.handle,
+                closure: (c as *const CaoLangClosure).cast_mut(),
+            }),
+            CaoLangObjectBody::NativeFunction(f) => Ok(Callee::Native(f.handle)),
+            _ => Err(ExecutionErrorPayload::invalid_argument(format!(
+                "{context} expects a function object argument, instead got: {}",
+                o.type_name()
+            ))),
+        }
+    }
+}
+
 pub fn instr_call_function<T>(
     src_ptr: usize,
     instr_ptr: &mut usize,
@@ -151,40 +245,28 @@ pub fn instr_call_function<T>(
             "Call instruction expects a function object argument",
         ));
     };
-    let arity;
-    let label;
-    let mut closure = std::ptr::null_mut();
-    unsafe {
-        match &o.as_ref().body {
-            CaoLangObjectBody::Function(f) => {
-                arity = f.arity;
-                label = f.handle;
-            }
-            CaoLangObjectBody::Closure(c) => {
-                arity = c.function.arity;
-                label = c.function.handle;
-                closure = (c as *const CaoLangClosure).cast_mut();
-            }
-            CaoLangObjectBody::NativeFunction(f) => {
-                return call_native(vm, f.handle);
-            }
-            _ => {
-                return Err(ExecutionErrorPayload::invalid_argument(format!(
-                    "Call instruction expects a function object argument, instead got: {}",
-                    o.as_ref().type_name()
-                )));
-            }
-        }
-    }
+    let callee = resolve_callable(unsafe { o.as_ref() }, "Call instruction")?;
+    let (arity, max_locals, label, closure) = match callee {
+        Callee::Native(handle) => return call_native(vm, handle),
+        Callee::Lane {
+            arity,
+            max_locals,
+            label,
+            closure,
+        } => (arity, max_locals, label, closure),
+    };
 
     push_call_frame(
         arity as usize,
+        max_locals as usize,
         src_ptr as u32,
         *instr_ptr as u32,
         closure,
         &mut vm.runtime_data,
     )?;
 
+    vm.fire_observe_enter_frame(label, arity);
+
     // set the instr_ptr to the new lane's beginning
     *instr_ptr = program
         .labels
@@ -195,6 +277,91 @@ pub fn instr_call_function<T>(
     Ok(())
 }
 
+/// Tail-call variant of [`instr_call_function`]: instead of growing the call stack, reuses the
+/// current (top) [`CallFrame`] for the callee, so a chain of tail calls runs in constant call
+/// stack depth. Only emitted by the compiler for a `Call` card in tail position - the last thing
+/// its enclosing function does.
+pub fn instr_tail_call<T>(
+    src_ptr: usize,
+    instr_ptr: &mut usize,
+    program: &CaoCompiledProgram,
+    vm: &mut Vm<T>,
+) -> ExecutionResult {
+    let Value::Object(o) = vm.runtime_data.value_stack.pop() else {
+        return Err(ExecutionErrorPayload::invalid_argument(
+            "TailCall instruction expects a function object argument",
+        ));
+    };
+    let callee = resolve_callable(unsafe { o.as_ref() }, "TailCall instruction")?;
+    let (arity, max_locals, label, closure) = match callee {
+        Callee::Native(handle) => {
+            // a native callee can't reuse the current frame the way a cao-lang function can - call
+            // it normally, then return right away so its result goes straight back to the original
+            // caller instead of falling through to this function's own body.
+            call_native(vm, handle)?;
+            return instr_return(vm, instr_ptr);
+        }
+        Callee::Lane {
+            arity,
+            max_locals,
+            label,
+            closure,
+        } => (arity, max_locals, label, closure),
+    };
+    let arity = arity as usize;
+
+    let stack_offset = vm
+        .runtime_data
+        .call_stack
+        .last()
+        .expect("Call stack was empty")
+        .stack_offset as usize;
+
+    // close upvalues captured by the outgoing frame before its locals are overwritten
+    _close_upvalues(vm, stack_offset)?;
+
+    // the callee's arguments are the top `arity` values on the stack; shift them down to start at
+    // `stack_offset`, the base the outgoing frame's locals occupied, and drop everything above
+    let len = vm.runtime_data.value_stack.len();
+    let args_start = len
+        .checked_sub(arity)
+        .ok_or(ExecutionErrorPayload::MissingArgument)?;
+    for i in 0..arity {
+        let arg = vm.runtime_data.value_stack.get(args_start + i);
+        vm.runtime_data
+            .value_stack
+            .set(stack_offset + i, arg)
+            .map_err(ExecutionErrorPayload::from)?;
+    }
+    vm.runtime_data.value_stack.clear_until(stack_offset + arity);
+
+    // reserve the callee's locals (arguments included) in one bulk extension, same as a fresh call
+    vm.runtime_data
+        .value_stack
+        .reserve_locals(stack_offset, max_locals as usize)
+        .map_err(ExecutionErrorPayload::from)?;
+
+    let label_pos = program
+        .labels
+        .0
+        .get(label)
+        .ok_or_else(|| ExecutionErrorPayload::ProcedureNotFound(label))?
+        .pos;
+
+    // reuse the current frame: keep `dst_instr_ptr` so the eventual Return still goes back to the
+    // original caller, but take on the callee's call-site address and closure
+    let frame = vm
+        .runtime_data
+        .call_stack
+        .last_mut()
+        .expect("Call stack was empty");
+    frame.src_instr_ptr = src_ptr as u32;
+    frame.closure = closure;
+
+    *instr_ptr = label_pos as usize;
+    Ok(())
+}
+
 pub fn execute_call_native<T>(
     vm: &mut Vm<T>,
     instr_ptr: &mut usize,
@@ -212,14 +379,28 @@ pub fn call_native<T>(vm: &mut Vm<T>, handle: Handle) -> ExecutionResult {
         .get(handle)
         .ok_or(ExecutionErrorPayload::ProcedureNotFound(handle))?
         .clone();
-    let res = procedure
-        .fun
-        .call(vm)
-        .map_err(|err| ExecutionErrorPayload::TaskFailure {
+    vm.fire_observe_native_call(handle, procedure.name());
+    let height_before = vm.runtime_data.value_stack.len();
+    procedure.fun.call(vm).map_err(|err| match err {
+        // not a real failure: let `Vm::run_resumable`/`Vm::resume` catch it at the top of the
+        // interpreter loop instead of burying it inside a `TaskFailure`
+        ExecutionErrorPayload::Suspended => ExecutionErrorPayload::Suspended,
+        err => ExecutionErrorPayload::TaskFailure {
             name: procedure.name().to_string(),
             error: Box::new(err),
-        })?;
-    vm.stack_push(res)?;
+        },
+    })?;
+    // The callable pushed its results (and maybe leftover scratch) somewhere above
+    // `height_before`; take the top `num_results` values as the call's return values and discard
+    // everything else the call left on the stack.
+    let num_results = procedure.fun.num_results() as usize;
+    let results: Vec<Value> = (0..num_results)
+        .map(|i| vm.runtime_data.value_stack.peek_last(i))
+        .collect();
+    vm.runtime_data.value_stack.clear_until(height_before);
+    for v in results.into_iter().rev() {
+        vm.stack_push(v)?;
+    }
     Ok(())
 }
 
@@ -280,14 +461,7 @@ pub fn instr_return<T>(vm: &mut Vm<T>, instr_ptr: &mut usize) -> ExecutionResult
     let value = match vm.runtime_data.call_stack.pop() {
         // return value
         Some(call_frame) => {
-            let stack_start_location = unsafe {
-                vm.runtime_data
-                    .value_stack
-                    .as_slice()
-                    .as_ptr()
-                    .add(call_frame.stack_offset as usize)
-            };
-            _close_upvalues(vm, stack_start_location)?;
+            _close_upvalues(vm, call_frame.stack_offset as usize)?;
 
             vm.runtime_data
                 .value_stack
@@ -316,6 +490,7 @@ pub fn instr_return<T>(vm: &mut Vm<T>, instr_ptr: &mut usize) -> ExecutionResult
 
     // push the return value
     trace!("Return {value:?}");
+    vm.fire_observe_exit_frame(value);
     vm.stack_push(value)?;
     Ok(())
 }
@@ -325,12 +500,13 @@ pub fn instr_copy_last<T>(vm: &mut Vm<T>) -> ExecutionResult {
     vm.runtime_data
         .value_stack
         .push(val)
-        .map_err(|_| ExecutionErrorPayload::Stackoverflow)?;
+        .map_err(ExecutionErrorPayload::from)?;
 
     Ok(())
 }
 
-/// push `i=0` onto the stack
+/// push `i=0` onto the stack, plus the pullable iterator wrapping the loop's iterable (see
+/// [`for_each`])
 pub fn begin_for_each<T>(
     vm: &mut Vm<T>,
     bytecode: &[u8],
@@ -338,51 +514,55 @@ pub fn begin_for_each<T>(
 ) -> ExecutionResult {
     let i_handle: u32 = unsafe { decode_value(bytecode, instr_ptr) };
     let t_handle: u32 = unsafe { decode_value(bytecode, instr_ptr) };
+    let snapshot_handle: u32 = unsafe { decode_value(bytecode, instr_ptr) };
     let item_val = vm.runtime_data.value_stack.last();
-    // test if the input is a table
-    let item = vm.get_table_mut(item_val)?;
-    debug!("Starting for-each on table {:?}", item);
+
+    // wrap the input in a pullable iterator: a bare Table gets a fresh position cursor of its
+    // own, while an existing Iterator (Map/Filter/Range/Chars/Native/...) is driven as-is. Each
+    // variant tracks its own cursor, so unlike the table-keys snapshot this replaces, nothing
+    // here is invalidated by the body mutating the source mid-loop.
+    let iter = vm.to_iterator(item_val)?;
+    debug!("Starting for-each on {:?}", iter);
+    let iter_val = Value::Object(iter);
+
     let offset = stack_offset(vm);
     write_local_var(vm, i_handle, Value::Integer(0), offset)?;
-    write_local_var(vm, t_handle, item_val, offset)?;
+    write_local_var(vm, t_handle, iter_val, offset)?;
+    write_local_var(vm, snapshot_handle, iter_val, offset)?;
 
     Ok(())
 }
 
 /// Assumes that [begin_for_each](begin_for_each) was called once to set up the loop
 ///
-/// Pushes the next key and the object onto the stack. Assumes that the lane takes these as
-/// parameters.
+/// Pulls the next `(index, key, value)` triple from the iterator `begin_for_each` stashed in
+/// `t_handle` and writes it into the loop's `i`/`k`/`v` locals. Assumes that the lane takes these
+/// as parameters.
 ///
 /// Pushes should_continue on top of the stack
 pub fn for_each<T>(vm: &mut Vm<T>, bytecode: &[u8], instr_ptr: &mut usize) -> ExecutionResult {
     let loop_variable: u32 = unsafe { decode_value(bytecode, instr_ptr) };
     let t_handle: u32 = unsafe { decode_value(bytecode, instr_ptr) };
+    // kept only to match `BeginForEach`'s operand layout: the iterator stashed in `t_handle` now
+    // tracks its own cursor, so there's no separate keys snapshot to read back through
+    let _snapshot_handle: u32 = unsafe { decode_value(bytecode, instr_ptr) };
 
     let i_handle: u32 = unsafe { decode_value(bytecode, instr_ptr) };
     let k_handle: u32 = unsafe { decode_value(bytecode, instr_ptr) };
     let v_handle: u32 = unsafe { decode_value(bytecode, instr_ptr) };
 
     let offset = stack_offset(vm);
-    let i = read_local_var(vm, loop_variable)?;
-    let obj_val = read_local_var(vm, t_handle)?;
-
-    let i = i64::try_from(i).map_err(|_| {
-        ExecutionErrorPayload::AssertionError("ForEach i must be an integer. This error can be caused by stack corruption. Check your function calls!".to_string())
-    })?;
-    let obj = vm.get_table(obj_val).map_err(|_| {
-        ExecutionErrorPayload::AssertionError("ForEach value is not an object. This error can be caused by stack corruption. Check your function calls!".to_string())
-    })?;
-
-    debug_assert!(0 <= i, "for_each overflow");
-
-    let n = obj.len() as i64;
-
-    let should_continue = 0 <= i && i < n;
-    if should_continue {
-        let key = obj.nth_key(i as usize);
-        let val = obj.get(&key).copied().unwrap_or(Value::Nil);
+    let iter_val = read_local_var(vm, t_handle)?;
+    let iter_ptr = match iter_val {
+        Value::Object(o) => o,
+        _ => {
+            return Err(ExecutionErrorPayload::AssertionError("ForEach iterator is not an object. This error can be caused by stack corruption. Check your function calls!".to_string()))
+        }
+    };
 
+    let pulled = vm.iterator_pull(iter_ptr)?;
+    let should_continue = pulled.is_some();
+    if let Some((i, key, val)) = pulled {
         write_local_var(vm, v_handle, val, offset)?;
         write_local_var(vm, k_handle, key, offset)?;
         write_local_var(vm, i_handle, Value::Integer(i), offset)?;
@@ -440,43 +620,26 @@ pub fn register_upvalue<T>(
     let c = resolve_closure(closure)?;
 
     if is_local {
-        let location = &vm.runtime_data.value_stack.as_slice()[index as usize];
-        let location = (location as *const Value).cast_mut();
-        unsafe {
-            // look for an existing upvalue to the same location
-            let mut prev_upvalue = std::ptr::null_mut();
-            let mut upvalue = vm.runtime_data.open_upvalues;
-            while let Some(u) = upvalue.as_ref().and_then(|o| o.as_upvalue()) {
-                if u.location <= location {
-                    break;
-                }
-                prev_upvalue = upvalue;
-                upvalue = u.next;
+        // `index` is a frame-relative local slot, the same as `SetLocalVar`/`ReadLocalVar` -
+        // translate it to an absolute value-stack index before storing it in the upvalue, since
+        // `CaoLangUpvalue::Open`/`_close_upvalues` compare and index against the raw stack.
+        // Capturing in anything but the outermost call frame (`stack_offset == 0`) would
+        // otherwise silently point the upvalue at an unrelated earlier frame's slot.
+        let index = stack_offset(vm) + index as usize;
+        // look for an existing upvalue to the same stack slot, so two closures capturing the
+        // same local share one upvalue and observe each other's writes
+        let existing = vm.runtime_data.open_upvalues.iter().copied().find(|o| unsafe {
+            matches!(o.as_ref().as_upvalue(), Some(CaoLangUpvalue::Open(i)) if *i == index)
+        });
+        let upvalue = match existing {
+            Some(upvalue) => upvalue,
+            None => {
+                let upvalue = vm.init_upvalue(CaoLangUpvalue::Open(index))?.into_inner();
+                vm.runtime_data.open_upvalues.push(upvalue);
+                upvalue
             }
-            if upvalue
-                .as_ref()
-                .and_then(|u| u.as_upvalue())
-                .filter(|x| x.location == location)
-                .is_some()
-            {
-                // if there is an existing upvalue to this location reuse that
-                c.upvalues.push(NonNull::new_unchecked(upvalue));
-            } else {
-                let upvalue = vm.init_upvalue(location)?;
-
-                // keep the open upvalues sorted
-                match prev_upvalue.as_mut().and_then(|u| u.as_upvalue_mut()) {
-                    Some(prev_upvalue) => {
-                        prev_upvalue.next = upvalue.0.as_ptr();
-                    }
-                    None => {
-                        vm.runtime_data.open_upvalues = upvalue.0.as_ptr();
-                    }
-                }
-
-                c.upvalues.push(upvalue.0);
-            }
-        }
+        };
+        c.upvalues.push(upvalue);
     } else {
         let closure = unsafe {
             vm.runtime_data
@@ -503,9 +666,11 @@ pub fn read_upvalue<T>(vm: &mut Vm<T>, bytecode: &[u8], instr_ptr: &mut usize) -
         };
         match c.upvalues.get_mut(index as usize) {
             Some(u) => {
-                let u = resolve_upvalue(u.as_mut())?;
-                debug_assert!(!u.location.is_null());
-                let value = *u.location;
+                let u = *resolve_upvalue(u.as_mut())?;
+                let value = match u {
+                    CaoLangUpvalue::Open(index) => vm.runtime_data.value_stack.as_slice()[index],
+                    CaoLangUpvalue::Closed(handle) => vm.runtime_data.closed_upvalues[handle],
+                };
                 vm.stack_push(value)
             }
             None => return Err(ExecutionErrorPayload::InvalidUpvalue),
@@ -522,10 +687,28 @@ pub fn write_upvalue<T>(vm: &mut Vm<T>, bytecode: &[u8], instr_ptr: &mut usize)
             return Err(ExecutionErrorPayload::NotClosure);
         };
         match c.upvalues.get_mut(index as usize) {
-            Some(u) => {
-                let u = resolve_upvalue(u.as_mut())?;
-                debug_assert!(!u.location.is_null());
-                std::ptr::write(u.location, value);
+            Some(upvalue_obj) => {
+                let mut upvalue_obj = *upvalue_obj;
+                let u = *resolve_upvalue(upvalue_obj.as_mut())?;
+                match u {
+                    CaoLangUpvalue::Open(index) => {
+                        vm.runtime_data
+                            .value_stack
+                            .set(index, value)
+                            .map_err(ExecutionErrorPayload::from)?;
+                        // `index` is a raw value-stack slot, already covered by
+                        // `gc_mark_roots`'s scan of the whole stack - treat it like any other
+                        // root write rather than the upvalue object's own write barrier.
+                        vm.runtime_data.gc_root_write_barrier(value);
+                    }
+                    CaoLangUpvalue::Closed(handle) => {
+                        vm.runtime_data.closed_upvalues[handle] = value;
+                        // `closed_upvalues[handle]` is only reachable by marking tracing through
+                        // this Upvalue object, so it's the upvalue object that needs re-graying
+                        // if it's already been blackened this cycle.
+                        vm.runtime_data.write_barrier(upvalue_obj, value);
+                    }
+                }
                 Ok(())
             }
             None => return Err(ExecutionErrorPayload::InvalidUpvalue),
@@ -533,33 +716,39 @@ pub fn write_upvalue<T>(vm: &mut Vm<T>, bytecode: &[u8], instr_ptr: &mut usize)
     }
 }
 
-fn _close_upvalues<T>(vm: &mut Vm<T>, top: *const Value) -> ExecutionResult {
-    if top.is_null() {
-        return Err(ExecutionErrorPayload::invalid_argument(
-            "Can't close upvalues on an empty stack",
-        ));
-    }
-
-    unsafe {
-        while let Some(upvalue) = vm
-            .runtime_data
-            .open_upvalues
-            .as_mut()
-            .map(|x| x.as_upvalue_mut().unwrap())
-        {
-            if upvalue.location < top.cast_mut() {
-                break;
+/// Closes every upvalue open at or above `top_index`, a stack index: the frame that owned those
+/// locals is going away, so each one's value moves into [`RuntimeData::closed_upvalues`] and the
+/// upvalue object switches from [`CaoLangUpvalue::Open`] to [`CaoLangUpvalue::Closed`].
+///
+/// `pub(crate)` rather than private: [`super::Vm::unwind_to_handler`] also needs this when a
+/// `Throw` unwinds past locals captured between the `Try` and the throw site, not just the
+/// frame-return case [`close_upvalues`] covers.
+pub(crate) fn _close_upvalues<T>(vm: &mut Vm<T>, top_index: usize) -> ExecutionResult {
+    let mut i = 0;
+    while i < vm.runtime_data.open_upvalues.len() {
+        let mut obj = vm.runtime_data.open_upvalues[i];
+        let obj_ref = unsafe { obj.as_mut() };
+        let index = match *resolve_upvalue(obj_ref)? {
+            CaoLangUpvalue::Open(index) => index,
+            CaoLangUpvalue::Closed(_) => {
+                unreachable!("open_upvalues only ever holds Open upvalues")
             }
-            upvalue.value = std::ptr::read(upvalue.location);
-            upvalue.location = (&mut upvalue.value) as *mut _;
-            vm.runtime_data.open_upvalues = upvalue.next;
+        };
+        if index < top_index {
+            i += 1;
+            continue;
         }
+        let value = vm.runtime_data.value_stack.as_slice()[index];
+        let handle = vm.runtime_data.closed_upvalues.len();
+        vm.runtime_data.closed_upvalues.push(value);
+        *resolve_upvalue(obj_ref)? = CaoLangUpvalue::Closed(handle);
+        vm.runtime_data.open_upvalues.swap_remove(i);
     }
 
     Ok(())
 }
 
 pub fn close_upvalues<T>(vm: &mut Vm<T>) -> ExecutionResult {
-    let top = vm.runtime_data.value_stack.top_location();
-    _close_upvalues(vm, top)
+    let top_index = vm.runtime_data.value_stack.len();
+    _close_upvalues(vm, top_index)
 }
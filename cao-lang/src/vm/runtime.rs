@@ -1,12 +1,16 @@
+pub mod cao_lang_bigint;
+pub mod cao_lang_bytes;
 pub mod cao_lang_function;
+pub mod cao_lang_iterator;
 pub mod cao_lang_object;
 pub mod cao_lang_string;
 pub mod cao_lang_table;
 
-use std::{alloc::Layout, pin::Pin, ptr::NonNull};
+use core::{alloc::Layout, pin::Pin, ptr::NonNull};
 
 use crate::{
-    alloc::{AllocProxy, Allocator, CaoLangAllocator},
+    alloc::{AllocBackend, AllocProxy, Allocator, CaoLangAllocator, GcPolicy},
+    alloc_crate::boxed::Box,
     collections::{bounded_stack::BoundedStack, value_stack::ValueStack},
     prelude::*,
     value::Value,
@@ -15,7 +19,10 @@ use crate::{
 use tracing::debug;
 
 use self::{
+    cao_lang_bigint::CaoLangBigInt,
+    cao_lang_bytes::CaoLangBytes,
     cao_lang_function::{CaoLangClosure, CaoLangFunction, CaoLangNativeFunction, CaoLangUpvalue},
+    cao_lang_iterator::CaoLangIterator,
     cao_lang_object::{CaoLangObject, GcMarker, ObjectGcGuard},
     cao_lang_string::CaoLangString,
 };
@@ -27,7 +34,73 @@ pub struct RuntimeData {
     pub(crate) memory: AllocProxy,
     pub(crate) object_list: Vec<NonNull<CaoLangObject>>,
     pub(crate) current_program: *const CaoCompiledProgram,
-    pub(crate) open_upvalues: *mut CaoLangObject,
+    /// Currently open upvalues (see [`CaoLangUpvalue::Open`]), in no particular order. Scanned
+    /// linearly by [`crate::vm::instr_execution::register_upvalue`] to find-or-create an upvalue
+    /// for a given stack slot, and drained by
+    /// [`crate::vm::instr_execution::close_upvalues`]/`_close_upvalues` when a frame returns.
+    pub(crate) open_upvalues: Vec<NonNull<CaoLangObject>>,
+    /// Storage for upvalues that have been closed (see [`CaoLangUpvalue::Closed`]): append-only,
+    /// a closed upvalue's handle is its index here.
+    pub(crate) closed_upvalues: Vec<Value>,
+
+    /// Current phase of the incremental collector. `Idle` between collection cycles.
+    pub(crate) gc_phase: GcPhase,
+    /// Gray worklist: discovered, but not yet fully marked objects.
+    pub(crate) gray: Vec<NonNull<CaoLangObject>>,
+    /// Objects found unreachable during sweep that registered a finalizer. They are kept alive
+    /// (not freed, not in `object_list` any more) until [`crate::vm::Vm::gc_step`] invokes their
+    /// finalizer and frees them; `RuntimeData` alone has no way to call native functions.
+    pub(crate) pending_finalizers: Vec<NonNull<CaoLangObject>>,
+
+    /// Installed `Card::Try` handlers, innermost last. Pushed by the `PushHandler` instruction,
+    /// popped by `PopHandler` on normal exit of the guarded body; a catchable fault unwinds to
+    /// (and pops) the last entry instead of aborting the program. See
+    /// [`crate::procedures::ExecutionErrorPayload::is_catchable`].
+    pub(crate) handler_stack: Vec<HandlerFrame>,
+
+    /// xorshift64 state backing [`Instruction::Random`](crate::instruction::Instruction::Random)
+    /// and [`Instruction::DiceRoll`](crate::instruction::Instruction::DiceRoll). Seeded with
+    /// [`DEFAULT_RNG_SEED`] by default, or with [`crate::vm::Vm::with_seed`]'s argument; carried
+    /// across [`RuntimeData::snapshot`]/[`RuntimeData::restore`] so a resumed program keeps
+    /// rolling the same sequence a replay would have produced.
+    pub(crate) rng_state: u64,
+
+    /// Remaining instruction budget set via [`crate::vm::Vm::set_fuel`]/[`crate::vm::Vm::add_fuel`],
+    /// `None` meaning unmetered. Distinct from `max_instr`/`Timeout`: lives here on `RuntimeData`
+    /// rather than as a local in [`crate::vm::Vm::run_once`], so it keeps counting down across
+    /// every `run_once` call a single top-level run makes (including `Card::Try` retries) instead
+    /// of resetting each time, and survives a [`crate::vm::Vm::suspend`]/[`crate::vm::Vm::resume`]
+    /// round trip the same way the value/call stacks do.
+    pub(crate) fuel: Option<u64>,
+    /// Total instructions run against `fuel` so far; never reset on its own, only by a fresh
+    /// [`crate::vm::Vm::set_fuel`] call.
+    pub(crate) fuel_consumed: u64,
+}
+
+/// Default seed for [`RuntimeData::rng_state`] when a `Vm` is built without
+/// [`crate::vm::Vm::with_seed`]. Just needs to be nonzero - xorshift64 never recovers from a
+/// zero state.
+const DEFAULT_RNG_SEED: u64 = 0x2545_f491_4f6c_dd1d;
+
+/// One installed `Card::Try` handler: where to resume (`target`), and the value/call stack
+/// depths to unwind back to, recorded at the moment `PushHandler` ran.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct HandlerFrame {
+    pub target: u32,
+    pub value_stack_depth: usize,
+    pub call_stack_depth: usize,
+}
+
+/// Where the incremental collector currently is within a mark-and-sweep cycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum GcPhase {
+    Idle,
+    Marking,
+    /// `cursor` walks `object_list` backwards so objects can be removed via `swap_remove`
+    /// without disturbing the indices still left to visit.
+    Sweeping {
+        cursor: usize,
+    },
 }
 
 impl Drop for RuntimeData {
@@ -41,7 +114,13 @@ pub(crate) struct CallFrame {
     pub src_instr_ptr: u32,
     /// Store return addresses of Function calls
     pub dst_instr_ptr: u32,
-    /// beginning of the local stack
+    /// Base of this call's locals on the value stack: the first of its `num_params +
+    /// num_declared_locals` slots, reserved in one shot by
+    /// [`ValueStack::reserve_locals`](crate::collections::value_stack::ValueStack::reserve_locals)
+    /// when the call was made, and torn down by `clear_until(stack_offset)` in
+    /// [`crate::vm::instr_execution::instr_return`] on return. `SetLocalVar`/`ReadLocalVar`
+    /// index relative to this the same way a `get_local(frame, i)`/`set_local(frame, i, v)` pair
+    /// would.
     pub stack_offset: u32,
     pub closure: *mut CaoLangClosure,
 }
@@ -51,9 +130,26 @@ impl RuntimeData {
         memory_limit: usize,
         stack_size: usize,
         call_stack_size: usize,
+    ) -> Result<Pin<Box<Self>>, ExecutionErrorPayload> {
+        Self::with_backend(
+            memory_limit,
+            stack_size,
+            call_stack_size,
+            AllocBackend::System,
+        )
+    }
+
+    /// Like [`RuntimeData::new`], but services the heap from `backend` instead of the global
+    /// allocator. Use this to run the interpreter against a fixed, caller-provided byte span
+    /// (e.g. for `no_std`/embedded/WASM-with-fixed-heap builds).
+    pub fn with_backend(
+        memory_limit: usize,
+        stack_size: usize,
+        call_stack_size: usize,
+        backend: AllocBackend,
     ) -> Result<Pin<Box<Self>>, ExecutionErrorPayload> {
         // we have a chicken-egg problem if we want to store the allocator in this structure
-        let allocator = CaoLangAllocator::new(std::ptr::null_mut(), memory_limit);
+        let allocator = CaoLangAllocator::with_backend(core::ptr::null_mut(), memory_limit, backend);
         let memory: AllocProxy = allocator.into();
         let mut res = Box::pin(Self {
             value_stack: ValueStack::new(stack_size),
@@ -61,8 +157,16 @@ impl RuntimeData {
             global_vars: Vec::with_capacity(16),
             object_list: Vec::with_capacity(16),
             memory,
-            current_program: std::ptr::null(),
-            open_upvalues: std::ptr::null_mut(),
+            current_program: core::ptr::null(),
+            open_upvalues: Vec::new(),
+            closed_upvalues: Vec::new(),
+            gc_phase: GcPhase::Idle,
+            gray: Vec::new(),
+            pending_finalizers: Vec::new(),
+            handler_stack: Vec::new(),
+            rng_state: DEFAULT_RNG_SEED,
+            fuel: None,
+            fuel_consumed: 0,
         });
         unsafe {
             let reference: &mut Self = Pin::get_mut(res.as_mut());
@@ -89,9 +193,10 @@ impl RuntimeData {
             let obj_ptr: NonNull<CaoLangObject> = obj_ptr.cast();
             let obj = CaoLangObject {
                 marker: GcMarker::White,
+                finalizer: None,
                 body: CaoLangObjectBody::Table(table),
             };
-            std::ptr::write(obj_ptr.as_ptr(), obj);
+            core::ptr::write(obj_ptr.as_ptr(), obj);
             self.object_list.push(obj_ptr);
             Ok(ObjectGcGuard::new(obj_ptr))
         }
@@ -113,9 +218,10 @@ impl RuntimeData {
             let obj_ptr: NonNull<CaoLangObject> = obj_ptr.cast();
             let obj = CaoLangObject {
                 marker: GcMarker::White,
+                finalizer: None,
                 body: CaoLangObjectBody::NativeFunction(CaoLangNativeFunction { handle }),
             };
-            std::ptr::write(obj_ptr.as_ptr(), obj);
+            core::ptr::write(obj_ptr.as_ptr(), obj);
             self.object_list.push(obj_ptr);
 
             Ok(ObjectGcGuard::new(obj_ptr))
@@ -126,6 +232,7 @@ impl RuntimeData {
         &mut self,
         handle: Handle,
         arity: u32,
+        max_locals: u32,
     ) -> Result<ObjectGcGuard, ExecutionErrorPayload> {
         unsafe {
             let obj_ptr = self
@@ -139,9 +246,14 @@ impl RuntimeData {
             let obj_ptr: NonNull<CaoLangObject> = obj_ptr.cast();
             let obj = CaoLangObject {
                 marker: GcMarker::White,
-                body: CaoLangObjectBody::Function(CaoLangFunction { handle, arity }),
+                finalizer: None,
+                body: CaoLangObjectBody::Function(CaoLangFunction {
+                    handle,
+                    arity,
+                    max_locals,
+                }),
             };
-            std::ptr::write(obj_ptr.as_ptr(), obj);
+            core::ptr::write(obj_ptr.as_ptr(), obj);
             self.object_list.push(obj_ptr);
 
             Ok(ObjectGcGuard::new(obj_ptr))
@@ -152,6 +264,7 @@ impl RuntimeData {
         &mut self,
         handle: Handle,
         arity: u32,
+        max_locals: u32,
     ) -> Result<ObjectGcGuard, ExecutionErrorPayload> {
         unsafe {
             let obj_ptr = self
@@ -165,12 +278,17 @@ impl RuntimeData {
             let obj_ptr: NonNull<CaoLangObject> = obj_ptr.cast();
             let obj = CaoLangObject {
                 marker: GcMarker::White,
+                finalizer: None,
                 body: CaoLangObjectBody::Closure(CaoLangClosure {
-                    function: CaoLangFunction { handle, arity },
+                    function: CaoLangFunction {
+                        handle,
+                        arity,
+                        max_locals,
+                    },
                     upvalues: vec![],
                 }),
             };
-            std::ptr::write(obj_ptr.as_ptr(), obj);
+            core::ptr::write(obj_ptr.as_ptr(), obj);
             self.object_list.push(obj_ptr);
 
             Ok(ObjectGcGuard::new(obj_ptr))
@@ -179,7 +297,7 @@ impl RuntimeData {
 
     pub fn init_upvalue(
         &mut self,
-        location: *mut Value,
+        state: CaoLangUpvalue,
     ) -> Result<ObjectGcGuard, ExecutionErrorPayload> {
         unsafe {
             let obj_ptr = self
@@ -193,13 +311,10 @@ impl RuntimeData {
             let obj_ptr: NonNull<CaoLangObject> = obj_ptr.cast();
             let obj = CaoLangObject {
                 marker: GcMarker::White,
-                body: CaoLangObjectBody::Upvalue(CaoLangUpvalue {
-                    location,
-                    value: Value::Nil,
-                    next: std::ptr::null_mut(),
-                }),
+                finalizer: None,
+                body: CaoLangObjectBody::Upvalue(state),
             };
-            std::ptr::write(obj_ptr.as_ptr(), obj);
+            core::ptr::write(obj_ptr.as_ptr(), obj);
             self.object_list.push(obj_ptr);
 
             Ok(ObjectGcGuard::new(obj_ptr))
@@ -223,18 +338,109 @@ impl RuntimeData {
                 .map_err(|_| ExecutionErrorPayload::OutOfMemory)?;
 
             let result: *mut u8 = ptr.as_mut();
-            std::ptr::copy(payload.as_ptr(), result, payload.len());
+            core::ptr::copy(payload.as_ptr(), result, payload.len());
 
             let obj_ptr: NonNull<CaoLangObject> = obj_ptr.cast();
             let obj = CaoLangObject {
                 marker: GcMarker::White,
+                finalizer: None,
                 body: CaoLangObjectBody::String(CaoLangString {
                     len: payload.len(),
                     ptr,
                     alloc: self.memory.clone(),
                 }),
             };
-            std::ptr::write(obj_ptr.as_ptr(), obj);
+            core::ptr::write(obj_ptr.as_ptr(), obj);
+            self.object_list.push(obj_ptr);
+
+            Ok(ObjectGcGuard::new(obj_ptr))
+        }
+    }
+
+    /// Initialize a new cao-lang byte buffer holding a copy of `payload` and return a pointer to
+    /// it. Unlike [`RuntimeData::init_string`], `payload` need not be valid UTF-8.
+    pub fn init_bytes(&mut self, payload: &[u8]) -> Result<ObjectGcGuard, ExecutionErrorPayload> {
+        unsafe {
+            let obj_ptr = self
+                .memory
+                .alloc(Layout::new::<CaoLangObject>())
+                .map_err(|err| {
+                    debug!("Failed to allocate table {:?}", err);
+                    ExecutionErrorPayload::OutOfMemory
+                })?;
+
+            let layout = CaoLangBytes::layout(payload.len());
+            let mut ptr = self
+                .memory
+                .alloc(layout)
+                .map_err(|_| ExecutionErrorPayload::OutOfMemory)?;
+
+            let result: *mut u8 = ptr.as_mut();
+            core::ptr::copy(payload.as_ptr(), result, payload.len());
+
+            let obj_ptr: NonNull<CaoLangObject> = obj_ptr.cast();
+            let obj = CaoLangObject {
+                marker: GcMarker::White,
+                finalizer: None,
+                body: CaoLangObjectBody::Bytes(CaoLangBytes {
+                    len: payload.len(),
+                    ptr,
+                    alloc: self.memory.clone(),
+                }),
+            };
+            core::ptr::write(obj_ptr.as_ptr(), obj);
+            self.object_list.push(obj_ptr);
+
+            Ok(ObjectGcGuard::new(obj_ptr))
+        }
+    }
+
+    /// Initialize a new cao-lang big integer holding `value` and return a pointer to it. Only
+    /// meant to be called with a `value` outside `i64`'s range - see [`Vm::checked_arith_op`].
+    pub fn init_bigint(&mut self, value: i128) -> Result<ObjectGcGuard, ExecutionErrorPayload> {
+        unsafe {
+            let obj_ptr = self
+                .memory
+                .alloc(Layout::new::<CaoLangObject>())
+                .map_err(|err| {
+                    debug!("Failed to allocate BigInt {:?}", err);
+                    ExecutionErrorPayload::OutOfMemory
+                })?;
+
+            let obj_ptr: NonNull<CaoLangObject> = obj_ptr.cast();
+            let obj = CaoLangObject {
+                marker: GcMarker::White,
+                finalizer: None,
+                body: CaoLangObjectBody::BigInt(CaoLangBigInt(value)),
+            };
+            core::ptr::write(obj_ptr.as_ptr(), obj);
+            self.object_list.push(obj_ptr);
+
+            Ok(ObjectGcGuard::new(obj_ptr))
+        }
+    }
+
+    /// Initialize a new lazy iterator object wrapping `body` and return a pointer to it.
+    pub fn init_iterator(
+        &mut self,
+        body: CaoLangIterator,
+    ) -> Result<ObjectGcGuard, ExecutionErrorPayload> {
+        unsafe {
+            let obj_ptr = self
+                .memory
+                .alloc(Layout::new::<CaoLangObject>())
+                .map_err(|err| {
+                    debug!("Failed to allocate Iterator {:?}", err);
+                    ExecutionErrorPayload::OutOfMemory
+                })?;
+
+            let obj_ptr: NonNull<CaoLangObject> = obj_ptr.cast();
+            let obj = CaoLangObject {
+                marker: GcMarker::White,
+                finalizer: None,
+                body: CaoLangObjectBody::Iterator(body),
+            };
+            core::ptr::write(obj_ptr.as_ptr(), obj);
             self.object_list.push(obj_ptr);
 
             Ok(ObjectGcGuard::new(obj_ptr))
@@ -243,7 +449,7 @@ impl RuntimeData {
 
     pub fn free_object(&mut self, obj: NonNull<CaoLangObject>) {
         unsafe {
-            std::ptr::drop_in_place(obj.as_ptr());
+            core::ptr::drop_in_place(obj.as_ptr());
             self.memory
                 .dealloc(obj.cast(), Layout::new::<CaoLangObject>());
         }
@@ -254,10 +460,11 @@ impl RuntimeData {
         self.value_stack.clear();
         self.global_vars.clear();
         self.call_stack.clear();
+        self.handler_stack.clear();
     }
 
     fn clear_objects(&mut self) {
-        for obj_ptr in std::mem::take(&mut self.object_list).into_iter() {
+        for obj_ptr in core::mem::take(&mut self.object_list).into_iter() {
             self.free_object(obj_ptr);
         }
     }
@@ -268,8 +475,107 @@ impl RuntimeData {
             self.memory
                 .get_inner()
                 .limit
-                .store(capacity, std::sync::atomic::Ordering::Relaxed);
+                .store(capacity, core::sync::atomic::Ordering::Relaxed);
+        }
+    }
+
+    /// Sets the allocated-bytes threshold (see [`CaoLangAllocator::alloc`]) past which the next
+    /// allocation starts an incremental collection cycle, instead of leaving it pinned to the
+    /// quarter of the memory limit it's seeded with in [`CaoLangAllocator::with_backend`]. A
+    /// lower threshold collects more eagerly (smaller heaps, more frequent pauses); a higher one
+    /// lets more garbage pile up between cycles.
+    pub fn set_gc_threshold(&mut self, threshold: usize) {
+        unsafe {
+            self.memory
+                .get_inner()
+                .next_gc
+                .store(threshold, core::sync::atomic::Ordering::Relaxed);
+        }
+    }
+
+    /// Sets how much bigger `next_gc` grows relative to the surviving heap size once a cycle
+    /// finishes sweeping (e.g. `2.0` lets the heap double before the next cycle triggers). Takes
+    /// effect starting with the next cycle's retune; does not touch the current `next_gc` value.
+    ///
+    /// Only has an effect while the allocator's [`GcPolicy`] is the default
+    /// [`DoublingGcPolicy`](crate::alloc::DoublingGcPolicy) - an embedder that installed a custom
+    /// policy via [`Self::set_gc_policy`] owns its own cadence, and this is a no-op for it.
+    pub fn set_gc_heap_growth_factor(&mut self, factor: f64) {
+        let percent = ((factor * 100.0) as usize).max(100);
+        unsafe {
+            if let Some(policy) = self.memory.get_inner().policy.as_doubling_policy() {
+                policy.set_growth_factor_percent(percent);
+            }
+        }
+    }
+
+    /// Replaces the allocator's [`GcPolicy`], letting an embedder swap out the default doubling
+    /// heuristic for a fixed-increment, percentage-of-limit, or time/allocation-hybrid cadence of
+    /// their own. Takes effect starting with the next call to
+    /// [`CaoLangAllocator::alloc`]/`realloc`.
+    pub fn set_gc_policy(&mut self, policy: Box<dyn GcPolicy>) {
+        unsafe {
+            self.memory.get_inner().policy = policy;
+        }
+    }
+
+    /// Disables the allocator's automatic collection trigger: [`CaoLangAllocator::alloc`] will no
+    /// longer start a new cycle just because `next_gc` was crossed, though a cycle already in
+    /// progress still runs to completion. Re-enable with `set_auto_gc_enabled(true)`, or drive
+    /// collection explicitly via [`crate::vm::Vm::collect_garbage`].
+    pub fn set_auto_gc_enabled(&mut self, enabled: bool) {
+        unsafe {
+            self.memory
+                .get_inner()
+                .auto_gc_enabled
+                .store(enabled, core::sync::atomic::Ordering::Relaxed);
+        }
+    }
+
+    /// Replace the call stack with a freshly sized one, dropping whatever call frames (and, via
+    /// [`RuntimeData::clear`], value stack/heap contents) were live - only meant to be called
+    /// right after construction, before a program has started running.
+    pub fn set_call_stack_limit(&mut self, capacity: usize) {
+        self.clear();
+        self.call_stack = BoundedStack::new(capacity);
+    }
+
+    /// Replace the value stack with a freshly sized one, dropping whatever was live (via
+    /// [`RuntimeData::clear`]) - only meant to be called right after construction, before a
+    /// program has started running.
+    pub fn set_stack_limit(&mut self, capacity: usize) {
+        self.clear();
+        self.value_stack = ValueStack::new(capacity);
+    }
+
+    /// Re-seed the RNG backing [`Instruction::Random`](crate::instruction::Instruction::Random)/
+    /// [`Instruction::DiceRoll`](crate::instruction::Instruction::DiceRoll), so a program driven
+    /// by the same seed always rolls the same sequence. A `seed` of `0` falls back to
+    /// [`DEFAULT_RNG_SEED`], since xorshift64 can never advance past a zero state.
+    pub fn seed_rng(&mut self, seed: u64) {
+        self.rng_state = if seed == 0 { DEFAULT_RNG_SEED } else { seed };
+    }
+
+    /// Advances the xorshift64 generator one step and returns the new state.
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.rng_state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.rng_state = x;
+        x
+    }
+
+    /// Rolls a uniform integer in the inclusive range `[lo, hi]`, backing
+    /// [`Instruction::Random`](crate::instruction::Instruction::Random). Errors if `hi < lo`.
+    pub fn next_random_range(&mut self, lo: i64, hi: i64) -> Result<i64, ExecutionErrorPayload> {
+        if hi < lo {
+            return Err(ExecutionErrorPayload::invalid_argument(
+                "Random's upper bound must not be less than its lower bound",
+            ));
         }
+        let span = (hi - lo + 1) as u64;
+        Ok(lo + (self.next_u64() % span) as i64)
     }
 
     /// Types implementing Drop are not supported, thus the `Copy` bound
@@ -277,120 +583,218 @@ impl RuntimeData {
         &mut self,
         val: T,
     ) -> Result<*mut T, ExecutionErrorPayload> {
-        let l = std::alloc::Layout::new::<T>();
+        let l = Layout::new::<T>();
         unsafe {
             let ptr = self
                 .memory
                 .alloc(l)
                 .map_err(|_| ExecutionErrorPayload::OutOfMemory)?;
 
-            std::ptr::write(ptr.as_ptr() as *mut T, val);
+            core::ptr::write(ptr.as_ptr() as *mut T, val);
             Ok(ptr.as_ptr() as *mut T)
         }
     }
 
-    pub fn gc(&mut self) {
-        debug!("• GC");
-        // mark all roots for collection
-        let mut progress_tracker = Vec::with_capacity(self.value_stack.len());
-        for val in self.value_stack.iter() {
-            if let Value::Object(mut t) = val {
-                unsafe {
-                    let t = t.as_mut();
-                    t.marker = GcMarker::Gray;
-                    progress_tracker.push(t);
+    /// Enqueues `val` onto the gray worklist if it is an object that hasn't been seen yet this
+    /// cycle. Also grays a still-`Protected` object: an [`ObjectGcGuard`] held by an in-flight
+    /// native call reverts to `White` the moment it drops (see its `Drop` impl), and a root write
+    /// barrier (e.g. [`Self::gc_root_write_barrier`] from [`crate::vm::Vm::stack_push`]) commonly
+    /// fires on such an object *before* its guard has dropped - without this it would be a no-op
+    /// here, leaving the object to fall back to `White` ungrayed once the guard does drop, with
+    /// nothing left to re-discover it before the cycle sweeps.
+    fn gc_enqueue_value(&mut self, val: Value) {
+        if let Value::Object(mut obj) = val {
+            unsafe {
+                if matches!(obj.as_ref().marker, GcMarker::White | GcMarker::Protected) {
+                    obj.as_mut().marker = GcMarker::Gray;
+                    self.gray.push(obj);
                 }
             }
         }
-        // mark globals
-        for val in self.global_vars.iter() {
-            if let Value::Object(mut t) = val {
-                unsafe {
-                    let t = t.as_mut();
-                    t.marker = GcMarker::Gray;
-                    progress_tracker.push(t);
-                }
-            }
+    }
+
+    /// Write barrier: re-grays `parent` if it is already `Black` and `child` is a still-`White`
+    /// heap object, so it gets rescanned before the current cycle sweeps. Without this, an
+    /// object fully marked earlier in an incremental cycle could be mutated afterwards to point
+    /// at an object nothing else references, and that object would wrongly get swept as garbage.
+    /// A no-op for every other `parent` marker, since `Gray`/`Protected` objects are (or still
+    /// will be) scanned anyway and a `White` one isn't reachable from a root yet.
+    ///
+    /// Call this whenever code stores a new reference into an existing, already-allocated heap
+    /// object - [`crate::vm::Instruction::SetProperty`]/[`crate::vm::Instruction::AppendTable`]
+    /// do this for tables.
+    ///
+    /// [`crate::vm::Instruction::SetProperty`]: crate::instruction::Instruction::SetProperty
+    /// [`crate::vm::Instruction::AppendTable`]: crate::instruction::Instruction::AppendTable
+    pub(crate) fn write_barrier(&mut self, parent: NonNull<CaoLangObject>, child: Value) {
+        let parent_is_black = unsafe { matches!(parent.as_ref().marker, GcMarker::Black) };
+        if parent_is_black {
+            // shade the child gray so it still gets scanned this cycle, rather than re-graying
+            // `parent` (which would mean rescanning referents it has already fully marked)
+            self.gc_enqueue_value(child);
         }
+    }
 
-        macro_rules! checked_enqueue_value {
-            ($val: ident) => {
-                if let Value::Object(mut value) = $val {
-                    let t = value.as_mut();
-                    if matches!(t.marker, GcMarker::White) {
-                        t.marker = GcMarker::Gray;
-                        progress_tracker.push(t);
-                    }
-                }
-            };
+    /// Write barrier for root-like storage that [`Self::gc_mark_roots`] only ever scans once, at
+    /// the start of a cycle: the global variable table, the value stack, and closed-over upvalue
+    /// slots. Unlike [`Self::write_barrier`], there's no parent object whose marker to check -
+    /// these slots are roots, so treat them as permanently black and shade whatever gets written
+    /// into them for the rest of the current cycle. A no-op while the collector is `Idle`, since
+    /// the next cycle's `gc_mark_roots` will see the new value anyway.
+    ///
+    /// Call this whenever code writes into a root slot: [`crate::vm::Vm::stack_push`] does this
+    /// for the value stack, and [`crate::vm::instr_execution::instr_set_var`] /
+    /// [`crate::vm::instr_execution::write_upvalue`] do this for globals and open upvalues.
+    pub(crate) fn gc_root_write_barrier(&mut self, val: Value) {
+        if self.gc_phase != GcPhase::Idle {
+            self.gc_enqueue_value(val);
         }
+    }
 
-        // mark referenced objects for collection
-        while let Some(obj) = progress_tracker.pop() {
-            obj.marker = GcMarker::Black;
-            match &mut obj.body {
-                CaoLangObjectBody::Table(obj) => {
-                    for (key, value) in obj.iter() {
-                        unsafe {
-                            checked_enqueue_value!(key);
-                            checked_enqueue_value!(value);
+    /// Marks the roots (value stack + globals) gray and moves the collector into the `Marking`
+    /// phase. No-op if a cycle is already in progress.
+    fn gc_mark_roots(&mut self) {
+        if self.gc_phase != GcPhase::Idle {
+            return;
+        }
+        for val in self.value_stack.as_slice().iter().copied() {
+            self.gc_enqueue_value(val);
+        }
+        for val in self.global_vars.iter().copied() {
+            self.gc_enqueue_value(val);
+        }
+        self.gc_phase = GcPhase::Marking;
+    }
+
+    /// Performs at most `budget` units of marking work (one unit = one gray object blackened).
+    /// Transitions to `Sweeping` once the gray worklist drains. Returns `true` once marking is
+    /// done (either just now, or already done on entry).
+    fn gc_mark_step(&mut self, budget: usize) -> bool {
+        for _ in 0..budget {
+            let Some(mut obj) = self.gray.pop() else {
+                self.gc_phase = GcPhase::Sweeping {
+                    cursor: self.object_list.len(),
+                };
+                return true;
+            };
+            unsafe {
+                let obj = obj.as_mut();
+                obj.marker = GcMarker::Black;
+                match &obj.body {
+                    CaoLangObjectBody::Table(table) => {
+                        let referents: Vec<(Value, Value)> =
+                            table.iter().map(|(k, v)| (*k, *v)).collect();
+                        for (key, value) in referents {
+                            self.gc_enqueue_value(key);
+                            self.gc_enqueue_value(value);
                         }
                     }
-                }
-                CaoLangObjectBody::Closure(c) => {
-                    for upvalue in &mut c.upvalues {
-                        unsafe {
-                            let t = upvalue.as_mut();
-                            if matches!(t.marker, GcMarker::White) {
-                                t.marker = GcMarker::Gray;
-                                progress_tracker.push(t);
-                            }
+                    CaoLangObjectBody::Closure(c) => {
+                        let upvalues = c.upvalues.clone();
+                        for upvalue in upvalues {
+                            self.gc_enqueue_value(Value::Object(upvalue));
                         }
                     }
-                }
-                CaoLangObjectBody::String(_) => {
-                    // strings don't have children
-                }
-                CaoLangObjectBody::Function(_) => {
-                    // function objects don't have children
-                }
-                CaoLangObjectBody::NativeFunction(_) => {
-                    // native function objects don't have children
-                }
-                CaoLangObjectBody::Upvalue(u) => unsafe {
-                    if let Some(t) = u.location.as_mut() {
-                        checked_enqueue_value!(t);
+                    CaoLangObjectBody::Upvalue(u) => {
+                        let val = match *u {
+                            CaoLangUpvalue::Open(index) => self.value_stack.as_slice()[index],
+                            CaoLangUpvalue::Closed(handle) => self.closed_upvalues[handle],
+                        };
+                        self.gc_enqueue_value(val);
                     }
-                },
+                    CaoLangObjectBody::String(_)
+                    | CaoLangObjectBody::Bytes(_)
+                    | CaoLangObjectBody::BigInt(_)
+                    | CaoLangObjectBody::Function(_)
+                    | CaoLangObjectBody::NativeFunction(_) => {
+                        // leaf objects: no children to mark
+                    }
+                    CaoLangObjectBody::Iterator(it) => match it {
+                        CaoLangIterator::Table { source, .. }
+                        | CaoLangIterator::Chars { source, .. } => {
+                            self.gc_enqueue_value(Value::Object(*source));
+                        }
+                        CaoLangIterator::Range { .. } => {
+                            // no heap references: just a pair of integers
+                        }
+                        CaoLangIterator::Native { callback, .. } => {
+                            self.gc_enqueue_value(*callback);
+                        }
+                        CaoLangIterator::Map { source, callback }
+                        | CaoLangIterator::Filter { source, callback } => {
+                            self.gc_enqueue_value(Value::Object(*source));
+                            self.gc_enqueue_value(*callback);
+                        }
+                    },
+                }
             }
         }
-        // sweep
-        //
-        let mut collected = Vec::with_capacity(self.object_list.len());
-        for (i, object) in self.object_list.iter().copied().enumerate() {
-            unsafe {
-                let obj = object.as_ref();
-                if matches!(obj.marker, GcMarker::White) {
-                    collected.push(i);
+        matches!(self.gc_phase, GcPhase::Sweeping { .. })
+    }
+
+    /// Performs at most `budget` units of sweep work (one unit = one object inspected). White,
+    /// finalizer-less objects are freed right away; white objects with a finalizer are moved
+    /// into `pending_finalizers` instead, since only [`crate::vm::Vm`] can invoke native code.
+    /// Returns `true` once the whole object list has been swept this cycle.
+    fn gc_sweep_step(&mut self, budget: usize) -> bool {
+        let GcPhase::Sweeping { mut cursor } = self.gc_phase else {
+            return true;
+        };
+        for _ in 0..budget {
+            if cursor == 0 {
+                for mut obj in self.object_list.iter().copied() {
+                    unsafe {
+                        let obj = obj.as_mut();
+                        if !matches!(obj.marker, GcMarker::Protected) {
+                            obj.marker = GcMarker::White;
+                        }
+                    }
                 }
+                self.gc_phase = GcPhase::Idle;
+                return true;
             }
-        }
-        for i in collected.into_iter().rev() {
-            let obj = self.object_list.swap_remove(i);
-            self.free_object(obj);
-        }
-        // unmark remaning objects
-        for table in self.object_list.iter_mut() {
-            unsafe {
-                let table = table.as_mut();
-                if !matches!(table.marker, GcMarker::Protected) {
-                    table.marker = GcMarker::White;
+            cursor -= 1;
+            let obj = self.object_list[cursor];
+            let is_white = unsafe { matches!(obj.as_ref().marker, GcMarker::White) };
+            if is_white {
+                self.object_list.swap_remove(cursor);
+                let has_finalizer = unsafe { obj.as_ref().finalizer.is_some() };
+                if has_finalizer {
+                    self.pending_finalizers.push(obj);
+                } else {
+                    self.free_object(obj);
                 }
             }
         }
+        self.gc_phase = GcPhase::Sweeping { cursor };
+        false
+    }
+
+    /// Runs a full, synchronous mark-and-sweep collection, driving the incremental primitives
+    /// above to completion in one call. Objects with a registered finalizer are left in
+    /// `pending_finalizers`, not freed -- only [`crate::vm::Vm::gc_step`] can run their
+    /// finalizer and reclaim them.
+    pub fn gc(&mut self) {
+        debug!("• GC");
+        self.gc_mark_roots();
+        while !self.gc_mark_step(usize::MAX) {}
+        while !self.gc_sweep_step(usize::MAX) {}
         debug!("✓ GC");
     }
 
+    /// Drives the incremental collector forward by roughly `budget` units of work, starting a
+    /// new cycle if the collector is idle. Returns `true` once a full cycle (mark + sweep)
+    /// completes within this call; the caller (only [`crate::vm::Vm::gc_step`] has the native
+    /// callables needed to drain `pending_finalizers`) is responsible for finalizing and freeing
+    /// anything left in `pending_finalizers` once a cycle completes.
+    pub(crate) fn gc_work(&mut self, budget: usize) -> bool {
+        self.gc_mark_roots();
+        if !matches!(self.gc_phase, GcPhase::Sweeping { .. }) && !self.gc_mark_step(budget) {
+            return false;
+        }
+        self.gc_sweep_step(budget)
+    }
+
     pub fn capture_upvalue() {}
 }
 
@@ -415,4 +819,96 @@ mod tests {
 
         assert_eq!(res, &Value::Integer(42));
     }
+
+    #[test]
+    fn table_lookup_treats_integral_reals_as_the_same_key_as_their_integer_test() {
+        // `Value`'s `Eq`/`Hash` must agree (see `Value::eq_with_seen`/`canonical_hash_key`):
+        // inserting under an `Integer` key and reading back with the numerically equal `Real`
+        // (and vice versa) must hit the same table row, across a spread of representative values.
+        let samples: &[i64] = &[0, 1, -1, 42, -42, i32::MAX as i64, i32::MIN as i64];
+        for &i in samples {
+            let mut vm = Vm::new(()).unwrap();
+            let mut table = vm.init_table().unwrap();
+            let table = table.deref_mut().as_table_mut().unwrap();
+
+            table.insert(Value::Integer(i), Value::Integer(1)).unwrap();
+            assert_eq!(table.get(Value::Real(i as f64)), Some(&Value::Integer(1)));
+
+            table
+                .insert(Value::Real((i + 1) as f64), Value::Integer(2))
+                .unwrap();
+            assert_eq!(table.get(Value::Integer(i + 1)), Some(&Value::Integer(2)));
+        }
+    }
+
+    #[test]
+    fn sorted_iter_orders_keys_by_the_documented_tier_then_value_test() {
+        // `Nil < numbers < strings < bytes < tables` (see `Value`'s `Ord` impl) - mix keys from
+        // every tier plus within-tier ties (two numbers, two strings) and check `sorted_iter`
+        // comes back in exactly that order regardless of insertion order.
+        let mut vm = Vm::new(()).unwrap();
+        let mut table = vm.init_table().unwrap();
+        let table = table.deref_mut().as_table_mut().unwrap();
+
+        let bytes = vm.init_bytes(&[1, 2, 3]).unwrap();
+        let nested = vm.init_table().unwrap();
+        let hello = vm.init_string("hello").unwrap();
+        let apple = vm.init_string("apple").unwrap();
+
+        table
+            .insert(Value::Object(nested.into_inner()), Value::Nil)
+            .unwrap();
+        table
+            .insert(Value::Object(bytes.into_inner()), Value::Nil)
+            .unwrap();
+        table.insert(Value::Integer(5), Value::Nil).unwrap();
+        table
+            .insert(Value::Object(hello.into_inner()), Value::Nil)
+            .unwrap();
+        table.insert(Value::Nil, Value::Nil).unwrap();
+        table.insert(Value::Integer(-1), Value::Nil).unwrap();
+        table
+            .insert(Value::Object(apple.into_inner()), Value::Nil)
+            .unwrap();
+
+        fn tier(k: &Value) -> u8 {
+            match k {
+                Value::Nil => 0,
+                Value::Integer(_) => 1,
+                Value::Object(o) => unsafe {
+                    if o.as_ref().as_str().is_some() {
+                        2
+                    } else if o.as_ref().as_bytes().is_some() {
+                        3
+                    } else {
+                        4
+                    }
+                },
+                _ => unreachable!(),
+            }
+        }
+
+        let sorted = table.sorted_iter();
+        let tiers: Vec<u8> = sorted.iter().map(|&(k, _)| tier(k)).collect();
+        assert_eq!(tiers, vec![0, 1, 1, 2, 2, 3, 4]);
+
+        let numbers: Vec<i64> = sorted[1..3].iter().map(|&(k, _)| k.as_int().unwrap()).collect();
+        assert_eq!(numbers, vec![-1, 5]);
+
+        let strings: Vec<&str> = sorted[3..5]
+            .iter()
+            .map(|&(k, _)| unsafe { k.as_str().unwrap() })
+            .collect();
+        assert_eq!(strings, vec!["apple", "hello"]);
+    }
+
+    #[test]
+    fn bytes_round_trip_test() {
+        let mut vm = Vm::new(()).unwrap();
+
+        let b = vm.init_bytes(&[0xde, 0xad, 0xbe, 0xef]).unwrap();
+        let obj = unsafe { b.into_inner().as_ref() };
+
+        assert_eq!(obj.as_bytes().unwrap(), &[0xde, 0xad, 0xbe, 0xef]);
+    }
 }
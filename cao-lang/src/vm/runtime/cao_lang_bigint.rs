@@ -0,0 +1,25 @@
+/// A heap-allocated integer wider than [`crate::value::Value::Integer`]'s `i64`, backing
+/// [`super::cao_lang_object::CaoLangObjectBody::BigInt`]. `Add`/`Sub`/`Mul` on two `i64`s promote
+/// here instead of wrapping once they'd overflow - see [`crate::vm::Vm::checked_arith_op`].
+///
+/// Backed by `i128` rather than an unbounded limb vector: doubling the width is exactly enough to
+/// hold the result of any single `i64 op i64`, which covers the overflow this type exists to
+/// catch. A chain of `BigInt op BigInt` operations that would itself overflow `i128` is out of
+/// scope - true unbounded precision would need a full limb-vector bignum, which is a much larger
+/// and riskier change to make without a compiler to check it against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CaoLangBigInt(pub i128);
+
+impl CaoLangBigInt {
+    /// Narrows back down to `i64` if `self` is in range - the common case right after a promoted
+    /// operation's result turns out to fit after all (e.g. `BIG_NUMBER - BIG_NUMBER`).
+    pub fn to_i64(self) -> Option<i64> {
+        i64::try_from(self.0).ok()
+    }
+}
+
+impl core::fmt::Display for CaoLangBigInt {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
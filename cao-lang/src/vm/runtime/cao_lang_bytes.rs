@@ -0,0 +1,46 @@
+use core::{alloc::Layout, fmt::Debug, ptr::NonNull};
+
+use crate::alloc::AllocProxy;
+
+/// CaoLang Bytes are opaque binary blobs - unlike [`super::cao_lang_string::CaoLangString`], the
+/// payload is not required to be valid UTF-8.
+pub struct CaoLangBytes {
+    pub(crate) len: usize,
+    pub(crate) ptr: NonNull<u8>,
+    pub(crate) alloc: AllocProxy,
+}
+
+impl Debug for CaoLangBytes {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Bytes: {:?}", self.as_bytes())
+    }
+}
+
+impl Drop for CaoLangBytes {
+    fn drop(&mut self) {
+        unsafe { self.alloc.dealloc(self.ptr.into(), Self::layout(self.len)) }
+    }
+}
+
+impl CaoLangBytes {
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe { core::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+
+    pub fn as_bytes_mut(&mut self) -> &mut [u8] {
+        unsafe { core::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Layout of a byte buffer with given length
+    pub(crate) fn layout(len: usize) -> Layout {
+        Layout::array::<u8>(len).unwrap()
+    }
+}
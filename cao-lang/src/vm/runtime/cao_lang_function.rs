@@ -1,5 +1,6 @@
-use std::ptr::NonNull;
+use core::ptr::NonNull;
 
+use crate::alloc_crate::vec::Vec;
 use crate::{prelude::Handle, value::Value};
 
 use super::cao_lang_object::CaoLangObject;
@@ -8,6 +9,10 @@ use super::cao_lang_object::CaoLangObject;
 pub struct CaoLangFunction {
     pub handle: Handle,
     pub arity: u32,
+    /// Number of local slots this function needs at most at any one time, reserved in a single
+    /// [`crate::collections::value_stack::ValueStack`] extension when its [`super::CallFrame`] is
+    /// pushed.
+    pub max_locals: u32,
 }
 
 #[derive(Debug)]
@@ -20,8 +25,8 @@ pub struct CaoLangClosure {
     pub upvalues: Vec<NonNull<CaoLangObject>>,
 }
 
-impl std::fmt::Debug for CaoLangClosure {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Debug for CaoLangClosure {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("CaoLangClosure")
             .field("function", &self.function)
             .field(
@@ -36,9 +41,19 @@ impl std::fmt::Debug for CaoLangClosure {
     }
 }
 
-#[derive(Debug)]
-pub struct CaoLangUpvalue {
-    pub location: *mut Value,
-    pub value: Value,
-    pub next: *mut CaoLangObject,
+/// Where an upvalue's captured value currently lives. Represented as indices rather than a raw
+/// `*mut Value`/intrusive pointer so `RuntimeData` stays relocatable: a `Vec` may reallocate its
+/// backing storage on growth (invalidating a pointer into it), but an index survives that, and
+/// both variants survive being copied out of a [`super::RuntimeData::snapshot`]/
+/// [`super::RuntimeData::restore`] round trip.
+#[derive(Debug, Clone, Copy)]
+pub enum CaoLangUpvalue {
+    /// The captured local is still live on the value stack, at this absolute index. Tracked in
+    /// [`super::RuntimeData::open_upvalues`] so two closures capturing the same local share one
+    /// upvalue (and observe each other's writes) instead of getting independent copies.
+    Open(usize),
+    /// The frame that owned the captured local has returned (or the upvalue was force-closed
+    /// ahead of a snapshot). The value now lives at this index in
+    /// [`super::RuntimeData::closed_upvalues`].
+    Closed(usize),
 }
@@ -0,0 +1,44 @@
+use core::ptr::NonNull;
+
+use crate::value::Value;
+
+use super::cao_lang_object::CaoLangObject;
+
+/// Lazy, pull-based iteration state backing a
+/// [`super::cao_lang_object::CaoLangObjectBody::Iterator`] object.
+///
+/// [`crate::vm::Vm::iterator_pull`] drives these: `Table`/`Chars`/`Range` read their own source
+/// directly, `Map`/`Filter` recursively pull from `source` and apply `callback` before yielding,
+/// and `Native` calls `callback` to produce each row in turn, so a chain of adapters never
+/// allocates a table of its own until something actually materializes it (see
+/// `stdlib::native_to_array`).
+#[derive(Debug)]
+pub enum CaoLangIterator {
+    /// Walks `source` (a `Table` object) by position.
+    Table {
+        source: NonNull<CaoLangObject>,
+        next: usize,
+    },
+    /// Walks `source` (a `String` object) one `char` at a time.
+    Chars {
+        source: NonNull<CaoLangObject>,
+        next: usize,
+    },
+    /// Walks the integers `next..end`, exclusive of `end`.
+    Range { next: i64, end: i64 },
+    /// Calls `callback(index)` for `index` counting up from 0, stopping the first time it
+    /// returns [`Value::Nil`]. Lets embedders and stdlib code expose an arbitrary sequence -
+    /// generated on the fly, backed by an external resource, etc. - as something a `for_each`
+    /// loop can consume like any other iterable.
+    Native { callback: Value, next: i64 },
+    /// Pulls `(i, k, v)` from `source`, then replaces `v` with `callback(i, v, k)`.
+    Map {
+        source: NonNull<CaoLangObject>,
+        callback: Value,
+    },
+    /// Pulls `(i, k, v)` from `source`, skipping rows for which `callback(i, v, k)` is falsy.
+    Filter {
+        source: NonNull<CaoLangObject>,
+        callback: Value,
+    },
+}
@@ -1,21 +1,26 @@
-use std::ptr::NonNull;
+use core::ptr::NonNull;
 
-use crate::value::Value;
+use crate::alloc_crate::vec::Vec;
+use crate::{prelude::Handle, value::Value};
 
 use super::{
+    cao_lang_bigint::CaoLangBigInt,
+    cao_lang_bytes::CaoLangBytes,
     cao_lang_function::{CaoLangClosure, CaoLangFunction, CaoLangNativeFunction, CaoLangUpvalue},
+    cao_lang_iterator::CaoLangIterator,
     cao_lang_string::CaoLangString,
     cao_lang_table::CaoLangTable,
 };
 
-// note Gray is not actually useful for now, but it might come in handy if we want to do finalizers
+/// Tri-color marking state used by the incremental collector in
+/// [`crate::vm::runtime::RuntimeData::gc`]/[`crate::vm::runtime::RuntimeData::gc_work`].
 #[derive(Debug, Clone, Copy)]
 pub enum GcMarker {
     /// Unprocessed
     White,
-    /// Visited
+    /// Discovered, but its referents are not yet marked
     Gray,
-    /// Done
+    /// Done: this object and everything it points to has been marked
     Black,
     /// This object can not be collected
     Protected,
@@ -24,6 +29,9 @@ pub enum GcMarker {
 #[derive(Debug)]
 pub struct CaoLangObject {
     pub marker: GcMarker,
+    /// Native function invoked just before this object is deallocated, if it turns out to be
+    /// unreachable. See [`crate::vm::Vm::gc_step`].
+    pub finalizer: Option<Handle>,
     pub body: CaoLangObjectBody,
 }
 
@@ -31,17 +39,20 @@ pub struct CaoLangObject {
 pub enum CaoLangObjectBody {
     Table(CaoLangTable),
     String(CaoLangString),
+    Bytes(CaoLangBytes),
+    BigInt(CaoLangBigInt),
     Function(CaoLangFunction),
     NativeFunction(CaoLangNativeFunction),
     Closure(CaoLangClosure),
     Upvalue(CaoLangUpvalue),
+    Iterator(CaoLangIterator),
 }
 
 /// RAII style guard that ensures that an object survives the GC
 /// Useful for native function that allocate multiple objects, potentially triggering GC
 pub struct ObjectGcGuard(pub(crate) NonNull<CaoLangObject>);
 
-impl std::ops::Deref for ObjectGcGuard {
+impl core::ops::Deref for ObjectGcGuard {
     type Target = CaoLangObject;
 
     fn deref(&self) -> &Self::Target {
@@ -49,7 +60,7 @@ impl std::ops::Deref for ObjectGcGuard {
     }
 }
 
-impl std::ops::DerefMut for ObjectGcGuard {
+impl core::ops::DerefMut for ObjectGcGuard {
     fn deref_mut(&mut self) -> &mut Self::Target {
         unsafe { self.0.as_mut() }
     }
@@ -58,7 +69,16 @@ impl std::ops::DerefMut for ObjectGcGuard {
 impl Drop for ObjectGcGuard {
     fn drop(&mut self) {
         unsafe {
-            self.0.as_mut().marker = GcMarker::White;
+            let obj = self.0.as_mut();
+            // Only clear a marker this guard itself is still responsible for. A write barrier
+            // fired while the guard was held (e.g. `RuntimeData::gc_root_write_barrier` from
+            // `Vm::stack_push`, once the still-`Protected` object lands on a root slot mid-cycle)
+            // may have already advanced it to `Gray`/`Black` so the current cycle keeps tracking
+            // it - stomping that back to `White` here would undo the barrier and let the object
+            // get swept while still reachable.
+            if matches!(obj.marker, GcMarker::Protected) {
+                obj.marker = GcMarker::White;
+            }
         }
     }
 }
@@ -87,10 +107,13 @@ impl CaoLangObject {
         match &self.body {
             CaoLangObjectBody::Table(_) => "Table",
             CaoLangObjectBody::String(_) => "String",
+            CaoLangObjectBody::Bytes(_) => "Bytes",
+            CaoLangObjectBody::BigInt(_) => "BigInt",
             CaoLangObjectBody::Function(_) => "Function",
             CaoLangObjectBody::NativeFunction(_) => "NativeFunction",
             CaoLangObjectBody::Closure(_) => "Closure",
             CaoLangObjectBody::Upvalue(_) => "Upvalue",
+            CaoLangObjectBody::Iterator(_) => "Iterator",
         }
     }
 
@@ -115,6 +138,27 @@ impl CaoLangObject {
         }
     }
 
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match &self.body {
+            CaoLangObjectBody::Bytes(b) => Some(b.as_bytes()),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes_mut(&mut self) -> Option<&mut [u8]> {
+        match &mut self.body {
+            CaoLangObjectBody::Bytes(b) => Some(b.as_bytes_mut()),
+            _ => None,
+        }
+    }
+
+    pub fn as_bigint(&self) -> Option<CaoLangBigInt> {
+        match &self.body {
+            CaoLangObjectBody::BigInt(b) => Some(*b),
+            _ => None,
+        }
+    }
+
     pub fn as_function(&self) -> Option<&CaoLangFunction> {
         match &self.body {
             CaoLangObjectBody::Function(f) => Some(f),
@@ -143,30 +187,52 @@ impl CaoLangObject {
         }
     }
 
+    pub fn as_iterator(&self) -> Option<&CaoLangIterator> {
+        match &self.body {
+            CaoLangObjectBody::Iterator(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_iterator_mut(&mut self) -> Option<&mut CaoLangIterator> {
+        match &mut self.body {
+            CaoLangObjectBody::Iterator(v) => Some(v),
+            _ => None,
+        }
+    }
+
     pub fn len(&self) -> usize {
         match &self.body {
             CaoLangObjectBody::Table(t) => t.len(),
             CaoLangObjectBody::String(s) => s.len(),
+            CaoLangObjectBody::Bytes(b) => b.len(),
+            CaoLangObjectBody::BigInt(_) => 0,
             CaoLangObjectBody::Function(_) => 0,
             CaoLangObjectBody::NativeFunction(_) => 0,
             CaoLangObjectBody::Closure(_) => 0,
             CaoLangObjectBody::Upvalue(_) => 0,
+            // an iterator's remaining length is not known without draining it
+            CaoLangObjectBody::Iterator(_) => 0,
         }
     }
 
     pub fn is_empty(&self) -> bool {
         match &self.body {
-            CaoLangObjectBody::Table(_) | CaoLangObjectBody::String(_) => self.len() == 0,
-            CaoLangObjectBody::Function(_)
+            CaoLangObjectBody::Table(_)
+            | CaoLangObjectBody::String(_)
+            | CaoLangObjectBody::Bytes(_) => self.len() == 0,
+            CaoLangObjectBody::BigInt(_)
+            | CaoLangObjectBody::Function(_)
             | CaoLangObjectBody::Closure(_)
             | CaoLangObjectBody::Upvalue(_)
-            | CaoLangObjectBody::NativeFunction(_) => false,
+            | CaoLangObjectBody::NativeFunction(_)
+            | CaoLangObjectBody::Iterator(_) => false,
         }
     }
 }
 
-impl std::hash::Hash for CaoLangObject {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+impl core::hash::Hash for CaoLangObject {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
         match &self.body {
             CaoLangObjectBody::Table(o) => {
                 for (k, v) in o.iter() {
@@ -177,6 +243,12 @@ impl std::hash::Hash for CaoLangObject {
             CaoLangObjectBody::String(s) => {
                 s.as_str().hash(state);
             }
+            CaoLangObjectBody::Bytes(b) => {
+                b.as_bytes().hash(state);
+            }
+            CaoLangObjectBody::BigInt(b) => {
+                b.0.hash(state);
+            }
             CaoLangObjectBody::Function(f) => {
                 f.handle.value().hash(state);
                 f.arity.hash(state);
@@ -186,46 +258,124 @@ impl std::hash::Hash for CaoLangObject {
                 c.function.handle.value().hash(state);
                 c.function.arity.hash(state);
             }
-            CaoLangObjectBody::Upvalue(u) => {
-                u.location.hash(state);
+            CaoLangObjectBody::Upvalue(u) => match u {
+                CaoLangUpvalue::Open(i) | CaoLangUpvalue::Closed(i) => i.hash(state),
+            },
+            CaoLangObjectBody::Iterator(it) => {
+                (it as *const CaoLangIterator).hash(state);
             }
         }
     }
 }
 
-impl PartialEq for CaoLangObject {
-    fn eq(&self, other: &Self) -> bool {
-        match (&self.body, &other.body) {
+impl CaoLangObject {
+    /// Deep structural equality, same as the [`PartialEq`] impl below, but threading a stack of
+    /// object pairs currently being compared higher up the call chain - a table can reach itself
+    /// again through its own values (see `test_cycle_gc`), and without this a cyclic table would
+    /// recurse forever instead of comparing equal. A pair already on `seen` is assumed equal,
+    /// mirroring how `gc`'s mark phase treats an already-gray object as handled rather than
+    /// re-visiting it.
+    pub(crate) fn eq_with_seen(
+        &self,
+        other: &Self,
+        seen: &mut Vec<(*const CaoLangObject, *const CaoLangObject)>,
+    ) -> bool {
+        let pair = (self as *const Self, other as *const Self);
+        if seen.contains(&pair) {
+            return true;
+        }
+        seen.push(pair);
+        let result = match (&self.body, &other.body) {
             (CaoLangObjectBody::Table(lhs), CaoLangObjectBody::Table(rhs)) => {
-                if lhs.len() != rhs.len() {
-                    return false;
-                }
-                for ((kl, vl), (kr, vr)) in lhs.iter().zip(rhs.iter()) {
-                    if kl != kr || vl != vr {
-                        return false;
-                    }
-                }
-                true
+                lhs.len() == rhs.len()
+                    && lhs.iter().zip(rhs.iter()).all(|((kl, vl), (kr, vr))| {
+                        kl.eq_with_seen(kr, seen) && vl.eq_with_seen(vr, seen)
+                    })
             }
             (CaoLangObjectBody::String(lhs), CaoLangObjectBody::String(rhs)) => {
                 lhs.as_str().eq(rhs.as_str())
             }
+            (CaoLangObjectBody::Bytes(lhs), CaoLangObjectBody::Bytes(rhs)) => {
+                lhs.as_bytes().eq(rhs.as_bytes())
+            }
+            (CaoLangObjectBody::BigInt(lhs), CaoLangObjectBody::BigInt(rhs)) => lhs.0 == rhs.0,
             _ => false,
-        }
+        };
+        seen.pop();
+        result
+    }
+}
+
+impl PartialEq for CaoLangObject {
+    fn eq(&self, other: &Self) -> bool {
+        self.eq_with_seen(other, &mut Vec::new())
     }
 }
 
 impl PartialOrd for CaoLangObject {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        if let (CaoLangObjectBody::Bytes(lhs), CaoLangObjectBody::Bytes(rhs)) =
+            (&self.body, &other.body)
+        {
+            return Some(lhs.as_bytes().cmp(rhs.as_bytes()));
+        }
         self.eq(other)
-            .then_some(std::cmp::Ordering::Equal)
+            .then_some(core::cmp::Ordering::Equal)
             .or_else(|| {
                 // equal len but non-eq objects should not return Equal
                 let res = self.len().cmp(&other.len());
                 match res {
-                    std::cmp::Ordering::Equal => None,
+                    core::cmp::Ordering::Equal => None,
                     _ => Some(res),
                 }
             })
     }
 }
+
+impl CaoLangObject {
+    /// Type-rank tier used by `Value`'s total `Ord` impl (see there for the full tier list,
+    /// `Nil < numbers < strings < bytes < tables < functions`) - lower sorts first. `BigInt`
+    /// shares the numeric tier with `Value::Integer`/`Value::Real` since it's conceptually still a
+    /// number, not its own kind of object.
+    pub(crate) fn value_rank(&self) -> u8 {
+        match &self.body {
+            CaoLangObjectBody::BigInt(_) => 1,
+            CaoLangObjectBody::String(_) => 2,
+            CaoLangObjectBody::Bytes(_) => 3,
+            CaoLangObjectBody::Table(_) => 4,
+            CaoLangObjectBody::Function(_)
+            | CaoLangObjectBody::NativeFunction(_)
+            | CaoLangObjectBody::Closure(_)
+            | CaoLangObjectBody::Upvalue(_)
+            | CaoLangObjectBody::Iterator(_) => 5,
+        }
+    }
+
+    /// Within-tier comparison for `Value`'s total `Ord` impl. Only ever called on a pair sharing
+    /// [`Self::value_rank`] (`Value::cmp` routes on that tier first), except `BigInt`: its tier is
+    /// shared with `Value::Integer`/`Real`, so a `BigInt`-vs-`Integer`/`Real` pair is handled by
+    /// `Value::cmp` itself and never reaches here.
+    pub(crate) fn total_cmp(&self, other: &Self) -> core::cmp::Ordering {
+        match (&self.body, &other.body) {
+            (CaoLangObjectBody::BigInt(lhs), CaoLangObjectBody::BigInt(rhs)) => lhs.0.cmp(&rhs.0),
+            (CaoLangObjectBody::String(lhs), CaoLangObjectBody::String(rhs)) => {
+                lhs.as_str().cmp(rhs.as_str())
+            }
+            (CaoLangObjectBody::Bytes(lhs), CaoLangObjectBody::Bytes(rhs)) => {
+                lhs.as_bytes().cmp(rhs.as_bytes())
+            }
+            (CaoLangObjectBody::Table(lhs), CaoLangObjectBody::Table(rhs)) => lhs
+                .sorted_iter()
+                .zip(rhs.sorted_iter())
+                .map(|((kl, vl), (kr, vr))| kl.cmp(kr).then_with(|| vl.cmp(vr)))
+                .find(|o| *o != core::cmp::Ordering::Equal)
+                .unwrap_or_else(|| lhs.len().cmp(&rhs.len())),
+            // Functions (and upvalues/iterators) have no meaningful value to order by, but `Ord`
+            // must still be total: fall back to each object's allocation address. That's stable
+            // for the object's lifetime, which is enough for a deterministic `sorted_iter`, but
+            // (unlike the tiers above) isn't stable across a save/restore round trip - functions
+            // aren't expected to be used as sorted table keys in the first place.
+            _ => core::ptr::addr_of!(*self).cmp(&core::ptr::addr_of!(*other)),
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use std::{alloc::Layout, fmt::Debug, ptr::NonNull};
+use core::{alloc::Layout, fmt::Debug, ptr::NonNull};
 
 use crate::alloc::AllocProxy;
 
@@ -10,7 +10,7 @@ pub struct CaoLangString {
 }
 
 impl Debug for CaoLangString {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         let str = self.as_str();
         write!(f, "String: {str:?}")
     }
@@ -27,7 +27,7 @@ impl CaoLangString {
         unsafe {
             let ptr = self.ptr;
             let len = self.len;
-            std::str::from_utf8_unchecked(std::slice::from_raw_parts(ptr.as_ptr(), len as usize))
+            core::str::from_utf8_unchecked(core::slice::from_raw_parts(ptr.as_ptr(), len as usize))
         }
     }
 
@@ -41,6 +41,6 @@ impl CaoLangString {
 
     /// Layout of a string with given length
     pub(crate) fn layout(len: usize) -> Layout {
-        std::alloc::Layout::array::<char>(len).unwrap()
+        Layout::array::<char>(len).unwrap()
     }
 }
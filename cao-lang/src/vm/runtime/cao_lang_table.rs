@@ -1,3 +1,4 @@
+use crate::alloc_crate::vec::Vec;
 use crate::{
     alloc::AllocProxy,
     collections::hash_map::{CaoHashMap, MapError},
@@ -5,6 +6,14 @@ use crate::{
     value::Value,
 };
 
+/// A `cao-lang` runtime table: an insertion-ordered `Value -> Value` map, backed by
+/// [`CaoHashMap`] for lookups and a parallel `keys` vec for iteration order. Tables are heap
+/// objects ([`super::cao_lang_object::CaoLangObjectBody::Table`]) and so can reference each other
+/// cyclically through [`Value::Object`](crate::value::Value::Object) entries;
+/// [`RuntimeData::gc_mark_step`](super::RuntimeData::gc_mark_step) traces a table by visiting
+/// every `(key, value)` pair from [`Self::iter`] and enqueueing any objects it finds, the same way
+/// it traces a closure's upvalues - cycles fall out of the collector's `White`/`Gray`/`Black`
+/// marking naturally, with no special-casing needed here.
 pub struct CaoLangTable {
     map: CaoHashMap<Value, Value, AllocProxy>,
     keys: Vec<Value>,
@@ -17,8 +26,8 @@ impl Clone for CaoLangTable {
     }
 }
 
-impl std::fmt::Debug for CaoLangTable {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Debug for CaoLangTable {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_map()
             .entries(self.keys.iter().map(|k| (k, self.map.get(k))))
             .finish()
@@ -85,6 +94,10 @@ impl CaoLangTable {
         _insert(self, key.into(), value.into())
     }
 
+    pub fn get(&self, key: impl Into<Value>) -> Option<&Value> {
+        self.map.get(&key.into())
+    }
+
     pub fn remove(&mut self, key: Value) -> Result<(), ExecutionErrorPayload> {
         self.keys.retain(|k| {
             let retain = k != &key;
@@ -128,6 +141,16 @@ impl CaoLangTable {
             .filter_map(|k| self.map.get(k).map(|v| (k, v)))
     }
 
+    /// Same entries as [`Self::iter`], but sorted by key using `Value`'s total `Ord` impl instead
+    /// of insertion order - lets a script (or a saved-state diff) observe a table's keys in a
+    /// deterministic order regardless of how they were inserted or how the backing hash map
+    /// happens to lay them out.
+    pub fn sorted_iter(&self) -> Vec<(&Value, &Value)> {
+        let mut entries: Vec<_> = self.iter().collect();
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+        entries
+    }
+
     pub fn keys(&self) -> &[Value] {
         &self.keys
     }
@@ -137,7 +160,7 @@ impl CaoLangTable {
     }
 }
 
-impl std::ops::Deref for CaoLangTable {
+impl core::ops::Deref for CaoLangTable {
     type Target = CaoHashMap<Value, Value, AllocProxy>;
 
     fn deref(&self) -> &Self::Target {
@@ -0,0 +1,488 @@
+//! Serializing a live [`RuntimeData`] into a self-describing, `serde`-friendly blob and
+//! reconstructing it later, so long-running programs can be suspended and resumed.
+//!
+//! The heap is full of `NonNull<CaoLangObject>` pointers referencing each other (table values,
+//! closures pointing at their function and upvalues, upvalues pointing at the next open upvalue
+//! in the chain...). None of those pointers are meaningful outside of this process, so the heap
+//! is flattened into a `Vec<ObjectSnapshot>` and every pointer is rewritten to the index of the
+//! object it points to. Restoring walks the same list, allocates every object up front and only
+//! then patches the indices back into real pointers, since a later object may reference an
+//! earlier one and vice versa.
+use core::pin::Pin;
+
+use crate::{prelude::Handle, procedures::ExecutionErrorPayload, value::Value};
+
+use super::{
+    runtime::{
+        cao_lang_function::{CaoLangClosure, CaoLangUpvalue},
+        cao_lang_iterator::CaoLangIterator,
+        cao_lang_object::{CaoLangObject, CaoLangObjectBody, GcMarker},
+    },
+    CallFrame, RuntimeData,
+};
+
+/// Errors from [`RuntimeData::restore`] (and, through it, [`super::Vm::restore`] /
+/// [`super::Vm::restore_bytes`]) reconstructing a snapshot.
+///
+/// `Display` is implemented by hand rather than via `#[derive(thiserror::Error)]`, for the same
+/// no_std reason documented on [`ExecutionErrorPayload`].
+#[derive(Debug)]
+pub enum RestoreBytesError {
+    /// The snapshot is truncated, corrupt, or otherwise references data it doesn't actually
+    /// contain (e.g. an out-of-range heap index) - whether it arrived as a `VmSnapshot` built by
+    /// hand or was decoded from a `bincode` blob of an incompatible format.
+    BadBlob,
+    Execution(ExecutionErrorPayload),
+}
+
+impl core::fmt::Display for RestoreBytesError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::BadBlob => write!(f, "snapshot blob is corrupt or uses an incompatible format"),
+            Self::Execution(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RestoreBytesError {}
+
+impl From<ExecutionErrorPayload> for RestoreBytesError {
+    fn from(err: ExecutionErrorPayload) -> Self {
+        Self::Execution(err)
+    }
+}
+
+/// A [`Value`] with heap references rewritten to the index of the pointee in
+/// [`VmSnapshot::heap`].
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ValueSnapshot {
+    Nil,
+    Integer(i64),
+    Real(f64),
+    Object(usize),
+}
+
+/// A [`CaoLangObject`] with every inter-object pointer rewritten to a heap index.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ObjectSnapshot {
+    Table(Vec<(ValueSnapshot, ValueSnapshot)>),
+    String(String),
+    Bytes(Vec<u8>),
+    BigInt(i128),
+    Function {
+        handle: Handle,
+        arity: u32,
+        max_locals: u32,
+    },
+    NativeFunction {
+        handle: Handle,
+    },
+    Closure {
+        handle: Handle,
+        arity: u32,
+        max_locals: u32,
+        upvalues: Vec<usize>,
+    },
+    /// Only closed upvalues can be snapshotted: an open upvalue points into the live value
+    /// stack, which is meaningless once the VM is torn down, so `RuntimeData::snapshot` closes
+    /// every open upvalue before walking the heap.
+    Upvalue(ValueSnapshot),
+    Iterator(IteratorSnapshot),
+}
+
+/// A [`CaoLangIterator`] with its `source` pointer rewritten to a heap index.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IteratorSnapshot {
+    Table {
+        source: usize,
+        next: usize,
+    },
+    Chars {
+        source: usize,
+        next: usize,
+    },
+    Range {
+        next: i64,
+        end: i64,
+    },
+    Native {
+        callback: ValueSnapshot,
+        next: i64,
+    },
+    Map {
+        source: usize,
+        callback: ValueSnapshot,
+    },
+    Filter {
+        source: usize,
+        callback: ValueSnapshot,
+    },
+}
+
+/// A serializable snapshot of [`RuntimeData`], taken at a particular instruction pointer.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VmSnapshot {
+    pub instr_ptr: usize,
+    pub value_stack: Vec<ValueSnapshot>,
+    pub call_stack: Vec<CallFrameSnapshot>,
+    pub global_vars: Vec<ValueSnapshot>,
+    /// Flat heap, indexed by the ids used throughout this snapshot.
+    pub heap: Vec<ObjectSnapshot>,
+    /// [`RuntimeData::rng_state`], so a resumed program's `Random`/`DiceRoll` rolls keep following
+    /// the same sequence a non-suspended run would have produced.
+    pub rng_state: u64,
+    /// [`RuntimeData::fuel`], the remaining instruction budget set via
+    /// [`crate::vm::Vm::set_fuel`]/[`crate::vm::Vm::add_fuel`], if any.
+    pub fuel: Option<u64>,
+    /// [`RuntimeData::fuel_consumed`].
+    pub fuel_consumed: u64,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CallFrameSnapshot {
+    pub src_instr_ptr: u32,
+    pub dst_instr_ptr: u32,
+    pub stack_offset: u32,
+    /// Index into `VmSnapshot::heap` of the `Closure` object owning this frame, if any.
+    pub closure: Option<usize>,
+}
+
+impl RuntimeData {
+    /// Snapshot this VM's live state at `instr_ptr` into a serializable blob.
+    ///
+    /// Every open upvalue must already be closed (see [`Vm::snapshot`](super::Vm::snapshot)):
+    /// [`RuntimeData::restore`] always reconstructs upvalues as
+    /// [`CaoLangUpvalue::Closed`] rather than re-deriving which live frame an
+    /// [`CaoLangUpvalue::Open`] one would belong to, so any still-open upvalue would come back
+    /// from a round trip with the wrong (closed) semantics.
+    pub fn snapshot(&self, instr_ptr: usize) -> VmSnapshot {
+        // index objects in allocation order so heap ids are stable across snapshot/restore
+        let index_of: crate::alloc_crate::collections::BTreeMap<*mut CaoLangObject, usize> = self
+            .object_list
+            .iter()
+            .enumerate()
+            .map(|(i, ptr)| (ptr.as_ptr(), i))
+            .collect();
+        let to_value_snapshot = |v: Value| match v {
+            Value::Nil => ValueSnapshot::Nil,
+            Value::Integer(i) => ValueSnapshot::Integer(i),
+            Value::Real(r) => ValueSnapshot::Real(r),
+            Value::Object(o) => ValueSnapshot::Object(index_of[&o.as_ptr()]),
+        };
+
+        let heap = self
+            .object_list
+            .iter()
+            .map(|obj| {
+                let obj = unsafe { obj.as_ref() };
+                match &obj.body {
+                    CaoLangObjectBody::Table(t) => ObjectSnapshot::Table(
+                        t.iter()
+                            .map(|(k, v)| (to_value_snapshot(*k), to_value_snapshot(*v)))
+                            .collect(),
+                    ),
+                    CaoLangObjectBody::String(s) => ObjectSnapshot::String(s.as_str().to_string()),
+                    CaoLangObjectBody::Bytes(b) => ObjectSnapshot::Bytes(b.as_bytes().to_vec()),
+                    CaoLangObjectBody::BigInt(b) => ObjectSnapshot::BigInt(b.0),
+                    CaoLangObjectBody::Function(f) => ObjectSnapshot::Function {
+                        handle: f.handle,
+                        arity: f.arity,
+                        max_locals: f.max_locals,
+                    },
+                    CaoLangObjectBody::NativeFunction(f) => {
+                        ObjectSnapshot::NativeFunction { handle: f.handle }
+                    }
+                    CaoLangObjectBody::Closure(c) => ObjectSnapshot::Closure {
+                        handle: c.function.handle,
+                        arity: c.function.arity,
+                        max_locals: c.function.max_locals,
+                        upvalues: c.upvalues.iter().map(|u| index_of[&u.as_ptr()]).collect(),
+                    },
+                    CaoLangObjectBody::Upvalue(u) => ObjectSnapshot::Upvalue(to_value_snapshot(
+                        match u {
+                            CaoLangUpvalue::Open(index) => self.value_stack.as_slice()[*index],
+                            CaoLangUpvalue::Closed(handle) => self.closed_upvalues[*handle],
+                        },
+                    )),
+                    CaoLangObjectBody::Iterator(it) => ObjectSnapshot::Iterator(match it {
+                        CaoLangIterator::Table { source, next } => IteratorSnapshot::Table {
+                            source: index_of[&source.as_ptr()],
+                            next: *next,
+                        },
+                        CaoLangIterator::Chars { source, next } => IteratorSnapshot::Chars {
+                            source: index_of[&source.as_ptr()],
+                            next: *next,
+                        },
+                        CaoLangIterator::Range { next, end } => IteratorSnapshot::Range {
+                            next: *next,
+                            end: *end,
+                        },
+                        CaoLangIterator::Native { callback, next } => IteratorSnapshot::Native {
+                            callback: to_value_snapshot(*callback),
+                            next: *next,
+                        },
+                        CaoLangIterator::Map { source, callback } => IteratorSnapshot::Map {
+                            source: index_of[&source.as_ptr()],
+                            callback: to_value_snapshot(*callback),
+                        },
+                        CaoLangIterator::Filter { source, callback } => IteratorSnapshot::Filter {
+                            source: index_of[&source.as_ptr()],
+                            callback: to_value_snapshot(*callback),
+                        },
+                    }),
+                }
+            })
+            .collect();
+
+        // `CallFrame::closure` points at the `CaoLangClosure` payload *inside* its owning
+        // object, not at the object's own address, so resolve it by finding which object's
+        // memory the pointer falls within rather than by an exact address match.
+        let object_containing = |ptr: *mut CaoLangClosure| -> Option<usize> {
+            (!ptr.is_null()).then(|| {
+                let addr = ptr as usize;
+                self.object_list
+                    .iter()
+                    .position(|obj| {
+                        let start = obj.as_ptr() as usize;
+                        let end = start + core::mem::size_of::<CaoLangObject>();
+                        (start..end).contains(&addr)
+                    })
+                    .expect("closure pointer must belong to an object on the heap")
+            })
+        };
+
+        let call_stack = self
+            .call_stack
+            .iter()
+            .map(|frame| CallFrameSnapshot {
+                src_instr_ptr: frame.src_instr_ptr,
+                dst_instr_ptr: frame.dst_instr_ptr,
+                stack_offset: frame.stack_offset,
+                closure: object_containing(frame.closure),
+            })
+            .collect();
+
+        VmSnapshot {
+            instr_ptr,
+            value_stack: self
+                .value_stack
+                .as_slice()
+                .iter()
+                .map(|v| to_value_snapshot(*v))
+                .collect(),
+            call_stack,
+            global_vars: self
+                .global_vars
+                .iter()
+                .map(|v| to_value_snapshot(*v))
+                .collect(),
+            heap,
+            rng_state: self.rng_state,
+            fuel: self.fuel,
+            fuel_consumed: self.fuel_consumed,
+        }
+    }
+
+    /// Reconstruct a fresh [`RuntimeData`] from a [`VmSnapshot`], returning it along with the
+    /// instruction pointer execution should resume at.
+    pub fn restore(
+        snapshot: &VmSnapshot,
+        memory_limit: usize,
+        stack_size: usize,
+        call_stack_size: usize,
+    ) -> Result<(Pin<Box<Self>>, usize), RestoreBytesError> {
+        let mut runtime = Self::new(memory_limit, stack_size, call_stack_size)?;
+
+        // Allocate every heap object up front (with placeholder bodies for the ones that need to
+        // reference other objects), then patch the inter-object pointers once every object has a
+        // stable address.
+        let mut objects = Vec::with_capacity(snapshot.heap.len());
+        for obj in &snapshot.heap {
+            let guard = match obj {
+                ObjectSnapshot::Table(_) => runtime.init_table()?,
+                ObjectSnapshot::String(s) => runtime.init_string(s.as_str())?,
+                ObjectSnapshot::Bytes(b) => runtime.init_bytes(b.as_slice())?,
+                ObjectSnapshot::BigInt(b) => runtime.init_bigint(*b)?,
+                ObjectSnapshot::Function {
+                    handle,
+                    arity,
+                    max_locals,
+                } => runtime.init_function(*handle, *arity, *max_locals)?,
+                ObjectSnapshot::NativeFunction { handle } => {
+                    runtime.init_native_function(*handle)?
+                }
+                ObjectSnapshot::Closure {
+                    handle,
+                    arity,
+                    max_locals,
+                    ..
+                } => runtime.init_closure(*handle, *arity, *max_locals)?,
+                // the actual value (which may reference an object not allocated yet) is patched
+                // into `closed_upvalues[handle]` in the second pass below
+                ObjectSnapshot::Upvalue(_) => {
+                    let handle = runtime.closed_upvalues.len();
+                    runtime.closed_upvalues.push(Value::Nil);
+                    runtime.init_upvalue(CaoLangUpvalue::Closed(handle))?
+                }
+                // `source` (and `callback`, for Map/Filter) may reference an object that has not
+                // been allocated yet; patched in the second pass below once every object has a
+                // stable address, same as `Upvalue`'s value above.
+                ObjectSnapshot::Iterator(snap) => {
+                    let body = match snap {
+                        IteratorSnapshot::Table { next, .. } => CaoLangIterator::Table {
+                            source: core::ptr::NonNull::dangling(),
+                            next: *next,
+                        },
+                        IteratorSnapshot::Chars { next, .. } => CaoLangIterator::Chars {
+                            source: core::ptr::NonNull::dangling(),
+                            next: *next,
+                        },
+                        IteratorSnapshot::Range { next, end } => {
+                            CaoLangIterator::Range { next: *next, end: *end }
+                        }
+                        IteratorSnapshot::Native { next, .. } => CaoLangIterator::Native {
+                            callback: Value::Nil,
+                            next: *next,
+                        },
+                        IteratorSnapshot::Map { .. } => CaoLangIterator::Map {
+                            source: core::ptr::NonNull::dangling(),
+                            callback: Value::Nil,
+                        },
+                        IteratorSnapshot::Filter { .. } => CaoLangIterator::Filter {
+                            source: core::ptr::NonNull::dangling(),
+                            callback: Value::Nil,
+                        },
+                    };
+                    runtime.init_iterator(body)?
+                }
+            };
+            objects.push(guard.into_inner());
+        }
+
+        // `objects` is indexed by heap indices coming straight out of the snapshot, which may be
+        // hand-built or decoded from an untrusted `bincode` blob - bounds-check every lookup
+        // rather than trusting them, so a corrupt snapshot yields `BadBlob` instead of a panic.
+        let object_at = |idx: usize| -> Result<core::ptr::NonNull<CaoLangObject>, RestoreBytesError> {
+            objects.get(idx).copied().ok_or(RestoreBytesError::BadBlob)
+        };
+
+        let to_value = |v: &ValueSnapshot| -> Result<Value, RestoreBytesError> {
+            Ok(match v {
+                ValueSnapshot::Nil => Value::Nil,
+                ValueSnapshot::Integer(i) => Value::Integer(*i),
+                ValueSnapshot::Real(r) => Value::Real(*r),
+                ValueSnapshot::Object(idx) => Value::Object(object_at(*idx)?),
+            })
+        };
+
+        for (obj_ptr, snap) in objects.iter().zip(&snapshot.heap) {
+            let mut obj_ptr = *obj_ptr;
+            let obj = unsafe { obj_ptr.as_mut() };
+            match (&mut obj.body, snap) {
+                (CaoLangObjectBody::Table(t), ObjectSnapshot::Table(entries)) => {
+                    for (k, v) in entries {
+                        t.insert(to_value(k)?, to_value(v)?)?;
+                    }
+                }
+                (CaoLangObjectBody::Closure(c), ObjectSnapshot::Closure { upvalues, .. }) => {
+                    c.upvalues = upvalues
+                        .iter()
+                        .map(|idx| object_at(*idx))
+                        .collect::<Result<_, _>>()?;
+                }
+                (
+                    CaoLangObjectBody::Upvalue(CaoLangUpvalue::Closed(handle)),
+                    ObjectSnapshot::Upvalue(value),
+                ) => {
+                    runtime.closed_upvalues[*handle] = to_value(value)?;
+                }
+                (
+                    CaoLangObjectBody::Iterator(CaoLangIterator::Table { source, .. }),
+                    ObjectSnapshot::Iterator(IteratorSnapshot::Table { source: idx, .. }),
+                ) => {
+                    *source = object_at(*idx)?;
+                }
+                (
+                    CaoLangObjectBody::Iterator(CaoLangIterator::Chars { source, .. }),
+                    ObjectSnapshot::Iterator(IteratorSnapshot::Chars { source: idx, .. }),
+                ) => {
+                    *source = object_at(*idx)?;
+                }
+                (
+                    CaoLangObjectBody::Iterator(CaoLangIterator::Native { callback, .. }),
+                    ObjectSnapshot::Iterator(IteratorSnapshot::Native { callback: cb, .. }),
+                ) => {
+                    *callback = to_value(cb)?;
+                }
+                (
+                    CaoLangObjectBody::Iterator(CaoLangIterator::Map { source, callback }),
+                    ObjectSnapshot::Iterator(IteratorSnapshot::Map {
+                        source: idx,
+                        callback: cb,
+                    }),
+                ) => {
+                    *source = object_at(*idx)?;
+                    *callback = to_value(cb)?;
+                }
+                (
+                    CaoLangObjectBody::Iterator(CaoLangIterator::Filter { source, callback }),
+                    ObjectSnapshot::Iterator(IteratorSnapshot::Filter {
+                        source: idx,
+                        callback: cb,
+                    }),
+                ) => {
+                    *source = object_at(*idx)?;
+                    *callback = to_value(cb)?;
+                }
+                _ => {}
+            }
+            obj.marker = GcMarker::White;
+        }
+
+        for v in &snapshot.value_stack {
+            runtime
+                .value_stack
+                .push(to_value(v)?)
+                .map_err(ExecutionErrorPayload::from)?;
+        }
+        runtime.global_vars = snapshot
+            .global_vars
+            .iter()
+            .map(to_value)
+            .collect::<Result<_, _>>()?;
+        for frame in &snapshot.call_stack {
+            let closure = match frame.closure {
+                Some(idx) => {
+                    let mut obj_ptr = object_at(idx)?;
+                    let obj = unsafe { obj_ptr.as_mut() };
+                    match &mut obj.body {
+                        CaoLangObjectBody::Closure(c) => c as *mut CaoLangClosure,
+                        _ => unreachable!("closure index must point at a Closure object"),
+                    }
+                }
+                None => core::ptr::null_mut(),
+            };
+            runtime
+                .call_stack
+                .push(CallFrame {
+                    src_instr_ptr: frame.src_instr_ptr,
+                    dst_instr_ptr: frame.dst_instr_ptr,
+                    stack_offset: frame.stack_offset,
+                    closure,
+                })
+                .map_err(ExecutionErrorPayload::from)?;
+        }
+
+        runtime.seed_rng(snapshot.rng_state);
+        runtime.fuel = snapshot.fuel;
+        runtime.fuel_consumed = snapshot.fuel_consumed;
+
+        Ok((runtime, snapshot.instr_ptr))
+    }
+}
@@ -1,4 +1,5 @@
 use super::*;
+use std::cell::RefCell;
 
 #[test]
 fn test_binary_operatons() {
@@ -13,15 +14,57 @@ fn test_binary_operatons() {
         .push(Value::Integer(42))
         .unwrap();
 
+    // Integer operands stay Integer - `Div` truncates instead of promoting to `Real`.
     vm.binary_op(|a, b| (a + a / b) * b).unwrap();
 
     let result = vm.runtime_data.value_stack.pop();
     match result {
-        Value::Real(result) => assert_eq!(result, (512.0 + 512.0 / 42.0) * 42.0),
+        Value::Integer(result) => assert_eq!(result, (512 + 512 / 42) * 42),
         _ => panic!("Invalid result type"),
     }
 }
 
+#[test]
+fn test_binary_operations_promote_to_real_when_mixed() {
+    let mut vm = Vm::new(()).unwrap();
+
+    vm.runtime_data
+        .value_stack
+        .push(Value::Integer(512))
+        .unwrap();
+    vm.runtime_data.value_stack.push(Value::Real(42.0)).unwrap();
+
+    vm.binary_op(|a, b| a / b).unwrap();
+
+    let result = vm.runtime_data.value_stack.pop();
+    match result {
+        Value::Real(result) => assert_eq!(result, 512.0 / 42.0),
+        _ => panic!("Invalid result type"),
+    }
+}
+
+#[test]
+fn test_integer_division_by_zero_is_an_execution_error() {
+    let mut vm = Vm::new(()).unwrap();
+
+    vm.runtime_data.value_stack.push(Value::Integer(1)).unwrap();
+    vm.runtime_data.value_stack.push(Value::Integer(0)).unwrap();
+
+    let err = vm.checked_div_op(|a, b| a / b).unwrap_err();
+    assert!(matches!(err, ExecutionErrorPayload::DivideByZero));
+}
+
+#[test]
+fn test_arithmetic_on_nil_is_an_execution_error() {
+    let mut vm = Vm::new(()).unwrap();
+
+    vm.runtime_data.value_stack.push(Value::Nil).unwrap();
+    vm.runtime_data.value_stack.push(Value::Integer(1)).unwrap();
+
+    let err = vm.binary_arith_op(|a, b| a + b).unwrap_err();
+    assert!(matches!(err, ExecutionErrorPayload::InvalidArgument { .. }));
+}
+
 #[test]
 fn test_can_init_str() {
     let mut vm = Vm::new(()).unwrap();
@@ -100,3 +143,901 @@ fn test_cycle_gc() {
 
     assert!(vm.runtime_data.object_list.is_empty());
 }
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_can_snapshot_and_restore_a_whole_vm() {
+    let mut vm = Vm::new(()).unwrap();
+
+    // a self-referential table: `a` contains itself and `b`, `b` contains `a`
+    let mut a = vm.init_table().unwrap();
+    let mut b = vm.init_table().unwrap();
+    a.as_table_mut()
+        .unwrap()
+        .append(Value::Object(b.0))
+        .unwrap();
+    let ao = Value::Object(a.0);
+    a.as_table_mut().unwrap().append(ao).unwrap();
+    b.as_table_mut().unwrap().append(ao).unwrap();
+
+    vm.runtime_data.value_stack.push(Value::Integer(42)).unwrap();
+    vm.runtime_data.value_stack.push(ao).unwrap();
+    vm.runtime_data.global_vars.push(Value::Real(3.5));
+
+    let snapshot = vm.snapshot(7);
+    let pl = serde_json::to_string(&snapshot).unwrap();
+    let snapshot: super::snapshot::VmSnapshot = serde_json::from_str(&pl).unwrap();
+
+    let (mut restored, instr_ptr) = Vm::restore(&snapshot, ()).unwrap();
+    assert_eq!(instr_ptr, 7);
+    assert_eq!(restored.runtime_data.global_vars, vec![Value::Real(3.5)]);
+    assert_eq!(restored.runtime_data.value_stack.as_slice().len(), 2);
+    assert_eq!(
+        restored.runtime_data.value_stack.as_slice()[0],
+        Value::Integer(42)
+    );
+
+    // the restored `a` must still point at a `b` that points back at `a`
+    let restored_a = match restored.runtime_data.value_stack.as_slice()[1] {
+        Value::Object(o) => o,
+        _ => panic!("expected an object"),
+    };
+    let restored_a_table = unsafe { restored_a.as_ref() }.as_table().unwrap();
+    assert_eq!(restored_a_table.len(), 2);
+
+    restored.runtime_data.gc();
+    assert_eq!(restored.runtime_data.object_list.len(), 2);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_snapshot_bytes_round_trips_through_a_flat_blob() {
+    let mut vm = Vm::new(()).unwrap();
+    vm.runtime_data.value_stack.push(Value::Integer(42)).unwrap();
+
+    let bytes = vm.snapshot_bytes(7);
+    let (restored, instr_ptr) = Vm::restore_bytes(&bytes, ()).unwrap();
+
+    assert_eq!(instr_ptr, 7);
+    assert_eq!(
+        restored.runtime_data.value_stack.as_slice()[0],
+        Value::Integer(42)
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_restore_bytes_rejects_garbage() {
+    let err = Vm::restore_bytes(b"not a snapshot", ()).unwrap_err();
+    assert!(matches!(err, super::snapshot::RestoreBytesError::BadBlob));
+}
+
+#[test]
+fn test_tables_with_equal_contents_compare_equal() {
+    let mut vm = Vm::new(()).unwrap();
+
+    let mut a = vm.init_table().unwrap();
+    let key_a = vm.init_string("pog").unwrap();
+    a.as_table_mut()
+        .unwrap()
+        .insert(Value::Object(key_a.into_inner()), 42)
+        .unwrap();
+
+    let mut b = vm.init_table().unwrap();
+    let key_b = vm.init_string("pog").unwrap();
+    b.as_table_mut()
+        .unwrap()
+        .insert(Value::Object(key_b.into_inner()), 42)
+        .unwrap();
+
+    assert_eq!(Value::Object(a.0), Value::Object(b.0));
+}
+
+#[test]
+fn test_cyclic_tables_compare_equal_without_overflowing_the_stack() {
+    let mut vm = Vm::new(()).unwrap();
+
+    // `a` and `b` both contain themselves - comparing them must terminate instead of
+    // recursing through the cycle forever.
+    let mut a = vm.init_table().unwrap();
+    let ao = Value::Object(a.0);
+    a.as_table_mut().unwrap().append(ao).unwrap();
+
+    let mut b = vm.init_table().unwrap();
+    let bo = Value::Object(b.0);
+    b.as_table_mut().unwrap().append(bo).unwrap();
+
+    assert_eq!(ao, bo);
+}
+
+#[test]
+fn test_trap_handler_observes_a_fault_before_it_propagates() {
+    let seen = Rc::new(RefCell::new(None));
+    let seen_in_handler = seen.clone();
+
+    let mut vm = Vm::new(()).unwrap().with_trap_handler(move |err| {
+        *seen_in_handler.borrow_mut() = Some(err.payload.clone());
+    });
+
+    // `1 / 0` on integers is a genuine fault (`DivideByZero`), not a control-flow signal like
+    // `Suspended`/`Timeout` - the trap handler should see it.
+    let mut bytecode = vec![Instruction::ScalarInt as u8];
+    crate::bytecode::write_to_vec(1i64, &mut bytecode);
+    bytecode.push(Instruction::ScalarInt as u8);
+    crate::bytecode::write_to_vec(0i64, &mut bytecode);
+    bytecode.push(Instruction::Div as u8);
+    bytecode.push(Instruction::Exit as u8);
+    let program = CaoCompiledProgram {
+        bytecode,
+        ..Default::default()
+    };
+
+    let err = vm.run(&program).unwrap_err();
+    assert!(matches!(err.payload, ExecutionErrorPayload::DivideByZero));
+    assert!(matches!(
+        seen.borrow().as_ref().unwrap(),
+        ExecutionErrorPayload::DivideByZero
+    ));
+}
+
+#[test]
+fn test_interrupt_handle_stops_a_running_program() {
+    let mut vm = Vm::new(()).unwrap().with_max_iter(257);
+
+    // `remaining_iters` starts at 257 and is checked for interruption after it ticks down to a
+    // multiple of 256, so a single `ScalarNil`/`Pop`/`Goto` loop iteration is enough to land on
+    // that check - no need to actually run anywhere near 257 instructions.
+    let loop_start = 0i32;
+    let mut bytecode = vec![Instruction::ScalarNil as u8];
+    bytecode.push(Instruction::Pop as u8);
+    bytecode.push(Instruction::Goto as u8);
+    crate::bytecode::write_to_vec(loop_start, &mut bytecode);
+    let program = CaoCompiledProgram {
+        bytecode,
+        ..Default::default()
+    };
+
+    vm.interrupt_handle()
+        .store(true, std::sync::atomic::Ordering::Relaxed);
+
+    let err = vm.run(&program).unwrap_err();
+    assert!(matches!(err.payload, ExecutionErrorPayload::Interrupted));
+}
+
+#[test]
+fn test_breakpoint_pauses_before_the_instruction_runs() {
+    let mut vm = Vm::new(()).unwrap();
+
+    let mut bytecode = vec![Instruction::ScalarInt as u8];
+    crate::bytecode::write_to_vec(42i64, &mut bytecode);
+    bytecode.push(Instruction::Exit as u8);
+    let program = CaoCompiledProgram {
+        bytecode,
+        ..Default::default()
+    };
+
+    // Breakpoint on the second instruction, not the first.
+    let breakpoint = Instruction::ScalarInt.span() as u32;
+    vm.add_breakpoint(breakpoint).unwrap();
+
+    let err = vm.run(&program).unwrap_err();
+    assert!(matches!(err.payload, ExecutionErrorPayload::Paused));
+    // Nothing past the breakpoint ran - the ScalarInt push happened, Exit didn't.
+    assert_eq!(vm.runtime_data.value_stack.len(), 1);
+
+    vm.remove_breakpoint(breakpoint);
+    vm.clear_breakpoints();
+}
+
+#[test]
+fn test_debugger_can_pause_or_abort_the_run() {
+    struct CountingDebugger {
+        steps_before_pause: RefCell<i32>,
+    }
+
+    impl Debugger<()> for CountingDebugger {
+        fn on_step(&mut self, _vm: &Vm<()>, _instr_ptr: u32, _instr: Instruction) -> StepAction {
+            let mut remaining = self.steps_before_pause.borrow_mut();
+            if *remaining == 0 {
+                StepAction::Pause
+            } else {
+                *remaining -= 1;
+                StepAction::Continue
+            }
+        }
+    }
+
+    let mut bytecode = vec![Instruction::ScalarInt as u8];
+    crate::bytecode::write_to_vec(42i64, &mut bytecode);
+    bytecode.push(Instruction::Exit as u8);
+    let program = CaoCompiledProgram {
+        bytecode,
+        ..Default::default()
+    };
+
+    let mut vm = Vm::new(()).unwrap().with_debugger(CountingDebugger {
+        steps_before_pause: RefCell::new(1),
+    });
+    let err = vm.run(&program).unwrap_err();
+    assert!(matches!(err.payload, ExecutionErrorPayload::Paused));
+
+    struct AbortingDebugger;
+    impl Debugger<()> for AbortingDebugger {
+        fn on_step(&mut self, _vm: &Vm<()>, _instr_ptr: u32, _instr: Instruction) -> StepAction {
+            StepAction::Abort
+        }
+    }
+
+    let mut vm = Vm::new(()).unwrap().with_debugger(AbortingDebugger);
+    let err = vm.run(&program).unwrap_err();
+    assert!(matches!(err.payload, ExecutionErrorPayload::DebuggerAbort));
+}
+
+#[test]
+fn test_step_executes_one_instruction_at_a_time() {
+    let mut bytecode = vec![Instruction::ScalarInt as u8];
+    crate::bytecode::write_to_vec(1i64, &mut bytecode);
+    bytecode.push(Instruction::ScalarInt as u8);
+    crate::bytecode::write_to_vec(2i64, &mut bytecode);
+    bytecode.push(Instruction::Add as u8);
+    bytecode.push(Instruction::Exit as u8);
+    let program = CaoCompiledProgram {
+        bytecode,
+        ..Default::default()
+    };
+
+    // Pause right away, before the first instruction runs, so we get a `Suspended` whose
+    // `current_program` is set but nothing has executed yet.
+    let mut vm = Vm::new(()).unwrap();
+    vm.add_breakpoint(0).unwrap();
+    let outcome = vm.run_resumable(&program).unwrap();
+    let suspended = match outcome {
+        RunOutcome::Yielded(suspended) => suspended,
+        RunOutcome::Finished(_) => panic!("expected the breakpoint to pause the run"),
+    };
+
+    let mut vm = Vm::new(()).unwrap();
+    vm.runtime_data = suspended.runtime_data;
+    let mut pc = suspended.instr_ptr;
+
+    let (offset, instr) = vm.step(&mut pc).unwrap();
+    assert_eq!(offset, 0);
+    assert_eq!(instr, Instruction::ScalarInt);
+    assert_eq!(vm.runtime_data.value_stack.len(), 1);
+
+    let (offset, instr) = vm.step(&mut pc).unwrap();
+    assert_eq!(offset, Instruction::ScalarInt.span() as u32);
+    assert_eq!(instr, Instruction::ScalarInt);
+    assert_eq!(vm.runtime_data.value_stack.len(), 2);
+
+    // `Add` and `Exit` haven't run yet - only the two pushes have.
+    assert_eq!(vm.runtime_data.value_stack.as_slice(), &[Value::Integer(1), Value::Integer(2)]);
+
+    let (_, instr) = vm.step(&mut pc).unwrap();
+    assert_eq!(instr, Instruction::Add);
+    assert_eq!(vm.runtime_data.value_stack.as_slice(), &[Value::Integer(3)]);
+}
+
+#[test]
+fn test_add_overflow_promotes_to_bigint_instead_of_wrapping() {
+    let vm = Vm::new(()).unwrap();
+
+    let mut bytecode = vec![Instruction::ScalarInt as u8];
+    crate::bytecode::write_to_vec(i64::MAX, &mut bytecode);
+    bytecode.push(Instruction::ScalarInt as u8);
+    crate::bytecode::write_to_vec(1i64, &mut bytecode);
+    bytecode.push(Instruction::Add as u8);
+    bytecode.push(Instruction::Exit as u8);
+    let program = CaoCompiledProgram {
+        bytecode,
+        ..Default::default()
+    };
+
+    match vm.run_resumable(&program).unwrap() {
+        RunOutcome::Finished(result) => {
+            assert_eq!(result.as_bigint(), Some(i64::MAX as i128 + 1));
+        }
+        RunOutcome::Yielded(_) => panic!("expected the program to finish, not yield"),
+    }
+}
+
+#[test]
+fn test_sub_and_mul_narrow_a_bigint_result_back_to_integer_once_it_fits() {
+    let vm = Vm::new(()).unwrap();
+
+    // `(i64::MAX + 1) - 1` promotes on the `Add`, then narrows back to a plain `Integer` once
+    // the `Sub` brings it back into `i64`'s range.
+    let mut bytecode = vec![Instruction::ScalarInt as u8];
+    crate::bytecode::write_to_vec(i64::MAX, &mut bytecode);
+    bytecode.push(Instruction::ScalarInt as u8);
+    crate::bytecode::write_to_vec(1i64, &mut bytecode);
+    bytecode.push(Instruction::Add as u8);
+    bytecode.push(Instruction::ScalarInt as u8);
+    crate::bytecode::write_to_vec(1i64, &mut bytecode);
+    bytecode.push(Instruction::Sub as u8);
+    bytecode.push(Instruction::Exit as u8);
+    let program = CaoCompiledProgram {
+        bytecode,
+        ..Default::default()
+    };
+
+    match vm.run_resumable(&program).unwrap() {
+        RunOutcome::Finished(result) => {
+            assert_eq!(result, Value::Integer(i64::MAX));
+        }
+        RunOutcome::Yielded(_) => panic!("expected the program to finish, not yield"),
+    }
+}
+
+#[test]
+fn test_yield_instruction_pops_its_value_and_resume_continues_past_it() {
+    let vm = Vm::new(()).unwrap();
+
+    let mut bytecode = vec![Instruction::ScalarInt as u8];
+    crate::bytecode::write_to_vec(1i64, &mut bytecode);
+    bytecode.push(Instruction::Yield as u8);
+    bytecode.push(Instruction::ScalarInt as u8);
+    crate::bytecode::write_to_vec(2i64, &mut bytecode);
+    bytecode.push(Instruction::Add as u8);
+    bytecode.push(Instruction::Exit as u8);
+
+    let program = CaoCompiledProgram {
+        bytecode,
+        ..Default::default()
+    };
+
+    let outcome = vm.run_resumable(&program).unwrap();
+    let suspended = match outcome {
+        RunOutcome::Yielded(suspended) => suspended,
+        RunOutcome::Finished(_) => panic!("expected the program to yield at `Yield`"),
+    };
+    assert_eq!(suspended.yielded_value(), Some(Value::Integer(1)));
+
+    let vm = Vm::new(()).unwrap();
+    let outcome = vm.resume(suspended, Value::Integer(10)).unwrap();
+    match outcome {
+        RunOutcome::Finished(Value::Integer(result)) => assert_eq!(result, 12),
+        RunOutcome::Finished(_) => panic!("expected the program to finish with an integer"),
+        RunOutcome::Yielded(_) => panic!("expected the program to finish, not yield again"),
+    }
+}
+
+#[test]
+fn test_fuel_exhaustion_pauses_and_resume_continues_after_topping_up() {
+    let vm = Vm::new(()).unwrap();
+
+    let mut bytecode = vec![Instruction::ScalarInt as u8];
+    crate::bytecode::write_to_vec(1i64, &mut bytecode);
+    bytecode.push(Instruction::ScalarInt as u8);
+    crate::bytecode::write_to_vec(2i64, &mut bytecode);
+    // Unlike `Yield`, running out of fuel isn't itself an instruction expecting a value back, but
+    // `resume` pushes `resume_value` regardless - discard it the same way the `Yield` test above
+    // discards its own, so `Add` only sees the two `ScalarInt`s this program pushed.
+    bytecode.push(Instruction::Pop as u8);
+    bytecode.push(Instruction::Add as u8);
+    bytecode.push(Instruction::Exit as u8);
+
+    let program = CaoCompiledProgram {
+        bytecode,
+        ..Default::default()
+    };
+
+    // Fuel for exactly the two `ScalarInt`s - pauses right before `Pop`.
+    let outcome = vm.run_until_fuel_exhausted(&program, 2).unwrap();
+    let suspended = match outcome {
+        RunOutcome::Yielded(suspended) => suspended,
+        RunOutcome::Finished(_) => panic!("expected the program to pause once its fuel ran out"),
+    };
+
+    let mut vm = Vm::new(()).unwrap();
+    vm.add_fuel(100);
+    let outcome = vm.resume(suspended, Value::Nil).unwrap();
+    match outcome {
+        RunOutcome::Finished(Value::Integer(result)) => assert_eq!(result, 3),
+        RunOutcome::Finished(_) => panic!("expected the program to finish with an integer"),
+        RunOutcome::Yielded(_) => panic!("expected the program to finish, not pause again"),
+    }
+}
+
+#[test]
+fn test_pick_instruction_end_to_end() {
+    let mut vm = Vm::new(()).unwrap();
+
+    // push 10, 20, 30; Pick(1) duplicates the 20 on top -> [10, 20, 30, 20]
+    let mut bytecode = vec![Instruction::ScalarInt as u8];
+    crate::bytecode::write_to_vec(10i64, &mut bytecode);
+    bytecode.push(Instruction::ScalarInt as u8);
+    crate::bytecode::write_to_vec(20i64, &mut bytecode);
+    bytecode.push(Instruction::ScalarInt as u8);
+    crate::bytecode::write_to_vec(30i64, &mut bytecode);
+    bytecode.push(Instruction::Pick as u8);
+    crate::bytecode::write_to_vec(1u32, &mut bytecode);
+    bytecode.push(Instruction::Exit as u8);
+
+    let program = CaoCompiledProgram {
+        bytecode,
+        ..Default::default()
+    };
+
+    vm.run(&program).unwrap();
+
+    assert_eq!(vm.runtime_data.value_stack.pop(), Value::Integer(20));
+    assert_eq!(vm.runtime_data.value_stack.pop(), Value::Integer(30));
+    assert_eq!(vm.runtime_data.value_stack.pop(), Value::Integer(20));
+    assert_eq!(vm.runtime_data.value_stack.pop(), Value::Integer(10));
+}
+
+#[test]
+fn test_swap_instruction_end_to_end() {
+    let mut vm = Vm::new(()).unwrap();
+
+    // push 10, 20, 30; Swap(0, 2) swaps the top (30) with the bottom (10) -> [30, 20, 10]
+    let mut bytecode = vec![Instruction::ScalarInt as u8];
+    crate::bytecode::write_to_vec(10i64, &mut bytecode);
+    bytecode.push(Instruction::ScalarInt as u8);
+    crate::bytecode::write_to_vec(20i64, &mut bytecode);
+    bytecode.push(Instruction::ScalarInt as u8);
+    crate::bytecode::write_to_vec(30i64, &mut bytecode);
+    bytecode.push(Instruction::Swap as u8);
+    crate::bytecode::write_to_vec(0u32, &mut bytecode);
+    crate::bytecode::write_to_vec(2u32, &mut bytecode);
+    bytecode.push(Instruction::Exit as u8);
+
+    let program = CaoCompiledProgram {
+        bytecode,
+        ..Default::default()
+    };
+
+    vm.run(&program).unwrap();
+
+    assert_eq!(vm.runtime_data.value_stack.pop(), Value::Integer(10));
+    assert_eq!(vm.runtime_data.value_stack.pop(), Value::Integer(20));
+    assert_eq!(vm.runtime_data.value_stack.pop(), Value::Integer(30));
+}
+
+#[test]
+fn test_rotate_instruction_end_to_end() {
+    let mut vm = Vm::new(()).unwrap();
+
+    // push 10, 20, 30; Rotate(3) moves the top (30) to the bottom of the window -> [30, 10, 20]
+    let mut bytecode = vec![Instruction::ScalarInt as u8];
+    crate::bytecode::write_to_vec(10i64, &mut bytecode);
+    bytecode.push(Instruction::ScalarInt as u8);
+    crate::bytecode::write_to_vec(20i64, &mut bytecode);
+    bytecode.push(Instruction::ScalarInt as u8);
+    crate::bytecode::write_to_vec(30i64, &mut bytecode);
+    bytecode.push(Instruction::Rotate as u8);
+    crate::bytecode::write_to_vec(3u32, &mut bytecode);
+    bytecode.push(Instruction::Exit as u8);
+
+    let program = CaoCompiledProgram {
+        bytecode,
+        ..Default::default()
+    };
+
+    vm.run(&program).unwrap();
+
+    assert_eq!(vm.runtime_data.value_stack.pop(), Value::Integer(20));
+    assert_eq!(vm.runtime_data.value_stack.pop(), Value::Integer(10));
+    assert_eq!(vm.runtime_data.value_stack.pop(), Value::Integer(30));
+}
+
+fn random_range_program(lo: i64, hi: i64) -> CaoCompiledProgram {
+    let mut bytecode = vec![Instruction::ScalarInt as u8];
+    crate::bytecode::write_to_vec(lo, &mut bytecode);
+    bytecode.push(Instruction::ScalarInt as u8);
+    crate::bytecode::write_to_vec(hi, &mut bytecode);
+    bytecode.push(Instruction::Random as u8);
+    bytecode.push(Instruction::Exit as u8);
+    CaoCompiledProgram {
+        bytecode,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_random_instruction_is_reproducible_given_the_same_seed() {
+    let program = random_range_program(1, 6);
+
+    let mut a = Vm::new(()).unwrap().with_seed(42);
+    a.run(&program).unwrap();
+    let roll_a = a.runtime_data.value_stack.pop();
+
+    let mut b = Vm::new(()).unwrap().with_seed(42);
+    b.run(&program).unwrap();
+    let roll_b = b.runtime_data.value_stack.pop();
+
+    assert_eq!(roll_a, roll_b);
+    match roll_a {
+        Value::Integer(n) => assert!((1..=6).contains(&n)),
+        other => panic!("expected an Integer, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_random_instruction_errors_when_the_upper_bound_is_less_than_the_lower_bound() {
+    let mut vm = Vm::new(()).unwrap();
+    let program = random_range_program(6, 1);
+
+    let result = vm.run(&program);
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_dice_roll_instruction_sums_count_independent_rolls() {
+    let mut bytecode = vec![Instruction::ScalarInt as u8];
+    crate::bytecode::write_to_vec(3i64, &mut bytecode); // count
+    bytecode.push(Instruction::ScalarInt as u8);
+    crate::bytecode::write_to_vec(6i64, &mut bytecode); // sides
+    bytecode.push(Instruction::DiceRoll as u8);
+    bytecode.push(Instruction::Exit as u8);
+
+    let program = CaoCompiledProgram {
+        bytecode,
+        ..Default::default()
+    };
+
+    let mut vm = Vm::new(()).unwrap().with_seed(1337);
+    vm.run(&program).unwrap();
+
+    match vm.runtime_data.value_stack.pop() {
+        Value::Integer(total) => assert!((3..=18).contains(&total)),
+        other => panic!("expected an Integer, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_native_function_with_too_few_stack_args_errors_with_stack_underflow() {
+    use crate::traits::{into_f2, VmFunction};
+
+    fn add(_vm: &mut Vm<()>, _a: i64, _b: i64) -> Result<(), ExecutionErrorPayload> {
+        Ok(())
+    }
+
+    let mut vm = Vm::new(()).unwrap();
+    vm.runtime_data
+        .value_stack
+        .push(Value::Integer(1))
+        .unwrap();
+
+    let err = into_f2(add).call(&mut vm).unwrap_err();
+
+    assert!(matches!(err, ExecutionErrorPayload::StackUnderflow));
+}
+
+/// Builds a program that calls a single-argument `countdown` function recursing `n` times via
+/// `TailCall`: `countdown(n) = if n <= 0 { n } else { countdown(n - 1) }`, always in tail
+/// position. Returns the program plus the `Handle` shared by every `FunctionPointer` to it.
+fn countdown_program(n: i64) -> (CaoCompiledProgram, Handle) {
+    let handle = Handle::from_u32(1);
+
+    let mut bytecode = vec![Instruction::ScalarInt as u8];
+    crate::bytecode::write_to_vec(n, &mut bytecode);
+    bytecode.push(Instruction::FunctionPointer as u8);
+    crate::bytecode::write_to_vec(handle, &mut bytecode);
+    crate::bytecode::write_to_vec(1u32, &mut bytecode); // arity
+    crate::bytecode::write_to_vec(1u32, &mut bytecode); // max_locals
+    bytecode.push(Instruction::CallFunction as u8);
+    bytecode.push(Instruction::Exit as u8);
+
+    let countdown_pos = bytecode.len() as u32;
+
+    bytecode.push(Instruction::ReadLocalVar as u8);
+    crate::bytecode::write_to_vec(0u32, &mut bytecode);
+    bytecode.push(Instruction::ScalarInt as u8);
+    crate::bytecode::write_to_vec(0i64, &mut bytecode);
+    bytecode.push(Instruction::LessOrEq as u8);
+    bytecode.push(Instruction::GotoIfFalse as u8);
+    let else_branch_patch = bytecode.len();
+    crate::bytecode::write_to_vec(0i32, &mut bytecode); // patched below
+
+    // then-branch: n <= 0, so just return n
+    bytecode.push(Instruction::ReadLocalVar as u8);
+    crate::bytecode::write_to_vec(0u32, &mut bytecode);
+    bytecode.push(Instruction::Return as u8);
+
+    let else_branch_pos = bytecode.len() as i32;
+    bytecode[else_branch_patch..else_branch_patch + 4]
+        .copy_from_slice(&else_branch_pos.to_ne_bytes());
+
+    // else-branch: tail-call countdown(n - 1)
+    bytecode.push(Instruction::ReadLocalVar as u8);
+    crate::bytecode::write_to_vec(0u32, &mut bytecode);
+    bytecode.push(Instruction::ScalarInt as u8);
+    crate::bytecode::write_to_vec(1i64, &mut bytecode);
+    bytecode.push(Instruction::Sub as u8);
+    bytecode.push(Instruction::FunctionPointer as u8);
+    crate::bytecode::write_to_vec(handle, &mut bytecode);
+    crate::bytecode::write_to_vec(1u32, &mut bytecode); // arity
+    crate::bytecode::write_to_vec(1u32, &mut bytecode); // max_locals
+    bytecode.push(Instruction::TailCall as u8);
+
+    let mut program = CaoCompiledProgram {
+        bytecode,
+        ..Default::default()
+    };
+    program
+        .labels
+        .0
+        .insert(handle, Label::new(countdown_pos))
+        .unwrap();
+
+    (program, handle)
+}
+
+#[test]
+fn test_tail_call_does_not_grow_the_call_stack() {
+    let (program, _) = countdown_program(10_000);
+
+    // a call stack deep enough for only a handful of ordinary (non-tail) calls - recursing 10,000
+    // times via `TailCall` must still fit, since every call reuses the same frame instead of
+    // pushing a new one.
+    let mut vm = Vm::new(()).unwrap().with_call_stack_limit(4);
+
+    vm.run(&program).unwrap();
+
+    assert_eq!(vm.runtime_data.value_stack.pop(), Value::Integer(0));
+}
+
+/// Counts of the [`RuntimeObserver`] hooks fired while a program runs.
+#[derive(Default)]
+struct ObserverCounts {
+    enter_frames: usize,
+    exit_frames: usize,
+    executed_ops: usize,
+}
+
+/// Records hook calls into a shared [`ObserverCounts`], the same "closure over an `Rc<RefCell>`"
+/// shape `test_trap_handler_observes_a_fault_before_it_propagates` uses for `with_trap_handler`.
+struct CountingObserver(Rc<RefCell<ObserverCounts>>);
+
+impl RuntimeObserver<()> for CountingObserver {
+    fn observe_enter_frame(&mut self, _vm: &Vm<()>, _label: Handle, arity: u32) {
+        assert_eq!(arity, 1);
+        self.0.borrow_mut().enter_frames += 1;
+    }
+
+    fn observe_exit_frame(&mut self, _vm: &Vm<()>, return_value: Value) {
+        assert_eq!(return_value, Value::Integer(42));
+        self.0.borrow_mut().exit_frames += 1;
+    }
+
+    fn observe_execute_op(&mut self, _vm: &Vm<()>, _op: Instruction, _instr_ptr: usize) {
+        self.0.borrow_mut().executed_ops += 1;
+    }
+}
+
+#[test]
+fn test_observer_sees_every_call_frame_and_instruction() {
+    let counts = Rc::new(RefCell::new(ObserverCounts::default()));
+
+    let handle = Handle::from_u32(1);
+
+    let mut bytecode = vec![Instruction::ScalarInt as u8];
+    crate::bytecode::write_to_vec(21i64, &mut bytecode);
+    bytecode.push(Instruction::FunctionPointer as u8);
+    crate::bytecode::write_to_vec(handle, &mut bytecode);
+    crate::bytecode::write_to_vec(1u32, &mut bytecode); // arity
+    crate::bytecode::write_to_vec(1u32, &mut bytecode); // max_locals
+    bytecode.push(Instruction::CallFunction as u8);
+    bytecode.push(Instruction::Exit as u8);
+
+    let double_pos = bytecode.len() as u32;
+    bytecode.push(Instruction::ReadLocalVar as u8);
+    crate::bytecode::write_to_vec(0u32, &mut bytecode);
+    bytecode.push(Instruction::ScalarInt as u8);
+    crate::bytecode::write_to_vec(2i64, &mut bytecode);
+    bytecode.push(Instruction::Mul as u8);
+    bytecode.push(Instruction::Return as u8);
+
+    let mut program = CaoCompiledProgram {
+        bytecode,
+        ..Default::default()
+    };
+    program
+        .labels
+        .0
+        .insert(handle, Label::new(double_pos))
+        .unwrap();
+
+    let mut vm = Vm::new(()).unwrap().with_observer(CountingObserver(counts.clone()));
+
+    vm.run(&program).unwrap();
+
+    assert_eq!(vm.runtime_data.value_stack.pop(), Value::Integer(42));
+    let counts = counts.borrow();
+    assert_eq!(counts.enter_frames, 1);
+    assert_eq!(counts.exit_frames, 1);
+    // ScalarInt, FunctionPointer, CallFunction, (callee:) ReadLocalVar, ScalarInt, Mul, Return, Exit
+    assert_eq!(counts.executed_ops, 8);
+}
+
+/// A native function that pops a cao-lang callable off the stack and calls it back into the VM
+/// via [`Vm::call_value`], passing `10` as its sole argument - the higher-order-function pattern
+/// `call_value` exists for (`map`/`filter`/`sort` taking a cao-lang callback).
+struct ApplyWithTen;
+
+impl crate::traits::VmFunction<()> for ApplyWithTen {
+    fn call(&self, vm: &mut Vm<()>) -> Result<(), ExecutionErrorPayload> {
+        let callable = vm.stack_pop();
+        let result = vm.call_value(callable, &[Value::Integer(10)])?;
+        vm.stack_push(result)?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_call_value_invokes_a_cao_lang_function_from_a_native_call() {
+    let handle = Handle::from_u32(1);
+
+    let mut bytecode = vec![Instruction::FunctionPointer as u8];
+    crate::bytecode::write_to_vec(handle, &mut bytecode);
+    crate::bytecode::write_to_vec(1u32, &mut bytecode); // arity
+    crate::bytecode::write_to_vec(1u32, &mut bytecode); // max_locals
+    bytecode.push(Instruction::CallNative as u8);
+    crate::bytecode::write_to_vec(Handle::from_str("apply_with_ten").unwrap(), &mut bytecode);
+    bytecode.push(Instruction::Exit as u8);
+
+    let increment_pos = bytecode.len() as u32;
+    bytecode.push(Instruction::ReadLocalVar as u8);
+    crate::bytecode::write_to_vec(0u32, &mut bytecode);
+    bytecode.push(Instruction::ScalarInt as u8);
+    crate::bytecode::write_to_vec(1i64, &mut bytecode);
+    bytecode.push(Instruction::Add as u8);
+    bytecode.push(Instruction::Return as u8);
+
+    let mut program = CaoCompiledProgram {
+        bytecode,
+        ..Default::default()
+    };
+    program
+        .labels
+        .0
+        .insert(handle, Label::new(increment_pos))
+        .unwrap();
+
+    let mut vm = Vm::new(()).unwrap();
+    vm.register_native_function("apply_with_ten", ApplyWithTen)
+        .unwrap();
+
+    vm.run(&program).unwrap();
+
+    assert_eq!(vm.runtime_data.value_stack.pop(), Value::Integer(11));
+}
+
+#[test]
+fn test_two_closures_capturing_the_same_local_share_one_upvalue() {
+    let handle_a = Handle::from_u32(1);
+    let handle_b = Handle::from_u32(2);
+
+    // local 0 := 10
+    let mut bytecode = vec![Instruction::ScalarInt as u8];
+    crate::bytecode::write_to_vec(10i64, &mut bytecode);
+
+    // closure A captures local 0 as upvalue 0
+    bytecode.push(Instruction::Closure as u8);
+    crate::bytecode::write_to_vec(handle_a, &mut bytecode);
+    crate::bytecode::write_to_vec(0u32, &mut bytecode); // arity
+    crate::bytecode::write_to_vec(0u32, &mut bytecode); // max_locals
+    bytecode.push(Instruction::CopyLast as u8);
+    bytecode.push(Instruction::RegisterUpvalue as u8);
+    crate::bytecode::write_to_vec(0u8, &mut bytecode); // index of the captured local
+    crate::bytecode::write_to_vec(1u8, &mut bytecode); // is_local
+
+    // closure B captures the very same local 0 as its own upvalue 0
+    bytecode.push(Instruction::Closure as u8);
+    crate::bytecode::write_to_vec(handle_b, &mut bytecode);
+    crate::bytecode::write_to_vec(0u32, &mut bytecode); // arity
+    crate::bytecode::write_to_vec(0u32, &mut bytecode); // max_locals
+    bytecode.push(Instruction::CopyLast as u8);
+    bytecode.push(Instruction::RegisterUpvalue as u8);
+    crate::bytecode::write_to_vec(0u8, &mut bytecode);
+    crate::bytecode::write_to_vec(1u8, &mut bytecode);
+
+    // call B: writes 42 into the shared upvalue
+    bytecode.push(Instruction::CallFunction as u8);
+    bytecode.push(Instruction::Pop as u8); // discard B's return value
+
+    // call A: reads the upvalue back, proving it observed B's write
+    bytecode.push(Instruction::CallFunction as u8);
+    bytecode.push(Instruction::Exit as u8);
+
+    let closure_a_pos = bytecode.len() as u32;
+    bytecode.push(Instruction::ReadUpvalue as u8);
+    crate::bytecode::write_to_vec(0u32, &mut bytecode);
+    bytecode.push(Instruction::Return as u8);
+
+    let closure_b_pos = bytecode.len() as u32;
+    bytecode.push(Instruction::ScalarInt as u8);
+    crate::bytecode::write_to_vec(42i64, &mut bytecode);
+    bytecode.push(Instruction::SetUpvalue as u8);
+    crate::bytecode::write_to_vec(0u32, &mut bytecode);
+    bytecode.push(Instruction::ScalarNil as u8);
+    bytecode.push(Instruction::Return as u8);
+
+    let mut program = CaoCompiledProgram {
+        bytecode,
+        ..Default::default()
+    };
+    program
+        .labels
+        .0
+        .insert(handle_a, Label::new(closure_a_pos))
+        .unwrap();
+    program
+        .labels
+        .0
+        .insert(handle_b, Label::new(closure_b_pos))
+        .unwrap();
+
+    let mut vm = Vm::new(()).unwrap();
+
+    vm.run(&program).unwrap();
+
+    assert_eq!(vm.runtime_data.value_stack.pop(), Value::Integer(42));
+}
+
+#[test]
+fn test_closure_capture_accounts_for_the_creating_frames_stack_offset() {
+    let make_closure = Handle::from_u32(1);
+    let inner = Handle::from_u32(2);
+
+    // a filler value so `make_closure` gets called with a non-zero `stack_offset` - the scenario
+    // that requires `RegisterUpvalue`'s local index to be translated relative to the current
+    // frame instead of taken as an absolute stack position
+    let mut bytecode = vec![Instruction::ScalarInt as u8];
+    crate::bytecode::write_to_vec(99i64, &mut bytecode);
+
+    // call `make_closure`, which builds and returns a closure over one of its own locals
+    bytecode.push(Instruction::Closure as u8);
+    crate::bytecode::write_to_vec(make_closure, &mut bytecode);
+    crate::bytecode::write_to_vec(0u32, &mut bytecode); // arity
+    crate::bytecode::write_to_vec(0u32, &mut bytecode); // max_locals
+    bytecode.push(Instruction::CallFunction as u8);
+
+    // call the closure `make_closure` handed back; it should still see the captured local even
+    // though the frame that created it is long gone
+    bytecode.push(Instruction::CallFunction as u8);
+    bytecode.push(Instruction::Exit as u8);
+
+    let make_closure_pos = bytecode.len() as u32;
+    // local 0 := 7
+    bytecode.push(Instruction::ScalarInt as u8);
+    crate::bytecode::write_to_vec(7i64, &mut bytecode);
+    bytecode.push(Instruction::Closure as u8);
+    crate::bytecode::write_to_vec(inner, &mut bytecode);
+    crate::bytecode::write_to_vec(0u32, &mut bytecode); // arity
+    crate::bytecode::write_to_vec(0u32, &mut bytecode); // max_locals
+    bytecode.push(Instruction::CopyLast as u8);
+    bytecode.push(Instruction::RegisterUpvalue as u8);
+    crate::bytecode::write_to_vec(0u8, &mut bytecode); // local 0, frame-relative
+    crate::bytecode::write_to_vec(1u8, &mut bytecode); // is_local
+    bytecode.push(Instruction::Return as u8); // hands the closure back, closing its upvalue
+
+    let inner_pos = bytecode.len() as u32;
+    bytecode.push(Instruction::ReadUpvalue as u8);
+    crate::bytecode::write_to_vec(0u32, &mut bytecode);
+    bytecode.push(Instruction::Return as u8);
+
+    let mut program = CaoCompiledProgram {
+        bytecode,
+        ..Default::default()
+    };
+    program
+        .labels
+        .0
+        .insert(make_closure, Label::new(make_closure_pos))
+        .unwrap();
+    program
+        .labels
+        .0
+        .insert(inner, Label::new(inner_pos))
+        .unwrap();
+
+    let mut vm = Vm::new(()).unwrap();
+
+    vm.run(&program).unwrap();
+
+    assert_eq!(vm.runtime_data.value_stack.pop(), Value::Integer(7));
+}
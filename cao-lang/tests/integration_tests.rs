@@ -1,7 +1,7 @@
 use std::ops::DerefMut;
 
 use cao_lang::{
-    compiler::{CompositeCard, Module, UnaryExpression},
+    compiler::{CompositeCard, ForEach, Module, UnaryExpression},
     prelude::*,
 };
 
@@ -329,6 +329,180 @@ fn local_variable_doesnt_leak_out_of_scope() {
     ));
 }
 
+#[test]
+fn try_catch_binds_thrown_value_to_catch_var() {
+    let program = CaoProgram {
+        imports: Default::default(),
+        submodules: Default::default(),
+        functions: [(
+            "main".into(),
+            Function::default().with_card(Card::try_catch(
+                vec![Card::throw(Card::string_card("boom"))],
+                Some("err".to_string()),
+                vec![Card::set_global_var("result", Card::read_var("err"))],
+            )),
+        )]
+        .into(),
+    };
+
+    let program = compile(program, None).expect("compile");
+
+    let mut vm = Vm::new(()).unwrap().with_max_iter(500);
+    vm.run(&program).expect("run");
+
+    let res = vm
+        .read_var_by_name("result", &program.variables)
+        .expect("Failed to read result variable");
+    let res = unsafe { res.as_str().expect("Failed to read string") };
+    assert_eq!(res, "boom");
+}
+
+#[test]
+fn try_catch_recovers_from_runtime_fault() {
+    let program = CaoProgram {
+        imports: Default::default(),
+        submodules: Default::default(),
+        functions: [(
+            "main".into(),
+            Function::default().with_card(Card::try_catch(
+                vec![Card::set_global_var(
+                    "unused",
+                    Card::Div(Box::new([Card::ScalarInt(1), Card::ScalarInt(0)])),
+                )],
+                Some("err".to_string()),
+                vec![Card::set_global_var("kind", Card::read_var("err.kind"))],
+            )),
+        )]
+        .into(),
+    };
+
+    let program = compile(program, None).expect("compile");
+
+    let mut vm = Vm::new(()).unwrap().with_max_iter(500);
+    vm.run(&program).expect("run");
+
+    let res = vm
+        .read_var_by_name("kind", &program.variables)
+        .expect("Failed to read kind variable");
+    let res = unsafe { res.as_str().expect("Failed to read string") };
+    assert_eq!(res, "DivideByZero");
+}
+
+#[test]
+fn try_catch_var_doesnt_leak_out_of_scope() {
+    let program = CaoProgram {
+        imports: Default::default(),
+        submodules: Default::default(),
+        functions: [(
+            "main".into(),
+            Function::default()
+                .with_card(Card::try_catch(
+                    vec![Card::throw(Card::string_card("boom"))],
+                    Some("err".to_string()),
+                    vec![],
+                ))
+                .with_card(Card::read_var("err")),
+        )]
+        .into(),
+    };
+
+    let program = compile(program, None).expect("compile");
+
+    let mut vm = Vm::new(()).unwrap().with_max_iter(500);
+    let res = vm.run(&program);
+    assert!(matches!(
+        res.map_err(|err| err.payload),
+        Err(ExecutionErrorPayload::VarNotFound(_))
+    ));
+}
+
+#[test]
+fn for_each_sums_table_values() {
+    let program = CaoProgram {
+        imports: Default::default(),
+        submodules: Default::default(),
+        functions: [(
+            "main".into(),
+            Function::default()
+                .with_card(Card::set_var("t", Card::CreateTable))
+                .with_card(Card::set_property(
+                    Card::ScalarInt(3),
+                    Card::read_var("t"),
+                    Card::string_card("a"),
+                ))
+                .with_card(Card::set_property(
+                    Card::ScalarInt(5),
+                    Card::read_var("t"),
+                    Card::string_card("b"),
+                ))
+                .with_card(Card::set_property(
+                    Card::ScalarInt(7),
+                    Card::read_var("t"),
+                    Card::string_card("c"),
+                ))
+                .with_card(Card::set_var("sum", Card::ScalarInt(0)))
+                .with_card(Card::ForEach(Box::new(ForEach {
+                    i: None,
+                    k: None,
+                    v: Some("v".to_string()),
+                    iterable: Box::new(Card::read_var("t")),
+                    body: Box::new(Card::set_var(
+                        "sum",
+                        Card::Add(Box::new([Card::read_var("sum"), Card::read_var("v")])),
+                    )),
+                })))
+                .with_card(Card::set_global_var("result", Card::read_var("sum"))),
+        )]
+        .into(),
+    };
+
+    let program = compile(program, None).expect("compile");
+
+    let mut vm = Vm::new(()).unwrap().with_max_iter(500);
+    vm.run(&program).expect("run");
+
+    let res = vm
+        .read_var_by_name("result", &program.variables)
+        .expect("Failed to read result variable");
+    assert_eq!(res, Value::Integer(15));
+}
+
+#[test]
+fn for_each_loop_vars_dont_leak_out_of_scope() {
+    let program = CaoProgram {
+        imports: Default::default(),
+        submodules: Default::default(),
+        functions: [(
+            "main".into(),
+            Function::default()
+                .with_card(Card::set_var("t", Card::CreateTable))
+                .with_card(Card::set_property(
+                    Card::ScalarInt(1),
+                    Card::read_var("t"),
+                    Card::string_card("a"),
+                ))
+                .with_card(Card::ForEach(Box::new(ForEach {
+                    i: None,
+                    k: Some("k".to_string()),
+                    v: Some("v".to_string()),
+                    iterable: Box::new(Card::read_var("t")),
+                    body: Box::new(Card::ScalarNil),
+                })))
+                .with_card(Card::read_var("v")),
+        )]
+        .into(),
+    };
+
+    let program = compile(program, None).expect("compile");
+
+    let mut vm = Vm::new(()).unwrap().with_max_iter(500);
+    let res = vm.run(&program);
+    assert!(matches!(
+        res.map_err(|err| err.payload),
+        Err(ExecutionErrorPayload::VarNotFound(_))
+    ));
+}
+
 #[test]
 fn simple_for_loop() {
     let program = CaoProgram {
@@ -1,21 +1,59 @@
-use cao_lang::{compiler, prelude::CaoProgram};
-use clap::{Arg, Command};
+use cao_lang::{compiler, disasm, prelude::CaoProgram};
+use clap::{builder::PossibleValuesParser, Arg, Command};
 
 fn main() {
-    let app = Command::new("Cao-Lang Disassembler").arg(
-        Arg::new("json")
-            .long("json")
-            .help("Accept json encoded cao-lang program"),
-    );
+    let app = Command::new("Cao-Lang Disassembler")
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .num_args(0)
+                .help("Input is a json encoded cao-lang program"),
+        )
+        .arg(
+            Arg::new("yaml")
+                .long("yaml")
+                .num_args(0)
+                .conflicts_with("json")
+                .help("Input is a yaml encoded cao-lang program"),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .num_args(1)
+                .value_parser(PossibleValuesParser::new(["text", "json"]))
+                .default_value("text")
+                .help(
+                    "Output format: a human-readable listing, or one JSON object per instruction",
+                ),
+        );
 
     let args = app.get_matches();
-    if args.is_present("json") {
-        let reader = std::io::BufReader::new(std::io::stdin().lock());
-        let pl: CaoProgram = serde_json::from_reader(reader).expect("Failed to deserialize");
-        let compiled = compiler::compile(pl, None).expect("Failed to compile");
 
-        compiled.print_disassembly();
+    let mut payload = String::new();
+    std::io::Read::read_to_string(&mut std::io::stdin().lock(), &mut payload)
+        .expect("Failed to read stdin");
+
+    let pl: CaoProgram = if args.get_flag("yaml") {
+        serde_yaml::from_str(&payload).expect("Failed to deserialize yaml")
+    } else if args.get_flag("json") {
+        serde_json::from_str(&payload).expect("Failed to deserialize json")
     } else {
-        panic!("Missing format")
+        // no format was specified: try json, falling back to yaml
+        serde_json::from_str(&payload)
+            .or_else(|_| serde_yaml::from_str(&payload))
+            .expect("Failed to deserialize input as json or yaml")
+    };
+
+    let compiled = compiler::compile(pl, None).expect("Failed to compile");
+
+    match args.get_one::<String>("format").map(String::as_str) {
+        Some("json") => {
+            let entries = disasm::disasm_entries(&compiled).expect("Failed to disassemble");
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&entries).expect("Failed to serialize disassembly")
+            );
+        }
+        _ => compiled.print_disassembly(),
     }
 }
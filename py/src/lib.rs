@@ -1,9 +1,12 @@
 use pyo3::{
     exceptions::{PyRuntimeError, PyValueError},
     prelude::*,
+    types::{PyBytes, PyDict, PyTuple},
     wrap_pyfunction,
 };
 
+use cao_lang::prelude::{ExecutionErrorPayload, OwnedEntry, OwnedValue, VmFunction};
+
 #[pyclass]
 #[derive(Clone)]
 pub struct CompilationUnit {
@@ -50,6 +53,181 @@ impl CompilationOptions {
     pub fn set_recursion_limit(&mut self, value: u32) {
         self.inner.recursion_limit = value;
     }
+
+    #[getter(constant_folding)]
+    pub fn get_constant_folding(&self) -> bool {
+        self.inner.constant_folding
+    }
+
+    #[setter(constant_folding)]
+    pub fn set_constant_folding(&mut self, value: bool) {
+        self.inner.constant_folding = value;
+    }
+}
+
+/// Converts a Cao-Lang [`OwnedValue`] (the host-facing, GC-independent value representation used
+/// for round-tripping values in and out of a running [`cao_lang::vm::Vm`]) into a Python object.
+fn owned_value_to_py(py: Python<'_>, value: &OwnedValue) -> Py<PyAny> {
+    match value {
+        OwnedValue::Nil => py.None(),
+        OwnedValue::Integer(i) => i.into_py(py),
+        OwnedValue::Real(r) => r.into_py(py),
+        OwnedValue::String(s) => s.into_py(py),
+        OwnedValue::Bytes(b) => PyBytes::new_bound(py, b).into(),
+        // Python's `int` is already arbitrary-precision, so a BigInt round-trips through it the
+        // same as an Integer
+        OwnedValue::BigInt(i) => i.into_py(py),
+        OwnedValue::Table(entries) => {
+            let dict = PyDict::new_bound(py);
+            for entry in entries {
+                let key = owned_value_to_py(py, &entry.key);
+                let value = owned_value_to_py(py, &entry.value);
+                dict.set_item(key, value)
+                    .expect("setting an item on a freshly created dict never fails");
+            }
+            dict.into()
+        }
+    }
+}
+
+/// The inverse of [`owned_value_to_py`]. Python `bool`s fold into [`OwnedValue::Integer`] (`0`/`1`)
+/// the same way Cao-Lang's own [`cao_lang::prelude::Value`] represents booleans.
+fn py_to_owned_value(obj: &Bound<'_, PyAny>) -> PyResult<OwnedValue> {
+    if obj.is_none() {
+        return Ok(OwnedValue::Nil);
+    }
+    if let Ok(b) = obj.extract::<bool>() {
+        return Ok(OwnedValue::Integer(b as i64));
+    }
+    if let Ok(i) = obj.extract::<i64>() {
+        return Ok(OwnedValue::Integer(i));
+    }
+    // Python `int`s wider than `i64` (but within `i128`) round-trip as a BigInt instead of
+    // falling through to `f64` and silently losing precision.
+    if let Ok(i) = obj.extract::<i128>() {
+        return Ok(OwnedValue::BigInt(i));
+    }
+    if let Ok(f) = obj.extract::<f64>() {
+        return Ok(OwnedValue::Real(f));
+    }
+    if let Ok(s) = obj.extract::<String>() {
+        return Ok(OwnedValue::String(s));
+    }
+    if let Ok(b) = obj.extract::<Vec<u8>>() {
+        return Ok(OwnedValue::Bytes(b));
+    }
+    if let Ok(dict) = obj.downcast::<PyDict>() {
+        let mut entries = Vec::with_capacity(dict.len());
+        for (key, value) in dict.iter() {
+            entries.push(OwnedEntry {
+                key: py_to_owned_value(&key)?,
+                value: py_to_owned_value(&value)?,
+            });
+        }
+        return Ok(OwnedValue::Table(entries));
+    }
+    Err(PyValueError::new_err(format!(
+        "Can not convert Python value {obj} to a Cao-Lang value"
+    )))
+}
+
+/// Wraps a Python callable so it can be registered as a Cao-Lang native function via
+/// [`Vm::register_fn`]. Pops `arity` values off the VM stack - in call order, mirroring the stack
+/// discipline of the built-in `VmFunction1..4` impls in `cao_lang::traits` - converts each to a
+/// Python object, invokes `callable`, then converts the return value back and pushes it.
+struct PyNativeFunction {
+    arity: usize,
+    callable: Py<PyAny>,
+}
+
+impl VmFunction<()> for PyNativeFunction {
+    fn call(&self, vm: &mut cao_lang::vm::Vm<()>) -> Result<(), ExecutionErrorPayload> {
+        Python::with_gil(|py| {
+            let mut args = Vec::with_capacity(self.arity);
+            for _ in 0..self.arity {
+                let owned = OwnedValue::try_from(vm.stack_pop()).map_err(|_| {
+                    ExecutionErrorPayload::invalid_argument(
+                        "Cao-Lang value has no Python representation",
+                    )
+                })?;
+                args.push(owned_value_to_py(py, &owned));
+            }
+            args.reverse();
+
+            let result = self
+                .callable
+                .call1(py, PyTuple::new_bound(py, args))
+                .map_err(|err| ExecutionErrorPayload::TaskFailure {
+                    name: "<python>".to_string(),
+                    error: Box::new(ExecutionErrorPayload::invalid_argument(err.to_string())),
+                })?;
+
+            let owned = py_to_owned_value(result.bind(py)).map_err(|err| {
+                ExecutionErrorPayload::TaskFailure {
+                    name: "<python>".to_string(),
+                    error: Box::new(ExecutionErrorPayload::invalid_argument(err.to_string())),
+                }
+            })?;
+            let value = vm.insert_value(&owned)?;
+            vm.stack_push(value)
+        })
+    }
+}
+
+/// A Cao-Lang VM that Python host functions can be registered on before running a program.
+///
+/// Unlike the stateless [`run`] function, this keeps the same [`cao_lang::vm::Vm`] across calls so
+/// registered functions and globals survive between `run` and `read_var`.
+#[pyclass]
+pub struct Vm {
+    inner: cao_lang::vm::Vm<'static, ()>,
+}
+
+#[pymethods]
+impl Vm {
+    #[new]
+    fn new() -> PyResult<Self> {
+        let inner =
+            cao_lang::vm::Vm::new(()).map_err(|err| PyRuntimeError::new_err(err.to_string()))?;
+        Ok(Self { inner })
+    }
+
+    /// Register `callback` as the native function `name`, to be called with `arity` arguments
+    /// popped off the stack.
+    fn register_fn(&mut self, name: String, arity: usize, callback: Py<PyAny>) -> PyResult<()> {
+        self.inner
+            .register_native_function(
+                name,
+                PyNativeFunction {
+                    arity,
+                    callable: callback,
+                },
+            )
+            .map_err(|err| PyRuntimeError::new_err(err.to_string()))
+    }
+
+    fn run(&mut self, prog: &CaoCompiledProgram) -> PyResult<()> {
+        self.inner
+            .run(&prog.inner)
+            .map_err(|err| PyRuntimeError::new_err(err.to_string()))
+    }
+
+    /// Read a global variable by name, as set by the program `prog` was compiled from (see the
+    /// benches' `read_var_by_name` usage for the Rust-side equivalent). Returns `None` if `prog`
+    /// declares no such variable.
+    fn read_var(&self, name: &str, prog: &CaoCompiledProgram) -> PyResult<Option<Py<PyAny>>> {
+        let value = self.inner.read_var_by_name(name, &prog.inner.variables);
+        Python::with_gil(|py| {
+            value
+                .map(|v| {
+                    let owned = OwnedValue::try_from(v).map_err(|_| {
+                        PyValueError::new_err("Cao-Lang value has no Python representation")
+                    })?;
+                    Ok(owned_value_to_py(py, &owned))
+                })
+                .transpose()
+        })
+    }
 }
 
 #[pyclass]
@@ -92,6 +270,7 @@ fn cao_lang_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<CompilationUnit>()?;
     m.add_class::<CompilationOptions>()?;
     m.add_class::<CaoCompiledProgram>()?;
+    m.add_class::<Vm>()?;
 
     Ok(())
 }
@@ -55,10 +55,12 @@ pub mod vm;
 
 use crate::compiler::NodeId;
 use crate::instruction::Instruction;
+use crate::traits::ByteEncodeProperties;
 use arrayvec::ArrayString;
 use serde_derive::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::ops::Index;
+use std::str::FromStr;
 
 pub type TPointer = i32;
 
@@ -96,7 +98,24 @@ impl Index<i32> for Label {
 }
 
 pub type VarName = ArrayString<[u8; 64]>;
-impl crate::traits::AutoByteEncodeProperties for VarName {}
+
+/// Length-prefixed, explicit little-endian encoding, same scheme as `String`'s
+/// [`ByteEncodeProperties`](crate::traits::ByteEncodeProperties) impl - `VarName` is just a
+/// `String` bounded to a fixed capacity.
+impl crate::traits::ByteEncodeProperties for VarName {
+    const BYTELEN: usize = 64 + i32::BYTELEN;
+    type DecodeError = crate::traits::StringDecodeError;
+
+    fn encode(self) -> Vec<u8> {
+        self.as_str().to_owned().encode()
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, Self::DecodeError> {
+        let s = String::decode(bytes)?;
+        VarName::from_str(&s)
+            .map_err(|_| crate::traits::StringDecodeError::CapacityError(64))
+    }
+}
 
 /// Metadata about a subprogram in the program.
 /// Subprograms consume their inputs and produce outputs.
@@ -85,111 +85,139 @@ impl ByteEncodeProperties for () {
     }
 }
 
-/// Opts in for the default implementation of ByteEncodeProperties
-/// Note that using this with pointers, arrays, strings etc. will not work as one might expect!
-pub trait AutoByteEncodeProperties {
-    fn displayname() -> &'static str {
-        type_name::<Self>()
-    }
-}
+impl<T: std::fmt::Debug> ObjectProperties for T {}
 
-impl AutoByteEncodeProperties for i8 {
-    fn displayname() -> &'static str {
-        "Integer"
-    }
-}
-impl AutoByteEncodeProperties for i16 {
-    fn displayname() -> &'static str {
-        "Integer"
-    }
-}
-impl AutoByteEncodeProperties for i32 {
-    fn displayname() -> &'static str {
-        "Integer"
-    }
-}
-impl AutoByteEncodeProperties for i64 {
-    fn displayname() -> &'static str {
-        "Integer"
-    }
-}
-impl AutoByteEncodeProperties for u8 {
-    fn displayname() -> &'static str {
-        "Integer"
-    }
-}
-impl AutoByteEncodeProperties for u16 {
-    fn displayname() -> &'static str {
-        "Integer"
-    }
+/// A primitive (or a tuple of primitives) failed to decode: the slice handed to
+/// [`ByteEncodeProperties::decode`] was shorter than the type's `BYTELEN`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrimitiveDecodeError {
+    pub displayname: &'static str,
+    pub expected_len: usize,
+    pub got_len: usize,
+}
+
+/// Implements [`ByteEncodeProperties`] for a numeric primitive via its own `to_le_bytes`/
+/// `from_le_bytes`, instead of transmuting the whole value in and out of a byte buffer: the
+/// previous blanket impl read `*(bytes.as_ptr() as *const Self)`, which is undefined behavior on
+/// an unaligned slice and dumps the host's native byte order, so a program serialized on a
+/// big-endian host would decode incorrectly on a little-endian one.
+macro_rules! impl_byte_encode_primitive {
+    ($ty:ty) => {
+        impl ByteEncodeProperties for $ty {
+            type DecodeError = PrimitiveDecodeError;
+
+            fn encode(self) -> Vec<u8> {
+                self.to_le_bytes().to_vec()
+            }
+
+            fn decode(bytes: &[u8]) -> Result<Self, Self::DecodeError> {
+                let buf: &[u8; Self::BYTELEN] = bytes
+                    .get(..Self::BYTELEN)
+                    .and_then(|s| s.try_into().ok())
+                    .ok_or(PrimitiveDecodeError {
+                        displayname: Self::displayname(),
+                        expected_len: Self::BYTELEN,
+                        got_len: bytes.len(),
+                    })?;
+                Ok(Self::from_le_bytes(*buf))
+            }
+        }
+    };
 }
-impl AutoByteEncodeProperties for u32 {
-    fn displayname() -> &'static str {
-        "Integer"
+
+impl_byte_encode_primitive!(i8);
+impl_byte_encode_primitive!(i16);
+impl_byte_encode_primitive!(i32);
+impl_byte_encode_primitive!(i64);
+impl_byte_encode_primitive!(u8);
+impl_byte_encode_primitive!(u16);
+impl_byte_encode_primitive!(u32);
+impl_byte_encode_primitive!(u64);
+impl_byte_encode_primitive!(f32);
+impl_byte_encode_primitive!(f64);
+
+impl<T1: ByteEncodeProperties<DecodeError = PrimitiveDecodeError>> ByteEncodeProperties for (T1,) {
+    const BYTELEN: usize = T1::BYTELEN;
+    type DecodeError = PrimitiveDecodeError;
+
+    fn encode(self) -> Vec<u8> {
+        self.0.encode()
     }
-}
-impl AutoByteEncodeProperties for u64 {
-    fn displayname() -> &'static str {
-        "Integer"
+
+    fn decode(bytes: &[u8]) -> Result<Self, Self::DecodeError> {
+        Ok((T1::decode(bytes)?,))
     }
 }
-impl AutoByteEncodeProperties for f32 {
-    fn displayname() -> &'static str {
-        "Floating point"
+
+impl<T1, T2> ByteEncodeProperties for (T1, T2)
+where
+    T1: ByteEncodeProperties<DecodeError = PrimitiveDecodeError>,
+    T2: ByteEncodeProperties<DecodeError = PrimitiveDecodeError>,
+{
+    const BYTELEN: usize = T1::BYTELEN + T2::BYTELEN;
+    type DecodeError = PrimitiveDecodeError;
+
+    fn encode(self) -> Vec<u8> {
+        let mut result = self.0.encode();
+        result.extend(self.1.encode());
+        result
     }
-}
-impl AutoByteEncodeProperties for f64 {
-    fn displayname() -> &'static str {
-        "Floating point"
+
+    fn decode(bytes: &[u8]) -> Result<Self, Self::DecodeError> {
+        let v1 = T1::decode(bytes)?;
+        let v2 = T2::decode(&bytes[T1::BYTELEN..])?;
+        Ok((v1, v2))
     }
 }
 
-impl<T1: AutoByteEncodeProperties> AutoByteEncodeProperties for (T1,) {}
-
-impl<T1: AutoByteEncodeProperties, T2: AutoByteEncodeProperties> AutoByteEncodeProperties
-    for (T1, T2)
+impl<T1, T2, T3> ByteEncodeProperties for (T1, T2, T3)
+where
+    T1: ByteEncodeProperties<DecodeError = PrimitiveDecodeError>,
+    T2: ByteEncodeProperties<DecodeError = PrimitiveDecodeError>,
+    T3: ByteEncodeProperties<DecodeError = PrimitiveDecodeError>,
 {
-}
+    const BYTELEN: usize = T1::BYTELEN + T2::BYTELEN + T3::BYTELEN;
+    type DecodeError = PrimitiveDecodeError;
 
-impl<T1: AutoByteEncodeProperties, T2: AutoByteEncodeProperties, T3: AutoByteEncodeProperties>
-    AutoByteEncodeProperties for (T1, T2, T3)
-{
-}
+    fn encode(self) -> Vec<u8> {
+        let mut result = self.0.encode();
+        result.extend(self.1.encode());
+        result.extend(self.2.encode());
+        result
+    }
 
-impl<
-        T1: AutoByteEncodeProperties,
-        T2: AutoByteEncodeProperties,
-        T3: AutoByteEncodeProperties,
-        T4: AutoByteEncodeProperties,
-    > AutoByteEncodeProperties for (T1, T2, T3, T4)
-{
+    fn decode(bytes: &[u8]) -> Result<Self, Self::DecodeError> {
+        let v1 = T1::decode(bytes)?;
+        let v2 = T2::decode(&bytes[T1::BYTELEN..])?;
+        let v3 = T3::decode(&bytes[T1::BYTELEN + T2::BYTELEN..])?;
+        Ok((v1, v2, v3))
+    }
 }
 
-impl<T: std::fmt::Debug> ObjectProperties for T {}
-
-impl<T: Sized + Clone + Copy + AutoByteEncodeProperties + std::fmt::Debug> ByteEncodeProperties
-    for T
+impl<T1, T2, T3, T4> ByteEncodeProperties for (T1, T2, T3, T4)
+where
+    T1: ByteEncodeProperties<DecodeError = PrimitiveDecodeError>,
+    T2: ByteEncodeProperties<DecodeError = PrimitiveDecodeError>,
+    T3: ByteEncodeProperties<DecodeError = PrimitiveDecodeError>,
+    T4: ByteEncodeProperties<DecodeError = PrimitiveDecodeError>,
 {
-    type DecodeError = ();
+    const BYTELEN: usize = T1::BYTELEN + T2::BYTELEN + T3::BYTELEN + T4::BYTELEN;
+    type DecodeError = PrimitiveDecodeError;
 
     fn encode(self) -> Vec<u8> {
-        let mut result = vec![0; Self::BYTELEN];
-        unsafe {
-            let dayum = mem::transmute::<*const Self, *const u8>(&self as *const Self);
-            for i in 0..Self::BYTELEN {
-                result[i] = *(dayum.add(i));
-            }
-        }
+        let mut result = self.0.encode();
+        result.extend(self.1.encode());
+        result.extend(self.2.encode());
+        result.extend(self.3.encode());
         result
     }
 
     fn decode(bytes: &[u8]) -> Result<Self, Self::DecodeError> {
-        if bytes.len() < Self::BYTELEN {
-            Err(())
-        } else {
-            let result = unsafe { *(bytes.as_ptr() as *const Self) };
-            Ok(result)
-        }
+        let v1 = T1::decode(bytes)?;
+        let v2 = T2::decode(&bytes[T1::BYTELEN..])?;
+        let v3 = T3::decode(&bytes[T1::BYTELEN + T2::BYTELEN..])?;
+        let v4 = T4::decode(&bytes[T1::BYTELEN + T2::BYTELEN + T3::BYTELEN..])?;
+        Ok((v1, v2, v3, v4))
     }
 }
 
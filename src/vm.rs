@@ -199,6 +199,67 @@ impl<Aux> VM<Aux> {
         self.stack.pop()
     }
 
+    pub fn with_memory_limit(mut self, memory_limit: usize) -> Self {
+        self.memory_limit = memory_limit;
+        self
+    }
+
+    /// Allocate `s` in the memory arena as a length-prefixed UTF-8 blob, tracking its real
+    /// encoded length in the returned `Object` instead of a fixed `ByteEncodeProperties::BYTELEN`
+    /// - unlike `set_value::<String>`, this isn't capped at `MAX_STR_LEN`.
+    pub fn alloc_string(&mut self, s: &str) -> Result<TPointer, ExecutionError> {
+        let result = self.memory.len();
+        let len = i32::try_from(s.len()).map_err(|_| ExecutionError::OutOfMemory)?;
+        let mut bytes = len.encode();
+        bytes.extend_from_slice(s.as_bytes());
+
+        if result + bytes.len() > self.memory_limit {
+            return Err(ExecutionError::OutOfMemory);
+        }
+
+        let object = Object {
+            index: Some(result as TPointer),
+            size: bytes.len() as u32,
+        };
+        self.memory.extend(bytes);
+        self.objects.insert(result as TPointer, object);
+
+        debug!(self.logger, "Allocated string {:?} at {}", object, result);
+
+        Ok(result as TPointer)
+    }
+
+    /// Read a string allocated by `alloc_string`/`concat_strings`, sized off the `Object`'s own
+    /// recorded length rather than `String::BYTELEN`, so strings longer than `MAX_STR_LEN`
+    /// round-trip correctly.
+    pub fn read_string(&self, ptr: TPointer) -> Option<String> {
+        let object = self.objects.get(&ptr)?;
+        let index = object.index?;
+        let head = index as usize;
+        let tail = (head + object.size as usize).min(self.memory.len());
+        String::decode(&self.memory[head..tail]).ok()
+    }
+
+    /// Concatenate two heap strings, allocating the result as a new string in the arena. The
+    /// inputs are left in place; only the new, combined string is returned.
+    pub fn concat_strings(&mut self, a: TPointer, b: TPointer) -> Result<TPointer, ExecutionError> {
+        let mut res = self.read_string(a).ok_or(ExecutionError::InvalidArgument)?;
+        let tail = self.read_string(b).ok_or(ExecutionError::InvalidArgument)?;
+        res.push_str(&tail);
+        self.alloc_string(&res)
+    }
+
+    /// Reset the arena-scoped heap state ahead of a fresh `run`: the memory arena and its
+    /// tracked objects/converters, plus the operand stack. Registered functions and variables
+    /// are left untouched. Any `Scalar::Pointer` obtained from a previous run is invalidated,
+    /// since the memory backing it is gone.
+    pub fn clear(&mut self) {
+        self.memory.clear();
+        self.objects.clear();
+        self.converters.clear();
+        self.stack.clear();
+    }
+
     pub fn run(&mut self, program: &CompiledProgram) -> Result<i32, ExecutionError> {
         debug!(self.logger, "Running program");
         let mut ptr = 0;
@@ -363,8 +424,8 @@ impl<Aux> VM<Aux> {
                 Instruction::StringLiteral => {
                     let literal = Self::read_str(&mut ptr, &program.bytecode)
                         .ok_or(ExecutionError::InvalidArgument)?;
-                    let obj = self.set_value(literal)?;
-                    self.stack.push(Scalar::Pointer(obj.index.unwrap() as i32));
+                    let ptr_ = self.alloc_string(&literal)?;
+                    self.stack.push(Scalar::Pointer(ptr_));
                 }
                 Instruction::Call => self.execute_call(&mut ptr, &program.bytecode)?,
             }
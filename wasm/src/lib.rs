@@ -1,6 +1,10 @@
 use std::fmt::Write as _;
 
-use cao_lang::{compiler as caoc, prelude::*, vm::Vm};
+use cao_lang::{
+    compiler as caoc,
+    prelude::*,
+    vm::{RunOutcome, Suspended, Vm},
+};
 use wasm_bindgen::prelude::*;
 
 /// Init the error handling of the library
@@ -8,6 +12,9 @@ use wasm_bindgen::prelude::*;
 pub fn _start() {
     #[cfg(feature = "console_error_panic_hook")]
     console_error_panic_hook::set_once();
+    // Pulls in the std-backed `log`/`wasm_logger` machinery, so skip it for the smallest,
+    // `no_std`-core wasm builds.
+    #[cfg(feature = "std")]
     wasm_logger::init(wasm_logger::Config::default());
 }
 
@@ -141,12 +148,30 @@ pub enum CompileResult {
 }
 
 #[wasm_bindgen]
-#[derive(Debug, Default)]
-pub struct CompileOptions {}
+#[derive(Debug)]
+pub struct CompileOptions {
+    pub constant_folding: bool,
+}
+
+impl Default for CompileOptions {
+    fn default() -> Self {
+        caoc::CompileOptions::new().into()
+    }
+}
 
 impl From<CompileOptions> for caoc::CompileOptions {
-    fn from(_: CompileOptions) -> Self {
-        caoc::CompileOptions::new()
+    fn from(value: CompileOptions) -> Self {
+        let mut ops = caoc::CompileOptions::new();
+        ops.constant_folding = value.constant_folding;
+        ops
+    }
+}
+
+impl From<caoc::CompileOptions> for CompileOptions {
+    fn from(value: caoc::CompileOptions) -> Self {
+        Self {
+            constant_folding: value.constant_folding,
+        }
     }
 }
 
@@ -160,6 +185,7 @@ struct Context {
 pub struct RunResult {
     logs: String,
     result: Result<(), JsValue>,
+    return_value: OwnedValue,
 }
 
 #[wasm_bindgen]
@@ -176,33 +202,74 @@ impl RunResult {
             Err(err) => err.clone(),
         }
     }
+
+    /// The program's final top-of-stack value, structured (nested tables included) rather than
+    /// flattened into the log string. `Nil` if the program errored, or if the final value was a
+    /// function/closure/iterator - [`OwnedValue`] has no representation for those.
+    #[wasm_bindgen(getter, js_name = "returnValue")]
+    pub fn return_value(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.return_value).expect("failed to serialize result")
+    }
 }
 
 fn cao_lang_log(vm: &mut Vm<Context>, val: Value) -> Result<Value, ExecutionErrorPayload> {
-    match val {
-        Value::Nil => writeln!(&mut vm.get_aux_mut().logs, "nil").unwrap(),
-        Value::Object(o) => unsafe {
-            let o = o.as_ref();
-            match &o.body {
-                cao_lang::vm::runtime::cao_lang_object::CaoLangObjectBody::String(s) => {
-                    let pl = s.as_str();
-                    writeln!(&mut vm.get_aux_mut().logs, "{pl}").unwrap();
+    let mut visited = std::collections::HashSet::new();
+    let mut rendered = String::new();
+    render_value(val, &mut rendered, &mut visited);
+    writeln!(&mut vm.get_aux_mut().logs, "{rendered}").unwrap();
+    Ok(Value::Nil)
+}
+
+/// Recursively renders `value` into `out`. `visited` tracks the object pointers already on the
+/// current path, so a table that (directly or transitively) contains itself prints `...` on the
+/// revisit instead of recursing forever.
+fn render_value(value: Value, out: &mut String, visited: &mut std::collections::HashSet<usize>) {
+    use cao_lang::vm::runtime::cao_lang_object::CaoLangObjectBody;
+
+    match value {
+        Value::Nil => out.push_str("nil"),
+        Value::Integer(i) => write!(out, "{i}").unwrap(),
+        Value::Real(r) => write!(out, "{r}").unwrap(),
+        Value::Object(o) => {
+            let ptr = o.as_ptr() as usize;
+            let obj = unsafe { o.as_ref() };
+            match &obj.body {
+                CaoLangObjectBody::String(s) => {
+                    write!(out, "{}", unsafe { s.as_str() }.unwrap_or_default()).unwrap()
+                }
+                CaoLangObjectBody::Bytes(b) => write!(out, "{:?}", b.as_bytes()).unwrap(),
+                CaoLangObjectBody::BigInt(b) => write!(out, "{b}").unwrap(),
+                CaoLangObjectBody::Function(f) => {
+                    write!(out, "function: {}/{:?}", f.arity, f.handle).unwrap()
                 }
-                // TODO: log recursively
-                cao_lang::vm::runtime::cao_lang_object::CaoLangObjectBody::Table(_) => todo!(),
-                // TODO: more information
-                cao_lang::vm::runtime::cao_lang_object::CaoLangObjectBody::Function(_) => todo!(),
-                cao_lang::vm::runtime::cao_lang_object::CaoLangObjectBody::NativeFunction(_) => {
-                    todo!()
+                CaoLangObjectBody::NativeFunction(f) => {
+                    write!(out, "native: {:?}", f.handle).unwrap()
+                }
+                CaoLangObjectBody::Closure(c) => {
+                    write!(out, "function: {}/{:?}", c.function.arity, c.function.handle).unwrap()
+                }
+                CaoLangObjectBody::Upvalue(_) => out.push_str("upvalue"),
+                CaoLangObjectBody::Iterator(_) => out.push_str("iterator"),
+                CaoLangObjectBody::Table(table) => {
+                    if !visited.insert(ptr) {
+                        out.push_str("...");
+                        return;
+                    }
+                    out.push_str("{ ");
+                    for (i, (key, val)) in table.iter().enumerate() {
+                        if i > 0 {
+                            out.push_str(", ");
+                        }
+                        render_value(*key, out, visited);
+                        out.push_str(" = ");
+                        render_value(*val, out, visited);
+                    }
+                    out.push_str(" }");
+                    visited.remove(&ptr);
                 }
-                cao_lang::vm::runtime::cao_lang_object::CaoLangObjectBody::Closure(_) => todo!(),
-                cao_lang::vm::runtime::cao_lang_object::CaoLangObjectBody::Upvalue(_) => todo!(),
             }
-        },
-        Value::Integer(pl) => writeln!(&mut vm.get_aux_mut().logs, "{pl}").unwrap(),
-        Value::Real(pl) => writeln!(&mut vm.get_aux_mut().logs, "{pl}").unwrap(),
+        }
     }
-    Ok(Value::Nil)
 }
 
 /// Runs the given compiled Cao-Lang program (output of `compile`).
@@ -215,14 +282,142 @@ pub fn run_program(program: JsValue) -> Result<RunResult, JsValue> {
     vm.register_native_function("log", into_f1(cao_lang_log))
         .expect("Failed to register log function");
     let program: CaoCompiledProgram = serde_wasm_bindgen::from_value(program).map_err(err_to_js)?;
-    let result = vm.run(&program).map_err(err_to_js).map(drop);
+    let run_result = vm.run(&program);
+    let return_value = match &run_result {
+        Ok(()) => OwnedValue::try_from(vm.stack_pop()).unwrap_or_default(),
+        Err(_) => OwnedValue::default(),
+    };
+    let result = run_result.map_err(err_to_js).map(drop);
 
     Ok(RunResult {
         logs: vm.unwrap_aux().logs,
         result,
+        return_value,
     })
 }
 
+/// Renders a compiled Cao-Lang program (output of `compile`) back into a human-readable
+/// instruction listing, for tooling that wants to inspect what `compile` produced.
+#[wasm_bindgen]
+pub fn disassemble(program: JsValue) -> Result<String, JsValue> {
+    let program: CaoCompiledProgram = serde_wasm_bindgen::from_value(program).map_err(err_to_js)?;
+    cao_lang::disasm::disasm(&program).map_err(err_to_js)
+}
+
+/// A Cao-Lang program paused mid-run (see [`Vm::run_resumable`]/[`Vm::resume`]), so a JS host can
+/// drive a long-running script one budgeted slice at a time instead of blocking the thread until
+/// it finishes. `None` once the wrapped program has finished.
+#[wasm_bindgen]
+pub struct CaoLangExecution {
+    suspended: Option<Suspended>,
+    finished: bool,
+    result: Value,
+}
+
+#[wasm_bindgen]
+impl CaoLangExecution {
+    /// Starts running `program`, pausing after at most `max_instr` instructions (or sooner, if it
+    /// calls `Instruction::Yield`/the `yield` native, or finishes on its own). Drive it further
+    /// with `step`/`resume`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(program: JsValue, max_instr: u32) -> Result<CaoLangExecution, JsValue> {
+        let program: CaoCompiledProgram = serde_wasm_bindgen::from_value(program).map_err(err_to_js)?;
+        let vm = new_execution_vm().with_max_iter(max_instr as u64);
+        let outcome = vm.run_resumable(&program).map_err(err_to_js)?;
+        Ok(Self::from_outcome(outcome))
+    }
+
+    /// Continues the paused program without injecting a value, i.e. `resume(null)`. No-op once
+    /// `isFinished` is true.
+    pub fn step(&mut self) -> Result<(), JsValue> {
+        self.resume(JsValue::NULL)
+    }
+
+    /// Continues the paused program, injecting `input` as the suspended call's return value (see
+    /// [`Vm::resume`]). No-op once `isFinished` is true.
+    pub fn resume(&mut self, input: JsValue) -> Result<(), JsValue> {
+        let Some(suspended) = self.suspended.take() else {
+            return Ok(());
+        };
+        let input = js_to_value(input)?;
+        let vm = new_execution_vm();
+        let outcome = vm.resume(suspended, input).map_err(err_to_js)?;
+        *self = Self::from_outcome(outcome);
+        Ok(())
+    }
+
+    #[wasm_bindgen(getter, js_name = "isFinished")]
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// The program's final value once `isFinished` is true; `null` while still paused.
+    #[wasm_bindgen(getter)]
+    pub fn value(&self) -> JsValue {
+        if self.finished {
+            value_to_js(self.result)
+        } else {
+            JsValue::NULL
+        }
+    }
+
+    fn from_outcome(outcome: RunOutcome) -> Self {
+        match outcome {
+            RunOutcome::Finished(result) => Self {
+                suspended: None,
+                finished: true,
+                result,
+            },
+            RunOutcome::Yielded(suspended) => Self {
+                suspended: Some(suspended),
+                finished: false,
+                result: Value::Nil,
+            },
+        }
+    }
+}
+
+fn new_execution_vm() -> Vm<'static, Context> {
+    let mut vm = Vm::new(Context::default()).expect("Failed to initialize VM");
+    vm.register_native_stdlib().expect("Failed to init stdlib");
+    vm.register_native_function("log", into_f1(cao_lang_log))
+        .expect("Failed to register log function");
+    vm
+}
+
+/// Converts a `Value` back out to JS for `CaoLangExecution::value`. Objects other than strings
+/// have no JS-side representation here, so they render as a placeholder instead of failing the
+/// call.
+fn value_to_js(value: Value) -> JsValue {
+    match value {
+        Value::Nil => JsValue::NULL,
+        Value::Integer(i) => JsValue::from_f64(i as f64),
+        Value::Real(r) => JsValue::from_f64(r),
+        Value::Object(o) => unsafe {
+            match o.as_ref().as_str() {
+                Some(s) => JsValue::from_str(s),
+                None => JsValue::from_str(&format!("<{}>", o.as_ref().type_name())),
+            }
+        },
+    }
+}
+
+/// Converts a JS value into a [`Value`] for [`CaoLangExecution::resume`]'s injected input.
+/// Strings/objects aren't supported here since that would require allocating into a running
+/// `Vm`, which `resume` doesn't have yet at the point this is called.
+fn js_to_value(value: JsValue) -> Result<Value, JsValue> {
+    if value.is_null() || value.is_undefined() {
+        return Ok(Value::Nil);
+    }
+    match value.as_f64() {
+        Some(n) if n.fract() == 0.0 => Ok(Value::Integer(n as i64)),
+        Some(n) => Ok(Value::Real(n)),
+        None => Err(JsValue::from_str(
+            "CaoLangExecution::resume only supports number/null input values",
+        )),
+    }
+}
+
 fn err_to_js(e: impl std::error::Error) -> JsValue {
     JsValue::from_str(&format!("{:?}", e))
 }
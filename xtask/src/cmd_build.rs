@@ -12,6 +12,32 @@ pub fn cmd_build_c(args: &[&str]) -> CmdResult<()> {
     Ok(())
 }
 
+/// Cross-compiles the core VM (`cao-lang`, no default features - `disasm`/`std` pull in pieces
+/// `wasm32-unknown-unknown` can't provide) to confirm it still builds `no_std`. Doesn't produce
+/// anything meant to be shipped on its own; `cao-lang-wasm`/`wasm` own the actual browser-facing
+/// bindings and bring their own build pipeline.
+pub fn cmd_build_wasm() -> CmdResult<()> {
+    let status = Command::new("cargo")
+        .args([
+            "build",
+            "--package",
+            "cao-lang",
+            "--no-default-features",
+            "--target",
+            "wasm32-unknown-unknown",
+        ])
+        .current_dir(project_root())
+        .spawn()
+        .with_context(|| "Spawning the cargo build task failed")?
+        .wait()
+        .with_context(|| "Failed to wait for the cargo build task")?;
+
+    if !status.success() {
+        return Err(anyhow!("wasm32 build failed"));
+    }
+    Ok(())
+}
+
 pub fn configure_c_interface<T>(args: impl IntoIterator<Item = T>) -> CmdResult<()>
 where
     T: AsRef<OsStr>,
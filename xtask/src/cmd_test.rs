@@ -9,6 +9,33 @@ use crate::{
     project_root, CmdResult,
 };
 
+/// `wasm32-unknown-unknown` has no test runner configured in this repo (no `wasm-bindgen-test`
+/// harness wired up), so there's nothing to actually execute the way `ctest` does for the `c`
+/// target. This compiles the test binary for the target and stops there, which is still enough to
+/// catch the common failure mode of a change that builds for the host but not for `wasm32`.
+pub fn cmd_test_wasm() -> CmdResult<()> {
+    let status = Command::new("cargo")
+        .args([
+            "test",
+            "--package",
+            "cao-lang",
+            "--no-default-features",
+            "--target",
+            "wasm32-unknown-unknown",
+            "--no-run",
+        ])
+        .current_dir(project_root())
+        .spawn()
+        .with_context(|| "Spawning the cargo test task failed")?
+        .wait()
+        .with_context(|| "Failed to wait for the cargo test task")?;
+
+    if !status.success() {
+        return Err(anyhow!("wasm32 test build failed"));
+    }
+    Ok(())
+}
+
 pub fn cmd_test_c(args: &[&str]) -> CmdResult<()> {
     let mut args = args
         .iter()
@@ -2,8 +2,14 @@ use std::process::Command;
 
 use crate::{project_root, CmdResult};
 use anyhow::Context;
+use cao_lang::stdlib::stdlib_fingerprint;
 use semver::Version;
 
+/// Key the stdlib fingerprint is stored under in `cao-lang/Cargo.toml`'s `[package.metadata]`
+/// table, so a `patch` bump can tell whether the stdlib's native/function surface changed since
+/// the last release.
+const STDLIB_FINGERPRINT_KEY: &str = "stdlib_fingerprint";
+
 pub fn cmd_bump_version(target: &str) -> CmdResult<String> {
     assert_git_not_dirty()
         .with_context(|| "Please commit your changes before creating a new version")?;
@@ -143,6 +149,28 @@ fn bump_cargo_manifest_version(
         _ => unreachable!(),
     };
 
+    // Only `cao-lang/Cargo.toml` carries a stored fingerprint; the wasm/py manifests just wrap
+    // the core crate, so this is a no-op for them.
+    if let Some(metadata) = package.get_mut("metadata").and_then(|m| m.as_table_mut()) {
+        let current_fingerprint = stdlib_fingerprint();
+        if let Some(prev) = metadata
+            .get(STDLIB_FINGERPRINT_KEY)
+            .and_then(|v| v.as_str())
+        {
+            let prev_fingerprint = u32::from_str_radix(prev.trim_start_matches("0x"), 16)
+                .with_context(|| "Failed to parse stored stdlib fingerprint")?;
+            if target == "patch" && prev_fingerprint != current_fingerprint {
+                return Err(anyhow::anyhow!(
+                    "stdlib fingerprint changed (0x{prev_fingerprint:08x} -> 0x{current_fingerprint:08x}) but this is only a patch bump; ABI-breaking stdlib changes need at least a minor bump"
+                ));
+            }
+        }
+        metadata.insert(
+            STDLIB_FINGERPRINT_KEY.to_string(),
+            toml::Value::String(format!("0x{current_fingerprint:08x}")),
+        );
+    }
+
     package.as_table_mut().unwrap().insert(
         "version".to_string(),
         toml::Value::String(version.to_string()),
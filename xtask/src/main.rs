@@ -32,11 +32,11 @@ fn main() {
                 Arg::new("TARGET")
                     .num_args(1)
                     .required(true)
-                    .value_parser(PossibleValuesParser::new( ["c"]))
+                    .value_parser(PossibleValuesParser::new( ["c", "wasm"]))
             )
             .arg(
             Arg::new("--")
-            .help("Arguments to pass to cmake configure")
+            .help("Arguments to pass to cmake configure (ignored by the `wasm` target)")
                     .num_args(..).required(false)
             )
         )
@@ -45,11 +45,11 @@ fn main() {
                 Arg::new("TARGET")
                     .num_args(1)
                     .required(true)
-                    .value_parser(PossibleValuesParser::new( ["c"]))
+                    .value_parser(PossibleValuesParser::new( ["c", "wasm"]))
             )
             .arg(
             Arg::new("--")
-            .help("Arguments to pass to cmake configure")
+            .help("Arguments to pass to cmake configure (ignored by the `wasm` target)")
             .num_args(..).required(false)
             )
             ,
@@ -84,6 +84,12 @@ fn main() {
                         code = 2;
                     }
                 }
+                "wasm" => {
+                    if let Err(e) = cmd_build::cmd_build_wasm() {
+                        eprintln!("Build command failed: {}", e);
+                        code = 2;
+                    }
+                }
                 _ => unreachable!(),
             }
         }
@@ -102,6 +108,12 @@ fn main() {
                         code = 3;
                     }
                 }
+                "wasm" => {
+                    if let Err(e) = cmd_test::cmd_test_wasm() {
+                        eprintln!("Test command failed: {}", e);
+                        code = 3;
+                    }
+                }
                 _ => unreachable!(),
             }
         }